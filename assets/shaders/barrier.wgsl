@@ -0,0 +1,118 @@
+/// Camera
+
+struct CameraUniform {
+    proj_mat: mat4x4<f32>,
+    view_mat: mat4x4<f32>,
+    all_mat: mat4x4<f32>,
+    cam_pos: vec4<f32>,
+    sky_color: vec4<f32>,
+    // w is light intensity
+    sun_dir: vec4<f32>,
+    // Fog start, fog end, ambient light
+    fog: vec4<f32>,
+    // x is debug view (0 - shaded, 1 - depth, 2 - normals, 3 - AO, 4 - light)
+    debug: vec4<u32>,
+    // Elapsed time (seconds), frame time (seconds)
+    time: vec4<f32>,
+    // Resolution (pixels), inverse resolution
+    screen: vec4<f32>,
+}
+
+@group(0)
+@binding(0)
+var<uniform> camera: CameraUniform;
+
+
+/// Barrier
+
+struct BarrierLocals {
+    // Border, bottom and top of the world, visibility distance (0 if there is no border)
+    bounds: vec4<f32>,
+}
+
+@group(1)
+@binding(0)
+var<uniform> locals: BarrierLocals;
+
+
+/// Vertex Shader
+
+struct VertexOutput {
+    @builtin(position) clip_pos: vec4<f32>,
+    @location(0) world_pos: vec3<f32>,
+}
+
+// Wall quads on the four borders, limited to the visible area around the camera
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[index % 6u];
+    let wall = index / 6u;
+
+    let border = locals.bounds.x;
+    let distance = locals.bounds.w;
+    let cam = camera.cam_pos.xyz;
+
+    // Walls 0 and 1 are perpendicular to X, walls 2 and 3 to Z
+    let side = select(-border, border, wall % 2u == 0u);
+    let along_cam = select(cam.x, cam.z, wall < 2u);
+    let along = clamp(
+        along_cam + mix(-distance, distance, corner.x),
+        -border,
+        border,
+    );
+    let y = clamp(
+        cam.y + mix(-distance, distance, corner.y),
+        locals.bounds.y,
+        locals.bounds.z,
+    );
+
+    var world_pos: vec3<f32>;
+    if (wall < 2u) {
+        world_pos = vec3<f32>(side, y, along);
+    } else {
+        world_pos = vec3<f32>(along, y, side);
+    }
+
+    var out: VertexOutput;
+    out.clip_pos = camera.all_mat * vec4<f32>(world_pos, 1.0);
+    out.world_pos = world_pos;
+
+    return out;
+}
+
+
+/// Fragment shader
+
+let BARRIER_COLOR: vec3<f32> = vec3<f32>(0.35, 0.75, 1.0);
+// Stripes per block and their speed (stripes per second)
+let STRIPE_DENSITY: f32 = 0.25;
+let STRIPE_SPEED: f32 = 0.5;
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    let distance = locals.bounds.w;
+    if (distance <= 0.0) {
+        discard;
+    }
+
+    // Fades out away from the camera
+    let fade = 1.0 - clamp(length(in.world_pos - camera.cam_pos.xyz) / distance, 0.0, 1.0);
+
+    // Diagonal stripes moving up (one of x and z is constant on a wall)
+    let stripe_pos = in.world_pos.x + in.world_pos.z + in.world_pos.y;
+    let stripe = step(0.5, fract(stripe_pos * STRIPE_DENSITY - camera.time.x * STRIPE_SPEED));
+
+    return vec4<f32>(BARRIER_COLOR, fade * fade * (0.15 + 0.4 * stripe));
+}
@@ -0,0 +1,56 @@
+/// Camera
+
+struct CameraUniform {
+    proj_mat: mat4x4<f32>,
+    view_mat: mat4x4<f32>,
+    all_mat: mat4x4<f32>,
+    cam_pos: vec4<f32>,
+    sky_color: vec4<f32>,
+    // w is light intensity
+    sun_dir: vec4<f32>,
+    // Fog start, fog end, ambient light
+    fog: vec4<f32>,
+    // x is debug view (0 - shaded, 1 - depth, 2 - normals, 3 - AO, 4 - light)
+    debug: vec4<u32>,
+    // Elapsed time (seconds), frame time (seconds)
+    time: vec4<f32>,
+    // Resolution (pixels), inverse resolution
+    screen: vec4<f32>,
+}
+
+@group(0)
+@binding(0)
+var<uniform> camera: CameraUniform;
+
+
+/// Vertex Shader
+
+// Two triangles covering the whole viewport at the far plane
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+) -> @builtin(position) vec4<f32> {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+
+    return vec4<f32>(corners[index], 1.0, 1.0);
+}
+
+
+/// Fragment shader
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    // Same as the clear color of the first pass
+    if (camera.debug.x != 0u) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    return vec4<f32>(camera.sky_color.rgb, 1.0);
+}
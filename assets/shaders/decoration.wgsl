@@ -0,0 +1,190 @@
+/// Camera
+
+struct CameraUniform {
+    proj_mat: mat4x4<f32>,
+    view_mat: mat4x4<f32>,
+    all_mat: mat4x4<f32>,
+    cam_pos: vec4<f32>,
+    sky_color: vec4<f32>,
+    // w is light intensity
+    sun_dir: vec4<f32>,
+    // Fog start, fog end, ambient light
+    fog: vec4<f32>,
+    // x is debug view (0 - shaded, 1 - depth, 2 - normals, 3 - AO, 4 - light)
+    debug: vec4<u32>,
+    // Elapsed time (seconds), frame time (seconds)
+    time: vec4<f32>,
+    // Resolution (pixels), inverse resolution
+    screen: vec4<f32>,
+}
+
+@group(0)
+@binding(0)
+var<uniform> camera: CameraUniform;
+
+
+/// Per-draw data
+
+struct DrawData {
+    // Added to vertex positions
+    offset: vec4<f32>,
+    // Multiplies vertex colors
+    tint: vec4<f32>,
+}
+
+// Replaced with a push constant by the renderer if supported
+@group(1)
+@binding(0)
+var<uniform> draw: DrawData;
+
+
+/// Lighting
+
+// Flat normal (from screen space derivatives) facing the camera
+fn flat_normal(world_pos: vec3<f32>) -> vec3<f32> {
+    let normal = normalize(cross(dpdx(world_pos), dpdy(world_pos)));
+    // Visible surfaces always face the camera
+    if (dot(normal, camera.cam_pos.xyz - world_pos) < 0.0) {
+        return -normal;
+    }
+    return normal;
+}
+
+fn light(normal: vec3<f32>) -> f32 {
+    return camera.fog.z + camera.sun_dir.w * max(dot(normal, camera.sun_dir.xyz), 0.0);
+}
+
+// Flat shading with distance fog
+fn shade(color: vec3<f32>, world_pos: vec3<f32>) -> vec4<f32> {
+    let intensity = light(flat_normal(world_pos));
+    let fog = smoothstep(camera.fog.x, camera.fog.y, distance(camera.cam_pos.xyz, world_pos));
+
+    return vec4<f32>(mix(color * intensity, camera.sky_color.rgb, fog), 1.0);
+}
+
+// Intermediate value selected by the debug view
+fn debug_view(world_pos: vec3<f32>) -> vec4<f32> {
+    let normal = flat_normal(world_pos);
+
+    switch (camera.debug.x) {
+        // Linear distance to the camera, white is near
+        case 1u: {
+            let depth = clamp(distance(camera.cam_pos.xyz, world_pos) / camera.fog.y, 0.0, 1.0);
+            return vec4<f32>(vec3<f32>(1.0 - depth), 1.0);
+        }
+        case 2u: {
+            return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+        }
+        // No ambient occlusion yet
+        case 3u: {
+            return vec4<f32>(1.0);
+        }
+        case 4u: {
+            return vec4<f32>(vec3<f32>(light(normal)), 1.0);
+        }
+        default: {
+            return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+        }
+    }
+}
+
+
+/// Vertex Shader
+
+struct InstanceInput {
+    // Block position (4 bits per axis) and shape seed (high 16 bits)
+    @location(0) packed: u32,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_pos: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
+    // Position on the quad, v goes up
+    @location(2) uv: vec2<f32>,
+}
+
+// Brightness of the bottom of tufts relative to their tops (same as `TerrainMesh::STEM_SHADE`)
+let STEM_SHADE: f32 = 0.7;
+let PI: f32 = 3.14159265;
+
+// Two crossed quads (6 vertices each) on top of the block. Rotation, size and offset inside the
+// block are taken from the seed
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex: u32,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Block centers are at integer coordinates
+    let block = vec3<f32>(
+        f32(instance.packed & 15u),
+        f32((instance.packed >> 4u) & 15u),
+        f32((instance.packed >> 8u) & 15u),
+    );
+    let seed = instance.packed >> 16u;
+    let angle = f32(seed & 255u) / 255.0 * PI;
+    let height = 0.35 + f32((seed >> 8u) & 15u) / 15.0 * 0.3;
+    let shift = vec2<f32>(f32((seed >> 12u) & 3u), f32((seed >> 14u) & 3u)) / 3.0 - 0.5;
+
+    // Corners of two triangles: (0, 0), (0, 1), (1, 1), (0, 0), (1, 1), (1, 0)
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+    let uv = corners[vertex % 6u];
+    // Second quad is perpendicular to the first one
+    let quad_angle = angle + f32(vertex / 6u) * PI * 0.5;
+    let dir = vec3<f32>(cos(quad_angle), 0.0, sin(quad_angle));
+
+    let base = block + vec3<f32>(shift.x * 0.4, 0.5, shift.y * 0.4);
+    let local = base + dir * (uv.x - 0.5) * 0.7 + vec3<f32>(0.0, uv.y * height, 0.0);
+    let world_pos = local + draw.offset.xyz;
+
+    out.clip_pos = camera.all_mat * vec4<f32>(world_pos, 1.0);
+    out.color = instance.color.rgb * mix(STEM_SHADE, 1.0, uv.y) * draw.tint.rgb;
+    out.world_pos = world_pos;
+    out.uv = uv;
+
+    return out;
+}
+
+
+/// Fragment shader
+
+// Alpha tested like plant quads, but with three blades
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    // Shaded before discarding, derivatives require uniform control flow
+    let color = shade(in.color, in.world_pos);
+
+    let blade = 1.0 - abs(fract(in.uv.x * 3.0) - 0.5) * 2.0;
+    if (in.uv.y > blade) {
+        discard;
+    }
+
+    return color;
+}
+
+@fragment
+fn fs_debug(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return debug_view(in.world_pos);
+}
+
+// Blended additively, so brighter pixels are drawn more times
+@fragment
+fn fs_overdraw(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.12, 0.06, 0.02, 1.0);
+}
@@ -4,6 +4,18 @@ struct CameraUniform {
     proj_mat: mat4x4<f32>,
     view_mat: mat4x4<f32>,
     all_mat: mat4x4<f32>,
+    cam_pos: vec4<f32>,
+    sky_color: vec4<f32>,
+    // w is light intensity
+    sun_dir: vec4<f32>,
+    // Fog start, fog end, ambient light
+    fog: vec4<f32>,
+    // x is debug view (0 - shaded, 1 - depth, 2 - normals, 3 - AO, 4 - light)
+    debug: vec4<u32>,
+    // Elapsed time (seconds), frame time (seconds)
+    time: vec4<f32>,
+    // Resolution (pixels), inverse resolution
+    screen: vec4<f32>,
 }
 
 @group(0)
@@ -11,6 +23,72 @@ struct CameraUniform {
 var<uniform> camera: CameraUniform;
 
 
+/// Per-draw data
+
+struct DrawData {
+    // Added to vertex positions
+    offset: vec4<f32>,
+    // Multiplies vertex colors
+    tint: vec4<f32>,
+}
+
+// Replaced with a push constant by the renderer if supported
+@group(1)
+@binding(0)
+var<uniform> draw: DrawData;
+
+
+/// Lighting
+
+// Flat normal (from screen space derivatives) facing the camera
+fn flat_normal(world_pos: vec3<f32>) -> vec3<f32> {
+    let normal = normalize(cross(dpdx(world_pos), dpdy(world_pos)));
+    // Visible surfaces always face the camera
+    if (dot(normal, camera.cam_pos.xyz - world_pos) < 0.0) {
+        return -normal;
+    }
+    return normal;
+}
+
+fn light(normal: vec3<f32>) -> f32 {
+    return camera.fog.z + camera.sun_dir.w * max(dot(normal, camera.sun_dir.xyz), 0.0);
+}
+
+// Flat shading with distance fog
+fn shade(color: vec3<f32>, world_pos: vec3<f32>) -> vec4<f32> {
+    let intensity = light(flat_normal(world_pos));
+    let fog = smoothstep(camera.fog.x, camera.fog.y, distance(camera.cam_pos.xyz, world_pos));
+
+    return vec4<f32>(mix(color * intensity, camera.sky_color.rgb, fog), 1.0);
+}
+
+// Intermediate value selected by the debug view
+fn debug_view(world_pos: vec3<f32>) -> vec4<f32> {
+    let normal = flat_normal(world_pos);
+
+    switch (camera.debug.x) {
+        // Linear distance to the camera, white is near
+        case 1u: {
+            let depth = clamp(distance(camera.cam_pos.xyz, world_pos) / camera.fog.y, 0.0, 1.0);
+            return vec4<f32>(vec3<f32>(1.0 - depth), 1.0);
+        }
+        case 2u: {
+            return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+        }
+        // No ambient occlusion yet
+        case 3u: {
+            return vec4<f32>(1.0);
+        }
+        case 4u: {
+            return vec4<f32>(vec3<f32>(light(normal)), 1.0);
+        }
+        default: {
+            return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+        }
+    }
+}
+
+
 /// Vertex Shader
 
 struct VertexInput {
@@ -28,6 +106,7 @@ struct InstanceInput {
 struct VertexOutput {
     @builtin(position) clip_pos: vec4<f32>,
     @location(0) color: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
 }
 
 // This function is used to transform vertices
@@ -47,8 +126,10 @@ fn vs_main(
     );
 
     // Manual casting of `VertexModel` to `VertexOutput`
-    out.clip_pos = camera.all_mat * model_matrix * vec4<f32>(model.pos, 1.0);
-    out.color = model.color;
+    let world_pos = model_matrix * vec4<f32>(model.pos, 1.0) + vec4<f32>(draw.offset.xyz, 0.0);
+    out.clip_pos = camera.all_mat * world_pos;
+    out.color = model.color * draw.tint.rgb;
+    out.world_pos = world_pos.xyz;
 
     return out;
 }
@@ -61,6 +142,20 @@ fn vs_main(
 fn fs_main(
     in: VertexOutput
 ) -> @location(0) vec4<f32> {
-    // Just contant color for every fragment
-    return vec4<f32>(in.color, 1.0);
+    return shade(in.color, in.world_pos);
+}
+
+@fragment
+fn fs_debug(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return debug_view(in.world_pos);
+}
+
+// Blended additively, so brighter pixels are drawn more times
+@fragment
+fn fs_overdraw(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.12, 0.06, 0.02, 1.0);
 }
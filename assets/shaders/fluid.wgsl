@@ -0,0 +1,187 @@
+/// Camera
+
+struct CameraUniform {
+    proj_mat: mat4x4<f32>,
+    view_mat: mat4x4<f32>,
+    all_mat: mat4x4<f32>,
+    cam_pos: vec4<f32>,
+    sky_color: vec4<f32>,
+    // w is light intensity
+    sun_dir: vec4<f32>,
+    // Fog start, fog end, ambient light
+    fog: vec4<f32>,
+    // x is debug view (0 - shaded, 1 - depth, 2 - normals, 3 - AO, 4 - light)
+    debug: vec4<u32>,
+    // Elapsed time (seconds), frame time (seconds)
+    time: vec4<f32>,
+    // Resolution (pixels), inverse resolution
+    screen: vec4<f32>,
+}
+
+@group(0)
+@binding(0)
+var<uniform> camera: CameraUniform;
+
+
+/// Per-draw data
+
+struct DrawData {
+    // Added to vertex positions
+    offset: vec4<f32>,
+    // Multiplies vertex colors
+    tint: vec4<f32>,
+}
+
+// Replaced with a push constant by the renderer if supported
+@group(1)
+@binding(0)
+var<uniform> draw: DrawData;
+
+
+/// Scene depth
+
+// Opaque geometry depth. Group 1 if push constants are supported
+@group(2)
+@binding(0)
+var scene_depth: texture_depth_2d;
+
+
+/// Lighting
+
+// Flat normal (from screen space derivatives) facing the camera
+fn flat_normal(world_pos: vec3<f32>) -> vec3<f32> {
+    let normal = normalize(cross(dpdx(world_pos), dpdy(world_pos)));
+    // Visible surfaces always face the camera
+    if (dot(normal, camera.cam_pos.xyz - world_pos) < 0.0) {
+        return -normal;
+    }
+    return normal;
+}
+
+fn light(normal: vec3<f32>) -> f32 {
+    return camera.fog.z + camera.sun_dir.w * max(dot(normal, camera.sun_dir.xyz), 0.0);
+}
+
+// Surface is fully transparent at shorelines and fades in over this depth (blocks)
+let SHORE_FADE: f32 = 1.5;
+// Alpha when looking straight at the surface
+let MIN_ALPHA: f32 = 0.45;
+let WAVE_STRENGTH: f32 = 0.12;
+
+// Perturb normal of horizontal surfaces with a few scrolling waves
+fn wave_normal(normal: vec3<f32>, world_pos: vec3<f32>) -> vec3<f32> {
+    let t = camera.time.x;
+    let p = world_pos.xz;
+    let dx = sin(p.x * 1.7 + t * 1.3) * 0.6 + sin((p.x + p.y) * 2.9 + t * 2.1) * 0.4;
+    let dz = cos(p.y * 1.9 - t * 1.1) * 0.6 + cos((p.x - p.y) * 3.3 + t * 1.7) * 0.4;
+
+    return normalize(normal + vec3<f32>(dx, 0.0, dz) * WAVE_STRENGTH * abs(normal.y));
+}
+
+// View space depth of a depth buffer value
+fn view_depth(depth: f32) -> f32 {
+    let a = camera.proj_mat[2][2];
+    let b = camera.proj_mat[3][2];
+    // Orthographic projection is linear
+    if (camera.proj_mat[3][3] == 1.0) {
+        return (depth - b) / a;
+    }
+    return b / (depth - a);
+}
+
+// Intermediate value selected by the debug view
+fn debug_view(world_pos: vec3<f32>) -> vec4<f32> {
+    let normal = flat_normal(world_pos);
+
+    switch (camera.debug.x) {
+        // Linear distance to the camera, white is near
+        case 1u: {
+            let depth = clamp(distance(camera.cam_pos.xyz, world_pos) / camera.fog.y, 0.0, 1.0);
+            return vec4<f32>(vec3<f32>(1.0 - depth), 1.0);
+        }
+        case 2u: {
+            return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+        }
+        // No ambient occlusion yet
+        case 3u: {
+            return vec4<f32>(1.0);
+        }
+        case 4u: {
+            return vec4<f32>(vec3<f32>(light(normal)), 1.0);
+        }
+        default: {
+            return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+        }
+    }
+}
+
+
+/// Vertex Shader
+
+struct VertexInput {
+    @location(0) pos: vec3<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_pos: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_pos = model.pos + draw.offset.xyz;
+    out.clip_pos = camera.all_mat * vec4<f32>(world_pos, 1.0);
+    out.color = model.color * draw.tint.rgb;
+    out.world_pos = world_pos;
+
+    return out;
+}
+
+
+/// Fragment shader
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    let normal = wave_normal(flat_normal(in.world_pos), in.world_pos);
+    let to_camera = normalize(camera.cam_pos.xyz - in.world_pos);
+
+    // Lit like terrain, with a sun highlight on the waves
+    let reflected = reflect(-camera.sun_dir.xyz, normal);
+    let specular = pow(max(dot(reflected, to_camera), 0.0), 32.0) * camera.sun_dir.w;
+    let color = in.color * light(normal) + vec3<f32>(specular);
+
+    // Fresnel-style transparency, surface is more opaque at grazing angles
+    let fresnel = pow(1.0 - max(dot(normal, to_camera), 0.0), 5.0);
+    var alpha = mix(MIN_ALPHA, 1.0, fresnel);
+
+    // Shallow fluid fades out towards shorelines
+    let scene = view_depth(textureLoad(scene_depth, vec2<i32>(in.clip_pos.xy), 0));
+    alpha = alpha * clamp((scene - view_depth(in.clip_pos.z)) / SHORE_FADE, 0.0, 1.0);
+
+    let fog = smoothstep(camera.fog.x, camera.fog.y, distance(camera.cam_pos.xyz, in.world_pos));
+
+    return vec4<f32>(mix(color, camera.sky_color.rgb, fog), alpha);
+}
+
+@fragment
+fn fs_debug(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return debug_view(in.world_pos);
+}
+
+// Blended additively, so brighter pixels are drawn more times
+@fragment
+fn fs_overdraw(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.12, 0.06, 0.02, 1.0);
+}
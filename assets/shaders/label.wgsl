@@ -0,0 +1,61 @@
+/// Camera
+
+struct CameraUniform {
+    proj_mat: mat4x4<f32>,
+    view_mat: mat4x4<f32>,
+    all_mat: mat4x4<f32>,
+    cam_pos: vec4<f32>,
+    sky_color: vec4<f32>,
+    // w is light intensity
+    sun_dir: vec4<f32>,
+    // Fog start, fog end, ambient light
+    fog: vec4<f32>,
+    // x is debug view (0 - shaded, 1 - depth, 2 - normals, 3 - AO, 4 - light)
+    debug: vec4<u32>,
+    // Elapsed time (seconds), frame time (seconds)
+    time: vec4<f32>,
+    // Resolution (pixels), inverse resolution
+    screen: vec4<f32>,
+}
+
+@group(0)
+@binding(0)
+var<uniform> camera: CameraUniform;
+
+
+/// Vertex Shader
+
+struct VertexInput {
+    @location(0) anchor: vec3<f32>,
+    @location(1) offset: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_pos: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+// Labels always face the camera, so offset is applied in view space
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let view_pos = camera.view_mat * vec4<f32>(model.anchor, 1.0);
+    out.clip_pos = camera.proj_mat * (view_pos + vec4<f32>(model.offset, 0.0, 0.0));
+    out.color = model.color;
+
+    return out;
+}
+
+
+/// Fragment shader
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,84 @@
+/// Loading
+
+struct LoadingLocals {
+    // Progress (0..1), time since loading started (seconds), screen aspect ratio
+    progress: vec4<f32>,
+    background: vec4<f32>,
+}
+
+@group(1)
+@binding(0)
+var<uniform> locals: LoadingLocals;
+
+
+/// Vertex Shader
+
+struct VertexOutput {
+    @builtin(position) clip_pos: vec4<f32>,
+    // Position on the screen (-1..1, y up)
+    @location(0) screen: vec2<f32>,
+}
+
+// Two triangles covering the whole screen
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[index];
+
+    var out: VertexOutput;
+    out.clip_pos = vec4<f32>(corner, 0.0, 1.0);
+    out.screen = corner;
+
+    return out;
+}
+
+
+/// Fragment shader
+
+// Bar half size in screen space (height is corrected by the aspect ratio)
+let BAR_HALF_WIDTH: f32 = 0.4;
+let BAR_HALF_HEIGHT: f32 = 0.025;
+let BORDER_WIDTH: f32 = 0.006;
+let BORDER_COLOR: vec3<f32> = vec3<f32>(0.08, 0.08, 0.08);
+let EMPTY_COLOR: vec3<f32> = vec3<f32>(0.2, 0.2, 0.22);
+let FILL_COLOR: vec3<f32> = vec3<f32>(0.189, 0.82, 0.378);
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    let progress = clamp(locals.progress.x, 0.0, 1.0);
+    let time = locals.progress.y;
+    let aspect = max(locals.progress.z, 0.0001);
+
+    // Square pixels, so the border has the same width on all sides
+    let pos = vec2<f32>(in.screen.x, in.screen.y / aspect);
+    let half_size = vec2<f32>(BAR_HALF_WIDTH, BAR_HALF_HEIGHT);
+    let dist = abs(pos) - half_size;
+
+    // Background with a subtle vertical gradient
+    if (dist.x > BORDER_WIDTH || dist.y > BORDER_WIDTH) {
+        return vec4<f32>(locals.background.rgb * (0.55 + 0.15 * in.screen.y), 1.0);
+    }
+    if (dist.x > 0.0 || dist.y > 0.0) {
+        return vec4<f32>(BORDER_COLOR, 1.0);
+    }
+
+    // Filled part with moving stripes, so the screen doesn't look frozen
+    let filled = (pos.x + BAR_HALF_WIDTH) / (BAR_HALF_WIDTH * 2.0);
+    if (filled <= progress) {
+        let stripe = step(0.5, fract((pos.x - pos.y) * 20.0 - time));
+        return vec4<f32>(FILL_COLOR * (0.9 + 0.1 * stripe), 1.0);
+    }
+
+    return vec4<f32>(EMPTY_COLOR, 1.0);
+}
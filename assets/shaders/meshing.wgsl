@@ -0,0 +1,144 @@
+/// Meshing
+
+struct Palette {
+    // Block color, w is 1 for opaque blocks
+    colors: array<vec4<f32>, 32>,
+}
+
+struct Counter {
+    quads: atomic<u32>,
+}
+
+// Block ids (one per u32) in `BlockCoord::flatten` order
+@group(0)
+@binding(0)
+var<storage, read> blocks: array<u32>;
+
+@group(0)
+@binding(1)
+var<uniform> palette: Palette;
+
+// Position and color of every vertex (6 floats, same as `TerrainVertex`)
+@group(0)
+@binding(2)
+var<storage, read_write> vertices: array<f32>;
+
+@group(0)
+@binding(3)
+var<storage, read_write> indices: array<u32>;
+
+@group(0)
+@binding(4)
+var<storage, read_write> counter: Counter;
+
+let CHUNK_SIZE: i32 = 16;
+
+fn opaque(block: u32) -> bool {
+    return palette.colors[block].w > 0.5;
+}
+
+fn write_vertex(index: u32, position: vec3<f32>, color: vec3<f32>) {
+    let offset = index * 6u;
+    vertices[offset] = position.x;
+    vertices[offset + 1u] = position.y;
+    vertices[offset + 2u] = position.z;
+    vertices[offset + 3u] = color.r;
+    vertices[offset + 4u] = color.g;
+    vertices[offset + 5u] = color.b;
+}
+
+
+/// Compute shader
+
+// One invocation per block. Faces of opaque blocks not hidden by opaque neighbors are appended
+// to the output buffers
+@compute
+@workgroup_size(64)
+fn cs_main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+) {
+    let index = i32(id.x);
+    if (index >= CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) {
+        return;
+    }
+
+    let block = blocks[index];
+    if (!opaque(block)) {
+        return;
+    }
+
+    let pos = vec3<i32>(
+        index / (CHUNK_SIZE * CHUNK_SIZE),
+        index / CHUNK_SIZE % CHUNK_SIZE,
+        index % CHUNK_SIZE,
+    );
+    let color = palette.colors[block].rgb;
+
+    // Down, Up, Left, Right, Front, Back (same as `Direction`)
+    var normals = array<vec3<i32>, 6>(
+        vec3<i32>(0, -1, 0),
+        vec3<i32>(0, 1, 0),
+        vec3<i32>(-1, 0, 0),
+        vec3<i32>(1, 0, 0),
+        vec3<i32>(0, 0, -1),
+        vec3<i32>(0, 0, 1),
+    );
+    // Quad corners in the order of `Quad::corners`
+    var corners = array<vec3<f32>, 24>(
+        vec3<f32>(0.5, -0.5, -0.5),
+        vec3<f32>(0.5, -0.5, 0.5),
+        vec3<f32>(-0.5, -0.5, 0.5),
+        vec3<f32>(-0.5, -0.5, -0.5),
+
+        vec3<f32>(0.5, 0.5, 0.5),
+        vec3<f32>(0.5, 0.5, -0.5),
+        vec3<f32>(-0.5, 0.5, -0.5),
+        vec3<f32>(-0.5, 0.5, 0.5),
+
+        vec3<f32>(-0.5, 0.5, -0.5),
+        vec3<f32>(-0.5, -0.5, -0.5),
+        vec3<f32>(-0.5, -0.5, 0.5),
+        vec3<f32>(-0.5, 0.5, 0.5),
+
+        vec3<f32>(0.5, 0.5, 0.5),
+        vec3<f32>(0.5, -0.5, 0.5),
+        vec3<f32>(0.5, -0.5, -0.5),
+        vec3<f32>(0.5, 0.5, -0.5),
+
+        vec3<f32>(0.5, 0.5, -0.5),
+        vec3<f32>(0.5, -0.5, -0.5),
+        vec3<f32>(-0.5, -0.5, -0.5),
+        vec3<f32>(-0.5, 0.5, -0.5),
+
+        vec3<f32>(-0.5, 0.5, 0.5),
+        vec3<f32>(-0.5, -0.5, 0.5),
+        vec3<f32>(0.5, -0.5, 0.5),
+        vec3<f32>(0.5, 0.5, 0.5),
+    );
+
+    for (var dir = 0; dir < 6; dir = dir + 1) {
+        // Faces on chunk edges are always visible
+        let neighbor = pos + normals[dir];
+        let inside = all(neighbor >= vec3<i32>(0)) && all(neighbor < vec3<i32>(CHUNK_SIZE));
+        if (inside) {
+            let neighbor_index = neighbor.x * CHUNK_SIZE * CHUNK_SIZE + neighbor.y * CHUNK_SIZE + neighbor.z;
+            if (opaque(blocks[neighbor_index])) {
+                continue;
+            }
+        }
+
+        let quad = atomicAdd(&counter.quads, 1u);
+        let base = quad * 4u;
+        for (var corner = 0; corner < 4; corner = corner + 1) {
+            write_vertex(base + u32(corner), vec3<f32>(pos) + corners[dir * 4 + corner], color);
+        }
+
+        let offset = quad * 6u;
+        indices[offset] = base;
+        indices[offset + 1u] = base + 1u;
+        indices[offset + 2u] = base + 2u;
+        indices[offset + 3u] = base;
+        indices[offset + 4u] = base + 2u;
+        indices[offset + 5u] = base + 3u;
+    }
+}
@@ -0,0 +1,103 @@
+/// Minimap
+
+struct MinimapLocals {
+    // Center x, center y, half width, half height (clip space)
+    rect: vec4<f32>,
+    // Player position (texture coordinates), visible radius (texture coordinates), camera yaw
+    view: vec4<f32>,
+    // Opacity
+    style: vec4<f32>,
+}
+
+@group(1)
+@binding(0)
+var<uniform> locals: MinimapLocals;
+
+// Top block colors of world columns, wrapped around (alpha is 0 for unknown columns)
+@group(1)
+@binding(1)
+var map_texture: texture_2d<f32>;
+
+@group(1)
+@binding(2)
+var map_sampler: sampler;
+
+
+/// Vertex Shader
+
+struct VertexOutput {
+    @builtin(position) clip_pos: vec4<f32>,
+    // Position on the minimap (-1..1, y up)
+    @location(0) local: vec2<f32>,
+}
+
+// Two triangles covering the minimap rectangle
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[index];
+
+    var out: VertexOutput;
+    out.clip_pos = vec4<f32>(locals.rect.xy + corner * locals.rect.zw, 0.0, 1.0);
+    out.local = corner;
+
+    return out;
+}
+
+
+/// Fragment shader
+
+let BORDER_WIDTH: f32 = 0.04;
+let BORDER_COLOR: vec4<f32> = vec4<f32>(0.08, 0.08, 0.08, 1.0);
+let UNKNOWN_COLOR: vec4<f32> = vec4<f32>(0.15, 0.15, 0.18, 0.85);
+let MARKER_COLOR: vec4<f32> = vec4<f32>(1.0, 0.2, 0.2, 1.0);
+
+// Arrow in the center pointing to the top (camera always looks up on the minimap)
+fn marker(pos: vec2<f32>) -> bool {
+    return pos.y > -0.05 && pos.y < 0.08 && abs(pos.x) < (0.08 - pos.y) * 0.5;
+}
+
+// Color of the minimap point before applying the opacity
+fn minimap_color(
+    in: VertexOutput
+) -> vec4<f32> {
+    let dist = length(in.local);
+    if (dist > 1.0) {
+        return vec4<f32>(0.0);
+    }
+    if (dist > 1.0 - BORDER_WIDTH) {
+        return BORDER_COLOR;
+    }
+    if (marker(in.local)) {
+        return MARKER_COLOR;
+    }
+
+    // Rotate so the camera forward direction points up
+    let forward = vec2<f32>(sin(locals.view.w), cos(locals.view.w));
+    let right = vec2<f32>(forward.y, -forward.x);
+    let offset = in.local.x * right + in.local.y * forward;
+
+    let texel = textureSampleLevel(map_texture, map_sampler, locals.view.xy + offset * locals.view.z, 0.0);
+    if (texel.a == 0.0) {
+        return UNKNOWN_COLOR;
+    }
+
+    return vec4<f32>(texel.rgb, 1.0);
+}
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    let color = minimap_color(in);
+    return vec4<f32>(color.rgb, color.a * locals.style.x);
+}
@@ -0,0 +1,125 @@
+/// Camera
+
+struct CameraUniform {
+    proj_mat: mat4x4<f32>,
+    view_mat: mat4x4<f32>,
+    all_mat: mat4x4<f32>,
+    cam_pos: vec4<f32>,
+    sky_color: vec4<f32>,
+    // w is light intensity
+    sun_dir: vec4<f32>,
+    // Fog start, fog end, ambient light
+    fog: vec4<f32>,
+    // x is debug view (0 - shaded, 1 - depth, 2 - normals, 3 - AO, 4 - light)
+    debug: vec4<u32>,
+    // Elapsed time (seconds), frame time (seconds)
+    time: vec4<f32>,
+    // Resolution (pixels), inverse resolution
+    screen: vec4<f32>,
+}
+
+@group(0)
+@binding(0)
+var<uniform> camera: CameraUniform;
+
+
+/// Scene
+
+// Color of the first and second passes
+@group(1)
+@binding(0)
+var scene_texture: texture_2d<f32>;
+
+@group(1)
+@binding(1)
+var scene_sampler: sampler;
+
+
+/// Post
+
+struct PostLocals {
+    // Color multiplied over the scene, distortion strength
+    tint: vec4<f32>,
+    // Damage vignette intensity, fade to black
+    effects: vec4<f32>,
+    // Crosshair half length (pixels), thickness (pixels), opacity
+    crosshair: vec4<f32>,
+}
+
+@group(2)
+@binding(0)
+var<uniform> locals: PostLocals;
+
+
+/// Vertex Shader
+
+struct VertexOutput {
+    @builtin(position) clip_pos: vec4<f32>,
+    // Texture coordinates of the scene (y down)
+    @location(0) uv: vec2<f32>,
+}
+
+// Two triangles covering the whole screen
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[index];
+
+    var out: VertexOutput;
+    out.clip_pos = vec4<f32>(corner, 0.0, 1.0);
+    out.uv = vec2<f32>(corner.x, -corner.y) * 0.5 + 0.5;
+
+    return out;
+}
+
+
+/// Fragment shader
+
+// Waves across the screen and their speed (radians per second)
+let WAVE_FREQUENCY: f32 = 25.0;
+let WAVE_SPEED: f32 = 2.5;
+let VIGNETTE_COLOR: vec3<f32> = vec3<f32>(0.7, 0.0, 0.0);
+// Distance from the center where the vignette starts (corners are ~1.4)
+let VIGNETTE_START: f32 = 0.4;
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    let time = camera.time.x;
+    let distortion = locals.tint.w;
+
+    // Underwater: sample the scene along a slow wave
+    let wave = vec2<f32>(
+        sin(in.uv.y * WAVE_FREQUENCY + time * WAVE_SPEED),
+        cos(in.uv.x * WAVE_FREQUENCY + time * WAVE_SPEED * 0.8),
+    );
+    let uv = clamp(in.uv + wave * distortion, vec2<f32>(0.0), vec2<f32>(1.0));
+    var color = textureSample(scene_texture, scene_sampler, uv).rgb * locals.tint.rgb;
+
+    // Damage: red edges
+    let edge = smoothstep(VIGNETTE_START, 1.4, length(in.uv * 2.0 - 1.0));
+    color = mix(color, VIGNETTE_COLOR, clamp(edge * locals.effects.x, 0.0, 1.0));
+
+    // Death: fade to black
+    color = color * (1.0 - clamp(locals.effects.y, 0.0, 1.0));
+
+    // Crosshair: inverted scene color, so it's visible on any background
+    let offset = abs(in.clip_pos.xy - camera.screen.xy * 0.5);
+    let half_thickness = locals.crosshair.y * 0.5;
+    if ((offset.x < locals.crosshair.x && offset.y < half_thickness)
+        || (offset.y < locals.crosshair.x && offset.x < half_thickness)) {
+        color = mix(color, vec3<f32>(1.0) - color, locals.crosshair.z);
+    }
+
+    return vec4<f32>(color, 1.0);
+}
@@ -4,6 +4,18 @@ struct CameraUniform {
     proj_mat: mat4x4<f32>,
     view_mat: mat4x4<f32>,
     all_mat: mat4x4<f32>,
+    cam_pos: vec4<f32>,
+    sky_color: vec4<f32>,
+    // w is light intensity
+    sun_dir: vec4<f32>,
+    // Fog start, fog end, ambient light
+    fog: vec4<f32>,
+    // x is debug view (0 - shaded, 1 - depth, 2 - normals, 3 - AO, 4 - light)
+    debug: vec4<u32>,
+    // Elapsed time (seconds), frame time (seconds)
+    time: vec4<f32>,
+    // Resolution (pixels), inverse resolution
+    screen: vec4<f32>,
 }
 
 @group(0)
@@ -11,6 +23,72 @@ struct CameraUniform {
 var<uniform> camera: CameraUniform;
 
 
+/// Per-draw data
+
+struct DrawData {
+    // Added to vertex positions
+    offset: vec4<f32>,
+    // Multiplies vertex colors
+    tint: vec4<f32>,
+}
+
+// Replaced with a push constant by the renderer if supported
+@group(1)
+@binding(0)
+var<uniform> draw: DrawData;
+
+
+/// Lighting
+
+// Flat normal (from screen space derivatives) facing the camera
+fn flat_normal(world_pos: vec3<f32>) -> vec3<f32> {
+    let normal = normalize(cross(dpdx(world_pos), dpdy(world_pos)));
+    // Visible surfaces always face the camera
+    if (dot(normal, camera.cam_pos.xyz - world_pos) < 0.0) {
+        return -normal;
+    }
+    return normal;
+}
+
+fn light(normal: vec3<f32>) -> f32 {
+    return camera.fog.z + camera.sun_dir.w * max(dot(normal, camera.sun_dir.xyz), 0.0);
+}
+
+// Flat shading with distance fog
+fn shade(color: vec3<f32>, world_pos: vec3<f32>) -> vec4<f32> {
+    let intensity = light(flat_normal(world_pos));
+    let fog = smoothstep(camera.fog.x, camera.fog.y, distance(camera.cam_pos.xyz, world_pos));
+
+    return vec4<f32>(mix(color * intensity, camera.sky_color.rgb, fog), 1.0);
+}
+
+// Intermediate value selected by the debug view
+fn debug_view(world_pos: vec3<f32>) -> vec4<f32> {
+    let normal = flat_normal(world_pos);
+
+    switch (camera.debug.x) {
+        // Linear distance to the camera, white is near
+        case 1u: {
+            let depth = clamp(distance(camera.cam_pos.xyz, world_pos) / camera.fog.y, 0.0, 1.0);
+            return vec4<f32>(vec3<f32>(1.0 - depth), 1.0);
+        }
+        case 2u: {
+            return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+        }
+        // No ambient occlusion yet
+        case 3u: {
+            return vec4<f32>(1.0);
+        }
+        case 4u: {
+            return vec4<f32>(vec3<f32>(light(normal)), 1.0);
+        }
+        default: {
+            return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+        }
+    }
+}
+
+
 /// Vertex Shader
 
 struct VertexInput {
@@ -21,6 +99,7 @@ struct VertexInput {
 struct VertexOutput {
     @builtin(position) clip_pos: vec4<f32>,
     @location(0) color: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
 }
 
 // This function is used to transform vertices
@@ -31,8 +110,10 @@ fn vs_main(
     var out: VertexOutput;
 
     // Manual casting of `VertexModel` to `VertexOutput`
-    out.clip_pos = camera.all_mat * vec4<f32>(model.pos, 1.0);
-    out.color = model.color;
+    let world_pos = model.pos + draw.offset.xyz;
+    out.clip_pos = camera.all_mat * vec4<f32>(world_pos, 1.0);
+    out.color = model.color * draw.tint.rgb;
+    out.world_pos = world_pos;
 
     return out;
 }
@@ -45,6 +126,40 @@ fn vs_main(
 fn fs_main(
     in: VertexOutput
 ) -> @location(0) vec4<f32> {
-    // Just contant color for every fragment
-    return vec4<f32>(in.color, 1.0);
+    return shade(in.color, in.world_pos);
+}
+
+// Alpha tested plant quads. Blades are cut out procedurally from the position inside the block
+@fragment
+fn fs_cutout(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    // Shaded before discarding, derivatives require uniform control flow
+    let color = shade(in.color, in.world_pos);
+
+    // Both diagonal quads span the whole block along x
+    let u = fract(in.world_pos.x);
+    let v = fract(in.world_pos.y);
+    // Four blades narrowing to the top
+    let blade = 1.0 - abs(fract(u * 4.0) - 0.5) * 2.0;
+    if (v > blade) {
+        discard;
+    }
+
+    return color;
+}
+
+@fragment
+fn fs_debug(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return debug_view(in.world_pos);
+}
+
+// Blended additively, so brighter pixels are drawn more times
+@fragment
+fn fs_overdraw(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.12, 0.06, 0.02, 1.0);
 }
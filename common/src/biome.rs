@@ -0,0 +1,95 @@
+use glam::Vec3;
+use noise::{NoiseFn, Perlin};
+
+use crate::coord::GlobalUnit;
+
+/// Climate of a world column. Both values are in `[0; 1]`
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Climate {
+    pub temperature: f32,
+    pub humidity: f32,
+}
+
+impl Climate {
+    /// Tint of vegetation colors (multiplier)
+    pub fn tint(&self) -> Vec3 {
+        const COLD_DRY: Vec3 = Vec3::new(1.0, 0.95, 0.85);
+        const COLD_HUMID: Vec3 = Vec3::new(0.7, 0.95, 1.05);
+        const HOT_DRY: Vec3 = Vec3::new(1.35, 1.0, 0.55);
+        const HOT_HUMID: Vec3 = Vec3::new(0.75, 1.1, 0.75);
+
+        let cold = COLD_DRY.lerp(COLD_HUMID, self.humidity);
+        let hot = HOT_DRY.lerp(HOT_HUMID, self.humidity);
+        cold.lerp(hot, self.temperature)
+    }
+}
+
+/// Smooth climate noise, so tints blend across biome borders
+pub struct ClimateMap {
+    temperature: Perlin,
+    humidity: Perlin,
+}
+
+impl ClimateMap {
+    /// Horizontal size of climate features (blocks)
+    pub const WAVELENGTH: f64 = 256.0;
+
+    pub fn new() -> Self {
+        Self::with_seed(Perlin::DEFAULT_SEED)
+    }
+
+    /// Climate of the world generated from `seed`
+    pub fn with_seed(seed: u32) -> Self {
+        Self {
+            temperature: Perlin::new(seed.wrapping_add(1)),
+            humidity: Perlin::new(seed.wrapping_add(2)),
+        }
+    }
+
+    pub fn get(&self, x: GlobalUnit, z: GlobalUnit) -> Climate {
+        let point = [x as f64 / Self::WAVELENGTH, z as f64 / Self::WAVELENGTH];
+        let normalize = |value: f64| (value as f32 * 0.5 + 0.5).clamp(0.0, 1.0);
+
+        Climate {
+            temperature: normalize(self.temperature.get(point)),
+            humidity: normalize(self.humidity.get(point)),
+        }
+    }
+}
+
+impl Default for ClimateMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{Climate, ClimateMap};
+
+    #[test]
+    fn smooth_climate() {
+        let map = ClimateMap::new();
+
+        for x in -64..64 {
+            let (a, b) = (map.get(x * 7, 13), map.get(x * 7 + 1, 13));
+            assert!((0.0..=1.0).contains(&a.temperature) && (0.0..=1.0).contains(&a.humidity));
+            // Neighbor columns differ only slightly
+            assert!((a.tint() - b.tint()).abs().max_element() < 0.05);
+        }
+
+        let hot = Climate {
+            temperature: 1.0,
+            humidity: 0.0,
+        };
+        let cold = Climate {
+            temperature: 0.0,
+            humidity: 0.0,
+        };
+        assert_ne!(hot.tint(), cold.tint());
+    }
+}
@@ -33,13 +33,17 @@ pub enum Block {
     // Cold Biomes
     SnowBlock,
     Ice,
+
+    // Plants
+    TallGrass,
+    Flower,
 }
 
 impl Block {
     pub const MIN: BlockRepr = Self::Air as BlockRepr;
-    pub const MAX: BlockRepr = Self::Ice as BlockRepr;
+    pub const MAX: BlockRepr = Self::Flower as BlockRepr;
 
-    pub const ALL: [Self; 17] = [
+    pub const ALL: [Self; 19] = [
         Self::Air,
         Self::Stone,
         Self::Dirt,
@@ -57,15 +61,56 @@ impl Block {
         Self::Mud,
         Self::SnowBlock,
         Self::Ice,
+        Self::TallGrass,
+        Self::Flower,
     ];
 
     pub fn id(&self) -> BlockRepr {
         *self as BlockRepr
     }
 
+    /// Block by its variant name, ignoring case (e.g. `sandstone`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|block| format!("{block:?}").eq_ignore_ascii_case(name))
+    }
+
+    /// Hash of the block registry (ids, names and properties).
+    ///
+    /// Used to check that both sides of a connection agree on block ids
+    pub fn registry_hash() -> u64 {
+        // FNV-1a, stable across builds unlike `DefaultHasher`
+        const OFFSET: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        Self::ALL
+            .iter()
+            .flat_map(|block| {
+                let mut bytes = format!("{block:?}").into_bytes();
+                bytes.extend([
+                    block.id(),
+                    block.opaque() as u8,
+                    block.liquid() as u8,
+                    block.cross() as u8,
+                ]);
+                bytes
+            })
+            .fold(OFFSET, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(PRIME)
+            })
+    }
+
+    /// Block hides faces of its neighbors
     #[inline]
     pub fn opaque(&self) -> bool {
-        !matches!(self, Self::Air)
+        !matches!(self, Self::Air) && !self.cross()
+    }
+
+    /// Block is rendered as two intersecting quads instead of a cube
+    #[inline]
+    pub fn cross(&self) -> bool {
+        matches!(self, Self::TallGrass | Self::Flower)
     }
 
     #[inline]
@@ -81,6 +126,18 @@ impl Block {
         )
     }
 
+    /// Entities can stand on the block and can't pass through it
+    #[inline]
+    pub fn solid(&self) -> bool {
+        self.opaque() && !self.liquid()
+    }
+
+    /// Color is tinted by the climate of the column (see [`crate::biome::Climate::tint`])
+    #[inline]
+    pub fn tinted(&self) -> bool {
+        matches!(self, Self::Grass | Self::Leaves | Self::TallGrass)
+    }
+
     pub fn color(&self) -> Vec3 {
         match self {
             Self::Air => Vec3::new(1.0, 1.0, 1.0),
@@ -100,6 +157,8 @@ impl Block {
             Self::Mud => Vec3::new(0.17, 0.131, 0.0221),
             Self::SnowBlock => Vec3::new(0.98, 0.98, 0.98),
             Self::Ice => Vec3::new(0.747, 0.877, 0.97),
+            Self::TallGrass => Vec3::new(0.25, 0.76, 0.3),
+            Self::Flower => Vec3::new(0.95, 0.78, 0.12),
         }
     }
 }
@@ -124,6 +183,8 @@ impl From<BlockRepr> for Block {
             14 => Self::Mud,
             15 => Self::SnowBlock,
             16 => Self::Ice,
+            17 => Self::TallGrass,
+            18 => Self::Flower,
             _ => Self::Air,
         }
     }
@@ -0,0 +1,131 @@
+use glam::Vec3;
+
+use crate::coord::{ChunkId, GlobalCoord, GlobalUnit, G_CHUNK_SIZE};
+
+/// Limits of the world. Blocks outside aren't generated, edited or loaded
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct WorldBounds {
+    /// Lowest block height (inclusive)
+    pub min_y: GlobalUnit,
+    /// Highest block height (inclusive)
+    pub max_y: GlobalUnit,
+    /// Blocks are limited to `-border..border` on X and Z axes (unlimited if `None`)
+    pub border: Option<GlobalUnit>,
+}
+
+impl WorldBounds {
+    pub const DEFAULT_MIN_Y: GlobalUnit = -256;
+    pub const DEFAULT_MAX_Y: GlobalUnit = 255;
+
+    pub fn contains(&self, pos: GlobalCoord) -> bool {
+        let inside = |value: GlobalUnit| {
+            self.border
+                .is_none_or(|border| (-border..border).contains(&value))
+        };
+
+        (self.min_y..=self.max_y).contains(&pos.y) && inside(pos.x) && inside(pos.z)
+    }
+
+    /// Check if at least one block of the chunk is inside
+    pub fn contains_chunk(&self, id: ChunkId) -> bool {
+        let min = id.to_coord();
+        let overlaps = |start: GlobalUnit, end: GlobalUnit, min: GlobalUnit| {
+            min + G_CHUNK_SIZE > start && min < end
+        };
+
+        overlaps(self.min_y, self.max_y + 1, min.y)
+            && self.border.is_none_or(|border| {
+                overlaps(-border, border, min.x) && overlaps(-border, border, min.z)
+            })
+    }
+
+    /// Check if every block of the chunk is inside
+    pub fn contains_chunk_fully(&self, id: ChunkId) -> bool {
+        let min = id.to_coord();
+        let max = GlobalCoord::new(
+            min.x + G_CHUNK_SIZE - 1,
+            min.y + G_CHUNK_SIZE - 1,
+            min.z + G_CHUNK_SIZE - 1,
+        );
+
+        self.contains(GlobalCoord::new(min.x, min.y, min.z)) && self.contains(max)
+    }
+
+    /// Horizontal distance from `pos` to the nearest border (negative outside). `None` if there is
+    /// no border
+    pub fn border_distance(&self, pos: Vec3) -> Option<f32> {
+        self.border
+            .map(|border| border as f32 - pos.x.abs().max(pos.z.abs()))
+    }
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self {
+            min_y: Self::DEFAULT_MIN_Y,
+            max_y: Self::DEFAULT_MAX_Y,
+            border: None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::coord::{ChunkId, GlobalCoord};
+
+    use super::WorldBounds;
+
+    const BOUNDS: WorldBounds = WorldBounds {
+        min_y: -8,
+        max_y: 23,
+        border: Some(20),
+    };
+
+    #[test]
+    fn contains_blocks() {
+        assert!(BOUNDS.contains(GlobalCoord::new(0, 0, 0)));
+        assert!(BOUNDS.contains(GlobalCoord::new(-20, -8, 19)));
+        assert!(BOUNDS.contains(GlobalCoord::new(19, 23, -20)));
+        assert!(!BOUNDS.contains(GlobalCoord::new(20, 0, 0)));
+        assert!(!BOUNDS.contains(GlobalCoord::new(0, 0, -21)));
+        assert!(!BOUNDS.contains(GlobalCoord::new(0, -9, 0)));
+        assert!(!BOUNDS.contains(GlobalCoord::new(0, 24, 0)));
+
+        let unlimited = WorldBounds::default();
+        assert!(unlimited.contains(GlobalCoord::new(1 << 40, 0, -(1 << 40))));
+    }
+
+    #[test]
+    fn contains_chunks() {
+        assert!(BOUNDS.contains_chunk(ChunkId::ZERO));
+        assert!(BOUNDS.contains_chunk_fully(ChunkId::ZERO));
+
+        // Partially inside on every axis
+        assert!(BOUNDS.contains_chunk(ChunkId::new(1, 1, -2)));
+        assert!(!BOUNDS.contains_chunk_fully(ChunkId::new(1, 1, -2)));
+
+        assert!(!BOUNDS.contains_chunk(ChunkId::new(2, 0, 0)));
+        assert!(!BOUNDS.contains_chunk(ChunkId::new(0, 0, -3)));
+        assert!(!BOUNDS.contains_chunk(ChunkId::new(0, 2, 0)));
+        assert!(!BOUNDS.contains_chunk(ChunkId::new(0, -2, 0)));
+    }
+
+    #[test]
+    fn border_distance() {
+        assert_eq!(
+            BOUNDS.border_distance(Vec3::new(5.0, 100.0, -12.0)),
+            Some(8.0)
+        );
+        assert_eq!(
+            BOUNDS.border_distance(Vec3::new(25.0, 0.0, 0.0)),
+            Some(-5.0)
+        );
+        assert_eq!(WorldBounds::default().border_distance(Vec3::ZERO), None);
+    }
+}
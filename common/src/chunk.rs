@@ -0,0 +1,637 @@
+use std::sync::Arc;
+
+use common_base::prof;
+use noise::{NoiseFn, Perlin};
+
+use crate::{
+    block::Block,
+    bounds::WorldBounds,
+    coord::{
+        BlockCoord, ChunkId, GlobalCoord, GlobalUnit, LocalUnit, CHUNK_CUBE, CHUNK_SIZE,
+        G_CHUNK_SIZE, L_CHUNK_SIZE,
+    },
+    direction::Direction,
+    rng::{self, salt},
+};
+
+/// Represents blocks of a single chunk. Shared between client and server.
+///
+/// Blocks are stored on the heap and shared between clones until one of them is modified
+#[derive(Clone)]
+pub struct Chunk {
+    blocks: Arc<[Block; CHUNK_CUBE]>,
+}
+
+impl Chunk {
+    const SEA_LEVEL: GlobalUnit = 0;
+    const SEA_LEVEL_BIAS: GlobalUnit = 15;
+    /// Seed of the terrain generated by the game and the server
+    pub const DEFAULT_SEED: u32 = Perlin::DEFAULT_SEED;
+
+    pub fn new() -> Self {
+        Self::from_blocks([Block::Air; CHUNK_CUBE])
+    }
+
+    pub fn from_blocks(blocks: [Block; CHUNK_CUBE]) -> Self {
+        Self {
+            blocks: Arc::new(blocks),
+        }
+    }
+
+    pub fn blocks(&self) -> &[Block; CHUNK_CUBE] {
+        &self.blocks
+    }
+
+    /// Mutable access to the blocks. Copies them if they're shared
+    pub fn blocks_mut(&mut self) -> &mut [Block; CHUNK_CUBE] {
+        Arc::make_mut(&mut self.blocks)
+    }
+
+    /// Cheap handle to the current blocks (e.g. for background tasks)
+    pub fn shared_blocks(&self) -> Arc<[Block; CHUNK_CUBE]> {
+        self.blocks.clone()
+    }
+
+    pub fn get(&self, pos: BlockCoord) -> Block {
+        self.blocks[pos.flatten()]
+    }
+
+    pub fn set(&mut self, pos: BlockCoord, block: Block) {
+        self.blocks_mut()[pos.flatten()] = block;
+    }
+
+    /// Check if chunk has at least one visible (non-air) block
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().all(|block| *block == Block::Air)
+    }
+
+    /// Apply random tick to the block at `pos`.
+    /// Returns new block if block has been changed
+    pub fn random_tick(&mut self, pos: BlockCoord) -> Option<Block> {
+        // Blocks on the top edge don't know about their upper neighbor
+        if pos.on_chunk_edge(Direction::Up) {
+            return None;
+        }
+
+        let above = self.get(pos.neighbor(Direction::Up));
+        let new = match self.get(pos) {
+            Block::Dirt if !above.opaque() => Block::Grass,
+            Block::Grass if above.opaque() && !above.liquid() => Block::Dirt,
+            _ => return None,
+        };
+
+        self.set(pos, new);
+        Some(new)
+    }
+
+    fn lerp(lhs: f64, rhs: f64, f: f64) -> f64 {
+        // More precise, less performant
+        lhs * (1.0 - f) + (rhs * f)
+        // Less precise, more performant
+        // lhs + f * (rhs - lhs)
+    }
+
+    /// Generate chunk without blocks outside `bounds`
+    pub fn generate(id: ChunkId, bounds: &WorldBounds) -> Self {
+        Self::generate_with_seed(id, bounds, Self::DEFAULT_SEED)
+    }
+
+    /// Same as [`Self::generate`], but terrain comes from another `seed`
+    pub fn generate_with_seed(id: ChunkId, bounds: &WorldBounds, seed: u32) -> Self {
+        let mut chunk = Self::generate_flat_with_seed(id, seed);
+        if bounds.contains_chunk_fully(id) {
+            return chunk;
+        }
+
+        let coord = id.to_coord();
+        chunk
+            .blocks_mut()
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| !bounds.contains(coord.to_global(&BlockCoord::from(*i))))
+            .for_each(|(_, block)| *block = Block::Air);
+
+        chunk
+    }
+
+    /// First solid block below the sky in the `x`, `z` column of generated terrain. `None` if
+    /// there are no solid blocks in bounds
+    pub fn generated_surface(
+        x: GlobalUnit,
+        z: GlobalUnit,
+        bounds: &WorldBounds,
+    ) -> Option<GlobalCoord> {
+        let top = GlobalCoord::new(x, bounds.max_y, z).to_chunk_id();
+        let bottom = bounds.min_y.div_euclid(G_CHUNK_SIZE);
+
+        (bottom..=top.y).rev().find_map(|y| {
+            let id = ChunkId::new(top.x, y, top.z);
+            let chunk = Self::generate(id, bounds);
+            let column = GlobalCoord::new(x, 0, z).to_block();
+
+            (0..L_CHUNK_SIZE)
+                .rev()
+                .map(|y| BlockCoord::new(column.x, y, column.z))
+                .find(|&pos| chunk.get(pos).solid())
+                .map(|pos| id.to_coord().to_global(&pos))
+        })
+    }
+
+    pub fn generate_flat(id: ChunkId) -> Self {
+        Self::generate_flat_with_seed(id, Self::DEFAULT_SEED)
+    }
+
+    pub fn generate_flat_with_seed(id: ChunkId, seed: u32) -> Self {
+        const WAVELENGTH: f64 = 10.0;
+
+        prof!("Chunk::generate_flat");
+        let perlin = Perlin::new(seed);
+        let coord = id.to_coord();
+        let mut blocks = [Block::Air; CHUNK_CUBE];
+        let height_map = (0..CHUNK_SIZE)
+            .map(|x| {
+                (0..CHUNK_SIZE)
+                    .map(|y| {
+                        let p = perlin.get([
+                            (x as f64 + coord.x as f64) * 0.1 / WAVELENGTH,
+                            (y as f64 + coord.z as f64) * 0.1 / WAVELENGTH,
+                        ]);
+                        Self::lerp(
+                            (Self::SEA_LEVEL - Self::SEA_LEVEL_BIAS) as f64,
+                            (Self::SEA_LEVEL + Self::SEA_LEVEL_BIAS) as f64,
+                            p,
+                        ) as GlobalUnit
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        blocks.iter_mut().enumerate().for_each(|(i, block)| {
+            let pos = coord.to_global(&BlockCoord::from(i));
+            let y_height = height_map[(pos.x as usize) % CHUNK_SIZE][(pos.z as usize) % CHUNK_SIZE];
+            *block = match pos.y {
+                y if y == y_height => {
+                    if y > Self::SEA_LEVEL - 20 {
+                        Block::Grass
+                    } else {
+                        Block::Sand
+                    }
+                }
+                y if y < y_height && y > y_height - 11 => Block::Dirt,
+                y if y < y_height - 10 => Block::Stone,
+                y if y > y_height && y < Self::SEA_LEVEL - 20 => Block::Water,
+                y if y == y_height + 1 && y_height > Self::SEA_LEVEL - 20 => {
+                    Self::decoration(pos.x, pos.z, seed)
+                }
+                _ => Block::Air,
+            };
+        });
+
+        Self::from_blocks(blocks)
+    }
+
+    /// Plant growing on the grass block of the `x`, `z` column
+    fn decoration(x: GlobalUnit, z: GlobalUnit, seed: u32) -> Block {
+        // Stable per column, so regenerated chunks get the same plants
+        match rng::hash_column(rng::mix_seed(seed, salt::DECORATION), x, z) % 64 {
+            0 => Block::Flower,
+            1..=7 => Block::TallGrass,
+            _ => Block::Air,
+        }
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Solid and empty 4³ regions of a chunk. Cheap to query when deciding if a chunk needs a mesh
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Occupancy {
+    /// Bit per region with only solid blocks
+    solid: u64,
+    /// Bit per region with only air
+    empty: u64,
+}
+
+impl Occupancy {
+    /// Side of a region in blocks
+    pub const REGION_SIZE: usize = 4;
+    /// Regions along each axis
+    const REGIONS: usize = CHUNK_SIZE / Self::REGION_SIZE;
+
+    pub fn new(blocks: &[Block; CHUNK_CUBE]) -> Self {
+        let mut occupancy = Self { solid: 0, empty: 0 };
+        (0..Self::REGIONS.pow(3)).for_each(|region| occupancy.classify(blocks, region));
+
+        occupancy
+    }
+
+    /// Reclassify the region of the block at `pos` after it has been changed
+    pub fn update(&mut self, blocks: &[Block; CHUNK_CUBE], pos: BlockCoord) {
+        let region = |value: LocalUnit| value as usize / Self::REGION_SIZE;
+        self.classify(
+            blocks,
+            (region(pos.x) * Self::REGIONS + region(pos.y)) * Self::REGIONS + region(pos.z),
+        );
+    }
+
+    fn classify(&mut self, blocks: &[Block; CHUNK_CUBE], region: usize) {
+        let (x, y, z) = Self::region_origin(region);
+        let (mut solid, mut empty) = (true, true);
+        for i in 0..Self::REGION_SIZE.pow(3) {
+            let block = blocks[BlockCoord::new(
+                x + (i / (Self::REGION_SIZE * Self::REGION_SIZE)) as LocalUnit,
+                y + (i / Self::REGION_SIZE % Self::REGION_SIZE) as LocalUnit,
+                z + (i % Self::REGION_SIZE) as LocalUnit,
+            )
+            .flatten()];
+            solid &= block.solid();
+            empty &= block == Block::Air;
+        }
+
+        let bit = 1 << region;
+        self.solid = if solid {
+            self.solid | bit
+        } else {
+            self.solid & !bit
+        };
+        self.empty = if empty {
+            self.empty | bit
+        } else {
+            self.empty & !bit
+        };
+    }
+
+    /// First block of the region
+    fn region_origin(region: usize) -> (LocalUnit, LocalUnit, LocalUnit) {
+        let origin = |index: usize| (index % Self::REGIONS * Self::REGION_SIZE) as LocalUnit;
+        (
+            origin(region / (Self::REGIONS * Self::REGIONS)),
+            origin(region / Self::REGIONS),
+            origin(region),
+        )
+    }
+
+    /// Chunk has only air
+    pub fn is_empty(&self) -> bool {
+        self.empty == u64::MAX
+    }
+
+    /// Chunk has only solid blocks
+    pub fn is_solid(&self) -> bool {
+        self.solid == u64::MAX
+    }
+
+    /// Check if the chunk side facing `dir` is covered by solid blocks
+    pub fn side_solid(&self, dir: Direction) -> bool {
+        (0..Self::REGIONS.pow(3))
+            .filter(|&region| {
+                let (x, y, z) = Self::region_origin(region);
+                let last = (CHUNK_SIZE - Self::REGION_SIZE) as LocalUnit;
+                match dir {
+                    Direction::Down => y == 0,
+                    Direction::Up => y == last,
+                    Direction::Left => x == 0,
+                    Direction::Right => x == last,
+                    Direction::Front => z == 0,
+                    Direction::Back => z == last,
+                }
+            })
+            .all(|region| self.solid & 1 << region != 0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug)]
+pub struct LoadArea {
+    start: ChunkId,
+    end: ChunkId,
+    current: ChunkId,
+}
+
+impl LoadArea {
+    const fn new(start: ChunkId, end: ChunkId) -> Self {
+        Self {
+            start,
+            end,
+            current: start,
+        }
+    }
+
+    pub fn new_cube(center: ChunkId, dist: GlobalUnit) -> Self {
+        Self::new(
+            ChunkId::new(center.x - dist, center.y - dist, center.z - dist),
+            ChunkId::new(center.x + dist, center.y + dist, center.z + dist),
+        )
+    }
+
+    /// Vertical distance is half of the horizontal one
+    pub fn new_cuboid(center: ChunkId, dist: GlobalUnit) -> Self {
+        Self::new_column(center, dist, dist / 2, dist / 2)
+    }
+
+    /// Same as [`Self::new_cuboid`], but the vertical range is `below` and `above` the center
+    pub fn new_column(
+        center: ChunkId,
+        dist: GlobalUnit,
+        below: GlobalUnit,
+        above: GlobalUnit,
+    ) -> Self {
+        Self::new(
+            ChunkId::new(center.x - dist, center.y - below, center.z - dist),
+            ChunkId::new(center.x + dist, center.y + above, center.z + dist),
+        )
+    }
+
+    /// Grow the area by `offset` chunks towards the offset direction on each axis
+    pub fn extend(self, offset: ChunkId) -> Self {
+        let grow = |start: GlobalUnit, end: GlobalUnit, offset: GlobalUnit| {
+            (start + offset.min(0), end + offset.max(0))
+        };
+        let (start_x, end_x) = grow(self.start.x, self.end.x, offset.x);
+        let (start_y, end_y) = grow(self.start.y, self.end.y, offset.y);
+        let (start_z, end_z) = grow(self.start.z, self.end.z, offset.z);
+
+        Self::new(
+            ChunkId::new(start_x, start_y, start_z),
+            ChunkId::new(end_x, end_y, end_z),
+        )
+    }
+
+    pub fn contains(&self, id: ChunkId) -> bool {
+        !(id.x < self.start.x
+            || id.x > self.end.x
+            || id.y < self.start.y
+            || id.y > self.end.y
+            || id.z < self.start.z
+            || id.z > self.end.z)
+    }
+}
+
+impl Iterator for LoadArea {
+    type Item = ChunkId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current.z > self.end.z {
+            return None;
+        }
+
+        let item = self.current;
+        let mut new = self.current;
+
+        fn clamped_inc(src: &mut GlobalUnit, clamp: GlobalUnit) -> bool {
+            if *src < clamp {
+                *src += 1;
+                false
+            } else {
+                true
+            }
+        }
+
+        if clamped_inc(&mut new.x, self.end.x) {
+            new.x = self.start.x;
+            if clamped_inc(&mut new.y, self.end.y) {
+                new.y = self.start.y;
+                clamped_inc(&mut new.z, self.end.z + 1);
+            }
+        }
+
+        self.current = new;
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        block::Block,
+        bounds::WorldBounds,
+        coord::{BlockCoord, ChunkId, GlobalCoord, CHUNK_CUBE},
+        direction::Direction,
+    };
+
+    use super::{Chunk, LoadArea, Occupancy};
+
+    #[test]
+    fn load_area_iter_cube() {
+        let loaded_area = LoadArea::new_cube(ChunkId::ZERO, 1).collect::<Vec<_>>();
+
+        assert_eq!(
+            loaded_area,
+            [
+                ChunkId::new(-1, -1, -1),
+                ChunkId::new(0, -1, -1),
+                ChunkId::new(1, -1, -1),
+                ChunkId::new(-1, 0, -1),
+                ChunkId::new(0, 0, -1),
+                ChunkId::new(1, 0, -1),
+                ChunkId::new(-1, 1, -1),
+                ChunkId::new(0, 1, -1),
+                ChunkId::new(1, 1, -1),
+                ChunkId::new(-1, -1, 0),
+                ChunkId::new(0, -1, 0),
+                ChunkId::new(1, -1, 0),
+                ChunkId::new(-1, 0, 0),
+                ChunkId::ZERO,
+                ChunkId::new(1, 0, 0),
+                ChunkId::new(-1, 1, 0),
+                ChunkId::new(0, 1, 0),
+                ChunkId::new(1, 1, 0),
+                ChunkId::new(-1, -1, 1),
+                ChunkId::new(0, -1, 1),
+                ChunkId::new(1, -1, 1),
+                ChunkId::new(-1, 0, 1),
+                ChunkId::new(0, 0, 1),
+                ChunkId::new(1, 0, 1),
+                ChunkId::new(-1, 1, 1),
+                ChunkId::new(0, 1, 1),
+                ChunkId::new(1, 1, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn load_area_column() {
+        let area = LoadArea::new_column(ChunkId::new(0, 5, 0), 1, 3, 0);
+
+        assert_eq!(area.clone().count(), 3 * 3 * 4);
+        assert!(area.contains(ChunkId::new(1, 2, -1)));
+        assert!(!area.contains(ChunkId::new(0, 1, 0)));
+        assert!(!area.contains(ChunkId::new(0, 6, 0)));
+    }
+
+    #[test]
+    fn load_area_iter_cuboid() {
+        let loaded_area = LoadArea::new_cuboid(ChunkId::ZERO, 1).collect::<Vec<_>>();
+
+        assert_eq!(
+            loaded_area,
+            [
+                ChunkId::new(-1, 0, -1),
+                ChunkId::new(0, 0, -1),
+                ChunkId::new(1, 0, -1),
+                ChunkId::new(-1, 0, 0),
+                ChunkId::ZERO,
+                ChunkId::new(1, 0, 0),
+                ChunkId::new(-1, 0, 1),
+                ChunkId::new(0, 0, 1),
+                ChunkId::new(1, 0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn load_area_contains() {
+        let load_area = LoadArea::new_cube(ChunkId::ZERO, 2);
+
+        assert!(load_area.contains(ChunkId::ZERO));
+        assert!(load_area.contains(ChunkId::new(1, 1, 1)));
+        assert!(!load_area.contains(ChunkId::new(3, 3, 3)));
+        assert!(!load_area.contains(ChunkId::new(3, 32, 12)));
+    }
+
+    #[test]
+    fn load_area_extend() {
+        let load_area = LoadArea::new_cube(ChunkId::ZERO, 1).extend(ChunkId::new(2, 0, -1));
+
+        assert!(load_area.contains(ChunkId::new(3, 0, 0)));
+        assert!(!load_area.contains(ChunkId::new(-2, 0, 0)));
+        assert!(load_area.contains(ChunkId::new(0, 0, -2)));
+        assert!(!load_area.contains(ChunkId::new(0, 0, 2)));
+        assert_eq!(load_area.count(), 5 * 3 * 4);
+    }
+
+    #[test]
+    fn random_tick_grass_spread() {
+        let mut chunk = Chunk::new();
+        chunk.set(BlockCoord::new(1, 1, 1), Block::Dirt);
+
+        assert_eq!(
+            chunk.random_tick(BlockCoord::new(1, 1, 1)),
+            Some(Block::Grass)
+        );
+        assert_eq!(chunk.random_tick(BlockCoord::new(1, 1, 1)), None);
+
+        chunk.set(BlockCoord::new(1, 2, 1), Block::Stone);
+        assert_eq!(
+            chunk.random_tick(BlockCoord::new(1, 1, 1)),
+            Some(Block::Dirt)
+        );
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let mut chunk = Chunk::new();
+        let shared = chunk.shared_blocks();
+
+        chunk.set(BlockCoord::new(1, 2, 3), Block::Stone);
+        assert_eq!(chunk.get(BlockCoord::new(1, 2, 3)), Block::Stone);
+        // Snapshot isn't affected
+        assert_eq!(shared[BlockCoord::new(1, 2, 3).flatten()], Block::Air);
+    }
+
+    #[test]
+    fn generate_within_bounds() {
+        let bounds = WorldBounds {
+            min_y: -4,
+            max_y: 4,
+            border: Some(8),
+        };
+
+        let id = ChunkId::new(0, -1, 0);
+        let chunk = Chunk::generate(id, &bounds);
+        let unbounded = Chunk::generate_flat(id);
+        for i in 0..CHUNK_CUBE {
+            let pos = BlockCoord::from(i);
+            if pos.x >= 8 || pos.y < 12 {
+                assert_eq!(chunk.get(pos), Block::Air);
+            } else {
+                assert_eq!(chunk.get(pos), unbounded.get(pos));
+            }
+        }
+
+        assert!(Chunk::generate(ChunkId::new(0, -2, 0), &bounds).is_empty());
+    }
+
+    #[test]
+    fn surface_below_sky() {
+        let bounds = WorldBounds::default();
+        let surface = Chunk::generated_surface(3, -7, &bounds).unwrap();
+        assert_eq!((surface.x, surface.z), (3, -7));
+
+        let block = |y| {
+            let pos = GlobalCoord::new(3, y, -7);
+            Chunk::generate_flat(pos.to_chunk_id()).get(pos.to_block())
+        };
+        assert!(block(surface.y).solid());
+        assert!((surface.y + 1..surface.y + 20).all(|y| !block(y).solid()));
+
+        // Nothing solid in bounds
+        let sky = WorldBounds {
+            min_y: 100,
+            max_y: 120,
+            border: None,
+        };
+        assert_eq!(Chunk::generated_surface(0, 0, &sky), None);
+    }
+
+    #[test]
+    fn plants_grow_on_grass() {
+        let mut plants = 0;
+        for id in [
+            ChunkId::ZERO,
+            ChunkId::new(1, 0, -1),
+            ChunkId::new(0, -1, 2),
+        ] {
+            let chunk = Chunk::generate_flat(id);
+            for i in 0..CHUNK_CUBE {
+                let pos = BlockCoord::from(i);
+                if !chunk.get(pos).cross() || pos.on_chunk_edge(Direction::Down) {
+                    continue;
+                }
+
+                assert_eq!(chunk.get(pos.neighbor(Direction::Down)), Block::Grass);
+                plants += 1;
+            }
+        }
+
+        assert!(plants > 0);
+    }
+
+    #[test]
+    fn occupancy_regions() {
+        let mut blocks = [Block::Air; CHUNK_CUBE];
+        let occupancy = Occupancy::new(&blocks);
+        assert!(occupancy.is_empty() && !occupancy.is_solid());
+
+        // Bottom half is stone
+        blocks
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| BlockCoord::from(*i).y < 8)
+            .for_each(|(_, block)| *block = Block::Stone);
+        let occupancy = Occupancy::new(&blocks);
+        assert!(!occupancy.is_empty() && !occupancy.is_solid());
+        assert!(occupancy.side_solid(Direction::Down));
+        assert!(!occupancy.side_solid(Direction::Up));
+        assert!(!occupancy.side_solid(Direction::Left));
+
+        // Everything is solid, except for a single water block on the left side
+        blocks.fill(Block::Dirt);
+        let pos = BlockCoord::new(0, 13, 6);
+        blocks[pos.flatten()] = Block::Water;
+        let mut occupancy = Occupancy::new(&blocks);
+        assert!(!occupancy.is_solid());
+        assert!(!occupancy.side_solid(Direction::Left));
+        assert!(occupancy.side_solid(Direction::Right));
+
+        // Only the region of the changed block is reclassified
+        blocks[pos.flatten()] = Block::Sand;
+        occupancy.update(&blocks, pos);
+        assert!(occupancy.is_solid());
+        assert_eq!(occupancy, Occupancy::new(&blocks));
+    }
+}
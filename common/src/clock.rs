@@ -1,10 +1,11 @@
 use std::{
     collections::VecDeque,
+    hint,
     time::{Duration, Instant},
 };
 
 use common_base::span;
-use spin_sleep::sleep;
+use spin_sleep::native_sleep;
 
 /// Clock tries to keep tick a constant time
 pub struct Clock {
@@ -14,6 +15,8 @@ pub struct Clock {
     last: Instant,
     /// Last tick duration
     last_dur: Duration,
+    /// Oversleep of the last tick. Next tick is shortened by it, so ticks average to the target
+    carry: Duration,
 
     // Statistics related
     /// Statistics store
@@ -26,12 +29,18 @@ pub struct Clock {
 
 impl Clock {
     pub const HISTORY_LENGTH: usize = 100;
+    /// Last part of the wait is spun instead of slept, since the OS may wake the thread up late
+    #[cfg(windows)]
+    pub const SPIN_MARGIN: Duration = Duration::from_millis(2);
+    #[cfg(not(windows))]
+    pub const SPIN_MARGIN: Duration = Duration::from_micros(500);
 
     pub fn new(target: Duration) -> Self {
         Self {
             target,
             last: Instant::now(),
             last_dur: target,
+            carry: Duration::ZERO,
             stats: ClockStats::new(),
             tick_durs: VecDeque::with_capacity(Self::HISTORY_LENGTH),
             tick_busy_durs: VecDeque::with_capacity(Self::HISTORY_LENGTH),
@@ -63,13 +72,23 @@ impl Clock {
         // Update stats
         self.stats.update(&self.tick_durs, &self.tick_busy_durs);
 
-        // Sleep if current tick duration is not negative
-        if let Some(sleep_dur) = self.target.checked_sub(busy) {
-            sleep(sleep_dur);
+        // Wait if the tick was shorter than the target
+        let deadline = self.last + self.target.saturating_sub(self.carry);
+        let waited = now < deadline;
+        if waited {
+            Self::wait_until(deadline);
         }
 
         // Time after sleep
         let after = Instant::now();
+        // Overrun ticks aren't compensated, only the wait inaccuracy is
+        self.carry = if waited {
+            after
+                .saturating_duration_since(deadline)
+                .min(self.target / 2)
+        } else {
+            Duration::ZERO
+        };
         // Save duration of current tick
         self.last_dur = after.duration_since(self.last);
 
@@ -90,6 +109,20 @@ impl Clock {
         // Save current tick time
         self.last = after;
     }
+
+    /// Sleep most of the time until `deadline` and spin the rest
+    fn wait_until(deadline: Instant) {
+        if let Some(sleep) = deadline
+            .saturating_duration_since(Instant::now())
+            .checked_sub(Self::SPIN_MARGIN)
+        {
+            native_sleep(sleep);
+        }
+
+        while Instant::now() < deadline {
+            hint::spin_loop();
+        }
+    }
 }
 
 // TODO: Add percentiles (50, 90, 95, 99)
@@ -120,3 +153,33 @@ impl ClockStats {
         self.avg_tps = 1.0 / (tick_durs.iter().sum::<f32>() / tick_durs.len().max(1) as f32);
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Clock;
+
+    #[test]
+    fn accurate_average_tick() {
+        const TICKS: u32 = 20;
+
+        let target = Duration::from_millis(5);
+        let mut clock = Clock::new(target);
+        let start = Instant::now();
+        for _ in 0..TICKS {
+            clock.tick();
+        }
+
+        let average = start.elapsed() / TICKS;
+        assert!(
+            average >= target - Duration::from_micros(500),
+            "{average:?}"
+        );
+        assert!(average < target + Duration::from_millis(1), "{average:?}");
+    }
+}
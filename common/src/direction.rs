@@ -1,4 +1,4 @@
-#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Direction {
     Down,
     Up,
@@ -0,0 +1,21 @@
+/// Unique entity identifier assigned by the server
+pub type EntityId = u64;
+
+/// Represents kind of entity
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum EntityKind {
+    Player = 0,
+}
+
+impl EntityKind {
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Player),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::mem;
+
+use crate::{
+    block::Block,
+    coord::{ChunkId, GlobalCoord},
+    entity::{EntityId, EntityKind},
+};
+
+/// Change of the world state published by the simulation
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum WorldEvent {
+    BlockChanged {
+        pos: GlobalCoord,
+        before: Block,
+        after: Block,
+    },
+    /// Block has been placed, broken or replaced by a player. Published by the server along with
+    /// applying the edit
+    BlockEdited {
+        pos: GlobalCoord,
+        before: Block,
+        after: Block,
+    },
+    /// Chunk has been generated or received from the server. Also published when a loaded chunk
+    /// is replaced as a whole
+    ChunkLoaded(ChunkId),
+    EntitySpawned {
+        id: EntityId,
+        kind: EntityKind,
+    },
+    /// Local player has crossed a chunk border
+    PlayerMovedChunk {
+        from: ChunkId,
+        to: ChunkId,
+    },
+}
+
+impl WorldEvent {
+    /// Chunk whose blocks have changed. `None` for events which don't change blocks
+    pub fn changed_chunk(&self) -> Option<ChunkId> {
+        match self {
+            Self::BlockChanged { pos, .. } | Self::BlockEdited { pos, .. } => {
+                Some(pos.to_chunk_id())
+            }
+            Self::ChunkLoaded(id) => Some(*id),
+            Self::EntitySpawned { .. } | Self::PlayerMovedChunk { .. } => None,
+        }
+    }
+}
+
+/// Double-buffered event queue.
+///
+/// Events published during a frame are readable during the next one, after [`Self::update`], so
+/// every consumer sees every event exactly once regardless of the order systems run in
+#[derive(Default, Debug)]
+pub struct EventBus {
+    /// Published since the last update
+    pending: Vec<WorldEvent>,
+    /// Published before the last update
+    current: Vec<WorldEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&mut self, event: WorldEvent) {
+        self.pending.push(event);
+    }
+
+    /// Make events published since the last call readable. Should be called once per frame,
+    /// previously readable events are dropped
+    pub fn update(&mut self) {
+        mem::swap(&mut self.pending, &mut self.current);
+        self.pending.clear();
+    }
+
+    /// Events published before the last update
+    pub fn read(&self) -> impl Iterator<Item = &WorldEvent> {
+        self.current.iter()
+    }
+
+    /// Drop both readable and pending events
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.current.clear();
+    }
+}
+
+impl Extend<WorldEvent> for EventBus {
+    fn extend<T: IntoIterator<Item = WorldEvent>>(&mut self, events: T) {
+        self.pending.extend(events);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        block::Block,
+        coord::{ChunkId, GlobalCoord},
+    };
+
+    use super::{EventBus, WorldEvent};
+
+    #[test]
+    fn events_readable_after_update() {
+        let mut bus = EventBus::new();
+        let changed = WorldEvent::BlockChanged {
+            pos: GlobalCoord::new(-1, 17, 3),
+            before: Block::Air,
+            after: Block::Stone,
+        };
+
+        bus.publish(changed);
+        assert_eq!(bus.read().count(), 0);
+
+        bus.update();
+        bus.extend([WorldEvent::ChunkLoaded(ChunkId::new(0, 0, 0))]);
+        assert_eq!(bus.read().copied().collect::<Vec<_>>(), [changed]);
+        assert_eq!(changed.changed_chunk(), Some(ChunkId::new(-1, 1, 0)));
+
+        bus.update();
+        assert_eq!(
+            bus.read().copied().collect::<Vec<_>>(),
+            [WorldEvent::ChunkLoaded(ChunkId::new(0, 0, 0))]
+        );
+
+        bus.update();
+        assert_eq!(bus.read().count(), 0);
+    }
+}
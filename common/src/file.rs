@@ -0,0 +1,55 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Extension of files being written by [`write_atomic`]
+pub const TMP_EXTENSION: &str = "tmp";
+
+/// Replace the file with `bytes` so it's either fully written or unchanged after a crash
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let tmp = tmp_path(path);
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(bytes)?;
+    // Data must reach the disk before the rename does
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(tmp, path)
+}
+
+/// Temporary file [`write_atomic`] writes `path` through
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(TMP_EXTENSION);
+    path.with_file_name(name)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{tmp_path, write_atomic};
+
+    #[test]
+    fn write_replaces_file() {
+        let dir = std::env::temp_dir().join(format!("ecg-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.txt");
+
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert!(!tmp_path(&path).exists());
+        assert_eq!(tmp_path(&path), dir.join("settings.txt.tmp"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
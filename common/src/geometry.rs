@@ -0,0 +1,214 @@
+use glam::{Mat4, Vec3, Vec4};
+
+/// Axis-aligned bounding box
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn translate(&self, offset: Vec3) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.min.cmple(point).all() && self.max.cmpge(point).all()
+    }
+
+    /// Boxes overlap (touching faces excluded)
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmplt(other.max).all() && self.max.cmpgt(other.min).all()
+    }
+}
+
+/// Half-line starting at `origin`
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Normalized direction
+    pub dir: Vec3,
+}
+
+impl Ray {
+    /// `dir` is normalized
+    pub fn new(origin: Vec3, dir: Vec3) -> Self {
+        Self {
+            origin,
+            dir: dir.normalize(),
+        }
+    }
+
+    /// Point at `distance` from the origin
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.dir * distance
+    }
+
+    /// Distance to the plane. `None` if the ray is parallel to the plane or points away from it
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denom = plane.normal.dot(self.dir);
+        if denom.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let distance = -plane.distance(self.origin) / denom;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Distance to the box (slab method). `0.0` if the ray starts inside
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let inv = self.dir.recip();
+        let t1 = (aabb.min - self.origin) * inv;
+        let t2 = (aabb.max - self.origin) * inv;
+
+        let near = t1.min(t2).max_element().max(0.0);
+        let far = t1.max(t2).min_element();
+
+        (near <= far).then_some(near)
+    }
+}
+
+/// Plane of points `p` where `normal.dot(p) + d == 0`
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Plane {
+    /// Normalized normal. Points to the positive half-space
+    pub normal: Vec3,
+    pub d: f32,
+}
+
+impl Plane {
+    pub const fn new(normal: Vec3, d: f32) -> Self {
+        Self { normal, d }
+    }
+
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Self {
+        let normal = normal.normalize();
+        Self::new(normal, -normal.dot(point))
+    }
+
+    /// Plane from `(a, b, c, d)` coefficients of `ax + by + cz + d = 0`
+    pub fn from_coefficients(coefficients: Vec4) -> Self {
+        let length = coefficients.truncate().length();
+        Self::new(coefficients.truncate() / length, coefficients.w / length)
+    }
+
+    /// Signed distance to the point (positive in front of the plane)
+    pub fn distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) + self.d
+    }
+}
+
+/// Volume visible by a camera. Plane normals point inside
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near, far
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extract planes from `projection * view` matrix (depth range is 0..1)
+    pub fn from_matrix(view_proj: Mat4) -> Self {
+        let (x, y, z, w) = (
+            view_proj.row(0),
+            view_proj.row(1),
+            view_proj.row(2),
+            view_proj.row(3),
+        );
+
+        Self {
+            planes: [w + x, w - x, w + y, w - y, z, w - z].map(Plane::from_coefficients),
+        }
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.distance(point) >= 0.0)
+    }
+
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance(center) >= -radius)
+    }
+
+    /// Conservative test: boxes near frustum corners may pass while being outside
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // Box corner furthest along the plane normal
+            let corner = Vec3::select(plane.normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            plane.distance(corner) >= 0.0
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, Vec3};
+
+    use super::{Aabb, Frustum, Plane, Ray};
+
+    #[test]
+    fn ray_intersections() {
+        let ray = Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -2.0, 0.0));
+        assert_eq!(ray.dir, Vec3::NEG_Y);
+
+        let ground = Plane::from_point_normal(Vec3::new(0.0, 1.0, 0.0), Vec3::Y);
+        assert_eq!(ray.intersect_plane(&ground), Some(4.0));
+        assert_eq!(ray.at(4.0), Vec3::new(0.0, 1.0, 0.0));
+        // Plane behind the ray
+        let ceiling = Plane::from_point_normal(Vec3::new(0.0, 10.0, 0.0), Vec3::NEG_Y);
+        assert_eq!(ray.intersect_plane(&ceiling), None);
+
+        let aabb = Aabb::new(Vec3::new(-1.0, 0.0, -1.0), Vec3::new(1.0, 2.0, 1.0));
+        assert_eq!(ray.intersect_aabb(&aabb), Some(3.0));
+        assert_eq!(
+            Ray::new(aabb.center(), Vec3::X).intersect_aabb(&aabb),
+            Some(0.0)
+        );
+        assert_eq!(
+            Ray::new(Vec3::new(5.0, 5.0, 0.0), Vec3::NEG_Y).intersect_aabb(&aabb),
+            None
+        );
+    }
+
+    #[test]
+    fn frustum_culling() {
+        // Camera at the origin looking along +Z
+        let view_proj = Mat4::perspective_lh(90f32.to_radians(), 1.0, 0.1, 100.0);
+        let frustum = Frustum::from_matrix(view_proj);
+
+        assert!(frustum.contains(Vec3::new(0.0, 0.0, 10.0)));
+        assert!(frustum.contains(Vec3::new(9.0, -9.0, 10.0)));
+        assert!(!frustum.contains(Vec3::new(0.0, 0.0, -1.0)));
+        assert!(!frustum.contains(Vec3::new(11.0, 0.0, 10.0)));
+        assert!(!frustum.contains(Vec3::new(0.0, 0.0, 101.0)));
+
+        let unit = Aabb::new(Vec3::ZERO, Vec3::ONE);
+        assert!(frustum.intersects_aabb(&unit.translate(Vec3::new(0.0, 0.0, 5.0))));
+        // Partially visible
+        assert!(frustum.intersects_aabb(&unit.translate(Vec3::new(4.5, 0.0, 5.0))));
+        assert!(!frustum.intersects_aabb(&unit.translate(Vec3::new(0.0, 0.0, -5.0))));
+        assert!(!frustum.intersects_aabb(&unit.translate(Vec3::new(-8.0, 0.0, 5.0))));
+
+        assert!(frustum.intersects_sphere(Vec3::new(0.0, 0.0, -0.5), 1.0));
+        assert!(!frustum.intersects_sphere(Vec3::new(0.0, 0.0, -5.0), 1.0));
+    }
+}
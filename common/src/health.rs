@@ -0,0 +1,169 @@
+use crate::block::Block;
+
+/// Default max health (in half hearts)
+pub const DEFAULT_MAX_HEALTH: u16 = 20;
+/// Falls up to this height (in blocks) don't hurt
+pub const SAFE_FALL_DISTANCE: f32 = 3.0;
+/// Damage dealt by lava each `LAVA_DAMAGE_INTERVAL`
+pub const LAVA_DAMAGE: u16 = 4;
+/// Interval between lava damage ticks (in seconds)
+pub const LAVA_DAMAGE_INTERVAL: f32 = 0.5;
+
+/// What has dealt the damage
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DamageSource {
+    Fall,
+    Lava,
+}
+
+impl DamageSource {
+    /// Blocks hurting entities which touch them
+    pub fn from_contact(block: Block) -> Option<Self> {
+        match block {
+            Block::Lava | Block::MovingLava | Block::Magma | Block::MovingMagma => Some(Self::Lava),
+            _ => None,
+        }
+    }
+
+    /// Localization key of the message shown when an entity dies
+    pub fn death_message_key(&self) -> &'static str {
+        match self {
+            Self::Fall => "death.fall",
+            Self::Lava => "death.lava",
+        }
+    }
+}
+
+/// Damage dealt by falling from `distance` blocks
+pub fn fall_damage(distance: f32) -> u16 {
+    (distance - SAFE_FALL_DISTANCE).ceil().max(0.0) as u16
+}
+
+/// Health component (in half hearts)
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Health {
+    current: u16,
+    max: u16,
+    /// Source of the last received damage
+    last_damage: Option<DamageSource>,
+}
+
+impl Health {
+    pub const fn new(max: u16) -> Self {
+        Self {
+            current: max,
+            max,
+            last_damage: None,
+        }
+    }
+
+    pub fn current(&self) -> u16 {
+        self.current
+    }
+
+    pub fn max(&self) -> u16 {
+        self.max
+    }
+
+    pub fn last_damage(&self) -> Option<DamageSource> {
+        self.last_damage
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+
+    /// Deal damage. Returns `true` if it has killed the entity
+    pub fn damage(&mut self, amount: u16, source: DamageSource) -> bool {
+        if self.is_dead() || amount == 0 {
+            return false;
+        }
+
+        self.current = self.current.saturating_sub(amount);
+        self.last_damage = Some(source);
+
+        self.is_dead()
+    }
+
+    /// Restore health. Dead entities can't be healed, use `reset` instead
+    pub fn heal(&mut self, amount: u16) {
+        if !self.is_dead() {
+            self.current = (self.current + amount).min(self.max);
+        }
+    }
+
+    /// Restore full health (e.g. on respawn)
+    pub fn reset(&mut self) {
+        self.current = self.max;
+        self.last_damage = None;
+    }
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_HEALTH)
+    }
+}
+
+/// Tracks height of a fall to compute fall damage on landing
+#[derive(Clone, Copy, Default, Debug)]
+pub struct FallTracker {
+    /// Highest position since the entity left the ground
+    highest: Option<f32>,
+}
+
+impl FallTracker {
+    /// Update with the current height. Returns fall distance on landing
+    pub fn update(&mut self, y: f32, on_ground: bool) -> Option<f32> {
+        if on_ground {
+            self.highest.take().map(|highest| (highest - y).max(0.0))
+        } else {
+            self.highest = Some(self.highest.map_or(y, |highest| highest.max(y)));
+            None
+        }
+    }
+
+    /// Forget the current fall (e.g. landed in water or teleported)
+    pub fn reset(&mut self) {
+        self.highest = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fall_damage, DamageSource, FallTracker, Health};
+
+    #[test]
+    fn damage_and_death() {
+        let mut health = Health::new(10);
+
+        assert!(!health.damage(4, DamageSource::Lava));
+        health.heal(100);
+        assert_eq!(health.current(), 10);
+
+        assert!(health.damage(12, DamageSource::Fall));
+        assert!(health.is_dead());
+        assert_eq!(health.last_damage(), Some(DamageSource::Fall));
+        // Already dead
+        assert!(!health.damage(1, DamageSource::Lava));
+
+        health.reset();
+        assert_eq!(health.current(), 10);
+    }
+
+    #[test]
+    fn fall_tracking() {
+        let mut fall = FallTracker::default();
+
+        assert_eq!(fall.update(10.0, true), None);
+        // Jump up and fall down
+        fall.update(11.0, false);
+        fall.update(12.0, false);
+        fall.update(5.0, false);
+        assert_eq!(fall.update(2.0, true), Some(10.0));
+        assert_eq!(fall.update(2.0, true), None);
+
+        assert_eq!(fall_damage(2.0), 0);
+        assert_eq!(fall_damage(10.0), 7);
+    }
+}
@@ -1,4 +1,25 @@
+pub mod biome;
 pub mod block;
+pub mod bounds;
+pub mod chunk;
 pub mod clock;
 pub mod coord;
 pub mod direction;
+pub mod entity;
+pub mod event;
+pub mod file;
+pub mod geometry;
+pub mod health;
+pub mod movement;
+pub mod net;
+pub mod palette;
+pub mod path;
+pub mod physics;
+pub mod rng;
+pub mod rules;
+pub mod schematic;
+pub mod sky;
+pub mod stats;
+
+#[cfg(test)]
+mod testing;
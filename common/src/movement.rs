@@ -0,0 +1,70 @@
+use glam::{Vec2, Vec3};
+
+use crate::{coord::GlobalCoord, geometry::Aabb, physics::sweep};
+
+/// Player movement speed (blocks per second)
+pub const PLAYER_SPEED: f32 = 25.0;
+/// Player bounding box relative to the eye position
+pub const PLAYER_AABB: Aabb = Aabb::new(Vec3::new(-0.3, -1.6, -0.3), Vec3::new(0.3, 0.2, 0.3));
+
+/// Input sequence number
+pub type InputSeq = u32;
+
+/// Single player movement input.
+///
+/// Applied by both the client (prediction) and the server (authoritative state)
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct PlayerInput {
+    /// Sequence number used for reconciliation
+    pub seq: InputSeq,
+    /// Forward (positive) / backward (negative) movement
+    pub forward: f32,
+    /// Left (positive) / right (negative) movement
+    pub strafe: f32,
+    /// Up (positive) / down (negative) movement
+    pub lift: f32,
+    /// Player rotation (yaw & pitch)
+    pub rot: Vec2,
+    /// Input duration in seconds
+    pub dt: f32,
+}
+
+impl PlayerInput {
+    /// Forward unit vector on the XZ plane
+    pub fn forward_xz(yaw: f32) -> Vec3 {
+        let (yaw_sin, yaw_cos) = yaw.sin_cos();
+        Vec3::new(yaw_sin, 0.0, yaw_cos)
+    }
+
+    /// All fields are finite numbers
+    pub fn is_finite(&self) -> bool {
+        self.forward.is_finite()
+            && self.strafe.is_finite()
+            && self.lift.is_finite()
+            && self.rot.is_finite()
+            && self.dt.is_finite()
+    }
+
+    /// Apply input to the position. Non-finite fields are treated as zero
+    pub fn apply(&self, mut pos: Vec3) -> Vec3 {
+        let finite = |value: f32| if value.is_finite() { value } else { 0.0 };
+        let move_modifier = PLAYER_SPEED * finite(self.dt);
+
+        let forward = Self::forward_xz(finite(self.rot.x));
+        let right = forward.cross(Vec3::Y);
+
+        // Move forward/backward
+        pos += forward * finite(self.forward).clamp(-1.0, 1.0) * move_modifier;
+        // Move left/right
+        pos += right * finite(self.strafe).clamp(-1.0, 1.0) * move_modifier;
+        // Move up/down
+        pos.y += finite(self.lift).clamp(-1.0, 1.0) * move_modifier;
+
+        pos
+    }
+
+    /// Apply input to the position colliding with solid blocks
+    pub fn apply_collide(&self, pos: Vec3, solid: impl Fn(GlobalCoord) -> Option<bool>) -> Vec3 {
+        pos + sweep(PLAYER_AABB.translate(pos), self.apply(pos) - pos, solid).delta
+    }
+}
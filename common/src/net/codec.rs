@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    block::{Block, BlockRepr},
+    bounds::WorldBounds,
+    coord::{BlockCoord, ChunkId, GlobalCoord, CHUNK_CUBE},
+    sky::{TimeOfDay, Weather},
+    stats::WorldStats,
+};
+
+use super::ProtocolError;
+
+/// Binary messages writer (little endian)
+#[derive(Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, value: f32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn f64(&mut self, value: f64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn vec2(&mut self, value: Vec2) {
+        self.f32(value.x);
+        self.f32(value.y);
+    }
+
+    pub fn vec3(&mut self, value: Vec3) {
+        self.f32(value.x);
+        self.f32(value.y);
+        self.f32(value.z);
+    }
+
+    pub fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value);
+    }
+
+    pub fn str(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    pub fn block(&mut self, value: Block) {
+        self.u8(value.id());
+    }
+
+    pub fn chunk_id(&mut self, value: ChunkId) {
+        self.i64(value.x);
+        self.i64(value.y);
+        self.i64(value.z);
+    }
+
+    pub fn global_coord(&mut self, value: GlobalCoord) {
+        self.i64(value.x);
+        self.i64(value.y);
+        self.i64(value.z);
+    }
+
+    /// Block coordinate as a flattened chunk index
+    pub fn block_coord(&mut self, value: BlockCoord) {
+        self.u16(value.flatten() as u16);
+    }
+
+    pub fn world_bounds(&mut self, value: WorldBounds) {
+        self.i64(value.min_y);
+        self.i64(value.max_y);
+        self.bool(value.border.is_some());
+        self.i64(value.border.unwrap_or_default());
+    }
+
+    pub fn time_of_day(&mut self, value: TimeOfDay) {
+        self.f32(value.hours());
+    }
+
+    pub fn weather(&mut self, value: Weather) {
+        self.u8(value.id());
+    }
+
+    pub fn world_stats(&mut self, value: WorldStats) {
+        self.u64(value.blocks_placed);
+        self.u64(value.blocks_broken);
+        self.f64(value.distance);
+        self.u64(value.playtime.as_millis() as u64);
+    }
+}
+
+/// Binary messages reader (little endian)
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    /// Number of bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ProtocolError> {
+        let mut out = [0; N];
+        out.copy_from_slice(self.slice(N)?);
+        Ok(out)
+    }
+
+    fn slice(&mut self, len: usize) -> Result<&'a [u8], ProtocolError> {
+        if self.remaining() < len {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+
+        let slice = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, ProtocolError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, ProtocolError> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, ProtocolError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, ProtocolError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, ProtocolError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub fn i64(&mut self) -> Result<i64, ProtocolError> {
+        Ok(i64::from_le_bytes(self.take()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, ProtocolError> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, ProtocolError> {
+        Ok(f64::from_le_bytes(self.take()?))
+    }
+
+    pub fn vec2(&mut self) -> Result<Vec2, ProtocolError> {
+        Ok(Vec2::new(self.f32()?, self.f32()?))
+    }
+
+    pub fn vec3(&mut self) -> Result<Vec3, ProtocolError> {
+        Ok(Vec3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], ProtocolError> {
+        let len = self.u32()? as usize;
+        self.slice(len)
+    }
+
+    pub fn str(&mut self) -> Result<String, ProtocolError> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| ProtocolError::InvalidString)
+    }
+
+    pub fn block(&mut self) -> Result<Block, ProtocolError> {
+        let id = self.u8()?;
+
+        if id > Block::MAX {
+            return Err(ProtocolError::InvalidBlock(id as BlockRepr));
+        }
+
+        Ok(Block::from(id))
+    }
+
+    pub fn chunk_id(&mut self) -> Result<ChunkId, ProtocolError> {
+        Ok(ChunkId::new(self.i64()?, self.i64()?, self.i64()?))
+    }
+
+    pub fn global_coord(&mut self) -> Result<GlobalCoord, ProtocolError> {
+        Ok(GlobalCoord::new(self.i64()?, self.i64()?, self.i64()?))
+    }
+
+    pub fn block_coord(&mut self) -> Result<BlockCoord, ProtocolError> {
+        let idx = self.u16()?;
+
+        if idx as usize >= CHUNK_CUBE {
+            return Err(ProtocolError::InvalidBlockCoord(idx));
+        }
+
+        Ok(BlockCoord::from(idx as usize))
+    }
+
+    pub fn world_bounds(&mut self) -> Result<WorldBounds, ProtocolError> {
+        let min_y = self.i64()?;
+        let max_y = self.i64()?;
+        let has_border = self.bool()?;
+        let border = self.i64()?;
+
+        Ok(WorldBounds {
+            min_y,
+            max_y,
+            border: has_border.then_some(border),
+        })
+    }
+
+    pub fn time_of_day(&mut self) -> Result<TimeOfDay, ProtocolError> {
+        Ok(TimeOfDay::new(self.f32()?))
+    }
+
+    pub fn weather(&mut self) -> Result<Weather, ProtocolError> {
+        let id = self.u8()?;
+        Weather::from_id(id).ok_or(ProtocolError::InvalidWeather(id))
+    }
+
+    pub fn world_stats(&mut self) -> Result<WorldStats, ProtocolError> {
+        Ok(WorldStats {
+            blocks_placed: self.u64()?,
+            blocks_broken: self.u64()?,
+            distance: self.f64()?,
+            playtime: Duration::from_millis(self.u64()?),
+        })
+    }
+}
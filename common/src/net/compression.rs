@@ -0,0 +1,244 @@
+use super::ProtocolError;
+
+/// Frame payload compression method.
+///
+/// Discriminants are wire ids sent in every frame header and in the handshake. Id 2 is reserved
+/// for Zstd, so adding it later doesn't change ids of existing methods
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+#[repr(u8)]
+pub enum Compression {
+    #[default]
+    None = 0,
+    /// LZ4 block format. Frames carry their own length, so the LZ4 frame format isn't used
+    Lz4 = 1,
+}
+
+impl Compression {
+    /// Methods supported by this build, most preferred first
+    pub const SUPPORTED: &'static [Compression] = &[Compression::Lz4];
+
+    /// Payloads smaller than this are sent as is
+    pub const MIN_SIZE: usize = 128;
+
+    /// Shortest match of LZ4
+    const MIN_MATCH: usize = 4;
+    /// Matches can't start in the last 12 bytes of the input
+    const MATCH_LIMIT: usize = 12;
+    /// Matches can't cover the last 5 bytes of the input
+    const LAST_LITERALS: usize = 5;
+    const MAX_OFFSET: usize = u16::MAX as usize;
+    const HASH_BITS: u32 = 12;
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::None),
+            1 => Some(Self::Lz4),
+            _ => None,
+        }
+    }
+
+    /// Pick the most preferred method supported by both sides
+    pub fn negotiate(offered: &[Compression]) -> Self {
+        Self::SUPPORTED
+            .iter()
+            .find(|method| offered.contains(method))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::None => data.to_vec(),
+            Self::Lz4 => Self::lz4_compress(data),
+        }
+    }
+
+    /// Decompress data. Output larger than `max_size` is treated as malformed
+    pub fn decompress(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>, ProtocolError> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Lz4 => Self::lz4_decompress(data, max_size),
+        }
+    }
+
+    /// Greedy compression with a single hash table of recent 4 byte sequences
+    fn lz4_compress(data: &[u8]) -> Vec<u8> {
+        let read = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        let hash = |seq: u32| (seq.wrapping_mul(2654435761) >> (32 - Self::HASH_BITS)) as usize;
+
+        let mut out = Vec::with_capacity(data.len() / 2);
+        // Positions are shifted by one, so 0 is an empty slot
+        let mut table = vec![0usize; 1 << Self::HASH_BITS];
+        let mut anchor = 0;
+        let mut i = 0;
+
+        while i + Self::MATCH_LIMIT <= data.len() {
+            let seq = read(i);
+            let slot = &mut table[hash(seq)];
+            let candidate = slot.checked_sub(1);
+            *slot = i + 1;
+
+            match candidate.filter(|&c| i - c <= Self::MAX_OFFSET && read(c) == seq) {
+                Some(start) => {
+                    let max_len = data.len() - Self::LAST_LITERALS - i;
+                    let len = Self::MIN_MATCH
+                        + (Self::MIN_MATCH..max_len)
+                            .take_while(|&k| data[start + k] == data[i + k])
+                            .count();
+
+                    Self::lz4_sequence(&mut out, &data[anchor..i], Some((i - start, len)));
+                    i += len;
+                    anchor = i;
+                }
+                None => i += 1,
+            }
+        }
+
+        Self::lz4_sequence(&mut out, &data[anchor..], None);
+        out
+    }
+
+    /// Write literals followed by a match (offset, length). The last sequence has no match
+    fn lz4_sequence(out: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+        fn extra_len(out: &mut Vec<u8>, mut len: usize) {
+            while len >= 255 {
+                out.push(255);
+                len -= 255;
+            }
+            out.push(len as u8);
+        }
+
+        let match_len = matched.map_or(0, |(_, len)| len - Self::MIN_MATCH);
+        out.push(((literals.len().min(15) as u8) << 4) | match_len.min(15) as u8);
+        if literals.len() >= 15 {
+            extra_len(out, literals.len() - 15);
+        }
+        out.extend_from_slice(literals);
+
+        if let Some((offset, _)) = matched {
+            out.extend_from_slice(&(offset as u16).to_le_bytes());
+            if match_len >= 15 {
+                extra_len(out, match_len - 15);
+            }
+        }
+    }
+
+    fn lz4_decompress(data: &[u8], max_size: usize) -> Result<Vec<u8>, ProtocolError> {
+        let mut out = Vec::with_capacity((data.len() * 4).min(max_size));
+        let mut i = 0;
+
+        let byte = |i: &mut usize| {
+            let byte = *data.get(*i).ok_or(ProtocolError::InvalidCompressedData)?;
+            *i += 1;
+            Ok(byte)
+        };
+        // Lengths of 15 continue in the following bytes
+        let length = |i: &mut usize, nibble: u8| {
+            let mut len = nibble as usize;
+            if nibble == 15 {
+                loop {
+                    let extra = byte(i)?;
+                    len += extra as usize;
+                    if extra != 255 {
+                        break;
+                    }
+                }
+            }
+            Ok::<_, ProtocolError>(len)
+        };
+
+        loop {
+            let token = *data.get(i).ok_or(ProtocolError::InvalidCompressedData)?;
+            i += 1;
+
+            let literals = length(&mut i, token >> 4)?;
+            let literals = i
+                .checked_add(literals)
+                .and_then(|end| data.get(i..end))
+                .filter(|literals| out.len() + literals.len() <= max_size)
+                .ok_or(ProtocolError::InvalidCompressedData)?;
+            out.extend_from_slice(literals);
+            i += literals.len();
+
+            // The last sequence has literals only
+            if i == data.len() {
+                return Ok(out);
+            }
+
+            let offset = data
+                .get(i..i + 2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+                .filter(|&offset| offset != 0 && offset <= out.len())
+                .ok_or(ProtocolError::InvalidCompressedData)?;
+            i += 2;
+            let len = length(&mut i, token & 0xF)? + Self::MIN_MATCH;
+            if out.len() + len > max_size {
+                return Err(ProtocolError::InvalidCompressedData);
+            }
+
+            // Match may overlap the bytes it produces
+            let start = out.len() - offset;
+            for k in start..start + len {
+                out.push(out[k]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::SeededRng;
+
+    use super::Compression;
+
+    #[test]
+    fn lz4_roundtrip() {
+        let mut rng = SeededRng::new(7);
+        let mut data = vec![0; 1000];
+        data.extend(0..=255);
+        data.extend([7, 7, 1, 2, 2, 3]);
+        data.extend((0..300).map(|_| (rng.next_f32() * 4.0) as u8));
+        data.extend(b"stone stone stone dirt stone");
+
+        for data in [&data[..], &[], &[1, 2, 3], &[0; 20], &data[..40]] {
+            let compressed = Compression::Lz4.compress(data);
+            assert_eq!(
+                Compression::Lz4
+                    .decompress(&compressed, data.len())
+                    .unwrap(),
+                data
+            );
+        }
+        assert!(Compression::Lz4.compress(&data).len() < data.len() / 2);
+
+        // Output limit
+        let compressed = Compression::Lz4.compress(&data);
+        assert!(Compression::Lz4
+            .decompress(&compressed, data.len() - 1)
+            .is_err());
+    }
+
+    #[test]
+    fn lz4_block_format() {
+        // Literal "a", match of 5 at offset 1, last literals "bcdef"
+        let block = [0x15, b'a', 1, 0, 0x50, b'b', b'c', b'd', b'e', b'f'];
+        assert_eq!(
+            Compression::Lz4.decompress(&block, 64).unwrap(),
+            b"aaaaaaaaaabcdef"
+        );
+
+        // Offset before the start of the output, zero offset, truncated literals
+        for block in [
+            &[0x10, b'a', 2, 0, 0x00][..],
+            &[0x10, b'a', 0, 0, 0x00],
+            &[0x50, b'a'],
+            &[],
+        ] {
+            assert!(Compression::Lz4.decompress(block, 64).is_err());
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{SocketAddr, TcpStream},
+};
+
+use super::{
+    codec::{Reader, Writer},
+    compression::Compression,
+    protocol::Message,
+    NetError, ProtocolError,
+};
+
+/// Traffic counters of a connection
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NetStats {
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    /// Bytes written to the socket
+    pub bytes_sent: u64,
+    /// Bytes read from the socket
+    pub bytes_received: u64,
+    /// Sent bytes before compression
+    pub raw_bytes_sent: u64,
+    /// Received bytes after decompression
+    pub raw_bytes_received: u64,
+}
+
+impl NetStats {
+    /// Compressed to raw size ratio of the sent data
+    pub fn sent_ratio(&self) -> f32 {
+        Self::ratio(self.bytes_sent, self.raw_bytes_sent)
+    }
+
+    /// Compressed to raw size ratio of the received data
+    pub fn received_ratio(&self) -> f32 {
+        Self::ratio(self.bytes_received, self.raw_bytes_received)
+    }
+
+    fn ratio(bytes: u64, raw: u64) -> f32 {
+        if raw == 0 {
+            1.0
+        } else {
+            bytes as f32 / raw as f32
+        }
+    }
+}
+
+/// Non-blocking framed TCP connection.
+///
+/// Every frame is prefixed with its length (`u32`, little endian) followed by compression method
+/// (`u8`) of the payload
+pub struct Connection {
+    stream: TcpStream,
+    addr: SocketAddr,
+    read_buf: Vec<u8>,
+    write_buf: Vec<u8>,
+    compression: Compression,
+    stats: NetStats,
+    /// Peer has closed the stream. Frames received before are still returned
+    closed: bool,
+}
+
+impl Connection {
+    /// Max size of a single frame
+    pub const MAX_FRAME_SIZE: usize = 1024 * 1024;
+    const READ_CHUNK: usize = 16 * 1024;
+
+    pub fn new(stream: TcpStream) -> Result<Self, NetError> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            addr: stream.peer_addr()?,
+            stream,
+            read_buf: Vec::new(),
+            write_buf: Vec::new(),
+            compression: Compression::None,
+            stats: NetStats::default(),
+            closed: false,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Set compression used for outgoing frames (negotiated during handshake).
+    /// Incoming frames are decompressed with whatever method they are marked with
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    pub fn stats(&self) -> NetStats {
+        self.stats
+    }
+
+    /// Queue message to be sent on the next `flush`
+    pub fn send<M: Message>(&mut self, msg: &M) {
+        let mut w = Writer::new();
+        msg.encode(&mut w);
+        let payload = w.into_inner();
+
+        self.stats.messages_sent += 1;
+        self.stats.raw_bytes_sent += payload.len() as u64 + 5;
+
+        // Small payloads are not worth compressing
+        let compressed = match self.compression {
+            Compression::None => None,
+            _ if payload.len() < Compression::MIN_SIZE => None,
+            method => Some((method, method.compress(&payload))),
+        };
+
+        match compressed {
+            // Keep raw payload if compression doesn't help
+            Some((method, data)) if data.len() < payload.len() => self.queue_frame(method, &data),
+            _ => self.queue_frame(Compression::None, &payload),
+        }
+    }
+
+    fn queue_frame(&mut self, method: Compression, payload: &[u8]) {
+        self.write_buf
+            .extend_from_slice(&(payload.len() as u32 + 1).to_le_bytes());
+        self.write_buf.push(method.id());
+        self.write_buf.extend_from_slice(payload);
+        self.stats.bytes_sent += payload.len() as u64 + 5;
+    }
+
+    /// Write as much of queued data as socket accepts
+    pub fn flush(&mut self) -> Result<(), NetError> {
+        while !self.write_buf.is_empty() {
+            match self.stream.write(&self.write_buf) {
+                Ok(0) => return Err(NetError::Closed),
+                Ok(n) => {
+                    self.write_buf.drain(..n);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number of bytes waiting to be sent
+    pub fn pending(&self) -> usize {
+        self.write_buf.len()
+    }
+
+    /// Receive all complete messages available at the moment. Once the peer closes the stream,
+    /// messages sent before closing are returned first (e.g. a disconnect reason), then
+    /// [`NetError::Closed`]
+    pub fn recv<M: Message>(&mut self) -> Result<Vec<M>, NetError> {
+        let mut chunk = [0; Self::READ_CHUNK];
+
+        while !self.closed {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => self.closed = true,
+                Ok(n) => self.read_buf.extend_from_slice(&chunk[..n]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut messages = Vec::new();
+        let mut offset = 0;
+
+        while self.read_buf.len() - offset >= 4 {
+            let len = u32::from_le_bytes(
+                self.read_buf[offset..offset + 4]
+                    .try_into()
+                    .expect("Slice has exactly 4 bytes"),
+            ) as usize;
+
+            if len > Self::MAX_FRAME_SIZE {
+                return Err(ProtocolError::FrameTooLarge(len).into());
+            }
+
+            if self.read_buf.len() - offset - 4 < len {
+                break;
+            }
+
+            let frame = &self.read_buf[offset + 4..offset + 4 + len];
+            let (&method, payload) = frame.split_first().ok_or(ProtocolError::UnexpectedEof)?;
+            let payload = match Compression::from_id(method) {
+                Some(Compression::None) => payload.to_vec(),
+                Some(method) => method.decompress(payload, Self::MAX_FRAME_SIZE)?,
+                None => return Err(ProtocolError::UnknownCompression(method).into()),
+            };
+
+            messages.push(M::decode(&mut Reader::new(&payload))?);
+            offset += 4 + len;
+
+            self.stats.messages_received += 1;
+            self.stats.bytes_received += 4 + len as u64;
+            self.stats.raw_bytes_received += 5 + payload.len() as u64;
+        }
+
+        self.read_buf.drain(..offset);
+
+        if self.closed && messages.is_empty() {
+            return Err(NetError::Closed);
+        }
+
+        Ok(messages)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{TcpListener, TcpStream},
+        thread,
+        time::Duration,
+    };
+
+    use crate::net::{protocol::ServerMsg, NetError};
+
+    use super::Connection;
+
+    #[test]
+    fn frames_before_close_are_received() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client =
+            Connection::new(TcpStream::connect(listener.local_addr().unwrap()).unwrap()).unwrap();
+        let mut server = Connection::new(listener.accept().unwrap().0).unwrap();
+
+        server.send(&ServerMsg::Disconnect {
+            reason: "Incompatible version".to_string(),
+        });
+        server.flush().unwrap();
+        drop(server);
+
+        let mut received = Vec::new();
+        let err = loop {
+            match client.recv::<ServerMsg>() {
+                Ok(messages) => received.extend(messages),
+                Err(err) => break err,
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+
+        assert!(matches!(err, NetError::Closed));
+        assert!(matches!(
+            received.as_slice(),
+            [ServerMsg::Disconnect { reason }] if reason == "Incompatible version"
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::block::BlockRepr;
+
+pub mod codec;
+pub mod compression;
+pub mod connection;
+pub mod protocol;
+
+/// Default server port
+pub const DEFAULT_PORT: u16 = 25300;
+/// Version of the network protocol. Must be bumped on every message format change
+pub const PROTOCOL_VERSION: u16 = 9;
+
+/// Represents malformed data errors
+#[derive(Error, Debug)]
+pub enum ProtocolError {
+    #[error("Unexpected end of message")]
+    UnexpectedEof,
+    #[error("Unknown message (tag: {0})")]
+    UnknownMessage(u8),
+    #[error("Invalid block id: {0}")]
+    InvalidBlock(BlockRepr),
+    #[error("Invalid block coordinate (index: {0})")]
+    InvalidBlockCoord(u16),
+    #[error("Invalid entity kind: {0}")]
+    InvalidEntityKind(u8),
+    #[error("Invalid weather: {0}")]
+    InvalidWeather(u8),
+    #[error("Invalid UTF-8 string")]
+    InvalidString,
+    #[error("Unknown compression method: {0}")]
+    UnknownCompression(u8),
+    #[error("Invalid compressed data")]
+    InvalidCompressedData,
+    #[error("Frame is too large ({0} bytes)")]
+    FrameTooLarge(usize),
+}
+
+/// Represents one of network errors
+#[derive(Error, Debug)]
+pub enum NetError {
+    #[error("IO error: {0}")]
+    Io(io::Error),
+    #[error("Protocol error: {0}")]
+    Protocol(ProtocolError),
+    #[error("Connection closed")]
+    Closed,
+}
+
+impl From<io::Error> for NetError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ProtocolError> for NetError {
+    fn from(err: ProtocolError) -> Self {
+        Self::Protocol(err)
+    }
+}
@@ -0,0 +1,574 @@
+use glam::{Vec2, Vec3};
+
+use crate::{
+    block::Block,
+    bounds::WorldBounds,
+    chunk::Chunk,
+    coord::{BlockCoord, ChunkId, GlobalCoord},
+    entity::{EntityId, EntityKind},
+    movement::{InputSeq, PlayerInput},
+    sky::{TimeOfDay, Weather},
+    stats::WorldStats,
+};
+
+use super::{
+    codec::{Reader, Writer},
+    compression::Compression,
+    ProtocolError, PROTOCOL_VERSION,
+};
+
+/// Block edit identifier assigned by the client
+pub type EditId = u32;
+
+/// Message that can be sent over the network
+pub trait Message: Sized {
+    fn encode(&self, w: &mut Writer);
+
+    fn decode(r: &mut Reader) -> Result<Self, ProtocolError>;
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Client -> Server
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Messages sent by the client
+#[derive(Clone, Debug)]
+pub enum ClientMsg {
+    /// First message after connection.
+    ///
+    /// Fields after `registry` are decoded only if `version` matches `PROTOCOL_VERSION`
+    Hello {
+        version: u16,
+        /// Block registry hash
+        registry: u64,
+        name: String,
+        /// Supported compression methods
+        compression: Vec<Compression>,
+    },
+    /// Player movement input
+    PlayerInput(PlayerInput),
+    /// Ask server to send the chunk
+    RequestChunk(ChunkId),
+    /// Place (or break with `Block::Air`) a block
+    BlockEdit {
+        id: EditId,
+        pos: GlobalCoord,
+        block: Block,
+    },
+    /// Client is leaving
+    Disconnect,
+    /// Latency probe. Server replies with `ServerMsg::Pong`
+    Ping(u32),
+    /// Ask server to send `ServerMsg::WorldStats` and `ServerMsg::Backups`
+    RequestStats,
+}
+
+impl Message for ClientMsg {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Self::Hello {
+                version,
+                registry,
+                name,
+                compression,
+            } => {
+                w.u8(0);
+                w.u16(*version);
+                w.u64(*registry);
+                w.str(name);
+                w.u8(compression.len() as u8);
+                compression.iter().for_each(|method| w.u8(method.id()));
+            }
+            Self::PlayerInput(input) => {
+                w.u8(1);
+                w.u32(input.seq);
+                w.f32(input.forward);
+                w.f32(input.strafe);
+                w.f32(input.lift);
+                w.vec2(input.rot);
+                w.f32(input.dt);
+            }
+            Self::RequestChunk(id) => {
+                w.u8(2);
+                w.chunk_id(*id);
+            }
+            Self::Disconnect => w.u8(3),
+            Self::BlockEdit { id, pos, block } => {
+                w.u8(4);
+                w.u32(*id);
+                w.global_coord(*pos);
+                w.block(*block);
+            }
+            Self::Ping(seq) => {
+                w.u8(5);
+                w.u32(*seq);
+            }
+            Self::RequestStats => w.u8(6),
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, ProtocolError> {
+        Ok(match r.u8()? {
+            0 => {
+                let version = r.u16()?;
+                let registry = r.u64()?;
+
+                // Message format may differ in other versions
+                if version != PROTOCOL_VERSION {
+                    return Ok(Self::Hello {
+                        version,
+                        registry,
+                        name: String::new(),
+                        compression: Vec::new(),
+                    });
+                }
+
+                let name = r.str()?;
+                let len = r.u8()?;
+                // Methods unknown to this build are skipped
+                let compression = (0..len)
+                    .map(|_| r.u8())
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter()
+                    .filter_map(Compression::from_id)
+                    .collect();
+                Self::Hello {
+                    version,
+                    registry,
+                    name,
+                    compression,
+                }
+            }
+            1 => Self::PlayerInput(PlayerInput {
+                seq: r.u32()?,
+                forward: r.f32()?,
+                strafe: r.f32()?,
+                lift: r.f32()?,
+                rot: r.vec2()?,
+                dt: r.f32()?,
+            }),
+            2 => Self::RequestChunk(r.chunk_id()?),
+            3 => Self::Disconnect,
+            4 => Self::BlockEdit {
+                id: r.u32()?,
+                pos: r.global_coord()?,
+                block: r.block()?,
+            },
+            5 => Self::Ping(r.u32()?),
+            6 => Self::RequestStats,
+            tag => return Err(ProtocolError::UnknownMessage(tag)),
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Server -> Client
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Messages sent by the server
+#[derive(Clone)]
+pub enum ServerMsg {
+    /// Response to `ClientMsg::Hello`
+    Welcome {
+        entity_id: EntityId,
+        tps: u32,
+        spawn: Vec3,
+        /// Compression chosen by the server
+        compression: Compression,
+        bounds: WorldBounds,
+    },
+    /// Full chunk payload
+    ChunkData { id: ChunkId, chunk: Chunk },
+    /// Blocks changed in the chunk since the last update
+    ChunkDelta {
+        id: ChunkId,
+        changes: Vec<(BlockCoord, Block)>,
+    },
+    /// New entity appeared
+    EntitySpawn {
+        id: EntityId,
+        kind: EntityKind,
+        /// Display name (empty for unnamed entities)
+        name: String,
+        pos: Vec3,
+        rot: Vec2,
+    },
+    /// Entity state snapshot
+    EntityState { id: EntityId, pos: Vec3, rot: Vec2 },
+    /// Entity has been removed
+    EntityDespawn(EntityId),
+    /// Server closes the connection
+    Disconnect { reason: String },
+    /// Authoritative player position after processing inputs up to `seq`
+    PlayerAck { seq: InputSeq, pos: Vec3 },
+    /// Result of `ClientMsg::BlockEdit`. `block` is the actual block at `pos`
+    BlockEditAck {
+        id: EditId,
+        accepted: bool,
+        pos: GlobalCoord,
+        block: Block,
+    },
+    /// Reply to `ClientMsg::Ping`
+    Pong(u32),
+    /// Player has been moved by the server. Pending inputs are dropped
+    Teleport { pos: Vec3 },
+    /// Current time of day and weather. Sent on join and whenever they are changed
+    WorldTime { time: TimeOfDay, weather: Weather },
+    /// Reply to `ClientMsg::RequestStats`
+    WorldStats(WorldStats),
+    /// Reply to `ClientMsg::RequestStats`. Names of world backups from the oldest to the newest
+    Backups(Vec<String>),
+}
+
+impl Message for ServerMsg {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Self::Welcome {
+                entity_id,
+                tps,
+                spawn,
+                compression,
+                bounds,
+            } => {
+                w.u8(0);
+                w.u64(*entity_id);
+                w.u32(*tps);
+                w.vec3(*spawn);
+                w.u8(compression.id());
+                w.world_bounds(*bounds);
+            }
+            Self::ChunkData { id, chunk } => {
+                w.u8(1);
+                w.chunk_id(*id);
+                chunk.blocks().iter().for_each(|block| w.block(*block));
+            }
+            Self::ChunkDelta { id, changes } => {
+                w.u8(2);
+                w.chunk_id(*id);
+                w.u16(changes.len() as u16);
+                changes.iter().for_each(|&(pos, block)| {
+                    w.block_coord(pos);
+                    w.block(block);
+                });
+            }
+            Self::EntitySpawn {
+                id,
+                kind,
+                name,
+                pos,
+                rot,
+            } => {
+                w.u8(3);
+                w.u64(*id);
+                w.u8(kind.id());
+                w.str(name);
+                w.vec3(*pos);
+                w.vec2(*rot);
+            }
+            Self::EntityState { id, pos, rot } => {
+                w.u8(4);
+                w.u64(*id);
+                w.vec3(*pos);
+                w.vec2(*rot);
+            }
+            Self::EntityDespawn(id) => {
+                w.u8(5);
+                w.u64(*id);
+            }
+            Self::Disconnect { reason } => {
+                w.u8(6);
+                w.str(reason);
+            }
+            Self::PlayerAck { seq, pos } => {
+                w.u8(7);
+                w.u32(*seq);
+                w.vec3(*pos);
+            }
+            Self::BlockEditAck {
+                id,
+                accepted,
+                pos,
+                block,
+            } => {
+                w.u8(8);
+                w.u32(*id);
+                w.bool(*accepted);
+                w.global_coord(*pos);
+                w.block(*block);
+            }
+            Self::Pong(seq) => {
+                w.u8(9);
+                w.u32(*seq);
+            }
+            Self::Teleport { pos } => {
+                w.u8(10);
+                w.vec3(*pos);
+            }
+            Self::WorldTime { time, weather } => {
+                w.u8(11);
+                w.time_of_day(*time);
+                w.weather(*weather);
+            }
+            Self::WorldStats(stats) => {
+                w.u8(12);
+                w.world_stats(*stats);
+            }
+            Self::Backups(names) => {
+                w.u8(13);
+                w.u16(names.len() as u16);
+                names.iter().for_each(|name| w.str(name));
+            }
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Self, ProtocolError> {
+        Ok(match r.u8()? {
+            0 => Self::Welcome {
+                entity_id: r.u64()?,
+                tps: r.u32()?,
+                spawn: r.vec3()?,
+                compression: {
+                    let id = r.u8()?;
+                    Compression::from_id(id).ok_or(ProtocolError::UnknownCompression(id))?
+                },
+                bounds: r.world_bounds()?,
+            },
+            1 => {
+                let id = r.chunk_id()?;
+                let mut chunk = Chunk::new();
+                for block in chunk.blocks_mut().iter_mut() {
+                    *block = r.block()?;
+                }
+                Self::ChunkData { id, chunk }
+            }
+            2 => {
+                let id = r.chunk_id()?;
+                let len = r.u16()? as usize;
+                let changes = (0..len)
+                    .map(|_| Ok((r.block_coord()?, r.block()?)))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+                Self::ChunkDelta { id, changes }
+            }
+            3 => Self::EntitySpawn {
+                id: r.u64()?,
+                kind: {
+                    let kind = r.u8()?;
+                    EntityKind::from_id(kind).ok_or(ProtocolError::InvalidEntityKind(kind))?
+                },
+                name: r.str()?,
+                pos: r.vec3()?,
+                rot: r.vec2()?,
+            },
+            4 => Self::EntityState {
+                id: r.u64()?,
+                pos: r.vec3()?,
+                rot: r.vec2()?,
+            },
+            5 => Self::EntityDespawn(r.u64()?),
+            6 => Self::Disconnect { reason: r.str()? },
+            7 => Self::PlayerAck {
+                seq: r.u32()?,
+                pos: r.vec3()?,
+            },
+            8 => Self::BlockEditAck {
+                id: r.u32()?,
+                accepted: r.bool()?,
+                pos: r.global_coord()?,
+                block: r.block()?,
+            },
+            9 => Self::Pong(r.u32()?),
+            10 => Self::Teleport { pos: r.vec3()? },
+            11 => Self::WorldTime {
+                time: r.time_of_day()?,
+                weather: r.weather()?,
+            },
+            12 => Self::WorldStats(r.world_stats()?),
+            13 => {
+                let len = r.u16()? as usize;
+                Self::Backups((0..len).map(|_| r.str()).collect::<Result<_, _>>()?)
+            }
+            tag => return Err(ProtocolError::UnknownMessage(tag)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use glam::{Vec2, Vec3};
+
+    use crate::{
+        block::Block,
+        bounds::WorldBounds,
+        chunk::Chunk,
+        coord::{BlockCoord, ChunkId},
+        movement::PlayerInput,
+        net::{
+            codec::{Reader, Writer},
+            compression::Compression,
+            PROTOCOL_VERSION,
+        },
+        sky::{TimeOfDay, Weather},
+        stats::WorldStats,
+    };
+
+    use super::{ClientMsg, Message, ServerMsg};
+
+    fn roundtrip<M: Message>(msg: &M) -> M {
+        let mut w = Writer::new();
+        msg.encode(&mut w);
+        let buf = w.into_inner();
+        let mut r = Reader::new(&buf);
+        let msg = M::decode(&mut r).unwrap();
+        assert_eq!(r.remaining(), 0);
+        msg
+    }
+
+    #[test]
+    fn client_msg_roundtrip() {
+        let input = PlayerInput {
+            seq: 42,
+            forward: 1.0,
+            strafe: -1.0,
+            lift: 0.0,
+            rot: Vec2::new(0.5, -0.5),
+            dt: 0.016,
+        };
+
+        match roundtrip(&ClientMsg::PlayerInput(input)) {
+            ClientMsg::PlayerInput(decoded) => assert_eq!(decoded, input),
+            msg => panic!("Unexpected message: {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn hello_other_version() {
+        let mut w = Writer::new();
+        w.u8(0);
+        w.u16(PROTOCOL_VERSION + 1);
+        w.u64(42);
+        // Unknown format of the rest
+        w.u32(u32::MAX);
+        let buf = w.into_inner();
+
+        match ClientMsg::decode(&mut Reader::new(&buf)).unwrap() {
+            ClientMsg::Hello {
+                version, registry, ..
+            } => {
+                assert_eq!(version, PROTOCOL_VERSION + 1);
+                assert_eq!(registry, 42);
+            }
+            msg => panic!("Unexpected message: {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn welcome_roundtrip() {
+        let bounds = WorldBounds {
+            min_y: -16,
+            max_y: 64,
+            border: Some(1024),
+        };
+
+        match roundtrip(&ServerMsg::Welcome {
+            entity_id: 7,
+            tps: 20,
+            spawn: Vec3::new(1.0, 2.0, 3.0),
+            compression: Compression::None,
+            bounds,
+        }) {
+            ServerMsg::Welcome {
+                entity_id,
+                bounds: decoded,
+                ..
+            } => {
+                assert_eq!(entity_id, 7);
+                assert_eq!(decoded, bounds);
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn world_time_roundtrip() {
+        match roundtrip(&ServerMsg::WorldTime {
+            time: TimeOfDay::new(13.25),
+            weather: Weather::Fog,
+        }) {
+            ServerMsg::WorldTime { time, weather } => {
+                assert_eq!(time, TimeOfDay::new(13.25));
+                assert_eq!(weather, Weather::Fog);
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn world_stats_roundtrip() {
+        let stats = WorldStats {
+            blocks_placed: 3,
+            blocks_broken: 2,
+            distance: 1234.5,
+            playtime: Duration::from_millis(90_061),
+        };
+
+        match roundtrip(&ServerMsg::WorldStats(stats)) {
+            ServerMsg::WorldStats(decoded) => assert_eq!(decoded, stats),
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn backups_roundtrip() {
+        let names = vec![
+            "1700000000-manual".to_string(),
+            "1700001800-scheduled".to_string(),
+        ];
+
+        match roundtrip(&ServerMsg::Backups(names.clone())) {
+            ServerMsg::Backups(decoded) => assert_eq!(decoded, names),
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn chunk_data_roundtrip() {
+        let mut chunk = Chunk::new();
+        chunk.set(BlockCoord::new(3, 2, 1), Block::Stone);
+
+        match roundtrip(&ServerMsg::ChunkData {
+            id: ChunkId::new(-1, 2, 3),
+            chunk,
+        }) {
+            ServerMsg::ChunkData { id, chunk } => {
+                assert_eq!(id, ChunkId::new(-1, 2, 3));
+                assert_eq!(chunk.get(BlockCoord::new(3, 2, 1)), Block::Stone);
+                assert_eq!(chunk.get(BlockCoord::ZERO), Block::Air);
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn chunk_delta_roundtrip() {
+        let changes = vec![
+            (BlockCoord::ZERO, Block::Stone),
+            (BlockCoord::new(15, 15, 15), Block::Air),
+        ];
+
+        match roundtrip(&ServerMsg::ChunkDelta {
+            id: ChunkId::new(0, -1, 0),
+            changes: changes.clone(),
+        }) {
+            ServerMsg::ChunkDelta {
+                id,
+                changes: decoded,
+            } => {
+                assert_eq!(id, ChunkId::new(0, -1, 0));
+                assert_eq!(decoded, changes);
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+}
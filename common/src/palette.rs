@@ -0,0 +1,170 @@
+use std::{fs, io, path::Path};
+
+use glam::Vec3;
+use thiserror::Error;
+
+use crate::block::Block;
+
+#[derive(Error, Debug)]
+pub enum PaletteError {
+    #[error("Failed to access palette file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Line {0}: unknown block `{1}`")]
+    UnknownBlock(usize, String),
+    #[error("Line {0}: invalid color `{1}`")]
+    InvalidColor(usize, String),
+}
+
+/// Colors of blocks. Client-side only, the world doesn't depend on it.
+///
+/// Palette files have a `block r g b` line (channels in `0..=1`) per recolored block, the other
+/// blocks keep their default colors. Lines starting with `#` are comments:
+///
+/// ```text
+/// # Autumn
+/// grass 0.72 0.5 0.16
+/// leaves 0.8 0.32 0.1
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub struct Palette {
+    name: String,
+    /// Indexed by the block id
+    colors: [Vec3; Block::ALL.len()],
+}
+
+impl Palette {
+    /// File extension of palettes
+    pub const EXTENSION: &'static str = "palette";
+    pub const DEFAULT_NAME: &'static str = "Default";
+
+    /// Default block colors
+    pub fn new() -> Self {
+        Self {
+            name: Self::DEFAULT_NAME.to_string(),
+            colors: Block::ALL.map(|block| block.color()),
+        }
+    }
+
+    pub fn parse(name: impl Into<String>, source: &str) -> Result<Self, PaletteError> {
+        let mut palette = Self {
+            name: name.into(),
+            ..Self::new()
+        };
+
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (block, color) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let block = Block::from_name(block)
+                .ok_or_else(|| PaletteError::UnknownBlock(i + 1, block.to_string()))?;
+            let channels = color
+                .split_whitespace()
+                .map(|channel| {
+                    channel
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|c| (0.0..=1.0).contains(c))
+                })
+                .collect::<Option<Vec<_>>>();
+            match channels.as_deref() {
+                Some(&[r, g, b]) => palette.set(block, Vec3::new(r, g, b)),
+                _ => return Err(PaletteError::InvalidColor(i + 1, color.trim().to_string())),
+            }
+        }
+
+        Ok(palette)
+    }
+
+    /// Load palette file. Named after the file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PaletteError> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+
+        Self::parse(name, &fs::read_to_string(path)?)
+    }
+
+    /// Load every palette file in the directory, sorted by name
+    pub fn load_dir(dir: impl AsRef<Path>) -> io::Result<Vec<Result<Self, PaletteError>>> {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == Self::EXTENSION));
+        paths.sort();
+
+        Ok(paths.into_iter().map(Self::load).collect())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn color(&self, block: Block) -> Vec3 {
+        self.colors[block.id() as usize]
+    }
+
+    pub fn set(&mut self, block: Block, color: Vec3) {
+        self.colors[block.id() as usize] = color;
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::block::Block;
+
+    use super::{Palette, PaletteError};
+
+    #[test]
+    fn parse_palette() {
+        let palette = Palette::parse(
+            "autumn",
+            "# Autumn\n\ngrass 0.72 0.5 0.16\n  TallGrass 1 0.5 0\n",
+        )
+        .unwrap();
+
+        assert_eq!(palette.name(), "autumn");
+        assert_eq!(palette.color(Block::Grass), Vec3::new(0.72, 0.5, 0.16));
+        assert_eq!(palette.color(Block::TallGrass), Vec3::new(1.0, 0.5, 0.0));
+        assert_eq!(palette.color(Block::Stone), Block::Stone.color());
+
+        assert!(matches!(
+            Palette::parse("", "gras 1 1 1"),
+            Err(PaletteError::UnknownBlock(1, block)) if block == "gras"
+        ));
+        assert!(matches!(
+            Palette::parse("", "# Colors\nsand 1 1\n"),
+            Err(PaletteError::InvalidColor(2, _))
+        ));
+        assert!(matches!(
+            Palette::parse("", "sand 1 2 1"),
+            Err(PaletteError::InvalidColor(1, _))
+        ));
+    }
+
+    #[test]
+    fn bundled_palettes_load() {
+        let palettes =
+            Palette::load_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/palettes")).unwrap();
+
+        assert!(!palettes.is_empty());
+        for palette in palettes {
+            assert_ne!(palette.unwrap(), Palette::new());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{coord::GlobalCoord, direction::Direction};
+
+/// Horizontal movement directions
+const HORIZONTAL: [Direction; 4] = [
+    Direction::Left,
+    Direction::Right,
+    Direction::Front,
+    Direction::Back,
+];
+
+/// Check if an entity (2 blocks tall) can stand at `pos`.
+///
+/// `solid` returns `None` for blocks in unloaded chunks, which are never walkable
+pub fn walkable(pos: GlobalCoord, solid: &impl Fn(GlobalCoord) -> Option<bool>) -> bool {
+    solid(pos) == Some(false)
+        && solid(pos.neighbor(Direction::Up)) == Some(false)
+        && solid(pos.neighbor(Direction::Down)) == Some(true)
+}
+
+fn distance(a: GlobalCoord, b: GlobalCoord) -> u64 {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y) + a.z.abs_diff(b.z)
+}
+
+/// Walkable positions reachable from `pos` in one step (climbing or dropping one block)
+fn neighbors(
+    pos: GlobalCoord,
+    solid: &impl Fn(GlobalCoord) -> Option<bool>,
+) -> impl Iterator<Item = GlobalCoord> + '_ {
+    let head_free = solid(pos.neighbor(Direction::Up).neighbor(Direction::Up)) == Some(false);
+
+    HORIZONTAL.into_iter().filter_map(move |dir| {
+        let next = pos.neighbor(dir);
+
+        if walkable(next, solid) {
+            Some(next)
+        } else if head_free && walkable(next.neighbor(Direction::Up), solid) {
+            Some(next.neighbor(Direction::Up))
+        } else if walkable(next.neighbor(Direction::Down), solid)
+            && solid(next.neighbor(Direction::Up)) == Some(false)
+        {
+            Some(next.neighbor(Direction::Down))
+        } else {
+            None
+        }
+    })
+}
+
+/// Find path between walkable positions using A* (Manhattan distance heuristic).
+///
+/// Search is limited to `max_nodes` visited positions. If the goal isn't reached, path to the
+/// explored position closest to the goal is returned (greedy fallback). Returned path excludes
+/// `start`, `None` means there is no way to get closer
+pub fn find_path(
+    start: GlobalCoord,
+    goal: GlobalCoord,
+    max_nodes: usize,
+    solid: impl Fn(GlobalCoord) -> Option<bool>,
+) -> Option<Vec<GlobalCoord>> {
+    // Node -> (parent, cost)
+    let mut visited = HashMap::from([(start, (start, 0))]);
+    let mut queue = BinaryHeap::from([Reverse((
+        distance(start, goal),
+        0,
+        start.x,
+        start.y,
+        start.z,
+    ))]);
+    let mut best = (distance(start, goal), start);
+
+    while let Some(Reverse((_, cost, x, y, z))) = queue.pop() {
+        let pos = GlobalCoord::new(x, y, z);
+
+        if pos == goal {
+            best = (0, pos);
+            break;
+        }
+        if visited.len() >= max_nodes {
+            break;
+        }
+        // Outdated queue entry
+        if visited.get(&pos).is_some_and(|&(_, known)| known < cost) {
+            continue;
+        }
+
+        for next in neighbors(pos, &solid) {
+            let next_cost = cost + 1 + pos.y.abs_diff(next.y);
+            if visited
+                .get(&next)
+                .is_some_and(|&(_, known)| known <= next_cost)
+            {
+                continue;
+            }
+
+            visited.insert(next, (pos, next_cost));
+
+            let h = distance(next, goal);
+            if h < best.0 {
+                best = (h, next);
+            }
+            queue.push(Reverse((next_cost + h, next_cost, next.x, next.y, next.z)));
+        }
+    }
+
+    let (_, mut pos) = best;
+    if pos == start {
+        return None;
+    }
+
+    let mut path = vec![pos];
+    while let Some(&(parent, _)) = visited.get(&pos).filter(|&&(parent, _)| parent != start) {
+        path.push(parent);
+        pos = parent;
+    }
+    path.reverse();
+
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{coord::GlobalCoord, testing::flat_world};
+
+    use super::find_path;
+
+    #[test]
+    fn straight_path() {
+        let path = find_path(
+            GlobalCoord::new(0, 1, 0),
+            GlobalCoord::new(3, 1, 0),
+            64,
+            flat_world(&[]),
+        )
+        .unwrap();
+
+        assert_eq!(path.len(), 3);
+        assert_eq!(path.last(), Some(&GlobalCoord::new(3, 1, 0)));
+    }
+
+    #[test]
+    fn around_wall_and_step() {
+        // Wall 2 blocks high at x = 1, z = -1..=1
+        let wall = (-1..=1)
+            .flat_map(|z| [GlobalCoord::new(1, 1, z), GlobalCoord::new(1, 2, z)])
+            .chain([GlobalCoord::new(3, 1, 0)])
+            .collect::<Vec<_>>();
+
+        let path = find_path(
+            GlobalCoord::new(0, 1, 0),
+            GlobalCoord::new(3, 2, 0),
+            256,
+            flat_world(&wall),
+        )
+        .unwrap();
+
+        assert_eq!(path.last(), Some(&GlobalCoord::new(3, 2, 0)));
+        assert!(path.iter().all(|pos| !wall.contains(pos)));
+        // Path goes around the wall (2 steps sideways and back)
+        assert_eq!(path.len(), 7);
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        // Start is walled in
+        let walls = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .flat_map(|(x, z)| [GlobalCoord::new(x, 1, z), GlobalCoord::new(x, 2, z)])
+            .collect::<Vec<_>>();
+
+        assert!(find_path(
+            GlobalCoord::new(0, 1, 0),
+            GlobalCoord::new(5, 1, 0),
+            64,
+            flat_world(&walls),
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,347 @@
+use std::{cell::Cell, ops::RangeInclusive};
+
+use glam::{BVec3, Vec3};
+
+use crate::{
+    coord::GlobalCoord,
+    direction::Direction,
+    geometry::{Aabb, Ray},
+};
+
+/// Tolerance used to avoid treating touching faces as overlapping
+const EPSILON: f32 = 1e-4;
+
+impl Aabb {
+    /// Blocks overlapped by the box (touching faces excluded)
+    pub fn blocks(&self) -> impl Iterator<Item = GlobalCoord> {
+        let (ys, zs) = (
+            layers(self.min.y, self.max.y),
+            layers(self.min.z, self.max.z),
+        );
+
+        layers(self.min.x, self.max.x).flat_map(move |x| {
+            let zs = zs.clone();
+            ys.clone()
+                .flat_map(move |y| zs.clone().map(move |z| GlobalCoord::new(x, y, z)))
+        })
+    }
+}
+
+/// Result of moving a box through the world
+#[derive(Clone, Copy, Debug)]
+pub struct Sweep {
+    /// Movement actually performed
+    pub delta: Vec3,
+    /// Axes on which movement has been blocked
+    pub collided: BVec3,
+}
+
+impl Sweep {
+    /// Box rests on a solid block
+    pub fn on_ground(&self, requested: Vec3) -> bool {
+        self.collided.y && requested.y < 0.0
+    }
+}
+
+/// Range of block layers covered by the box on the axis (touching faces excluded)
+fn layers(min: f32, max: f32) -> RangeInclusive<i64> {
+    (min + EPSILON).floor() as i64..=(max - EPSILON).ceil() as i64 - 1
+}
+
+/// Move `aabb` by `delta` resolving collisions with solid blocks.
+///
+/// Axes are resolved one by one (Y, X, Z), so the box slides along walls and floors.
+/// `solid` returns `None` for unloaded blocks, which are treated as solid.
+/// Blocks the box already overlaps with don't stop it, so stuck entities can get out
+pub fn sweep(aabb: Aabb, delta: Vec3, solid: impl Fn(GlobalCoord) -> Option<bool>) -> Sweep {
+    let solid = |pos| solid(pos).unwrap_or(true);
+
+    let mut aabb = aabb;
+    let mut moved = Vec3::ZERO;
+    let mut collided = [false; 3];
+
+    for axis in [1, 0, 2] {
+        let d = delta[axis];
+        if d == 0.0 {
+            continue;
+        }
+
+        let (a, b) = match axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+
+        // Block layers entered by the moving face (in movement order)
+        let (first, last, step) = if d > 0.0 {
+            (
+                (aabb.max[axis] - EPSILON).ceil() as i64,
+                (aabb.max[axis] + d - EPSILON).ceil() as i64 - 1,
+                1,
+            )
+        } else {
+            (
+                (aabb.min[axis] + EPSILON).floor() as i64 - 1,
+                (aabb.min[axis] + d + EPSILON).floor() as i64,
+                -1,
+            )
+        };
+        let count = (last - first) * step + 1;
+
+        let mut allowed = d;
+
+        'layers: for layer in (0..count).map(|i| first + i * step) {
+            for i in layers(aabb.min[a], aabb.max[a]) {
+                for j in layers(aabb.min[b], aabb.max[b]) {
+                    let mut pos = [0; 3];
+                    pos[axis] = layer;
+                    pos[a] = i;
+                    pos[b] = j;
+
+                    if solid(GlobalCoord::new(pos[0], pos[1], pos[2])) {
+                        allowed = if d > 0.0 {
+                            layer as f32 - aabb.max[axis]
+                        } else {
+                            (layer + 1) as f32 - aabb.min[axis]
+                        };
+                        collided[axis] = true;
+                        break 'layers;
+                    }
+                }
+            }
+        }
+
+        let mut offset = Vec3::ZERO;
+        offset[axis] = allowed;
+        aabb = aabb.translate(offset);
+        moved[axis] = allowed;
+    }
+
+    Sweep {
+        delta: moved,
+        collided: BVec3::new(collided[0], collided[1], collided[2]),
+    }
+}
+
+/// Block hit by a ray
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct RayHit {
+    pub pos: GlobalCoord,
+    /// Face the ray entered through (`pos.neighbor(face)` is the block in front of it).
+    /// `None` if the ray starts inside the block
+    pub face: Option<Direction>,
+    /// Distance from the ray origin
+    pub distance: f32,
+}
+
+/// Walk blocks along the ray until `hit` returns `true`
+pub fn raycast(ray: Ray, max_distance: f32, hit: impl Fn(GlobalCoord) -> bool) -> Option<RayHit> {
+    let Ray { origin, dir } = ray;
+    let mut pos = GlobalCoord::from_vec3(origin.floor());
+    let mut face = None;
+    let mut distance = 0.0;
+
+    let step = dir.signum();
+    // Distance along the ray between block borders on each axis
+    let delta = dir.abs().recip();
+    // Distance to the next block border on each axis
+    let mut next = Vec3::ZERO;
+    for axis in 0..3 {
+        let offset = origin[axis] - origin[axis].floor();
+        next[axis] = if dir[axis] == 0.0 {
+            f32::INFINITY
+        } else if dir[axis] > 0.0 {
+            (1.0 - offset) * delta[axis]
+        } else {
+            offset * delta[axis]
+        };
+    }
+
+    loop {
+        if hit(pos) {
+            return Some(RayHit {
+                pos,
+                face,
+                distance,
+            });
+        }
+
+        let axis = if next.x < next.y && next.x < next.z {
+            0
+        } else if next.y < next.z {
+            1
+        } else {
+            2
+        };
+
+        distance = next[axis];
+        if distance > max_distance {
+            return None;
+        }
+        next[axis] += delta[axis];
+
+        let forward = step[axis] > 0.0;
+        (pos, face) = match (axis, forward) {
+            (0, true) => (pos.neighbor(Direction::Right), Some(Direction::Left)),
+            (0, false) => (pos.neighbor(Direction::Left), Some(Direction::Right)),
+            (1, true) => (pos.neighbor(Direction::Up), Some(Direction::Down)),
+            (1, false) => (pos.neighbor(Direction::Down), Some(Direction::Up)),
+            (_, true) => (pos.neighbor(Direction::Back), Some(Direction::Front)),
+            (_, false) => (pos.neighbor(Direction::Front), Some(Direction::Back)),
+        };
+    }
+}
+
+/// Number of blocks between two points for which `opaque` returns `true`, up to `max`.
+///
+/// Blocks containing the points aren't counted, so e.g. a sound source placed into a wall isn't
+/// muffled by its own block
+pub fn occluders(from: Vec3, to: Vec3, max: u32, opaque: impl Fn(GlobalCoord) -> bool) -> u32 {
+    let distance = from.distance(to);
+    if distance < EPSILON || max == 0 {
+        return 0;
+    }
+
+    let ends = [from, to].map(|point| GlobalCoord::from_vec3(point.floor()));
+    let count = Cell::new(0);
+    raycast(Ray::new(from, to - from), distance, |pos| {
+        if !ends.contains(&pos) && opaque(pos) {
+            count.set(count.get() + 1);
+        }
+        count.get() >= max
+    });
+
+    count.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::{
+        coord::GlobalCoord,
+        direction::Direction,
+        geometry::{Aabb, Ray},
+        testing::flat_world,
+    };
+
+    use super::{occluders, raycast, sweep};
+
+    /// Unit sized box standing on the block column at (x, 1, z)
+    fn unit_box(x: f32, y: f32, z: f32) -> Aabb {
+        Aabb::new(Vec3::new(x, y, z), Vec3::new(x + 0.8, y + 1.8, z + 0.8))
+    }
+
+    #[test]
+    fn falls_onto_floor() {
+        let result = sweep(
+            unit_box(0.1, 3.0, 0.1),
+            Vec3::new(0.0, -5.0, 0.0),
+            flat_world(&[]),
+        );
+
+        assert!((result.delta.y + 2.0).abs() < 1e-4);
+        assert!(result.on_ground(Vec3::new(0.0, -5.0, 0.0)));
+    }
+
+    #[test]
+    fn slides_along_chunk_seam() {
+        // Walk along the floor across chunk border (x = 16) and a negative one (x = 0)
+        let result = sweep(
+            unit_box(14.1, 1.0, 0.1),
+            Vec3::new(4.0, 0.0, 0.0),
+            flat_world(&[]),
+        );
+        assert_eq!(result.delta, Vec3::new(4.0, 0.0, 0.0));
+        assert!(!result.collided.any());
+
+        let result = sweep(
+            unit_box(1.1, 1.0, -0.5),
+            Vec3::new(-3.0, 0.0, 0.0),
+            flat_world(&[]),
+        );
+        assert_eq!(result.delta, Vec3::new(-3.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn stops_at_step_across_chunk_seam() {
+        let step = [GlobalCoord::new(16, 1, 0)];
+        let result = sweep(
+            unit_box(14.1, 1.0, 0.1),
+            Vec3::new(4.0, 0.0, 0.0),
+            flat_world(&step),
+        );
+
+        assert!(result.collided.x);
+        assert!((result.delta.x - 1.1).abs() < 1e-4);
+
+        // Box above the step passes
+        let result = sweep(
+            unit_box(14.1, 2.0, 0.1),
+            Vec3::new(4.0, 0.0, 0.0),
+            flat_world(&step),
+        );
+        assert!(!result.collided.x);
+    }
+
+    #[test]
+    fn stops_in_corner() {
+        // Walls at x = -1 and z = -1
+        let walls = (-2..=2)
+            .flat_map(|i| {
+                [1, 2]
+                    .into_iter()
+                    .flat_map(move |y| [GlobalCoord::new(-1, y, i), GlobalCoord::new(i, y, -1)])
+            })
+            .collect::<Vec<_>>();
+
+        let result = sweep(
+            unit_box(0.5, 1.0, 0.5),
+            Vec3::new(-2.0, 0.0, -2.0),
+            flat_world(&walls),
+        );
+
+        assert!(result.collided.x && result.collided.z);
+        assert!((result.delta.x + 0.5).abs() < 1e-4);
+        assert!((result.delta.z + 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn raycast_hits_face() {
+        let floor = |pos: GlobalCoord| pos.y <= 0;
+
+        // Looking down at negative coordinates
+        let hit = raycast(
+            Ray::new(Vec3::new(-2.5, 3.5, -0.5), Vec3::NEG_Y),
+            8.0,
+            floor,
+        )
+        .unwrap();
+        assert_eq!(hit.pos, GlobalCoord::new(-3, 0, -1));
+        assert!(matches!(hit.face, Some(Direction::Up)));
+        assert!((hit.distance - 2.5).abs() < 1e-4);
+
+        // Diagonal ray
+        let ray = Ray::new(Vec3::new(0.5, 2.5, 0.5), Vec3::new(1.0, -1.0, 0.0));
+        let hit = raycast(ray, 8.0, floor).unwrap();
+        assert_eq!(hit.pos.y, 0);
+
+        // Too far
+        let ray = Ray::new(Vec3::new(0.5, 20.0, 0.5), Vec3::NEG_Y);
+        assert!(raycast(ray, 8.0, floor).is_none());
+    }
+    #[test]
+    fn occluders_between_points() {
+        // Wall of two blocks at x = -2 and x = -1
+        let wall = |pos: GlobalCoord| (-2..=-1).contains(&pos.x);
+        let (inside, outside) = (Vec3::new(0.5, 1.5, 0.5), Vec3::new(-4.5, 1.5, 0.5));
+
+        assert_eq!(occluders(inside, outside, 8, wall), 2);
+        assert_eq!(occluders(outside, inside, 8, wall), 2);
+        assert_eq!(occluders(inside, outside, 1, wall), 1);
+        assert_eq!(occluders(inside, inside + Vec3::X, 8, wall), 0);
+        // Source inside the wall is behind one block only
+        assert_eq!(occluders(inside, Vec3::new(-1.5, 1.5, 0.5), 8, wall), 1);
+        assert_eq!(occluders(inside, inside, 8, wall), 0);
+    }
+}
@@ -0,0 +1,158 @@
+//! Deterministic hashing and random numbers.
+//!
+//! Everything random in the world (terrain features, block tints, random ticks, wandering mobs)
+//! is derived from a seed here instead of the thread RNG, so a seed gives the same world on every
+//! run and machine. Features take their own stream with [`mix_seed`] and a [`salt`], so they
+//! don't correlate with each other
+
+use rand_core::{impls, Error, RngCore, SeedableRng};
+
+use crate::coord::{ChunkId, GlobalCoord, GlobalUnit};
+
+/// Salts of independent streams derived from one seed
+pub mod salt {
+    pub const DECORATION: u64 = 1;
+    pub const TINT: u64 = 2;
+    pub const RANDOM_TICK: u64 = 3;
+    pub const WANDER: u64 = 4;
+    pub const SPAWN: u64 = 5;
+}
+
+/// SplitMix64 finalizer. Every input bit affects every output bit
+pub const fn mix(value: u64) -> u64 {
+    let mut value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+/// Seed of the `salt` stream of the world generated from `seed`
+pub const fn mix_seed(seed: u32, salt: u64) -> u64 {
+    mix(seed as u64 | salt << 32)
+}
+
+/// Stable hash of a 3D position
+pub const fn hash3(seed: u64, x: GlobalUnit, y: GlobalUnit, z: GlobalUnit) -> u64 {
+    // Mix coordinates with large primes first, so neighbors don't differ in a few bits only
+    mix((x as u64).wrapping_mul(0x9e3779b97f4a7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2ae3d27d4eb4f)
+        ^ (z as u64).wrapping_mul(0x165667b19e3779f9)
+        ^ seed)
+}
+
+/// Stable hash of the block position
+pub const fn hash_pos(seed: u64, pos: GlobalCoord) -> u64 {
+    hash3(seed, pos.x, pos.y, pos.z)
+}
+
+/// Stable hash of the `x`, `z` column
+pub const fn hash_column(seed: u64, x: GlobalUnit, z: GlobalUnit) -> u64 {
+    hash3(seed, x, 0, z)
+}
+
+/// Small and fast SplitMix64 generator. Not cryptographically secure.
+///
+/// Implements [`RngCore`], so it works with `rand` distributions
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    const GAMMA: u64 = 0x9e3779b97f4a7c15;
+
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Generator of the chunk. The same for every run, regardless of the order chunks are
+    /// generated in
+    pub const fn for_chunk(seed: u64, id: ChunkId) -> Self {
+        Self::new(hash3(seed, id.x, id.y, id.z))
+    }
+
+    /// Same as [`RngCore::next_u64`], but doesn't need the trait in scope
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(Self::GAMMA);
+        mix(self.state)
+    }
+
+    /// Uniform value in `[0; 1)`
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits fill the mantissa exactly
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        (SeededRng::next_u64(self) >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        SeededRng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for SeededRng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Self::new(state)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::coord::{ChunkId, GlobalCoord};
+
+    use super::{hash_pos, mix_seed, salt, SeededRng};
+
+    #[test]
+    fn seeded_streams_repeat() {
+        let seed = mix_seed(7, salt::DECORATION);
+        assert_ne!(seed, mix_seed(8, salt::DECORATION));
+        assert_ne!(seed, mix_seed(7, salt::TINT));
+
+        let pos = GlobalCoord::new(-3, 12, 40);
+        assert_eq!(hash_pos(seed, pos), hash_pos(seed, pos));
+        assert_ne!(
+            hash_pos(seed, pos),
+            hash_pos(seed, GlobalCoord::new(-3, 12, 41))
+        );
+
+        let values = |mut rng: SeededRng| (0..16).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        let id = ChunkId::new(1, -2, 3);
+        assert_eq!(
+            values(SeededRng::for_chunk(seed, id)),
+            values(SeededRng::for_chunk(seed, id))
+        );
+        assert_ne!(
+            values(SeededRng::for_chunk(seed, id)),
+            values(SeededRng::for_chunk(seed, ChunkId::new(1, -2, 4)))
+        );
+
+        let mut rng = SeededRng::new(seed);
+        let sum = (0..1000)
+            .map(|_| rng.next_f32())
+            .inspect(|value| assert!((0.0..1.0).contains(value)))
+            .sum::<f32>();
+        assert!((400.0..600.0).contains(&sum));
+    }
+}
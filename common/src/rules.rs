@@ -0,0 +1,50 @@
+//! Rules of the world, changed by scripts (`rule <name> <value>`) and the debug overlay
+
+/// Switches of world systems
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GameRules {
+    /// Ambient mobs spawn at night
+    pub mob_spawning: bool,
+}
+
+impl GameRules {
+    pub const NAMES: [&'static str; 1] = ["mob_spawning"];
+
+    pub const fn new() -> Self {
+        Self { mob_spawning: true }
+    }
+
+    /// Set rule `name` from its text value. `None` if the rule or the value is invalid
+    pub fn set(&mut self, name: &str, value: &str) -> Option<()> {
+        match name {
+            "mob_spawning" => value.parse().ok().map(|value| self.mob_spawning = value),
+            _ => None,
+        }
+    }
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::GameRules;
+
+    #[test]
+    fn set_rules() {
+        let mut rules = GameRules::new();
+        assert_eq!(rules.set("mob_spawning", "false"), Some(()));
+        assert!(!rules.mob_spawning);
+
+        assert_eq!(rules.set("mob_spawning", "never"), None);
+        assert_eq!(rules.set("fire_spread", "true"), None);
+        assert!(!rules.mob_spawning);
+    }
+}
@@ -0,0 +1,218 @@
+use std::{fs, io, path::Path};
+
+use thiserror::Error;
+
+use crate::{
+    block::Block,
+    coord::GlobalCoord,
+    net::{
+        codec::{Reader, Writer},
+        ProtocolError,
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum SchematicError {
+    #[error("Failed to access schematic file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Not a schematic file")]
+    InvalidMagic,
+    #[error("Unsupported schematic version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Block registry mismatch")]
+    RegistryMismatch,
+    #[error("Invalid size: {0:?}")]
+    InvalidSize(GlobalCoord),
+    #[error("Corrupted schematic: {0}")]
+    Corrupted(#[from] ProtocolError),
+}
+
+/// Cuboid of blocks copied from the world
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Schematic {
+    size: GlobalCoord,
+    /// X-major order
+    blocks: Vec<Block>,
+}
+
+impl Schematic {
+    /// Max number of blocks in a schematic
+    pub const MAX_VOLUME: i64 = 1 << 20;
+    /// File extension of saved schematics
+    pub const EXTENSION: &'static str = "schem";
+
+    const MAGIC: [u8; 4] = *b"ECGS";
+    const VERSION: u8 = 1;
+
+    /// Copy region between two corners (both inclusive, in any order).
+    /// Returns `None` if the region is too large or some block isn't available
+    pub fn copy(
+        a: GlobalCoord,
+        b: GlobalCoord,
+        mut block: impl FnMut(GlobalCoord) -> Option<Block>,
+    ) -> Option<Self> {
+        let min = GlobalCoord::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let size = GlobalCoord::new(
+            (a.x - b.x).abs() + 1,
+            (a.y - b.y).abs() + 1,
+            (a.z - b.z).abs() + 1,
+        );
+        if size.x * size.y * size.z > Self::MAX_VOLUME {
+            return None;
+        }
+
+        let mut blocks = Vec::with_capacity((size.x * size.y * size.z) as usize);
+        for x in 0..size.x {
+            for y in 0..size.y {
+                for z in 0..size.z {
+                    blocks.push(block(GlobalCoord::new(min.x + x, min.y + y, min.z + z))?);
+                }
+            }
+        }
+
+        Some(Self { size, blocks })
+    }
+
+    /// Number of blocks along each axis
+    pub fn size(&self) -> GlobalCoord {
+        self.size
+    }
+
+    pub fn volume(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Block at `offset` from the minimal corner
+    pub fn get(&self, offset: GlobalCoord) -> Option<Block> {
+        let inside = |value: i64, size: i64| (0..size).contains(&value);
+        if !(inside(offset.x, self.size.x)
+            && inside(offset.y, self.size.y)
+            && inside(offset.z, self.size.z))
+        {
+            return None;
+        }
+
+        Some(self.blocks[((offset.x * self.size.y + offset.y) * self.size.z + offset.z) as usize])
+    }
+
+    /// Blocks with their offsets from the minimal corner
+    pub fn iter(&self) -> impl Iterator<Item = (GlobalCoord, Block)> + '_ {
+        let size = self.size;
+
+        self.blocks.iter().enumerate().map(move |(i, &block)| {
+            let i = i as i64;
+            (
+                GlobalCoord::new(i / (size.y * size.z), i / size.z % size.y, i % size.z),
+                block,
+            )
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        Self::MAGIC.into_iter().for_each(|byte| writer.u8(byte));
+        writer.u8(Self::VERSION);
+        writer.u64(Block::registry_hash());
+        writer.global_coord(self.size);
+        self.blocks.iter().for_each(|&block| writer.block(block));
+
+        writer.into_inner()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, SchematicError> {
+        let mut reader = Reader::new(bytes);
+
+        for byte in Self::MAGIC {
+            if reader.u8().ok() != Some(byte) {
+                return Err(SchematicError::InvalidMagic);
+            }
+        }
+        let version = reader.u8()?;
+        if version != Self::VERSION {
+            return Err(SchematicError::UnsupportedVersion(version));
+        }
+        if reader.u64()? != Block::registry_hash() {
+            return Err(SchematicError::RegistryMismatch);
+        }
+
+        let size = reader.global_coord()?;
+        let valid = |value: i64| (1..=Self::MAX_VOLUME).contains(&value);
+        if !(valid(size.x) && valid(size.y) && valid(size.z))
+            || size.x * size.y * size.z > Self::MAX_VOLUME
+        {
+            return Err(SchematicError::InvalidSize(size));
+        }
+
+        let blocks = (0..size.x * size.y * size.z)
+            .map(|_| reader.block())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { size, blocks })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SchematicError> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SchematicError> {
+        Ok(fs::write(path, self.encode())?)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{block::Block, coord::GlobalCoord};
+
+    use super::{Schematic, SchematicError};
+
+    /// Stone at the bottom layer, air above
+    fn world(pos: GlobalCoord) -> Option<Block> {
+        (pos.x < 100).then_some(if pos.y == 0 { Block::Stone } else { Block::Air })
+    }
+
+    #[test]
+    fn copy_region() {
+        let schematic =
+            Schematic::copy(GlobalCoord::new(3, 1, -2), GlobalCoord::new(1, 0, 0), world).unwrap();
+
+        assert_eq!(schematic.size(), GlobalCoord::new(3, 2, 3));
+        assert_eq!(schematic.volume(), 18);
+        assert_eq!(schematic.get(GlobalCoord::new(2, 0, 2)), Some(Block::Stone));
+        assert_eq!(schematic.get(GlobalCoord::new(0, 1, 0)), Some(Block::Air));
+        assert_eq!(schematic.get(GlobalCoord::new(3, 0, 0)), None);
+        assert!(schematic
+            .iter()
+            .all(|(offset, block)| schematic.get(offset) == Some(block)));
+
+        // Not loaded
+        assert!(Schematic::copy(GlobalCoord::ZERO, GlobalCoord::new(100, 0, 0), world).is_none());
+        // Too large
+        assert!(
+            Schematic::copy(GlobalCoord::ZERO, GlobalCoord::new(-1, 1023, 1023), |_| {
+                Some(Block::Air)
+            })
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn encode_decode() {
+        let schematic =
+            Schematic::copy(GlobalCoord::ZERO, GlobalCoord::new(4, 2, 7), world).unwrap();
+        let bytes = schematic.encode();
+
+        assert_eq!(Schematic::decode(&bytes).unwrap(), schematic);
+        assert!(matches!(
+            Schematic::decode(b"PNG!"),
+            Err(SchematicError::InvalidMagic)
+        ));
+        assert!(matches!(
+            Schematic::decode(&bytes[..bytes.len() - 1]),
+            Err(SchematicError::Corrupted(_))
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    f32::consts::TAU,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Time of day in hours (`0..24`). Midnight is 0, noon is 12
+#[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
+pub struct TimeOfDay(f32);
+
+impl TimeOfDay {
+    pub const HOURS: f32 = 24.0;
+    /// Real duration of a full day (seconds)
+    pub const DAY_LENGTH: f32 = 1200.0;
+
+    pub const MIDNIGHT: Self = Self(0.0);
+    pub const SUNRISE: Self = Self(6.0);
+    pub const NOON: Self = Self(12.0);
+    pub const SUNSET: Self = Self(18.0);
+    /// Morning time new worlds start with
+    pub const DEFAULT: Self = Self(10.0);
+
+    /// Hours are wrapped into a single day
+    pub fn new(hours: f32) -> Self {
+        let hours = hours.rem_euclid(Self::HOURS);
+        // `rem_euclid` may round up to the divisor
+        Self(if hours < Self::HOURS { hours } else { 0.0 })
+    }
+
+    pub fn hours(&self) -> f32 {
+        self.0
+    }
+
+    pub fn add(&mut self, hours: f32) {
+        *self = Self::new(self.0 + hours);
+    }
+
+    /// Move time forward by `dt` real seconds
+    pub fn advance(&mut self, dt: f32) {
+        self.add(dt * Self::HOURS / Self::DAY_LENGTH);
+    }
+
+    /// Angle of the sun above the eastern horizon (radians). Sun rises at 6 and sets at 18
+    pub fn sun_angle(&self) -> f32 {
+        (self.0 - Self::SUNRISE.0) / Self::HOURS * TAU
+    }
+
+    /// Height of the sun (-1 at midnight, 1 at noon)
+    pub fn sun_height(&self) -> f32 {
+        self.sun_angle().sin()
+    }
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = (self.0 * 60.0) as u32;
+        write!(f, "{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+/// Parses hours (`14.5`), clock time (`14:30`) or names (`noon`)
+impl FromStr for TimeOfDay {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hours = match s.to_ascii_lowercase().as_str() {
+            "midnight" | "night" => return Ok(Self::MIDNIGHT),
+            "sunrise" => return Ok(Self::SUNRISE),
+            "noon" | "day" => return Ok(Self::NOON),
+            "sunset" => return Ok(Self::SUNSET),
+            s => match s.split_once(':') {
+                Some((hours, minutes)) => {
+                    let minutes = minutes.parse::<u8>().map_err(|_| ())?;
+                    if minutes >= 60 {
+                        return Err(());
+                    }
+                    hours.parse::<u8>().map_err(|_| ())? as f32 + minutes as f32 / 60.0
+                }
+                None => s.parse::<f32>().map_err(|_| ())?,
+            },
+        };
+
+        if (0.0..Self::HOURS).contains(&hours) {
+            Ok(Self(hours))
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Weather affecting lighting and fog
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+#[repr(u8)]
+pub enum Weather {
+    #[default]
+    Clear,
+    /// Dim light and gray sky
+    Overcast,
+    /// Short view distance
+    Fog,
+}
+
+impl Weather {
+    pub const ALL: [Self; 3] = [Self::Clear, Self::Overcast, Self::Fog];
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::Overcast => "overcast",
+            Self::Fog => "fog",
+        }
+    }
+}
+
+impl Display for Weather {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Weather {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|weather| weather.name().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{TimeOfDay, Weather};
+
+    #[test]
+    fn time_wraps() {
+        let mut time = TimeOfDay::new(23.0);
+        time.add(2.5);
+        assert_eq!(time.hours(), 1.5);
+
+        time.add(-3.0);
+        assert_eq!(time.hours(), 22.5);
+
+        time.advance(TimeOfDay::DAY_LENGTH / 4.0);
+        assert_eq!(time.hours(), 4.5);
+        assert_eq!(TimeOfDay::new(-1e-9).hours(), 0.0);
+    }
+
+    #[test]
+    fn sun_height() {
+        assert!((TimeOfDay::NOON.sun_height() - 1.0).abs() < 1e-5);
+        assert!((TimeOfDay::MIDNIGHT.sun_height() + 1.0).abs() < 1e-5);
+        assert!(TimeOfDay::SUNRISE.sun_height().abs() < 1e-5);
+    }
+
+    #[test]
+    fn parse_time() {
+        assert_eq!("14:30".parse(), Ok(TimeOfDay::new(14.5)));
+        assert_eq!("7.25".parse(), Ok(TimeOfDay::new(7.25)));
+        assert_eq!("Noon".parse(), Ok(TimeOfDay::NOON));
+        assert_eq!(TimeOfDay::new(14.5).to_string(), "14:30");
+        assert!("24".parse::<TimeOfDay>().is_err());
+        assert!("12:60".parse::<TimeOfDay>().is_err());
+        assert!("dusk".parse::<TimeOfDay>().is_err());
+    }
+
+    #[test]
+    fn parse_weather() {
+        assert_eq!("FOG".parse(), Ok(Weather::Fog));
+        assert!("rain".parse::<Weather>().is_err());
+        assert!(Weather::ALL
+            .into_iter()
+            .all(|weather| Weather::from_id(weather.id()) == Some(weather)));
+    }
+}
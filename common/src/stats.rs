@@ -0,0 +1,151 @@
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+use crate::{
+    block::Block,
+    event::{EventBus, WorldEvent},
+};
+
+/// Totals of all players of a world
+#[derive(PartialEq, Clone, Copy, Default, Debug)]
+pub struct WorldStats {
+    pub blocks_placed: u64,
+    pub blocks_broken: u64,
+    /// Walked and flown distance in blocks
+    pub distance: f64,
+    /// Time with at least one player online
+    pub playtime: Duration,
+}
+
+impl WorldStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count player edits among readable events. Replacing a block counts as breaking and placing
+    pub fn collect(&mut self, events: &EventBus) {
+        for event in events.read() {
+            if let WorldEvent::BlockEdited { before, after, .. } = *event {
+                if before != Block::Air {
+                    self.blocks_broken += 1;
+                }
+                if after != Block::Air {
+                    self.blocks_placed += 1;
+                }
+            }
+        }
+    }
+
+    pub fn travel(&mut self, distance: f32) {
+        self.distance += distance as f64;
+    }
+
+    pub fn play(&mut self, duration: Duration) {
+        self.playtime += duration;
+    }
+
+    /// Text with a `key value` pair per line
+    pub fn encode(&self) -> String {
+        format!(
+            "blocks_placed {}\nblocks_broken {}\ndistance {}\nplaytime {}\n",
+            self.blocks_placed,
+            self.blocks_broken,
+            self.distance,
+            self.playtime.as_secs_f64(),
+        )
+    }
+
+    /// Keys of newer versions are ignored, missing ones are zero
+    pub fn decode(text: &str) -> Option<Self> {
+        let mut stats = Self::new();
+
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            let (Some(key), Some(value)) = (words.next(), words.next()) else {
+                continue;
+            };
+            match key {
+                "blocks_placed" => stats.blocks_placed = value.parse().ok()?,
+                "blocks_broken" => stats.blocks_broken = value.parse().ok()?,
+                "distance" => stats.distance = value.parse().ok()?,
+                "playtime" => {
+                    stats.playtime = Duration::try_from_secs_f64(value.parse().ok()?).ok()?
+                }
+                _ => {}
+            }
+        }
+
+        Some(stats)
+    }
+}
+
+impl Display for WorldStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = self.playtime.as_secs() / 60;
+        write!(
+            f,
+            "Blocks placed: {}, blocks broken: {}, distance: {:.0} blocks, playtime: {}h {:02}m",
+            self.blocks_placed,
+            self.blocks_broken,
+            self.distance,
+            minutes / 60,
+            minutes % 60,
+        )
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        block::Block,
+        coord::GlobalCoord,
+        event::{EventBus, WorldEvent},
+    };
+
+    use super::WorldStats;
+
+    #[test]
+    fn count_edits_and_persist() {
+        let pos = GlobalCoord::new(0, 0, 0);
+        let mut events = EventBus::new();
+        events.extend([
+            WorldEvent::BlockEdited {
+                pos,
+                before: Block::Air,
+                after: Block::Stone,
+            },
+            WorldEvent::BlockEdited {
+                pos,
+                before: Block::Stone,
+                after: Block::Sand,
+            },
+            // Not made by a player
+            WorldEvent::BlockChanged {
+                pos,
+                before: Block::Sand,
+                after: Block::Air,
+            },
+        ]);
+        events.update();
+
+        let mut stats = WorldStats::new();
+        stats.collect(&events);
+        stats.travel(12.5);
+        stats.play(Duration::from_secs(3900));
+        assert_eq!((stats.blocks_placed, stats.blocks_broken), (2, 1));
+        assert_eq!(WorldStats::decode(&stats.encode()), Some(stats));
+        assert_eq!(
+            stats.to_string(),
+            "Blocks placed: 2, blocks broken: 1, distance: 12 blocks, playtime: 1h 05m"
+        );
+        assert_eq!(WorldStats::decode("distance far\n"), None);
+    }
+}
@@ -0,0 +1,8 @@
+//! Fixtures shared by tests of several modules
+
+use crate::coord::GlobalCoord;
+
+/// Solidity lookup of a flat floor at y = 0 plus additional solid blocks
+pub fn flat_world(blocks: &[GlobalCoord]) -> impl Fn(GlobalCoord) -> Option<bool> + '_ {
+    move |pos| Some(pos.y <= 0 || blocks.contains(&pos))
+}
@@ -0,0 +1,264 @@
+//! Resource packs replacing the default assets without rebuilding the game.
+//!
+//! Pack is a directory in [`PACKS_DIR`]:
+//!
+//! ```text
+//! my_pack/
+//!     pack.txt          # Description (first line), optional
+//!     blocks.palette    # Block colors, see `common::palette::Palette`
+//!     shaders/*.wgsl    # Replace built-in shaders of the same name
+//!     fonts/*.ttf       # Fonts selectable in the settings, see also `FONTS_DIR`
+//!     textures/         # Reserved
+//!     sounds/           # Reserved
+//! ```
+//!
+//! Enabled packs are applied in the load order, so files of later packs override files of earlier
+//! ones. Files missing from every pack come from the built-in assets
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use common::palette::{Palette, PaletteError};
+use tracing::warn;
+
+use crate::{
+    consts::{FONTS_DIR, PACKS_DIR},
+    error::{Context, Error},
+};
+
+/// Directory of replaceable assets
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ResourcePack {
+    name: String,
+    description: String,
+    root: PathBuf,
+}
+
+impl ResourcePack {
+    pub const DESCRIPTION_FILE: &'static str = "pack.txt";
+    pub const PALETTE_FILE: &'static str = "blocks.palette";
+    pub const SHADERS_DIR: &'static str = "shaders";
+    pub const FONTS_DIR: &'static str = "fonts";
+
+    /// Open pack directory. Named after the directory
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, Error> {
+        let root = root.into();
+        if !root.is_dir() {
+            return Err(Error::Asset {
+                path: root.display().to_string(),
+                reason: "Resource pack isn't a directory".to_string(),
+            });
+        }
+
+        let description = match fs::read_to_string(root.join(Self::DESCRIPTION_FILE)) {
+            Ok(text) => text.lines().next().unwrap_or_default().trim().to_string(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err).context("Failed to read resource pack description"),
+        };
+        let name = root
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+
+        Ok(Self {
+            name,
+            description,
+            root,
+        })
+    }
+
+    /// Every pack in the directory, sorted by name. Broken packs are skipped with a warning
+    pub fn scan(dir: impl AsRef<Path>) -> Result<Vec<Self>, Error> {
+        let mut paths = fs::read_dir(dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .context("Failed to read resource packs directory")?;
+        paths.retain(|path| path.is_dir());
+        paths.sort();
+
+        Ok(paths
+            .into_iter()
+            .filter_map(|path| {
+                Self::open(path)
+                    .map_err(|err| warn!(%err, "Skipping resource pack"))
+                    .ok()
+            })
+            .collect())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the file relative to the pack root. `None` if the pack doesn't have it
+    pub fn file(&self, path: impl AsRef<Path>) -> Option<PathBuf> {
+        Some(self.root.join(path)).filter(|path| path.is_file())
+    }
+}
+
+/// Resolves assets through the enabled resource packs
+#[derive(Default, Clone, Debug)]
+pub struct AssetManager {
+    /// Load order, later packs take precedence
+    packs: Vec<ResourcePack>,
+}
+
+impl AssetManager {
+    /// Built-in assets only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Packs in the load order
+    pub fn with_packs(packs: Vec<ResourcePack>) -> Self {
+        Self { packs }
+    }
+
+    /// Packs available in [`PACKS_DIR`]. Empty if the directory doesn't exist
+    pub fn available() -> Vec<ResourcePack> {
+        if !Path::new(PACKS_DIR).is_dir() {
+            return Vec::new();
+        }
+
+        ResourcePack::scan(PACKS_DIR).unwrap_or_else(|err| {
+            warn!(%err, "Failed to scan resource packs");
+            Vec::new()
+        })
+    }
+
+    pub fn packs(&self) -> &[ResourcePack] {
+        &self.packs
+    }
+
+    /// File from the last pack which has it. `None` if the built-in asset should be used
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Option<PathBuf> {
+        let path = path.as_ref();
+        self.packs.iter().rev().find_map(|pack| pack.file(path))
+    }
+
+    /// Overriding source of the built-in shader file (e.g. `terrain.wgsl`). Unreadable files are
+    /// skipped with a warning
+    pub fn shader(&self, file: &str) -> Option<String> {
+        let path = self.resolve(Path::new(ResourcePack::SHADERS_DIR).join(file))?;
+        fs::read_to_string(&path)
+            .map_err(|err| warn!(%err, ?path, "Failed to read shader override"))
+            .ok()
+    }
+
+    /// Font files in [`FONTS_DIR`] and the enabled packs, sorted and deduplicated
+    pub fn fonts(&self) -> Vec<String> {
+        let mut fonts = self
+            .packs
+            .iter()
+            .map(|pack| pack.root.join(ResourcePack::FONTS_DIR))
+            .chain([PathBuf::from(FONTS_DIR)])
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let extension = path.extension()?.to_ascii_lowercase();
+                (extension == "ttf" || extension == "otf")
+                    .then(|| path.file_name()?.to_str().map(str::to_string))?
+            })
+            .collect::<Vec<_>>();
+        fonts.sort();
+        fonts.dedup();
+
+        fonts
+    }
+
+    /// Data of the font file from the last pack which has it or [`FONTS_DIR`]
+    pub fn font(&self, file: &str) -> Result<Vec<u8>, Error> {
+        let path = self
+            .resolve(Path::new(ResourcePack::FONTS_DIR).join(file))
+            .unwrap_or_else(|| Path::new(FONTS_DIR).join(file));
+
+        fs::read(&path).context(format!("Failed to read font {path:?}"))
+    }
+
+    /// Block colors of the last pack which has them, named after the pack. Default palette if
+    /// none or it's broken
+    pub fn palette(&self) -> Palette {
+        let Some((pack, path)) = self.packs.iter().rev().find_map(|pack| {
+            pack.file(ResourcePack::PALETTE_FILE)
+                .map(|path| (pack, path))
+        }) else {
+            return Palette::new();
+        };
+
+        fs::read_to_string(&path)
+            .map_err(PaletteError::from)
+            .and_then(|source| Palette::parse(pack.name(), &source))
+            .unwrap_or_else(|err| {
+                warn!(%err, ?path, "Failed to load resource pack palette");
+                Palette::new()
+            })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use common::{block::Block, palette::Palette};
+    use glam::Vec3;
+
+    use super::{AssetManager, ResourcePack};
+
+    #[test]
+    fn later_packs_override() {
+        let dir = env::temp_dir().join(format!("ecg-packs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (pack, shader, palette) in [
+            ("a", Some("// a"), "grass 1 0 0"),
+            ("b", None, "grass 0 0 1"),
+        ] {
+            let root = dir.join(pack);
+            fs::create_dir_all(root.join(ResourcePack::SHADERS_DIR)).unwrap();
+            if let Some(shader) = shader {
+                fs::write(root.join("shaders/terrain.wgsl"), shader).unwrap();
+            }
+            fs::write(root.join(ResourcePack::PALETTE_FILE), palette).unwrap();
+        }
+        fs::write(
+            dir.join("b").join(ResourcePack::DESCRIPTION_FILE),
+            "Blue\nGrass",
+        )
+        .unwrap();
+
+        let packs = ResourcePack::scan(&dir).unwrap();
+        assert_eq!(
+            packs.iter().map(ResourcePack::name).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert_eq!(packs[1].description(), "Blue");
+
+        let assets = AssetManager::with_packs(packs.clone());
+        assert_eq!(assets.shader("terrain.wgsl").as_deref(), Some("// a"));
+        assert_eq!(assets.shader("post.wgsl"), None);
+        assert_eq!(assets.palette().color(Block::Grass), Vec3::Z);
+        assert_eq!(assets.palette().name(), "b");
+
+        let assets = AssetManager::with_packs(packs.into_iter().rev().collect());
+        assert_eq!(assets.palette().color(Block::Grass), Vec3::X);
+        assert_eq!(AssetManager::new().palette(), Palette::new());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
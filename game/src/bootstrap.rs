@@ -1,9 +1,11 @@
 use std::{env::var, str::FromStr};
 
 use thiserror::Error;
-use tracing::metadata::LevelFilter;
+use tracing::{metadata::LevelFilter, warn};
 use tracing_subscriber::{fmt::fmt, EnvFilter};
 
+use crate::{consts::SETTINGS_FILE, settings::Settings};
+
 #[derive(Error, Debug)]
 pub enum BootstrapError {
     #[error("Can't parse log level (found: {0:?})")]
@@ -42,3 +44,12 @@ pub fn bootstrap() -> Result<(), BootstrapError> {
 
     Ok(())
 }
+
+/// Read the settings file. Called once on startup after the log subscriber is set up, so warnings
+/// about invalid lines are shown
+pub fn load_settings() -> Settings {
+    Settings::load(SETTINGS_FILE).unwrap_or_else(|err| {
+        warn!(%err, "Failed to load settings, using defaults");
+        Settings::new()
+    })
+}
@@ -1,10 +1,20 @@
 use lazy_static::lazy_static;
 
+/// Async runtime threads when detected automatically. Only networking runs there
 pub const ASYNC_THREADS: usize = 2;
 pub const MIN_WINDOW_WIDTH: u32 = 854;
 pub const MIN_WINDOW_HEIGHT: u32 = 480;
+/// Block color palettes selectable in the settings
+pub const PALETTES_DIR: &str = "assets/palettes";
+/// Resource packs selectable in the settings
+pub const PACKS_DIR: &str = "assets/packs";
+/// Localization bundles selectable in the settings
+pub const LANG_DIR: &str = "assets/lang";
+/// Fonts selectable in the settings
+pub const FONTS_DIR: &str = "assets/fonts";
+/// Player preferences
+pub const SETTINGS_FILE: &str = "settings.txt";
 
 lazy_static! {
     pub static ref CPU_CORES: usize = num_cpus::get();
-    pub static ref BLOCKING_THREADS: usize = (*CPU_CORES / 2).max(2);
 }
@@ -1,28 +1,70 @@
 // TODO: Make crate from this module
 
-use std::time::Instant;
+use std::{
+    collections::VecDeque,
+    mem::size_of,
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver},
+    time::{Duration, Instant},
+};
 
 use common::{
     block::{Block, BlockRepr},
     clock::ClockStats,
-    coord::{ChunkId, GlobalCoord, CHUNK_CUBE},
+    coord::{ChunkId, GlobalCoord, GlobalUnit, CHUNK_SIZE, G_CHUNK_SIZE},
+    health::Health,
+    net::protocol::ClientMsg,
+    palette::Palette,
+    schematic::Schematic,
+    sky::{TimeOfDay, Weather},
 };
 use egui::{
-    global_dark_light_mode_switch, ComboBox, Context, DragValue, FontDefinitions, Grid,
-    RadioButton, Slider, Style, TopBottomPanel, Window,
+    global_dark_light_mode_switch, pos2, vec2, Align2, Area, Button, Checkbox, Color32, ComboBox,
+    Context, DragValue, FontData, FontDefinitions, FontFamily, Grid, RadioButton, Rect, Response,
+    ScrollArea, Sense, Shape, Slider, Stroke, Style, TopBottomPanel, Ui, Window,
 };
 use egui_winit_platform::{Platform, PlatformDescriptor};
-use wgpu::PresentMode;
-use winit::{event::WindowEvent, window::Window as WinitWindow};
+use tokio::runtime::Runtime;
+use tracing::warn;
+use wgpu::Backends;
+use winit::{
+    dpi::PhysicalSize,
+    event::WindowEvent,
+    window::{Window as WinitWindow, WindowId},
+};
 
 use crate::{
-    render::{renderer::Renderer, RenderMode},
+    assets::{AssetManager, ResourcePack},
+    consts::{FONTS_DIR, PACKS_DIR, PALETTES_DIR, SETTINGS_FILE},
+    error::Error,
+    headless::Headless,
+    i18n::{Language, Locale},
+    memory,
+    render::{
+        capture::{CaptureOutput, CaptureSettings},
+        pacer::PacerStats,
+        pipelines::Environment,
+        renderer::Renderer,
+        DebugView, DrawStages, PresentPolicy, RenderMode,
+    },
     scene::{
-        camera::{Camera, CameraMode},
-        chunk::ChunkManager,
+        camera::{Camera, CameraMode, Projection},
+        chunk::{ChunkManager, LogicChunk, TerrainStatus},
+        debug::DebugPalette,
+        entity::RemoteEntities,
+        heatmap::ChunkMetric,
+        inset::{InsetView, ViewInset},
+        map::MapView,
+        sim::SimClock,
+        spawn::MobSpawner,
+        survival::Survival,
         Scene,
     },
-    types::WEvent,
+    settings::{
+        ChunkSettings, ControlSettings, GraphicsSettings, Settings, ThreadSettings, UiSettings,
+    },
+    types::{F32x3, U32x2, WEvent},
+    window::binding::{GameInput, KeyBindings},
 };
 
 /// Handles everything related to debug overlay drawing
@@ -31,6 +73,13 @@ pub struct DebugOverlay {
     pub platform: Platform,
     state: DebugOverlayState,
     time: Instant,
+    window_id: WindowId,
+    /// Scale factor reported by the window
+    window_scale: f64,
+    /// UI scale applied over the window scale factor
+    ui_scale: f32,
+    /// Applied font file (`None` for the built-in fonts) and body text size
+    font: (Option<String>, f32),
 }
 
 impl DebugOverlay {
@@ -47,19 +96,41 @@ impl DebugOverlay {
             }),
             state: DebugOverlayState::new(),
             time: Instant::now(),
+            window_id: window.id(),
+            window_scale: window.scale_factor(),
+            ui_scale: 1.0,
+            font: (None, UiSettings::DEFAULT_FONT_SIZE),
         }
     }
 
+    /// Window has been resized
+    pub fn resize(&mut self, size: U32x2) {
+        self.platform.handle_event(&WEvent::WindowEvent {
+            window_id: self.window_id,
+            event: WindowEvent::Resized(PhysicalSize::new(size.x, size.y)),
+        });
+    }
+
+    /// Window has been moved to a monitor with another scale factor
+    pub fn set_window_scale(&mut self, scale_factor: f32, size: U32x2) {
+        self.window_scale = scale_factor as f64;
+        self.rescale(PhysicalSize::new(size.x, size.y));
+    }
+
+    /// Egui pixels per point: window scale factor multiplied by the UI scale
+    pub fn scale_factor(&self) -> f32 {
+        (self.window_scale * self.ui_scale as f64) as f32
+    }
+
     pub fn handle_event(&mut self, event: &WEvent, cursor_grubbed: bool) -> bool {
         if let WEvent::WindowEvent {
             event: window_event,
             ..
         } = &event
         {
+            // Size and scale factor changes come through `resize` and `set_window_scale`, so they
+            // aren't missed while the overlay is hidden
             match window_event {
-                WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. } => {
-                    self.platform.handle_event(event)
-                }
                 WindowEvent::ReceivedCharacter(_)
                 | WindowEvent::KeyboardInput { .. }
                 | WindowEvent::ModifiersChanged(_)
@@ -88,18 +159,98 @@ impl DebugOverlay {
         // Update internal egui time (used for animations)
         self.platform.update_time(self.time.elapsed().as_secs_f64());
 
+        // Scale and fonts are applied between drags, so slider doesn't jump from under the pointer
+        let ui_settings = &payload.scene.settings.ui;
+        if !self.platform.context().input().pointer.any_down() {
+            if ui_settings.scale != self.ui_scale {
+                self.ui_scale = ui_settings.scale;
+                let resolution = payload.renderer.resolution();
+                self.rescale(PhysicalSize::new(resolution.x, resolution.y));
+            }
+
+            let font = (ui_settings.font.clone(), ui_settings.font_size);
+            if font != self.font {
+                self.font = font;
+                self.apply_font(payload.renderer.assets());
+            }
+        }
+
         // Begin frame
         self.platform.begin_frame();
 
         // Draw UI
         self.state.draw(&self.platform.context(), payload);
     }
+
+    /// Put the font file before the built-in fonts and scale text styles to the font size
+    fn apply_font(&mut self, assets: &AssetManager) {
+        const NAME: &str = "custom";
+
+        let ctx = self.platform.context();
+        let (file, size) = &self.font;
+
+        let mut fonts = FontDefinitions::default();
+        if let Some(file) = file {
+            // Egui panics on invalid fonts
+            let data =
+                assets
+                    .font(file)
+                    .and_then(|data| match ab_glyph::FontRef::try_from_slice(&data) {
+                        Ok(_) => Ok(data),
+                        Err(err) => Err(Error::Asset {
+                            path: file.clone(),
+                            reason: err.to_string(),
+                        }),
+                    });
+            match data {
+                Ok(data) => {
+                    fonts
+                        .font_data
+                        .insert(NAME.to_string(), FontData::from_owned(data));
+                    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+                        fonts
+                            .families
+                            .entry(family)
+                            .or_default()
+                            .insert(0, NAME.to_string());
+                    }
+                }
+                Err(err) => warn!(%err, "Failed to load overlay font"),
+            }
+        }
+        ctx.set_fonts(fonts);
+
+        let scale = size / UiSettings::DEFAULT_FONT_SIZE;
+        let mut style = (*ctx.style()).clone();
+        style.text_styles = Style::default()
+            .text_styles
+            .into_iter()
+            .map(|(text_style, mut font)| {
+                font.size *= scale;
+                (text_style, font)
+            })
+            .collect();
+        ctx.set_style(style);
+    }
+
+    /// Pass the combined scale factor to egui, so pointer positions are converted to points too
+    fn rescale(&mut self, mut size: PhysicalSize<u32>) {
+        self.platform.handle_event(&WEvent::WindowEvent {
+            window_id: self.window_id,
+            event: WindowEvent::ScaleFactorChanged {
+                scale_factor: self.scale_factor() as f64,
+                new_inner_size: &mut size,
+            },
+        });
+    }
 }
 
 pub struct DebugPayload<'a> {
     pub clock_stats: ClockStats,
+    pub pacer_stats: PacerStats,
     pub scene: &'a mut Scene,
     pub renderer: &'a mut Renderer,
+    pub runtime: &'a Runtime,
 }
 
 /// Represents debug overlay state (windows, buttons, etc.)
@@ -109,36 +260,90 @@ pub struct DebugOverlayState {
     top_bar_visible: bool,
     /// Graphics tweaks window
     graphics_opened: bool,
+    /// Key bindings window
+    controls_opened: bool,
+    /// Keys are captured by position instead of the character
+    bind_physical: bool,
     /// GPU timings
     gpu_stats_opened: bool,
     /// Camera tweaks window
     camera_opened: bool,
     /// Chunk tweaks window
     chunks_opened: bool,
+    /// Remote entities window
+    entities_opened: bool,
+    /// Network statistics window
+    network_opened: bool,
     /// Block changer
     painter_opened: bool,
     /// Teleport window
     teleport_opened: bool,
+    /// Structure copy and paste
+    schematic_opened: bool,
+    /// Block under the crosshair
+    inspector_opened: bool,
+    /// Heap statistics window
+    memory_opened: bool,
+    /// World statistics window
+    stats_opened: bool,
+    /// Sky, fog and lighting parameters
+    environment_opened: bool,
+    /// Terrain map export window
+    map_export_opened: bool,
+    /// Simulation time control
+    simulation_opened: bool,
+
+    /// UI strings
+    locale: Locale,
+    /// Languages selectable in the menu
+    languages: Vec<Language>,
 
     // Sub states
     graphics_tweaks: GraphicsTweaks,
+    palettes: PaletteList,
+    packs: PackList,
+    frame_capture: FrameCaptureTweaks,
     painter: Painter,
     teleport: Teleport,
+    schematic_tool: SchematicTool,
+    map_export: MapExport,
+    chunk_inspector: ChunkInspector,
+    alloc_rate: AllocRate,
 }
 
 impl DebugOverlayState {
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             top_bar_visible: true,
             graphics_opened: false,
+            controls_opened: false,
+            bind_physical: false,
             gpu_stats_opened: false,
             camera_opened: false,
             chunks_opened: false,
+            entities_opened: false,
+            network_opened: false,
             painter_opened: false,
             teleport_opened: false,
+            schematic_opened: false,
+            inspector_opened: false,
+            memory_opened: false,
+            stats_opened: false,
+            environment_opened: false,
+            map_export_opened: false,
+            simulation_opened: false,
+            locale: Locale::new(),
+            languages: Locale::available(),
             graphics_tweaks: GraphicsTweaks::new(),
+            palettes: PaletteList::new(),
+            packs: PackList::new(),
+            frame_capture: FrameCaptureTweaks::new(),
             painter: Painter::new(),
             teleport: Teleport::new(),
+            schematic_tool: SchematicTool::new(),
+            map_export: MapExport::new(),
+            chunk_inspector: ChunkInspector::new(),
+            alloc_rate: AllocRate::new(),
         }
     }
 
@@ -146,65 +351,327 @@ impl DebugOverlayState {
     pub fn draw(&mut self, ctx: &Context, payload: DebugPayload) {
         let DebugPayload {
             clock_stats,
+            pacer_stats,
             scene:
                 Scene {
                     camera,
+                    map,
+                    minimap,
+                    inset,
                     chunk_manager,
-                    fps,
+                    block_edits,
+                    sim,
+                    net,
+                    disconnect_reason,
+                    world_stats,
+                    world_backups,
+                    entities,
+                    spawner,
+                    rules,
+                    remote_entities,
+                    labels,
+                    show_chunk_labels,
+                    show_frustum,
+                    gizmos,
+                    show_paths,
+                    chunk_heatmap,
+                    frozen_frustum,
+                    survival,
+                    picked,
+                    selection,
+                    environment,
+                    sky,
+                    player_id,
+                    latency_wait,
+                    settings,
+                    binding_capture,
                     ..
                 },
             renderer,
+            runtime,
         } = payload;
 
+        // Selected language is applied after drawing, as strings are borrowed until then
+        let mut new_locale = None;
+        let tr = &self.locale;
+        let [r, g, b] = settings.ui.debug_palette.error();
+        let error_color = Color32::from_rgb(r, g, b);
+
         if self.top_bar_visible {
             TopBottomPanel::top("menu_bar").show(ctx, |ui| {
                 ui.horizontal_wrapped(|ui| {
                     global_dark_light_mode_switch(ui);
                     ui.separator();
-                    ui.menu_button("Game", |menu| {
-                        if menu.button("GPU Stats").clicked() {
+                    ui.menu_button(tr.get("menu.game"), |menu| {
+                        if menu.button(tr.get("window.gpu_stats")).clicked() {
                             self.gpu_stats_opened = true;
                         }
-                        if menu.button("Graphics").clicked() {
+                        if menu.button(tr.get("window.graphics")).clicked() {
                             self.graphics_opened = true;
                         }
+                        if menu.button(tr.get("window.controls")).clicked() {
+                            self.controls_opened = true;
+                        }
+                        if menu.button(tr.get("window.memory")).clicked() {
+                            self.memory_opened = true;
+                        }
+                        if menu.button(tr.get("window.stats")).clicked() {
+                            self.stats_opened = true;
+                            if let Some(net) = net {
+                                net.send(ClientMsg::RequestStats);
+                            }
+                        }
                     });
-                    ui.menu_button("Scene", |menu| {
-                        if menu.button("Camera").clicked() {
+                    ui.menu_button(tr.get("menu.scene"), |menu| {
+                        if menu.button(tr.get("window.camera")).clicked() {
                             self.camera_opened = true;
                         }
-                        if menu.button("ChunkManager").clicked() {
+                        if menu.button(tr.get("window.chunks")).clicked() {
                             self.chunks_opened = true;
                         }
-                        if menu.button("Reset Camera").clicked() {
+                        if menu.button(tr.get("window.entities")).clicked() {
+                            self.entities_opened = true;
+                        }
+                        if menu.button(tr.get("window.network")).clicked() {
+                            self.network_opened = true;
+                        }
+                        if menu.button(tr.get("window.inspector")).clicked() {
+                            self.inspector_opened = true;
+                        }
+                        if menu.button(tr.get("window.environment")).clicked() {
+                            self.environment_opened = true;
+                        }
+                        if menu.button(tr.get("window.map_export")).clicked() {
+                            self.map_export_opened = true;
+                        }
+                        if menu.button(tr.get("window.simulation")).clicked() {
+                            self.simulation_opened = true;
+                        }
+                        if menu.button(tr.get("menu.reset_camera")).clicked() {
                             camera.f_pos = Camera::DEFAULT_POSITION;
                             camera.f_rot = Camera::DEFAULT_ORIENTATION;
                             camera.set_mode(CameraMode::FirstPerson);
                         }
                     });
-                    ui.menu_button("Cheats", |menu| {
-                        if menu.button("Painter").clicked() {
+                    ui.menu_button(tr.get("menu.cheats"), |menu| {
+                        if menu.button(tr.get("window.painter")).clicked() {
                             self.painter_opened = true;
                         }
-                        if menu.button("Teleport").clicked() {
+                        if menu.button(tr.get("window.teleport")).clicked() {
                             self.teleport_opened = true;
                         }
+                        if menu.button(tr.get("window.schematic")).clicked() {
+                            self.schematic_opened = true;
+                        }
+                    });
+                    ui.menu_button(tr.get("menu.language"), |menu| {
+                        for language in &self.languages {
+                            let selected = language.code == self.locale.code();
+                            if menu.radio(selected, &language.name).clicked() && !selected {
+                                match Locale::load(&language.code) {
+                                    Ok(locale) => new_locale = Some(locale),
+                                    Err(err) => warn!(%err, "Failed to load language"),
+                                }
+                            }
+                        }
                     });
                     ui.separator();
-                    ui.label(format!(
-                        "FPS: {:.1} ({}ms)",
-                        clock_stats.avg_tps,
-                        clock_stats.avg_tick_dur.as_millis(),
+                    ui.label(tr.format(
+                        "hud.fps",
+                        &[
+                            ("fps", &format!("{:.1}", clock_stats.avg_tps)),
+                            ("tick", &clock_stats.avg_tick_dur.as_millis()),
+                        ],
+                    ));
+                    ui.separator();
+                    ui.label(tr.format(
+                        "hud.time",
+                        &[("time", &sky.time), ("weather", &sky.weather)],
                     ));
                 })
             });
         }
 
-        Window::new("GPU Stats")
+        if let Some(reason) = disconnect_reason {
+            let mut close = false;
+
+            Window::new(tr.get("hud.disconnected"))
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(reason.as_str());
+                    close = ui.button(tr.get("hud.ok")).clicked();
+                });
+
+            if close {
+                *disconnect_reason = None;
+            }
+        }
+
+        // Survival HUD
+        if let Some(survival) = survival.as_mut().filter(|_| player_id.is_none()) {
+            Area::new("hud_hearts")
+                .anchor(Align2::CENTER_BOTTOM, [0.0, -16.0])
+                .interactable(false)
+                .show(ctx, |ui| draw_hearts(ui, &survival.health));
+
+            if let Some(respawn_in) = survival.respawn_in() {
+                Window::new(tr.get("hud.died"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                    .show(ctx, |ui| {
+                        if let Some(source) = survival.health.last_damage() {
+                            ui.label(tr.get(source.death_message_key()));
+                        }
+                        ui.label(tr.format("hud.respawn_in", &[("seconds", &respawn_in.ceil())]));
+                        if ui.button(tr.get("hud.respawn_now")).clicked() {
+                            survival.respawn(&mut camera.f_pos);
+                            camera.pos = camera.f_pos;
+                        }
+                    });
+            }
+        }
+
+        let alloc_stats = memory::stats();
+        if let Some(stats) = alloc_stats {
+            self.alloc_rate.update(stats.allocations);
+        }
+
+        Window::new(tr.get("window.memory"))
+            .open(&mut self.memory_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                const MIB: f64 = 1024.0 * 1024.0;
+
+                ui.collapsing("Heap", |ui| {
+                    let Some(stats) = alloc_stats else {
+                        ui.label("Not available with tracy-memory");
+                        return;
+                    };
+
+                    Grid::new("memory_heap_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Live:");
+                            ui.label(format!("{:.2} MiB", stats.bytes_live as f64 / MIB));
+                            ui.end_row();
+
+                            ui.label("Peak:");
+                            ui.label(format!("{:.2} MiB", stats.bytes_peak as f64 / MIB));
+                            ui.end_row();
+
+                            ui.label("Live allocations:");
+                            ui.label(format!("{}", stats.live_allocations()));
+                            ui.end_row();
+
+                            ui.label("Allocations/sec:");
+                            ui.label(format!("{:.0}", self.alloc_rate.per_sec));
+                            ui.end_row();
+                        });
+                });
+
+                ui.collapsing("Subsystems (estimated)", |ui| {
+                    Grid::new("memory_subsystems_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Logic chunks:");
+                            ui.label(format!(
+                                "{:.2} MiB ({})",
+                                (chunk_manager.chunks.capacity() * size_of::<LogicChunk>()) as f64
+                                    / MIB,
+                                chunk_manager.chunks.len()
+                            ));
+                            ui.end_row();
+
+                            ui.label("Terrain meshes (GPU):");
+                            ui.label(format!(
+                                "{:.2} / {:.0} MiB ({})",
+                                chunk_manager.terrain_memory() as f64 / MIB,
+                                chunk_manager.memory_budget as f64 / MIB,
+                                chunk_manager.chunks.mesh_count()
+                            ));
+                            ui.end_row();
+
+                            ui.label("Evicted meshes:");
+                            ui.label(format!(
+                                "{}",
+                                chunk_manager
+                                    .chunks
+                                    .iter()
+                                    .filter(|(_, chunk)| {
+                                        matches!(chunk.status(), TerrainStatus::Evicted)
+                                    })
+                                    .count()
+                            ));
+                            ui.end_row();
+
+                            ui.label("Meshes in flight:");
+                            ui.label(format!(
+                                "{}",
+                                chunk_manager
+                                    .chunks
+                                    .iter()
+                                    .filter(|(_, chunk)| {
+                                        matches!(chunk.status(), TerrainStatus::Pending)
+                                    })
+                                    .count()
+                            ));
+                            ui.end_row();
+
+                            ui.label("Chunks generating:");
+                            ui.label(format!("{}", chunk_manager.chunk_gen_ids.len()));
+                            ui.end_row();
+
+                            ui.label("Local entities:");
+                            ui.label(format!("{}", entities.len()));
+                            ui.end_row();
+                        });
+                });
+            });
+
+        Window::new(tr.get("window.gpu_stats"))
             .open(&mut self.gpu_stats_opened)
             .resizable(false)
             .show(ctx, |ui| {
                 ui.label(format!("wgpu Backend: {}", renderer.graphics_backend(),));
+                ui.label(format!("Frames in flight: {}", renderer.frames_in_flight()));
+                ui.collapsing("Frame Pacing", |ui| {
+                    let millis = |duration: Duration| duration.as_secs_f32() * 1000.0;
+                    match pacer_stats.refresh {
+                        Some(refresh) => ui.label(format!(
+                            "Refresh: {:.2}ms ({})",
+                            millis(refresh),
+                            if pacer_stats.display_refresh {
+                                "monitor"
+                            } else {
+                                "measured"
+                            }
+                        )),
+                        None => ui.label("Refresh: unknown"),
+                    };
+                    ui.label(format!(
+                        "Present interval: {:.2}ms ± {:.2}ms",
+                        millis(pacer_stats.avg_interval),
+                        millis(pacer_stats.jitter)
+                    ));
+                    ui.label(format!(
+                        "Max frame work: {:.2}ms",
+                        millis(pacer_stats.max_work)
+                    ));
+                    ui.label(format!("Latency wait: {:.2}ms", millis(pacer_stats.waited)));
+                    ui.label(format!(
+                        "Input latency: {:.2}ms to present, {:.2}ms to GPU (max {:.2}ms)",
+                        millis(pacer_stats.present_latency),
+                        millis(pacer_stats.latency),
+                        millis(pacer_stats.max_latency)
+                    ))
+                    .on_hover_text(
+                        "From the oldest input event of a frame, medians of the recent frames",
+                    );
+                });
                 ui.collapsing("Timings", |ui| {
                     renderer.timings().iter().for_each(|timing| {
                         ui.label(format!(
@@ -217,22 +684,32 @@ impl DebugOverlayState {
                     });
                 });
                 ui.collapsing("Buffers", |ui| {
-                    let (terrain_vertices, terrain_indices) = chunk_manager.terrain.values().fold(
+                    let (terrain_vertices, terrain_indices) = chunk_manager.chunks.meshes().fold(
                         (0, 0),
-                        |(vertices, indices), chunk| {
+                        |(vertices, indices), (_, chunk)| {
                             (
-                                vertices + chunk.vertex_buffer.length(),
-                                indices + chunk.index_buffer.length(),
+                                vertices + chunk.slice.vertices.len(),
+                                indices + chunk.slice.indices.len(),
                             )
                         },
                     );
                     ui.label("Terrain Chunks:");
                     ui.label(format!("\tVertices: {}", terrain_vertices));
                     ui.label(format!("\tIndices: {}", terrain_indices));
+                    ui.label(format!(
+                        "\tArena: {} pages, {:.2} MiB",
+                        chunk_manager.arena.page_count(),
+                        chunk_manager.arena.capacity() as f64 / (1024.0 * 1024.0)
+                    ));
                 });
             });
 
-        Window::new("Graphics")
+        // Tweaks follow the applied values until the window is opened
+        if !self.graphics_opened {
+            self.graphics_tweaks =
+                GraphicsTweaks::current(renderer.render_mode(), &settings.graphics, *latency_wait);
+        }
+        Window::new(tr.get("window.graphics"))
             .open(&mut self.graphics_opened)
             .resizable(false)
             .show(ctx, |ui| {
@@ -242,35 +719,84 @@ impl DebugOverlayState {
                     .show(ui, |ui| {
                         ui.label("Present Mode");
                         ComboBox::from_id_source("present_mode")
-                            .selected_text(format!("{:?}", self.graphics_tweaks.present_mode))
+                            .selected_text(self.graphics_tweaks.present_mode.name())
                             .show_ui(ui, |ui| {
-                                ui.selectable_value(
-                                    &mut self.graphics_tweaks.present_mode,
-                                    PresentMode::Fifo,
-                                    "Fifo",
-                                );
-                                ui.selectable_value(
-                                    &mut self.graphics_tweaks.present_mode,
-                                    PresentMode::Mailbox,
-                                    "Mailbox",
-                                );
-                                ui.selectable_value(
-                                    &mut self.graphics_tweaks.present_mode,
-                                    PresentMode::Immediate,
-                                    "Immediate",
-                                );
+                                for policy in PresentPolicy::ALL {
+                                    ui.selectable_value(
+                                        &mut self.graphics_tweaks.present_mode,
+                                        policy,
+                                        policy.name(),
+                                    );
+                                }
                             });
                         ui.end_row();
 
+                        ui.label("Present Mode in Use");
+                        ui.label(format!("{:?}", renderer.present_mode()))
+                            .on_hover_text(
+                            "Auto prefers Mailbox, then FifoRelaxed, then Fifo. Unsupported modes \
+                             fall back to Fifo",
+                        );
+                        ui.end_row();
+
                         ui.label("FPS Cap");
+                        ui.add(
+                            Slider::new(&mut self.graphics_tweaks.fps, GraphicsSettings::FPS_RANGE)
+                                .integer(),
+                        );
+                        ui.end_row();
+
+                        ui.label("Latency Wait");
+                        ui.checkbox(&mut self.graphics_tweaks.latency_wait, "")
+                            .on_hover_text("Sleep before input sampling (Fifo only)");
+                        ui.end_row();
+
+                        ui.label("Frames in Flight");
+                        ui.add(Slider::new(
+                            &mut self.graphics_tweaks.frames_in_flight,
+                            RenderMode::MIN_FRAMES_IN_FLIGHT..=RenderMode::MAX_FRAMES_IN_FLIGHT,
+                        ));
+                        ui.end_row();
+
+                        ui.label("Backend");
+                        ComboBox::from_id_source("backends")
+                            .selected_text(backend_name(self.graphics_tweaks.backends))
+                            .show_ui(ui, |ui| {
+                                for backends in [
+                                    Backends::PRIMARY,
+                                    Backends::VULKAN,
+                                    Backends::METAL,
+                                    Backends::DX12,
+                                    Backends::DX11,
+                                    Backends::GL,
+                                ] {
+                                    ui.selectable_value(
+                                        &mut self.graphics_tweaks.backends,
+                                        backends,
+                                        backend_name(backends),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Grass Density");
                         ui.add(
                             Slider::new(
-                                &mut self.graphics_tweaks.fps,
-                                Scene::FPS_MIN..=Scene::FPS_MAX,
+                                &mut self.graphics_tweaks.decoration_density,
+                                0..=RenderMode::MAX_DECORATION_DENSITY,
                             )
-                            .integer(),
+                            .suffix("%"),
                         );
                         ui.end_row();
+
+                        ui.label("Compute Meshing");
+                        ui.add_enabled(
+                            renderer.compute_supported(),
+                            Checkbox::new(&mut self.graphics_tweaks.compute_meshing, ""),
+                        )
+                        .on_hover_text("Build chunk meshes on the GPU (experimental)")
+                        .on_disabled_hover_text("Compute shaders aren't supported");
+                        ui.end_row();
                     });
 
                 ui.horizontal(|ui| {
@@ -279,106 +805,603 @@ impl DebugOverlayState {
                     }
                     if ui.button("Apply").clicked() {
                         renderer.set_render_mode(self.graphics_tweaks.as_render_mode());
-                        *fps = self.graphics_tweaks.fps;
+                        settings.graphics.fps = self.graphics_tweaks.fps;
+                        settings.graphics.present_mode = self.graphics_tweaks.present_mode;
+                        *latency_wait = self.graphics_tweaks.latency_wait;
+                        if let Err(err) = settings.save(SETTINGS_FILE) {
+                            warn!(%err, "Failed to save settings");
+                        }
+                    }
+                    if ui.button("Recreate Renderer").clicked() {
+                        renderer.request_recreate();
                     }
                 });
-            });
-
-        Window::new("Camera")
-            .open(&mut self.camera_opened)
-            .resizable(false)
-            .show(ctx, |ui| {
-                ui.collapsing("Tweaks", |ui| {
-                    Grid::new("camera_tweaks")
-                        .num_columns(2)
-                        .striped(true)
-                        .show(ui, |ui| {
-                            ui.label("Mode");
-                            ui.vertical(|ui| {
-                                if ui
-                                    .add(RadioButton::new(
-                                        matches!(camera.mode, CameraMode::FirstPerson { .. }),
-                                        "First Person",
-                                    ))
-                                    .clicked()
-                                {
-                                    camera.set_mode(CameraMode::FirstPerson);
-                                }
-                                if ui
-                                    .add(RadioButton::new(
-                                        matches!(camera.mode, CameraMode::ThirdPerson { .. }),
-                                        "Third Person",
-                                    ))
-                                    .clicked()
-                                {
-                                    camera.set_mode(CameraMode::ThirdPerson);
-                                }
-                            });
-                            ui.end_row();
 
-                            ui.checkbox(&mut camera.smooth_position, "Smooth position");
-                            ui.end_row();
+                ui.collapsing("Draw Stages", |ui| {
+                    let stages = &mut renderer.draw_stages;
+                    ui.checkbox(&mut stages.terrain, "Terrain");
+                    ui.checkbox(&mut stages.figures, "Figures");
+                    ui.checkbox(&mut stages.labels, "Labels");
+                    ui.checkbox(&mut stages.decorations, "Decorations");
+                    ui.checkbox(&mut stages.fluids, "Fluids");
+                    ui.checkbox(&mut stages.debug, "Debug geometry");
+                    ui.checkbox(&mut stages.hud, "HUD");
+                    if ui.button("Enable All").clicked() {
+                        *stages = DrawStages::new();
+                    }
+                });
 
-                            ui.checkbox(&mut camera.smooth_rotation, "Smooth rotation");
-                            ui.end_row();
+                ui.collapsing("Interface", |ui| {
+                    // Sliders are saved once released
+                    let saved = |response: Response| {
+                        response.drag_released() || response.changed() && !response.dragged()
+                    };
+                    let mut save = false;
 
-                            ui.label("FOV");
+                    Grid::new("interface").num_columns(2).show(ui, |ui| {
+                        ui.label("UI Scale");
+                        save |= saved(
                             ui.add(
-                                Slider::new(&mut camera.f_fov, Camera::MIN_FOV..=Camera::MAX_FOV)
-                                    .custom_formatter(|fov, _| {
-                                        format!("{:.1}° ({:.2})", fov.to_degrees(), fov)
-                                    }),
-                            );
-                            ui.end_row();
+                                Slider::new(&mut settings.ui.scale, UiSettings::SCALE_RANGE)
+                                    .step_by(0.05),
+                            ),
+                        );
+                        ui.end_row();
 
-                            ui.label("Z Near");
+                        ui.label("HUD Opacity");
+                        save |= saved(ui.add(Slider::new(
+                            &mut settings.ui.hud_opacity,
+                            UiSettings::HUD_OPACITY_RANGE,
+                        )));
+                        ui.end_row();
+
+                        ui.label("Large Crosshair");
+                        save |= ui.checkbox(&mut settings.ui.large_crosshair, "").changed();
+                        ui.end_row();
+
+                        ui.label("Font");
+                        ComboBox::from_id_source("font")
+                            .selected_text(settings.ui.font.as_deref().unwrap_or("Default"))
+                            .show_ui(ui, |ui| {
+                                save |= ui
+                                    .selectable_value(&mut settings.ui.font, None, "Default")
+                                    .changed();
+                                for font in renderer.assets().fonts() {
+                                    let label = font.clone();
+                                    save |= ui
+                                        .selectable_value(&mut settings.ui.font, Some(font), label)
+                                        .changed();
+                                }
+                            })
+                            .response
+                            .on_hover_text(format!("Fonts from {FONTS_DIR} and resource packs"));
+                        ui.end_row();
+
+                        ui.label("Font Size");
+                        save |= saved(
                             ui.add(
                                 Slider::new(
-                                    &mut camera.near,
-                                    Camera::MIN_Z_NEAR..=Camera::MAX_Z_NEAR,
+                                    &mut settings.ui.font_size,
+                                    UiSettings::FONT_SIZE_RANGE,
                                 )
-                                .max_decimals(3),
-                            );
-                            ui.end_row();
+                                .step_by(1.0),
+                            ),
+                        );
+                        ui.end_row();
 
-                            ui.label("Z Far");
-                            ui.add(
-                                Slider::new(&mut camera.far, Camera::MIN_Z_FAR..=Camera::MAX_Z_FAR)
-                                    .max_decimals(1),
+                        ui.label("Debug Colors");
+                        ComboBox::from_id_source("debug_palette")
+                            .selected_text(settings.ui.debug_palette.name())
+                            .show_ui(ui, |ui| {
+                                for palette in DebugPalette::ALL {
+                                    save |= ui
+                                        .selectable_value(
+                                            &mut settings.ui.debug_palette,
+                                            palette,
+                                            palette.name(),
+                                        )
+                                        .on_hover_text("Heatmap, frustum and chunk label colors")
+                                        .changed();
+                                }
+                            });
+                        ui.end_row();
+                    });
+
+                    if ui.button("Reset").clicked() {
+                        settings.ui = UiSettings::new();
+                        save = true;
+                    }
+                    if save {
+                        if let Err(err) = settings.save(SETTINGS_FILE) {
+                            warn!(%err, "Failed to save settings");
+                        }
+                    }
+                });
+
+                ui.collapsing("Threads", |ui| {
+                    let threads = &mut settings.threads;
+                    let mut save = false;
+
+                    // Automatic counts are shown, so switching to manual starts from them
+                    let mut thread_count = |ui: &mut Ui, count: &mut Option<usize>, auto: usize| {
+                        ui.horizontal(|ui| {
+                            let mut manual = count.is_some();
+                            if ui.checkbox(&mut manual, "Manual").changed() {
+                                *count = manual.then_some(auto);
+                                save = true;
+                            }
+                            let mut value = count.unwrap_or(auto);
+                            let response = ui.add_enabled(
+                                manual,
+                                Slider::new(&mut value, 1..=ThreadSettings::MAX_THREADS)
+                                    .logarithmic(true),
                             );
-                            ui.end_row();
+                            if response.changed() {
+                                *count = Some(value);
+                            }
+                            save |= response.drag_released()
+                                || response.changed() && !response.dragged();
                         });
-                });
-                ui.collapsing("Tracker", |ui| {
+                    };
+
+                    Grid::new("threads").num_columns(2).show(ui, |ui| {
+                        ui.label("Async")
+                            .on_hover_text("Networking. Applied after a restart");
+                        let auto = ThreadSettings::new().async_threads();
+                        thread_count(ui, &mut threads.async_threads, auto);
+                        ui.end_row();
+
+                        ui.label("Blocking")
+                            .on_hover_text("Chunk generation and meshing. Applied at once");
+                        let auto = ThreadSettings::new().blocking_threads();
+                        thread_count(ui, &mut threads.blocking_threads, auto);
+                        ui.end_row();
+                    });
+
+                    let (running, queued) = chunk_manager.jobs.jobs();
                     ui.label(format!(
-                        "Position: x:{:.3} y:{:.3} z:{:.3}\n\
-                        Yaw: {:.3} ({:.2})\n\
-                        Pitch: {:.3} ({:.2})\n\
-                        Distance: {:.2}\n\
-                        FOV: {:.3} {:.2}\n\
-                        {:#?}",
-                        camera.pos.x,
-                        camera.pos.y,
-                        camera.pos.z,
-                        camera.rot.x,
-                        camera.rot.x.to_degrees(),
-                        camera.rot.y,
-                        camera.rot.y.to_degrees(),
-                        camera.dist,
-                        camera.fov,
-                        camera.fov.to_degrees(),
-                        camera.mode
+                        "Jobs: {running} running, {queued} queued ({} workers)",
+                        chunk_manager.jobs.workers()
                     ));
+
+                    if save {
+                        if let Err(err) = settings.save(SETTINGS_FILE) {
+                            warn!(%err, "Failed to save settings");
+                        }
+                    }
                 });
-                ui.collapsing("Future Tracker", |ui| {
-                    ui.label(format!(
-                        "Position: x:{:.3} y:{:.3} z:{:.3}\n\
-                        Yaw: {:.3} ({:.2})\n\
-                        Pitch: {:.3} ({:.2})\n\
-                        Distance: {:.2}\n\
-                        FOV: {:.3} {:.2}\n",
-                        camera.f_pos.x,
-                        camera.f_pos.y,
+
+                if ui
+                    .button("Reload Settings")
+                    .on_hover_text(format!("Apply changes made to {SETTINGS_FILE}"))
+                    .clicked()
+                {
+                    match Settings::load(SETTINGS_FILE) {
+                        Ok(loaded) => *settings = loaded,
+                        Err(err) => warn!(%err, "Failed to reload settings"),
+                    }
+                }
+
+                ui.collapsing("Palette", |ui| {
+                    let palettes = &mut self.palettes;
+
+                    ui.horizontal(|ui| {
+                        ComboBox::from_id_source("palette")
+                            .selected_text(chunk_manager.palette().name())
+                            .show_ui(ui, |ui| {
+                                for palette in &palettes.palettes {
+                                    let selected = palette == chunk_manager.palette();
+                                    if ui.selectable_label(selected, palette.name()).clicked()
+                                        && !selected
+                                    {
+                                        chunk_manager.set_palette(palette.clone());
+                                        minimap.release_buffer();
+                                    }
+                                }
+                            });
+                        if ui
+                            .button("Rescan")
+                            .on_hover_text(format!("Load palettes from {PALETTES_DIR}"))
+                            .clicked()
+                        {
+                            palettes.scan();
+                        }
+                    });
+
+                    for error in &palettes.errors {
+                        ui.colored_label(error_color, error);
+                    }
+                });
+
+                ui.collapsing("Resource Packs", |ui| {
+                    let packs = &mut self.packs;
+
+                    if packs.entries.is_empty() {
+                        ui.label(format!("No packs in {PACKS_DIR}"));
+                    }
+                    // Later packs override earlier ones
+                    let mut swap = None;
+                    let count = packs.entries.len();
+                    for (i, (pack, enabled)) in packs.entries.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(enabled, pack.name())
+                                .on_hover_text(pack.description());
+                            if ui.add_enabled(i > 0, Button::new("⏶")).clicked() {
+                                swap = Some(i - 1);
+                            }
+                            if ui.add_enabled(i + 1 < count, Button::new("⏷")).clicked() {
+                                swap = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = swap {
+                        packs.entries.swap(i, i + 1);
+                    }
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Rescan").clicked() {
+                            packs.scan(renderer.assets());
+                        }
+                        if ui
+                            .button("Apply")
+                            .on_hover_text("Recreates the renderer to reload shaders")
+                            .clicked()
+                        {
+                            let assets = packs.assets();
+                            chunk_manager.set_palette(assets.palette());
+                            minimap.release_buffer();
+                            renderer.set_assets(assets);
+                        }
+                    });
+                });
+
+                ui.collapsing("Debug View", |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for view in DebugView::ALL {
+                            ui.radio_value(&mut renderer.debug_view, view, format!("{view:?}"));
+                        }
+                    });
+                });
+
+                ui.collapsing("Frame Capture", |ui| {
+                    let capture = &mut self.frame_capture;
+
+                    Grid::new("frame_capture")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Output");
+                            ui.horizontal(|ui| {
+                                ui.radio_value(&mut capture.pipe, false, "PNG");
+                                ui.radio_value(&mut capture.pipe, true, "Pipe");
+                            });
+                            ui.end_row();
+
+                            if capture.pipe {
+                                ui.label("Command");
+                                ui.text_edit_singleline(&mut capture.command);
+                            } else {
+                                ui.label("Directory");
+                                ui.text_edit_singleline(&mut capture.directory);
+                            }
+                            ui.end_row();
+
+                            ui.label("Every Nth frame");
+                            ui.add(DragValue::new(&mut capture.every).clamp_range(1..=600));
+                            ui.end_row();
+                        });
+
+                    match renderer.capture_stats() {
+                        Some(stats) => {
+                            ui.label(format!(
+                                "Captured: {} (dropped {})",
+                                stats.captured, stats.dropped
+                            ));
+                            if ui.button("Stop").clicked() {
+                                renderer.stop_capture();
+                            }
+                        }
+                        None => {
+                            if ui.button("Start").clicked() {
+                                capture.error = renderer
+                                    .start_capture(capture.settings())
+                                    .err()
+                                    .map(|err| err.to_string());
+                            }
+                        }
+                    }
+
+                    if let Some(error) = &capture.error {
+                        ui.colored_label(error_color, error);
+                    }
+                });
+            });
+
+        Window::new(tr.get("window.controls"))
+            .open(&mut self.controls_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut save = false;
+
+                ui.horizontal(|ui| {
+                    ui.label("Mouse Sensitivity");
+                    let response = ui.add(Slider::new(
+                        &mut settings.controls.sensitivity,
+                        ControlSettings::SENSITIVITY_RANGE,
+                    ));
+                    save |= response.drag_released() || response.changed() && !response.dragged();
+                });
+                ui.separator();
+
+                Grid::new("bindings").num_columns(2).show(ui, |ui| {
+                    for input in GameInput::ALL {
+                        ui.label(input.name());
+                        let text = match (*binding_capture, settings.bindings.binding(input)) {
+                            (Some((capturing, _)), _) if capturing == input => {
+                                "Press a key or button...".to_string()
+                            }
+                            (_, Some(binding)) => binding.name(),
+                            (_, None) => "Unbound".to_string(),
+                        };
+                        if ui.button(text).clicked() {
+                            *binding_capture = Some((input, self.bind_physical));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.checkbox(&mut self.bind_physical, "Bind by key position")
+                    .on_hover_text(
+                        "Same physical key on every keyboard layout (e.g. WASD on AZERTY)",
+                    );
+                ui.horizontal(|ui| {
+                    if binding_capture.is_some() && ui.button("Cancel").clicked() {
+                        *binding_capture = None;
+                    }
+                    if ui.button("Reset").clicked() {
+                        settings.bindings = KeyBindings::new();
+                        save = true;
+                    }
+                });
+                ui.label("Escape can't be rebound");
+
+                if save {
+                    if let Err(err) = settings.save(SETTINGS_FILE) {
+                        warn!(%err, "Failed to save settings");
+                    }
+                }
+            });
+
+        Window::new(tr.get("window.camera"))
+            .open(&mut self.camera_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.collapsing("Tweaks", |ui| {
+                    Grid::new("camera_tweaks")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Mode");
+                            ui.vertical(|ui| {
+                                if ui
+                                    .add(RadioButton::new(
+                                        matches!(camera.mode, CameraMode::FirstPerson { .. }),
+                                        "First Person",
+                                    ))
+                                    .clicked()
+                                {
+                                    camera.set_mode(CameraMode::FirstPerson);
+                                }
+                                if ui
+                                    .add(RadioButton::new(
+                                        matches!(camera.mode, CameraMode::ThirdPerson { .. }),
+                                        "Third Person",
+                                    ))
+                                    .clicked()
+                                {
+                                    camera.set_mode(CameraMode::ThirdPerson);
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label("Projection");
+                            ui.vertical(|ui| {
+                                if ui
+                                    .add(RadioButton::new(
+                                        camera.projection == Projection::Perspective,
+                                        "Perspective",
+                                    ))
+                                    .clicked()
+                                {
+                                    camera.projection = Projection::Perspective;
+                                }
+                                if ui
+                                    .add(RadioButton::new(
+                                        matches!(
+                                            camera.projection,
+                                            Projection::Orthographic { .. }
+                                        ),
+                                        "Orthographic",
+                                    ))
+                                    .clicked()
+                                {
+                                    camera.projection = Projection::Orthographic {
+                                        height: MapView::DEFAULT_ZOOM / 2.0,
+                                    };
+                                }
+                                if let Projection::Orthographic { height } = &mut camera.projection
+                                {
+                                    ui.add(
+                                        Slider::new(height, MapView::MIN_ZOOM..=MapView::MAX_ZOOM)
+                                            .logarithmic(true)
+                                            .suffix(" blocks"),
+                                    );
+                                }
+                            });
+                            ui.end_row();
+
+                            let mut enabled = survival.is_some();
+                            if ui
+                                .add_enabled(
+                                    player_id.is_none(),
+                                    Checkbox::new(&mut enabled, "Survival mode"),
+                                )
+                                .changed()
+                            {
+                                *survival = enabled.then(|| Survival::new(camera.f_pos));
+                            }
+                            ui.end_row();
+
+                            ui.checkbox(&mut camera.smooth_position, "Smooth position");
+                            ui.end_row();
+
+                            ui.checkbox(&mut camera.smooth_rotation, "Smooth rotation");
+                            ui.end_row();
+
+                            ui.label("FOV");
+                            ui.add(
+                                Slider::new(&mut camera.f_fov, Camera::MIN_FOV..=Camera::MAX_FOV)
+                                    .custom_formatter(|fov, _| {
+                                        format!("{:.1}° ({:.2})", fov.to_degrees(), fov)
+                                    }),
+                            );
+                            ui.end_row();
+
+                            ui.label("Z Near");
+                            ui.add(
+                                Slider::new(
+                                    &mut camera.near,
+                                    Camera::MIN_Z_NEAR..=Camera::MAX_Z_NEAR,
+                                )
+                                .max_decimals(3),
+                            );
+                            ui.end_row();
+
+                            ui.label("Z Far");
+                            ui.horizontal(|ui| {
+                                let mut manual = camera.far_override.is_some();
+                                if ui
+                                    .checkbox(&mut manual, "Manual")
+                                    .on_hover_text("Follows the draw distance otherwise")
+                                    .changed()
+                                {
+                                    camera.far_override = manual.then_some(camera.far);
+                                }
+                                let mut far = camera.far;
+                                if ui
+                                    .add_enabled(
+                                        manual,
+                                        Slider::new(
+                                            &mut far,
+                                            Camera::MIN_Z_FAR..=Camera::MAX_Z_FAR,
+                                        )
+                                        .max_decimals(1),
+                                    )
+                                    .changed()
+                                {
+                                    camera.far_override = Some(far);
+                                }
+                            });
+                            ui.end_row();
+                        });
+                });
+                ui.collapsing("Map", |ui| {
+                    ui.checkbox(&mut map.enabled, "Top-down map (M)");
+                    ui.horizontal(|ui| {
+                        ui.label("Zoom");
+                        ui.add(
+                            Slider::new(&mut map.zoom, MapView::MIN_ZOOM..=MapView::MAX_ZOOM)
+                                .logarithmic(true)
+                                .suffix(" blocks"),
+                        );
+                    });
+                });
+                ui.collapsing("Minimap", |ui| {
+                    ui.checkbox(&mut minimap.enabled, "Show minimap (N)");
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Zoom: {} blocks", minimap.zoom()));
+                        if ui.button("-").clicked() {
+                            minimap.zoom_out();
+                        }
+                        if ui.button("+").clicked() {
+                            minimap.zoom_in();
+                        }
+                    });
+                });
+                ui.collapsing("Frustum", |ui| {
+                    ui.checkbox(show_frustum, "Show frustum")
+                        .on_hover_text("Near plane is red, far plane is blue");
+                    ui.horizontal(|ui| {
+                        if frozen_frustum.is_some() {
+                            if ui.button("Unfreeze").clicked() {
+                                *frozen_frustum = None;
+                            }
+                        } else if ui.button("Freeze").clicked() {
+                            *frozen_frustum = Some((camera.proj_mat(), camera.view_mat()));
+                            *show_frustum = true;
+                        }
+                    });
+                });
+                ui.collapsing("Gizmos", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Shapes: {}", gizmos.len()));
+                        if ui.button("Clear").clicked() {
+                            gizmos.clear();
+                        }
+                    });
+                });
+                ui.collapsing("Inset View", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("View");
+                        ComboBox::from_id_source("inset_view")
+                            .selected_text(inset.view.map_or("Off", InsetView::name))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut inset.view, None, "Off");
+                                for view in InsetView::ALL {
+                                    ui.selectable_value(&mut inset.view, Some(view), view.name());
+                                }
+                            });
+                    })
+                    .response
+                    .on_hover_text("Terrain is culled for the main view");
+                    ui.horizontal(|ui| {
+                        ui.label("Size");
+                        ui.add(Slider::new(
+                            &mut inset.size,
+                            ViewInset::MIN_SIZE..=ViewInset::MAX_SIZE,
+                        ));
+                    });
+                });
+                ui.collapsing("Tracker", |ui| {
+                    ui.label(format!(
+                        "Position: x:{:.3} y:{:.3} z:{:.3}\n\
+                        Yaw: {:.3} ({:.2})\n\
+                        Pitch: {:.3} ({:.2})\n\
+                        Distance: {:.2}\n\
+                        FOV: {:.3} {:.2}\n\
+                        {:#?}",
+                        camera.pos.x,
+                        camera.pos.y,
+                        camera.pos.z,
+                        camera.rot.x,
+                        camera.rot.x.to_degrees(),
+                        camera.rot.y,
+                        camera.rot.y.to_degrees(),
+                        camera.dist,
+                        camera.fov,
+                        camera.fov.to_degrees(),
+                        camera.mode
+                    ));
+                });
+                ui.collapsing("Future Tracker", |ui| {
+                    ui.label(format!(
+                        "Position: x:{:.3} y:{:.3} z:{:.3}\n\
+                        Yaw: {:.3} ({:.2})\n\
+                        Pitch: {:.3} ({:.2})\n\
+                        Distance: {:.2}\n\
+                        FOV: {:.3} {:.2}\n",
+                        camera.f_pos.x,
+                        camera.f_pos.y,
                         camera.f_pos.z,
                         camera.f_rot.x,
                         camera.f_rot.x.to_degrees(),
@@ -391,7 +1414,7 @@ impl DebugOverlayState {
                 });
             });
 
-        Window::new("ChunkManager")
+        Window::new(tr.get("window.chunks"))
             .open(&mut self.chunks_opened)
             .resizable(false)
             .show(ctx, |ui| {
@@ -401,15 +1424,125 @@ impl DebugOverlayState {
                         .striped(true)
                         .show(ui, |ui| {
                             ui.label("Draw distance");
-                            ui.add(
-                                DragValue::new(&mut chunk_manager.draw_distance)
+                            let response = ui.add(
+                                DragValue::new(&mut settings.graphics.draw_distance)
                                     .fixed_decimals(0)
                                     .speed(1.0)
-                                    .clamp_range(
-                                        ChunkManager::MIN_DRAW_DISTANCE
-                                            ..=ChunkManager::MAX_DRAW_DISTANCE,
-                                    ),
+                                    .clamp_range(GraphicsSettings::DRAW_DISTANCE_RANGE),
                             );
+                            if response.drag_released() || response.changed() && !response.dragged()
+                            {
+                                if let Err(err) = settings.save(SETTINGS_FILE) {
+                                    warn!(%err, "Failed to save settings");
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label("Lookahead");
+                            ui.add(
+                                Slider::new(
+                                    &mut chunk_manager.lookahead,
+                                    0.0..=ChunkManager::MAX_LOOKAHEAD,
+                                )
+                                .suffix(" s"),
+                            )
+                            .on_hover_text("Preload chunks in the direction of travel");
+                            ui.end_row();
+
+                            ui.label("Vertical bias");
+                            let response = ui
+                                .add(Slider::new(
+                                    &mut settings.chunks.vertical_bias,
+                                    ChunkSettings::VERTICAL_BIAS_RANGE,
+                                ))
+                                .on_hover_text(
+                                    "Load more chunks towards the terrain surface and fewer of \
+                                     the sky or deep underground",
+                                );
+                            if response.drag_released() || response.changed() && !response.dragged()
+                            {
+                                if let Err(err) = settings.save(SETTINGS_FILE) {
+                                    warn!(%err, "Failed to save settings");
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label("Mesh memory budget");
+                            let mut budget_mib = chunk_manager.memory_budget / (1024 * 1024);
+                            if ui
+                                .add(
+                                    Slider::new(
+                                        &mut budget_mib,
+                                        ChunkManager::MIN_MEMORY_BUDGET / (1024 * 1024)
+                                            ..=ChunkManager::MAX_MEMORY_BUDGET / (1024 * 1024),
+                                    )
+                                    .logarithmic(true)
+                                    .suffix(" MiB"),
+                                )
+                                .changed()
+                            {
+                                chunk_manager.memory_budget = budget_mib * 1024 * 1024;
+                            }
+                            ui.end_row();
+
+                            ui.label("Upload budget");
+                            let mut budget_kib = chunk_manager.upload_budget / 1024;
+                            if ui
+                                .add(
+                                    Slider::new(
+                                        &mut budget_kib,
+                                        ChunkManager::MIN_UPLOAD_BUDGET / 1024
+                                            ..=ChunkManager::MAX_UPLOAD_BUDGET / 1024,
+                                    )
+                                    .logarithmic(true)
+                                    .suffix(" KiB/frame"),
+                                )
+                                .changed()
+                            {
+                                chunk_manager.upload_budget = budget_kib * 1024;
+                            }
+                            ui.end_row();
+
+                            ui.label("Uploads");
+                            ui.label(format!(
+                                "{} KiB ({} pending)",
+                                chunk_manager.uploaded() / 1024,
+                                chunk_manager.pending_uploads()
+                            ));
+                            ui.end_row();
+
+                            ui.checkbox(show_chunk_labels, "Chunk labels");
+                            ui.end_row();
+
+                            ui.label("Heatmap");
+                            ComboBox::from_id_source("chunk_heatmap")
+                                .selected_text(
+                                    chunk_heatmap.metric.map_or("Off", |metric| metric.name()),
+                                )
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut chunk_heatmap.metric, None, "Off");
+                                    for metric in ChunkMetric::ALL {
+                                        ui.selectable_value(
+                                            &mut chunk_heatmap.metric,
+                                            Some(metric),
+                                            metric.name(),
+                                        );
+                                    }
+                                })
+                                .response
+                                .on_hover_text(
+                                    "Outline meshed chunks from green (low) to red (highest)",
+                                );
+                            ui.end_row();
+
+                            if let Some(metric) = chunk_heatmap.metric {
+                                ui.label("Heatmap max");
+                                ui.label(metric.format(chunk_heatmap.max()));
+                                ui.end_row();
+                            }
+
+                            ui.checkbox(&mut chunk_manager.frozen, "Freeze streaming")
+                                .on_hover_text("Stop loading, unloading and remeshing chunks");
                             ui.end_row();
 
                             if ui.button("Clear Mesh").clicked() {
@@ -430,20 +1563,302 @@ impl DebugOverlayState {
                         .num_columns(2)
                         .striped(true)
                         .show(ui, |ui| {
-                            let ChunkManager { logic, terrain, .. } = chunk_manager;
+                            let chunks = &chunk_manager.chunks;
 
                             ui.label("Logic Chunks:");
-                            ui.label(format!("{} ({})", logic.len(), logic.capacity()));
+                            ui.label(format!("{} ({})", chunks.len(), chunks.capacity()));
                             ui.end_row();
 
                             ui.label("Terrain Chunks:");
-                            ui.label(format!("{} ({})", terrain.len(), terrain.capacity()));
+                            ui.label(format!("{}", chunks.mesh_count()));
+                            ui.end_row();
+                        });
+                });
+
+                ui.collapsing("Inspector", |ui| {
+                    let inspector = &mut self.chunk_inspector;
+
+                    ui.horizontal(|ui| {
+                        ui.add(DragValue::new(&mut inspector.chunk_id.x).prefix("x: "));
+                        ui.add(DragValue::new(&mut inspector.chunk_id.y).prefix("y: "));
+                        ui.add(DragValue::new(&mut inspector.chunk_id.z).prefix("z: "));
+                        if ui.button("Current").clicked() {
+                            inspector.chunk_id = GlobalCoord::from_vec3(camera.pos).to_chunk_id();
+                        }
+                    });
+
+                    let id = inspector.chunk_id;
+                    let Some(chunk) = chunk_manager.chunks.get(&id) else {
+                        ui.label("Chunk isn't loaded");
+                        return;
+                    };
+
+                    Grid::new("chunk_inspector_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Status:");
+                            ui.label(format!("{:?}", chunk.status()));
                             ui.end_row();
+
+                            let terrain = chunk.terrain();
+
+                            ui.label("Vertices:");
+                            ui.label(format!(
+                                "{}",
+                                terrain.map_or(0, |terrain| terrain.slice.vertices.len())
+                            ));
+                            ui.end_row();
+
+                            ui.label("Indices:");
+                            ui.label(format!(
+                                "{}",
+                                terrain.map_or(0, |terrain| terrain.slice.indices.len())
+                            ));
+                            ui.end_row();
+
+                            ui.label("Last remesh:");
+                            ui.label(terrain.map_or("Never".to_string(), |terrain| {
+                                format!("{:.1}s ago", terrain.built_at.elapsed().as_secs_f32())
+                            }));
+                            ui.end_row();
+                        });
+
+                    ui.label("Blocks:");
+                    Grid::new("chunk_inspector_histogram")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            chunk.histogram().into_iter().for_each(|(block, count)| {
+                                ui.label(format!("{block:?}"));
+                                ui.label(format!("{count}"));
+                                ui.end_row();
+                            });
+                        });
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Remesh").clicked() {
+                            chunk_manager.remesh(id);
+                        }
+                        if ui.button("Dump").clicked() {
+                            let path = format!("chunk_{}_{}_{}.txt", id.x, id.y, id.z);
+                            inspector.message = Some(match chunk_manager.dump(id, &path) {
+                                Ok(()) => format!("Saved to {path}"),
+                                Err(err) => {
+                                    warn!(%err, "Failed to dump chunk");
+                                    format!("Failed to save: {err}")
+                                }
+                            });
+                        }
+                    });
+
+                    if let Some(message) = &inspector.message {
+                        ui.label(message);
+                    }
+                });
+            });
+
+        Window::new(tr.get("window.entities"))
+            .open(&mut self.entities_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("entities_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Interpolation delay");
+                        let mut delay = remote_entities.interp_delay.as_millis() as u64;
+                        if ui
+                            .add(
+                                Slider::new(
+                                    &mut delay,
+                                    0..=RemoteEntities::MAX_INTERP_DELAY.as_millis() as u64,
+                                )
+                                .suffix("ms"),
+                            )
+                            .changed()
+                        {
+                            remote_entities.interp_delay = Duration::from_millis(delay);
+                        }
+                        ui.end_row();
+
+                        ui.label("Remote entities:");
+                        ui.label(format!("{}", remote_entities.inner.len()));
+                        ui.end_row();
+
+                        ui.label("Local entities:");
+                        ui.label(format!("{}", entities.len()));
+                        ui.end_row();
+
+                        ui.label("Show paths");
+                        ui.checkbox(show_paths, "");
+                        ui.end_row();
+
+                        ui.label("Mob spawning");
+                        ui.checkbox(
+                            &mut rules.mob_spawning,
+                            format!("{}/{}", spawner.count(), MobSpawner::MAX_MOBS),
+                        )
+                        .on_hover_text("Ambient mobs spawn around the camera at night");
+                        ui.end_row();
+
+                        ui.label("Label distance");
+                        ui.add(
+                            Slider::new(&mut labels.max_distance, 1.0..=128.0).suffix(" blocks"),
+                        );
+                        labels.fade_distance = labels.max_distance * 0.75;
+                        ui.end_row();
+                    });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Spawn mob").clicked() {
+                        let spawned =
+                            entities.spawn_mob(camera.pos, |pos| chunk_manager.solid(pos));
+                        if spawned.is_none() {
+                            warn!("No ground to spawn mob on");
+                        }
+                    }
+                    if ui.button("Despawn mobs").clicked() {
+                        let mobs = entities
+                            .iter()
+                            .filter(|(_, components)| components.wander.is_some())
+                            .map(|(id, _)| id)
+                            .collect::<Vec<_>>();
+                        mobs.into_iter().for_each(|id| {
+                            entities.despawn(id);
                         });
+                    }
                 });
             });
 
-        Window::new("Painter")
+        Window::new(tr.get("window.network"))
+            .open(&mut self.network_opened)
+            .resizable(false)
+            .show(ctx, |ui| match net {
+                Some(net) => {
+                    let stats = net.stats();
+
+                    Grid::new("network_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Server:");
+                            ui.label(net.addr().to_string());
+                            ui.end_row();
+
+                            ui.label("Ping:");
+                            ui.label(match stats.ping {
+                                Some(ping) => format!("{}ms", ping.as_millis()),
+                                None => "-".to_string(),
+                            });
+                            ui.end_row();
+
+                            ui.label("Packet loss:");
+                            ui.label(format!("{:.1}%", stats.packet_loss * 100.0));
+                            ui.end_row();
+
+                            ui.label("In:");
+                            ui.label(format!(
+                                "{:.1} KiB/s ({} messages)",
+                                stats.bytes_in_per_sec as f32 / 1024.0,
+                                stats.traffic.messages_received,
+                            ));
+                            ui.end_row();
+
+                            ui.label("Out:");
+                            ui.label(format!(
+                                "{:.1} KiB/s ({} messages)",
+                                stats.bytes_out_per_sec as f32 / 1024.0,
+                                stats.traffic.messages_sent,
+                            ));
+                            ui.end_row();
+
+                            ui.label("Compression ratio:");
+                            ui.label(format!(
+                                "in {:.2} / out {:.2}",
+                                stats.traffic.received_ratio(),
+                                stats.traffic.sent_ratio(),
+                            ));
+                            ui.end_row();
+
+                            ui.label("Queued chunk requests:");
+                            ui.label(format!("{}", chunk_manager.pending_chunks()));
+                            ui.end_row();
+
+                            ui.label("Entities:");
+                            ui.label(format!("{}", remote_entities.inner.len()));
+                            ui.end_row();
+                        });
+                }
+                None => {
+                    ui.label("Not connected");
+                }
+            });
+
+        Window::new(tr.get("window.stats"))
+            .open(&mut self.stats_opened)
+            .resizable(false)
+            .show(ctx, |ui| match net {
+                Some(net) => {
+                    match world_stats {
+                        Some(stats) => {
+                            Grid::new("stats_grid")
+                                .num_columns(2)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label("Blocks placed:");
+                                    ui.label(stats.blocks_placed.to_string());
+                                    ui.end_row();
+
+                                    ui.label("Blocks broken:");
+                                    ui.label(stats.blocks_broken.to_string());
+                                    ui.end_row();
+
+                                    ui.label("Distance traveled:");
+                                    ui.label(format!("{:.0} blocks", stats.distance));
+                                    ui.end_row();
+
+                                    let minutes = stats.playtime.as_secs() / 60;
+                                    ui.label("Playtime:");
+                                    ui.label(format!("{}h {:02}m", minutes / 60, minutes % 60));
+                                    ui.end_row();
+                                });
+                        }
+                        None => {
+                            ui.label("Waiting for the server...");
+                        }
+                    }
+
+                    ui.label("Totals of all players of the server world");
+
+                    ui.separator();
+                    match world_backups {
+                        Some(names) if names.is_empty() => {
+                            ui.label("No backups");
+                        }
+                        Some(names) => {
+                            ui.label(format!("Backups ({}):", names.len()));
+                            ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+                                names.iter().rev().for_each(|name| {
+                                    ui.monospace(name);
+                                });
+                            });
+                            ui.label("Restore by restarting the server with --restore <name>");
+                        }
+                        None => {
+                            ui.label("Waiting for the server...");
+                        }
+                    }
+                    if ui.button("Refresh").clicked() {
+                        net.send(ClientMsg::RequestStats);
+                    }
+                }
+                None => {
+                    ui.label("Statistics are kept by the server. Not connected");
+                }
+            });
+
+        Window::new(tr.get("window.painter"))
             .open(&mut self.painter_opened)
             .resizable(false)
             .show(ctx, |ui| {
@@ -461,14 +1876,12 @@ impl DebugOverlayState {
                             ui.label("Block Changer");
 
                             if ui.button("Set").clicked() {
-                                if let Some(chunk) = chunk_manager
-                                    .logic
-                                    .get_mut(&self.painter.block_pos.to_chunk_id())
-                                {
-                                    chunk.blocks_mut()
-                                        [self.painter.block_pos.to_block().flatten()] =
-                                        Block::from(self.painter.block);
-                                }
+                                block_edits.edit(
+                                    chunk_manager,
+                                    net.as_ref(),
+                                    self.painter.block_pos,
+                                    Block::from(self.painter.block),
+                                );
                             }
                         });
 
@@ -495,53 +1908,307 @@ impl DebugOverlayState {
                     });
                 });
 
-                ui.group(|ui| {
-                    ui.vertical(|ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("Chunk Filler");
-                            if ui.button("Fill").clicked() {
-                                if let Some(chunk) =
-                                    chunk_manager.logic.get_mut(&self.painter.chunk_id)
-                                {
-                                    *chunk.blocks_mut() =
-                                        [Block::from(self.painter.block); CHUNK_CUBE];
-                                }
+                ui.group(|ui| {
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Chunk Filler");
+                            // Chunk filling isn't routed through the server
+                            if ui.add_enabled(net.is_none(), Button::new("Fill")).clicked() {
+                                let origin = self.painter.chunk_id.to_coord();
+                                let block = Block::from(self.painter.block);
+
+                                block_edits.begin_action();
+                                for x in 0..G_CHUNK_SIZE {
+                                    for y in 0..G_CHUNK_SIZE {
+                                        for z in 0..G_CHUNK_SIZE {
+                                            block_edits.edit(
+                                                chunk_manager,
+                                                None,
+                                                GlobalCoord::new(
+                                                    origin.x + x,
+                                                    origin.y + y,
+                                                    origin.z + z,
+                                                ),
+                                                block,
+                                            );
+                                        }
+                                    }
+                                }
+                                block_edits.end_action();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                DragValue::new(&mut self.painter.chunk_id.x)
+                                    .prefix("x: ")
+                                    .fixed_decimals(0)
+                                    .speed(1.0),
+                            );
+                            ui.add(
+                                DragValue::new(&mut self.painter.chunk_id.y)
+                                    .prefix("y: ")
+                                    .fixed_decimals(0)
+                                    .speed(1.0),
+                            );
+                            ui.add(
+                                DragValue::new(&mut self.painter.chunk_id.z)
+                                    .prefix("z: ")
+                                    .fixed_decimals(0)
+                                    .speed(1.0),
+                            );
+                        });
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Camera Position").clicked() {
+                        let pos = GlobalCoord::from_vec3(camera.pos.floor());
+                        self.painter.block_pos = pos;
+                        self.painter.chunk_id = pos.to_chunk_id();
+                    }
+                    if ui
+                        .add_enabled(picked.is_some(), Button::new("Picked Block"))
+                        .clicked()
+                    {
+                        if let Some(hit) = picked {
+                            self.painter.block_pos = hit.pos;
+                            self.painter.chunk_id = hit.pos.to_chunk_id();
+                        }
+                    }
+                    if ui.button("Reset").clicked() {
+                        self.painter = Painter::new();
+                    }
+                });
+                ui.checkbox(&mut selection.enabled, "Outline picked block");
+
+                ui.horizontal(|ui| {
+                    let history = block_edits.history();
+                    ui.label(format!(
+                        "History: {} / {}",
+                        history.undo_len(),
+                        history.redo_len()
+                    ));
+
+                    if ui
+                        .add_enabled(history.undo_len() > 0, Button::new("Undo"))
+                        .clicked()
+                    {
+                        block_edits.undo(chunk_manager, net.as_ref());
+                    }
+                    if ui
+                        .add_enabled(block_edits.history().redo_len() > 0, Button::new("Redo"))
+                        .clicked()
+                    {
+                        block_edits.redo(chunk_manager, net.as_ref());
+                    }
+                });
+            });
+
+        Window::new(tr.get("window.schematic"))
+            .open(&mut self.schematic_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let tool = &mut self.schematic_tool;
+
+                Grid::new("schematic").num_columns(5).show(ui, |ui| {
+                    for (label, coord) in [
+                        ("Corner A", &mut tool.first),
+                        ("Corner B", &mut tool.second),
+                    ] {
+                        ui.label(label);
+                        drag_coord(ui, coord);
+                        if ui
+                            .add_enabled(picked.is_some(), Button::new("Picked Block"))
+                            .clicked()
+                        {
+                            if let Some(hit) = picked {
+                                *coord = hit.pos;
+                            }
+                        }
+                        ui.end_row();
+                    }
+
+                    ui.label("Target");
+                    drag_coord(ui, &mut tool.target);
+                    // Structure is placed in front of the picked face
+                    if ui
+                        .add_enabled(picked.is_some(), Button::new("Picked Face"))
+                        .clicked()
+                    {
+                        if let Some(hit) = picked {
+                            tool.target = hit.face.map_or(hit.pos, |face| hit.pos.neighbor(face));
+                        }
+                    }
+                    ui.end_row();
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Copy").clicked() {
+                        tool.message = Some(
+                            match Schematic::copy(tool.first, tool.second, |pos| {
+                                chunk_manager.block(pos)
+                            }) {
+                                Some(schematic) => {
+                                    let message = format!("Copied {} blocks", schematic.volume());
+                                    tool.schematic = Some(schematic);
+                                    message
+                                }
+                                None => format!(
+                                    "Region isn't loaded or exceeds {} blocks",
+                                    Schematic::MAX_VOLUME
+                                ),
+                            },
+                        );
+                    }
+                    if ui
+                        .add_enabled(tool.schematic.is_some(), Button::new("Paste"))
+                        .clicked()
+                    {
+                        if let Some(schematic) = &tool.schematic {
+                            let mut skipped = 0;
+
+                            block_edits.begin_action();
+                            for (offset, block) in schematic.iter() {
+                                if tool.skip_air && block == Block::Air {
+                                    continue;
+                                }
+                                let pos = GlobalCoord::new(
+                                    tool.target.x + offset.x,
+                                    tool.target.y + offset.y,
+                                    tool.target.z + offset.z,
+                                );
+                                if !block_edits.edit(chunk_manager, net.as_ref(), pos, block) {
+                                    skipped += 1;
+                                }
+                            }
+                            block_edits.end_action();
+
+                            tool.message = Some(format!(
+                                "Pasted {} blocks ({skipped} skipped)",
+                                schematic.volume()
+                            ));
+                        }
+                    }
+                    ui.add(Checkbox::new(&mut tool.skip_air, "Skip Air"));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Path:");
+                    ui.text_edit_singleline(&mut tool.path);
+                });
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(tool.schematic.is_some(), Button::new("Save"))
+                        .clicked()
+                    {
+                        if let Some(schematic) = &tool.schematic {
+                            tool.message = Some(match schematic.save(&tool.path) {
+                                Ok(()) => format!("Saved to {}", tool.path),
+                                Err(err) => err.to_string(),
+                            });
+                        }
+                    }
+                    if ui.button("Load").clicked() {
+                        tool.message = Some(match Schematic::load(&tool.path) {
+                            Ok(schematic) => {
+                                let size = schematic.size();
+                                tool.schematic = Some(schematic);
+                                format!("Loaded {}x{}x{} structure", size.x, size.y, size.z)
+                            }
+                            Err(err) => err.to_string(),
+                        });
+                    }
+                });
+
+                match &tool.schematic {
+                    Some(schematic) => {
+                        let size = schematic.size();
+                        ui.label(format!("Clipboard: {}x{}x{}", size.x, size.y, size.z));
+                    }
+                    None => {
+                        ui.label("Clipboard is empty");
+                    }
+                }
+                if let Some(message) = &tool.message {
+                    ui.label(message);
+                }
+            });
+
+        Window::new(tr.get("window.map_export"))
+            .open(&mut self.map_export_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let export = &mut self.map_export;
+                if let Some(message) = export.pending.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                    export.message = Some(message);
+                    export.pending = None;
+                }
+
+                Grid::new("map_export").num_columns(3).show(ui, |ui| {
+                    for (label, (x, z)) in [
+                        ("Corner A", &mut export.first),
+                        ("Corner B", &mut export.second),
+                    ] {
+                        ui.label(label);
+                        ui.add(DragValue::new(x).prefix("x: ").speed(1.0));
+                        ui.add(DragValue::new(z).prefix("z: ").speed(1.0));
+                        ui.end_row();
+                    }
+                });
+
+                if ui.button("Around Camera").clicked() {
+                    let half = MapExport::DEFAULT_SIZE / 2;
+                    let center = GlobalCoord::from_vec3(camera.f_pos);
+                    export.first = (center.x - half, center.z - half);
+                    export.second = (center.x + half - 1, center.z + half - 1);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Path:");
+                    ui.text_edit_singleline(&mut export.path);
+                });
+
+                if ui
+                    .add_enabled(export.pending.is_none(), Button::new("Export"))
+                    .clicked()
+                {
+                    // Terrain is generated again, so it takes a while and doesn't include edits
+                    let headless = Headless::default().with_bounds(chunk_manager.bounds);
+                    let (first, second, path) = (export.first, export.second, export.path.clone());
+                    let (tx, rx) = channel();
+                    chunk_manager.jobs.spawn(runtime, move || {
+                        let message = match headless.color_map(first, second) {
+                            Some(map) => match map.save(&path) {
+                                Ok(()) => format!("Exported {}x{} map", map.width, map.height),
+                                Err(err) => err.to_string(),
+                            },
+                            None => {
+                                format!("Map side exceeds the limit of {}", Headless::MAX_MAP_SIZE)
                             }
-                        });
-                        ui.horizontal(|ui| {
-                            ui.add(
-                                DragValue::new(&mut self.painter.chunk_id.x)
-                                    .prefix("x: ")
-                                    .fixed_decimals(0)
-                                    .speed(1.0),
-                            );
-                            ui.add(
-                                DragValue::new(&mut self.painter.chunk_id.y)
-                                    .prefix("y: ")
-                                    .fixed_decimals(0)
-                                    .speed(1.0),
-                            );
-                            ui.add(
-                                DragValue::new(&mut self.painter.chunk_id.z)
-                                    .prefix("z: ")
-                                    .fixed_decimals(0)
-                                    .speed(1.0),
-                            );
-                        });
+                        };
+                        let _ = tx.send(message);
                     });
-                });
+                    export.pending = Some(rx);
+                    export.message = Some("Exporting...".to_string());
+                }
 
-                // TODO: Add button to set position to camera
-                if ui.button("Reset").clicked() {
-                    self.painter = Painter::new();
+                if let Some(message) = &export.message {
+                    ui.label(message);
                 }
             });
 
-        Window::new("Teleport")
+        Window::new(tr.get("window.teleport"))
             .open(&mut self.teleport_opened)
             .resizable(false)
             .show(ctx, |ui| {
-                Grid::new("teleport").num_columns(3).show(ui, |ui| {
+                // Server owns player position in multiplayer
+                ui.set_enabled(net.is_none());
+
+                let mut target = None;
+
+                Grid::new("teleport").num_columns(4).show(ui, |ui| {
+                    ui.label("Position");
                     ui.add(
                         DragValue::new(&mut self.teleport.target_pos.x)
                             .prefix("x: ")
@@ -560,40 +2227,467 @@ impl DebugOverlayState {
                             .fixed_decimals(0)
                             .speed(1.0),
                     );
+                    if ui.button("Go").clicked() {
+                        target = Some(self.teleport.target_pos.as_vec());
+                    }
+                    ui.end_row();
+
+                    ui.label("Chunk");
+                    ui.add(DragValue::new(&mut self.teleport.chunk_id.x).prefix("x: "));
+                    ui.add(DragValue::new(&mut self.teleport.chunk_id.y).prefix("y: "));
+                    ui.add(DragValue::new(&mut self.teleport.chunk_id.z).prefix("z: "));
+                    if ui.button("Go").clicked() {
+                        // Center of the chunk
+                        target = Some(
+                            self.teleport.chunk_id.to_coord().as_vec() + CHUNK_SIZE as f32 / 2.0,
+                        );
+                    }
                     ui.end_row();
+                });
 
+                ui.horizontal(|ui| {
                     if ui.button("Reset").clicked() {
-                        self.teleport = Teleport::new();
+                        self.teleport.target_pos = GlobalCoord::ZERO;
+                        self.teleport.chunk_id = ChunkId::ZERO;
                     }
                     if ui.button("Player Position").clicked() {
-                        self.teleport.target_pos = GlobalCoord::from_vec3(camera.pos);
+                        self.teleport.target_pos = GlobalCoord::from_vec3(camera.pos.floor());
+                        self.teleport.chunk_id = self.teleport.target_pos.to_chunk_id();
+                    }
+                    if ui.button("Spawn").clicked() {
+                        target = Some(
+                            survival
+                                .as_ref()
+                                .map_or(Camera::DEFAULT_POSITION, |survival| survival.spawn),
+                        );
+                    }
+                });
+
+                if !self.teleport.history.is_empty() {
+                    ui.separator();
+                    ui.label("History");
+                    for pos in self.teleport.history.iter() {
+                        if ui
+                            .button(format!("{:.0}, {:.0}, {:.0}", pos.x, pos.y, pos.z))
+                            .clicked()
+                        {
+                            target = Some(*pos);
+                        }
+                    }
+                }
+
+                if let Some(target) = target {
+                    self.teleport.push_history(camera.f_pos);
+                    camera.f_pos = target;
+                    if let Some(survival) = survival {
+                        survival.reset_fall();
+                    }
+                }
+            });
+
+        Window::new(tr.get("window.simulation"))
+            .open(&mut self.simulation_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("simulation_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Tick rate");
+                        ui.label(format!(
+                            "{:.1} TPS",
+                            SimClock::TICK_RATE as f32 * sim.scale()
+                        ));
+                        ui.end_row();
+
+                        ui.label("Steps");
+                        ui.label(sim.steps().to_string());
+                        ui.end_row();
+
+                        ui.label("Speed");
+                        let mut scale = sim.scale();
+                        if ui
+                            .add(
+                                Slider::new(&mut scale, SimClock::MIN_SCALE..=SimClock::MAX_SCALE)
+                                    .logarithmic(true)
+                                    .suffix("x"),
+                            )
+                            .changed()
+                        {
+                            sim.set_scale(scale);
+                        }
+                        ui.end_row();
+                    });
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(if sim.paused {
+                            "Resume (F6)"
+                        } else {
+                            "Pause (F6)"
+                        })
+                        .clicked()
+                    {
+                        sim.toggle_pause();
+                    }
+                    if ui.button("Step (F7)").clicked() {
+                        sim.step(1);
+                    }
+                    if ui.button("Step 10").clicked() {
+                        sim.step(10);
                     }
-                    if ui.button("Teleport").clicked() {
-                        camera.f_pos = self.teleport.target_pos.as_vec();
+                    if ui.button("Reset Speed").clicked() {
+                        sim.set_scale(1.0);
                     }
                 });
+
+                if player_id.is_some() {
+                    ui.label("Server controls the simulation while connected");
+                }
+            });
+
+        Window::new(tr.get("window.environment"))
+            .open(&mut self.environment_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("environment_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Sky color");
+                        let mut color = environment.sky_color.to_array();
+                        if ui.color_edit_button_rgb(&mut color).changed() {
+                            environment.sky_color = F32x3::from(color);
+                        }
+                        ui.end_row();
+
+                        ui.label("Fog start");
+                        ui.add(Slider::new(&mut environment.fog_start, 0.0..=1024.0));
+                        ui.end_row();
+
+                        ui.label("Fog end");
+                        ui.add(Slider::new(&mut environment.fog_end, 0.0..=1024.0));
+                        environment.fog_end = environment.fog_end.max(environment.fog_start);
+                        ui.end_row();
+
+                        ui.label("Sun azimuth");
+                        ui.drag_angle(&mut environment.sun_azimuth);
+                        ui.end_row();
+
+                        ui.label("Light intensity");
+                        ui.add(Slider::new(&mut environment.light_intensity, 0.0..=2.0));
+                        ui.end_row();
+
+                        ui.label("Ambient light");
+                        ui.add(Slider::new(&mut environment.ambient, 0.0..=1.0));
+                        ui.end_row();
+                    });
+
+                if ui.button("Reset").clicked() {
+                    *environment = Environment::default();
+                }
+
+                ui.separator();
+                ui.add_enabled_ui(net.is_none(), |ui| {
+                    Grid::new("sky_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Time of day");
+                            let mut hours = sky.time.hours();
+                            if ui
+                                .add(
+                                    Slider::new(&mut hours, 0.0..=TimeOfDay::HOURS)
+                                        .custom_formatter(|hours, _| {
+                                            TimeOfDay::new(hours as f32).to_string()
+                                        }),
+                                )
+                                .changed()
+                            {
+                                sky.time = TimeOfDay::new(hours);
+                            }
+                            ui.end_row();
+
+                            ui.label("Freeze time");
+                            ui.checkbox(&mut sky.frozen, "");
+                            ui.end_row();
+
+                            ui.label("Weather");
+                            ComboBox::from_id_source("weather")
+                                .selected_text(sky.weather.name())
+                                .show_ui(ui, |ui| {
+                                    for weather in Weather::ALL {
+                                        ui.selectable_value(
+                                            &mut sky.weather,
+                                            weather,
+                                            weather.name(),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+                        });
+                });
+                if net.is_some() {
+                    ui.label("Server controls time and weather while connected");
+                }
+            });
+
+        Window::new(tr.get("window.inspector"))
+            .open(&mut self.inspector_opened)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let Some(hit) = picked else {
+                    ui.label(format!("No block within {} blocks", Scene::PICK_DISTANCE));
+                    return;
+                };
+
+                Grid::new("inspector_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let chunk_id = hit.pos.to_chunk_id();
+                        let chunk = chunk_manager.chunks.get(&chunk_id);
+
+                        ui.label("Position:");
+                        ui.label(format!("{}, {}, {}", hit.pos.x, hit.pos.y, hit.pos.z));
+                        ui.end_row();
+
+                        ui.label("Chunk:");
+                        ui.label(format!("{}, {}, {}", chunk_id.x, chunk_id.y, chunk_id.z));
+                        ui.end_row();
+
+                        let block_coord = hit.pos.to_block();
+                        ui.label("Block coord:");
+                        ui.label(format!(
+                            "{}, {}, {}",
+                            block_coord.x, block_coord.y, block_coord.z
+                        ));
+                        ui.end_row();
+
+                        ui.label("Block:");
+                        ui.label(format!("{:?}", chunk_manager.block(hit.pos)));
+                        ui.end_row();
+
+                        ui.label("Face:");
+                        ui.label(format!("{:?}", hit.face));
+                        ui.end_row();
+
+                        ui.label("Distance:");
+                        ui.label(format!("{:.2}", hit.distance));
+                        ui.end_row();
+
+                        ui.label("Chunk status:");
+                        ui.label(format!("{:?}", chunk.map(|chunk| chunk.status())));
+                        ui.end_row();
+
+                        ui.label("Chunk mesh:");
+                        ui.label(if chunk.and_then(LogicChunk::terrain).is_some() {
+                            "Uploaded"
+                        } else {
+                            "None"
+                        });
+                        ui.end_row();
+                    });
             });
+
+        if let Some(locale) = new_locale {
+            self.locale = locale;
+        }
     }
 }
 
 pub struct GraphicsTweaks {
     fps: u32,
-    present_mode: PresentMode,
+    latency_wait: bool,
+    present_mode: PresentPolicy,
+    frames_in_flight: u32,
+    backends: Backends,
+    compute_meshing: bool,
+    decoration_density: u32,
 }
 
 impl GraphicsTweaks {
     pub const fn new() -> Self {
         Self {
-            fps: Scene::FPS_DEFAULT,
+            fps: GraphicsSettings::new().fps,
+            latency_wait: false,
             present_mode: RenderMode::new().present_mode,
+            frames_in_flight: RenderMode::new().frames_in_flight,
+            backends: RenderMode::new().backends,
+            compute_meshing: RenderMode::new().compute_meshing,
+            decoration_density: RenderMode::new().decoration_density,
+        }
+    }
+
+    /// Values in use
+    pub fn current(
+        render_mode: &RenderMode,
+        graphics: &GraphicsSettings,
+        latency_wait: bool,
+    ) -> Self {
+        Self {
+            fps: graphics.fps,
+            latency_wait,
+            present_mode: graphics.present_mode,
+            frames_in_flight: render_mode.frames_in_flight,
+            backends: render_mode.backends,
+            compute_meshing: render_mode.compute_meshing,
+            decoration_density: render_mode.decoration_density,
         }
     }
 
     pub fn as_render_mode(&self) -> RenderMode {
         RenderMode {
             present_mode: self.present_mode,
+            frames_in_flight: self.frames_in_flight,
+            backends: self.backends,
+            compute_meshing: self.compute_meshing,
+            decoration_density: self.decoration_density,
+        }
+    }
+}
+
+fn backend_name(backends: Backends) -> &'static str {
+    match backends {
+        Backends::PRIMARY => "Primary",
+        Backends::VULKAN => "Vulkan",
+        Backends::METAL => "Metal",
+        Backends::DX12 => "DirectX 12",
+        Backends::DX11 => "DirectX 11",
+        Backends::GL => "OpenGL",
+        _ => "Custom",
+    }
+}
+
+/// Palettes available in the settings
+pub struct PaletteList {
+    /// Default palette followed by the loaded ones
+    palettes: Vec<Palette>,
+    /// Files failed to load on the last scan
+    errors: Vec<String>,
+}
+
+impl PaletteList {
+    pub fn new() -> Self {
+        let mut list = Self {
+            palettes: Vec::new(),
+            errors: Vec::new(),
+        };
+        list.scan();
+        list
+    }
+
+    /// Reload palettes from [`PALETTES_DIR`]
+    fn scan(&mut self) {
+        self.palettes = vec![Palette::new()];
+        self.errors.clear();
+
+        match Palette::load_dir(PALETTES_DIR) {
+            Ok(palettes) => palettes.into_iter().for_each(|palette| match palette {
+                Ok(palette) => self.palettes.push(palette),
+                Err(err) => self.errors.push(err.to_string()),
+            }),
+            Err(err) => warn!(%err, "Failed to read palettes directory"),
+        }
+    }
+}
+
+impl Default for PaletteList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resource packs available in the settings
+pub struct PackList {
+    /// Packs in the load order and whether they are enabled
+    entries: Vec<(ResourcePack, bool)>,
+}
+
+impl PackList {
+    pub fn new() -> Self {
+        let mut list = Self {
+            entries: Vec::new(),
+        };
+        list.scan(&AssetManager::new());
+        list
+    }
+
+    /// Reload packs from [`PACKS_DIR`]. Packs of `active` keep their order and stay enabled,
+    /// the others go after them
+    fn scan(&mut self, active: &AssetManager) {
+        let mut available = AssetManager::available();
+        let enabled = active
+            .packs()
+            .iter()
+            .filter_map(|pack| {
+                let i = available.iter().position(|other| other == pack)?;
+                Some((available.remove(i), true))
+            })
+            .collect::<Vec<_>>();
+        self.entries = enabled
+            .into_iter()
+            .chain(available.into_iter().map(|pack| (pack, false)))
+            .collect();
+    }
+
+    /// Enabled packs in the load order
+    fn assets(&self) -> AssetManager {
+        AssetManager::with_packs(
+            self.entries
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(pack, _)| pack.clone())
+                .collect(),
+        )
+    }
+}
+
+impl Default for PackList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct FrameCaptureTweaks {
+    /// Pipe frames to the command instead of saving PNGs
+    pipe: bool,
+    directory: String,
+    command: String,
+    every: u32,
+    /// Why the capture failed to start
+    error: Option<String>,
+}
+
+impl FrameCaptureTweaks {
+    pub fn new() -> Self {
+        Self {
+            pipe: false,
+            directory: "capture".to_string(),
+            command: "ffmpeg -y -f rawvideo -pix_fmt rgba -s {width}x{height} -r 60 -i - \
+                      capture.mp4"
+                .to_string(),
+            every: 1,
+            error: None,
         }
     }
+
+    fn settings(&self) -> CaptureSettings {
+        CaptureSettings {
+            output: if self.pipe {
+                CaptureOutput::Pipe(self.command.clone())
+            } else {
+                CaptureOutput::Png(PathBuf::from(&self.directory))
+            },
+            every: self.every,
+        }
+    }
+}
+
+impl Default for FrameCaptureTweaks {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub struct Painter {
@@ -612,14 +2706,214 @@ impl Painter {
     }
 }
 
+pub struct SchematicTool {
+    /// Corners of the copied region (inclusive)
+    first: GlobalCoord,
+    second: GlobalCoord,
+    /// Minimal corner of the pasted structure
+    target: GlobalCoord,
+    /// Air blocks of the structure don't replace the world
+    skip_air: bool,
+    path: String,
+    schematic: Option<Schematic>,
+    /// Result of the last action
+    message: Option<String>,
+}
+
+impl SchematicTool {
+    pub fn new() -> Self {
+        Self {
+            first: GlobalCoord::ZERO,
+            second: GlobalCoord::ZERO,
+            target: GlobalCoord::ZERO,
+            skip_air: true,
+            path: format!("structure.{}", Schematic::EXTENSION),
+            schematic: None,
+            message: None,
+        }
+    }
+}
+
+impl Default for SchematicTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Top-down terrain map export, see [`Headless::color_map`]
+pub struct MapExport {
+    /// Corners of the region (x, z), both inclusive
+    first: (GlobalUnit, GlobalUnit),
+    second: (GlobalUnit, GlobalUnit),
+    path: String,
+    /// Result of the running export
+    pending: Option<Receiver<String>>,
+    /// Result of the last export
+    message: Option<String>,
+}
+
+impl MapExport {
+    /// Side of the region around the camera
+    const DEFAULT_SIZE: GlobalUnit = 256;
+
+    pub fn new() -> Self {
+        Self {
+            first: (0, 0),
+            second: (Self::DEFAULT_SIZE - 1, Self::DEFAULT_SIZE - 1),
+            path: "map.png".to_string(),
+            pending: None,
+            message: None,
+        }
+    }
+}
+
+impl Default for MapExport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct Teleport {
     target_pos: GlobalCoord,
+    chunk_id: ChunkId,
+    /// Positions before recent teleports (newest first)
+    history: VecDeque<F32x3>,
 }
 
 impl Teleport {
+    const HISTORY_SIZE: usize = 8;
+
     pub const fn new() -> Self {
         Self {
             target_pos: GlobalCoord::ZERO,
+            chunk_id: ChunkId::ZERO,
+            history: VecDeque::new(),
+        }
+    }
+
+    fn push_history(&mut self, pos: F32x3) {
+        self.history.retain(|&old| old.distance_squared(pos) > 1.0);
+        self.history.push_front(pos);
+        self.history.truncate(Self::HISTORY_SIZE);
+    }
+}
+
+pub struct ChunkInspector {
+    chunk_id: ChunkId,
+    /// Result of the last dump
+    message: Option<String>,
+}
+
+impl ChunkInspector {
+    pub const fn new() -> Self {
+        Self {
+            chunk_id: ChunkId::ZERO,
+            message: None,
+        }
+    }
+}
+
+impl Default for ChunkInspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Allocation rate measured over about a second
+pub struct AllocRate {
+    since: Instant,
+    allocations: u64,
+    per_sec: f64,
+}
+
+impl AllocRate {
+    const INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            allocations: 0,
+            per_sec: 0.0,
+        }
+    }
+
+    fn update(&mut self, allocations: u64) {
+        let elapsed = self.since.elapsed();
+        if elapsed >= Self::INTERVAL {
+            self.per_sec =
+                allocations.saturating_sub(self.allocations) as f64 / elapsed.as_secs_f64();
+            self.allocations = allocations;
+            self.since = Instant::now();
         }
     }
 }
+
+impl Default for AllocRate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draw health as a row of hearts (each heart is 2 health points)
+fn draw_hearts(ui: &mut Ui, health: &Health) {
+    const SIZE: f32 = 16.0;
+    const SPACING: f32 = 2.0;
+    const EMPTY: Color32 = Color32::from_gray(60);
+    const FULL: Color32 = Color32::from_rgb(220, 30, 40);
+
+    let hearts = health.max().div_ceil(2);
+    let (rect, _) =
+        ui.allocate_exact_size(vec2(hearts as f32 * (SIZE + SPACING), SIZE), Sense::hover());
+    let painter = ui.painter();
+
+    for i in 0..hearts {
+        let heart = Rect::from_min_size(
+            rect.min + vec2(i as f32 * (SIZE + SPACING), 0.0),
+            vec2(SIZE, SIZE),
+        );
+        draw_heart(painter, heart, EMPTY);
+
+        // Half hearts are clipped
+        let filled = health.current().saturating_sub(i * 2).min(2);
+        if filled > 0 {
+            let clip = Rect::from_min_max(
+                heart.min,
+                pos2(heart.min.x + SIZE * filled as f32 / 2.0, heart.max.y),
+            );
+            draw_heart(&painter.with_clip_rect(clip), heart, FULL);
+        }
+    }
+}
+
+/// Drag values for each coordinate of `coord`
+fn drag_coord(ui: &mut Ui, coord: &mut GlobalCoord) {
+    for (prefix, value) in [
+        ("x: ", &mut coord.x),
+        ("y: ", &mut coord.y),
+        ("z: ", &mut coord.z),
+    ] {
+        ui.add(
+            DragValue::new(value)
+                .prefix(prefix)
+                .fixed_decimals(0)
+                .speed(1.0),
+        );
+    }
+}
+
+fn draw_heart(painter: &egui::Painter, rect: Rect, color: Color32) {
+    let radius = rect.width() / 4.0;
+    let top = rect.top() + radius;
+
+    painter.circle_filled(pos2(rect.left() + radius, top), radius, color);
+    painter.circle_filled(pos2(rect.right() - radius, top), radius, color);
+    painter.add(Shape::convex_polygon(
+        vec![
+            pos2(rect.left(), top),
+            pos2(rect.right(), top),
+            pos2(rect.center().x, rect.bottom()),
+        ],
+        color,
+        Stroke::none(),
+    ));
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use tokio::runtime::Builder;
+use tracing::{debug, info};
+
+use crate::{
+    bootstrap::{bootstrap, load_settings},
+    error::Error,
+    render::RenderMode,
+    scene::Scene,
+    settings::{Settings, ThreadSettings},
+    utils::VERSION,
+    window::Window,
+    Game,
+};
+
+/// Application embedding the engine. Hooks are called on the main thread
+pub trait GameState {
+    /// Called once the scene has been created
+    fn init(&mut self, _scene: &mut Scene) {}
+
+    /// Called every frame before the scene is updated. Returns `true` to close the game
+    fn tick(&mut self, _scene: &mut Scene, _dt: Duration) -> bool {
+        false
+    }
+
+    /// Called when the game is closing, before the scene is dropped
+    fn shutdown(&mut self, _scene: &mut Scene) {}
+}
+
+/// Stock game without additional behavior
+impl GameState for () {}
+
+/// Engine startup parameters
+#[derive(Clone)]
+pub struct EngineSettings {
+    /// Install the default log subscriber (`LOG_LEVEL` environment variable). Disable if the
+    /// embedding application sets up its own
+    pub logging: bool,
+    /// Player settings, including runtime and job pool threads. Read from the settings file once
+    /// logging is set up if `None`
+    pub settings: Option<Settings>,
+    /// Initial graphics settings. Present mode is taken from [`Self::settings`] if `None`
+    pub render_mode: Option<RenderMode>,
+}
+
+impl EngineSettings {
+    pub fn new() -> Self {
+        Self {
+            logging: true,
+            settings: None,
+            render_mode: None,
+        }
+    }
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates the runtime, window and renderer and runs the game loop.
+///
+/// ```no_run
+/// use ecg_game::engine::{EngineBuilder, EngineSettings};
+///
+/// let settings = EngineSettings {
+///     logging: false,
+///     ..Default::default()
+/// };
+/// let exit_code = EngineBuilder::new().with_settings(settings).run(()).unwrap();
+/// ```
+#[derive(Default)]
+pub struct EngineBuilder {
+    settings: EngineSettings,
+}
+
+impl EngineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_settings(mut self, settings: EngineSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Run the game until it's closed. Blocks the calling (main) thread. Returns exit code
+    pub fn run(self, mut state: impl GameState) -> Result<i32, Error> {
+        let settings = self.settings;
+
+        if settings.logging {
+            bootstrap()?;
+        }
+
+        #[cfg(feature = "tracy")]
+        {
+            debug!("Starting profiling client");
+            let _client = tracy_client::Client::start();
+        }
+
+        info!("Starting game instance. ECG v{VERSION}");
+
+        let player_settings = settings.settings.unwrap_or_else(load_settings);
+        let render_mode = settings.render_mode.unwrap_or(RenderMode {
+            present_mode: player_settings.graphics.present_mode,
+            ..RenderMode::new()
+        });
+
+        let threads = &player_settings.threads;
+        info!(
+            async_threads = threads.async_threads(),
+            blocking_threads = threads.blocking_threads(),
+            "Starting async runtime"
+        );
+        // Blocking threads are limited by the job pool, so they can be changed at runtime
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(threads.async_threads().max(1))
+            .max_blocking_threads(ThreadSettings::MAX_THREADS)
+            .build()
+            .map_err(|source| Error::Io {
+                context: "Failed to start async runtime".to_string(),
+                source,
+            })?;
+        let (window, event_loop) = Window::new(&runtime, render_mode)?;
+
+        let game = Game::new(window, runtime);
+
+        debug!("Game starts");
+        Ok(game.run(event_loop, player_settings, &mut state))
+    }
+}
@@ -1,11 +1,63 @@
+use std::io;
+
+use common::net::NetError;
+use thiserror::Error;
+
 use crate::{bootstrap::BootstrapError, render::error::RenderError};
 
-#[derive(Debug)]
+/// Error category. Used to explain the error to the player
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ErrorKind {
+    Render,
+    Io,
+    Asset,
+    Net,
+    Config,
+}
+
+/// Crate-level error
+#[derive(Error, Debug)]
 pub enum Error {
     /// Error related to bootstrapping
+    #[error("Bootstrap error: {0}")]
     BootstrapError(BootstrapError),
-    /// Error re
+    /// Error related to rendering
+    #[error("Render error: {0}")]
     RenderError(RenderError),
+    /// Error related to the server connection
+    #[error("Network error: {0}")]
+    NetError(NetError),
+    /// IO error with a description of the failed operation
+    #[error("{context}: {source}")]
+    Io { context: String, source: io::Error },
+    /// Missing or malformed game file
+    #[error("Invalid asset {path:?}: {reason}")]
+    Asset { path: String, reason: String },
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::BootstrapError(_) => ErrorKind::Config,
+            Self::RenderError(_) => ErrorKind::Render,
+            Self::NetError(_) => ErrorKind::Net,
+            Self::Io { .. } => ErrorKind::Io,
+            Self::Asset { .. } => ErrorKind::Asset,
+        }
+    }
+
+    /// Explanation for the player (e.g. for a crash message box). Details are in `Display`
+    pub fn user_message(&self) -> &'static str {
+        match self.kind() {
+            ErrorKind::Render => {
+                "Failed to render the game. Make sure your graphics drivers are up to date"
+            }
+            ErrorKind::Io => "Failed to access game files. Check free disk space and permissions",
+            ErrorKind::Asset => "Game files are missing or damaged. Try reinstalling the game",
+            ErrorKind::Net => "Connection to the server failed. Check the address and your network",
+            ErrorKind::Config => "Invalid game configuration. Check settings and environment",
+        }
+    }
 }
 
 impl From<BootstrapError> for Error {
@@ -19,3 +71,45 @@ impl From<RenderError> for Error {
         Self::RenderError(err)
     }
 }
+
+impl From<NetError> for Error {
+    fn from(err: NetError) -> Self {
+        Self::NetError(err)
+    }
+}
+
+/// Attach a description of the failed operation to IO errors
+pub trait Context<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, Error>;
+}
+
+impl<T> Context<T> for io::Result<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, Error> {
+        self.map_err(|source| Error::Io {
+            context: context.into(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::render::error::RenderError;
+
+    use super::{Context, Error, ErrorKind};
+
+    #[test]
+    fn error_context_and_kind() {
+        let err = Err::<(), _>(io::Error::new(io::ErrorKind::NotFound, "no such file"))
+            .context("Failed to open world")
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert_eq!(err.to_string(), "Failed to open world: no such file");
+
+        let err = Error::from(RenderError::AdapterNotFound);
+        assert_eq!(err.kind(), ErrorKind::Render);
+        assert!(err.to_string().contains("adapters not found"));
+    }
+}
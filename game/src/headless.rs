@@ -0,0 +1,258 @@
+//! Terrain generation and meshing without a window or GPU, for tools and integration tests.
+//!
+//! ```
+//! use common::coord::ChunkId;
+//! use ecg_game::headless::Headless;
+//!
+//! let chunks = Headless::new(42).region(ChunkId::new(-1, -1, -1), ChunkId::new(1, 0, 1));
+//! assert_eq!(chunks.len(), 3 * 2 * 3);
+//! ```
+
+use std::{fs, io, path::Path};
+
+use common::{
+    biome::ClimateMap,
+    block::Block,
+    bounds::WorldBounds,
+    chunk::Chunk,
+    coord::{BlockCoord, ChunkId, GlobalUnit, CHUNK_SIZE, G_CHUNK_SIZE},
+};
+use glam::Vec3;
+
+use crate::render::{capture::encode_png, mesh::TerrainMesh};
+
+/// Generated chunk with its mesh
+pub struct HeadlessChunk {
+    pub id: ChunkId,
+    pub chunk: Chunk,
+    /// CPU-side mesh data, the same that would be uploaded to the GPU
+    pub mesh: TerrainMesh,
+}
+
+/// Top-down image of generated terrain. Pixels go row by row along Z, X grows to the right.
+/// Alpha is 0 where columns have no opaque blocks
+pub struct ColorMap {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl ColorMap {
+    pub fn encode_png(&self) -> Vec<u8> {
+        encode_png(self.width, self.height, &self.rgba)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.encode_png())
+    }
+}
+
+/// Generates chunks and builds their meshes on the calling thread
+#[derive(Clone, Copy, Debug)]
+pub struct Headless {
+    pub seed: u32,
+    pub bounds: WorldBounds,
+}
+
+impl Headless {
+    /// Max side of color maps in blocks
+    pub const MAX_MAP_SIZE: GlobalUnit = 2048;
+
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            bounds: WorldBounds::default(),
+        }
+    }
+
+    pub fn with_bounds(mut self, bounds: WorldBounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    pub fn chunk(&self, id: ChunkId) -> Chunk {
+        Chunk::generate_with_seed(id, &self.bounds, self.seed)
+    }
+
+    pub fn mesh(&self, id: ChunkId, chunk: &Chunk) -> TerrainMesh {
+        TerrainMesh::build_with_seed(id.to_coord(), chunk.blocks(), self.seed)
+    }
+
+    /// Generate and mesh every chunk between two corners (both inclusive, in any order).
+    /// Chunks are ordered by X, then Y, then Z
+    pub fn region(&self, a: ChunkId, b: ChunkId) -> Vec<HeadlessChunk> {
+        let mut chunks = Vec::new();
+
+        for x in a.x.min(b.x)..=a.x.max(b.x) {
+            for y in a.y.min(b.y)..=a.y.max(b.y) {
+                for z in a.z.min(b.z)..=a.z.max(b.z) {
+                    let id = ChunkId::new(x, y, z);
+                    let chunk = self.chunk(id);
+                    let mesh = self.mesh(id, &chunk);
+                    chunks.push(HeadlessChunk { id, chunk, mesh });
+                }
+            }
+        }
+
+        chunks
+    }
+
+    /// Colors of the highest opaque blocks of columns between two (x, z) corners (both
+    /// inclusive, in any order). Vegetation is tinted by the climate, so biomes are visible.
+    /// `None` if the region is larger than [`Self::MAX_MAP_SIZE`]
+    pub fn color_map(
+        &self,
+        a: (GlobalUnit, GlobalUnit),
+        b: (GlobalUnit, GlobalUnit),
+    ) -> Option<ColorMap> {
+        let (min_x, min_z) = (a.0.min(b.0), a.1.min(b.1));
+        let (width, height) = ((a.0 - b.0).abs() + 1, (a.1 - b.1).abs() + 1);
+        if width > Self::MAX_MAP_SIZE || height > Self::MAX_MAP_SIZE {
+            return None;
+        }
+
+        let climate = ClimateMap::with_seed(self.seed);
+        let top = self.bounds.max_y.div_euclid(G_CHUNK_SIZE);
+        let bottom = self.bounds.min_y.div_euclid(G_CHUNK_SIZE);
+        let mut rgba = vec![0; (width * height * 4) as usize];
+
+        for cx in min_x.div_euclid(G_CHUNK_SIZE)..=(min_x + width - 1).div_euclid(G_CHUNK_SIZE) {
+            for cz in min_z.div_euclid(G_CHUNK_SIZE)..=(min_z + height - 1).div_euclid(G_CHUNK_SIZE)
+            {
+                // Columns of the chunk column inside the region, which have no color yet
+                let mut columns = (0..CHUNK_SIZE as u8)
+                    .flat_map(|x| (0..CHUNK_SIZE as u8).map(move |z| (x, z)))
+                    .map(|(x, z)| {
+                        let column = (
+                            cx * G_CHUNK_SIZE + x as GlobalUnit,
+                            cz * G_CHUNK_SIZE + z as GlobalUnit,
+                        );
+                        ((x, z), column)
+                    })
+                    .filter(|(_, (x, z))| {
+                        (min_x..min_x + width).contains(x) && (min_z..min_z + height).contains(z)
+                    })
+                    .collect::<Vec<_>>();
+
+                for cy in (bottom..=top).rev() {
+                    let id = ChunkId::new(cx, cy, cz);
+                    if columns.is_empty() {
+                        break;
+                    } else if !self.bounds.contains_chunk(id) {
+                        continue;
+                    }
+
+                    let chunk = self.chunk(id);
+                    columns.retain(|&((x, z), (gx, gz))| {
+                        let Some(block) = (0..CHUNK_SIZE as u8)
+                            .rev()
+                            .map(|y| chunk.get(BlockCoord::new(x, y, z)))
+                            .find(Block::opaque)
+                        else {
+                            return true;
+                        };
+
+                        let mut color = block.color();
+                        if block.tinted() {
+                            color *= climate.get(gx, gz).tint();
+                        }
+                        let rgb = color.clamp(Vec3::ZERO, Vec3::ONE) * 255.0;
+                        let i = (((gz - min_z) * width + gx - min_x) * 4) as usize;
+                        rgba[i..i + 4].copy_from_slice(&[
+                            rgb.x as u8,
+                            rgb.y as u8,
+                            rgb.z as u8,
+                            255,
+                        ]);
+
+                        false
+                    });
+                }
+            }
+        }
+
+        Some(ColorMap {
+            width: width as u32,
+            height: height as u32,
+            rgba,
+        })
+    }
+}
+
+impl Default for Headless {
+    fn default() -> Self {
+        Self::new(Chunk::DEFAULT_SEED)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::{biome::ClimateMap, bounds::WorldBounds, chunk::Chunk, coord::ChunkId};
+    use glam::Vec3;
+
+    use crate::render::mesh::TerrainMesh;
+
+    use super::Headless;
+
+    #[test]
+    fn default_seed_matches_game() {
+        let headless = Headless::default();
+        let chunks = headless.region(ChunkId::new(1, 0, 0), ChunkId::new(0, -1, 0));
+        assert_eq!(
+            chunks.iter().map(|chunk| chunk.id).collect::<Vec<_>>(),
+            [
+                ChunkId::new(0, -1, 0),
+                ChunkId::new(0, 0, 0),
+                ChunkId::new(1, -1, 0),
+                ChunkId::new(1, 0, 0),
+            ]
+        );
+
+        for chunk in chunks {
+            let generated = Chunk::generate(chunk.id, &WorldBounds::default());
+            assert_eq!(chunk.chunk.blocks(), generated.blocks());
+
+            let mesh = TerrainMesh::build(chunk.id.to_coord(), generated.blocks());
+            assert_eq!(chunk.mesh.indices, mesh.indices);
+            assert_eq!(chunk.mesh.vertices.len(), mesh.vertices.len());
+        }
+    }
+
+    #[test]
+    fn seeds_differ() {
+        let id = ChunkId::new(0, -1, 0);
+        let (a, b) = (Headless::new(1).chunk(id), Headless::new(2).chunk(id));
+
+        assert_ne!(a.blocks(), b.blocks());
+        assert_eq!(a.blocks(), Headless::new(1).chunk(id).blocks());
+    }
+
+    #[test]
+    fn color_map_of_surface() {
+        let headless = Headless::default();
+        let map = headless.color_map((5, -3), (-4, 2)).unwrap();
+        assert_eq!((map.width, map.height), (10, 6));
+        assert_eq!(map.rgba.len(), 10 * 6 * 4);
+
+        // Every column has ground
+        assert!(map.rgba.chunks_exact(4).all(|pixel| pixel[3] == 255));
+        // Top-left pixel is the (-4, -3) column
+        let surface = Chunk::generated_surface(-4, -3, &headless.bounds).unwrap();
+        let block =
+            Chunk::generate(surface.to_chunk_id(), &headless.bounds).get(surface.to_block());
+        let mut color = block.color();
+        if block.tinted() {
+            color *= ClimateMap::new().get(-4, -3).tint();
+        }
+        let rgb = color.clamp(Vec3::ZERO, Vec3::ONE) * 255.0;
+        assert_eq!(map.rgba[..4], [rgb.x as u8, rgb.y as u8, rgb.z as u8, 255]);
+
+        assert!(headless
+            .color_map((0, 0), (Headless::MAX_MAP_SIZE, 0))
+            .is_none());
+    }
+}
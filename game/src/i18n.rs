@@ -0,0 +1,172 @@
+//! UI strings by key.
+//!
+//! Bundles are files in [`LANG_DIR`] named by the language code (e.g. `ru.lang`) with a
+//! `key = value` line per string. `{name}` in values is replaced with the argument of the same
+//! name. English is built in, so strings missing from the selected language fall back to it
+
+use std::{collections::HashMap, fmt::Display, fs, path::Path};
+
+use tracing::warn;
+
+use crate::{
+    consts::LANG_DIR,
+    error::{Context, Error},
+};
+
+/// Language code and name
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Language {
+    pub code: String,
+    pub name: String,
+}
+
+/// Strings of the selected language
+pub struct Locale {
+    code: String,
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Locale {
+    pub const FALLBACK_CODE: &'static str = "en";
+    /// Key of the language name in its own bundle
+    const NAME_KEY: &'static str = "language.name";
+    const FALLBACK: &'static str = include_str!("../../assets/lang/en.lang");
+    const EXTENSION: &'static str = "lang";
+
+    /// English
+    pub fn new() -> Self {
+        Self {
+            code: Self::FALLBACK_CODE.to_string(),
+            strings: HashMap::new(),
+            fallback: parse_bundle(Self::FALLBACK),
+        }
+    }
+
+    /// Load bundle of the language from [`LANG_DIR`]
+    pub fn load(code: &str) -> Result<Self, Error> {
+        if code == Self::FALLBACK_CODE {
+            return Ok(Self::new());
+        }
+
+        let path = Path::new(LANG_DIR).join(format!("{code}.{}", Self::EXTENSION));
+        let source = fs::read_to_string(&path).context(format!("Failed to read {path:?}"))?;
+
+        Ok(Self {
+            code: code.to_string(),
+            strings: parse_bundle(&source),
+            ..Self::new()
+        })
+    }
+
+    /// Languages with a bundle in [`LANG_DIR`] and English, sorted by code
+    pub fn available() -> Vec<Language> {
+        let mut languages = fs::read_dir(LANG_DIR)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != Self::EXTENSION {
+                    return None;
+                }
+                let code = path.file_stem()?.to_string_lossy().into_owned();
+                let strings = parse_bundle(&fs::read_to_string(&path).ok()?);
+                let name = strings
+                    .get(Self::NAME_KEY)
+                    .cloned()
+                    .unwrap_or_else(|| code.clone());
+
+                Some(Language { code, name })
+            })
+            .filter(|language| language.code != Self::FALLBACK_CODE)
+            .collect::<Vec<_>>();
+        languages.push(Self::new().language());
+        languages.sort_by(|a, b| a.code.cmp(&b.code));
+
+        languages
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn language(&self) -> Language {
+        Language {
+            code: self.code.clone(),
+            name: self.get(Self::NAME_KEY).to_string(),
+        }
+    }
+
+    /// String of the selected language, English one or the key itself if there is none
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    /// Same as [`Self::get`], but `{name}` placeholders are replaced with arguments
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |string, (name, value)| {
+                string.replace(&format!("{{{name}}}"), &value.to_string())
+            })
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse `key = value` lines. Empty lines and lines starting with `#` are skipped
+fn parse_bundle(source: &str) -> HashMap<String, String> {
+    source
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|(i, line)| match line.split_once('=') {
+            Some((key, value)) => Some((key.trim().to_string(), value.trim().to_string())),
+            None => {
+                warn!(line = i + 1, "Skipping invalid localization line");
+                None
+            }
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_bundle, Locale};
+
+    #[test]
+    fn fallback_and_arguments() {
+        let mut locale = Locale::new();
+        assert_eq!(locale.get("hud.died"), "You died");
+        assert_eq!(locale.get("missing.key"), "missing.key");
+
+        locale.strings = parse_bundle("# Comment\nhud.died = Вы погибли\ninvalid\n");
+        assert_eq!(locale.get("hud.died"), "Вы погибли");
+        assert_eq!(locale.get("hud.ok"), "OK");
+        assert_eq!(
+            locale.format("hud.respawn_in", &[("seconds", &3)]),
+            "Respawn in 3s"
+        );
+    }
+
+    #[test]
+    fn bundles_have_fallback_keys() {
+        let fallback = parse_bundle(Locale::FALLBACK);
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/lang");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let bundle = parse_bundle(&std::fs::read_to_string(entry.unwrap().path()).unwrap());
+            assert!(bundle.keys().all(|key| fallback.contains_key(key)));
+        }
+    }
+}
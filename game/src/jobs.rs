@@ -0,0 +1,170 @@
+//! Blocking jobs (chunk generation and meshing) run on a limited number of runtime threads.
+//!
+//! Thread count of the runtime is fixed once it's built, so jobs are queued here and run by up to
+//! [`JobPool::workers`] threads taken from the blocking pool of the runtime. The limit can be
+//! changed at any time
+
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio::runtime::Runtime;
+use tracing::error;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Queue {
+    jobs: VecDeque<Job>,
+    /// Threads taking jobs from the queue
+    running: usize,
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    workers: AtomicUsize,
+}
+
+impl Shared {
+    /// Run queued jobs until there are none or the thread is above the limit
+    fn work(&self) {
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().unwrap();
+                if queue.running > self.workers.load(Ordering::Relaxed) || queue.jobs.is_empty() {
+                    queue.running -= 1;
+                    return;
+                }
+                queue.jobs.pop_front().unwrap()
+            };
+
+            // Worker must not be lost, otherwise the queue may stall
+            if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                error!("Job panicked");
+            }
+        }
+    }
+}
+
+/// Queue of blocking jobs with a limit of threads running them
+#[derive(Clone)]
+pub struct JobPool {
+    shared: Arc<Shared>,
+}
+
+impl JobPool {
+    pub fn new(workers: usize) -> Self {
+        let shared = Shared::default();
+        shared.workers.store(workers.max(1), Ordering::Relaxed);
+
+        Self {
+            shared: Arc::new(shared),
+        }
+    }
+
+    /// Max jobs running at once
+    pub fn workers(&self) -> usize {
+        self.shared.workers.load(Ordering::Relaxed)
+    }
+
+    /// Extra threads are released once their current jobs finish
+    pub fn set_workers(&self, runtime: &Runtime, workers: usize) {
+        let workers = workers.max(1);
+        if self.shared.workers.swap(workers, Ordering::Relaxed) < workers {
+            self.wake(runtime);
+        }
+    }
+
+    /// Jobs running and waiting for a thread
+    pub fn jobs(&self) -> (usize, usize) {
+        let queue = self.shared.queue.lock().unwrap();
+        (queue.running, queue.jobs.len())
+    }
+
+    pub fn spawn(&self, runtime: &Runtime, job: impl FnOnce() + Send + 'static) {
+        self.shared
+            .queue
+            .lock()
+            .unwrap()
+            .jobs
+            .push_back(Box::new(job));
+        self.wake(runtime);
+    }
+
+    /// Start threads for queued jobs up to the limit
+    fn wake(&self, runtime: &Runtime) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        while queue.running < self.workers().min(queue.running + queue.jobs.len()) {
+            queue.running += 1;
+            let shared = self.shared.clone();
+            runtime.spawn_blocking(move || shared.work());
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc::channel,
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use tokio::runtime::Builder;
+
+    use super::JobPool;
+
+    #[test]
+    fn limit_running_jobs() {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .max_blocking_threads(8)
+            .build()
+            .unwrap();
+        let pool = JobPool::new(2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = channel();
+
+        let spawn = |count| {
+            for _ in 0..count {
+                let (running, peak, tx) = (running.clone(), peak.clone(), tx.clone());
+                pool.spawn(&runtime, move || {
+                    peak.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    tx.send(()).unwrap();
+                });
+            }
+            for _ in 0..count {
+                rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            }
+        };
+
+        spawn(8);
+        assert_eq!(peak.swap(0, Ordering::SeqCst), 2);
+
+        pool.set_workers(&runtime, 4);
+        spawn(16);
+        assert!((3..=4).contains(&peak.load(Ordering::SeqCst)));
+        // A panicking job doesn't take its thread down
+        pool.set_workers(&runtime, 1);
+        pool.spawn(&runtime, || panic!("Job failed"));
+        spawn(2);
+        assert_eq!(pool.jobs().1, 0);
+    }
+}
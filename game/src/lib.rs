@@ -1,16 +1,28 @@
+use std::time::{Duration, Instant};
+
 use common::clock::Clock;
 use common_log::{prof, span};
 use tokio::runtime::Runtime;
-use tracing::{debug, info};
-use winit::{event::WindowEvent, event_loop::ControlFlow};
+use tracing::{debug, error, info, warn};
+use winit::{
+    event::WindowEvent, event_loop::ControlFlow, platform::run_return::EventLoopExtRunReturn,
+};
 
+pub mod assets;
 pub mod bootstrap;
 pub mod consts;
 #[cfg(feature = "debug_overlay")]
 pub mod egui;
+pub mod engine;
 pub mod error;
+pub mod headless;
+pub mod i18n;
+pub mod jobs;
+pub mod memory;
+pub mod net;
 pub mod render;
 pub mod scene;
+pub mod settings;
 pub mod types;
 pub mod utils;
 pub mod window;
@@ -19,7 +31,10 @@ pub mod window;
 use crate::egui::DebugOverlay;
 
 use crate::{
+    engine::GameState,
+    render::pacer::FramePacer,
     scene::Scene,
+    settings::Settings,
     types::{EventLoop, WEvent},
     utils::ExitCode,
     window::Window,
@@ -30,6 +45,7 @@ pub struct Game {
     pub window: Window,
     pub runtime: Runtime,
     pub clock: Clock,
+    pub pacer: FramePacer,
 
     // Debug UI
     #[cfg(feature = "debug_overlay")]
@@ -38,6 +54,8 @@ pub struct Game {
 
 impl Game {
     pub const BACKGROUND_FPS: u32 = 30;
+    /// How long shutdown waits for background tasks
+    pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
     pub fn new(window: Window, runtime: Runtime) -> Self {
         // Logging span
@@ -55,64 +73,126 @@ impl Game {
             window,
             runtime,
             clock: Clock::new(Clock::tps_to_duration(Self::BACKGROUND_FPS)),
+            pacer: FramePacer::new(),
             #[cfg(feature = "debug_overlay")]
             overlay,
         }
     }
 
-    pub fn tick(&mut self, control_flow: &mut ControlFlow, scene: &mut Scene) {
+    pub fn tick(
+        &mut self,
+        control_flow: &mut ControlFlow,
+        scene: &mut Scene,
+        state: &mut impl GameState,
+    ) {
         span!(_guard, "MainEventsCleared");
         let exit;
+        // Wait before sampling input, so it's as fresh as possible when the frame is presented
+        if self.pacer.update_display(self.window.inner()) {
+            self.window.renderer_mut().on_monitor_change();
+        }
+        let present_mode = self.window.renderer().present_mode();
+        self.pacer.latency_wait = scene.latency_wait && self.window.focused;
+        self.pacer.begin_frame(present_mode);
+
         // Fetch occurred events
         let events = self.window.fetch_events();
+        self.pacer.input(self.window.fetch_input_time());
 
         // Update game state
         {
             span!(_guard, "StateTick");
-            exit = scene.tick(self, events, self.clock.duration());
+            let dt = self.clock.duration();
+            // Embedding state goes first, so its scene changes apply to this frame
+            exit = state.tick(scene, dt) | scene.tick(self, events, dt);
         }
 
         if exit {
             *control_flow = ControlFlow::Exit;
         }
 
+        // Backend switch or unrecoverable render error
+        if self.window.renderer().recreate_requested() {
+            span!(_guard, "RecreateRenderer");
+            match self.window.recreate_renderer(&self.runtime) {
+                Ok(()) => scene.rebind(self.window.renderer_mut()),
+                Err(err) => {
+                    error!(%err, "Failed to recreate renderer");
+                    control_flow.set_exit_with_code(ExitCode::RendererLost.as_int());
+                    return;
+                }
+            }
+        }
+
         // Render
         {
             span!(_guard, "Render");
 
             #[cfg(feature = "debug_overlay")]
-            let scale_factor = self.window.inner().scale_factor() as f32;
+            let scale_factor = self.overlay.scale_factor();
+            let mut lost = false;
 
-            if let Some(mut drawer) = self
-                .window
-                .renderer_mut()
-                .start_frame(&scene.globals_bind_group)
-                .expect("Unrecoverable render error when starting a new frame")
-            {
-                prof!(guard, "Render::FirstPass");
-                scene.draw(drawer.first_pass());
-                drop(guard);
-
-                #[cfg(feature = "debug_overlay")]
-                if scene.show_overlay {
-                    drawer
-                        .draw_overlay(&mut self.overlay.platform, scale_factor)
-                        .expect("Unrecoverable render error when drawing debug overlay");
+            match self.pacer.acquire(|| {
+                self.window
+                    .renderer_mut()
+                    .start_frame(&scene.globals_bind_group)
+            }) {
+                Ok(Some(mut drawer)) => {
+                    prof!(guard, "Render::FirstPass");
+                    scene.draw(drawer.first_pass(scene.current_environment().sky_color));
+                    drop(guard);
+
+                    prof!(guard, "Render::SecondPass");
+                    scene.draw_second_pass(drawer.second_pass());
+                    drop(guard);
+
+                    if let Some((globals, viewport)) =
+                        scene.inset.pass().filter(|_| scene.loading.is_none())
+                    {
+                        prof!(guard, "Render::InsetPass");
+                        scene.draw_inset(drawer.view_pass(globals, viewport));
+                        drop(guard);
+                    }
+
+                    prof!(guard, "Render::PostPass");
+                    scene.draw_post_pass(drawer.post_pass());
+                    drop(guard);
+
+                    #[cfg(feature = "debug_overlay")]
+                    if scene.show_overlay {
+                        drawer
+                            .draw_overlay(&mut self.overlay.platform, scale_factor)
+                            .expect("Unrecoverable render error when drawing debug overlay");
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    error!(%err, "Failed to start a new frame");
+                    lost = true;
                 }
             }
+
+            // Renderer will be recreated on the next tick
+            if lost {
+                self.window.renderer_mut().request_recreate();
+            }
+
+            self.pacer.presented(self.window.renderer().frame_index());
+            let finished = self.window.renderer_mut().finished_frames();
+            self.pacer.frames_finished(&finished);
         }
 
         // Wait for next frame
         if !exit {
             span!(_guard, "Sleep");
-            let max_fps = scene.fps;
+            let max_fps = scene.settings.graphics.fps;
 
             // Lower target frame time when the game window is not focused
-            self.clock.target = Clock::tps_to_duration(if self.window.focused {
-                max_fps
+            self.clock.target = if self.window.focused {
+                self.pacer.target(present_mode, max_fps)
             } else {
-                max_fps.min(Self::BACKGROUND_FPS)
-            });
+                Clock::tps_to_duration(max_fps.min(Self::BACKGROUND_FPS))
+            };
 
             // Sleep remaining time
             self.clock.tick();
@@ -123,15 +203,21 @@ impl Game {
         }
     }
 
-    pub fn run(mut self, event_loop: EventLoop) {
-        // TODO: PlayStates
-        let mut scene = Scene::new(&mut self.window);
+    /// Run the game loop until the game is closed. Returns exit code
+    pub fn run(
+        mut self,
+        mut event_loop: EventLoop,
+        settings: Settings,
+        state: &mut impl GameState,
+    ) -> i32 {
+        let mut scene = Scene::new(&mut self.window, settings);
+        state.init(&mut scene);
 
         let mut poll_span = None;
         let mut event_span = None;
 
         debug!("Entering game loop");
-        event_loop.run(move |event, _, control_flow| {
+        let exit_code = event_loop.run_return(|event, _, control_flow| {
             // Continuos rendering
             control_flow.set_poll();
 
@@ -173,7 +259,7 @@ impl Game {
                     event_span.take();
                     poll_span.take();
 
-                    self.tick(control_flow, &mut scene);
+                    self.tick(control_flow, &mut scene, state);
 
                     prof!(span, "PollWinit");
                     poll_span = Some(span);
@@ -181,5 +267,37 @@ impl Game {
                 _ => {}
             }
         });
+
+        state.shutdown(&mut scene);
+        self.shutdown(scene);
+
+        exit_code
+    }
+
+    /// Release everything in order: stop chunk jobs, disconnect from the server, drop scene and
+    /// GPU resources and then wait for the remaining tasks (at most [`Self::SHUTDOWN_TIMEOUT`])
+    fn shutdown(mut self, mut scene: Scene) {
+        span!(_guard, "Shutdown");
+        info!("Shutting down");
+
+        scene.chunk_manager.shutdown();
+
+        if let Some(net) = scene.net.take() {
+            debug!(addr = %net.addr(), "Disconnecting from server");
+            drop(net);
+        }
+
+        // Scene holds GPU resources, so it goes before the renderer
+        drop(scene);
+        self.window.destroy_renderer();
+
+        debug!("Waiting for background tasks");
+        let start = Instant::now();
+        self.runtime.shutdown_timeout(Self::SHUTDOWN_TIMEOUT);
+        if start.elapsed() >= Self::SHUTDOWN_TIMEOUT {
+            warn!("Some background tasks didn't finish in time");
+        }
+
+        info!("Game has been shut down");
     }
 }
@@ -1,15 +1,11 @@
 #![windows_subsystem = "windows"]
 
-use tokio::runtime::Builder;
-use tracing::{debug, info};
+use tracing::error;
 
 use ecg_game::{
-    bootstrap::bootstrap,
-    consts::{ASYNC_THREADS, BLOCKING_THREADS},
-    error::Error,
-    utils::VERSION,
-    window::Window,
-    Game,
+    engine::EngineBuilder,
+    utils::ExitCode,
+    window::{dialog, Window},
 };
 
 #[cfg_attr(feature = "tracy-memory", global_allocator)]
@@ -17,28 +13,36 @@ use ecg_game::{
 static GLOBAL: common::tracy_client::ProfiledAllocator<std::alloc::System> =
     common::tracy_client::ProfiledAllocator::new(std::alloc::System, 100);
 
-fn main() -> Result<(), Error> {
-    bootstrap()?;
-
-    #[cfg(feature = "tracy")]
-    {
-        debug!("Starting profiling client");
-        let _client = tracy_client::Client::start();
+/// Counts allocations for the debug overlay. Replaced by the tracy allocator with `tracy-memory`
+#[cfg(not(feature = "tracy-memory"))]
+#[global_allocator]
+static ALLOCATOR: ecg_game::memory::CountingAllocator<std::alloc::System> =
+    ecg_game::memory::CountingAllocator::new(std::alloc::System);
+
+fn main() {
+    #[cfg(not(feature = "tracy-memory"))]
+    ecg_game::memory::register(&ALLOCATOR);
+
+    match EngineBuilder::new().run(()) {
+        Ok(0) => {}
+        Ok(code) => {
+            if code == ExitCode::RendererLost.as_int() {
+                dialog::show_error(
+                    &Window::title(),
+                    "Graphics device has been lost and couldn't be recovered. Make sure your \
+                     graphics drivers are up to date",
+                );
+            }
+            std::process::exit(code)
+        }
+        Err(err) => {
+            error!(kind = ?err.kind(), "{err}");
+            let message = format!("{}\n\n{err}", err.user_message());
+            eprintln!("{message}");
+            if !dialog::show_error(&Window::title(), &message) {
+                error!("Failed to show error message box");
+            }
+            std::process::exit(1);
+        }
     }
-
-    info!("Starting game instance. ECG v{VERSION}");
-
-    let runtime = Builder::new_multi_thread()
-        .worker_threads(ASYNC_THREADS)
-        .max_blocking_threads(*BLOCKING_THREADS)
-        .build()
-        .unwrap();
-    let (window, event_loop) = Window::new(&runtime)?;
-
-    let game = Game::new(window, runtime);
-
-    debug!("Game starts");
-    game.run(event_loop);
-
-    Ok(())
 }
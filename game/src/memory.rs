@@ -0,0 +1,145 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+
+/// Counting allocator installed by the binary, see [`register`]
+static REGISTERED: OnceLock<&'static CountingAllocator<System>> = OnceLock::new();
+
+/// Heap statistics snapshot
+#[derive(Clone, Copy, Default, Debug)]
+pub struct AllocStats {
+    /// Total number of allocations since start
+    pub allocations: u64,
+    /// Total number of deallocations since start
+    pub deallocations: u64,
+    /// Bytes currently allocated
+    pub bytes_live: usize,
+    /// Max of `bytes_live` since start
+    pub bytes_peak: usize,
+}
+
+impl AllocStats {
+    /// Number of currently live allocations
+    pub fn live_allocations(&self) -> u64 {
+        self.allocations.saturating_sub(self.deallocations)
+    }
+}
+
+/// Make [`stats`] report `allocator`. Called by the binary declaring it as the global allocator,
+/// the library doesn't declare one so embedders can use their own
+pub fn register(allocator: &'static CountingAllocator<System>) {
+    let _ = REGISTERED.set(allocator);
+}
+
+/// Heap statistics of the global allocator. `None` if counting allocator isn't registered
+pub fn stats() -> Option<AllocStats> {
+    REGISTERED.get().map(|allocator| allocator.stats())
+}
+
+/// Allocator wrapper which counts allocations and live bytes
+pub struct CountingAllocator<A> {
+    inner: A,
+    allocations: AtomicU64,
+    deallocations: AtomicU64,
+    bytes_live: AtomicUsize,
+    bytes_peak: AtomicUsize,
+}
+
+impl<A> CountingAllocator<A> {
+    pub const fn new(inner: A) -> Self {
+        Self {
+            inner,
+            allocations: AtomicU64::new(0),
+            deallocations: AtomicU64::new(0),
+            bytes_live: AtomicUsize::new(0),
+            bytes_peak: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> AllocStats {
+        AllocStats {
+            allocations: self.allocations.load(Ordering::Relaxed),
+            deallocations: self.deallocations.load(Ordering::Relaxed),
+            bytes_live: self.bytes_live.load(Ordering::Relaxed),
+            bytes_peak: self.bytes_peak.load(Ordering::Relaxed),
+        }
+    }
+
+    fn add(&self, size: usize) {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        let live = self.bytes_live.fetch_add(size, Ordering::Relaxed) + size;
+        self.bytes_peak.fetch_max(live, Ordering::Relaxed);
+    }
+
+    fn sub(&self, size: usize) {
+        self.deallocations.fetch_add(1, Ordering::Relaxed);
+        self.bytes_live.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+// Counting only uses relaxed atomics, so it can't break the inner allocator guarantees
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            self.add(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        self.sub(layout.size());
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.add(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            // Counted as a new allocation of the new size replacing the old one
+            self.sub(layout.size());
+            self.add(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+
+    use super::CountingAllocator;
+
+    #[test]
+    fn counting_allocator() {
+        let allocator = CountingAllocator::new(System);
+        let layout = Layout::from_size_align(64, 8).unwrap();
+
+        unsafe {
+            let a = allocator.alloc(layout);
+            let b = allocator.alloc_zeroed(layout);
+            let b = allocator.realloc(b, layout, 256);
+            allocator.dealloc(a, layout);
+
+            let stats = allocator.stats();
+            assert_eq!(stats.bytes_live, 256);
+            assert_eq!(stats.bytes_peak, 320);
+            assert_eq!(stats.live_allocations(), 1);
+
+            allocator.dealloc(b, Layout::from_size_align(256, 8).unwrap());
+        }
+
+        assert_eq!(allocator.stats().bytes_live, 0);
+    }
+}
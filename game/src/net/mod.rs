@@ -0,0 +1,200 @@
+use std::{
+    env::var,
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{channel, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use common::{
+    block::Block,
+    net::{
+        compression::Compression,
+        connection::Connection,
+        protocol::{ClientMsg, ServerMsg},
+        NetError, PROTOCOL_VERSION,
+    },
+};
+use tracing::{debug, error, info, warn};
+
+use self::stats::{NetClientStats, StatsTracker};
+
+pub mod stats;
+
+/// Handle to the network client task.
+///
+/// Connection itself lives in a separate thread, messages are exchanged through channels
+pub struct NetClient {
+    addr: SocketAddr,
+    tx: Sender<ClientMsg>,
+    rx: Receiver<ServerMsg>,
+    stats: Arc<Mutex<NetClientStats>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl NetClient {
+    /// How long network task sleeps between socket polls
+    const POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+    /// Environment variable with server address
+    pub const SERVER_ENV: &'static str = "ECG_SERVER";
+    /// Environment variable with player name
+    pub const NAME_ENV: &'static str = "ECG_NAME";
+    pub const DEFAULT_NAME: &'static str = "Player";
+
+    /// Connect to the server specified in `ECG_SERVER` environment variable (if any)
+    pub fn from_env() -> Option<Self> {
+        let addr = var(Self::SERVER_ENV).ok()?;
+        let name = var(Self::NAME_ENV).unwrap_or_else(|_| Self::DEFAULT_NAME.to_string());
+
+        let addr = match addr.to_socket_addrs().map(|mut addrs| addrs.next()) {
+            Ok(Some(addr)) => addr,
+            Ok(None) | Err(_) => {
+                error!(%addr, "Can't resolve server address");
+                return None;
+            }
+        };
+
+        Self::connect(addr, name)
+            .map_err(|err| error!(%addr, %err, "Failed to connect to server"))
+            .ok()
+    }
+
+    pub fn connect(addr: SocketAddr, name: String) -> Result<Self, NetError> {
+        info!(%addr, "Connecting to server");
+
+        let mut conn = Connection::new(TcpStream::connect(addr)?)?;
+        conn.send(&ClientMsg::Hello {
+            version: PROTOCOL_VERSION,
+            registry: Block::registry_hash(),
+            name,
+            compression: Compression::SUPPORTED.to_vec(),
+        });
+
+        let (tx, task_rx) = channel();
+        let (task_tx, rx) = channel();
+        let stats = Arc::new(Mutex::new(NetClientStats::default()));
+
+        let handle = {
+            let stats = stats.clone();
+            thread::Builder::new()
+                .name("network".to_string())
+                .spawn(move || Self::task(conn, task_rx, task_tx, stats))?
+        };
+
+        Ok(Self {
+            addr,
+            tx,
+            rx,
+            stats,
+            handle: Some(handle),
+        })
+    }
+
+    fn task(
+        mut conn: Connection,
+        rx: Receiver<ClientMsg>,
+        tx: Sender<ServerMsg>,
+        stats: Arc<Mutex<NetClientStats>>,
+    ) {
+        let mut tracker = StatsTracker::new(Instant::now());
+
+        loop {
+            if let Some(seq) = tracker.ping(Instant::now()) {
+                conn.send(&ClientMsg::Ping(seq));
+            }
+
+            // Outgoing messages
+            loop {
+                match rx.try_recv() {
+                    Ok(msg) => conn.send(&msg),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        conn.send(&ClientMsg::Disconnect);
+                        let _ = conn.flush();
+                        debug!("Network task stopped");
+                        return;
+                    }
+                }
+            }
+
+            if let Err(err) = conn.flush() {
+                warn!(%err, "Failed to send data to server");
+                return;
+            }
+
+            // Incoming messages
+            match conn.recv::<ServerMsg>() {
+                Ok(messages) => {
+                    for msg in messages {
+                        match msg {
+                            ServerMsg::Welcome { compression, .. } => {
+                                conn.set_compression(compression)
+                            }
+                            ServerMsg::Pong(seq) => {
+                                tracker.pong(seq, Instant::now());
+                                continue;
+                            }
+                            _ => {}
+                        }
+
+                        if tx.send(msg).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(err) => {
+                    warn!(%err, "Connection to server lost");
+                    return;
+                }
+            }
+
+            let update = tracker.update(conn.stats(), Instant::now());
+            if let Ok(mut stats) = stats.lock() {
+                *stats = update;
+            }
+
+            thread::sleep(Self::POLL_INTERVAL);
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Check if network task is still alive
+    pub fn connected(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Connection statistics collected by the network task
+    pub fn stats(&self) -> NetClientStats {
+        self.stats.lock().map(|stats| *stats).unwrap_or_default()
+    }
+
+    pub fn send(&self, msg: ClientMsg) {
+        let _ = self.tx.send(msg);
+    }
+
+    /// Fetch all received messages
+    pub fn fetch(&self) -> Vec<ServerMsg> {
+        self.rx.try_iter().collect()
+    }
+}
+
+impl Drop for NetClient {
+    fn drop(&mut self) {
+        // Closing the channel stops the network task
+        let (tx, _) = channel();
+        drop(std::mem::replace(&mut self.tx, tx));
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
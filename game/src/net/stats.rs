@@ -0,0 +1,142 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use common::net::connection::NetStats;
+
+/// Connection statistics exposed by the network task
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NetClientStats {
+    pub traffic: NetStats,
+    /// Round trip time of the last answered ping
+    pub ping: Option<Duration>,
+    /// Share of recent pings left unanswered (0..1)
+    pub packet_loss: f32,
+    pub bytes_in_per_sec: u64,
+    pub bytes_out_per_sec: u64,
+}
+
+/// Collects connection statistics inside the network task
+pub(super) struct StatsTracker {
+    next_seq: u32,
+    last_ping: Option<Instant>,
+    /// Pings waiting for reply
+    pending: VecDeque<(u32, Instant)>,
+    /// Recent ping results (`true` if answered)
+    results: VecDeque<bool>,
+
+    last_rate: Instant,
+    last_traffic: NetStats,
+
+    stats: NetClientStats,
+}
+
+impl StatsTracker {
+    const PING_INTERVAL: Duration = Duration::from_secs(1);
+    /// Pings without reply after this time are considered lost
+    const PING_TIMEOUT: Duration = Duration::from_secs(3);
+    /// Number of pings used for packet loss estimation
+    const LOSS_WINDOW: usize = 32;
+    const RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(now: Instant) -> Self {
+        Self {
+            next_seq: 0,
+            last_ping: None,
+            pending: VecDeque::new(),
+            results: VecDeque::with_capacity(Self::LOSS_WINDOW),
+            last_rate: now,
+            last_traffic: NetStats::default(),
+            stats: NetClientStats::default(),
+        }
+    }
+
+    /// Returns sequence number of the ping to send (if it's time to)
+    pub fn ping(&mut self, now: Instant) -> Option<u32> {
+        if self
+            .last_ping
+            .is_some_and(|last| now.duration_since(last) < Self::PING_INTERVAL)
+        {
+            return None;
+        }
+
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        self.last_ping = Some(now);
+        self.pending.push_back((seq, now));
+
+        Some(seq)
+    }
+
+    pub fn pong(&mut self, seq: u32, now: Instant) {
+        if let Some(idx) = self.pending.iter().position(|&(pending, _)| pending == seq) {
+            let (_, sent) = self.pending.remove(idx).expect("Index is valid");
+            self.stats.ping = Some(now.duration_since(sent));
+            self.push_result(true);
+        }
+    }
+
+    /// Expire lost pings and update traffic rates
+    pub fn update(&mut self, traffic: NetStats, now: Instant) -> NetClientStats {
+        while let Some(&(_, sent)) = self.pending.front() {
+            if now.duration_since(sent) < Self::PING_TIMEOUT {
+                break;
+            }
+
+            self.pending.pop_front();
+            self.push_result(false);
+        }
+
+        let elapsed = now.duration_since(self.last_rate);
+        if elapsed >= Self::RATE_INTERVAL {
+            let secs = elapsed.as_secs_f64();
+            self.stats.bytes_in_per_sec =
+                ((traffic.bytes_received - self.last_traffic.bytes_received) as f64 / secs) as u64;
+            self.stats.bytes_out_per_sec =
+                ((traffic.bytes_sent - self.last_traffic.bytes_sent) as f64 / secs) as u64;
+            self.last_traffic = traffic;
+            self.last_rate = now;
+        }
+
+        self.stats.traffic = traffic;
+        self.stats
+    }
+
+    fn push_result(&mut self, answered: bool) {
+        if self.results.len() >= Self::LOSS_WINDOW {
+            self.results.pop_front();
+        }
+        self.results.push_back(answered);
+
+        let lost = self.results.iter().filter(|&&answered| !answered).count();
+        self.stats.packet_loss = lost as f32 / self.results.len() as f32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use common::net::connection::NetStats;
+
+    use super::StatsTracker;
+
+    #[test]
+    fn ping_and_loss() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut tracker = StatsTracker::new(start);
+
+        let first = tracker.ping(at(0)).unwrap();
+        assert_eq!(tracker.ping(at(500)), None);
+        tracker.ping(at(1000)).unwrap();
+
+        tracker.pong(first, at(40));
+        // Second ping is never answered
+        let stats = tracker.update(NetStats::default(), at(5000));
+
+        assert_eq!(stats.ping, Some(Duration::from_millis(40)));
+        assert_eq!(stats.packet_loss, 0.5);
+    }
+}
@@ -0,0 +1,401 @@
+use std::{mem::size_of, ops::Range};
+
+use bytemuck::cast_slice;
+use common_log::span;
+use tracing::debug;
+use wgpu::{
+    Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Device, Queue,
+};
+
+use super::primitives::terrain::TerrainVertex;
+
+/// First-fit allocator of ranges inside a fixed capacity. Freed ranges are merged with adjacent
+/// free ranges
+#[derive(Clone, Debug)]
+pub struct RangeAllocator {
+    capacity: u32,
+    /// Sorted, non-adjacent free ranges
+    free: Vec<Range<u32>>,
+}
+
+impl RangeAllocator {
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            free: std::iter::once(0..capacity).collect(),
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Number of allocated units
+    pub fn used(&self) -> u32 {
+        self.capacity
+            - self
+                .free
+                .iter()
+                .map(|range| range.len() as u32)
+                .sum::<u32>()
+    }
+
+    pub fn alloc(&mut self, size: u32) -> Option<Range<u32>> {
+        if size == 0 {
+            return Some(0..0);
+        }
+
+        let (i, range) = self
+            .free
+            .iter_mut()
+            .enumerate()
+            .find(|(_, range)| range.len() as u32 >= size)?;
+
+        let allocated = range.start..range.start + size;
+        range.start += size;
+        if range.start == range.end {
+            self.free.remove(i);
+        }
+
+        Some(allocated)
+    }
+
+    pub fn free(&mut self, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let i = self.free.partition_point(|free| free.start < range.start);
+        let merge_prev = i > 0 && self.free[i - 1].end == range.start;
+        let merge_next = i < self.free.len() && self.free[i].start == range.end;
+
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                self.free[i - 1].end = self.free[i].end;
+                self.free.remove(i);
+            }
+            (true, false) => self.free[i - 1].end = range.end,
+            (false, true) => self.free[i].start = range.start,
+            (false, false) => self.free.insert(i, range),
+        }
+    }
+}
+
+/// Location of a mesh in [`TerrainArena`]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ArenaSlice {
+    pub page: usize,
+    pub vertices: Range<u32>,
+    pub indices: Range<u32>,
+}
+
+impl ArenaSlice {
+    /// Size of the mesh data (bytes)
+    pub fn size(&self) -> u64 {
+        (self.vertices.len() * size_of::<TerrainVertex>() + self.indices.len() * size_of::<u32>())
+            as u64
+    }
+}
+
+/// Pair of large vertex and index buffers meshes are sub-allocated from
+pub struct ArenaPage {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    vertices: RangeAllocator,
+    indices: RangeAllocator,
+}
+
+impl ArenaPage {
+    fn new(device: &Device, vertices: u32, indices: u32) -> Self {
+        debug!(vertices, indices, "Creating terrain arena page");
+
+        let usage = BufferUsages::COPY_DST | BufferUsages::COPY_SRC;
+        Self {
+            vertex_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("Buffer: TerrainArena vertices"),
+                size: vertices as BufferAddress * size_of::<TerrainVertex>() as BufferAddress,
+                usage: usage | BufferUsages::VERTEX,
+                mapped_at_creation: false,
+            }),
+            index_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("Buffer: TerrainArena indices"),
+                size: indices as BufferAddress * size_of::<u32>() as BufferAddress,
+                usage: usage | BufferUsages::INDEX,
+                mapped_at_creation: false,
+            }),
+            vertices: RangeAllocator::new(vertices),
+            indices: RangeAllocator::new(indices),
+        }
+    }
+
+    fn alloc(&mut self, vertices: u32, indices: u32) -> Option<(Range<u32>, Range<u32>)> {
+        let vertex_range = self.vertices.alloc(vertices)?;
+        match self.indices.alloc(indices) {
+            Some(index_range) => Some((vertex_range, index_range)),
+            None => {
+                self.vertices.free(vertex_range);
+                None
+            }
+        }
+    }
+
+    /// Share of the page used by meshes (by vertices)
+    fn occupancy(&self) -> f32 {
+        self.vertices.used() as f32 / self.vertices.capacity() as f32
+    }
+
+    fn is_empty(&self) -> bool {
+        self.vertices.used() == 0 && self.indices.used() == 0
+    }
+
+    /// Size of the page buffers (bytes)
+    fn size(&self) -> u64 {
+        self.vertex_buffer.size() + self.index_buffer.size()
+    }
+}
+
+/// Terrain meshes packed into a few large buffers.
+///
+/// Avoids creating two buffers per chunk and allows drawing many chunks without rebinding
+pub struct TerrainArena {
+    /// Freed pages are `None`, so slices keep valid page indices
+    pages: Vec<Option<ArenaPage>>,
+}
+
+impl TerrainArena {
+    /// Vertices per page. Larger meshes get their own page
+    pub const PAGE_VERTICES: u32 = 1 << 18;
+    /// Indices per page (6 indices per 4 vertices of a quad)
+    pub const PAGE_INDICES: u32 = Self::PAGE_VERTICES / 2 * 3;
+    /// Pages used less than this are emptied by [`Self::defragment`]
+    pub const DEFRAGMENT_OCCUPANCY: f32 = 0.25;
+
+    pub const fn new() -> Self {
+        Self { pages: Vec::new() }
+    }
+
+    pub fn page(&self, index: usize) -> Option<&ArenaPage> {
+        self.pages.get(index).and_then(Option::as_ref)
+    }
+
+    /// Number of allocated pages
+    pub fn page_count(&self) -> usize {
+        self.pages.iter().flatten().count()
+    }
+
+    /// Size of all pages (bytes)
+    pub fn capacity(&self) -> u64 {
+        self.pages.iter().flatten().map(ArenaPage::size).sum()
+    }
+
+    /// Allocate space for a mesh without uploading it (e.g. the mesh is copied on the GPU). A new
+    /// page is created if it doesn't fit into existing ones
+    pub fn reserve(&mut self, device: &Device, vertex_count: u32, index_count: u32) -> ArenaSlice {
+        match self.alloc_in_pages(vertex_count, index_count, None) {
+            Some(slice) => slice,
+            None => {
+                let mut page = ArenaPage::new(
+                    device,
+                    vertex_count.max(Self::PAGE_VERTICES),
+                    index_count.max(Self::PAGE_INDICES),
+                );
+                let (vertices, indices) = page.alloc(vertex_count, index_count).unwrap();
+
+                let index = match self.pages.iter().position(Option::is_none) {
+                    Some(index) => {
+                        self.pages[index] = Some(page);
+                        index
+                    }
+                    None => {
+                        self.pages.push(Some(page));
+                        self.pages.len() - 1
+                    }
+                };
+
+                ArenaSlice {
+                    page: index,
+                    vertices,
+                    indices,
+                }
+            }
+        }
+    }
+
+    /// Upload the mesh. A new page is created if it doesn't fit into existing ones
+    pub fn alloc(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        vertices: &[TerrainVertex],
+        indices: &[u32],
+    ) -> ArenaSlice {
+        let slice = self.reserve(device, vertices.len() as u32, indices.len() as u32);
+
+        let page = self.pages[slice.page].as_ref().unwrap();
+        if !vertices.is_empty() {
+            queue.write_buffer(
+                &page.vertex_buffer,
+                (slice.vertices.start as usize * size_of::<TerrainVertex>()) as BufferAddress,
+                cast_slice(vertices),
+            );
+        }
+        if !indices.is_empty() {
+            queue.write_buffer(
+                &page.index_buffer,
+                (slice.indices.start as usize * size_of::<u32>()) as BufferAddress,
+                cast_slice(indices),
+            );
+        }
+
+        slice
+    }
+
+    /// Allocate in existing pages (except `skip`)
+    fn alloc_in_pages(
+        &mut self,
+        vertices: u32,
+        indices: u32,
+        skip: Option<usize>,
+    ) -> Option<ArenaSlice> {
+        self.pages
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != skip)
+            .find_map(|(index, page)| {
+                let (vertices, indices) = page.as_mut()?.alloc(vertices, indices)?;
+                Some(ArenaSlice {
+                    page: index,
+                    vertices,
+                    indices,
+                })
+            })
+    }
+
+    /// Release the mesh. Empty pages are dropped
+    pub fn free(&mut self, slice: &ArenaSlice) {
+        let Some(page) = self.pages.get_mut(slice.page).and_then(Option::as_mut) else {
+            return;
+        };
+
+        page.vertices.free(slice.vertices.clone());
+        page.indices.free(slice.indices.clone());
+
+        if page.is_empty() {
+            self.pages[slice.page] = None;
+        }
+    }
+
+    /// Drop all pages. Slices become invalid
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Move meshes out of the least occupied page into free space of other pages, so the page can
+    /// be dropped. Slices of the moved meshes are updated
+    pub fn defragment<'a>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        slices: impl Iterator<Item = &'a mut ArenaSlice>,
+    ) {
+        if self.page_count() < 2 {
+            return;
+        }
+
+        let Some(source) = self
+            .pages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, page)| Some((index, page.as_ref()?.occupancy())))
+            .filter(|&(_, occupancy)| occupancy < Self::DEFRAGMENT_OCCUPANCY)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+        else {
+            return;
+        };
+
+        span!(_guard, "defragment", "TerrainArena::defragment");
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("CommandEncoder: TerrainArena defragment"),
+        });
+        let mut moved = 0;
+
+        for slice in slices.filter(|slice| slice.page == source) {
+            let (vertices, indices) = (slice.vertices.len() as u32, slice.indices.len() as u32);
+            // Stop when other pages are full, the rest is moved on the next pass
+            let Some(target) = self.alloc_in_pages(vertices, indices, Some(source)) else {
+                break;
+            };
+
+            let (from, to) = (
+                self.pages[source].as_ref().unwrap(),
+                self.pages[target.page].as_ref().unwrap(),
+            );
+            let vertex_size = size_of::<TerrainVertex>() as BufferAddress;
+            let index_size = size_of::<u32>() as BufferAddress;
+            encoder.copy_buffer_to_buffer(
+                &from.vertex_buffer,
+                slice.vertices.start as BufferAddress * vertex_size,
+                &to.vertex_buffer,
+                target.vertices.start as BufferAddress * vertex_size,
+                vertices as BufferAddress * vertex_size,
+            );
+            encoder.copy_buffer_to_buffer(
+                &from.index_buffer,
+                slice.indices.start as BufferAddress * index_size,
+                &to.index_buffer,
+                target.indices.start as BufferAddress * index_size,
+                indices as BufferAddress * index_size,
+            );
+
+            self.free(slice);
+            *slice = target;
+            moved += 1;
+        }
+
+        if moved > 0 {
+            debug!(moved, page = source, "Defragmented terrain arena");
+            queue.submit(Some(encoder.finish()));
+        }
+    }
+}
+
+impl Default for TerrainArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::RangeAllocator;
+
+    #[test]
+    fn first_fit_and_merge() {
+        let mut allocator = RangeAllocator::new(100);
+
+        let a = allocator.alloc(30).unwrap();
+        let b = allocator.alloc(30).unwrap();
+        let c = allocator.alloc(30).unwrap();
+        assert_eq!((a.clone(), b.clone(), c.clone()), (0..30, 30..60, 60..90));
+        assert_eq!(allocator.alloc(20), None);
+        assert_eq!(allocator.used(), 90);
+
+        // Hole is reused by smaller allocations
+        allocator.free(b);
+        assert_eq!(allocator.alloc(10), Some(30..40));
+        assert_eq!(allocator.alloc(0), Some(0..0));
+
+        // Neighbors are merged back into a single range
+        allocator.free(30..40);
+        allocator.free(a);
+        allocator.free(c);
+        assert_eq!(allocator.used(), 0);
+        assert_eq!(allocator.alloc(100), Some(0..100));
+    }
+}
@@ -1,11 +1,14 @@
-use std::{marker::PhantomData, mem::size_of, ops::Deref};
+use std::{marker::PhantomData, mem::size_of, num::NonZeroU64, ops::Deref};
 
-use bytemuck::{cast_slice, Pod};
+use bytemuck::{bytes_of, cast_slice, Pod};
 use wgpu::{
-    util::{BufferInitDescriptor, DeviceExt},
-    BufferDescriptor, BufferUsages, Device, Queue,
+    util::{align_to, BufferInitDescriptor, DeviceExt},
+    BindingResource, BufferBinding, BufferDescriptor, BufferUsages, Device, DynamicOffset, Queue,
+    COPY_BUFFER_ALIGNMENT,
 };
 
+use super::error::RenderError;
+
 pub trait Bufferable {
     const LABEL: &'static str;
 }
@@ -50,31 +53,66 @@ impl<T: Copy + Pod + Bufferable> Buffer<T> {
 // Dynamic Buffer
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-pub struct DynamicBuffer<T: Copy + Pod + Bufferable>(Buffer<T>);
+pub struct DynamicBuffer<T: Copy + Pod + Bufferable> {
+    inner: Buffer<T>,
+    usage: BufferUsages,
+}
 
 impl<T: Copy + Pod + Bufferable> DynamicBuffer<T> {
     pub fn new(device: &Device, length: usize, usage: BufferUsages) -> Self {
-        Self(Buffer {
+        Self {
+            inner: Self::allocate(device, length, usage),
+            usage,
+        }
+    }
+
+    fn allocate(device: &Device, length: usize, usage: BufferUsages) -> Buffer<T> {
+        Buffer {
             buffer: device.create_buffer(&BufferDescriptor {
                 label: Some(T::LABEL),
-                size: size_of::<T>() as u64 * length as u64, // BUG
+                // Buffer copies require sizes aligned to `COPY_BUFFER_ALIGNMENT`
+                size: align_to(size_of::<T>() as u64 * length as u64, COPY_BUFFER_ALIGNMENT),
                 usage: usage | BufferUsages::COPY_DST,
                 mapped_at_creation: false,
             }),
             length,
             phantom: PhantomData,
-        })
+        }
     }
 
-    /// Update GPU-size value
-    pub fn update(&self, queue: &Queue, values: &[T], offset: usize) {
+    /// Reallocate buffer if it can't hold `length` elements. Capacity grows to the next power of
+    /// two.
+    ///
+    /// Returns `true` if buffer has been reallocated. In that case contents are lost and bind
+    /// groups referencing the buffer have to be recreated
+    pub fn ensure_capacity(&mut self, device: &Device, length: usize) -> bool {
+        if length <= self.inner.length {
+            return false;
+        }
+
+        self.inner = Self::allocate(device, length.next_power_of_two(), self.usage);
+        true
+    }
+
+    /// Update GPU-size value. Fails if values don't fit into the buffer
+    pub fn update(&self, queue: &Queue, values: &[T], offset: usize) -> Result<(), RenderError> {
+        let required = offset + values.len();
+        if required > self.inner.length {
+            return Err(RenderError::BufferOverflow {
+                required,
+                capacity: self.inner.length,
+            });
+        }
+
         if !values.is_empty() {
             queue.write_buffer(
-                &self.buffer,
+                &self.inner.buffer,
                 offset as u64 * size_of::<T>() as u64,
                 cast_slice(values),
             );
         }
+
+        Ok(())
     }
 }
 
@@ -82,7 +120,7 @@ impl<T: Copy + Pod + Bufferable> Deref for DynamicBuffer<T> {
     type Target = Buffer<T>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 
@@ -102,7 +140,7 @@ impl<T: Copy + Pod + Bufferable> Consts<T> {
         }
     }
 
-    pub fn update(&self, queue: &Queue, values: &[T], offset: usize) {
+    pub fn update(&self, queue: &Queue, values: &[T], offset: usize) -> Result<(), RenderError> {
         self.buffer.update(queue, values, offset)
     }
 
@@ -110,3 +148,103 @@ impl<T: Copy + Pod + Bufferable> Consts<T> {
         &self.buffer.buffer
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Dynamic Offset Uniform Buffer
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Array of uniform blocks in a single buffer. Blocks are bound one at a time with a dynamic
+/// offset (see [`DynamicConsts::offset`]), so per-object uniforms share a single bind group
+pub struct DynamicConsts<T: Copy + Pod + Bufferable> {
+    buffer: wgpu::Buffer,
+    length: usize,
+    /// Distance between blocks in bytes. Aligned to `min_uniform_buffer_offset_alignment`
+    stride: u64,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Copy + Pod + Bufferable> DynamicConsts<T> {
+    pub fn new(device: &Device, length: usize) -> Self {
+        let stride = align_to(
+            size_of::<T>() as u64,
+            device.limits().min_uniform_buffer_offset_alignment as u64,
+        );
+
+        Self {
+            buffer: Self::allocate(device, length, stride),
+            length,
+            stride,
+            phantom: PhantomData,
+        }
+    }
+
+    fn allocate(device: &Device, length: usize, stride: u64) -> wgpu::Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some(T::LABEL),
+            size: stride * length as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Dynamic offset of the block at `index`
+    pub fn offset(&self, index: usize) -> DynamicOffset {
+        (index as u64 * self.stride) as DynamicOffset
+    }
+
+    /// Reallocate buffer if it can't hold `length` blocks. Capacity grows to the next power of two.
+    ///
+    /// Returns `true` if buffer has been reallocated. In that case contents are lost and bind
+    /// groups referencing the buffer have to be recreated
+    pub fn ensure_capacity(&mut self, device: &Device, length: usize) -> bool {
+        if length <= self.length {
+            return false;
+        }
+
+        self.length = length.next_power_of_two();
+        self.buffer = Self::allocate(device, self.length, self.stride);
+        true
+    }
+
+    /// Write blocks starting from `offset` (in blocks). Fails if values don't fit into the buffer
+    pub fn update(&self, queue: &Queue, values: &[T], offset: usize) -> Result<(), RenderError> {
+        let required = offset + values.len();
+        if required > self.length {
+            return Err(RenderError::BufferOverflow {
+                required,
+                capacity: self.length,
+            });
+        }
+
+        if !values.is_empty() {
+            // Blocks are padded up to the stride
+            let mut data = vec![0; values.len() * self.stride as usize];
+            data.chunks_exact_mut(self.stride as usize)
+                .zip(values)
+                .for_each(|(block, value)| {
+                    block[..size_of::<T>()].copy_from_slice(bytes_of(value))
+                });
+
+            queue.write_buffer(&self.buffer, offset as u64 * self.stride, &data);
+        }
+
+        Ok(())
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Binding of a single block. The block is selected with a dynamic offset
+    pub fn binding(&self) -> BindingResource<'_> {
+        BindingResource::Buffer(BufferBinding {
+            buffer: &self.buffer,
+            offset: 0,
+            size: NonZeroU64::new(size_of::<T>() as u64),
+        })
+    }
+}
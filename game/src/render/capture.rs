@@ -0,0 +1,460 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    num::NonZeroU32,
+    path::PathBuf,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread,
+};
+
+use tracing::{info, warn};
+use wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, Extent3d, ImageCopyBuffer,
+    ImageCopyTexture, ImageDataLayout, MapMode, Origin3d, SurfaceConfiguration, Texture,
+    TextureAspect, TextureFormat, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use super::error::RenderError;
+
+/// Where captured frames go
+#[derive(Clone, Debug)]
+pub enum CaptureOutput {
+    /// Numbered PNG files in the directory
+    Png(PathBuf),
+    /// Raw RGBA frames written to stdin of the command (e.g. `ffmpeg`). `{width}` and `{height}`
+    /// in arguments are replaced with the frame size
+    Pipe(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct CaptureSettings {
+    pub output: CaptureOutput,
+    /// Capture every Nth presented frame
+    pub every: u32,
+}
+
+/// Capture progress
+#[derive(Clone, Copy, Default, Debug)]
+pub struct CaptureStats {
+    /// Frames handed to the writer
+    pub captured: u64,
+    /// Frames skipped because staging buffers or the writer queue were full
+    pub dropped: u64,
+}
+
+/// Frame copied into a staging buffer and waiting for it to be mapped
+struct PendingFrame {
+    index: u64,
+    staging: Staging,
+    /// One of `MAP_*` states
+    state: Arc<AtomicU8>,
+}
+
+struct Staging {
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+    padded_row: u32,
+}
+
+/// Frame ready to be written
+struct CapturedFrame {
+    index: u64,
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+/// Copies presented frames to staging buffers and hands them over to the writer thread.
+///
+/// Number of staging buffers and writer queue are bounded, so frames are dropped instead of
+/// stalling the game when the disk or encoder can't keep up
+pub struct FrameCapture {
+    every: u32,
+    /// Swap red and blue channels (BGRA surface)
+    bgra: bool,
+    /// Presented frames since the capture has started
+    frame: u64,
+    free: Vec<Staging>,
+    /// Staging buffer with the current frame copy (not submitted yet)
+    copied: Option<(u64, Staging)>,
+    pending: VecDeque<PendingFrame>,
+    writer: SyncSender<CapturedFrame>,
+    stats: CaptureStats,
+}
+
+impl FrameCapture {
+    /// Max number of frames being read back from GPU
+    pub const MAX_STAGING_BUFFERS: usize = 3;
+    /// Max number of frames waiting to be written
+    pub const WRITER_QUEUE: usize = 8;
+
+    const MAP_PENDING: u8 = 0;
+    const MAP_DONE: u8 = 1;
+    const MAP_FAILED: u8 = 2;
+
+    pub fn new(settings: CaptureSettings, format: TextureFormat) -> Result<Self, RenderError> {
+        let bgra = match format {
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            format => return Err(RenderError::UnsupportedCaptureFormat(format)),
+        };
+
+        if let CaptureOutput::Png(dir) = &settings.output {
+            fs::create_dir_all(dir)?;
+        }
+
+        let (writer, rx) = sync_channel(Self::WRITER_QUEUE);
+        let output = settings.output.clone();
+        thread::Builder::new()
+            .name("frame-capture".to_string())
+            .spawn(move || Self::writer(output, rx))?;
+
+        info!(?settings, "Frame capture started");
+
+        Ok(Self {
+            every: settings.every.max(1),
+            bgra,
+            frame: 0,
+            free: Vec::new(),
+            copied: None,
+            pending: VecDeque::new(),
+            writer,
+            stats: CaptureStats::default(),
+        })
+    }
+
+    pub fn stats(&self) -> CaptureStats {
+        self.stats
+    }
+
+    /// Record copy of the frame texture. Called before the frame is submitted
+    pub fn copy(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        texture: &Texture,
+        config: &SurfaceConfiguration,
+    ) {
+        let index = self.frame;
+        self.frame += 1;
+        if !index.is_multiple_of(self.every as u64) {
+            return;
+        }
+
+        if self.pending.len() >= Self::MAX_STAGING_BUFFERS {
+            self.stats.dropped += 1;
+            return;
+        }
+
+        let staging = match self.free.pop() {
+            Some(staging) if staging.width == config.width && staging.height == config.height => {
+                staging
+            }
+            // Resolution has changed
+            _ => Self::staging(device, config.width, config.height),
+        };
+
+        encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            ImageCopyBuffer {
+                buffer: &staging.buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(staging.padded_row),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: staging.width,
+                height: staging.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.copied = Some((index, staging));
+    }
+
+    /// Start reading back the copied frame. Called after the frame is submitted
+    pub fn submitted(&mut self) {
+        let Some((index, staging)) = self.copied.take() else {
+            return;
+        };
+
+        let state = Arc::new(AtomicU8::new(Self::MAP_PENDING));
+        {
+            let state = state.clone();
+            staging
+                .buffer
+                .slice(..)
+                .map_async(MapMode::Read, move |result| {
+                    let mapped = match result {
+                        Ok(()) => Self::MAP_DONE,
+                        Err(err) => {
+                            warn!(%err, "Failed to map capture buffer");
+                            Self::MAP_FAILED
+                        }
+                    };
+                    state.store(mapped, Ordering::Release);
+                });
+        }
+
+        self.pending.push_back(PendingFrame {
+            index,
+            staging,
+            state,
+        });
+    }
+
+    /// Pass read back frames to the writer
+    pub fn collect(&mut self) {
+        while self
+            .pending
+            .front()
+            .is_some_and(|frame| frame.state.load(Ordering::Acquire) != Self::MAP_PENDING)
+        {
+            let PendingFrame {
+                index,
+                staging,
+                state,
+            } = self.pending.pop_front().unwrap();
+            if state.load(Ordering::Acquire) == Self::MAP_FAILED {
+                self.stats.dropped += 1;
+                continue;
+            }
+
+            let row = staging.width as usize * 4;
+            let mut rgba = Vec::with_capacity(row * staging.height as usize);
+            {
+                let data = staging.buffer.slice(..).get_mapped_range();
+                data.chunks_exact(staging.padded_row as usize)
+                    .for_each(|padded| rgba.extend_from_slice(&padded[..row]));
+            }
+            staging.buffer.unmap();
+
+            if self.bgra {
+                rgba.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+            }
+
+            let frame = CapturedFrame {
+                index,
+                width: staging.width,
+                height: staging.height,
+                rgba,
+            };
+            match self.writer.try_send(frame) {
+                Ok(()) => self.stats.captured += 1,
+                Err(TrySendError::Full(_)) => self.stats.dropped += 1,
+                Err(TrySendError::Disconnected(_)) => {
+                    warn!("Frame capture writer has stopped");
+                    self.stats.dropped += 1;
+                }
+            }
+
+            self.free.push(staging);
+        }
+    }
+
+    fn staging(device: &Device, width: u32, height: u32) -> Staging {
+        let padded_row =
+            (width * 4).div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        Staging {
+            buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("CaptureStaging"),
+                size: padded_row as u64 * height as u64,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            width,
+            height,
+            padded_row,
+        }
+    }
+
+    /// Writer thread. Stops when the capture is dropped and the queue is drained
+    fn writer(output: CaptureOutput, rx: Receiver<CapturedFrame>) {
+        let mut pipe: Option<(Child, ChildStdin, u32, u32)> = None;
+
+        for frame in rx {
+            let result = match &output {
+                CaptureOutput::Png(dir) => {
+                    let path = dir.join(format!("frame_{:06}.png", frame.index));
+                    File::create(&path).and_then(|file| {
+                        let mut file = BufWriter::new(file);
+                        file.write_all(&encode_png(frame.width, frame.height, &frame.rgba))?;
+                        file.flush()
+                    })
+                }
+                CaptureOutput::Pipe(command) => {
+                    if pipe.is_none() {
+                        match spawn_encoder(command, frame.width, frame.height) {
+                            Ok((child, stdin)) => {
+                                pipe = Some((child, stdin, frame.width, frame.height))
+                            }
+                            Err(err) => {
+                                warn!(%err, %command, "Failed to start encoder");
+                                return;
+                            }
+                        }
+                    }
+
+                    match &mut pipe {
+                        Some((_, stdin, width, height))
+                            if *width == frame.width && *height == frame.height =>
+                        {
+                            stdin.write_all(&frame.rgba)
+                        }
+                        // Raw video stream can't change its size
+                        _ => Ok(()),
+                    }
+                }
+            };
+
+            if let Err(err) = result {
+                warn!(%err, index = frame.index, "Failed to write captured frame");
+            }
+        }
+
+        if let Some((mut child, stdin, ..)) = pipe {
+            drop(stdin);
+            if let Err(err) = child.wait() {
+                warn!(%err, "Encoder failed");
+            }
+        }
+        info!("Frame capture finished");
+    }
+}
+
+fn spawn_encoder(command: &str, width: u32, height: u32) -> io::Result<(Child, ChildStdin)> {
+    let mut args = command.split_whitespace().map(|arg| {
+        arg.replace("{width}", &width.to_string())
+            .replace("{height}", &height.to_string())
+    });
+    let program = args
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Empty encoder command"))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    let stdin = child.stdin.take().expect("Encoder stdin must be piped");
+
+    Ok((child, stdin))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// PNG
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Encode 8-bit RGBA image as PNG. Image data isn't compressed (stored deflate blocks), which is
+/// fast and good enough for frames which are going to be re-encoded anyway
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = u16::MAX as usize;
+
+    // Every scanline starts with filter type (0 - None)
+    let row = width as usize * 4;
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    rgba.chunks_exact(row).for_each(|line| {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    });
+
+    // zlib stream with stored blocks
+    let mut zlib = Vec::with_capacity(raw.len() + raw.len() / MAX_BLOCK * 5 + 16);
+    zlib.extend_from_slice(&[0x78, 0x01]);
+    let blocks = raw.len().div_ceil(MAX_BLOCK).max(1);
+    for i in 0..blocks {
+        let block = &raw[(i * MAX_BLOCK).min(raw.len())..((i + 1) * MAX_BLOCK).min(raw.len())];
+        let len = block.len() as u16;
+        zlib.push((i + 1 == blocks) as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8-bit depth, RGBA, deflate, no filter, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = Vec::with_capacity(zlib.len() + 64);
+    png.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &zlib);
+    png_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(u32::MAX, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+
+    let (a, b) = data
+        .chunks(5552)
+        .fold((1u32, 0u32), |(mut a, mut b), chunk| {
+            chunk.iter().for_each(|&byte| {
+                a += byte as u32;
+                b += a;
+            });
+            (a % MOD, b % MOD)
+        });
+
+    (b << 16) | a
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, encode_png};
+
+    #[test]
+    fn png_encoding() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+
+        let png = encode_png(2, 1, &[255, 0, 0, 255, 0, 255, 0, 255]);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+        // Signature, IHDR, IDAT with 2 header bytes + 5 bytes block header + 9 bytes data + adler
+        assert_eq!(png.len(), 8 + 25 + 12 + 2 + 5 + 9 + 4 + 12);
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    mem::size_of,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use bytemuck::{cast_slice, pod_read_unaligned};
+use common::{block::Block, coord::ChunkCoord, palette::Palette};
+use common_log::span;
+use tracing::warn;
+use wgpu::{BufferAddress, CommandEncoderDescriptor, ComputePassDescriptor, Maintain, MapMode};
+
+use super::{
+    arena::{ArenaSlice, TerrainArena},
+    buffer::Consts,
+    pipelines::meshing::{MeshingBindGroup, MeshingBuffers, MeshingPalette, MeshingPipeline},
+    primitives::terrain::TerrainVertex,
+    renderer::Renderer,
+};
+
+/// Readback state of a meshing job
+const MAP_PENDING: u8 = 0;
+const MAP_DONE: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+struct MeshingJob {
+    coord: ChunkCoord,
+    map_state: Arc<AtomicU8>,
+}
+
+struct MeshingSlot {
+    buffers: MeshingBuffers,
+    bind_group: MeshingBindGroup,
+    job: Option<MeshingJob>,
+}
+
+/// Builds terrain meshes in a compute shader (experimental).
+///
+/// Meshes are written into a small pool of scratch buffers. Once the number of generated quads is
+/// read back, the mesh is copied into [`TerrainArena`] on the GPU. Unlike the CPU mesher there is
+/// no color variation and biome tint, and chunks with cross blocks (plants) or fluids aren't
+/// supported
+pub struct ComputeMesher {
+    palette: Consts<MeshingPalette>,
+    slots: Vec<MeshingSlot>,
+}
+
+impl ComputeMesher {
+    /// Max number of chunks meshed at the same time
+    pub const SLOTS: usize = 4;
+
+    /// `None` if compute shaders aren't supported
+    pub fn new(renderer: &Renderer, palette: &Palette) -> Option<Self> {
+        renderer.meshing_pipeline()?;
+
+        let palette = renderer.create_consts(&[MeshingPalette::new(palette)]);
+        let slots = (0..Self::SLOTS)
+            .map(|_| {
+                let buffers = MeshingBuffers::new(&renderer.device);
+                MeshingSlot {
+                    bind_group: renderer.bind_meshing(&palette, &buffers),
+                    buffers,
+                    job: None,
+                }
+            })
+            .collect();
+
+        Some(Self { palette, slots })
+    }
+
+    /// Colors of chunks submitted after this call. Running jobs may use either palette
+    pub fn set_palette(&self, renderer: &Renderer, palette: &Palette) {
+        if let Err(err) = renderer.update_consts(&self.palette, &[MeshingPalette::new(palette)]) {
+            warn!(%err, "Failed to update meshing palette");
+        }
+    }
+
+    /// Chunk can be meshed by the compute shader
+    pub fn supports(blocks: &[Block]) -> bool {
+        !blocks.iter().any(|block| block.cross() || block.liquid())
+    }
+
+    pub fn has_free_slot(&self) -> bool {
+        self.slots.iter().any(|slot| slot.job.is_none())
+    }
+
+    /// Chunks being meshed
+    pub fn pending(&self) -> impl Iterator<Item = ChunkCoord> + '_ {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.job.as_ref().map(|job| job.coord))
+    }
+
+    /// Start meshing the chunk. Returns `false` if all slots are busy
+    pub fn submit(&mut self, renderer: &Renderer, coord: ChunkCoord, blocks: &[Block]) -> bool {
+        span!(_guard, "submit", "ComputeMesher::submit");
+
+        let Some(pipeline) = renderer.meshing_pipeline() else {
+            return false;
+        };
+        let Some(slot) = self.slots.iter_mut().find(|slot| slot.job.is_none()) else {
+            return false;
+        };
+
+        let blocks = blocks
+            .iter()
+            .map(|block| block.id() as u32)
+            .collect::<Vec<_>>();
+        renderer
+            .queue
+            .write_buffer(&slot.buffers.blocks, 0, cast_slice(&blocks));
+        renderer.queue.write_buffer(
+            &slot.buffers.counter,
+            0,
+            &[0; MeshingBuffers::COUNTER_SIZE as usize],
+        );
+
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("CommandEncoder: Meshing"),
+            });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("ComputePass: Meshing"),
+            });
+            pass.set_pipeline(&pipeline.inner);
+            pass.set_bind_group(0, &slot.bind_group.inner, &[]);
+            pass.dispatch_workgroups(MeshingPipeline::workgroups(), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(
+            &slot.buffers.counter,
+            0,
+            &slot.buffers.readback,
+            0,
+            MeshingBuffers::COUNTER_SIZE,
+        );
+        renderer.queue.submit(Some(encoder.finish()));
+
+        let map_state = Arc::new(AtomicU8::new(MAP_PENDING));
+        let callback_state = map_state.clone();
+        slot.buffers
+            .readback
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                callback_state.store(
+                    if result.is_ok() { MAP_DONE } else { MAP_FAILED },
+                    Ordering::Release,
+                );
+            });
+
+        slot.job = Some(MeshingJob { coord, map_state });
+        true
+    }
+
+    /// Copy finished meshes into the arena. Returns `None` slices for failed jobs
+    pub fn collect(
+        &mut self,
+        renderer: &Renderer,
+        arena: &mut TerrainArena,
+    ) -> Vec<(ChunkCoord, Option<ArenaSlice>)> {
+        if self.slots.iter().all(|slot| slot.job.is_none()) {
+            return Vec::new();
+        }
+
+        span!(_guard, "collect", "ComputeMesher::collect");
+        renderer.device.poll(Maintain::Poll);
+
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("CommandEncoder: Meshing copy"),
+            });
+        let mut copied = false;
+
+        let finished = self
+            .slots
+            .iter_mut()
+            .filter(|slot| {
+                slot.job
+                    .as_ref()
+                    .is_some_and(|job| job.map_state.load(Ordering::Acquire) != MAP_PENDING)
+            })
+            .map(|slot| {
+                let job = slot.job.take().unwrap();
+                if job.map_state.load(Ordering::Acquire) == MAP_FAILED {
+                    warn!(coord = ?job.coord, "Failed to read compute mesh");
+                    return (job.coord, None);
+                }
+
+                let quads = {
+                    let range = slot.buffers.readback.slice(..).get_mapped_range();
+                    pod_read_unaligned::<u32>(&range[..size_of::<u32>()])
+                };
+                slot.buffers.readback.unmap();
+
+                let (vertices, indices) = (quads * 4, quads * 6);
+                let slice = arena.reserve(&renderer.device, vertices, indices);
+                if quads > 0 {
+                    let page = arena.page(slice.page).unwrap();
+                    let vertex_size = size_of::<TerrainVertex>() as BufferAddress;
+                    let index_size = size_of::<u32>() as BufferAddress;
+                    encoder.copy_buffer_to_buffer(
+                        &slot.buffers.vertices,
+                        0,
+                        &page.vertex_buffer,
+                        slice.vertices.start as BufferAddress * vertex_size,
+                        vertices as BufferAddress * vertex_size,
+                    );
+                    encoder.copy_buffer_to_buffer(
+                        &slot.buffers.indices,
+                        0,
+                        &page.index_buffer,
+                        slice.indices.start as BufferAddress * index_size,
+                        indices as BufferAddress * index_size,
+                    );
+                    copied = true;
+                }
+
+                (job.coord, Some(slice))
+            })
+            .collect::<Vec<_>>();
+
+        if copied {
+            renderer.queue.submit(Some(encoder.finish()));
+        }
+
+        finished
+    }
+}
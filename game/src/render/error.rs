@@ -1,5 +1,7 @@
+use std::io;
+
 use thiserror::Error;
-use wgpu::{RequestDeviceError, SurfaceError};
+use wgpu::{RequestDeviceError, SurfaceError, TextureFormat};
 
 /// Represents one of renderer errors
 #[derive(Error, Debug)]
@@ -12,6 +14,12 @@ pub enum RenderError {
     NoCompatibleSurfaceFormat,
     #[error("Surface error: {0}")]
     SurfaceError(SurfaceError),
+    #[error("Buffer overflow: {required} elements don't fit into {capacity}")]
+    BufferOverflow { required: usize, capacity: usize },
+    #[error("Frame capture doesn't support {0:?} surface format")]
+    UnsupportedCaptureFormat(TextureFormat),
+    #[error("IO error: {0}")]
+    Io(io::Error),
 }
 
 impl From<RequestDeviceError> for RenderError {
@@ -25,3 +33,9 @@ impl From<SurfaceError> for RenderError {
         Self::SurfaceError(err)
     }
 }
+
+impl From<io::Error> for RenderError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -0,0 +1,207 @@
+//! Tiny built-in bitmap font used for world-space labels and rasterized TTF fonts replacing it
+
+use ab_glyph::{point, Font, FontArc, InvalidFont, ScaleFont};
+
+/// Glyph width in font pixels
+pub const GLYPH_WIDTH: u32 = 3;
+/// Glyph height in font pixels
+pub const GLYPH_HEIGHT: u32 = 5;
+/// Horizontal distance between glyph origins (includes 1 pixel spacing)
+pub const GLYPH_ADVANCE: u32 = GLYPH_WIDTH + 1;
+
+/// Glyph rows (top to bottom), 3 bits each with the leftmost pixel in the highest bit.
+///
+/// Lowercase letters are rendered as uppercase, unsupported characters as `?`
+pub fn glyph(c: char) -> [u8; GLYPH_HEIGHT as usize] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b101, 0b111, 0b111, 0b111, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b111, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// Text width in font pixels
+pub fn text_width(text: &str) -> u32 {
+    match text.chars().count() as u32 {
+        0 => 0,
+        len => len * GLYPH_ADVANCE - 1,
+    }
+}
+
+/// Positions of lit pixels of the text (x to the right, y up, origin at the bottom left corner)
+pub fn text_pixels(text: &str) -> impl Iterator<Item = (u32, u32)> + '_ {
+    text.chars().enumerate().flat_map(|(i, c)| {
+        let rows = glyph(c);
+
+        (0..GLYPH_HEIGHT).flat_map(move |row| {
+            (0..GLYPH_WIDTH)
+                .filter(move |col| rows[row as usize] & (1 << (GLYPH_WIDTH - 1 - col)) != 0)
+                .map(move |col| (i as u32 * GLYPH_ADVANCE + col, GLYPH_HEIGHT - 1 - row))
+        })
+    })
+}
+
+/// Font of world-space labels.
+///
+/// TTF fonts are rasterized to font pixels, so labels are drawn the same way with either font
+#[derive(Clone, Default)]
+pub enum LabelFont {
+    #[default]
+    Builtin,
+    Ttf(FontArc),
+}
+
+impl LabelFont {
+    /// Height of rasterized TTF text in font pixels
+    pub const TTF_HEIGHT: u32 = 16;
+    /// Min glyph coverage of a lit pixel
+    const TTF_THRESHOLD: f32 = 0.5;
+
+    pub fn ttf(data: Vec<u8>) -> Result<Self, InvalidFont> {
+        FontArc::try_from_vec(data).map(Self::Ttf)
+    }
+
+    /// Text height in font pixels
+    pub fn height(&self) -> u32 {
+        match self {
+            Self::Builtin => GLYPH_HEIGHT,
+            Self::Ttf(_) => Self::TTF_HEIGHT,
+        }
+    }
+
+    /// Text width in font pixels
+    pub fn text_width(&self, text: &str) -> u32 {
+        match self {
+            Self::Builtin => text_width(text),
+            Self::Ttf(font) => {
+                let font = font.as_scaled(Self::TTF_HEIGHT as f32);
+                let (width, _) = text.chars().map(|c| font.glyph_id(c)).fold(
+                    (0.0, None),
+                    |(width, previous), id| {
+                        let kern = previous.map_or(0.0, |previous| font.kern(previous, id));
+                        (width + kern + font.h_advance(id), Some(id))
+                    },
+                );
+
+                width.ceil() as u32
+            }
+        }
+    }
+
+    /// Positions of lit pixels of the text (x to the right, y up, origin at the bottom left corner)
+    pub fn text_pixels(&self, text: &str) -> Vec<(u32, u32)> {
+        let font = match self {
+            Self::Builtin => return text_pixels(text).collect(),
+            Self::Ttf(font) => font.as_scaled(Self::TTF_HEIGHT as f32),
+        };
+
+        let mut pixels = Vec::new();
+        let mut caret = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += font.kern(previous, id);
+            }
+            let glyph = id.with_scale_and_position(font.scale(), point(caret, font.ascent()));
+            caret += font.h_advance(id);
+            previous = Some(id);
+
+            let Some(outline) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|x, y, coverage| {
+                // Rows are counted from the top
+                let x = bounds.min.x as i32 + x as i32;
+                let y = bounds.min.y as i32 + y as i32;
+                if coverage >= Self::TTF_THRESHOLD
+                    && x >= 0
+                    && (0..Self::TTF_HEIGHT as i32).contains(&y)
+                {
+                    pixels.push((x as u32, Self::TTF_HEIGHT - 1 - y as u32));
+                }
+            });
+        }
+
+        pixels
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "debug_overlay"))]
+mod tests {
+    use super::LabelFont;
+
+    #[test]
+    fn ttf_text_pixels() {
+        // Bundled with egui
+        let data = egui::FontDefinitions::default().font_data["Hack"]
+            .font
+            .to_vec();
+        let font = LabelFont::ttf(data).unwrap();
+
+        let width = font.text_width("Chunk 1,2,3");
+        assert!(width > font.text_width("Chunk"));
+        let pixels = font.text_pixels("Chunk 1,2,3");
+        assert!(!pixels.is_empty());
+        assert!(pixels
+            .iter()
+            .all(|&(x, y)| x <= width && y < LabelFont::TTF_HEIGHT));
+
+        assert!(LabelFont::ttf(vec![0; 16]).is_err());
+    }
+}
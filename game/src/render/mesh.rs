@@ -1,71 +1,147 @@
-use std::sync::mpsc::Sender;
+use std::{
+    mem::size_of,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
 
 use crate::render::primitives::quad::Quad;
 use common::{
+    biome::ClimateMap,
     block::Block,
-    coord::{BlockCoord, ChunkCoord},
+    chunk::Chunk,
+    coord::{BlockCoord, ChunkCoord, GlobalCoord, GlobalUnit, CHUNK_SIZE},
     direction::Direction,
+    palette::Palette,
+    rng::{self, salt},
 };
 use common_log::prof;
-use rand::{thread_rng, Rng};
+use glam::Vec3;
+use thiserror::Error;
+
+use super::primitives::{decoration::DecorationInstance, terrain::TerrainVertex};
 
-use super::primitives::vertex::Vertex;
+/// Broken mesh found by [`TerrainMesh::validate`]
+#[derive(PartialEq, Eq, Error, Debug)]
+pub enum MeshError {
+    #[error("{0} vertices don't form quads")]
+    VertexCount(usize),
+    #[error("{0} indices don't form quads")]
+    IndexCount(usize),
+    #[error("Cutout ({cutout}) and fluid ({fluid}) starts are out of {indices} indices")]
+    Ranges {
+        cutout: u32,
+        fluid: u32,
+        indices: usize,
+    },
+    #[error("Index {index} is out of {vertices} vertices")]
+    IndexOutOfRange { index: u32, vertices: usize },
+    #[error("Vertex {0} isn't finite")]
+    NotFinite(usize),
+    #[error("Triangles of quad {0} are wound differently")]
+    Winding(usize),
+}
 
-pub type MeshTaskResult = (ChunkCoord, TerrainMesh);
+/// Built mesh and time spent building it
+pub type MeshTaskResult = (ChunkCoord, TerrainMesh, Duration);
 
 /// Mesh builder for terrain chunks
 pub struct TerrainMesh {
-    pub vertices: Vec<Vertex>,
+    /// Positions are relative to the chunk origin
+    pub vertices: Vec<TerrainVertex>,
+    /// Opaque geometry followed by cutout and fluid geometry
     pub indices: Vec<u32>,
+    /// Number of opaque indices. Indices up to [`Self::fluid`] are drawn by the cutout pipeline
+    pub cutout: u32,
+    /// Start of fluid indices. The rest is drawn by the fluid pipeline
+    pub fluid: u32,
+    /// Grass tufts in random order, so any prefix is spread evenly over the chunk
+    pub decorations: Vec<DecorationInstance>,
 }
 
 impl TerrainMesh {
-    pub fn task(tx: Sender<MeshTaskResult>, coord: ChunkCoord, blocks: &[Block]) {
-        let _ = tx.send((coord, Self::build(coord, blocks)));
+    /// Max deviation of block color channels
+    pub const COLOR_VARIATION: f32 = 0.05;
+    /// Brightness of the bottom of plants relative to their tops
+    pub const STEM_SHADE: f32 = 0.7;
+
+    pub fn task(
+        tx: Sender<MeshTaskResult>,
+        coord: ChunkCoord,
+        blocks: &[Block],
+        palette: &Palette,
+    ) {
+        let start = Instant::now();
+        let mesh = Self::build_with_palette(coord, blocks, Chunk::DEFAULT_SEED, palette);
+        let _ = tx.send((coord, mesh, start.elapsed()));
     }
 
     pub fn build(coord: ChunkCoord, blocks: &[Block]) -> Self {
+        Self::build_with_seed(coord, blocks, Chunk::DEFAULT_SEED)
+    }
+
+    /// Same as [`Self::build`], but biome tints come from the world generated from `seed`
+    pub fn build_with_seed(coord: ChunkCoord, blocks: &[Block], seed: u32) -> Self {
+        Self::build_with_palette(coord, blocks, seed, &Palette::new())
+    }
+
+    /// Same as [`Self::build_with_seed`], but block colors come from `palette`
+    pub fn build_with_palette(
+        coord: ChunkCoord,
+        blocks: &[Block],
+        seed: u32,
+        palette: &Palette,
+    ) -> Self {
         prof!("TerrainMesh::build");
 
-        let mut rng = thread_rng();
         let mut vertices = Vec::new();
         let mut indices = Vec::new();
         let mut index: u32 = 0;
+        let tints = blocks
+            .iter()
+            .any(Block::tinted)
+            .then(|| BiomeTints::new(coord, seed));
 
         blocks
             .iter()
             .enumerate()
             .filter_map(|(id, block)| {
+                if block.cross() || block.liquid() {
+                    return None;
+                }
                 if block.opaque() {
                     let pos = BlockCoord::from(id);
-                    let g_pos = coord.to_global(&pos).as_vec();
+                    let g_coord = coord.to_global(&pos);
+                    let l_pos = pos.as_vec();
                     let mut faces = Vec::new();
 
+                    // Faces are visible through fluids
                     Direction::ALL.iter().for_each(|&dir| {
-                        if pos.on_chunk_edge(dir) || !blocks[pos.neighbor(dir).flatten()].opaque() {
-                            faces.push(Quad::new(dir, g_pos));
+                        if pos.on_chunk_edge(dir) || !blocks[pos.neighbor(dir).flatten()].solid() {
+                            faces.push(Quad::new(dir, l_pos));
                         }
                     });
 
                     if !faces.is_empty() {
-                        return Some((block, faces));
+                        return Some((block, g_coord, faces));
                     }
                 }
 
                 None
             })
-            .for_each(|(block, faces)| {
-                let mut color = block.color();
-                color.x = rng.gen_range(color.x - 0.05..=color.x + 0.05);
-                color.y = rng.gen_range(color.y - 0.05..=color.y + 0.05);
-                color.z = rng.gen_range(color.z - 0.05..=color.z + 0.05);
+            .for_each(|(block, g_coord, faces)| {
+                let jitter = Self::color_jitter(g_coord);
+                let tints = tints.as_ref().filter(|_| block.tinted());
 
                 let mut block_vertices = faces
                     .into_iter()
                     .flat_map(|quad| {
-                        quad.corners()
-                            .into_iter()
-                            .map(|position| Vertex { position, color })
+                        quad.corners().into_iter().map(|position| {
+                            let color = match tints {
+                                Some(tints) => palette.color(*block) * tints.get(position),
+                                None => palette.color(*block),
+                            };
+                            TerrainVertex::new(position, color + jitter)
+                        })
                     })
                     .collect::<Vec<_>>();
 
@@ -83,10 +159,373 @@ impl TerrainMesh {
                 vertices.append(&mut block_vertices);
             });
 
-        Self { vertices, indices }
+        let cutout = indices.len() as u32;
+        Self::build_plants(
+            coord,
+            blocks,
+            palette,
+            tints.as_ref(),
+            &mut vertices,
+            &mut indices,
+        );
+        let fluid = indices.len() as u32;
+        Self::build_fluids(blocks, palette, &mut vertices, &mut indices);
+
+        Self {
+            vertices,
+            indices,
+            cutout,
+            fluid,
+            decorations: Self::build_decorations(coord, blocks, palette, tints.as_ref()),
+        }
+    }
+
+    /// Append two diagonal quads for every cross block
+    fn build_plants(
+        coord: ChunkCoord,
+        blocks: &[Block],
+        palette: &Palette,
+        tints: Option<&BiomeTints>,
+        vertices: &mut Vec<TerrainVertex>,
+        indices: &mut Vec<u32>,
+    ) {
+        blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.cross())
+            .for_each(|(id, block)| {
+                let pos = BlockCoord::from(id);
+                let g_coord = coord.to_global(&pos);
+                let pos = pos.as_vec();
+                let jitter = Self::color_jitter(g_coord);
+                let tint = |position: Vec3| tints.map_or(Vec3::ONE, |tints| tints.get(position));
+                let top = |position: Vec3| {
+                    if block.tinted() {
+                        palette.color(*block) * tint(position) + jitter
+                    } else {
+                        palette.color(*block) + jitter
+                    }
+                };
+                // Plants grow out of green stems
+                let bottom = |position: Vec3| {
+                    palette.color(Block::TallGrass) * tint(position) * Self::STEM_SHADE
+                };
+
+                for (from, to) in [(Vec3::ZERO, Vec3::new(1.0, 0.0, 1.0)), (Vec3::X, Vec3::Z)] {
+                    let (from, to) = (pos + from, pos + to);
+                    let index = vertices.len() as u32;
+                    vertices.extend([
+                        TerrainVertex::new(from, bottom(from)),
+                        TerrainVertex::new(from + Vec3::Y, top(from)),
+                        TerrainVertex::new(to + Vec3::Y, top(to)),
+                        TerrainVertex::new(to, bottom(to)),
+                    ]);
+                    indices.extend([index, index + 1, index + 2, index, index + 2, index + 3]);
+                }
+            });
+    }
+
+    /// Append faces of fluid blocks bordering non-opaque blocks. On chunk edges only the top face
+    /// is added, as side faces would be visible through the fluid of the neighbor chunk
+    fn build_fluids(
+        blocks: &[Block],
+        palette: &Palette,
+        vertices: &mut Vec<TerrainVertex>,
+        indices: &mut Vec<u32>,
+    ) {
+        blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.liquid())
+            .for_each(|(id, block)| {
+                let pos = BlockCoord::from(id);
+                Direction::ALL
+                    .into_iter()
+                    .filter(|&dir| {
+                        if pos.on_chunk_edge(dir) {
+                            matches!(dir, Direction::Up)
+                        } else {
+                            !blocks[pos.neighbor(dir).flatten()].opaque()
+                        }
+                    })
+                    .for_each(|dir| {
+                        let index = vertices.len() as u32;
+                        vertices.extend(
+                            Quad::new(dir, pos.as_vec()).corners().map(|position| {
+                                TerrainVertex::new(position, palette.color(*block))
+                            }),
+                        );
+                        indices.extend([index, index + 1, index + 2, index, index + 2, index + 3]);
+                    });
+            });
+    }
+
+    /// Grass tuft for every grass block with air above. Tufts on the top edge are kept, they are
+    /// hidden inside the block above if there is one
+    fn build_decorations(
+        coord: ChunkCoord,
+        blocks: &[Block],
+        palette: &Palette,
+        tints: Option<&BiomeTints>,
+    ) -> Vec<DecorationInstance> {
+        let mut decorations = blocks
+            .iter()
+            .enumerate()
+            .filter(|&(_, &block)| block == Block::Grass)
+            .map(|(id, _)| BlockCoord::from(id))
+            .filter(|pos| {
+                pos.on_chunk_edge(Direction::Up)
+                    || blocks[pos.neighbor(Direction::Up).flatten()] == Block::Air
+            })
+            .map(|pos| {
+                // Tuft takes the place of the block above
+                let hash = Self::hash(coord.to_global(&pos.neighbor(Direction::Up)));
+                let tint = tints.map_or(Vec3::ONE, |tints| tints.get(pos.as_vec()));
+                let color = palette.color(Block::TallGrass) * tint + Self::color_jitter_from(hash);
+                // Low 48 bits are used by the color jitter
+                let instance = DecorationInstance::new(pos, (hash >> 48) as u16, color);
+                // Independent of the shape, so density doesn't prefer any of them
+                (instance, Self::hash(coord.to_global(&pos)))
+            })
+            .collect::<Vec<_>>();
+        decorations.sort_unstable_by_key(|&(_, rank)| rank);
+
+        decorations
+            .into_iter()
+            .map(|(instance, _)| instance)
+            .collect()
+    }
+
+    /// Color offset of the block at `pos`. Stable across remeshes
+    pub fn color_jitter(pos: GlobalCoord) -> Vec3 {
+        Self::color_jitter_from(Self::hash(pos))
+    }
+
+    /// Stable hash of the block position. The same in every world
+    fn hash(pos: GlobalCoord) -> u64 {
+        rng::hash_pos(salt::TINT, pos)
+    }
+
+    fn color_jitter_from(hash: u64) -> Vec3 {
+        // 16 bits per channel mapped to [-1; 1]
+        let channel = |shift: u32| ((hash >> shift) & 0xffff) as f32 / 0xffff as f32 * 2.0 - 1.0;
+
+        Vec3::new(channel(0), channel(16), channel(32)) * Self::COLOR_VARIATION
     }
 
     pub fn is_empty(&self) -> bool {
         self.vertices.is_empty()
     }
+
+    /// Size of the data uploaded to the GPU (bytes)
+    /// Check mesh invariants. Cheap enough for debug builds only
+    pub fn validate(&self) -> Result<(), MeshError> {
+        if !self.vertices.len().is_multiple_of(4) {
+            return Err(MeshError::VertexCount(self.vertices.len()));
+        }
+        if !self.indices.len().is_multiple_of(6) {
+            return Err(MeshError::IndexCount(self.indices.len()));
+        }
+        if self.cutout > self.fluid || self.fluid as usize > self.indices.len() {
+            return Err(MeshError::Ranges {
+                cutout: self.cutout,
+                fluid: self.fluid,
+                indices: self.indices.len(),
+            });
+        }
+        if let Some(&index) = self
+            .indices
+            .iter()
+            .find(|&&index| index as usize >= self.vertices.len())
+        {
+            return Err(MeshError::IndexOutOfRange {
+                index,
+                vertices: self.vertices.len(),
+            });
+        }
+        if let Some(vertex) = self
+            .vertices
+            .iter()
+            .position(|vertex| !vertex.position.is_finite() || !vertex.color.is_finite())
+        {
+            return Err(MeshError::NotFinite(vertex));
+        }
+
+        // Both triangles of a quad must face the same side, or a half of it is culled
+        let normal = |triangle: &[u32]| {
+            let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize].position);
+            (b - a).cross(c - a)
+        };
+        match self
+            .indices
+            .chunks_exact(6)
+            .position(|quad| normal(&quad[..3]).dot(normal(&quad[3..])) <= 0.0)
+        {
+            Some(quad) => Err(MeshError::Winding(quad)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        (self.vertices.len() * size_of::<TerrainVertex>()
+            + self.indices.len() * size_of::<u32>()
+            + self.decorations.len() * size_of::<DecorationInstance>()) as u64
+    }
+}
+
+/// Vegetation tints at block corners of a chunk column, so colors blend smoothly across faces
+/// and chunk borders
+struct BiomeTints {
+    tints: Vec<Vec3>,
+}
+
+impl BiomeTints {
+    const SIZE: usize = CHUNK_SIZE + 1;
+
+    fn new(coord: ChunkCoord, seed: u32) -> Self {
+        let climate = ClimateMap::with_seed(seed);
+        let tints = (0..Self::SIZE * Self::SIZE)
+            .map(|i| {
+                let (x, z) = (
+                    (i / Self::SIZE) as GlobalUnit,
+                    (i % Self::SIZE) as GlobalUnit,
+                );
+                climate.get(coord.x + x, coord.z + z).tint()
+            })
+            .collect();
+
+        Self { tints }
+    }
+
+    /// Tint at the vertex `position` (inside the chunk)
+    fn get(&self, position: Vec3) -> Vec3 {
+        let local = position.round();
+        let (x, z) = (local.x as usize, local.z as usize);
+        self.tints[x.min(CHUNK_SIZE) * Self::SIZE + z.min(CHUNK_SIZE)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        block::Block,
+        coord::{BlockCoord, ChunkCoord, GlobalCoord, CHUNK_CUBE},
+    };
+
+    use super::{MeshError, TerrainMesh};
+
+    #[test]
+    fn deterministic_colors() {
+        let mut blocks = [Block::Air; CHUNK_CUBE];
+        blocks[..64].fill(Block::Grass);
+
+        let coord = ChunkCoord::new(16, 0, -32);
+        let colors = |mesh: TerrainMesh| {
+            mesh.vertices
+                .into_iter()
+                .map(|vertex| vertex.color)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            colors(TerrainMesh::build(coord, &blocks)),
+            colors(TerrainMesh::build(coord, &blocks))
+        );
+
+        let a = TerrainMesh::color_jitter(GlobalCoord::new(1, 2, 3));
+        let b = TerrainMesh::color_jitter(GlobalCoord::new(1, 2, 4));
+        assert_ne!(a, b);
+        assert!(a.abs().max_element() <= TerrainMesh::COLOR_VARIATION);
+    }
+
+    #[test]
+    fn plants_after_opaque() {
+        let mut blocks = [Block::Air; CHUNK_CUBE];
+        blocks[BlockCoord::new(4, 4, 4).flatten()] = Block::Stone;
+        blocks[BlockCoord::new(4, 5, 4).flatten()] = Block::TallGrass;
+
+        let mesh = TerrainMesh::build(ChunkCoord::new(0, 0, 0), &blocks);
+        // Plant doesn't hide the top face of the stone
+        assert_eq!(mesh.cutout, 6 * 6);
+        assert_eq!(mesh.indices.len(), 6 * 6 + 2 * 6);
+        assert_eq!(mesh.vertices.len(), 6 * 4 + 2 * 4);
+    }
+
+    #[test]
+    fn decorations_on_exposed_grass() {
+        let mut blocks = [Block::Air; CHUNK_CUBE];
+        for x in 0..4 {
+            blocks[BlockCoord::new(x, 4, 0).flatten()] = Block::Grass;
+        }
+        // Covered grass, grass under a plant and grass on the top edge
+        blocks[BlockCoord::new(0, 5, 0).flatten()] = Block::Dirt;
+        blocks[BlockCoord::new(1, 5, 0).flatten()] = Block::TallGrass;
+        blocks[BlockCoord::new(8, 15, 8).flatten()] = Block::Grass;
+
+        let coord = ChunkCoord::new(0, 0, 0);
+        let mesh = TerrainMesh::build(coord, &blocks);
+        let positions = |mesh: &TerrainMesh| {
+            mesh.decorations
+                .iter()
+                .map(|instance| instance.packed & 0xfff)
+                .collect::<Vec<_>>()
+        };
+        let mut sorted = positions(&mesh);
+        sorted.sort_unstable();
+        assert_eq!(sorted, [2 | 4 << 4, 3 | 4 << 4, 8 | 15 << 4 | 8 << 8]);
+
+        // Order is stable, so the same tufts are drawn at any density
+        assert_eq!(
+            positions(&mesh),
+            positions(&TerrainMesh::build(coord, &blocks))
+        );
+    }
+
+    #[test]
+    fn fluids_after_cutout() {
+        let mut blocks = [Block::Air; CHUNK_CUBE];
+        blocks[BlockCoord::new(4, 4, 4).flatten()] = Block::Stone;
+        blocks[BlockCoord::new(5, 4, 4).flatten()] = Block::Water;
+        blocks[BlockCoord::new(6, 4, 4).flatten()] = Block::Water;
+        // Only the top face is added on chunk edges
+        blocks[BlockCoord::new(0, 15, 8).flatten()] = Block::Water;
+
+        let mesh = TerrainMesh::build(ChunkCoord::new(0, 0, 0), &blocks);
+        // Stone face behind the water is visible
+        assert_eq!(mesh.cutout, 6 * 6);
+        assert_eq!(mesh.fluid, mesh.cutout);
+        // Faces between water blocks and towards the stone are hidden, the edge face is skipped
+        assert_eq!(mesh.indices.len() as u32 - mesh.fluid, (4 + 5 + 5) * 6);
+    }
+    #[test]
+    fn validate_broken_meshes() {
+        let mut blocks = [Block::Air; CHUNK_CUBE];
+        blocks[..CHUNK_CUBE / 2].fill(Block::Stone);
+        blocks[BlockCoord::new(15, 3, 3).flatten()] = Block::Water;
+        blocks[BlockCoord::new(3, 15, 3).flatten()] = Block::Flower;
+        let build = || TerrainMesh::build(ChunkCoord::new(-16, 0, 32), &blocks);
+        assert_eq!(build().validate(), Ok(()));
+
+        let mut mesh = build();
+        mesh.indices.swap(1, 2);
+        assert_eq!(mesh.validate(), Err(MeshError::Winding(0)));
+
+        let mut mesh = build();
+        let vertices = mesh.vertices.len();
+        *mesh.indices.last_mut().unwrap() = vertices as u32;
+        assert_eq!(
+            mesh.validate(),
+            Err(MeshError::IndexOutOfRange {
+                index: vertices as u32,
+                vertices
+            })
+        );
+
+        let mut mesh = build();
+        mesh.vertices[5].color.y = f32::NAN;
+        assert_eq!(mesh.validate(), Err(MeshError::NotFinite(5)));
+
+        let mut mesh = build();
+        mesh.vertices.pop();
+        assert_eq!(mesh.validate(), Err(MeshError::VertexCount(vertices - 1)));
+    }
 }
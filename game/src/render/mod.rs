@@ -1,24 +1,227 @@
-use wgpu::PresentMode;
+use wgpu::{Backends, PresentMode};
 
+use crate::types::U32x2;
+
+pub mod arena;
 pub mod buffer;
+pub mod capture;
+pub mod compute_mesh;
 pub mod error;
+pub mod font;
 pub mod mesh;
 pub mod model;
+pub mod pacer;
 pub mod pipelines;
 pub mod primitives;
+pub mod push_constants;
 pub mod renderer;
 pub mod shader;
 pub mod texture;
 
 #[derive(PartialEq, Eq, Clone)]
 pub struct RenderMode {
-    pub present_mode: PresentMode,
+    /// Graphics APIs to choose adapter from. Changing it recreates the renderer
+    pub backends: Backends,
+    pub present_mode: PresentPolicy,
+    /// Max number of submitted frames the GPU may still be working on. Lower values reduce input
+    /// latency, higher values keep the GPU busy
+    pub frames_in_flight: u32,
+    /// Build chunk meshes in a compute shader (experimental). Ignored if compute shaders aren't
+    /// supported
+    pub compute_meshing: bool,
+    /// Percentage of grass tufts drawn (0 disables them)
+    pub decoration_density: u32,
 }
 
 impl RenderMode {
+    pub const MIN_FRAMES_IN_FLIGHT: u32 = 1;
+    pub const MAX_FRAMES_IN_FLIGHT: u32 = 3;
+    pub const MAX_DECORATION_DENSITY: u32 = 100;
+
     pub const fn new() -> Self {
         Self {
-            present_mode: PresentMode::Fifo,
+            backends: Backends::PRIMARY,
+            present_mode: PresentPolicy::Fixed(PresentMode::Fifo),
+            frames_in_flight: 2,
+            compute_meshing: false,
+            decoration_density: 50,
+        }
+    }
+}
+
+/// Requested present mode. The mode in use is [`Renderer::present_mode`]
+///
+/// [`Renderer::present_mode`]: renderer::Renderer::present_mode
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum PresentPolicy {
+    /// Lowest latency mode without tearing: Mailbox, FifoRelaxed or Fifo, whichever is supported
+    /// first. Selected again when the surface is reconfigured or the window changes monitor
+    Auto,
+    /// Falls back to Fifo if the surface doesn't support it
+    Fixed(PresentMode),
+}
+
+impl PresentPolicy {
+    pub const ALL: [Self; 5] = [
+        Self::Auto,
+        Self::Fixed(PresentMode::Fifo),
+        Self::Fixed(PresentMode::FifoRelaxed),
+        Self::Fixed(PresentMode::Mailbox),
+        Self::Fixed(PresentMode::Immediate),
+    ];
+    /// Preference of [`Self::Auto`]
+    const AUTO_MODES: [PresentMode; 3] = [
+        PresentMode::Mailbox,
+        PresentMode::FifoRelaxed,
+        PresentMode::Fifo,
+    ];
+
+    /// Mode to use among the ones supported by the surface. Fifo is supported everywhere
+    pub fn select(self, supported: &[PresentMode]) -> PresentMode {
+        match self {
+            Self::Auto => Self::AUTO_MODES
+                .into_iter()
+                .find(|mode| supported.contains(mode))
+                .unwrap_or(PresentMode::Fifo),
+            Self::Fixed(mode) if supported.contains(&mode) => mode,
+            Self::Fixed(_) => PresentMode::Fifo,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::Auto => "Auto".to_string(),
+            Self::Fixed(mode) => format!("{mode:?}"),
+        }
+    }
+
+    /// Name in the settings file
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Fixed(PresentMode::Fifo) => "fifo",
+            Self::Fixed(PresentMode::FifoRelaxed) => "fifo_relaxed",
+            Self::Fixed(PresentMode::Mailbox) => "mailbox",
+            Self::Fixed(PresentMode::Immediate) => "immediate",
+            // Not listed in `ALL`
+            Self::Fixed(_) => "auto",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|policy| policy.key() == key)
+    }
+}
+
+/// Draw stages which can be skipped at runtime (used to isolate rendering bugs)
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct DrawStages {
+    pub terrain: bool,
+    pub figures: bool,
+    pub labels: bool,
+    /// Instanced grass tufts
+    pub decorations: bool,
+    /// Water and other fluid surfaces
+    pub fluids: bool,
+    /// Debug geometry (test pyramid)
+    pub debug: bool,
+    /// Screen-space HUD elements (minimap)
+    pub hud: bool,
+}
+
+impl DrawStages {
+    pub const fn new() -> Self {
+        Self {
+            terrain: true,
+            figures: true,
+            labels: true,
+            decorations: true,
+            fluids: true,
+            debug: true,
+            hud: true,
+        }
+    }
+}
+
+impl Default for DrawStages {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rectangle of the frame in pixels. Origin is the top left corner
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Viewport {
+    pub pos: U32x2,
+    pub size: U32x2,
+}
+
+/// Debug visualization replacing shaded terrain and figures
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub enum DebugView {
+    #[default]
+    Shaded,
+    /// Distance to the camera
+    Depth,
+    Normals,
+    /// Ambient occlusion (white until it's implemented)
+    Ao,
+    /// Light intensity without colors and fog
+    Light,
+    /// Number of fragments drawn per pixel (depth test is disabled)
+    Overdraw,
+}
+
+impl DebugView {
+    pub const ALL: [Self; 6] = [
+        Self::Shaded,
+        Self::Depth,
+        Self::Normals,
+        Self::Ao,
+        Self::Light,
+        Self::Overdraw,
+    ];
+
+    /// Value of `camera.debug.x` in shaders
+    pub const fn id(self) -> u32 {
+        self as u32
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use wgpu::PresentMode;
+
+    use super::PresentPolicy;
+
+    #[test]
+    fn select_present_mode() {
+        use PresentMode::*;
+
+        assert_eq!(
+            PresentPolicy::Auto.select(&[Fifo, Immediate, Mailbox]),
+            Mailbox
+        );
+        assert_eq!(
+            PresentPolicy::Auto.select(&[Fifo, FifoRelaxed]),
+            FifoRelaxed
+        );
+        assert_eq!(PresentPolicy::Auto.select(&[Immediate, Fifo]), Fifo);
+        assert_eq!(
+            PresentPolicy::Fixed(Immediate).select(&[Fifo, Immediate]),
+            Immediate
+        );
+        assert_eq!(
+            PresentPolicy::Fixed(Mailbox).select(&[Fifo, Immediate]),
+            Fifo
+        );
+
+        for policy in PresentPolicy::ALL {
+            assert_eq!(PresentPolicy::from_key(policy.key()), Some(policy));
         }
     }
 }
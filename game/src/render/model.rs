@@ -2,6 +2,9 @@ use wgpu::{Buffer, IndexFormat};
 
 // TODO: Static model mega-buffer
 pub trait Model {
+    /// Vertex type of the pipeline drawing the model
+    type Vertex;
+
     const INDEX_FORMAT: IndexFormat = IndexFormat::Uint16;
 
     fn get_vertices(&self) -> &Buffer;
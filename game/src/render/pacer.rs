@@ -0,0 +1,352 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use common_log::span;
+use spin_sleep::sleep;
+use wgpu::PresentMode;
+use winit::{monitor::MonitorHandle, window::Window};
+
+/// Frame pacing based on presentation timing.
+///
+/// With vsync frames are paced by the display, so the CPU only sleeps to hold an FPS cap at a whole
+/// number of refresh intervals. Optionally waits before input sampling, so frames are finished right
+/// before the next refresh.
+///
+/// Input latency is measured from the oldest input event of a frame to its present and to the GPU
+/// finishing it. Frames are matched by the renderer frame index
+pub struct FramePacer {
+    /// Sleep before input sampling to reduce latency (vsync only)
+    pub latency_wait: bool,
+    /// Refresh interval reported by the monitor
+    display_refresh: Option<Duration>,
+    display_checked_at: Option<Instant>,
+    /// Monitor the window has been on at the last check
+    monitor: Option<MonitorHandle>,
+    frame_start: Instant,
+    /// Time spent waiting for a swapchain image in the current frame
+    acquire: Duration,
+    last_present: Option<Instant>,
+    /// Present to present intervals (s)
+    intervals: VecDeque<f32>,
+    /// Frame work without waiting for a swapchain image (s)
+    work: VecDeque<f32>,
+    /// Last latency wait
+    waited: Duration,
+    /// Oldest input event not presented yet
+    input_at: Option<Instant>,
+    /// Index of the last presented frame
+    last_frame: u64,
+    /// Presented frames with input, waiting for the GPU (oldest first)
+    pending_input: VecDeque<(u64, Instant)>,
+    /// Input to present (s)
+    present_latency: VecDeque<f32>,
+    /// Input to the frame finished by the GPU (s)
+    latency: VecDeque<f32>,
+}
+
+impl FramePacer {
+    pub const HISTORY_LENGTH: usize = 120;
+    /// Frames are expected to be finished this long before the refresh
+    pub const LATENCY_MARGIN: Duration = Duration::from_millis(2);
+    /// How often the monitor refresh rate is queried
+    pub const DISPLAY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        Self {
+            latency_wait: false,
+            display_refresh: None,
+            display_checked_at: None,
+            monitor: None,
+            frame_start: Instant::now(),
+            acquire: Duration::ZERO,
+            last_present: None,
+            intervals: VecDeque::with_capacity(Self::HISTORY_LENGTH),
+            work: VecDeque::with_capacity(Self::HISTORY_LENGTH),
+            waited: Duration::ZERO,
+            input_at: None,
+            last_frame: 0,
+            pending_input: VecDeque::new(),
+            present_latency: VecDeque::with_capacity(Self::HISTORY_LENGTH),
+            latency: VecDeque::with_capacity(Self::HISTORY_LENGTH),
+        }
+    }
+
+    /// Query refresh rate of the monitor the window is on (rate limited). Returns `true` if the
+    /// window has moved to another monitor since the last check
+    pub fn update_display(&mut self, window: &Window) -> bool {
+        if self
+            .display_checked_at
+            .is_some_and(|at| at.elapsed() < Self::DISPLAY_CHECK_INTERVAL)
+        {
+            return false;
+        }
+
+        self.display_checked_at = Some(Instant::now());
+        let monitor = window.current_monitor();
+        let changed = self.monitor.is_some() && self.monitor != monitor;
+        self.display_refresh = monitor
+            .clone()
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .filter(|&millihertz| millihertz > 0)
+            .map(|millihertz| Duration::from_secs_f64(1000.0 / millihertz as f64));
+        self.monitor = monitor;
+
+        changed
+    }
+
+    /// Display refresh interval. Reported by the monitor or the median present interval
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        self.display_refresh.or_else(|| {
+            (self.intervals.len() >= Self::HISTORY_LENGTH / 4)
+                .then(|| Duration::from_secs_f32(percentile(&self.intervals, 0.5)))
+        })
+    }
+
+    /// Target frame duration for [`common::clock::Clock`]
+    pub fn target(&self, present_mode: PresentMode, max_fps: u32) -> Duration {
+        let cap = Duration::from_secs_f64(1.0 / max_fps.max(1) as f64);
+
+        match (present_mode, self.refresh_interval()) {
+            // Presentation blocks until the refresh, so the cap only limits frames which aren't
+            // presented (e.g. minimized window)
+            (PresentMode::Fifo | PresentMode::FifoRelaxed, Some(refresh)) if cap <= refresh => cap,
+            // Every frame is shown for the same number of refreshes
+            (PresentMode::Fifo | PresentMode::FifoRelaxed, Some(refresh)) => {
+                // Tolerance, so a cap of exactly N refreshes isn't rounded up
+                refresh * (cap.as_secs_f64() / refresh.as_secs_f64() - 1e-3).ceil() as u32
+            }
+            _ => cap,
+        }
+    }
+
+    /// Start a frame. Sleeps before input sampling if [`Self::latency_wait`] is enabled
+    pub fn begin_frame(&mut self, present_mode: PresentMode) {
+        self.waited = Duration::ZERO;
+
+        if let (
+            true,
+            PresentMode::Fifo | PresentMode::FifoRelaxed,
+            Some(refresh),
+            Some(last_present),
+        ) = (
+            self.latency_wait,
+            present_mode,
+            self.refresh_interval(),
+            self.last_present,
+        ) {
+            span!(_guard, "latency_wait", "FramePacer::begin_frame");
+
+            // Slowest recent frame, so the refresh isn't missed
+            let work = Duration::from_secs_f32(percentile(&self.work, 1.0));
+            let deadline = last_present + refresh;
+            if let Some(wait) = deadline
+                .checked_duration_since(Instant::now())
+                .and_then(|left| left.checked_sub(work + Self::LATENCY_MARGIN))
+            {
+                sleep(wait);
+                self.waited = wait;
+            }
+        }
+
+        self.frame_start = Instant::now();
+        self.acquire = Duration::ZERO;
+    }
+
+    /// Call `acquire` (start of the frame rendering) and measure how long it waited for the
+    /// swapchain
+    pub fn acquire<T>(&mut self, acquire: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = acquire();
+        self.acquire += start.elapsed();
+        result
+    }
+
+    /// Input events handled by the current frame. `at` is when the oldest one has been received
+    pub fn input(&mut self, at: Option<Instant>) {
+        if let Some(at) = at {
+            self.input_at.get_or_insert(at);
+        }
+    }
+
+    /// Frame has been presented. `frame` is [`Renderer::frame_index`] after the frame, the same if
+    /// nothing has been submitted
+    ///
+    /// [`Renderer::frame_index`]: super::renderer::Renderer::frame_index
+    pub fn presented(&mut self, frame: u64) {
+        let now = Instant::now();
+
+        // Renderer has been recreated
+        if frame < self.last_frame {
+            self.pending_input.clear();
+        }
+        // Input is kept for the next frame if this one hasn't been drawn (e.g. minimized window)
+        if frame != self.last_frame {
+            if let Some(input_at) = self.input_at.take() {
+                let latency = now.duration_since(input_at).as_secs_f32();
+                push_sample(&mut self.present_latency, latency);
+                self.pending_input.push_back((frame, input_at));
+            }
+        }
+        self.last_frame = frame;
+
+        if let Some(last) = self.last_present.replace(now) {
+            push_sample(&mut self.intervals, now.duration_since(last).as_secs_f32());
+        }
+        let work = now
+            .duration_since(self.frame_start)
+            .saturating_sub(self.acquire);
+        push_sample(&mut self.work, work.as_secs_f32());
+    }
+
+    /// Frames finished by the GPU, see [`Renderer::finished_frames`]
+    ///
+    /// [`Renderer::finished_frames`]: super::renderer::Renderer::finished_frames
+    pub fn frames_finished(&mut self, frames: &[(u64, Instant)]) {
+        for &(frame, at) in frames {
+            // Earlier frames are finished too
+            while let Some(&(pending, input_at)) = self.pending_input.front() {
+                if pending > frame {
+                    break;
+                }
+                self.pending_input.pop_front();
+                if pending == frame {
+                    let latency = at.saturating_duration_since(input_at).as_secs_f32();
+                    push_sample(&mut self.latency, latency);
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> PacerStats {
+        let mean = self.intervals.iter().sum::<f32>() / self.intervals.len().max(1) as f32;
+        let variance = self
+            .intervals
+            .iter()
+            .map(|interval| (interval - mean).powi(2))
+            .sum::<f32>()
+            / self.intervals.len().max(1) as f32;
+
+        PacerStats {
+            refresh: self.refresh_interval(),
+            display_refresh: self.display_refresh.is_some(),
+            avg_interval: Duration::from_secs_f32(mean),
+            jitter: Duration::from_secs_f32(variance.sqrt()),
+            max_work: Duration::from_secs_f32(percentile(&self.work, 1.0)),
+            waited: self.waited,
+            present_latency: Duration::from_secs_f32(percentile(&self.present_latency, 0.5)),
+            latency: Duration::from_secs_f32(percentile(&self.latency, 0.5)),
+            max_latency: Duration::from_secs_f32(percentile(&self.latency, 1.0)),
+        }
+    }
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PacerStats {
+    /// Refresh interval the frames are paced to
+    pub refresh: Option<Duration>,
+    /// Refresh interval is reported by the monitor (not measured)
+    pub display_refresh: bool,
+    /// Average present interval
+    pub avg_interval: Duration,
+    /// Standard deviation of present intervals
+    pub jitter: Duration,
+    /// Slowest recent frame (without swapchain waits)
+    pub max_work: Duration,
+    /// Last latency wait
+    pub waited: Duration,
+    /// Median time from input to present
+    pub present_latency: Duration,
+    /// Median time from input to the frame finished by the GPU
+    pub latency: Duration,
+    pub max_latency: Duration,
+}
+
+fn push_sample(history: &mut VecDeque<f32>, sample: f32) {
+    if history.len() >= FramePacer::HISTORY_LENGTH {
+        history.pop_front();
+    }
+    history.push_back(sample);
+}
+
+/// Value below which `p` (in `[0; 1]`) of samples fall. Zero if there are no samples
+fn percentile(samples: &VecDeque<f32>, p: f32) -> f32 {
+    let mut sorted = samples.iter().copied().collect::<Vec<_>>();
+    sorted.sort_by(f32::total_cmp);
+
+    match sorted.len() {
+        0 => 0.0,
+        len => sorted[((len - 1) as f32 * p).round() as usize],
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use wgpu::PresentMode;
+
+    use super::FramePacer;
+
+    #[test]
+    fn cap_at_refresh_multiples() {
+        let mut pacer = FramePacer::new();
+        pacer.display_refresh = Some(Duration::from_secs_f64(1.0 / 144.0));
+
+        // Display paced
+        assert_eq!(
+            pacer.target(PresentMode::Fifo, 240),
+            Duration::from_secs_f64(1.0 / 240.0)
+        );
+        // 60 FPS cap on 144 Hz is held at 48 FPS, not alternating 2 and 3 refreshes
+        assert_eq!(
+            pacer.target(PresentMode::Fifo, 60),
+            Duration::from_secs_f64(1.0 / 144.0) * 3
+        );
+        assert_eq!(
+            pacer.target(PresentMode::Mailbox, 60),
+            Duration::from_secs_f64(1.0 / 60.0)
+        );
+
+        // Unknown refresh rate falls back to the cap
+        pacer.display_refresh = None;
+        assert_eq!(
+            pacer.target(PresentMode::Fifo, 60),
+            Duration::from_secs_f64(1.0 / 60.0)
+        );
+    }
+
+    #[test]
+    fn match_input_by_frame() {
+        let mut pacer = FramePacer::new();
+        let input_at = Instant::now() - Duration::from_millis(10);
+
+        // Not drawn, so the input goes to the next frame
+        pacer.input(Some(input_at));
+        pacer.presented(0);
+        pacer.input(Some(input_at + Duration::from_millis(5)));
+        pacer.presented(1);
+        pacer.presented(2);
+        assert!(pacer.stats().present_latency >= Duration::from_millis(10));
+
+        pacer.frames_finished(&[
+            (1, input_at + Duration::from_millis(25)),
+            (2, input_at + Duration::from_millis(40)),
+        ]);
+        let stats = pacer.stats();
+        assert!((stats.latency.as_secs_f32() - 0.025).abs() < 1e-4);
+        assert_eq!(stats.latency, stats.max_latency);
+        assert!(pacer.pending_input.is_empty());
+    }
+}
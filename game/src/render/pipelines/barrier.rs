@@ -0,0 +1,140 @@
+use bytemuck::{Pod, Zeroable};
+use common::bounds::WorldBounds;
+use common_log::span;
+use wgpu::{
+    BindGroup, BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState,
+    DepthStencilState, Device, FragmentState, FrontFace, MultisampleState, PolygonMode,
+    PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule,
+    StencilState, SurfaceConfiguration, VertexState,
+};
+
+use crate::{
+    render::{
+        buffer::Bufferable,
+        renderer::layouts::{LayoutId, Layouts},
+        texture::Texture,
+    },
+    test_buffer_align,
+};
+
+/// World border shown near the camera
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub struct BarrierLocals {
+    /// Border (blocks from the origin), bottom and top of the world, distance of the barrier
+    /// visibility (blocks)
+    bounds: [f32; 4],
+}
+
+impl Bufferable for BarrierLocals {
+    const LABEL: &'static str = "Uniform: BarrierLocals";
+}
+
+impl BarrierLocals {
+    /// Barrier is visible this far from the camera (blocks)
+    pub const DISTANCE: f32 = 16.0;
+
+    /// Nothing is drawn if `bounds` have no border
+    pub fn new(bounds: &WorldBounds) -> Self {
+        Self {
+            bounds: [
+                bounds.border.unwrap_or_default() as f32,
+                bounds.min_y as f32,
+                // Top of the highest block
+                (bounds.max_y + 1) as f32,
+                if bounds.border.is_some() {
+                    Self::DISTANCE
+                } else {
+                    0.0
+                },
+            ],
+        }
+    }
+}
+
+impl Default for BarrierLocals {
+    fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+test_buffer_align!(BarrierLocals, 16);
+
+pub struct BarrierBindGroup {
+    pub inner: BindGroup,
+}
+
+/// Draws animated walls on the world border around the camera
+pub struct BarrierPipeline {
+    pub inner: RenderPipeline,
+}
+
+impl BarrierPipeline {
+    pub const LAYOUTS: &'static [LayoutId] = &[LayoutId::Globals, LayoutId::Barrier];
+    /// Four walls of two triangles
+    pub const VERTICES: u32 = 24;
+
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+    ) -> Self {
+        span!(_guard, "BarrierPipeline::new");
+
+        let layout = layouts.pipeline_layout(device, "Barrier", Self::LAYOUTS, &[]);
+
+        Self {
+            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("RenderPipeline: Barrier"),
+                layout: Some(&layout),
+                // Vertex shader entry point. Vertices are generated from their indices
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Use vertices as triangles
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    // Walls are seen from both sides
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                // Hidden by terrain, blended over everything else
+                depth_stencil: Some(DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Less,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        blend: Some(BlendState::ALPHA_BLENDING),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            }),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use common_log::span;
+use wgpu::{
+    BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState,
+    Device, FragmentState, FrontFace, MultisampleState, PolygonMode, PrimitiveState,
+    PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule, StencilState,
+    SurfaceConfiguration, VertexState,
+};
+
+use crate::render::{
+    renderer::layouts::{LayoutId, Layouts},
+    texture::Texture,
+};
+
+/// Clears the viewport to the sky color, depth included. Render passes can only clear whole
+/// attachments
+pub struct ClearPipeline {
+    pub inner: RenderPipeline,
+}
+
+impl ClearPipeline {
+    pub const LAYOUTS: &'static [LayoutId] = &[LayoutId::Globals];
+
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+    ) -> Self {
+        span!(_guard, "ClearPipeline::new");
+
+        let layout = layouts.pipeline_layout(device, "Clear", Self::LAYOUTS, &[]);
+
+        Self {
+            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("RenderPipeline: Clear"),
+                layout: Some(&layout),
+                // Vertex shader entry point. Vertices are generated from their indices
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Use vertices as triangles
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                // Vertices are at the far plane, so depth is reset as well
+                depth_stencil: Some(DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: CompareFunction::Always,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        blend: Some(BlendState::REPLACE),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            }),
+        }
+    }
+}
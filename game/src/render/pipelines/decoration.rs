@@ -0,0 +1,87 @@
+use common_log::span;
+use wgpu::{
+    ColorTargetState, ColorWrites, Device, FragmentState, FrontFace, MultisampleState, PolygonMode,
+    PrimitiveState, PrimitiveTopology, RenderPipelineDescriptor, ShaderModule,
+    SurfaceConfiguration, VertexState,
+};
+
+use crate::render::{
+    primitives::decoration::DecorationInstance, push_constants::PushConstants,
+    renderer::layouts::Layouts,
+};
+
+use super::{FragmentVariant, VariantPipeline};
+
+/// Instanced grass tufts. Alpha tested and double sided like terrain cutout
+pub struct DecorationPipeline {
+    pub variants: VariantPipeline,
+}
+
+impl DecorationPipeline {
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+        push_constants: &PushConstants,
+    ) -> Self {
+        span!(_guard, "DecorationPipeline::new");
+
+        let layout = layouts.pipeline_layout(
+            device,
+            "Decoration",
+            push_constants.layouts(),
+            push_constants.ranges(),
+        );
+
+        let create = |variant: FragmentVariant| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(&format!("RenderPipeline: Decoration ({variant:?})")),
+                layout: Some(&layout),
+                // Vertex shader entry point. Vertices are generated from the vertex index
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[DecorationInstance::LAYOUT],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Use vertices as triangles
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    // Both sides of tufts are visible
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(variant.depth_stencil()),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: variant.entry_point(),
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        blend: Some(variant.blend()),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            })
+        };
+
+        Self {
+            variants: VariantPipeline::new(create),
+        }
+    }
+}
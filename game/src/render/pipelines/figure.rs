@@ -1,58 +1,48 @@
 use common_log::span;
 use wgpu::{
-    BindGroupLayoutEntry, BindingType, BlendState, BufferBindingType, ColorTargetState,
-    ColorWrites, CompareFunction, DepthBiasState, DepthStencilState, Device, Face, FragmentState,
-    FrontFace, MultisampleState, PipelineLayoutDescriptor, PolygonMode, PrimitiveState,
-    PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule, ShaderStages,
-    StencilState, SurfaceConfiguration, VertexState,
+    ColorTargetState, ColorWrites, Device, Face, FragmentState, FrontFace, MultisampleState,
+    PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipelineDescriptor, ShaderModule,
+    SurfaceConfiguration, VertexState,
 };
 
 use crate::render::{
-    primitives::{instance::RawInstance, vertex::Vertex},
-    texture::Texture,
+    primitives::{figure::FigureVertex, instance::RawInstance},
+    push_constants::PushConstants,
+    renderer::layouts::Layouts,
 };
 
-use super::GlobalLayout;
+use super::{FragmentVariant, VariantPipeline};
 
 pub struct FigurePipeline {
-    pub inner: RenderPipeline,
+    pub variants: VariantPipeline,
 }
 
 impl FigurePipeline {
-    pub const LAYOUT: BindGroupLayoutEntry = BindGroupLayoutEntry {
-        binding: 0,
-        visibility: ShaderStages::VERTEX,
-        ty: BindingType::Buffer {
-            ty: BufferBindingType::Uniform,
-            has_dynamic_offset: false,
-            min_binding_size: None,
-        },
-        count: None,
-    };
-
     pub fn new(
         device: &Device,
         config: &SurfaceConfiguration,
         shader: &ShaderModule,
-        globals_layout: &GlobalLayout,
+        layouts: &Layouts,
+        push_constants: &PushConstants,
     ) -> Self {
         span!(_guard, "FigurePipeline::new");
 
-        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("PipelineLayout: Figure"),
-            bind_group_layouts: &[&globals_layout.globals],
-            push_constant_ranges: &[],
-        });
+        let layout = layouts.pipeline_layout(
+            device,
+            "Figure",
+            push_constants.layouts(),
+            push_constants.ranges(),
+        );
 
-        Self {
-            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
-                label: Some("RenderPipeline: Figure"),
+        let create = |variant: FragmentVariant| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(&format!("RenderPipeline: Figure ({variant:?})")),
                 layout: Some(&layout),
                 // Vertex shader entry point
                 vertex: VertexState {
                     module: shader,
                     entry_point: "vs_main",
-                    buffers: &[Vertex::LAYOUT, RawInstance::LAYOUT],
+                    buffers: &[FigureVertex::LAYOUT, RawInstance::LAYOUT],
                 },
                 // Properties of pipeline at primitives assembly and rasterization
                 primitive: PrimitiveState {
@@ -67,14 +57,7 @@ impl FigurePipeline {
                     polygon_mode: PolygonMode::Fill,
                     conservative: false,
                 },
-                // No depth yet
-                depth_stencil: Some(DepthStencilState {
-                    format: Texture::DEPTH_FORMAT,
-                    depth_write_enabled: true,
-                    depth_compare: CompareFunction::Less,
-                    stencil: StencilState::default(),
-                    bias: DepthBiasState::default(),
-                }),
+                depth_stencil: Some(variant.depth_stencil()),
                 multisample: MultisampleState {
                     // 1 to disable MSAA
                     count: 1,
@@ -84,16 +67,20 @@ impl FigurePipeline {
                 },
                 fragment: Some(FragmentState {
                     module: shader,
-                    entry_point: "fs_main",
+                    entry_point: variant.entry_point(),
                     // Color output formats. Just set to surface format
                     targets: &[Some(ColorTargetState {
                         format: config.format,
-                        blend: Some(BlendState::REPLACE),
+                        blend: Some(variant.blend()),
                         write_mask: ColorWrites::ALL,
                     })],
                 }),
                 multiview: None,
-            }),
+            })
+        };
+
+        Self {
+            variants: VariantPipeline::new(create),
         }
     }
 }
@@ -0,0 +1,100 @@
+use common_log::span;
+use wgpu::{
+    BindGroup, BlendState, ColorTargetState, ColorWrites, DepthStencilState, Device, FragmentState,
+    FrontFace, MultisampleState, PolygonMode, PrimitiveState, PrimitiveTopology,
+    RenderPipelineDescriptor, ShaderModule, SurfaceConfiguration, VertexState,
+};
+
+use crate::render::{
+    primitives::terrain::TerrainVertex,
+    push_constants::PushConstants,
+    renderer::layouts::{LayoutId, Layouts},
+};
+
+use super::{FragmentVariant, VariantPipeline};
+
+/// Blended fluid surfaces. Scene depth is sampled for shoreline fading, so depth isn't written
+pub struct FluidPipeline {
+    pub variants: VariantPipeline,
+}
+
+impl FluidPipeline {
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+        push_constants: &PushConstants,
+    ) -> Self {
+        span!(_guard, "FluidPipeline::new");
+
+        let layout = layouts.pipeline_layout(
+            device,
+            "Fluid",
+            &push_constants.layouts_with(&[LayoutId::Depth]),
+            push_constants.ranges(),
+        );
+
+        let create = |variant: FragmentVariant| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(&format!("RenderPipeline: Fluid ({variant:?})")),
+                layout: Some(&layout),
+                // Vertex shader entry point
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[TerrainVertex::LAYOUT],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Use vertices as triangles
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    // Surface is visible from below too
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                // Depth texture is bound as a sampled input of the pass
+                depth_stencil: Some(DepthStencilState {
+                    depth_write_enabled: false,
+                    ..variant.depth_stencil()
+                }),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: variant.entry_point(),
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        blend: Some(match variant {
+                            FragmentVariant::Shaded => BlendState::ALPHA_BLENDING,
+                            _ => variant.blend(),
+                        }),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            })
+        };
+
+        Self {
+            variants: VariantPipeline::new(create),
+        }
+    }
+}
+
+/// Scene depth texture bound to the fluid pass
+pub struct DepthBindGroup {
+    pub inner: BindGroup,
+}
@@ -0,0 +1,87 @@
+use common_log::span;
+use wgpu::{
+    BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState,
+    Device, FragmentState, FrontFace, MultisampleState, PolygonMode, PrimitiveState,
+    PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule, StencilState,
+    SurfaceConfiguration, VertexState,
+};
+
+use crate::render::{
+    primitives::label::LabelVertex,
+    renderer::layouts::{LayoutId, Layouts},
+    texture::Texture,
+};
+
+/// Draws camera facing text labels (nameplates, debug markers)
+pub struct LabelPipeline {
+    pub inner: RenderPipeline,
+}
+
+impl LabelPipeline {
+    pub const LAYOUTS: &'static [LayoutId] = &[LayoutId::Globals];
+
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+    ) -> Self {
+        span!(_guard, "LabelPipeline::new");
+
+        let layout = layouts.pipeline_layout(device, "Label", Self::LAYOUTS, &[]);
+
+        Self {
+            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("RenderPipeline: Label"),
+                layout: Some(&layout),
+                // Vertex shader entry point
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[LabelVertex::LAYOUT],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Use vertices as triangles
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    // Labels are always turned towards the camera
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                // Labels are hidden by terrain, but don't hide each other
+                depth_stencil: Some(DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Less,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        // Alpha is used for distance fade
+                        blend: Some(BlendState::ALPHA_BLENDING),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            }),
+        }
+    }
+}
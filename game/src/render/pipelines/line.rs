@@ -0,0 +1,85 @@
+use common_log::span;
+use wgpu::{
+    BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState,
+    Device, FragmentState, FrontFace, MultisampleState, PolygonMode, PrimitiveState,
+    PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule, StencilState,
+    SurfaceConfiguration, VertexState,
+};
+
+use crate::render::{
+    primitives::line::DebugLineVertex,
+    renderer::layouts::{LayoutId, Layouts},
+    texture::Texture,
+};
+
+/// Draws colored debug lines
+pub struct LinePipeline {
+    pub inner: RenderPipeline,
+}
+
+impl LinePipeline {
+    pub const LAYOUTS: &'static [LayoutId] = &[LayoutId::Globals];
+
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+    ) -> Self {
+        span!(_guard, "LinePipeline::new");
+
+        let layout = layouts.pipeline_layout(device, "Line", Self::LAYOUTS, &[]);
+
+        Self {
+            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("RenderPipeline: Line"),
+                layout: Some(&layout),
+                // Vertex shader entry point
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[DebugLineVertex::LAYOUT],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Every two vertices make a line
+                    topology: PrimitiveTopology::LineList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                // Debug lines are visible through everything
+                depth_stencil: Some(DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Always,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        blend: Some(BlendState::ALPHA_BLENDING),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            }),
+        }
+    }
+}
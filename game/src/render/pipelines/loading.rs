@@ -0,0 +1,126 @@
+use bytemuck::{Pod, Zeroable};
+use common_log::span;
+use wgpu::{
+    BindGroup, BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState,
+    DepthStencilState, Device, FragmentState, FrontFace, MultisampleState, PolygonMode,
+    PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule,
+    StencilState, SurfaceConfiguration, VertexState,
+};
+
+use crate::{
+    render::{
+        buffer::Bufferable,
+        renderer::layouts::{LayoutId, Layouts},
+        texture::Texture,
+    },
+    test_buffer_align,
+    types::F32x3,
+};
+
+/// Loading screen state
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub struct LoadingLocals {
+    /// Progress (0..1), time since loading started (seconds), screen aspect ratio (w is unused)
+    progress: [f32; 4],
+    /// Background color (w is unused)
+    background: [f32; 4],
+}
+
+impl Bufferable for LoadingLocals {
+    const LABEL: &'static str = "Uniform: LoadingLocals";
+}
+
+impl LoadingLocals {
+    pub fn new(progress: f32, time: f32, aspect: f32, background: F32x3) -> Self {
+        Self {
+            progress: [progress, time, aspect, 0.0],
+            background: [background.x, background.y, background.z, 1.0],
+        }
+    }
+}
+
+impl Default for LoadingLocals {
+    fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+test_buffer_align!(LoadingLocals, 16);
+
+pub struct LoadingBindGroup {
+    pub inner: BindGroup,
+}
+
+/// Draws the loading screen over the whole frame
+pub struct LoadingPipeline {
+    pub inner: RenderPipeline,
+}
+
+impl LoadingPipeline {
+    /// Globals are unused, but keep group 0 compatible with the rest of the first pass
+    pub const LAYOUTS: &'static [LayoutId] = &[LayoutId::Globals, LayoutId::Loading];
+
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+    ) -> Self {
+        span!(_guard, "LoadingPipeline::new");
+
+        let layout = layouts.pipeline_layout(device, "Loading", Self::LAYOUTS, &[]);
+
+        Self {
+            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("RenderPipeline: Loading"),
+                layout: Some(&layout),
+                // Vertex shader entry point. Vertices are generated from their indices
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Use vertices as triangles
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                // Drawn instead of the scene
+                depth_stencil: Some(DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Always,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        blend: Some(BlendState::REPLACE),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            }),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use common::{block::Block, coord::CHUNK_CUBE, palette::Palette};
+use common_log::span;
+use wgpu::{
+    BindGroup, Buffer, BufferAddress, BufferDescriptor, BufferUsages, ComputePipeline,
+    ComputePipelineDescriptor, Device, ShaderModule,
+};
+
+use crate::{
+    render::{
+        buffer::Bufferable,
+        primitives::terrain::TerrainVertex,
+        renderer::layouts::{LayoutId, Layouts},
+    },
+    test_buffer_align,
+};
+
+/// Must match the palette size in the shader
+const PALETTE_SIZE: usize = 32;
+const _: () = assert!((Block::MAX as usize) < PALETTE_SIZE);
+
+/// Block colors and opacity for the meshing compute shader
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub struct MeshingPalette {
+    /// Block color (w is 1 for opaque blocks), indexed by the block id
+    colors: [[f32; 4]; PALETTE_SIZE],
+}
+
+impl Bufferable for MeshingPalette {
+    const LABEL: &'static str = "Uniform: MeshingPalette";
+}
+
+impl MeshingPalette {
+    pub fn new(palette: &Palette) -> Self {
+        let mut colors = [[0.0; 4]; PALETTE_SIZE];
+        Block::ALL.iter().for_each(|&block| {
+            colors[block.id() as usize] = palette
+                .color(block)
+                .extend(block.opaque() as u8 as f32)
+                .into();
+        });
+
+        Self { colors }
+    }
+}
+
+test_buffer_align!(MeshingPalette, 16);
+
+/// Input and output buffers of a single meshing dispatch
+pub struct MeshingBuffers {
+    /// Block ids (one per u32)
+    pub blocks: Buffer,
+    pub vertices: Buffer,
+    pub indices: Buffer,
+    /// Number of generated quads
+    pub counter: Buffer,
+    /// Copy of `counter` read by the CPU
+    pub readback: Buffer,
+}
+
+impl MeshingBuffers {
+    /// Every face of every block, so output never overflows
+    pub const MAX_QUADS: usize = CHUNK_CUBE * 6;
+    /// Counter buffer size (bytes)
+    pub const COUNTER_SIZE: BufferAddress = 16;
+
+    pub fn new(device: &Device) -> Self {
+        let create = |label: &str, size: usize, usage: BufferUsages| {
+            device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size: size as BufferAddress,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+
+        Self {
+            blocks: create(
+                "Buffer: Meshing blocks",
+                CHUNK_CUBE * size_of::<u32>(),
+                BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            ),
+            vertices: create(
+                "Buffer: Meshing vertices",
+                Self::MAX_QUADS * 4 * size_of::<TerrainVertex>(),
+                BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            ),
+            indices: create(
+                "Buffer: Meshing indices",
+                Self::MAX_QUADS * 6 * size_of::<u32>(),
+                BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            ),
+            counter: create(
+                "Buffer: Meshing counter",
+                Self::COUNTER_SIZE as usize,
+                BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            ),
+            readback: create(
+                "Buffer: Meshing readback",
+                Self::COUNTER_SIZE as usize,
+                BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            ),
+        }
+    }
+}
+
+pub struct MeshingBindGroup {
+    pub inner: BindGroup,
+}
+
+/// Generates terrain meshes from chunk blocks in a compute shader
+pub struct MeshingPipeline {
+    pub inner: ComputePipeline,
+}
+
+impl MeshingPipeline {
+    pub const LAYOUTS: &'static [LayoutId] = &[LayoutId::Meshing];
+    /// Must match `workgroup_size` in the shader
+    pub const WORKGROUP_SIZE: u32 = 64;
+
+    pub fn new(device: &Device, shader: &ShaderModule, layouts: &Layouts) -> Self {
+        span!(_guard, "MeshingPipeline::new");
+
+        let layout = layouts.pipeline_layout(device, "Meshing", Self::LAYOUTS, &[]);
+
+        Self {
+            inner: device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("ComputePipeline: Meshing"),
+                layout: Some(&layout),
+                module: shader,
+                entry_point: "cs_main",
+            }),
+        }
+    }
+
+    /// Number of workgroups to process a chunk
+    pub const fn workgroups() -> u32 {
+        (CHUNK_CUBE as u32).div_ceil(Self::WORKGROUP_SIZE)
+    }
+}
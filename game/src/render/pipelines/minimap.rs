@@ -0,0 +1,137 @@
+use bytemuck::{Pod, Zeroable};
+use common_log::span;
+use wgpu::{
+    BindGroup, BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState,
+    DepthStencilState, Device, FragmentState, FrontFace, MultisampleState, PolygonMode,
+    PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule,
+    StencilState, SurfaceConfiguration, VertexState,
+};
+
+use crate::{
+    render::{
+        buffer::Bufferable,
+        renderer::layouts::{LayoutId, Layouts},
+        texture::Texture,
+    },
+    test_buffer_align,
+    types::F32x2,
+};
+
+/// Minimap placement and view
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub struct MinimapLocals {
+    /// Minimap rectangle in clip space: center x, center y, half width, half height
+    rect: [f32; 4],
+    /// Player position in texture coordinates, visible radius in texture coordinates, camera yaw
+    view: [f32; 4],
+    /// Opacity (yzw are unused)
+    style: [f32; 4],
+}
+
+impl Bufferable for MinimapLocals {
+    const LABEL: &'static str = "Uniform: MinimapLocals";
+}
+
+impl MinimapLocals {
+    pub fn new(
+        center: F32x2,
+        half_size: F32x2,
+        player: F32x2,
+        radius: f32,
+        yaw: f32,
+        opacity: f32,
+    ) -> Self {
+        Self {
+            rect: [center.x, center.y, half_size.x, half_size.y],
+            view: [player.x, player.y, radius, yaw],
+            style: [opacity, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl Default for MinimapLocals {
+    fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+test_buffer_align!(MinimapLocals, 16);
+
+/// Minimap uniforms and color texture
+pub struct MinimapBindGroup {
+    pub inner: BindGroup,
+}
+
+/// Draws the minimap in the screen corner
+pub struct MinimapPipeline {
+    pub inner: RenderPipeline,
+}
+
+impl MinimapPipeline {
+    /// Globals are unused, but keep group 0 compatible with the rest of the first pass
+    pub const LAYOUTS: &'static [LayoutId] = &[LayoutId::Globals, LayoutId::Minimap];
+
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+    ) -> Self {
+        span!(_guard, "MinimapPipeline::new");
+
+        let layout = layouts.pipeline_layout(device, "Minimap", Self::LAYOUTS, &[]);
+
+        Self {
+            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("RenderPipeline: Minimap"),
+                layout: Some(&layout),
+                // Vertex shader entry point. Vertices are generated from their indices
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Use vertices as triangles
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                // HUD is drawn over everything
+                depth_stencil: Some(DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Always,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        blend: Some(BlendState::ALPHA_BLENDING),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            }),
+        }
+    }
+}
@@ -1,24 +1,172 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
 use bytemuck::{Pod, Zeroable};
 use wgpu::{
-    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
-    BindGroupLayoutEntry, Device, ShaderStages,
+    BindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState, CompareFunction,
+    DepthBiasState, DepthStencilState, RenderPipeline, StencilState,
 };
 
 use crate::{
     test_buffer_align,
-    types::{Mat4, RawMat4},
+    types::{F32x2, F32x3, Mat4, RawMat4},
 };
 
 use super::{
     buffer::{Bufferable, Consts},
     renderer::Renderer,
+    texture::Texture,
+    DebugView,
 };
 
+pub mod barrier;
+pub mod clear;
+pub mod decoration;
 pub mod figure;
+pub mod fluid;
+pub mod label;
+pub mod line;
+pub mod loading;
+pub mod meshing;
+pub mod minimap;
+pub mod post;
+pub mod selection;
 pub mod terrain;
+pub mod text;
+
+/// Fragment stage variant of a pipeline. Debug variants are used by [`DebugView`]s
+#[derive(Clone, Copy, Debug)]
+pub enum FragmentVariant {
+    Shaded,
+    /// Intermediate values (`fs_debug`)
+    Debug,
+    /// Additive fragment counter (`fs_overdraw`) without depth testing
+    Overdraw,
+}
+
+impl FragmentVariant {
+    pub const fn entry_point(self) -> &'static str {
+        match self {
+            Self::Shaded => "fs_main",
+            Self::Debug => "fs_debug",
+            Self::Overdraw => "fs_overdraw",
+        }
+    }
+
+    pub const fn blend(self) -> BlendState {
+        match self {
+            Self::Shaded | Self::Debug => BlendState::REPLACE,
+            Self::Overdraw => BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::One,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent::REPLACE,
+            },
+        }
+    }
+
+    pub fn depth_stencil(self) -> DepthStencilState {
+        let overdraw = matches!(self, Self::Overdraw);
+
+        DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled: !overdraw,
+            depth_compare: if overdraw {
+                CompareFunction::Always
+            } else {
+                CompareFunction::Less
+            },
+            stencil: StencilState::default(),
+            bias: DepthBiasState::default(),
+        }
+    }
+}
+
+/// Pipeline with its debug variants
+pub struct VariantPipeline {
+    pub inner: RenderPipeline,
+    pub debug: RenderPipeline,
+    pub overdraw: RenderPipeline,
+}
+
+impl VariantPipeline {
+    pub fn new(create: impl Fn(FragmentVariant) -> RenderPipeline) -> Self {
+        Self {
+            inner: create(FragmentVariant::Shaded),
+            debug: create(FragmentVariant::Debug),
+            overdraw: create(FragmentVariant::Overdraw),
+        }
+    }
+
+    /// Pipeline for the debug view
+    pub fn get(&self, view: DebugView) -> &RenderPipeline {
+        match view {
+            DebugView::Shaded => &self.inner,
+            DebugView::Overdraw => &self.overdraw,
+            _ => &self.debug,
+        }
+    }
+}
 
-// TODO: Make global layout
-// TODO: Make bind groups for new layout system
+/// Scene lighting and atmosphere parameters
+#[derive(Clone, Copy, Debug)]
+pub struct Environment {
+    /// Sky (clear) color, also used for fog
+    pub sky_color: F32x3,
+    /// Distance where fog starts
+    pub fog_start: f32,
+    /// Distance where everything is hidden by fog
+    pub fog_end: f32,
+    /// Sun elevation above the horizon (radians)
+    pub sun_elevation: f32,
+    /// Sun direction on the horizontal plane (radians)
+    pub sun_azimuth: f32,
+    /// Intensity of direct sun light
+    pub light_intensity: f32,
+    /// Light received by surfaces facing away from the sun
+    pub ambient: f32,
+}
+
+impl Environment {
+    /// Share of the far plane where fog hides everything. The far plane reaches the corners of
+    /// the load area, so this is its edge
+    const FOG_END: f32 = FRAC_1_SQRT_2;
+    /// Share of the fog end where fog starts
+    const FOG_START: f32 = 0.5;
+
+    /// Place fog at the end of the view distance, so terrain fades out instead of being cut by
+    /// the far plane or the load area
+    pub fn fit_fog(&mut self, far: f32) {
+        self.fog_end = far * Self::FOG_END;
+        self.fog_start = self.fog_end * Self::FOG_START;
+    }
+
+    /// Unit vector pointing towards the sun
+    pub fn sun_dir(&self) -> F32x3 {
+        let (elevation_sin, elevation_cos) = self.sun_elevation.sin_cos();
+        let (azimuth_sin, azimuth_cos) = self.sun_azimuth.sin_cos();
+        F32x3::new(
+            azimuth_sin * elevation_cos,
+            elevation_sin,
+            azimuth_cos * elevation_cos,
+        )
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            sky_color: F32x3::new(0.458, 0.909, 1.0),
+            fog_start: 96.0,
+            fog_end: 192.0,
+            sun_elevation: 1.0,
+            sun_azimuth: 0.5,
+            light_intensity: 0.4,
+            ambient: 0.6,
+        }
+    }
+}
 
 #[repr(C)]
 #[derive(Pod, Zeroable, Clone, Copy)]
@@ -29,6 +177,20 @@ pub struct Globals {
     view_mat: RawMat4,
     /// proj_mat * view_mat
     all_mat: RawMat4,
+    /// Camera eye position (w is unused)
+    cam_pos: [f32; 4],
+    /// Sky color (w is unused)
+    sky_color: [f32; 4],
+    /// Direction towards the sun (w is light intensity)
+    sun_dir: [f32; 4],
+    /// Fog start, fog end, ambient light (w is unused)
+    fog: [f32; 4],
+    /// Debug view id (yzw are unused)
+    debug: [u32; 4],
+    /// Elapsed time, frame time (seconds, zw are unused)
+    time: [f32; 4],
+    /// Resolution (pixels), inverse resolution
+    screen: [f32; 4],
 }
 
 impl Bufferable for Globals {
@@ -36,22 +198,53 @@ impl Bufferable for Globals {
 }
 
 impl Globals {
-    pub fn new(proj_mat: Mat4, view_mat: Mat4) -> Self {
+    pub fn new(
+        proj_mat: Mat4,
+        view_mat: Mat4,
+        env: &Environment,
+        debug_view: DebugView,
+        time: f32,
+        delta: f32,
+        resolution: F32x2,
+    ) -> Self {
+        let cam_pos = view_mat.inverse().w_axis;
+        let resolution = resolution.max(F32x2::ONE);
+
         Self {
             proj_mat: proj_mat.to_cols_array_2d(),
             view_mat: view_mat.to_cols_array_2d(),
             all_mat: (proj_mat * view_mat).to_cols_array_2d(),
+            cam_pos: cam_pos.to_array(),
+            sky_color: env.sky_color.extend(1.0).to_array(),
+            sun_dir: env.sun_dir().extend(env.light_intensity).to_array(),
+            fog: [env.fog_start, env.fog_end, env.ambient, 0.0],
+            debug: [debug_view.id(), 0, 0, 0],
+            time: [time, delta, 0.0, 0.0],
+            screen: [
+                resolution.x,
+                resolution.y,
+                1.0 / resolution.x,
+                1.0 / resolution.y,
+            ],
         }
     }
 }
 
 impl Default for Globals {
     fn default() -> Self {
-        Self::new(Mat4::IDENTITY, Mat4::IDENTITY)
+        Self::new(
+            Mat4::IDENTITY,
+            Mat4::IDENTITY,
+            &Environment::default(),
+            DebugView::Shaded,
+            0.0,
+            0.0,
+            F32x2::ONE,
+        )
     }
 }
 
-test_buffer_align!(Globals);
+test_buffer_align!(Globals, 16);
 
 /// Global scene data
 pub struct GlobalModel {
@@ -71,50 +264,7 @@ pub struct GlobalsBindGroup {
     pub inner: BindGroup,
 }
 
-/// Represents created layouts on the GPU
-pub struct GlobalLayout {
-    pub globals: BindGroupLayout,
-}
-
-impl GlobalLayout {
-    const BASE_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] = &[
-        // Globals uniform
-        BindGroupLayoutEntry {
-            binding: 0,
-            visibility: ShaderStages::VERTEX_FRAGMENT,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform,
-                has_dynamic_offset: false,
-                min_binding_size: None,
-            },
-            count: None,
-        },
-    ];
-
-    const BASE_LAYOUT_DESC: BindGroupLayoutDescriptor<'static> = BindGroupLayoutDescriptor {
-        label: Some("BindGroupLayout: Globals"),
-        entries: Self::BASE_LAYOUT_ENTRIES,
-    };
-
-    pub fn new(device: &Device) -> Self {
-        Self {
-            globals: device.create_bind_group_layout(&Self::BASE_LAYOUT_DESC),
-        }
-    }
-
-    pub fn bind_globals(&self, device: &Device, global_model: &GlobalModel) -> GlobalsBindGroup {
-        GlobalsBindGroup {
-            inner: device.create_bind_group(&BindGroupDescriptor {
-                label: Some("BindGroup: Globals"),
-                layout: &self.globals,
-                entries: &[
-                    // Globals uniform
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: global_model.globals.buffer().as_entire_binding(),
-                    },
-                ],
-            }),
-        }
-    }
+/// Bind group of per-object uniforms from [`super::buffer::DynamicConsts`], bound with a dynamic offset
+pub struct LocalsBindGroup {
+    pub inner: BindGroup,
 }
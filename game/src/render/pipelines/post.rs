@@ -0,0 +1,139 @@
+use bytemuck::{Pod, Zeroable};
+use common_log::span;
+use wgpu::{
+    BindGroup, BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState,
+    DepthStencilState, Device, FragmentState, FrontFace, MultisampleState, PolygonMode,
+    PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule,
+    StencilState, SurfaceConfiguration, VertexState,
+};
+
+use crate::{
+    render::{
+        buffer::Bufferable,
+        renderer::layouts::{LayoutId, Layouts},
+        texture::Texture,
+    },
+    test_buffer_align,
+    types::F32x3,
+};
+
+/// Screen effects applied by the post pass
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub struct PostLocals {
+    /// Color multiplied over the scene (w is the distortion strength)
+    tint: [f32; 4],
+    /// Damage vignette intensity, fade to black (0..1, zw are unused)
+    effects: [f32; 4],
+    /// Crosshair half length and thickness in pixels, opacity (w is unused). Hidden if the
+    /// length is 0
+    crosshair: [f32; 4],
+}
+
+impl Bufferable for PostLocals {
+    const LABEL: &'static str = "Uniform: PostLocals";
+}
+
+impl PostLocals {
+    pub fn new(tint: F32x3, distortion: f32, vignette: f32, fade: f32) -> Self {
+        Self {
+            tint: [tint.x, tint.y, tint.z, distortion],
+            effects: [vignette, fade, 0.0, 0.0],
+            crosshair: [0.0; 4],
+        }
+    }
+
+    pub fn with_crosshair(mut self, length: f32, thickness: f32, opacity: f32) -> Self {
+        self.crosshair = [length, thickness, opacity, 0.0];
+        self
+    }
+}
+
+impl Default for PostLocals {
+    fn default() -> Self {
+        Self::new(F32x3::ONE, 0.0, 0.0, 0.0)
+    }
+}
+
+test_buffer_align!(PostLocals, 16);
+
+/// Scene color of the first and second passes. Rebound whenever the texture is recreated
+pub struct SceneBindGroup {
+    pub inner: BindGroup,
+}
+
+pub struct PostBindGroup {
+    pub inner: BindGroup,
+}
+
+/// Copies the scene to the screen applying camera state effects (underwater, damage, death)
+pub struct PostPipeline {
+    pub inner: RenderPipeline,
+}
+
+impl PostPipeline {
+    pub const LAYOUTS: &'static [LayoutId] = &[LayoutId::Globals, LayoutId::Scene, LayoutId::Post];
+
+    pub fn new(
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shader: &ShaderModule,
+        layouts: &Layouts,
+    ) -> Self {
+        span!(_guard, "PostPipeline::new");
+
+        let layout = layouts.pipeline_layout(device, "Post", Self::LAYOUTS, &[]);
+
+        Self {
+            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("RenderPipeline: Post"),
+                layout: Some(&layout),
+                // Vertex shader entry point. Vertices are generated from their indices
+                vertex: VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                // Properties of pipeline at primitives assembly and rasterization
+                primitive: PrimitiveState {
+                    // Use vertices as triangles
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    // Used for example to draw wireframes
+                    // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                // Covers the whole frame
+                depth_stencil: Some(DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Always,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                multisample: MultisampleState {
+                    // 1 to disable MSAA
+                    count: 1,
+                    mask: !0,
+                    // Something about anti-aliasing
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    // Color output formats. Just set to surface format
+                    targets: &[Some(ColorTargetState {
+                        format: config.format,
+                        blend: Some(BlendState::REPLACE),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            }),
+        }
+    }
+}
@@ -1,17 +1,20 @@
 use common_log::span;
 use wgpu::{
-    BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState,
-    Device, Face, FragmentState, FrontFace, MultisampleState, PipelineLayoutDescriptor,
-    PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor,
-    ShaderModule, StencilState, SurfaceConfiguration, VertexState,
+    ColorTargetState, ColorWrites, Device, Face, FragmentState, FrontFace, MultisampleState,
+    PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipelineDescriptor, ShaderModule,
+    SurfaceConfiguration, VertexState,
 };
 
-use crate::render::{primitives::vertex::Vertex, texture::Texture};
+use crate::render::{
+    primitives::terrain::TerrainVertex, push_constants::PushConstants, renderer::layouts::Layouts,
+};
 
-use super::GlobalLayout;
+use super::{FragmentVariant, VariantPipeline};
 
 pub struct TerrainPipeline {
-    pub inner: RenderPipeline,
+    pub variants: VariantPipeline,
+    /// Alpha tested, double sided variant for cross blocks (plants)
+    pub cutout: VariantPipeline,
 }
 
 impl TerrainPipeline {
@@ -19,25 +22,31 @@ impl TerrainPipeline {
         device: &Device,
         config: &SurfaceConfiguration,
         shader: &ShaderModule,
-        globals_layout: &GlobalLayout,
+        layouts: &Layouts,
+        push_constants: &PushConstants,
     ) -> Self {
         span!(_guard, "TerrainPipeline::new");
 
-        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("PipelineLayout: Terrain"),
-            bind_group_layouts: &[&globals_layout.globals],
-            push_constant_ranges: &[],
-        });
+        let layout = layouts.pipeline_layout(
+            device,
+            "Terrain",
+            push_constants.layouts(),
+            push_constants.ranges(),
+        );
 
-        Self {
-            inner: device.create_render_pipeline(&RenderPipelineDescriptor {
-                label: Some("RenderPipeline: Terrain"),
+        let create = |variant: FragmentVariant, cutout: bool| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(&if cutout {
+                    format!("RenderPipeline: Terrain cutout ({variant:?})")
+                } else {
+                    format!("RenderPipeline: Terrain ({variant:?})")
+                }),
                 layout: Some(&layout),
                 // Vertex shader entry point
                 vertex: VertexState {
                     module: shader,
                     entry_point: "vs_main",
-                    buffers: &[Vertex::LAYOUT],
+                    buffers: &[TerrainVertex::LAYOUT],
                 },
                 // Properties of pipeline at primitives assembly and rasterization
                 primitive: PrimitiveState {
@@ -45,21 +54,15 @@ impl TerrainPipeline {
                     topology: PrimitiveTopology::TriangleList,
                     strip_index_format: None,
                     front_face: FrontFace::Cw,
-                    cull_mode: Some(Face::Back),
+                    // Both sides of plant quads are visible
+                    cull_mode: (!cutout).then_some(Face::Back),
                     unclipped_depth: false,
                     // Used for example to draw wireframes
                     // Requires `NON_FILL_POLYGON_MODE` feature from GPU device
                     polygon_mode: PolygonMode::Fill,
                     conservative: false,
                 },
-                // No depth yet
-                depth_stencil: Some(DepthStencilState {
-                    format: Texture::DEPTH_FORMAT,
-                    depth_write_enabled: true,
-                    depth_compare: CompareFunction::Less,
-                    stencil: StencilState::default(),
-                    bias: DepthBiasState::default(),
-                }),
+                depth_stencil: Some(variant.depth_stencil()),
                 multisample: MultisampleState {
                     // 1 to disable MSAA
                     count: 1,
@@ -69,16 +72,24 @@ impl TerrainPipeline {
                 },
                 fragment: Some(FragmentState {
                     module: shader,
-                    entry_point: "fs_main",
+                    entry_point: match variant {
+                        FragmentVariant::Shaded if cutout => "fs_cutout",
+                        _ => variant.entry_point(),
+                    },
                     // Color output formats. Just set to surface format
                     targets: &[Some(ColorTargetState {
                         format: config.format,
-                        blend: Some(BlendState::REPLACE),
+                        blend: Some(variant.blend()),
                         write_mask: ColorWrites::ALL,
                     })],
                 }),
                 multiview: None,
-            }),
+            })
+        };
+
+        Self {
+            variants: VariantPipeline::new(|variant| create(variant, false)),
+            cutout: VariantPipeline::new(|variant| create(variant, true)),
         }
     }
 }
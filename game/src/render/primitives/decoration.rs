@@ -0,0 +1,46 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use common::coord::BlockCoord;
+use wgpu::{vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+
+use crate::{render::buffer::Bufferable, test_buffer_align, types::F32x3};
+
+/// Grass tuft on top of a block. Geometry (two crossed quads) is generated in the vertex shader
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub struct DecorationInstance {
+    /// Position of the supporting block in the chunk (4 bits per axis, x is the lowest) and the
+    /// random seed of the shape in the high 16 bits
+    pub packed: u32,
+    /// RGB color (alpha is unused)
+    pub color: [u8; 4],
+}
+
+impl Bufferable for DecorationInstance {
+    const LABEL: &'static str = "DecorationInstanceBuffer";
+}
+
+test_buffer_align!(DecorationInstance);
+
+impl DecorationInstance {
+    /// Vertices per instance (two quads)
+    pub const VERTICES: u32 = 12;
+
+    pub const ATTRS: [VertexAttribute; 2] = vertex_attr_array![0 => Uint32, 1 => Unorm8x4];
+
+    pub const LAYOUT: VertexBufferLayout<'static> = VertexBufferLayout {
+        array_stride: size_of::<Self>() as BufferAddress,
+        step_mode: VertexStepMode::Instance,
+        attributes: &Self::ATTRS,
+    };
+
+    pub fn new(pos: BlockCoord, seed: u16, color: F32x3) -> Self {
+        let rgb = color.clamp(F32x3::ZERO, F32x3::ONE) * 255.0;
+
+        Self {
+            packed: pos.x as u32 | (pos.y as u32) << 4 | (pos.z as u32) << 8 | (seed as u32) << 16,
+            color: [rgb.x as u8, rgb.y as u8, rgb.z as u8, 255],
+        }
+    }
+}
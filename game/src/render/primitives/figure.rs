@@ -0,0 +1,35 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+
+use crate::{render::buffer::Bufferable, test_buffer_align, types::F32x3};
+
+/// Vertex of figure models. Placed in the world by [`super::instance::RawInstance`]
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub struct FigureVertex {
+    pub position: F32x3,
+    pub color: F32x3,
+}
+
+impl Bufferable for FigureVertex {
+    const LABEL: &'static str = "FigureVertexBuffer";
+}
+
+test_buffer_align!(FigureVertex);
+
+impl FigureVertex {
+    pub const ATTRS: [VertexAttribute; 2] = vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    pub const LAYOUT: VertexBufferLayout<'static> = VertexBufferLayout {
+        array_stride: size_of::<Self>() as BufferAddress,
+        step_mode: VertexStepMode::Vertex,
+        attributes: &Self::ATTRS,
+    };
+
+    #[inline]
+    pub const fn new(position: F32x3, color: F32x3) -> Self {
+        Self { position, color }
+    }
+}
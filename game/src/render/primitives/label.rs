@@ -0,0 +1,48 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+
+use crate::{
+    render::buffer::Bufferable,
+    test_buffer_align,
+    types::{F32x2, F32x3},
+};
+
+/// Vertex of a camera facing label
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub struct LabelVertex {
+    /// World position of the label
+    pub anchor: F32x3,
+    /// Offset from the anchor in camera space (world units)
+    pub offset: F32x2,
+    /// RGBA color
+    pub color: [u8; 4],
+}
+
+impl Bufferable for LabelVertex {
+    const LABEL: &'static str = "LabelVertexBuffer";
+}
+
+test_buffer_align!(LabelVertex);
+
+impl LabelVertex {
+    pub const ATTRS: [VertexAttribute; 3] =
+        vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Unorm8x4];
+
+    pub const LAYOUT: VertexBufferLayout<'static> = VertexBufferLayout {
+        array_stride: size_of::<Self>() as BufferAddress,
+        step_mode: VertexStepMode::Vertex,
+        attributes: &Self::ATTRS,
+    };
+
+    #[inline]
+    pub const fn new(anchor: F32x3, offset: F32x2, color: [u8; 4]) -> Self {
+        Self {
+            anchor,
+            offset,
+            color,
+        }
+    }
+}
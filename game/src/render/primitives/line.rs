@@ -0,0 +1,36 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+
+use crate::{render::buffer::Bufferable, test_buffer_align, types::F32x3};
+
+/// Vertex of a debug line (every two vertices make a line)
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub struct DebugLineVertex {
+    pub pos: F32x3,
+    /// RGBA color
+    pub color: [u8; 4],
+}
+
+impl Bufferable for DebugLineVertex {
+    const LABEL: &'static str = "DebugLineVertexBuffer";
+}
+
+test_buffer_align!(DebugLineVertex);
+
+impl DebugLineVertex {
+    pub const ATTRS: [VertexAttribute; 2] = vertex_attr_array![0 => Float32x3, 1 => Unorm8x4];
+
+    pub const LAYOUT: VertexBufferLayout<'static> = VertexBufferLayout {
+        array_stride: size_of::<Self>() as BufferAddress,
+        step_mode: VertexStepMode::Vertex,
+        attributes: &Self::ATTRS,
+    };
+
+    #[inline]
+    pub const fn new(pos: F32x3, color: [u8; 4]) -> Self {
+        Self { pos, color }
+    }
+}
@@ -1,3 +1,8 @@
+pub mod decoration;
+pub mod figure;
 pub mod instance;
+pub mod label;
+pub mod line;
 pub mod quad;
-pub mod vertex;
+pub mod terrain;
+pub mod text;
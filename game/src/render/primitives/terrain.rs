@@ -5,22 +5,21 @@ use wgpu::{vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout
 
 use crate::{render::buffer::Bufferable, test_buffer_align, types::F32x3};
 
-// TODO: Make separate vertex structs for each pipeline
-/// Represents vertex data sent to vertex buffer
+/// Vertex of terrain meshes (also used by the debug pyramid)
 #[repr(C)]
 #[derive(Pod, Zeroable, Copy, Clone, Debug)]
-pub struct Vertex {
+pub struct TerrainVertex {
     pub position: F32x3,
     pub color: F32x3,
 }
 
-impl Bufferable for Vertex {
-    const LABEL: &'static str = "VertexBuffer";
+impl Bufferable for TerrainVertex {
+    const LABEL: &'static str = "TerrainVertexBuffer";
 }
 
-test_buffer_align!(Vertex);
+test_buffer_align!(TerrainVertex);
 
-impl Vertex {
+impl TerrainVertex {
     #[rustfmt::skip]
     pub const PYRAMID: &'static [Self] = &[
         // Top point of pyramid
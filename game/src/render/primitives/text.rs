@@ -0,0 +1,37 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+
+use crate::{render::buffer::Bufferable, test_buffer_align, types::F32x2};
+
+/// Vertex of screen-space text
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub struct TextVertex {
+    /// Position in pixels from the top left corner of the screen
+    pub pos: F32x2,
+    /// RGBA color
+    pub color: [u8; 4],
+}
+
+impl Bufferable for TextVertex {
+    const LABEL: &'static str = "TextVertexBuffer";
+}
+
+test_buffer_align!(TextVertex, 4);
+
+impl TextVertex {
+    pub const ATTRS: [VertexAttribute; 2] = vertex_attr_array![0 => Float32x2, 1 => Unorm8x4];
+
+    pub const LAYOUT: VertexBufferLayout<'static> = VertexBufferLayout {
+        array_stride: size_of::<Self>() as BufferAddress,
+        step_mode: VertexStepMode::Vertex,
+        attributes: &Self::ATTRS,
+    };
+
+    #[inline]
+    pub const fn new(pos: F32x2, color: [u8; 4]) -> Self {
+        Self { pos, color }
+    }
+}
@@ -0,0 +1,235 @@
+use std::{borrow::Cow, cell::RefCell, mem::size_of};
+
+use bytemuck::{bytes_of, Pod, Zeroable};
+use tracing::{info, warn};
+use wgpu::{Device, PushConstantRange, Queue, RenderPass, ShaderStages};
+
+use crate::{test_buffer_align, types::F32x3};
+
+use super::{
+    buffer::{Bufferable, DynamicConsts},
+    error::RenderError,
+    pipelines::LocalsBindGroup,
+    renderer::layouts::{LayoutId, Layouts},
+};
+
+/// Small per-draw data (chunk offset, figure tint)
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, PartialEq, Debug)]
+pub struct DrawData {
+    /// Added to vertex positions (w is unused)
+    offset: [f32; 4],
+    /// Multiplies vertex colors (w is unused)
+    tint: [f32; 4],
+}
+
+impl Bufferable for DrawData {
+    const LABEL: &'static str = "Uniform: DrawData";
+}
+
+impl DrawData {
+    pub const IDENTITY: Self = Self::new(F32x3::ZERO, F32x3::ONE);
+
+    pub const fn new(offset: F32x3, tint: F32x3) -> Self {
+        Self {
+            offset: [offset.x, offset.y, offset.z, 0.0],
+            tint: [tint.x, tint.y, tint.z, 1.0],
+        }
+    }
+
+    pub const fn offset(offset: F32x3) -> Self {
+        Self::new(offset, F32x3::ONE)
+    }
+
+    pub const fn tint(tint: F32x3) -> Self {
+        Self::new(F32x3::ZERO, tint)
+    }
+}
+
+impl Default for DrawData {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+test_buffer_align!(DrawData, 16);
+
+/// Way [`DrawData`] is passed to shaders.
+///
+/// Push constants are used when the device supports them. Otherwise draw data of the whole frame
+/// is collected into [`DynamicConsts`] bound at group 1 with a dynamic offset per draw
+pub enum PushConstants {
+    Native,
+    Uniform {
+        consts: DynamicConsts<DrawData>,
+        bind_group: LocalsBindGroup,
+        /// Draw data of the current frame. Written to `consts` before submission
+        pending: RefCell<Vec<DrawData>>,
+    },
+}
+
+impl PushConstants {
+    const STAGES: ShaderStages = ShaderStages::VERTEX_FRAGMENT;
+    const RANGES: &'static [PushConstantRange] = &[PushConstantRange {
+        stages: Self::STAGES,
+        range: 0..size_of::<DrawData>() as u32,
+    }];
+    /// Initial number of draws per frame for the uniform fallback
+    const INITIAL_CAPACITY: usize = 1024;
+
+    /// Declaration of `draw` in shaders. Replaced with a push constant declaration if supported
+    const UNIFORM_DECLARATION: &'static str =
+        "@group(1)\n@binding(0)\nvar<uniform> draw: DrawData;";
+    const PUSH_CONSTANT_DECLARATION: &'static str = "var<push_constant> draw: DrawData;";
+    /// Group following `draw` in shaders. Takes its place if push constants are supported
+    const NEXT_GROUP: &'static str = "@group(2)";
+    const PUSH_CONSTANT_NEXT_GROUP: &'static str = "@group(1)";
+
+    pub fn new(device: &Device, layouts: &Layouts) -> Self {
+        let supported = device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+            && device.limits().max_push_constant_size as usize >= size_of::<DrawData>();
+
+        if supported {
+            info!("Using push constants for per-draw data");
+            Self::Native
+        } else {
+            info!("Push constants aren't supported, using uniform buffer for per-draw data");
+            let consts = DynamicConsts::new(device, Self::INITIAL_CAPACITY);
+            Self::Uniform {
+                bind_group: Self::bind(device, layouts, &consts),
+                consts,
+                pending: RefCell::new(Vec::with_capacity(Self::INITIAL_CAPACITY)),
+            }
+        }
+    }
+
+    /// Push constant ranges of pipelines using [`DrawData`]
+    pub fn ranges(&self) -> &'static [PushConstantRange] {
+        match self {
+            Self::Native => Self::RANGES,
+            Self::Uniform { .. } => &[],
+        }
+    }
+
+    /// Bind group layouts of pipelines using [`DrawData`]
+    pub fn layouts(&self) -> &'static [LayoutId] {
+        match self {
+            Self::Native => &[LayoutId::Globals],
+            Self::Uniform { .. } => &[LayoutId::Globals, LayoutId::Locals],
+        }
+    }
+
+    /// Same as [`Self::layouts`] followed by `extra` groups (declared from group 2 in shaders)
+    pub fn layouts_with(&self, extra: &[LayoutId]) -> Vec<LayoutId> {
+        [self.layouts(), extra].concat()
+    }
+
+    /// Index of the first group after [`Self::layouts`]
+    pub fn next_group(&self) -> u32 {
+        self.layouts().len() as u32
+    }
+
+    fn bind(
+        device: &Device,
+        layouts: &Layouts,
+        consts: &DynamicConsts<DrawData>,
+    ) -> LocalsBindGroup {
+        LocalsBindGroup {
+            inner: layouts.bind(device, LayoutId::Locals, &[consts.binding()]),
+        }
+    }
+
+    /// Shader source with `draw` declared for this mode
+    pub fn shader_source<'a>(&self, source: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Native => Cow::Owned(
+                source
+                    .replace(Self::UNIFORM_DECLARATION, Self::PUSH_CONSTANT_DECLARATION)
+                    .replace(Self::NEXT_GROUP, Self::PUSH_CONSTANT_NEXT_GROUP),
+            ),
+            Self::Uniform { .. } => Cow::Borrowed(source),
+        }
+    }
+
+    /// Prepare for a new frame. Grows the uniform buffer if the last frame didn't fit
+    pub fn begin_frame(&mut self, device: &Device, layouts: &Layouts) {
+        if let Self::Uniform {
+            consts,
+            bind_group,
+            pending,
+        } = self
+        {
+            let pending = pending.get_mut();
+            if consts.ensure_capacity(device, pending.len()) {
+                *bind_group = Self::bind(device, layouts, consts);
+            }
+            pending.clear();
+        }
+    }
+
+    /// Set draw data for the following draw calls
+    pub fn set<'a>(&'a self, render_pass: &mut RenderPass<'a>, data: DrawData) {
+        match self {
+            Self::Native => render_pass.set_push_constants(Self::STAGES, 0, bytes_of(&data)),
+            Self::Uniform {
+                consts,
+                bind_group,
+                pending,
+            } => {
+                let mut pending = pending.borrow_mut();
+                pending.push(data);
+                // Extra draws reuse the last block. The buffer grows on the next frame
+                let index = (pending.len() - 1).min(consts.length() - 1);
+                render_pass.set_bind_group(1, &bind_group.inner, &[consts.offset(index)]);
+            }
+        }
+    }
+
+    /// Upload draw data of the frame. Called before submission
+    pub fn flush(&self, queue: &Queue) -> Result<(), RenderError> {
+        if let Self::Uniform {
+            consts, pending, ..
+        } = self
+        {
+            let pending = pending.borrow();
+            if pending.len() > consts.length() {
+                warn!(
+                    draws = pending.len(),
+                    capacity = consts.length(),
+                    "Too many draws for the draw data buffer"
+                );
+            }
+            consts.update(queue, &pending[..pending.len().min(consts.length())], 0)?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::render::shader::{
+        DecorationShader, DrawShader, FigureShader, FluidShader, TerrainShader,
+    };
+
+    use super::PushConstants;
+
+    #[test]
+    fn push_constant_declaration() {
+        for source in [
+            TerrainShader::SOURCE,
+            FigureShader::SOURCE,
+            DecorationShader::SOURCE,
+            FluidShader::SOURCE,
+        ] {
+            let native = PushConstants::Native.shader_source(source);
+            assert!(native.contains(PushConstants::PUSH_CONSTANT_DECLARATION));
+            assert!(!native.contains(PushConstants::UNIFORM_DECLARATION));
+            assert!(!native.contains(PushConstants::NEXT_GROUP));
+        }
+    }
+}
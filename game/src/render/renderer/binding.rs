@@ -1,11 +1,147 @@
-use crate::render::pipelines::{GlobalModel, GlobalsBindGroup};
+use bytemuck::Pod;
+use wgpu::{BindingResource, Device};
 
-use super::Renderer;
+use crate::render::{
+    buffer::{Bufferable, Consts, DynamicConsts},
+    pipelines::{
+        barrier::{BarrierBindGroup, BarrierLocals},
+        fluid::DepthBindGroup,
+        loading::{LoadingBindGroup, LoadingLocals},
+        meshing::{MeshingBindGroup, MeshingBuffers, MeshingPalette},
+        minimap::{MinimapBindGroup, MinimapLocals},
+        post::{PostBindGroup, PostLocals, SceneBindGroup},
+        GlobalModel, GlobalsBindGroup, LocalsBindGroup,
+    },
+    texture::Texture,
+};
+
+use super::{
+    layouts::{LayoutId, Layouts},
+    Renderer,
+};
 
 impl Renderer {
+    /// Bind scene depth for the fluid pass. Rebound whenever the depth texture is recreated
+    pub(super) fn bind_depth(
+        device: &Device,
+        layouts: &Layouts,
+        depth_texture: &Texture,
+    ) -> DepthBindGroup {
+        DepthBindGroup {
+            inner: layouts.bind(
+                device,
+                LayoutId::Depth,
+                &[BindingResource::TextureView(&depth_texture.view)],
+            ),
+        }
+    }
+
+    /// Bind scene color for the post pass. Rebound whenever the scene texture is recreated
+    pub(super) fn bind_scene(
+        device: &Device,
+        layouts: &Layouts,
+        scene_texture: &Texture,
+    ) -> SceneBindGroup {
+        SceneBindGroup {
+            inner: layouts.bind(
+                device,
+                LayoutId::Scene,
+                &[
+                    BindingResource::TextureView(&scene_texture.view),
+                    BindingResource::Sampler(&scene_texture.sampler),
+                ],
+            ),
+        }
+    }
+
     pub fn bind_globals(&self, global_model: &GlobalModel) -> GlobalsBindGroup {
-        self.layouts
-            .globals
-            .bind_globals(&self.device, global_model)
+        GlobalsBindGroup {
+            inner: self.layouts.bind(
+                &self.device,
+                LayoutId::Globals,
+                &[global_model.globals.buffer().as_entire_binding()],
+            ),
+        }
+    }
+
+    /// Bind a single block of `consts`. The block is selected with a dynamic offset when the bind
+    /// group is set
+    pub fn bind_locals<T: Copy + Pod + Bufferable>(
+        &self,
+        consts: &DynamicConsts<T>,
+    ) -> LocalsBindGroup {
+        LocalsBindGroup {
+            inner: self
+                .layouts
+                .bind(&self.device, LayoutId::Locals, &[consts.binding()]),
+        }
+    }
+
+    pub fn bind_minimap(
+        &self,
+        locals: &Consts<MinimapLocals>,
+        texture: &Texture,
+    ) -> MinimapBindGroup {
+        MinimapBindGroup {
+            inner: self.layouts.bind(
+                &self.device,
+                LayoutId::Minimap,
+                &[
+                    locals.buffer().as_entire_binding(),
+                    BindingResource::TextureView(&texture.view),
+                    BindingResource::Sampler(&texture.sampler),
+                ],
+            ),
+        }
+    }
+
+    pub fn bind_loading(&self, locals: &Consts<LoadingLocals>) -> LoadingBindGroup {
+        LoadingBindGroup {
+            inner: self.layouts.bind(
+                &self.device,
+                LayoutId::Loading,
+                &[locals.buffer().as_entire_binding()],
+            ),
+        }
+    }
+
+    pub fn bind_post(&self, locals: &Consts<PostLocals>) -> PostBindGroup {
+        PostBindGroup {
+            inner: self.layouts.bind(
+                &self.device,
+                LayoutId::Post,
+                &[locals.buffer().as_entire_binding()],
+            ),
+        }
+    }
+
+    pub fn bind_barrier(&self, locals: &Consts<BarrierLocals>) -> BarrierBindGroup {
+        BarrierBindGroup {
+            inner: self.layouts.bind(
+                &self.device,
+                LayoutId::Barrier,
+                &[locals.buffer().as_entire_binding()],
+            ),
+        }
+    }
+
+    pub fn bind_meshing(
+        &self,
+        palette: &Consts<MeshingPalette>,
+        buffers: &MeshingBuffers,
+    ) -> MeshingBindGroup {
+        MeshingBindGroup {
+            inner: self.layouts.bind(
+                &self.device,
+                LayoutId::Meshing,
+                &[
+                    buffers.blocks.as_entire_binding(),
+                    palette.buffer().as_entire_binding(),
+                    buffers.vertices.as_entire_binding(),
+                    buffers.indices.as_entire_binding(),
+                    buffers.counter.as_entire_binding(),
+                ],
+            ),
+        }
     }
 }
@@ -1,41 +1,75 @@
-use std::iter::once;
+use std::{cell::Cell, iter::once};
 
+use tracing::warn;
 use wgpu::{
     Color, CommandEncoder, Device, IndexFormat, LoadOp, Operations, Queue, RenderPass,
     RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
-    SurfaceTexture, TextureView, TextureViewDescriptor,
+    SurfaceConfiguration, SurfaceTexture, TextureView, TextureViewDescriptor,
 };
 use wgpu_profiler::scope::{ManualOwningScope, OwningScope, Scope};
 
+use crate::render::arena::TerrainArena;
 use crate::render::buffer::{Buffer, DynamicBuffer};
-use crate::render::pipelines::GlobalsBindGroup;
+use crate::render::capture::FrameCapture;
+use crate::render::pipelines::{
+    barrier::{BarrierBindGroup, BarrierPipeline},
+    fluid::DepthBindGroup,
+    loading::LoadingBindGroup,
+    minimap::MinimapBindGroup,
+    post::{PostBindGroup, SceneBindGroup},
+    GlobalsBindGroup,
+};
 
-use crate::render::primitives::instance::RawInstance;
-use crate::render::{model::Model, primitives::vertex::Vertex, texture::Texture};
+use crate::render::primitives::{
+    decoration::DecorationInstance, figure::FigureVertex, instance::RawInstance,
+    label::LabelVertex, line::DebugLineVertex, text::TextVertex,
+};
+use crate::render::push_constants::{DrawData, PushConstants};
+use crate::render::{
+    model::Model, primitives::terrain::TerrainVertex, texture::Texture, DebugView, DrawStages,
+    RenderMode, Viewport,
+};
 use crate::scene::chunk::TerrainChunk;
+use crate::types::{F32x3, U32x2};
 
 use super::pipelines::Pipelines;
-use super::Renderer;
+use super::{Frames, Renderer};
 
 #[cfg(feature = "debug_overlay")]
 use {
     egui::FullOutput,
     egui_wgpu_backend::{BackendError, ScreenDescriptor},
     egui_winit_platform::Platform,
-    wgpu::SurfaceConfiguration,
 };
 
 struct RendererBorrow<'frame> {
     device: &'frame Device,
+    draw_stages: DrawStages,
+    debug_view: DebugView,
+    /// [`RenderMode::decoration_density`]
+    decoration_density: u32,
     queue: &'frame Queue,
     pipelines: &'frame Pipelines,
+    push_constants: &'frame PushConstants,
     depth_texture: &'frame Texture,
-    #[cfg(feature = "debug_overlay")]
+    depth_bind_group: &'frame DepthBindGroup,
+    scene_texture: &'frame Texture,
+    scene_bind_group: &'frame SceneBindGroup,
+    frames: &'frame mut Frames,
+    /// Draw calls recorded so far
+    draw_calls: Cell<u32>,
+    capture: Option<&'frame mut FrameCapture>,
     surface_config: &'frame SurfaceConfiguration,
     #[cfg(feature = "debug_overlay")]
     egui_render_pass: &'frame mut egui_wgpu_backend::RenderPass,
 }
 
+impl RendererBorrow<'_> {
+    fn count_draw(&self) {
+        self.draw_calls.set(self.draw_calls.get() + 1);
+    }
+}
+
 /// Used to draw on current frame.
 ///
 /// Draw calls will be submitted when the object is dropped.
@@ -65,10 +99,19 @@ impl<'frame> Drawer<'frame> {
             encoder: Some(encoder),
             renderer: RendererBorrow {
                 device: &renderer.device,
+                draw_stages: renderer.draw_stages,
+                debug_view: renderer.debug_view,
+                decoration_density: renderer.render_mode.decoration_density,
                 queue: &renderer.queue,
                 pipelines: &renderer.pipelines,
+                push_constants: &renderer.push_constants,
                 depth_texture: &renderer.depth_texture,
-                #[cfg(feature = "debug_overlay")]
+                depth_bind_group: &renderer.depth_bind_group,
+                scene_texture: &renderer.scene_texture,
+                scene_bind_group: &renderer.scene_bind_group,
+                frames: &mut renderer.frames,
+                draw_calls: Cell::new(0),
+                capture: renderer.capture.as_mut(),
                 surface_config: &renderer.config,
                 #[cfg(feature = "debug_overlay")]
                 egui_render_pass: &mut renderer.egui_render_pass,
@@ -79,8 +122,14 @@ impl<'frame> Drawer<'frame> {
         }
     }
 
-    /// Returns sub drawer for the first pass
-    pub fn first_pass(&mut self) -> FirstPassDrawer {
+    /// Returns sub drawer for the first pass. Screen is cleared with `clear_color`
+    pub fn first_pass(&mut self, clear_color: F32x3) -> FirstPassDrawer {
+        // Debug views are easier to read on a black background
+        let clear_color = match self.renderer.debug_view {
+            DebugView::Shaded => clear_color,
+            _ => F32x3::ZERO,
+        };
+
         let mut render_pass = self.encoder.as_mut().unwrap().scoped_render_pass(
             "first_pass",
             self.renderer.device,
@@ -88,16 +137,16 @@ impl<'frame> Drawer<'frame> {
                 label: Some("FirstPass"),
                 // Where to we draw colors
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &self.output_view,
+                    view: &self.renderer.scene_texture.view,
                     resolve_target: None,
                     ops: Operations {
                         // Where to pick the previous frame.
                         // Clears screen with specified color
                         // NOTE: Right now used as simple skybox
                         load: LoadOp::Clear(Color {
-                            r: 0.458,
-                            g: 0.909,
-                            b: 1.0,
+                            r: clear_color.x as f64,
+                            g: clear_color.y as f64,
+                            b: clear_color.z as f64,
                             a: 1.0,
                         }),
                         // Write results to texture
@@ -124,6 +173,138 @@ impl<'frame> Drawer<'frame> {
         }
     }
 
+    /// Returns sub drawer for the second pass. Must be called after [`Self::first_pass`]
+    pub fn second_pass(&mut self) -> SecondPassDrawer<'_> {
+        let mut render_pass = self.encoder.as_mut().unwrap().scoped_render_pass(
+            "second_pass",
+            self.renderer.device,
+            &RenderPassDescriptor {
+                label: Some("SecondPass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.renderer.scene_texture.view,
+                    resolve_target: None,
+                    ops: Operations {
+                        // Keep the first pass
+                        load: LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                // Read-only, so depth can be sampled by the fluid pipeline at the same time
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &self.renderer.depth_texture.view,
+                    depth_ops: None,
+                    stencil_ops: None,
+                }),
+            },
+        );
+
+        render_pass.set_bind_group(0, &self.globals.inner, &[]);
+
+        SecondPassDrawer {
+            render_pass,
+            renderer: &self.renderer,
+            pipelines: self.renderer.pipelines,
+        }
+    }
+
+    /// Returns sub drawer for a second view of the scene drawn over `viewport` with its own
+    /// `globals`. The viewport is cleared first. Must be called after [`Self::second_pass`]
+    pub fn view_pass(
+        &mut self,
+        globals: &'frame GlobalsBindGroup,
+        viewport: Viewport,
+    ) -> FirstPassDrawer<'_> {
+        let mut render_pass = self.encoder.as_mut().unwrap().scoped_render_pass(
+            "view_pass",
+            self.renderer.device,
+            &RenderPassDescriptor {
+                label: Some("ViewPass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.renderer.scene_texture.view,
+                    resolve_target: None,
+                    ops: Operations {
+                        // Only the viewport is drawn over
+                        load: LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &self.renderer.depth_texture.view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            },
+        );
+
+        // Scissor rect must be inside the frame
+        let frame = U32x2::new(
+            self.renderer.surface_config.width,
+            self.renderer.surface_config.height,
+        );
+        let pos = viewport.pos.min(frame);
+        let size = viewport.size.min(frame - pos);
+        render_pass.set_viewport(
+            pos.x as f32,
+            pos.y as f32,
+            size.x.max(1) as f32,
+            size.y.max(1) as f32,
+            0.0,
+            1.0,
+        );
+        render_pass.set_scissor_rect(pos.x, pos.y, size.x, size.y);
+        render_pass.set_bind_group(0, &globals.inner, &[]);
+
+        {
+            let mut render_pass = render_pass.scope("clear", self.renderer.device);
+            render_pass.set_pipeline(&self.renderer.pipelines.clear.inner);
+            render_pass.draw(0..6, 0..1);
+            self.renderer.count_draw();
+        }
+
+        FirstPassDrawer {
+            render_pass,
+            renderer: &self.renderer,
+            pipelines: self.renderer.pipelines,
+        }
+    }
+
+    /// Returns sub drawer for the post pass, which draws the scene to the screen. Must be called
+    /// after [`Self::second_pass`]
+    pub fn post_pass(&mut self) -> PostPassDrawer<'_> {
+        let mut render_pass = self.encoder.as_mut().unwrap().scoped_render_pass(
+            "post_pass",
+            self.renderer.device,
+            &RenderPassDescriptor {
+                label: Some("PostPass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.output_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        // Whole frame is covered by the post pipeline
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &self.renderer.depth_texture.view,
+                    depth_ops: None,
+                    stencil_ops: None,
+                }),
+            },
+        );
+
+        render_pass.set_bind_group(0, &self.globals.inner, &[]);
+
+        PostPassDrawer {
+            render_pass,
+            renderer: &self.renderer,
+            pipelines: self.renderer.pipelines,
+        }
+    }
+
     // FIX: Handle egui textures better
     /// Draw debug overlay
     #[cfg(feature = "debug_overlay")]
@@ -189,17 +370,40 @@ impl<'frame> Drop for Drawer<'frame> {
         let (mut encoder, profiler) = encoder.end_scope();
         profiler.resolve_queries(&mut encoder);
 
+        let output_texture = self.output_texture.take().unwrap();
+        if let Some(capture) = &mut self.renderer.capture {
+            capture.copy(
+                self.renderer.device,
+                &mut encoder,
+                &output_texture.texture,
+                self.renderer.surface_config,
+            );
+        }
+
+        if let Err(err) = self.renderer.push_constants.flush(self.renderer.queue) {
+            warn!(%err, "Failed to upload draw data");
+        }
+
+        self.renderer.frames.draw_calls = self.renderer.draw_calls.get();
+
         // Submit render operations
-        self.renderer.queue.submit(once(encoder.finish()));
+        let submission = self.renderer.queue.submit(once(encoder.finish()));
+        self.renderer
+            .frames
+            .submitted(self.renderer.queue, submission);
+
+        if let Some(capture) = &mut self.renderer.capture {
+            capture.submitted();
+        }
 
         // Show rendered frame
-        self.output_texture.take().unwrap().present();
+        output_texture.present();
 
         profiler.end_frame().expect("GPU Profiler error!");
     }
 }
 
-// TODO: Add render texture to renderer and use it here (for upscale/downscale)
+// TODO: Render scene texture in a different resolution (for upscale/downscale)
 /// Sub drawer that handles first render pass (terrain, figures)
 #[must_use]
 pub struct FirstPassDrawer<'pass> {
@@ -210,56 +414,367 @@ pub struct FirstPassDrawer<'pass> {
 
 impl<'pass> FirstPassDrawer<'pass> {
     /// Draw debug pyramid
-    pub fn draw_pyramid(&mut self, vertices: &'pass Buffer<Vertex>, indices: &'pass Buffer<u16>) {
+    pub fn draw_pyramid(
+        &mut self,
+        vertices: &'pass Buffer<TerrainVertex>,
+        indices: &'pass Buffer<u16>,
+    ) {
+        if !self.renderer.draw_stages.debug {
+            return;
+        }
+
         let mut render_pass = self.render_pass.scope("pyramid", self.renderer.device);
 
-        render_pass.set_pipeline(&self.pipelines.terrain.inner);
+        render_pass.set_pipeline(
+            self.pipelines
+                .terrain
+                .variants
+                .get(self.renderer.debug_view),
+        );
+        self.renderer
+            .push_constants
+            .set(&mut render_pass, DrawData::IDENTITY);
         render_pass.set_vertex_buffer(0, vertices.buffer.slice(..));
         render_pass.set_index_buffer(indices.buffer.slice(..), IndexFormat::Uint16);
-        render_pass.draw_indexed(0..Vertex::INDICES.len() as u32, 0, 0..1);
+        render_pass.draw_indexed(0..TerrainVertex::INDICES.len() as u32, 0, 0..1);
+        self.renderer.count_draw();
     }
 
-    /// Returns TerrainDrawer
-    pub fn terrain_drawer(&mut self) -> TerrainDrawer<'_, 'pass> {
+    /// Returns TerrainDrawer. Chunk meshes are drawn from the `arena` buffers
+    pub fn terrain_drawer(&mut self, arena: &'pass TerrainArena) -> TerrainDrawer<'_, 'pass> {
         let mut render_pass = self.render_pass.scope("terrain", self.renderer.device);
 
-        render_pass.set_pipeline(&self.pipelines.terrain.inner);
+        render_pass.set_pipeline(
+            self.pipelines
+                .terrain
+                .variants
+                .get(self.renderer.debug_view),
+        );
 
-        TerrainDrawer { render_pass }
+        TerrainDrawer {
+            render_pass,
+            arena,
+            bound_page: None,
+            push_constants: self.renderer.push_constants,
+            draw_calls: &self.renderer.draw_calls,
+            part: TerrainPart::Opaque,
+            enabled: self.renderer.draw_stages.terrain,
+        }
+    }
+
+    /// Returns TerrainDrawer for cutout geometry (plants). Must be called after opaque terrain
+    pub fn cutout_drawer(&mut self, arena: &'pass TerrainArena) -> TerrainDrawer<'_, 'pass> {
+        let mut render_pass = self.render_pass.scope("cutout", self.renderer.device);
+
+        render_pass.set_pipeline(self.pipelines.terrain.cutout.get(self.renderer.debug_view));
+
+        TerrainDrawer {
+            render_pass,
+            arena,
+            bound_page: None,
+            push_constants: self.renderer.push_constants,
+            draw_calls: &self.renderer.draw_calls,
+            part: TerrainPart::Cutout,
+            enabled: self.renderer.draw_stages.terrain,
+        }
+    }
+
+    /// Returns DecorationDrawer. Must be called after opaque terrain
+    pub fn decoration_drawer(&mut self) -> DecorationDrawer<'_, 'pass> {
+        let mut render_pass = self.render_pass.scope("decorations", self.renderer.device);
+
+        render_pass.set_pipeline(
+            self.pipelines
+                .decoration
+                .variants
+                .get(self.renderer.debug_view),
+        );
+
+        DecorationDrawer {
+            render_pass,
+            push_constants: self.renderer.push_constants,
+            draw_calls: &self.renderer.draw_calls,
+            density: self.renderer.decoration_density,
+            enabled: self.renderer.draw_stages.decorations,
+        }
     }
 
     // FIX: Make `FiguresDrawer` sub drawer for this operation
-    pub fn draw_figure<T: Model>(
+    pub fn draw_figure<T: Model<Vertex = FigureVertex>>(
         &mut self,
         model: &'pass T,
         instances: &'pass DynamicBuffer<RawInstance>,
     ) {
+        // TODO: Make safe cast
+        self.draw_figure_instances(model, instances, instances.length() as u32, F32x3::ONE);
+    }
+
+    /// Draw only first `count` instances from the buffer. Colors are multiplied by `tint`
+    pub fn draw_figure_instances<T: Model<Vertex = FigureVertex>>(
+        &mut self,
+        model: &'pass T,
+        instances: &'pass DynamicBuffer<RawInstance>,
+        count: u32,
+        tint: F32x3,
+    ) {
+        if !self.renderer.draw_stages.figures {
+            return;
+        }
+
         let mut render_pass = self.render_pass.scope("figure", self.renderer.device);
 
-        let (index_buffer, count) = model.get_indices();
+        let (index_buffer, index_count) = model.get_indices();
 
-        render_pass.set_pipeline(&self.pipelines.figure.inner);
+        render_pass.set_pipeline(self.pipelines.figure.variants.get(self.renderer.debug_view));
+        self.renderer
+            .push_constants
+            .set(&mut render_pass, DrawData::tint(tint));
         render_pass.set_vertex_buffer(0, model.get_vertices().slice(..));
         render_pass.set_vertex_buffer(1, instances.buffer.slice(..));
         render_pass.set_index_buffer(index_buffer.slice(..), IndexFormat::Uint16);
-        // TODO: Make safe cast
-        render_pass.draw_indexed(0..count, 0, 0..instances.length() as u32);
+        render_pass.draw_indexed(0..index_count, 0, 0..count);
+        self.renderer.count_draw();
+    }
+
+    /// Draw first `count` vertices of debug lines. Used by views without a second pass
+    pub fn draw_lines(&mut self, vertices: &'pass DynamicBuffer<DebugLineVertex>, count: u32) {
+        if !self.renderer.draw_stages.debug {
+            return;
+        }
+
+        let mut render_pass = self.render_pass.scope("lines", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.line.inner);
+        render_pass.set_vertex_buffer(0, vertices.buffer.slice(..));
+        render_pass.draw(0..count, 0..1);
+        self.renderer.count_draw();
+    }
+
+    /// Draw loading screen over the whole frame. Not affected by draw stages
+    pub fn draw_loading(&mut self, bind_group: &'pass LoadingBindGroup) {
+        let mut render_pass = self.render_pass.scope("loading", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.loading.inner);
+        render_pass.set_bind_group(1, &bind_group.inner, &[]);
+        render_pass.draw(0..6, 0..1);
+        self.renderer.count_draw();
+    }
+}
+
+/// Sub drawer that handles second render pass (fluids, blended geometry). Depth is read-only
+#[must_use]
+pub struct SecondPassDrawer<'pass> {
+    render_pass: OwningScope<'pass, RenderPass<'pass>>,
+    renderer: &'pass RendererBorrow<'pass>,
+    pipelines: &'pass Pipelines,
+}
+
+impl<'pass> SecondPassDrawer<'pass> {
+    /// Returns TerrainDrawer for fluid surfaces. Scene depth of the first pass is sampled
+    pub fn fluid_drawer(&mut self, arena: &'pass TerrainArena) -> TerrainDrawer<'_, 'pass> {
+        let mut render_pass = self.render_pass.scope("fluids", self.renderer.device);
+
+        render_pass.set_pipeline(self.pipelines.fluid.variants.get(self.renderer.debug_view));
+        render_pass.set_bind_group(
+            self.renderer.push_constants.next_group(),
+            &self.renderer.depth_bind_group.inner,
+            &[],
+        );
+
+        TerrainDrawer {
+            render_pass,
+            arena,
+            bound_page: None,
+            push_constants: self.renderer.push_constants,
+            draw_calls: &self.renderer.draw_calls,
+            part: TerrainPart::Fluid,
+            enabled: self.renderer.draw_stages.fluids,
+        }
+    }
+
+    /// Draw world border walls around the camera. Must be called after fluids
+    pub fn draw_barrier(&mut self, bind_group: &'pass BarrierBindGroup) {
+        let mut render_pass = self.render_pass.scope("barrier", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.barrier.inner);
+        render_pass.set_bind_group(1, &bind_group.inner, &[]);
+        render_pass.draw(0..BarrierPipeline::VERTICES, 0..1);
+        self.renderer.count_draw();
+    }
+
+    /// Draw outline of the picked block. Must be called after fluids, so it's visible under water
+    pub fn draw_selection(&mut self, vertices: &'pass DynamicBuffer<DebugLineVertex>, count: u32) {
+        if !self.renderer.draw_stages.hud {
+            return;
+        }
+
+        let mut render_pass = self.render_pass.scope("selection", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.selection.inner);
+        render_pass.set_vertex_buffer(0, vertices.buffer.slice(..));
+        render_pass.draw(0..count, 0..1);
+        self.renderer.count_draw();
+    }
+
+    /// Draw first `count` vertices of label meshes. Must be called after fluids
+    pub fn draw_labels(&mut self, vertices: &'pass DynamicBuffer<LabelVertex>, count: u32) {
+        if !self.renderer.draw_stages.labels {
+            return;
+        }
+
+        let mut render_pass = self.render_pass.scope("labels", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.label.inner);
+        render_pass.set_vertex_buffer(0, vertices.buffer.slice(..));
+        render_pass.draw(0..count, 0..1);
+        self.renderer.count_draw();
+    }
+
+    /// Draw first `count` vertices of debug lines
+    pub fn draw_lines(&mut self, vertices: &'pass DynamicBuffer<DebugLineVertex>, count: u32) {
+        if !self.renderer.draw_stages.debug {
+            return;
+        }
+
+        let mut render_pass = self.render_pass.scope("lines", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.line.inner);
+        render_pass.set_vertex_buffer(0, vertices.buffer.slice(..));
+        render_pass.draw(0..count, 0..1);
+        self.renderer.count_draw();
     }
 }
 
+/// Sub drawer that handles the post pass (screen effects, HUD). Draws to the screen
+#[must_use]
+pub struct PostPassDrawer<'pass> {
+    render_pass: OwningScope<'pass, RenderPass<'pass>>,
+    renderer: &'pass RendererBorrow<'pass>,
+    pipelines: &'pass Pipelines,
+}
+
+impl<'pass> PostPassDrawer<'pass> {
+    /// Draw the scene with screen effects over the whole frame. Not affected by draw stages, so
+    /// must be called every frame
+    pub fn draw_post(&mut self, bind_group: &'pass PostBindGroup) {
+        let mut render_pass = self.render_pass.scope("post", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.post.inner);
+        render_pass.set_bind_group(1, &self.renderer.scene_bind_group.inner, &[]);
+        render_pass.set_bind_group(2, &bind_group.inner, &[]);
+        render_pass.draw(0..6, 0..1);
+        self.renderer.count_draw();
+    }
+
+    /// Draw first `count` vertices of screen-space text. Not affected by draw stages, so
+    /// performance numbers stay visible while stages are isolated
+    pub fn draw_text(&mut self, vertices: &'pass DynamicBuffer<TextVertex>, count: u32) {
+        let mut render_pass = self.render_pass.scope("text", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.text.inner);
+        render_pass.set_vertex_buffer(0, vertices.buffer.slice(..));
+        render_pass.draw(0..count, 0..1);
+        self.renderer.count_draw();
+    }
+
+    /// Draw minimap over the scene
+    pub fn draw_minimap(&mut self, bind_group: &'pass MinimapBindGroup) {
+        if !self.renderer.draw_stages.hud {
+            return;
+        }
+
+        let mut render_pass = self.render_pass.scope("minimap", self.renderer.device);
+
+        render_pass.set_pipeline(&self.pipelines.minimap.inner);
+        render_pass.set_bind_group(1, &bind_group.inner, &[]);
+        render_pass.draw(0..6, 0..1);
+        self.renderer.count_draw();
+    }
+}
+
+/// Part of the chunk mesh drawn by [`TerrainDrawer`]
+#[derive(Clone, Copy)]
+enum TerrainPart {
+    Opaque,
+    Cutout,
+    Fluid,
+}
+
 #[must_use]
 pub struct TerrainDrawer<'pass_ref, 'pass: 'pass_ref> {
     render_pass: Scope<'pass_ref, RenderPass<'pass>>,
+    arena: &'pass TerrainArena,
+    /// Arena page with bound buffers
+    bound_page: Option<usize>,
+    push_constants: &'pass PushConstants,
+    draw_calls: &'pass Cell<u32>,
+    part: TerrainPart,
+    /// Draw calls are skipped if the stage of the part is disabled
+    enabled: bool,
 }
 
 impl<'pass_ref, 'pass: 'pass_ref> TerrainDrawer<'pass_ref, 'pass> {
     /// Draw terrain chunk
     pub fn draw(&mut self, chunk: &'pass TerrainChunk) {
+        let indices = match self.part {
+            TerrainPart::Opaque => chunk.opaque_indices(),
+            TerrainPart::Cutout => chunk.cutout_indices(),
+            TerrainPart::Fluid => chunk.fluid_indices(),
+        };
+        if !self.enabled || indices.start == indices.end {
+            return;
+        }
+
+        let slice = &chunk.slice;
+        if self.bound_page != Some(slice.page) {
+            let Some(page) = self.arena.page(slice.page) else {
+                return;
+            };
+            self.render_pass
+                .set_vertex_buffer(0, page.vertex_buffer.slice(..));
+            self.render_pass
+                .set_index_buffer(page.index_buffer.slice(..), IndexFormat::Uint32);
+            self.bound_page = Some(slice.page);
+        }
+
+        // Meshes are built relative to the chunk origin
+        self.push_constants
+            .set(&mut self.render_pass, DrawData::offset(chunk.origin));
         self.render_pass
-            .set_vertex_buffer(0, chunk.vertex_buffer.buffer.slice(..));
+            .draw_indexed(indices, slice.vertices.start as i32, 0..1);
+        self.draw_calls.set(self.draw_calls.get() + 1);
+    }
+}
+
+#[must_use]
+pub struct DecorationDrawer<'pass_ref, 'pass: 'pass_ref> {
+    render_pass: Scope<'pass_ref, RenderPass<'pass>>,
+    push_constants: &'pass PushConstants,
+    draw_calls: &'pass Cell<u32>,
+    /// Percentage of instances drawn
+    density: u32,
+    /// Draw calls are skipped if decorations stage is disabled
+    enabled: bool,
+}
+
+impl<'pass_ref, 'pass: 'pass_ref> DecorationDrawer<'pass_ref, 'pass> {
+    /// Draw decorations of the chunk. Instances are shuffled, so the first ones are drawn
+    pub fn draw(&mut self, chunk: &'pass TerrainChunk) {
+        let Some(instances) = &chunk.decorations else {
+            return;
+        };
+        let count =
+            (instances.length() as u32 * self.density).div_ceil(RenderMode::MAX_DECORATION_DENSITY);
+        if !self.enabled || count == 0 {
+            return;
+        }
+
+        self.push_constants
+            .set(&mut self.render_pass, DrawData::offset(chunk.origin));
         self.render_pass
-            .set_index_buffer(chunk.index_buffer.buffer.slice(..), IndexFormat::Uint32);
+            .set_vertex_buffer(0, instances.buffer.slice(..));
         self.render_pass
-            .draw_indexed(0..chunk.index_buffer.length() as u32, 0, 0..1);
+            .draw(0..DecorationInstance::VERTICES, 0..count);
+        self.draw_calls.set(self.draw_calls.get() + 1);
     }
 }
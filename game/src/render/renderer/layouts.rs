@@ -1,15 +1,255 @@
-use wgpu::Device;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BufferBindingType, Device, PipelineLayout,
+    PipelineLayoutDescriptor, PushConstantRange, SamplerBindingType, ShaderStages,
+    TextureSampleType, TextureViewDimension,
+};
 
-use crate::render::pipelines::GlobalLayout;
+/// Bind group layouts shared by pipelines. Group index is the position in a pipeline's layout list
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum LayoutId {
+    /// `Globals` uniform (group 0 of every first pass pipeline)
+    Globals,
+    /// Per-object uniform block selected by a dynamic offset
+    Locals,
+    /// Minimap uniforms and column color texture
+    Minimap,
+    /// Loading screen uniforms
+    Loading,
+    /// Compute meshing input and output buffers. Only created if compute shaders are supported
+    Meshing,
+    /// Scene depth sampled by the fluid pass
+    Depth,
+    /// Scene color sampled by the post pass
+    Scene,
+    /// Post pass uniforms
+    Post,
+    /// World border uniforms
+    Barrier,
+}
+
+impl LayoutId {
+    pub const ALL: [Self; 9] = [
+        Self::Globals,
+        Self::Locals,
+        Self::Minimap,
+        Self::Loading,
+        Self::Meshing,
+        Self::Depth,
+        Self::Scene,
+        Self::Post,
+        Self::Barrier,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Globals => "Globals",
+            Self::Locals => "Locals",
+            Self::Minimap => "Minimap",
+            Self::Loading => "Loading",
+            Self::Meshing => "Meshing",
+            Self::Depth => "Depth",
+            Self::Scene => "Scene",
+            Self::Post => "Post",
+            Self::Barrier => "Barrier",
+        }
+    }
+
+    /// Layout uses compute shader stages
+    pub const fn compute(self) -> bool {
+        matches!(self, Self::Meshing)
+    }
+
+    pub const fn entries(self) -> &'static [BindGroupLayoutEntry] {
+        const VERTEX_FRAGMENT: ShaderStages = ShaderStages::VERTEX_FRAGMENT;
+        const GLOBALS: &[BindGroupLayoutEntry] = &[uniform(0, VERTEX_FRAGMENT, false)];
+        const LOCALS: &[BindGroupLayoutEntry] = &[uniform(0, VERTEX_FRAGMENT, true)];
+        const MINIMAP: &[BindGroupLayoutEntry] = &[
+            uniform(0, VERTEX_FRAGMENT, false),
+            // Column colors
+            texture(1, ShaderStages::FRAGMENT),
+            sampler(2, ShaderStages::FRAGMENT),
+        ];
+        const LOADING: &[BindGroupLayoutEntry] = &[uniform(0, VERTEX_FRAGMENT, false)];
+        const MESHING: &[BindGroupLayoutEntry] = &[
+            // Blocks
+            storage(0, true),
+            // Palette
+            uniform(1, ShaderStages::COMPUTE, false),
+            // Vertices, indices and quad counter
+            storage(2, false),
+            storage(3, false),
+            storage(4, false),
+        ];
+        const DEPTH: &[BindGroupLayoutEntry] = &[depth_texture(0, ShaderStages::FRAGMENT)];
+        const SCENE: &[BindGroupLayoutEntry] = &[
+            texture(0, ShaderStages::FRAGMENT),
+            sampler(1, ShaderStages::FRAGMENT),
+        ];
+        const POST: &[BindGroupLayoutEntry] = &[uniform(0, ShaderStages::FRAGMENT, false)];
+        const BARRIER: &[BindGroupLayoutEntry] = &[uniform(0, VERTEX_FRAGMENT, false)];
+
+        match self {
+            Self::Globals => GLOBALS,
+            Self::Locals => LOCALS,
+            Self::Minimap => MINIMAP,
+            Self::Loading => LOADING,
+            Self::Meshing => MESHING,
+            Self::Depth => DEPTH,
+            Self::Scene => SCENE,
+            Self::Post => POST,
+            Self::Barrier => BARRIER,
+        }
+    }
+}
+
+const fn uniform(
+    binding: u32,
+    visibility: ShaderStages,
+    has_dynamic_offset: bool,
+) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
 
+/// Storage buffer of a compute shader
+const fn storage(binding: u32, read_only: bool) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// Filterable 2D texture
+const fn texture(binding: u32, visibility: ShaderStages) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+/// Depth texture read with `textureLoad`
+const fn depth_texture(binding: u32, visibility: ShaderStages) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Texture {
+            sample_type: TextureSampleType::Depth,
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+const fn sampler(binding: u32, visibility: ShaderStages) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+        count: None,
+    }
+}
+
+/// Registry of bind group layouts. Pipeline layouts and bind groups are created through it
 pub struct Layouts {
-    pub globals: GlobalLayout,
+    /// Compute layouts are `None` if compute shaders aren't supported
+    layouts: Vec<Option<BindGroupLayout>>,
 }
 
 impl Layouts {
-    pub fn new(device: &Device) -> Self {
+    pub fn new(device: &Device, compute: bool) -> Self {
         Self {
-            globals: GlobalLayout::new(device),
+            layouts: LayoutId::ALL
+                .iter()
+                .map(|id| {
+                    (compute || !id.compute()).then(|| {
+                        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                            label: Some(&format!("BindGroupLayout: {}", id.label())),
+                            entries: id.entries(),
+                        })
+                    })
+                })
+                .collect(),
         }
     }
+
+    /// Panics if the layout isn't supported by the device
+    pub fn get(&self, id: LayoutId) -> &BindGroupLayout {
+        self.layouts[id as usize]
+            .as_ref()
+            .unwrap_or_else(|| panic!("{id:?} layout isn't supported"))
+    }
+
+    /// Pipeline layout with bind groups `groups` (in group order)
+    pub fn pipeline_layout(
+        &self,
+        device: &Device,
+        label: &str,
+        groups: &[LayoutId],
+        push_constant_ranges: &[PushConstantRange],
+    ) -> PipelineLayout {
+        device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some(&format!("PipelineLayout: {label}")),
+            bind_group_layouts: &groups.iter().map(|&id| self.get(id)).collect::<Vec<_>>(),
+            push_constant_ranges,
+        })
+    }
+
+    /// Bind group with `resources` bound in order of the layout entries
+    pub fn bind(&self, device: &Device, id: LayoutId, resources: &[BindingResource]) -> BindGroup {
+        let entries = id.entries();
+        debug_assert_eq!(entries.len(), resources.len(), "{id:?} bindings mismatch");
+
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some(&format!("BindGroup: {}", id.label())),
+            layout: self.get(id),
+            entries: &entries
+                .iter()
+                .zip(resources)
+                .map(|(entry, resource)| BindGroupEntry {
+                    binding: entry.binding,
+                    resource: resource.clone(),
+                })
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::LayoutId;
+
+    #[test]
+    fn layout_ids_in_order() {
+        // Layouts are stored in a vector indexed by the id
+        LayoutId::ALL
+            .iter()
+            .enumerate()
+            .for_each(|(i, &id)| assert_eq!(id as usize, i));
+    }
 }
@@ -1,26 +1,38 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
 use bytemuck::Pod;
 use common_log::span;
 use tokio::runtime::Runtime;
 use tracing::{error, info, warn};
 use wgpu::{
-    Backends, CommandEncoderDescriptor, CompositeAlphaMode, Device, DeviceDescriptor, Features,
-    Instance, PowerPreference, Queue, RequestAdapterOptions, Surface, SurfaceConfiguration,
-    SurfaceError, TextureUsages,
+    Adapter, Buffer, Color, CommandEncoderDescriptor, CompositeAlphaMode, Device, DeviceDescriptor,
+    DownlevelFlags, Features, Instance, LoadOp, Maintain, Operations, PowerPreference, PresentMode,
+    Queue, RenderPassColorAttachment, RenderPassDescriptor, RequestAdapterOptions, SubmissionIndex,
+    Surface, SurfaceConfiguration, SurfaceError, TextureUsages, TextureViewDescriptor,
 };
 use wgpu_profiler::{GpuProfiler, GpuTimerScopeResult};
 use winit::window::Window;
 
 use crate::{
+    assets::AssetManager,
     render::{renderer::layouts::Layouts, texture::Texture},
     types::{ProfileResult, U32x2},
 };
 
 use super::{
-    buffer::{Bufferable, Consts, DynamicBuffer},
+    buffer::{Bufferable, Consts, DynamicBuffer, DynamicConsts},
+    capture::{CaptureSettings, CaptureStats, FrameCapture},
     error::RenderError,
-    pipelines::GlobalsBindGroup,
-    shader::ShaderModules,
-    RenderMode,
+    pipelines::{
+        fluid::DepthBindGroup, meshing::MeshingPipeline, post::SceneBindGroup, GlobalsBindGroup,
+    },
+    push_constants::PushConstants,
+    shader::{ShaderLoader, ShaderModules},
+    DebugView, DrawStages, RenderMode,
 };
 
 use {drawer::Drawer, pipelines::Pipelines};
@@ -30,6 +42,57 @@ pub mod drawer;
 pub mod layouts;
 pub mod pipelines;
 
+/// Frame submitted to the GPU which may still be in progress
+struct InFlightFrame {
+    submission: SubmissionIndex,
+    /// Buffers destroyed once the frame has finished
+    garbage: Vec<Buffer>,
+}
+
+/// Frames submitted to the GPU and resources waiting for them
+#[derive(Default)]
+pub(super) struct Frames {
+    /// Submitted frames (oldest first)
+    in_flight: VecDeque<InFlightFrame>,
+    /// Buffers retired during the current frame
+    garbage: Vec<Buffer>,
+    /// Frames submitted so far. Frames are numbered from 1
+    submitted: u64,
+    /// Frames finished by the GPU and when it was noticed. Taken by [`Renderer::finished_frames`]
+    finished: Arc<Mutex<Vec<(u64, Instant)>>>,
+    /// Draw calls of the last submitted frame
+    draw_calls: u32,
+}
+
+impl Frames {
+    /// Block until at most `max` submitted frames are in progress
+    fn wait(&mut self, device: &Device, max: usize) {
+        span!(_guard, "wait", "Frames::wait");
+
+        while self.in_flight.len() > max {
+            if let Some(frame) = self.in_flight.pop_front() {
+                device.poll(Maintain::WaitForSubmissionIndex(frame.submission));
+                frame.garbage.iter().for_each(Buffer::destroy);
+            }
+        }
+    }
+
+    /// Track submitted frame
+    pub(super) fn submitted(&mut self, queue: &Queue, submission: SubmissionIndex) {
+        let garbage = std::mem::take(&mut self.garbage);
+        self.submitted += 1;
+
+        let (index, finished) = (self.submitted, self.finished.clone());
+        queue.on_submitted_work_done(move || {
+            finished.lock().unwrap().push((index, Instant::now()));
+        });
+        self.in_flight.push_back(InFlightFrame {
+            submission,
+            garbage,
+        });
+    }
+}
+
 /// Represents a render state of the entire game.
 /// `Renderer` contains any state necessary to interact
 /// with the GPU, along with pipeline state object (PSOs)
@@ -39,20 +102,39 @@ pub struct Renderer {
     pub device: Device,
     pub queue: Queue,
     surface: Surface,
+    /// Kept to query surface capabilities
+    adapter: Adapter,
     pub config: SurfaceConfiguration,
 
     // Inner state
     render_mode: RenderMode,
+    /// Enabled draw stages
+    pub draw_stages: DrawStages,
+    /// Debug visualization of terrain and figures
+    pub debug_view: DebugView,
     resolution: U32x2,
     is_minimized: bool,
+    /// Renderer should be rebuilt before the next frame
+    recreate_requested: bool,
+    frames: Frames,
+    capture: Option<FrameCapture>,
 
     // Textures
     depth_texture: Texture,
+    depth_bind_group: DepthBindGroup,
+    /// First and second passes are drawn here, then copied to the surface by the post pass
+    scene_texture: Texture,
+    scene_bind_group: SceneBindGroup,
 
     _shaders: ShaderModules,
+    /// Shader overrides of resource packs
+    assets: AssetManager,
     layouts: Layouts,
     // TODO: With a large number of pipelines, make (re)creation async
     pipelines: Pipelines,
+    push_constants: PushConstants,
+    /// Compute pipelines are available
+    compute_supported: bool,
 
     profiler: GpuProfiler,
     profiler_history: Vec<GpuTimerScopeResult>,
@@ -69,11 +151,11 @@ impl Renderer {
     pub fn new(
         window: &Window,
         render_mode: RenderMode,
+        assets: AssetManager,
         runtime: &Runtime,
     ) -> Result<Self, RenderError> {
         let size = window.inner_size();
-        // TODO: Parse backend from env
-        let backend = Backends::PRIMARY;
+        let backend = render_mode.backends;
 
         // Create new API instance (Primary APIs: Vulkan, DX12, Metal)
         let instance = Instance::new(backend);
@@ -123,6 +205,7 @@ impl Renderer {
         let (device, queue) = runtime.block_on(adapter.request_device(
             &DeviceDescriptor {
                 label: Some("GraphicDevice"),
+                // Includes `PUSH_CONSTANTS` if available (see `PushConstants`)
                 features: (adapter.features() | GpuProfiler::ALL_WGPU_TIMER_FEATURES)
                     - Features::MAPPABLE_PRIMARY_BUFFERS,
                 // TODO: Decide wether to support WASM target or not
@@ -142,6 +225,11 @@ impl Renderer {
             .ok_or(RenderError::NoCompatibleSurfaceFormat)?;
         info!("Using {surface_format:?} as surface format");
 
+        let present_mode = render_mode
+            .present_mode
+            .select(&surface.get_supported_present_modes(&adapter));
+        info!(policy = ?render_mode.present_mode, ?present_mode, "Present mode selected");
+
         let config = SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
@@ -152,39 +240,72 @@ impl Renderer {
             // - Fifo: VSync
             // - RelaxedFifo: Adaptive Sync (AMD on Vulkan)
             // - Mailbox: GSync (DX11/12 or NVIDIA on Vulkan)
-            // TODO: Add support for switching modes in game settings
-            present_mode: render_mode.present_mode,
+            present_mode,
             alpha_mode: CompositeAlphaMode::Auto,
         };
         surface.configure(&device, &config);
 
         let depth_texture = Texture::new_depth(&device, &config, "Depth Texture");
 
-        let shaders = ShaderModules::init_all(&device);
-        let layouts = Layouts::new(&device);
-        let pipelines = Pipelines::create(&device, &layouts, &shaders, &config);
+        // Storage buffers of the meshing shader
+        let compute_supported = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::COMPUTE_SHADERS)
+            && device.limits().max_storage_buffers_per_shader_stage >= 4;
+        info!(compute_supported, "Compute shaders");
+
+        let layouts = Layouts::new(&device, compute_supported);
+        let push_constants = PushConstants::new(&device, &layouts);
+        let loader = ShaderLoader {
+            device: &device,
+            runtime,
+            assets: &assets,
+        };
+        let shaders = ShaderModules::init_all(&loader, &push_constants, compute_supported);
+        let pipelines = Pipelines::create(&device, &layouts, &shaders, &config, &push_constants);
+        let depth_bind_group = Self::bind_depth(&device, &layouts, &depth_texture);
+        let scene_texture = Texture::new_target(&device, &config, "Scene Texture");
+        let scene_bind_group = Self::bind_scene(&device, &layouts, &scene_texture);
 
         #[cfg(feature = "debug_overlay")]
         let egui_render_pass =
             egui_wgpu_backend::RenderPass::new(&device, wgpu::TextureFormat::Bgra8UnormSrgb, 1);
 
-        let profiler = GpuProfiler::new(4, queue.get_timestamp_period(), device.features());
+        // Profiler results are read back one frame after the last frame in flight
+        let profiler = GpuProfiler::new(
+            RenderMode::MAX_FRAMES_IN_FLIGHT as usize + 1,
+            queue.get_timestamp_period(),
+            device.features(),
+        );
 
         Ok(Self {
             device,
             queue,
             surface,
+            adapter,
             config,
 
             render_mode,
+            draw_stages: DrawStages::new(),
+            debug_view: DebugView::Shaded,
             resolution: U32x2::new(size.width, size.height),
             is_minimized: false,
+            recreate_requested: false,
+            frames: Frames::default(),
+            capture: None,
 
             depth_texture,
+            depth_bind_group,
+            scene_texture,
+            scene_bind_group,
 
             layouts,
             _shaders: shaders,
+            assets,
             pipelines,
+            push_constants,
+            compute_supported,
 
             profiler,
             profiler_history: Vec::new(),
@@ -196,6 +317,100 @@ impl Renderer {
         })
     }
 
+    /// Drop the renderer and build a new one for the same window: new surface, device, pipelines
+    /// and every device-dependent resource.
+    ///
+    /// Used to switch backends/adapters and to recover from a lost device. Resources created
+    /// outside of the renderer (buffers, bind groups, meshes) have to be recreated by the owner
+    pub fn recreate(self, window: &Window, runtime: &Runtime) -> Result<Self, RenderError> {
+        span!(_guard, "recreate", "Renderer::recreate");
+        info!("Recreating renderer");
+
+        let render_mode = self.render_mode.clone();
+        let assets = self.assets.clone();
+        let draw_stages = self.draw_stages;
+        let debug_view = self.debug_view;
+        let resolution = self.resolution;
+
+        // Window can't have two surfaces at once
+        drop(self);
+
+        let mut renderer = Self::new(window, render_mode, assets, runtime)?;
+        renderer.draw_stages = draw_stages;
+        renderer.debug_view = debug_view;
+        renderer.on_resize(resolution);
+
+        Ok(renderer)
+    }
+
+    /// Block until GPU finishes all submitted work and free deferred buffers
+    pub fn wait_idle(&mut self) {
+        span!(_guard, "wait_idle", "Renderer::wait_idle");
+
+        self.frames.wait(&self.device, 0);
+        self.device.poll(Maintain::Wait);
+    }
+
+    /// Start copying presented frames to disk or an external encoder. Replaces the running capture
+    pub fn start_capture(&mut self, settings: CaptureSettings) -> Result<(), RenderError> {
+        self.capture = Some(FrameCapture::new(settings, self.config.format)?);
+
+        // Frames are copied straight from the surface
+        self.config.usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC;
+        if !self.is_minimized {
+            self.surface.configure(&self.device, &self.config);
+        }
+
+        Ok(())
+    }
+
+    /// Stop frame capture. Frames already read back are still written
+    pub fn stop_capture(&mut self) {
+        if self.capture.take().is_some() {
+            self.config.usage = TextureUsages::RENDER_ATTACHMENT;
+            if !self.is_minimized {
+                self.surface.configure(&self.device, &self.config);
+            }
+        }
+    }
+
+    /// Stats of the running frame capture
+    pub fn capture_stats(&self) -> Option<CaptureStats> {
+        self.capture.as_ref().map(FrameCapture::stats)
+    }
+
+    /// Ask the owner to rebuild the renderer (see [`Renderer::recreate`]) before the next frame
+    pub fn request_recreate(&mut self) {
+        self.recreate_requested = true;
+    }
+
+    pub fn recreate_requested(&self) -> bool {
+        self.recreate_requested
+    }
+
+    pub fn assets(&self) -> &AssetManager {
+        &self.assets
+    }
+
+    /// Replace resource packs. Shaders are reloaded by recreating the renderer
+    pub fn set_assets(&mut self, assets: AssetManager) {
+        self.assets = assets;
+        self.request_recreate();
+    }
+
+    /// Compute pipelines (e.g. [`Self::meshing_pipeline`]) are supported by the device
+    pub fn compute_supported(&self) -> bool {
+        self.compute_supported
+    }
+
+    pub fn meshing_pipeline(&self) -> Option<&MeshingPipeline> {
+        self.pipelines.meshing.as_ref()
+    }
+
+    pub fn render_mode(&self) -> &RenderMode {
+        &self.render_mode
+    }
+
     /// Get graphic backend API being used
     pub fn graphics_backend(&self) -> &str {
         &self.graphics_backend
@@ -216,22 +431,49 @@ impl Renderer {
         values: &[T],
     ) -> Consts<T> {
         let consts = Consts::new(device, values.len());
-        consts.update(queue, values, 0);
+        consts
+            .update(queue, values, 0)
+            .expect("Consts are created to fit values");
         consts
     }
 
     /// Update constant buffer
-    pub fn update_consts<T: Copy + Pod + Bufferable>(&self, consts: &Consts<T>, values: &[T]) {
+    pub fn update_consts<T: Copy + Pod + Bufferable>(
+        &self,
+        consts: &Consts<T>,
+        values: &[T],
+    ) -> Result<(), RenderError> {
         consts.update(&self.queue, values, 0)
     }
 
-    // TODO: Update only models
+    pub fn create_dynamic_consts<T: Copy + Pod + Bufferable>(
+        &self,
+        values: &[T],
+    ) -> DynamicConsts<T> {
+        let consts = DynamicConsts::new(&self.device, values.len());
+        consts
+            .update(&self.queue, values, 0)
+            .expect("Consts are created to fit values");
+        consts
+    }
+
+    /// Update blocks of per-object uniforms starting from `offset`
+    pub fn update_dynamic_consts<T: Copy + Pod + Bufferable>(
+        &self,
+        consts: &DynamicConsts<T>,
+        values: &[T],
+        offset: usize,
+    ) -> Result<(), RenderError> {
+        consts.update(&self.queue, values, offset)
+    }
+
+    /// Write values to the start of the buffer. Use [`DynamicBuffer::ensure_capacity`] to grow it
     pub fn update_dynamic_buffer<T: Copy + Pod + Bufferable>(
         &self,
         buffer: &DynamicBuffer<T>,
         values: &[T],
-    ) {
-        buffer.update(&self.queue, values, 0);
+    ) -> Result<(), RenderError> {
+        buffer.update(&self.queue, values, 0)
     }
 
     /// Resize surface to match window dimensions
@@ -246,26 +488,98 @@ impl Renderer {
             self.resolution = new;
             self.config.width = self.resolution.x;
             self.config.height = self.resolution.y;
+            let present_mode = self.select_present_mode();
+            if present_mode != self.config.present_mode {
+                info!(policy = ?self.render_mode.present_mode, ?present_mode, "Present mode selected");
+                self.config.present_mode = present_mode;
+            }
             self.surface.configure(&self.device, &self.config);
 
-            // Resize depth texture
+            // Resize depth and scene textures
             self.depth_texture = Texture::new_depth(&self.device, &self.config, "Depth Texture");
+            self.depth_bind_group =
+                Self::bind_depth(&self.device, &self.layouts, &self.depth_texture);
+            self.scene_texture = Texture::new_target(&self.device, &self.config, "Scene Texture");
+            self.scene_bind_group =
+                Self::bind_scene(&self.device, &self.layouts, &self.scene_texture);
         } else {
             self.is_minimized = true;
         }
     }
 
     /// Change `Renderer` configuration
-    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+    pub fn set_render_mode(&mut self, mut render_mode: RenderMode) {
+        render_mode.frames_in_flight = render_mode.frames_in_flight.clamp(
+            RenderMode::MIN_FRAMES_IN_FLIGHT,
+            RenderMode::MAX_FRAMES_IN_FLIGHT,
+        );
+        render_mode.decoration_density = render_mode
+            .decoration_density
+            .min(RenderMode::MAX_DECORATION_DENSITY);
+
         if self.render_mode != render_mode {
+            // Adapter is selected once per device
+            if self.render_mode.backends != render_mode.backends {
+                self.recreate_requested = true;
+            }
             self.render_mode = render_mode;
 
-            self.config.present_mode = self.render_mode.present_mode;
+            self.on_resize(self.resolution);
+        }
+    }
 
+    /// Window has been moved to another monitor. Present mode is selected again, as supported
+    /// modes may differ
+    pub fn on_monitor_change(&mut self) {
+        if self.select_present_mode() != self.config.present_mode {
             self.on_resize(self.resolution);
         }
     }
 
+    /// Present mode in use
+    pub fn present_mode(&self) -> PresentMode {
+        self.config.present_mode
+    }
+
+    fn select_present_mode(&self) -> PresentMode {
+        let supported = self.surface.get_supported_present_modes(&self.adapter);
+        self.render_mode.present_mode.select(&supported)
+    }
+
+    /// Present a frame of a single color. Shown before the first real frame, so a new window
+    /// doesn't show uninitialized contents
+    pub fn present_clear(&mut self, color: Color) -> Result<(), RenderError> {
+        if self.is_minimized {
+            return Ok(());
+        }
+
+        let texture = self.surface.get_current_texture()?;
+        let view = texture
+            .texture
+            .create_view(&TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("ClearEncoder"),
+            });
+        encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Clear Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(color),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        self.queue.submit(Some(encoder.finish()));
+        texture.present();
+
+        Ok(())
+    }
+
     /// Start frame rendering and create `Drawer`
     /// If there is an intermittent issue with the surface
     /// then Ok(None) will be returned
@@ -279,11 +593,23 @@ impl Renderer {
             return Ok(None);
         }
 
+        // One more frame is about to be submitted
+        self.frames
+            .wait(&self.device, self.render_mode.frames_in_flight as usize - 1);
+
+        // Pass captured frames to the writer
+        if let Some(capture) = &mut self.capture {
+            self.device.poll(Maintain::Poll);
+            capture.collect();
+        }
+
         // Try to save the latest profiling results
         if let Some(profile_results) = self.profiler.process_finished_frame() {
             self.profiler_history = profile_results;
         }
 
+        self.push_constants.begin_frame(&self.device, &self.layouts);
+
         // Used to send series of operations to GPU
         let encoder = self
             .device
@@ -310,6 +636,37 @@ impl Renderer {
         Ok(Some(Drawer::new(encoder, self, texture, globals)))
     }
 
+    /// Destroy buffer once all frames submitted so far have finished.
+    ///
+    /// Dropping a buffer defers its destruction too, but memory may be held for longer
+    pub fn destroy_later(&mut self, buffer: Buffer) {
+        self.frames.garbage.push(buffer);
+    }
+
+    /// Number of frames the GPU may still be working on
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames.in_flight.len()
+    }
+
+    /// Draw calls of the last submitted frame. Overlay draws aren't counted
+    pub fn draw_calls(&self) -> u32 {
+        self.frames.draw_calls
+    }
+
+    /// Index of the last submitted frame. Zero before the first one
+    pub fn frame_index(&self) -> u64 {
+        self.frames.submitted
+    }
+
+    /// Frames finished by the GPU since the last call with the time they were noticed finished.
+    ///
+    /// The device is checked on this call and when the renderer waits for frames, so the time may
+    /// be later than the actual completion
+    pub fn finished_frames(&mut self) -> Vec<(u64, Instant)> {
+        self.device.poll(Maintain::Poll);
+        std::mem::take(&mut *self.frames.finished.lock().unwrap())
+    }
+
     pub fn timings(&self) -> Vec<ProfileResult> {
         let mut vec = Vec::new();
 
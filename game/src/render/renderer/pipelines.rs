@@ -3,7 +3,14 @@
 use wgpu::{Device, SurfaceConfiguration};
 
 use crate::render::{
-    pipelines::{figure::FigurePipeline, terrain::TerrainPipeline},
+    pipelines::{
+        barrier::BarrierPipeline, clear::ClearPipeline, decoration::DecorationPipeline,
+        figure::FigurePipeline, fluid::FluidPipeline, label::LabelPipeline, line::LinePipeline,
+        loading::LoadingPipeline, meshing::MeshingPipeline, minimap::MinimapPipeline,
+        post::PostPipeline, selection::SelectionPipeline, terrain::TerrainPipeline,
+        text::TextPipeline,
+    },
+    push_constants::PushConstants,
     shader::ShaderModules,
 };
 
@@ -12,6 +19,19 @@ use super::layouts::Layouts;
 pub struct Pipelines {
     pub terrain: TerrainPipeline,
     pub figure: FigurePipeline,
+    pub decoration: DecorationPipeline,
+    pub fluid: FluidPipeline,
+    pub barrier: BarrierPipeline,
+    pub label: LabelPipeline,
+    pub line: LinePipeline,
+    pub selection: SelectionPipeline,
+    pub minimap: MinimapPipeline,
+    pub text: TextPipeline,
+    pub loading: LoadingPipeline,
+    pub clear: ClearPipeline,
+    pub post: PostPipeline,
+    /// `None` if compute shaders aren't supported
+    pub meshing: Option<MeshingPipeline>,
 }
 
 impl Pipelines {
@@ -20,10 +40,38 @@ impl Pipelines {
         layouts: &Layouts,
         shaders: &ShaderModules,
         config: &SurfaceConfiguration,
+        push_constants: &PushConstants,
     ) -> Self {
         Self {
-            terrain: TerrainPipeline::new(device, config, &shaders.terrain, &layouts.globals),
-            figure: FigurePipeline::new(device, config, &shaders.figure, &layouts.globals),
+            terrain: TerrainPipeline::new(
+                device,
+                config,
+                &shaders.terrain,
+                layouts,
+                push_constants,
+            ),
+            figure: FigurePipeline::new(device, config, &shaders.figure, layouts, push_constants),
+            decoration: DecorationPipeline::new(
+                device,
+                config,
+                &shaders.decoration,
+                layouts,
+                push_constants,
+            ),
+            fluid: FluidPipeline::new(device, config, &shaders.fluid, layouts, push_constants),
+            barrier: BarrierPipeline::new(device, config, &shaders.barrier, layouts),
+            label: LabelPipeline::new(device, config, &shaders.label, layouts),
+            line: LinePipeline::new(device, config, &shaders.line, layouts),
+            selection: SelectionPipeline::new(device, config, &shaders.line, layouts),
+            minimap: MinimapPipeline::new(device, config, &shaders.minimap, layouts),
+            text: TextPipeline::new(device, config, &shaders.text, layouts),
+            loading: LoadingPipeline::new(device, config, &shaders.loading, layouts),
+            clear: ClearPipeline::new(device, config, &shaders.clear, layouts),
+            post: PostPipeline::new(device, config, &shaders.post, layouts),
+            meshing: shaders
+                .meshing
+                .as_ref()
+                .map(|shader| MeshingPipeline::new(device, shader, layouts)),
         }
     }
 }
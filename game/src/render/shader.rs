@@ -1,16 +1,78 @@
 use std::borrow::Cow;
 
 use common_log::prof;
-use wgpu::{Device, ShaderModule, ShaderModuleDescriptor};
+use tokio::runtime::Runtime;
+use tracing::warn;
+use wgpu::{Device, ErrorFilter, ShaderModule, ShaderModuleDescriptor, ShaderSource};
+
+use crate::assets::AssetManager;
+
+use super::push_constants::PushConstants;
 
-// TODO: Make dynamic shader loading (at runtime)
 /// Consts for declaring shaders
 pub trait Shader {
+    /// File name in `assets/shaders`. Resource packs replace the shader with a file of the same
+    /// name
+    const FILE: &'static str;
     const DESCRIPTOR: ShaderModuleDescriptor<'static>;
 
-    fn init(device: &Device) -> ShaderModule {
+    fn init(loader: &ShaderLoader) -> ShaderModule {
         prof!(_guard, "Shader::new");
-        device.create_shader_module(Self::DESCRIPTOR)
+        loader.create(Self::FILE, Cow::Owned, Self::DESCRIPTOR)
+    }
+}
+
+/// Shaders with per-draw data ([`super::push_constants::DrawData`]) declared as `draw`
+pub trait DrawShader: Shader {
+    const SOURCE: &'static str;
+
+    /// Same as [`Shader::init`], but `draw` is declared the way `push_constants` passes it
+    fn init_draw(loader: &ShaderLoader, push_constants: &PushConstants) -> ShaderModule {
+        prof!(_guard, "DrawShader::new");
+        loader.create(
+            Self::FILE,
+            |source| Cow::Owned(push_constants.shader_source(&source).into_owned()),
+            ShaderModuleDescriptor {
+                label: Self::DESCRIPTOR.label,
+                source: ShaderSource::Wgsl(push_constants.shader_source(Self::SOURCE)),
+            },
+        )
+    }
+}
+
+/// Compiles shaders, preferring the ones from resource packs
+pub struct ShaderLoader<'a> {
+    pub device: &'a Device,
+    pub runtime: &'a Runtime,
+    pub assets: &'a AssetManager,
+}
+
+impl ShaderLoader<'_> {
+    /// Compile the override of `file` prepared by `prepare` if there is one. Invalid overrides
+    /// are replaced with the built-in shader.
+    ///
+    /// Overrides must keep entry points and bindings of the built-in shader, as pipelines aren't
+    /// validated against them
+    fn create<'a>(
+        &self,
+        file: &str,
+        prepare: impl FnOnce(String) -> Cow<'a, str>,
+        builtin: ShaderModuleDescriptor,
+    ) -> ShaderModule {
+        if let Some(source) = self.assets.shader(file) {
+            // Validation errors would be fatal otherwise
+            self.device.push_error_scope(ErrorFilter::Validation);
+            let module = self.device.create_shader_module(ShaderModuleDescriptor {
+                label: builtin.label,
+                source: ShaderSource::Wgsl(prepare(source)),
+            });
+            match self.runtime.block_on(self.device.pop_error_scope()) {
+                None => return module,
+                Some(err) => warn!(%err, file, "Invalid shader override, using the built-in one"),
+            }
+        }
+
+        self.device.create_shader_module(builtin)
     }
 }
 
@@ -18,13 +80,36 @@ pub trait Shader {
 pub struct ShaderModules {
     pub terrain: ShaderModule,
     pub figure: ShaderModule,
+    pub decoration: ShaderModule,
+    pub fluid: ShaderModule,
+    pub barrier: ShaderModule,
+    pub label: ShaderModule,
+    pub line: ShaderModule,
+    pub minimap: ShaderModule,
+    pub text: ShaderModule,
+    pub loading: ShaderModule,
+    pub clear: ShaderModule,
+    pub post: ShaderModule,
+    /// `None` if compute shaders aren't supported
+    pub meshing: Option<ShaderModule>,
 }
 
 impl ShaderModules {
-    pub fn init_all(device: &Device) -> Self {
+    pub fn init_all(loader: &ShaderLoader, push_constants: &PushConstants, compute: bool) -> Self {
         Self {
-            terrain: TerrainShader::init(device),
-            figure: FigureShader::init(device),
+            terrain: TerrainShader::init_draw(loader, push_constants),
+            figure: FigureShader::init_draw(loader, push_constants),
+            decoration: DecorationShader::init_draw(loader, push_constants),
+            fluid: FluidShader::init_draw(loader, push_constants),
+            barrier: BarrierShader::init(loader),
+            label: LabelShader::init(loader),
+            line: LineShader::init(loader),
+            minimap: MinimapShader::init(loader),
+            text: TextShader::init(loader),
+            loading: LoadingShader::init(loader),
+            clear: ClearShader::init(loader),
+            post: PostShader::init(loader),
+            meshing: compute.then(|| MeshingShader::init(loader)),
         }
     }
 }
@@ -33,28 +118,179 @@ impl ShaderModules {
 // Pipeline Shaders
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-// TODO: Load shaders from assets
-
 /// Terrain pipeline shader
 pub struct TerrainShader;
 
 impl Shader for TerrainShader {
+    const FILE: &'static str = "terrain.wgsl";
     const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
         label: Some("Shader"),
-        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
-            "../../../assets/shaders/terrain.wgsl"
-        ))),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(Self::SOURCE)),
     };
 }
 
+impl DrawShader for TerrainShader {
+    const SOURCE: &'static str = include_str!("../../../assets/shaders/terrain.wgsl");
+}
+
 /// Figure pipeline shader
 pub struct FigureShader;
 
 impl Shader for FigureShader {
+    const FILE: &'static str = "figure.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(Self::SOURCE)),
+    };
+}
+
+impl DrawShader for FigureShader {
+    const SOURCE: &'static str = include_str!("../../../assets/shaders/figure.wgsl");
+}
+
+/// Decoration (grass tufts) pipeline shader
+pub struct DecorationShader;
+
+impl Shader for DecorationShader {
+    const FILE: &'static str = "decoration.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(Self::SOURCE)),
+    };
+}
+
+impl DrawShader for DecorationShader {
+    const SOURCE: &'static str = include_str!("../../../assets/shaders/decoration.wgsl");
+}
+
+/// Fluid surface pipeline shader
+pub struct FluidShader;
+
+impl Shader for FluidShader {
+    const FILE: &'static str = "fluid.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(Self::SOURCE)),
+    };
+}
+
+impl DrawShader for FluidShader {
+    const SOURCE: &'static str = include_str!("../../../assets/shaders/fluid.wgsl");
+}
+
+/// World border pipeline shader
+pub struct BarrierShader;
+
+impl Shader for BarrierShader {
+    const FILE: &'static str = "barrier.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+            "../../../assets/shaders/barrier.wgsl"
+        ))),
+    };
+}
+
+/// Label pipeline shader
+pub struct LabelShader;
+
+impl Shader for LabelShader {
+    const FILE: &'static str = "label.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+            "../../../assets/shaders/label.wgsl"
+        ))),
+    };
+}
+
+/// Debug line pipeline shader
+pub struct LineShader;
+
+impl Shader for LineShader {
+    const FILE: &'static str = "line.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+            "../../../assets/shaders/line.wgsl"
+        ))),
+    };
+}
+
+/// Minimap pipeline shader
+pub struct MinimapShader;
+
+impl Shader for MinimapShader {
+    const FILE: &'static str = "minimap.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+            "../../../assets/shaders/minimap.wgsl"
+        ))),
+    };
+}
+
+/// Screen-space text pipeline shader
+pub struct TextShader;
+
+impl Shader for TextShader {
+    const FILE: &'static str = "text.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+            "../../../assets/shaders/text.wgsl"
+        ))),
+    };
+}
+
+/// Loading screen pipeline shader
+pub struct LoadingShader;
+
+impl Shader for LoadingShader {
+    const FILE: &'static str = "loading.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+            "../../../assets/shaders/loading.wgsl"
+        ))),
+    };
+}
+
+/// Viewport clear pipeline shader
+pub struct ClearShader;
+
+impl Shader for ClearShader {
+    const FILE: &'static str = "clear.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+            "../../../assets/shaders/clear.wgsl"
+        ))),
+    };
+}
+
+/// Post pass (screen effects) pipeline shader
+pub struct PostShader;
+
+impl Shader for PostShader {
+    const FILE: &'static str = "post.wgsl";
+    const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+            "../../../assets/shaders/post.wgsl"
+        ))),
+    };
+}
+
+/// Terrain meshing compute shader
+pub struct MeshingShader;
+
+impl Shader for MeshingShader {
+    const FILE: &'static str = "meshing.wgsl";
     const DESCRIPTOR: ShaderModuleDescriptor<'static> = ShaderModuleDescriptor {
         label: Some("Shader"),
         source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
-            "../../../assets/shaders/figure.wgsl"
+            "../../../assets/shaders/meshing.wgsl"
         ))),
     };
 }
@@ -1,11 +1,14 @@
 use common_log::span;
 use tracing::debug;
 use wgpu::{
-    AddressMode, CompareFunction, Device, Extent3d, FilterMode, Sampler, SamplerDescriptor,
-    SurfaceConfiguration, Texture as WTexture, TextureDescriptor, TextureDimension, TextureFormat,
-    TextureUsages, TextureView, TextureViewDescriptor,
+    AddressMode, CompareFunction, Device, Extent3d, FilterMode, ImageCopyTexture, ImageDataLayout,
+    Origin3d, Queue, Sampler, SamplerDescriptor, SurfaceConfiguration, Texture as WTexture,
+    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+    TextureViewDescriptor,
 };
 
+use crate::types::U32x2;
+
 /// Represents image that has been uploaded to the GPU
 pub struct Texture {
     pub texture: WTexture,
@@ -64,4 +67,119 @@ impl Texture {
             format: Self::DEPTH_FORMAT,
         }
     }
+
+    /// Color target with the surface size and format. Sampled by the post pass
+    pub fn new_target(device: &Device, config: &SurfaceConfiguration, label: &str) -> Self {
+        span!(_guard, "NewTargetTexture");
+
+        let size = Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        };
+
+        debug!(texture = label, "Creating new render target");
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: config.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: None,
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            size,
+            format: config.format,
+        }
+    }
+
+    /// Texture with RGBA colors that can be updated with [`Self::write`]. Filtered with the
+    /// nearest texel
+    pub fn new_rgba(device: &Device, size: U32x2, address_mode: AddressMode, label: &str) -> Self {
+        span!(_guard, "NewRgbaTexture");
+
+        let format = TextureFormat::Rgba8Unorm;
+        let size = Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        };
+
+        debug!(texture = label, "Creating new texture");
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: None,
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            size,
+            format,
+        }
+    }
+
+    /// Replace `size` texels starting at `offset` with `texels` (rows from top to bottom)
+    pub fn write(&self, queue: &Queue, offset: U32x2, size: U32x2, texels: &[[u8; 4]]) {
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: offset.x,
+                    y: offset.y,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            bytemuck::cast_slice(texels),
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(size.x * 4),
+                rows_per_image: None,
+            },
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
 }
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+
+use common::{coord::GlobalCoord, geometry::Aabb, path, physics::sweep};
+use rand::Rng;
+
+use crate::types::{F32x2, F32x3};
+
+/// Mob behaviour: walk to random reachable positions around and idle in between
+#[derive(Clone, Debug)]
+pub struct Wander {
+    /// Movement speed (blocks per second)
+    pub speed: f32,
+    /// Max distance of the next target (in blocks)
+    pub radius: i64,
+    path: VecDeque<GlobalCoord>,
+    /// Time left before picking the next target (in seconds)
+    idle: f32,
+}
+
+impl Wander {
+    pub const DEFAULT_SPEED: f32 = 3.0;
+    pub const DEFAULT_RADIUS: i64 = 12;
+    /// Pathfinding limit per target
+    const MAX_PATH_NODES: usize = 512;
+    /// Mob bounding box relative to its position (block corner)
+    pub const AABB: Aabb = Aabb::new(F32x3::new(0.1, 0.0, 0.1), F32x3::new(0.9, 0.9, 0.9));
+
+    pub fn new(speed: f32, radius: i64) -> Self {
+        Self {
+            speed,
+            radius,
+            path: VecDeque::new(),
+            idle: 0.0,
+        }
+    }
+
+    /// Block mob is standing in
+    pub fn block_pos(pos: F32x3) -> GlobalCoord {
+        GlobalCoord::from_vec3(pos.round())
+    }
+
+    pub fn path(&self) -> &VecDeque<GlobalCoord> {
+        &self.path
+    }
+
+    /// Advance mob state. `solid` returns `None` for unloaded blocks.
+    ///
+    /// Returns `true` if the mob has moved
+    pub fn tick(
+        &mut self,
+        pos: &mut F32x3,
+        rot: &mut F32x2,
+        dt: f32,
+        rng: &mut impl Rng,
+        solid: impl Fn(GlobalCoord) -> Option<bool>,
+    ) -> bool {
+        let Some(&next) = self.path.front() else {
+            self.idle -= dt;
+            if self.idle <= 0.0 {
+                self.idle = rng.gen_range(1.0..4.0);
+
+                let start = Self::block_pos(*pos);
+                let goal = GlobalCoord::new(
+                    start.x + rng.gen_range(-self.radius..=self.radius),
+                    start.y,
+                    start.z + rng.gen_range(-self.radius..=self.radius),
+                );
+
+                if let Some(path) = path::find_path(start, goal, Self::MAX_PATH_NODES, &solid) {
+                    self.path = path.into();
+                }
+            }
+
+            return false;
+        };
+
+        // World has changed since the path was found
+        if !path::walkable(next, &solid) {
+            self.path.clear();
+            return false;
+        }
+
+        let target = next.as_vec();
+        let delta = target - *pos;
+        let step = self.speed * dt;
+
+        if delta.length() <= step {
+            *pos = target;
+            self.path.pop_front();
+        } else {
+            let moved = sweep(Self::AABB.translate(*pos), delta.normalize() * step, &solid).delta;
+
+            // Stuck (e.g. something placed in the way)
+            if moved.length_squared() < f32::EPSILON {
+                self.path.clear();
+                return false;
+            }
+
+            *pos += moved;
+        }
+
+        // Face movement direction
+        if delta.x != 0.0 || delta.z != 0.0 {
+            rot.x = delta.x.atan2(delta.z);
+        }
+
+        true
+    }
+}
+
+impl Default for Wander {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SPEED, Self::DEFAULT_RADIUS)
+    }
+}
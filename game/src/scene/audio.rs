@@ -0,0 +1,111 @@
+//! Muffling of positional sounds by terrain.
+//!
+//! There is no audio output yet. Once positional sources are mixed, their volume and low-pass
+//! filter are taken from [`Occlusion::between`] the listener and the source, so cave ambience and
+//! machinery behind walls sound muffled
+
+use common::physics::occluders;
+
+use crate::types::F32x3;
+
+use super::chunk::ChunkManager;
+
+/// Volume and low-pass filter of a sound heard through terrain
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Occlusion {
+    /// Volume multiplier
+    pub gain: f32,
+    /// Low-pass filter cutoff frequency (Hz)
+    pub cutoff: f32,
+}
+
+impl Occlusion {
+    /// Blocks counted between the listener and the source. Thicker walls sound the same
+    pub const MAX_BLOCKS: u32 = 8;
+    /// Volume left after every block
+    const GAIN_PER_BLOCK: f32 = 0.7;
+    /// Cutoff of unobstructed sounds, above the audible range
+    pub const OPEN_CUTOFF: f32 = 20_000.0;
+    /// Cutoff behind [`Self::MAX_BLOCKS`] blocks
+    pub const MIN_CUTOFF: f32 = 400.0;
+
+    pub const NONE: Self = Self {
+        gain: 1.0,
+        cutoff: Self::OPEN_CUTOFF,
+    };
+
+    /// Occlusion of `blocks` solid blocks. Cutoff falls exponentially, so every block removes the
+    /// same share of high frequencies
+    pub fn from_blocks(blocks: u32) -> Self {
+        let blocks = blocks.min(Self::MAX_BLOCKS);
+        let depth = blocks as f32 / Self::MAX_BLOCKS as f32;
+
+        Self {
+            gain: Self::GAIN_PER_BLOCK.powi(blocks as i32),
+            cutoff: Self::OPEN_CUTOFF * (Self::MIN_CUTOFF / Self::OPEN_CUTOFF).powf(depth),
+        }
+    }
+
+    /// Occlusion by solid blocks on the straight line from the listener to the source. Chunks
+    /// which aren't loaded don't muffle sounds
+    pub fn between(chunk_manager: &ChunkManager, listener: F32x3, source: F32x3) -> Self {
+        Self::from_blocks(occluders(listener, source, Self::MAX_BLOCKS, |pos| {
+            chunk_manager.block(pos).is_some_and(|block| block.solid())
+        }))
+    }
+}
+
+impl Default for Occlusion {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        block::Block,
+        chunk::Chunk,
+        coord::{ChunkId, CHUNK_CUBE},
+    };
+
+    use crate::{
+        scene::chunk::{ChunkManager, LogicChunk},
+        types::F32x3,
+    };
+
+    use super::Occlusion;
+
+    #[test]
+    fn walls_muffle_sounds() {
+        assert_eq!(Occlusion::from_blocks(0), Occlusion::NONE);
+        let wall = Occlusion::from_blocks(2);
+        assert!(wall.gain < 1.0 && wall.cutoff < Occlusion::OPEN_CUTOFF);
+        assert!(Occlusion::from_blocks(3).cutoff < wall.cutoff);
+        assert_eq!(Occlusion::from_blocks(100), Occlusion::from_blocks(8));
+        assert!((Occlusion::from_blocks(8).cutoff - Occlusion::MIN_CUTOFF).abs() < 1.0);
+
+        // Listener in the air chunk hears a source inside the stone chunk below
+        let mut manager = ChunkManager::new();
+        manager
+            .chunks
+            .insert(ChunkId::new(0, 0, 0), LogicChunk::new());
+        manager.chunks.insert(
+            ChunkId::new(0, -1, 0),
+            LogicChunk::from_chunk(Chunk::from_blocks([Block::Stone; CHUNK_CUBE])),
+        );
+        let listener = F32x3::new(4.5, 2.5, 4.5);
+        assert_eq!(
+            Occlusion::between(&manager, listener, F32x3::new(4.5, -3.5, 4.5)),
+            Occlusion::from_blocks(3)
+        );
+        assert_eq!(
+            Occlusion::between(&manager, listener, F32x3::new(12.5, 6.5, 4.5)),
+            Occlusion::NONE
+        );
+    }
+}
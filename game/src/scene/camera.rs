@@ -1,12 +1,19 @@
 use std::{
-    f32::consts::{FRAC_PI_2, FRAC_PI_4, TAU},
+    f32::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2, TAU},
     time::Duration,
 };
 
+use common::{
+    coord::{GlobalCoord, CHUNK_SIZE},
+    movement::PlayerInput,
+};
 use common_log::prof;
-use winit::event::{ElementState, VirtualKeyCode};
+use winit::event::ElementState;
 
-use crate::types::{F32x2, F32x3, Mat4, Rad};
+use crate::{
+    types::{F32x2, F32x3, Mat4, Rad},
+    window::binding::GameInput,
+};
 
 /// Represents camera mode
 #[derive(PartialEq, Eq, Debug)]
@@ -15,6 +22,36 @@ pub enum CameraMode {
     ThirdPerson,
 }
 
+/// Represents camera projection
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Projection {
+    Perspective,
+    /// Parallel projection, `height` is the number of blocks visible vertically
+    Orthographic {
+        height: f32,
+    },
+}
+
+impl Projection {
+    /// Calculate projection matrix (`fov` is used only by the perspective projection)
+    pub fn matrix(&self, fov: Rad, aspect: f32, near: f32, far: f32) -> Mat4 {
+        match *self {
+            Self::Perspective => Mat4::perspective_lh(fov, aspect, near, far),
+            Self::Orthographic { height } => {
+                let (half_width, half_height) = (height * aspect / 2.0, height / 2.0);
+                Mat4::orthographic_lh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                )
+            }
+        }
+    }
+}
+
 /// Represents camera and its dependents state
 #[derive(Debug)]
 pub struct Camera {
@@ -28,6 +65,8 @@ pub struct Camera {
     /// Distance between camera and player
     pub dist: f32,
 
+    /// Projection type
+    pub projection: Projection,
     /// Projection aspect ratio
     pub aspect: f32,
     /// Field Of View
@@ -36,6 +75,8 @@ pub struct Camera {
     pub near: f32,
     /// Far Z axis plane
     pub far: f32,
+    /// Far plane set by the user. `None` follows the draw distance, see [`Self::far_for`]
+    pub far_override: Option<f32>,
 
     // Camera smoothness
     /// Desired position
@@ -89,12 +130,14 @@ impl Camera {
         Self {
             pos: Self::DEFAULT_POSITION,
             rot: Self::DEFAULT_ORIENTATION,
+            projection: Projection::Perspective,
             aspect,
             mode: CameraMode::FirstPerson,
             dist,
             fov: Self::DEFAULT_FOV.to_radians(),
             near: Self::Z_NEAR,
             far: Self::Z_FAR,
+            far_override: None,
             f_pos: Self::DEFAULT_POSITION,
             f_rot: Self::DEFAULT_ORIENTATION,
             f_dist: dist,
@@ -104,6 +147,12 @@ impl Camera {
         }
     }
 
+    /// Far plane reaching the corners of the area loaded `draw_distance` chunks around the camera
+    pub fn far_for(draw_distance: u16) -> f32 {
+        ((draw_distance as f32 + 1.0) * CHUNK_SIZE as f32 * SQRT_2)
+            .clamp(Self::MIN_Z_FAR, Self::MAX_Z_FAR)
+    }
+
     /// Resize projection
     pub fn proj_resize(&mut self, width: u32, height: u32) {
         self.aspect = width as f32 / height as f32;
@@ -113,7 +162,8 @@ impl Camera {
     ///
     /// Projection matrix warps the scene to give the effect of depth
     pub fn proj_mat(&self) -> Mat4 {
-        Mat4::perspective_lh(self.fov, self.aspect, self.near, self.far)
+        self.projection
+            .matrix(self.fov, self.aspect, self.near, self.far)
     }
 
     /// Calculate camera view matrix
@@ -126,6 +176,13 @@ impl Camera {
             * Mat4::from_translation(-self.pos)
     }
 
+    /// Calculate view matrix looking straight down at the camera position from `altitude` above
+    ///
+    /// Map is north-up: +Z points to the top of the screen
+    pub fn top_down_view_mat(&self, altitude: f32) -> Mat4 {
+        Mat4::look_at_lh(self.pos + F32x3::Y * altitude, self.pos, F32x3::Z)
+    }
+
     /// Rotate camera
     pub fn rotate(&mut self, delta: F32x2) {
         self.f_rot = clamp(self.f_rot + delta * Self::ROTATION_SCALE);
@@ -206,19 +263,25 @@ impl Camera {
 
     /// Get camera forward unit vector on the XY plane
     pub fn forward_xy(&self) -> F32x3 {
+        PlayerInput::forward_xz(self.rot.x)
+    }
+
+    /// Get camera view direction unit vector
+    pub fn forward(&self) -> F32x3 {
         let (yaw_sin, yaw_cos) = self.rot.x.sin_cos();
-        F32x3::new(yaw_sin, 0.0, yaw_cos)
+        let (pitch_sin, pitch_cos) = self.rot.y.sin_cos();
+        F32x3::new(yaw_sin * pitch_cos, -pitch_sin, yaw_cos * pitch_cos)
     }
 }
 
-fn lerp(lhs: f32, rhs: f32, f: f32) -> f32 {
+pub(crate) fn lerp(lhs: f32, rhs: f32, f: f32) -> f32 {
     // More precise, less performant
     lhs * (1.0 - f) + (rhs * f)
     // Less precise, more performant
     // lhs + f * (rhs - lhs)
 }
 
-fn lerp_angle(lhs: f32, rhs: f32, f: f32) -> f32 {
+pub(crate) fn lerp_angle(lhs: f32, rhs: f32, f: f32) -> f32 {
     lhs + f * {
         let t = (rhs - lhs).rem_euclid(TAU);
         (2.0 * t).rem_euclid(TAU) - t
@@ -243,8 +306,6 @@ pub struct CameraController {
 }
 
 impl CameraController {
-    const SPEED: f32 = 25.0;
-
     /// Resets camera controller inputs
     pub fn reset(&mut self) {
         self.forward = 0.0;
@@ -255,50 +316,51 @@ impl CameraController {
         self.down = 0.0;
     }
 
-    /// Processes input from keyboard
-    pub fn virtual_key(&mut self, key: VirtualKeyCode, state: ElementState) {
+    /// Processes bound movement keys
+    pub fn game_input(&mut self, input: GameInput, state: ElementState) {
         let force = if matches!(state, ElementState::Pressed) {
             1.0
         } else {
             0.0
         };
 
-        match key {
-            // Move forward
-            VirtualKeyCode::W | VirtualKeyCode::Up => self.forward = force,
-            // Move left
-            VirtualKeyCode::A | VirtualKeyCode::Left => self.left = force,
-            // Move backward
-            VirtualKeyCode::S | VirtualKeyCode::Down => self.backward = force,
-            // Move right
-            VirtualKeyCode::D | VirtualKeyCode::Right => self.right = force,
-            // Move up
-            VirtualKeyCode::Space => self.up = force,
-            // Move down
-            VirtualKeyCode::LShift => self.down = force,
-            // Skip other keys
+        match input {
+            GameInput::MoveForward => self.forward = force,
+            GameInput::MoveLeft => self.left = force,
+            GameInput::MoveBackward => self.backward = force,
+            GameInput::MoveRight => self.right = force,
+            GameInput::MoveUp => self.up = force,
+            GameInput::MoveDown => self.down = force,
+            // Skip other inputs
             _ => {}
         }
     }
 
+    /// Build movement input from pressed keys
+    pub fn input(&self, camera: &Camera, duration: Duration) -> PlayerInput {
+        PlayerInput {
+            seq: 0,
+            forward: self.forward - self.backward,
+            strafe: self.left - self.right,
+            lift: self.up - self.down,
+            rot: camera.rot,
+            dt: duration.as_secs_f32(),
+        }
+    }
+
     // TODO: Put in players logic
     /// Updates camera position
-    pub fn move_camera(&mut self, camera: &mut Camera, duration: Duration) {
+    pub fn move_camera(
+        &mut self,
+        camera: &mut Camera,
+        duration: Duration,
+        solid: impl Fn(GlobalCoord) -> Option<bool>,
+    ) {
         prof!(_guard, "Camera::move_camera");
 
-        let dur = duration.as_secs_f32();
-        let move_modifier = Self::SPEED * dur;
-
-        // Common calculations
-        let forward = camera.forward_xy();
-        let right = forward.cross(F32x3::Y);
-
-        // Move forward/backward
-        camera.f_pos += forward * (self.forward - self.backward) * move_modifier;
-        // Move left/right
-        camera.f_pos += right * (self.left - self.right) * move_modifier;
-        // Move up/down
-        camera.f_pos.y += (self.up - self.down) * move_modifier;
+        camera.f_pos = self
+            .input(camera, duration)
+            .apply_collide(camera.f_pos, solid);
     }
 }
 
@@ -314,3 +376,33 @@ impl Default for CameraController {
         }
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::coord::CHUNK_SIZE;
+
+    use crate::{render::pipelines::Environment, scene::chunk::ChunkManager};
+
+    use super::Camera;
+
+    #[test]
+    fn far_plane_follows_draw_distance() {
+        let far = Camera::far_for(8);
+        // Load area edge is in front of the far plane and its corners aren't beyond it
+        assert!(far > (9 * CHUNK_SIZE) as f32);
+        assert!(far < (9 * CHUNK_SIZE) as f32 * 1.5);
+        assert!(Camera::far_for(16) > far);
+        assert!(Camera::far_for(ChunkManager::MIN_DRAW_DISTANCE) >= Camera::MIN_Z_FAR);
+        assert!(Camera::far_for(ChunkManager::MAX_DRAW_DISTANCE) <= Camera::MAX_Z_FAR);
+
+        // Terrain fades out before the load area edge
+        let mut environment = Environment::default();
+        environment.fit_fog(far);
+        assert!(environment.fog_end <= (9 * CHUNK_SIZE) as f32);
+        assert!(environment.fog_start < environment.fog_end);
+    }
+}
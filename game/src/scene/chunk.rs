@@ -1,46 +1,131 @@
 use std::{
-    collections::{HashMap, HashSet},
-    sync::mpsc::{channel, Receiver, Sender},
+    cell::Cell,
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
+    fs::File,
+    io::{self, BufWriter, Write},
+    mem::size_of,
+    ops::Range,
+    path::Path,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use crate::{
-    consts::{BLOCKING_THREADS, CPU_CORES},
+    jobs::JobPool,
+    net::NetClient,
     render::{
+        arena::{ArenaSlice, TerrainArena},
         buffer::Buffer,
+        compute_mesh::ComputeMesher,
         mesh::{MeshTaskResult, TerrainMesh},
-        primitives::vertex::Vertex,
+        primitives::decoration::DecorationInstance,
+        renderer::Renderer,
     },
+    settings::{ChunkSettings, ThreadSettings},
+    types::F32x3,
 };
 use common::{
     block::Block,
+    bounds::WorldBounds,
+    chunk::{Chunk, LoadArea, Occupancy},
     coord::{BlockCoord, ChunkId, GlobalCoord, GlobalUnit, CHUNK_CUBE, CHUNK_SIZE},
+    direction::Direction,
+    event::WorldEvent,
+    geometry::{Aabb, Frustum, Ray},
+    net::protocol::ClientMsg,
+    palette::Palette,
+    physics::{raycast, RayHit},
 };
-use common_log::{prof, span};
-use noise::{NoiseFn, Perlin};
+use common_log::span;
 use tokio::runtime::Runtime;
-use wgpu::{BufferUsages, Device};
+use wgpu::BufferUsages;
 
 use super::camera::Camera;
 
 pub struct ChunkManager {
     // TODO: Move to game settings
     pub draw_distance: u16,
+    /// Stop loading, unloading, evicting and remeshing chunks (debug)
+    pub frozen: bool,
+    /// New tasks aren't started, see [`Self::shutdown`]
+    shutting_down: bool,
+    /// Chunks out of bounds aren't loaded. Set by the server when connected
+    pub bounds: WorldBounds,
+    /// Load area is extended by the distance the camera travels in this time (seconds)
+    pub lookahead: f32,
+    /// Share of the vertical load range moved towards the terrain surface, see
+    /// [`vertical_extent`]. Set from [`ChunkSettings`]
+    pub vertical_bias: f32,
+    /// Smoothed camera velocity (blocks per second)
+    velocity: F32x3,
+    /// Camera position and time of the last maintain
+    last_camera: Option<(F32x3, Instant)>,
 
     pub mesh_builder_rx: Receiver<MeshTaskResult>,
     pub mesh_builder_tx: Sender<MeshTaskResult>,
+    /// Built meshes waiting for the upload budget
+    uploads: VecDeque<MeshTaskResult>,
+    /// Max mesh data uploaded to the GPU per maintain (bytes). At least one mesh is uploaded
+    pub upload_budget: u64,
+    /// Mesh data uploaded on the last maintain (bytes)
+    uploaded: u64,
 
     pub chunk_gen_rx: Receiver<(ChunkId, LogicChunk)>,
     pub chunk_gen_tx: Sender<(ChunkId, LogicChunk)>,
     pub chunk_gen_ids: HashSet<ChunkId>,
 
-    pub logic: HashMap<ChunkId, LogicChunk>,
-    pub terrain: HashMap<ChunkId, TerrainChunk>,
+    /// Loaded chunks with their meshes
+    pub chunks: ChunkStore,
+    /// GPU buffers terrain meshes are allocated from
+    pub arena: TerrainArena,
+    /// GPU mesher, created once [`RenderMode::compute_meshing`] is enabled
+    ///
+    /// [`RenderMode::compute_meshing`]: crate::render::RenderMode::compute_meshing
+    compute_mesher: Option<ComputeMesher>,
+    /// Block colors of terrain meshes
+    palette: Arc<Palette>,
+    /// Palette has been changed since the last maintain
+    palette_changed: bool,
+    /// Max size of terrain meshes on the GPU (bytes). Least recently drawn meshes are evicted when
+    /// it's exceeded
+    pub memory_budget: u64,
+    /// Incremented on every maintain. Used to track when terrain has been drawn
+    frame: u64,
+    /// World events since the last [`Self::take_events`]
+    events: Vec<WorldEvent>,
+    /// Limit concurrent chunk tasks (e.g. window isn't focused)
+    pub throttled: bool,
+    /// Runs chunk generation and CPU meshing
+    pub jobs: JobPool,
 }
 
 impl ChunkManager {
     // Limits
     pub const MIN_DRAW_DISTANCE: u16 = 2;
     pub const MAX_DRAW_DISTANCE: u16 = 256;
+    pub const MAX_LOOKAHEAD: f32 = 10.0;
+
+    pub const DEFAULT_LOOKAHEAD: f32 = 2.0;
+    /// Time to reach the new velocity
+    const VELOCITY_SMOOTHING: f32 = 0.5;
+    /// Faster movement is treated as a teleport and isn't tracked
+    const MAX_TRACKED_SPEED: f32 = 1000.0;
+    /// Chunks are unloaded this far (in chunks) beyond the draw distance
+    pub const UNLOAD_MARGIN: u16 = 2;
+    pub const MIN_MEMORY_BUDGET: u64 = 16 * 1024 * 1024;
+    pub const MAX_MEMORY_BUDGET: u64 = 8 * 1024 * 1024 * 1024;
+    pub const DEFAULT_MEMORY_BUDGET: u64 = 512 * 1024 * 1024;
+    /// Chunks are kept at least for this time after loading
+    pub const MIN_RESIDENT_TIME: Duration = Duration::from_secs(5);
+    /// Max mesh tasks started per maintain and chunk generation tasks at once when throttled
+    pub const THROTTLED_TASKS: usize = 1;
+    pub const MIN_UPLOAD_BUDGET: u64 = 256 * 1024;
+    pub const MAX_UPLOAD_BUDGET: u64 = 64 * 1024 * 1024;
+    pub const DEFAULT_UPLOAD_BUDGET: u64 = 4 * 1024 * 1024;
 
     pub fn new() -> Self {
         let (mesh_builder_tx, mesh_builder_rx) = channel();
@@ -48,119 +133,713 @@ impl ChunkManager {
 
         Self {
             draw_distance: Self::MIN_DRAW_DISTANCE,
+            frozen: false,
+            shutting_down: false,
+            bounds: WorldBounds::default(),
+            lookahead: Self::DEFAULT_LOOKAHEAD,
+            vertical_bias: ChunkSettings::new().vertical_bias,
+            velocity: F32x3::ZERO,
+            last_camera: None,
 
             mesh_builder_rx,
             mesh_builder_tx,
+            uploads: VecDeque::new(),
+            upload_budget: Self::DEFAULT_UPLOAD_BUDGET,
+            uploaded: 0,
 
             chunk_gen_rx,
             chunk_gen_tx,
-            chunk_gen_ids: HashSet::with_capacity(*BLOCKING_THREADS * 4),
+            chunk_gen_ids: HashSet::new(),
+
+            chunks: ChunkStore::new(),
+            arena: TerrainArena::new(),
+            compute_mesher: None,
+            palette: Arc::new(Palette::new()),
+            palette_changed: false,
+            memory_budget: Self::DEFAULT_MEMORY_BUDGET,
+            frame: 0,
+            events: Vec::new(),
+            throttled: false,
+            jobs: JobPool::new(ThreadSettings::new().blocking_threads()),
+        }
+    }
+
+    /// Max chunks requested from the server at once
+    const MAX_REMOTE_REQUESTS: usize = 64;
+
+    /// Insert chunk received from the server.
+    ///
+    /// Chunks that are neither loaded nor requested (e.g. periodic resync) are ignored
+    pub fn insert(&mut self, id: ChunkId, chunk: Chunk) {
+        if self.chunk_gen_ids.remove(&id) || self.chunks.contains(&id) {
+            self.chunks.insert(id, LogicChunk::from_chunk(chunk));
+            self.events.push(WorldEvent::ChunkLoaded(id));
+        }
+    }
+
+    /// Apply block changes received from the server. Returns `false` if chunk isn't loaded
+    pub fn apply_delta(&mut self, id: ChunkId, changes: &[(BlockCoord, Block)]) -> bool {
+        match self.chunks.get_mut(&id) {
+            Some(chunk) => {
+                let origin = id.to_coord();
+                let mut edges = Vec::new();
+                for &(pos, block) in changes {
+                    let before = chunk.set_block(pos, block);
+                    if before != block {
+                        self.events.push(WorldEvent::BlockChanged {
+                            pos: origin.to_global(&pos),
+                            before,
+                            after: block,
+                        });
+                    }
+                    if culls_differently(before, block) {
+                        edges.extend(Self::edges(pos));
+                    }
+                }
+                // Remeshing is idempotent, so repeated edges are fine
+                edges.into_iter().for_each(|dir| {
+                    self.remesh(id.neighbor(dir));
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Chunk edges the block touches
+    pub fn edges(pos: BlockCoord) -> impl Iterator<Item = Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(move |&dir| pos.on_chunk_edge(dir))
+    }
+
+    /// Number of chunks being generated or requested from the server
+    pub fn pending_chunks(&self) -> usize {
+        self.chunk_gen_ids.len()
+    }
+
+    /// Get block from a loaded chunk
+    pub fn block(&self, pos: GlobalCoord) -> Option<Block> {
+        self.chunks
+            .get(&pos.to_chunk_id())
+            .map(|chunk| chunk.blocks()[pos.to_block().flatten()])
+    }
+
+    /// Rebuild chunk mesh on the next maintain. Returns `false` if chunk isn't loaded
+    pub fn remesh(&mut self, id: ChunkId) -> bool {
+        match self.chunks.get_mut(&id) {
+            Some(chunk) => {
+                chunk.status = TerrainStatus::None;
+                true
+            }
+            None => false,
+        }
+    }
 
-            logic: HashMap::new(),
-            terrain: HashMap::new(),
+    /// Write chunk blocks to a text file (`x y z block` per line, local coordinates)
+    pub fn dump(&self, id: ChunkId, path: impl AsRef<Path>) -> io::Result<()> {
+        let chunk = self
+            .chunks
+            .get(&id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Chunk isn't loaded"))?;
+
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "# Chunk {} {} {}", id.x, id.y, id.z)?;
+        for (i, block) in chunk.blocks().iter().enumerate() {
+            let pos = BlockCoord::from(i);
+            writeln!(file, "{} {} {} {:?}", pos.x, pos.y, pos.z, block)?;
         }
+
+        file.flush()
+    }
+
+    /// Check if block is solid. `None` if chunk isn't loaded
+    pub fn solid(&self, pos: GlobalCoord) -> Option<bool> {
+        self.block(pos).map(|block| block.solid())
+    }
+
+    /// First non-air block along the ray, with the face it's hit through. Unloaded chunks are
+    /// passed through
+    pub fn raycast(&self, ray: Ray, max_distance: f32) -> Option<RayHit> {
+        raycast(ray, max_distance, |pos| {
+            self.block(pos).is_some_and(|block| block != Block::Air)
+        })
+    }
+
+    /// Set block in a loaded chunk. Returns `false` if chunk isn't loaded.
+    ///
+    /// Neighbor chunks are remeshed too if the block on the edge changes faces of its neighbors
+    pub fn set_block(&mut self, pos: GlobalCoord, block: Block) -> bool {
+        let id = pos.to_chunk_id();
+        match self.chunks.get_mut(&id) {
+            Some(chunk) => {
+                let local = pos.to_block();
+                let before = chunk.set_block(local, block);
+                if before != block {
+                    self.events.push(WorldEvent::BlockChanged {
+                        pos,
+                        before,
+                        after: block,
+                    });
+                }
+                if culls_differently(before, block) {
+                    Self::edges(local).for_each(|dir| {
+                        self.remesh(id.neighbor(dir));
+                    });
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rebuild meshes of all loaded neighbors (e.g. after the whole chunk has been replaced)
+    pub fn remesh_neighbors(&mut self, id: ChunkId) {
+        Direction::ALL.into_iter().for_each(|dir| {
+            self.remesh(id.neighbor(dir));
+        });
     }
 
     /// Maintain chunk manager. Regenerate chunk meshes.
-    pub fn maintain(&mut self, device: &Device, runtime: &Runtime, camera: &Camera) {
+    ///
+    /// If `net` is present, chunks are requested from the server instead of being generated
+    pub fn maintain(
+        &mut self,
+        renderer: &Renderer,
+        runtime: &Runtime,
+        camera: &Camera,
+        net: Option<&NetClient>,
+    ) {
         span!(_guard, "maintain", "ChunkManager::maintain");
 
-        // Collect generated terrain chunks
-        self.mesh_builder_rx.try_iter().for_each(|(coord, mesh)| {
+        self.track_velocity(camera.pos);
+        self.frame += 1;
+
+        // Compute meshing can be toggled at any time. The mesher is dropped once its jobs finish
+        let compute_meshing = renderer.render_mode().compute_meshing;
+        if compute_meshing && self.compute_mesher.is_none() {
+            self.compute_mesher = ComputeMesher::new(renderer, &self.palette);
+        } else if !compute_meshing
+            && self
+                .compute_mesher
+                .as_ref()
+                .is_some_and(|mesher| mesher.pending().next().is_none())
+        {
+            self.compute_mesher = None;
+        }
+        if std::mem::take(&mut self.palette_changed) {
+            if let Some(mesher) = &self.compute_mesher {
+                mesher.set_palette(renderer, &self.palette);
+            }
+        }
+
+        // Upload built terrain chunks. Many meshes finish at once while streaming, so uploads are
+        // spread over frames to keep frame time flat. wgpu has a single queue, so staging writes
+        // go with the frame submission
+        self.uploads.extend(self.mesh_builder_rx.try_iter());
+        let count = within_budget(
+            self.uploads.iter().map(|(_, mesh, _)| mesh.size()),
+            self.upload_budget,
+        );
+        self.uploaded = 0;
+        for (coord, mesh, build_time) in self.uploads.drain(..count).collect::<Vec<_>>() {
             let coord = coord.to_id();
 
             // TODO: Check if terrain already rebuilt
-            if let Some(logic) = self.logic.get_mut(&coord) {
-                if matches!(logic.status, TerrainStatus::Pending) {
-                    self.terrain.insert(coord, TerrainChunk::new(device, mesh));
-                    logic.status = TerrainStatus::Built;
-                } else {
-                    tracing::warn!(?coord, "Chunk mesh building collision");
+            if self.pending(&coord) {
+                // Mesher bugs are reported instead of being drawn as garbage. The old mesh is kept
+                #[cfg(debug_assertions)]
+                if let Err(err) = mesh.validate() {
+                    tracing::error!(?coord, %err, "Invalid terrain mesh");
+                    if let Some(chunk) = self.chunks.get_mut(&coord) {
+                        chunk.status = TerrainStatus::Built;
+                    }
+                    continue;
                 }
+
+                self.uploaded += mesh.size();
+                let slice = self.arena.alloc(
+                    &renderer.device,
+                    &renderer.queue,
+                    &mesh.vertices,
+                    &mesh.indices,
+                );
+                let decorations = (!mesh.decorations.is_empty()).then(|| {
+                    Buffer::new(&renderer.device, &mesh.decorations, BufferUsages::VERTEX)
+                });
+                self.insert_terrain(
+                    coord,
+                    slice,
+                    (mesh.cutout, mesh.fluid),
+                    decorations,
+                    Some(build_time),
+                );
             }
-        });
+        }
+
+        // Collect terrain chunks meshed on the GPU
+        let computed = match &mut self.compute_mesher {
+            Some(mesher) => mesher.collect(renderer, &mut self.arena),
+            None => Vec::new(),
+        };
+        for (coord, slice) in computed {
+            let coord = coord.to_id();
+
+            match slice {
+                // Compute meshes have no cutout and fluid geometry and decorations
+                Some(slice) if self.pending(&coord) => {
+                    let opaque = slice.indices.len() as u32;
+                    // Build time of GPU meshes isn't measured
+                    self.insert_terrain(coord, slice, (opaque, opaque), None, None);
+                }
+                Some(slice) => self.arena.free(&slice),
+                // Try again on the next maintain
+                None => {
+                    self.remesh(coord);
+                }
+            }
+        }
 
         // Collect generated logic chunks
         self.chunk_gen_rx.try_iter().for_each(|(id, chunk)| {
             self.chunk_gen_ids.remove(&id);
-            self.logic.insert(id, chunk);
+            self.chunks.insert(id, chunk);
+            self.events.push(WorldEvent::ChunkLoaded(id));
         });
 
-        // Run mesh generating tasks
-        self.logic
+        if !self.frozen {
+            self.evict_meshes();
+        }
+        self.arena.defragment(
+            &renderer.device,
+            &renderer.queue,
+            self.chunks.meshes_mut().map(|chunk| &mut chunk.slice),
+        );
+
+        // Tasks started before freezing are still collected above
+        if self.frozen || self.shutting_down {
+            return;
+        }
+
+        // Evicted meshes are rebuilt once they are visible again
+        let frustum = Frustum::from_matrix(camera.proj_mat() * camera.view_mat());
+        self.chunks
             .iter_mut()
+            .filter(|(id, chunk)| {
+                matches!(chunk.status, TerrainStatus::Evicted) && {
+                    let min = id.to_coord().as_vec();
+                    frustum.intersects_aabb(&Aabb::new(min, min + CHUNK_SIZE as f32))
+                }
+            })
+            .for_each(|(_, chunk)| chunk.status = TerrainStatus::None);
+
+        // Run mesh generating tasks
+        let mut remesh = self
+            .chunks
+            .iter()
             .filter(|(_, chunk)| matches!(chunk.status, TerrainStatus::None))
-            .take(*BLOCKING_THREADS * 8)
-            .for_each(|(coord, chunk)| {
-                // TODO: Add a check for an empty mesh when it'll be aware of neighboring blocks
-                // Check if chunk has at least one opaque block. Otherwise skip mesh building
-                if chunk.blocks.iter().any(|block| block.opaque()) {
-                    let tx = self.mesh_builder_tx.clone();
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let workers = self.jobs.workers();
+        let mesh_tasks = if self.throttled {
+            Self::THROTTLED_TASKS
+        } else {
+            workers * 8
+        };
+        prioritize(&mut remesh, mesh_tasks, camera);
+        remesh.iter().for_each(|coord| {
+            if let Some(chunk) = self.chunks.get_mut(coord) {
+                // TODO: Skip buried chunks (see `Self::buried`) when meshes will be aware of
+                // neighboring blocks. Until then faces on chunk edges are always built
+                // Check if chunk has at least one non-air block. Otherwise skip mesh building
+                if !chunk.occupancy().is_empty() {
                     let coord = *coord;
-                    let blocks = chunk.blocks;
-                    runtime.spawn_blocking(move || {
-                        TerrainMesh::task(tx, coord.to_coord(), &blocks);
-                    });
+                    let blocks = chunk.chunk.shared_blocks();
+
+                    // Falls back to the CPU if the chunk isn't supported or all slots are busy
+                    let computed = self
+                        .compute_mesher
+                        .as_mut()
+                        .filter(|_| compute_meshing && ComputeMesher::supports(&*blocks))
+                        .is_some_and(|mesher| mesher.submit(renderer, coord.to_coord(), &*blocks));
+                    if !computed {
+                        let tx = self.mesh_builder_tx.clone();
+                        let palette = self.palette.clone();
+                        self.jobs.spawn(runtime, move || {
+                            TerrainMesh::task(tx, coord.to_coord(), &*blocks, &palette);
+                        });
+                    }
 
                     chunk.status = TerrainStatus::Pending;
                 } else {
                     // Free old mesh buffer for updated empty chunk
-                    self.terrain.remove(coord);
+                    if let Some(old) = chunk.terrain.take() {
+                        self.arena.free(&old.slice);
+                    }
                     chunk.status = TerrainStatus::Built;
                 }
-            });
+            }
+        });
 
         // Load new chunks
-        LoadArea::new_cuboid(
-            GlobalCoord::from_vec3(camera.pos).to_chunk_id(),
-            self.draw_distance as i64,
-        )
-        .filter(|id| {
-            !self.logic.contains_key(id)
-                && !self.chunk_gen_ids.contains(id)
-                && self.chunk_gen_ids.len() < *CPU_CORES
-        })
-        .take(*BLOCKING_THREADS * 4 - self.chunk_gen_ids.len())
-        .collect::<Vec<_>>()
-        .iter()
-        .for_each(|id| {
+        let pending = self.chunk_gen_ids.len();
+        let budget = match net {
+            _ if self.throttled => Self::THROTTLED_TASKS.saturating_sub(pending),
+            Some(_) => Self::MAX_REMOTE_REQUESTS.saturating_sub(pending),
+            None if pending < workers * 2 => workers * 4 - pending,
+            None => 0,
+        };
+        let load_area = self.load_area(camera);
+        let mut missing = load_area
+            .clone()
+            .filter(|id| {
+                self.bounds.contains_chunk(*id)
+                    && !self.chunks.contains(id)
+                    && !self.chunk_gen_ids.contains(id)
+            })
+            .collect::<Vec<_>>();
+        prioritize(&mut missing, budget, camera);
+        missing.iter().for_each(|id| {
             let id = *id;
             self.chunk_gen_ids.insert(id);
 
-            let tx = self.chunk_gen_tx.clone();
-            runtime.spawn_blocking(move || {
-                let _ = tx.send((id, LogicChunk::generate_flat(id)));
-            });
+            match net {
+                Some(net) => net.send(ClientMsg::RequestChunk(id)),
+                None => {
+                    let tx = self.chunk_gen_tx.clone();
+                    let bounds = self.bounds;
+                    self.jobs.spawn(runtime, move || {
+                        let chunk = Chunk::generate(id, &bounds);
+                        let _ = tx.send((id, LogicChunk::from_chunk(chunk)));
+                    });
+                }
+            }
         });
 
-        // Unload old chunks
-        let load_area = LoadArea::new_cuboid(
-            GlobalCoord::from_vec3(camera.pos).to_chunk_id(),
-            self.draw_distance as i64,
-        );
-        self.logic
-            .keys()
-            .filter(|&id| !load_area.contains(*id))
-            .copied()
+        // Unload old chunks. Unload area is larger than the load area and recently loaded chunks are
+        // kept, so chunks on the border aren't reloaded when the camera moves back and forth
+        let unload_area = self.area(camera, self.draw_distance + Self::UNLOAD_MARGIN);
+        self.chunks
+            .iter()
+            .filter(|(id, chunk)| {
+                !unload_area.contains(**id) && chunk.loaded_at.elapsed() >= Self::MIN_RESIDENT_TIME
+            })
+            .map(|(id, _)| *id)
             .collect::<Vec<_>>()
             .iter()
             .for_each(|id| {
-                self.logic.remove(id);
-                self.terrain.remove(id);
+                if let Some(terrain) = self.chunks.remove(id).and_then(|chunk| chunk.terrain) {
+                    self.arena.free(&terrain.slice);
+                }
             });
     }
 
+    /// Check if the chunk is solid and all its sides are hidden by solid sides of loaded
+    /// neighbors, so none of its faces can be seen
+    pub fn buried(&self, id: ChunkId) -> bool {
+        let solid_side = |id: ChunkId, dir: Direction| {
+            self.chunks
+                .get(&id)
+                .is_some_and(|chunk| chunk.occupancy().side_solid(dir))
+        };
+
+        self.chunks
+            .get(&id)
+            .is_some_and(|chunk| chunk.occupancy().is_solid())
+            && Direction::ALL
+                .into_iter()
+                .all(|dir| solid_side(id.neighbor(dir), dir.reverse()))
+    }
+
+    /// Mesh data uploaded on the last maintain (bytes)
+    pub fn uploaded(&self) -> u64 {
+        self.uploaded
+    }
+
+    /// Built meshes waiting to be uploaded
+    pub fn pending_uploads(&self) -> usize {
+        self.uploads.len()
+    }
+
+    /// Check if the chunk waits for its mesh. Late meshes of rebuilt chunks are reported
+    fn pending(&self, id: &ChunkId) -> bool {
+        match self.chunks.get(id) {
+            Some(chunk) if matches!(chunk.status, TerrainStatus::Pending) => true,
+            Some(_) => {
+                tracing::warn!(coord = ?id, "Chunk mesh building collision");
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Replace chunk mesh with the uploaded one. `parts` are starts of cutout and fluid indices
+    fn insert_terrain(
+        &mut self,
+        id: ChunkId,
+        slice: ArenaSlice,
+        (cutout, fluid): (u32, u32),
+        decorations: Option<Buffer<DecorationInstance>>,
+        build_time: Option<Duration>,
+    ) {
+        let Some(chunk) = self.chunks.get_mut(&id) else {
+            self.arena.free(&slice);
+            return;
+        };
+
+        let mut terrain = TerrainChunk::new(slice, cutout, fluid, id.to_coord().as_vec());
+        terrain.decorations = decorations;
+        terrain.build_time = build_time;
+        // Not evicted before it's drawn for the first time
+        terrain.mark_drawn(self.frame);
+        if let Some(old) = chunk.terrain.replace(terrain) {
+            self.arena.free(&old.slice);
+        }
+        chunk.status = TerrainStatus::Built;
+    }
+
+    /// Current frame number. Stored by [`TerrainChunk::mark_drawn`]
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Size of all terrain meshes on the GPU (bytes)
+    pub fn terrain_memory(&self) -> u64 {
+        self.chunks.meshes().map(|(_, chunk)| chunk.size()).sum()
+    }
+
+    /// Drop least recently drawn meshes until the memory budget is met. Logic chunks are kept
+    fn evict_meshes(&mut self) {
+        let total = self.terrain_memory();
+        if total <= self.memory_budget {
+            return;
+        }
+
+        let chunks = self
+            .chunks
+            .meshes()
+            .map(|(id, chunk)| (id, chunk.size(), chunk.last_drawn.get()))
+            .collect::<Vec<_>>();
+        let evicted = eviction_order(chunks, total, self.memory_budget, self.frame - 1);
+        if !evicted.is_empty() {
+            tracing::debug!(count = evicted.len(), total, "Evicting terrain meshes");
+        }
+
+        for id in evicted {
+            if let Some(chunk) = self.chunks.get_mut(&id) {
+                if let Some(terrain) = chunk.terrain.take() {
+                    self.arena.free(&terrain.slice);
+                }
+                chunk.status = TerrainStatus::Evicted;
+            }
+        }
+    }
+
+    /// Chunks that should be loaded. Extended in the direction of travel by [`Self::lookahead`]
+    pub fn load_area(&self, camera: &Camera) -> LoadArea {
+        self.area(camera, self.draw_distance)
+    }
+
+    fn area(&self, camera: &Camera, distance: u16) -> LoadArea {
+        let max_lead = self.draw_distance as f32;
+        let lead = (self.velocity * self.lookahead / CHUNK_SIZE as f32)
+            .clamp(F32x3::splat(-max_lead), F32x3::splat(max_lead))
+            .round();
+
+        let center = GlobalCoord::from_vec3(camera.pos).to_chunk_id();
+        let distance = distance as i64;
+        let (below, above) = vertical_extent(
+            distance / 2,
+            self.surface_altitude(center, distance),
+            self.vertical_bias,
+        );
+
+        LoadArea::new_column(center, distance, below, above).extend(ChunkId::new(
+            lead.x as i64,
+            lead.y as i64,
+            lead.z as i64,
+        ))
+    }
+
+    /// Height of the `center` chunk above the terrain surface (in chunks), found from loaded
+    /// chunks of its column within `range`. Negative when the surface is above.
+    ///
+    /// If only empty chunks are loaded, the surface is assumed to be just below them. `None` if
+    /// nothing is loaded
+    fn surface_altitude(&self, center: ChunkId, range: GlobalUnit) -> Option<GlobalUnit> {
+        let mut lowest_empty = None;
+
+        for y in (center.y - range..=center.y + range).rev() {
+            match self.chunks.get(&ChunkId::new(center.x, y, center.z)) {
+                Some(chunk) if !chunk.occupancy().is_empty() => return Some(center.y - y),
+                Some(_) => lowest_empty = Some(y),
+                None => {}
+            }
+        }
+
+        lowest_empty.map(|y| center.y - y + 1)
+    }
+
+    /// Smoothed camera velocity (blocks per second)
+    pub fn velocity(&self) -> F32x3 {
+        self.velocity
+    }
+
+    fn track_velocity(&mut self, pos: F32x3) {
+        let now = Instant::now();
+
+        if let Some((last_pos, last_time)) = self.last_camera {
+            let dt = now.duration_since(last_time).as_secs_f32();
+            if dt > 0.0 {
+                let velocity = (pos - last_pos) / dt;
+                self.velocity = if velocity.length() > Self::MAX_TRACKED_SPEED {
+                    F32x3::ZERO
+                } else {
+                    self.velocity
+                        .lerp(velocity, (dt / Self::VELOCITY_SMOOTHING).min(1.0))
+                };
+            }
+        }
+
+        self.last_camera = Some((pos, now));
+    }
+
+    /// Block changes and loaded chunks since the last call. Published on the scene event bus
+    pub fn take_events(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Replace block colors. Every meshed chunk is rebuilt, old meshes are drawn until then
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = Arc::new(palette);
+        self.palette_changed = true;
+        self.chunks
+            .iter_mut()
+            .filter(|(_, chunk)| {
+                matches!(chunk.status, TerrainStatus::Pending | TerrainStatus::Built)
+            })
+            .for_each(|(_, chunk)| chunk.status = TerrainStatus::None);
+    }
+
+    /// Stop starting new generation and meshing tasks. Running tasks are left to finish
+    pub fn shutdown(&mut self) {
+        self.shutting_down = true;
+        self.chunk_gen_ids.clear();
+    }
+
     pub fn cleanup(&mut self) {
-        self.logic.shrink_to_fit();
-        self.terrain.shrink_to_fit();
+        self.chunks.shrink_to_fit();
     }
 
     pub fn clear_mesh(&mut self) {
-        self.logic
-            .values_mut()
-            .for_each(|chunk| chunk.status = TerrainStatus::None);
-        self.terrain.clear();
+        self.chunks.iter_mut().for_each(|(_, chunk)| {
+            chunk.status = TerrainStatus::None;
+            chunk.terrain = None;
+        });
+        self.uploads.clear();
+        self.arena.clear();
+        // Buffers may belong to the old device
+        self.compute_mesher = None;
+    }
+}
+
+/// Blocks hide different faces of their neighbors. Solid faces are visible through fluids
+fn culls_differently(a: Block, b: Block) -> bool {
+    a.opaque() != b.opaque() || a.liquid() != b.liquid()
+}
+
+/// Chunks loaded below and above the camera, `half` each when the bias is 0.
+///
+/// The range is shifted towards the surface by `bias` of the camera `altitude` (in chunks), so a
+/// flying camera loads more terrain below and fewer sky chunks, and an underground one loads less
+/// of the solid rock under it. At least a chunk is kept on each side
+fn vertical_extent(
+    half: GlobalUnit,
+    altitude: Option<GlobalUnit>,
+    bias: f32,
+) -> (GlobalUnit, GlobalUnit) {
+    let shift = altitude.map_or(0, |altitude| {
+        (altitude.clamp(-half, half) as f32 * bias.clamp(0.0, 1.0)).round() as GlobalUnit
+    });
+    let min = half.min(1);
+    let below = (half + shift).clamp(min, 2 * half - min);
+
+    (below, 2 * half - below)
+}
+
+/// Loading and meshing priority of the chunk (lower goes first).
+///
+/// Distance to the camera weighted by the view direction, so chunks in front of the camera are
+/// processed before the ones behind it
+pub fn load_priority(id: ChunkId, eye: F32x3, forward: F32x3) -> f32 {
+    /// Multiplier range is `VIEW_WEIGHT - 1..=VIEW_WEIGHT + 1` (front..back)
+    const VIEW_WEIGHT: f32 = 1.5;
+
+    let center = id.to_coord().as_vec() + CHUNK_SIZE as f32 / 2.0;
+    let to_chunk = center - eye;
+    let distance = to_chunk.length();
+    if distance < CHUNK_SIZE as f32 {
+        // Chunks around the camera are visible whatever the direction is
+        return distance * (VIEW_WEIGHT - 1.0);
+    }
+
+    distance * (VIEW_WEIGHT - forward.dot(to_chunk / distance))
+}
+
+/// Number of leading items which fit into `budget`. The first item always fits, so large meshes
+/// aren't stuck
+fn within_budget(sizes: impl Iterator<Item = u64>, budget: u64) -> usize {
+    let mut total = 0;
+    sizes
+        .enumerate()
+        .take_while(|&(i, size)| {
+            total += size;
+            i == 0 || total <= budget
+        })
+        .count()
+}
+
+/// Chunks to evict to fit `total` bytes into `budget`, least recently drawn first.
+///
+/// Chunks drawn since `protected_frame` are never evicted, as they would be rebuilt right away
+fn eviction_order(
+    mut chunks: Vec<(ChunkId, u64, u64)>,
+    mut total: u64,
+    budget: u64,
+    protected_frame: u64,
+) -> Vec<ChunkId> {
+    chunks.sort_unstable_by_key(|&(_, _, last_drawn)| last_drawn);
+
+    chunks
+        .into_iter()
+        .take_while(|&(_, _, last_drawn)| last_drawn < protected_frame)
+        .take_while(|&(_, size, _)| {
+            let over = total > budget;
+            total = total.saturating_sub(size);
+            over
+        })
+        .map(|(id, _, _)| id)
+        .collect()
+}
+
+/// Keep the `count` chunks with the highest priority, ordered by it
+fn prioritize(ids: &mut Vec<ChunkId>, count: usize, camera: &Camera) {
+    let (eye, forward) = (camera.pos, camera.forward());
+    let key = |id: &ChunkId| load_priority(*id, eye, forward);
+
+    if count == 0 {
+        ids.clear();
+        return;
+    }
+    if ids.len() > count {
+        ids.select_nth_unstable_by(count - 1, |a, b| key(a).total_cmp(&key(b)));
+        ids.truncate(count);
     }
+    ids.sort_by(|a, b| key(a).total_cmp(&key(b)));
 }
 
 impl Default for ChunkManager {
@@ -171,35 +850,40 @@ impl Default for ChunkManager {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, Debug)]
 pub enum TerrainStatus {
     #[default]
     None,
     Pending,
     Built,
+    /// Mesh has been dropped to fit the memory budget. It's rebuilt when the chunk is visible
+    Evicted,
 }
 
 /// Represents chunk state
 pub struct LogicChunk {
-    blocks: [Block; CHUNK_CUBE],
+    chunk: Chunk,
+    /// Mesh on the GPU. Old mesh is kept while the new one is being built
+    terrain: Option<TerrainChunk>,
+    /// Computed when the chunk is loaded. Reset by [`Self::blocks_mut`]
+    occupancy: Cell<Option<Occupancy>>,
     status: TerrainStatus,
+    /// When the chunk has been loaded (generated or received)
+    loaded_at: Instant,
 }
 
 impl LogicChunk {
-    const SEA_LEVEL: GlobalUnit = 0;
-    const SEA_LEVEL_BIAS: GlobalUnit = 15;
-
-    pub const fn new() -> Self {
-        Self {
-            blocks: [Block::Air; CHUNK_CUBE],
-            status: TerrainStatus::None,
-        }
+    pub fn new() -> Self {
+        Self::from_chunk(Chunk::new())
     }
 
-    pub const fn from_blocks(blocks: [Block; CHUNK_CUBE]) -> Self {
+    pub fn from_chunk(chunk: Chunk) -> Self {
         Self {
-            blocks,
+            occupancy: Cell::new(Some(Occupancy::new(chunk.blocks()))),
+            chunk,
+            terrain: None,
             status: TerrainStatus::None,
+            loaded_at: Instant::now(),
         }
     }
 
@@ -207,61 +891,81 @@ impl LogicChunk {
         self.status
     }
 
+    pub fn terrain(&self) -> Option<&TerrainChunk> {
+        self.terrain.as_ref()
+    }
+
+    pub fn blocks(&self) -> &[Block; CHUNK_CUBE] {
+        self.chunk.blocks()
+    }
+
+    /// Mutable access to the blocks. Invalidates the whole chunk mesh
     pub fn blocks_mut(&mut self) -> &mut [Block; CHUNK_CUBE] {
         self.status = TerrainStatus::None;
-        &mut self.blocks
-    }
-
-    fn lerp(lhs: f64, rhs: f64, f: f64) -> f64 {
-        // More precise, less performant
-        lhs * (1.0 - f) + (rhs * f)
-        // Less precise, more performant
-        // lhs + f * (rhs - lhs)
-    }
-
-    fn generate_flat(id: ChunkId) -> LogicChunk {
-        const WAVELENGTH: f64 = 10.0;
-
-        prof!("LogicChunk::generate_flat");
-        let perlin = Perlin::new(Perlin::DEFAULT_SEED);
-        let coord = id.to_coord();
-        let mut blocks = [Block::Air; CHUNK_CUBE];
-        let height_map = (0..CHUNK_SIZE)
-            .map(|x| {
-                (0..CHUNK_SIZE)
-                    .map(|y| {
-                        let p = perlin.get([
-                            (x as f64 + coord.x as f64) * 0.1 / WAVELENGTH,
-                            (y as f64 + coord.z as f64) * 0.1 / WAVELENGTH,
-                        ]);
-                        Self::lerp(
-                            (Self::SEA_LEVEL - Self::SEA_LEVEL_BIAS) as f64,
-                            (Self::SEA_LEVEL + Self::SEA_LEVEL_BIAS) as f64,
-                            p,
-                        ) as GlobalUnit
-                    })
-                    .collect::<Vec<_>>()
-            })
+        self.occupancy.set(None);
+        self.chunk.blocks_mut()
+    }
+
+    pub fn occupancy(&self) -> Occupancy {
+        let occupancy = self
+            .occupancy
+            .get()
+            .unwrap_or_else(|| Occupancy::new(self.chunk.blocks()));
+        self.occupancy.set(Some(occupancy));
+
+        occupancy
+    }
+
+    /// Set a single block. Mesh is invalidated only if the edit changes any visible face.
+    ///
+    /// Returns the previous block
+    pub fn set_block(&mut self, pos: BlockCoord, block: Block) -> Block {
+        let previous = self.chunk.get(pos);
+        if previous == block {
+            return previous;
+        }
+
+        // Opacity change affects faces of the neighbors, otherwise only block's own faces change.
+        // Plants are always meshed
+        let visible = culls_differently(previous, block)
+            || previous.cross()
+            || block.cross()
+            || (block.opaque() && self.exposed(pos));
+        self.chunk.set(pos, block);
+        if let Some(mut occupancy) = self.occupancy.get() {
+            occupancy.update(self.chunk.blocks(), pos);
+            self.occupancy.set(Some(occupancy));
+        }
+
+        if visible {
+            self.status = TerrainStatus::None;
+        }
+
+        previous
+    }
+
+    /// Check if block has at least one face which gets into the mesh
+    fn exposed(&self, pos: BlockCoord) -> bool {
+        Direction::ALL
+            .iter()
+            .any(|&dir| pos.on_chunk_edge(dir) || !self.chunk.get(pos.neighbor(dir)).solid())
+    }
+
+    /// Number of blocks of each type (most common first)
+    pub fn histogram(&self) -> Vec<(Block, usize)> {
+        let mut counts = [0; Block::MAX as usize + 1];
+        self.blocks()
+            .iter()
+            .for_each(|block| counts[block.id() as usize] += 1);
+
+        let mut histogram = Block::ALL
+            .into_iter()
+            .map(|block| (block, counts[block.id() as usize]))
+            .filter(|&(_, count)| count > 0)
             .collect::<Vec<_>>();
-        blocks.iter_mut().enumerate().for_each(|(i, block)| {
-            let pos = coord.to_global(&BlockCoord::from(i));
-            let y_height = height_map[(pos.x as usize) % CHUNK_SIZE][(pos.z as usize) % CHUNK_SIZE];
-            *block = match pos.y {
-                y if y == y_height => {
-                    if y > Self::SEA_LEVEL - 20 {
-                        Block::Grass
-                    } else {
-                        Block::Sand
-                    }
-                }
-                y if y < y_height && y > y_height - 11 => Block::Dirt,
-                y if y < y_height - 10 => Block::Stone,
-                y if y > y_height && y < Self::SEA_LEVEL - 20 => Block::Water,
-                _ => Block::Air,
-            };
-        });
+        histogram.sort_by_key(|&(_, count)| Reverse(count));
 
-        LogicChunk::from_blocks(blocks)
+        histogram
     }
 }
 
@@ -271,166 +975,398 @@ impl Default for LogicChunk {
     }
 }
 
-/// Represents chunk mesh on GPU
-pub struct TerrainChunk {
-    pub vertex_buffer: Buffer<Vertex>,
-    pub index_buffer: Buffer<u32>,
+/// Loaded chunks indexed by their ids. Chunks own their meshes, so both are looked up and unloaded
+/// together
+#[derive(Default)]
+pub struct ChunkStore {
+    chunks: HashMap<ChunkId, LogicChunk>,
 }
 
-impl TerrainChunk {
-    pub fn new(device: &Device, mesh: TerrainMesh) -> Self {
-        Self {
-            vertex_buffer: Buffer::new(device, &mesh.vertices, BufferUsages::VERTEX),
-            index_buffer: Buffer::new(device, &mesh.indices, BufferUsages::INDEX),
-        }
+impl ChunkStore {
+    pub fn new() -> Self {
+        Self::default()
     }
-}
 
-////////////////////////////////////////////////////////////////////////////////////////////////////
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
 
-pub struct LoadArea {
-    start: ChunkId,
-    end: ChunkId,
-    current: ChunkId,
-}
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
 
-impl LoadArea {
-    const fn new(start: ChunkId, end: ChunkId) -> Self {
-        Self {
-            start,
-            end,
-            current: start,
+    pub fn capacity(&self) -> usize {
+        self.chunks.capacity()
+    }
+
+    pub fn contains(&self, id: &ChunkId) -> bool {
+        self.chunks.contains_key(id)
+    }
+
+    pub fn get(&self, id: &ChunkId) -> Option<&LogicChunk> {
+        self.chunks.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &ChunkId) -> Option<&mut LogicChunk> {
+        self.chunks.get_mut(id)
+    }
+
+    /// Insert or replace the chunk. Mesh of the replaced chunk is kept until the new one is built
+    pub fn insert(&mut self, id: ChunkId, mut chunk: LogicChunk) {
+        if let Some(old) = self.chunks.remove(&id) {
+            chunk.terrain = old.terrain;
         }
+        self.chunks.insert(id, chunk);
     }
 
-    pub fn new_cube(center: ChunkId, dist: GlobalUnit) -> Self {
-        Self::new(
-            ChunkId::new(center.x - dist, center.y - dist, center.z - dist),
-            ChunkId::new(center.x + dist, center.y + dist, center.z + dist),
-        )
+    /// Remove the chunk. Its mesh must be freed by the caller
+    pub fn remove(&mut self, id: &ChunkId) -> Option<LogicChunk> {
+        self.chunks.remove(id)
     }
 
-    pub fn new_cuboid(center: ChunkId, dist: GlobalUnit) -> Self {
-        Self::new(
-            ChunkId::new(center.x - dist, center.y - dist / 2, center.z - dist),
-            ChunkId::new(center.x + dist, center.y + dist / 2, center.z + dist),
-        )
+    pub fn clear(&mut self) {
+        self.chunks.clear();
     }
 
-    pub fn contains(&self, id: ChunkId) -> bool {
-        !(id.x < self.start.x
-            || id.x > self.end.x
-            || id.y < self.start.y
-            || id.y > self.end.y
-            || id.z < self.start.z
-            || id.z > self.end.z)
+    pub fn shrink_to_fit(&mut self) {
+        self.chunks.shrink_to_fit();
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = ChunkId> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ChunkId, &LogicChunk)> {
+        self.chunks.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&ChunkId, &mut LogicChunk)> {
+        self.chunks.iter_mut()
+    }
+
+    /// Chunks ordered by distance from `center` (closest first)
+    pub fn by_distance(&self, center: ChunkId) -> Vec<(ChunkId, &LogicChunk)> {
+        let mut chunks = self
+            .chunks
+            .iter()
+            .map(|(id, chunk)| (*id, chunk))
+            .collect::<Vec<_>>();
+        chunks.sort_by_key(|(id, _)| {
+            let (x, y, z) = (id.x - center.x, id.y - center.y, id.z - center.z);
+            x * x + y * y + z * z
+        });
+
+        chunks
+    }
+
+    pub fn terrain(&self, id: &ChunkId) -> Option<&TerrainChunk> {
+        self.chunks.get(id).and_then(LogicChunk::terrain)
+    }
+
+    /// Chunks with meshes
+    pub fn meshes(&self) -> impl Iterator<Item = (ChunkId, &TerrainChunk)> {
+        self.chunks
+            .iter()
+            .filter_map(|(id, chunk)| Some((*id, chunk.terrain.as_ref()?)))
+    }
+
+    pub fn meshes_mut(&mut self) -> impl Iterator<Item = &mut TerrainChunk> {
+        self.chunks
+            .values_mut()
+            .filter_map(|chunk| chunk.terrain.as_mut())
+    }
+
+    pub fn mesh_count(&self) -> usize {
+        self.meshes().count()
     }
 }
 
-impl Iterator for LoadArea {
-    type Item = ChunkId;
+/// Represents chunk mesh on GPU
+pub struct TerrainChunk {
+    /// Mesh location in [`ChunkManager::arena`]
+    pub slice: ArenaSlice,
+    /// Number of opaque indices, followed by cutout ones
+    pub cutout: u32,
+    /// Start of fluid indices (after cutout ones)
+    pub fluid: u32,
+    /// Position of the chunk. Mesh vertices are relative to it
+    pub origin: F32x3,
+    /// Grass tufts. Not stored in the arena, as they are drawn instanced
+    pub decorations: Option<Buffer<DecorationInstance>>,
+    /// When the mesh has been uploaded
+    pub built_at: Instant,
+    /// Time spent building the mesh on the CPU
+    pub build_time: Option<Duration>,
+    /// [`ChunkManager::frame`] the mesh has been drawn last time
+    last_drawn: Cell<u64>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current.z > self.end.z {
-            return None;
+impl TerrainChunk {
+    pub fn new(slice: ArenaSlice, cutout: u32, fluid: u32, origin: F32x3) -> Self {
+        Self {
+            slice,
+            cutout,
+            fluid,
+            origin,
+            decorations: None,
+            built_at: Instant::now(),
+            build_time: None,
+            last_drawn: Cell::new(0),
         }
+    }
 
-        let item = self.current;
-        let mut new = self.current;
+    /// Size of the mesh and decoration buffers (bytes)
+    pub fn size(&self) -> u64 {
+        let decorations = self.decorations.as_ref().map_or(0, |buffer| {
+            (buffer.length() * size_of::<DecorationInstance>()) as u64
+        });
+        self.slice.size() + decorations
+    }
 
-        fn clamped_inc(src: &mut GlobalUnit, clamp: GlobalUnit) -> bool {
-            if *src < clamp {
-                *src += 1;
-                false
-            } else {
-                true
-            }
-        }
+    /// Indices of opaque geometry in the arena page
+    pub fn opaque_indices(&self) -> Range<u32> {
+        self.slice.indices.start..self.slice.indices.start + self.cutout
+    }
 
-        if clamped_inc(&mut new.x, self.end.x) {
-            new.x = self.start.x;
-            if clamped_inc(&mut new.y, self.end.y) {
-                new.y = self.start.y;
-                clamped_inc(&mut new.z, self.end.z + 1);
-            }
-        }
+    /// Indices of cutout geometry (plants) in the arena page
+    pub fn cutout_indices(&self) -> Range<u32> {
+        self.slice.indices.start + self.cutout..self.slice.indices.start + self.fluid
+    }
+
+    /// Indices of fluid geometry in the arena page
+    pub fn fluid_indices(&self) -> Range<u32> {
+        self.slice.indices.start + self.fluid..self.slice.indices.end
+    }
 
-        self.current = new;
-        Some(item)
+    pub fn mark_drawn(&self, frame: u64) {
+        self.last_drawn.set(frame);
+    }
+
+    /// [`ChunkManager::frame`] the mesh has been drawn last time
+    pub fn last_drawn(&self) -> u64 {
+        self.last_drawn.get()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use common::coord::ChunkId;
+    use common::{
+        block::Block,
+        chunk::Chunk,
+        coord::{BlockCoord, ChunkId, GlobalCoord, CHUNK_CUBE},
+        direction::Direction,
+        event::WorldEvent,
+        geometry::Ray,
+    };
+
+    use crate::{render::arena::ArenaSlice, types::F32x3};
+
+    use super::{
+        eviction_order, load_priority, vertical_extent, within_budget, ChunkManager, ChunkStore,
+        LogicChunk, TerrainChunk, TerrainStatus,
+    };
 
-    use super::LoadArea;
+    #[test]
+    fn raycast_loaded_blocks() {
+        let mut manager = ChunkManager::new();
+        manager.chunks.insert(ChunkId::ZERO, LogicChunk::new());
+        manager.set_block(GlobalCoord::new(5, 3, 2), Block::Stone);
+
+        let hit = manager
+            .raycast(Ray::new(F32x3::new(0.5, 3.5, 2.5), F32x3::X), 16.0)
+            .unwrap();
+        assert_eq!(hit.pos, GlobalCoord::new(5, 3, 2));
+        assert_eq!(hit.face, Some(Direction::Left));
+        assert!((hit.distance - 4.5).abs() < 1e-5);
+
+        assert!(manager
+            .raycast(Ray::new(F32x3::new(0.5, 3.5, 2.5), F32x3::X), 4.0)
+            .is_none());
+        // Unloaded chunks are passed through
+        assert!(manager
+            .raycast(Ray::new(F32x3::new(0.5, 3.5, 2.5), -F32x3::X), 16.0)
+            .is_none());
+    }
+
+    #[test]
+    fn hidden_edits_skip_remesh() {
+        let mut chunk = LogicChunk::from_chunk(Chunk::from_blocks([Block::Stone; CHUNK_CUBE]));
+        chunk.status = TerrainStatus::Built;
+
+        // Enclosed block and no-op edits
+        chunk.set_block(BlockCoord::new(8, 8, 8), Block::Dirt);
+        chunk.set_block(BlockCoord::new(0, 0, 0), Block::Stone);
+        assert!(matches!(chunk.status(), TerrainStatus::Built));
+
+        // Face on the chunk edge changes color
+        chunk.set_block(BlockCoord::new(0, 8, 8), Block::Dirt);
+        assert!(matches!(chunk.status(), TerrainStatus::None));
+
+        // Opening a hole exposes neighbors
+        chunk.status = TerrainStatus::Built;
+        chunk.set_block(BlockCoord::new(4, 4, 4), Block::Air);
+        assert!(matches!(chunk.status(), TerrainStatus::None));
+    }
 
     #[test]
-    fn load_area_iter_cube() {
-        let loaded_area = LoadArea::new_cube(ChunkId::ZERO, 1).collect::<Vec<_>>();
+    fn edge_edits_remesh_neighbors() {
+        let mut manager = ChunkManager::new();
+        for id in [ChunkId::ZERO, ChunkId::new(-1, 0, 0), ChunkId::new(1, 0, 0)] {
+            let mut chunk = LogicChunk::new();
+            chunk.status = TerrainStatus::Built;
+            manager.chunks.insert(id, chunk);
+        }
+
+        manager.set_block(GlobalCoord::new(0, 8, 8), Block::Stone);
+        let status = |manager: &ChunkManager, x| {
+            manager.chunks.get(&ChunkId::new(x, 0, 0)).unwrap().status()
+        };
+        assert!(matches!(status(&manager, -1), TerrainStatus::None));
+        assert!(matches!(status(&manager, 1), TerrainStatus::Built));
 
+        // No-op edits aren't published
+        manager.set_block(GlobalCoord::new(0, 8, 8), Block::Stone);
+        manager.apply_delta(
+            ChunkId::new(-1, 0, 0),
+            &[(BlockCoord::new(15, 0, 2), Block::Sand)],
+        );
         assert_eq!(
-            loaded_area,
+            manager.take_events(),
             [
-                ChunkId::new(-1, -1, -1),
-                ChunkId::new(0, -1, -1),
-                ChunkId::new(1, -1, -1),
-                ChunkId::new(-1, 0, -1),
-                ChunkId::new(0, 0, -1),
-                ChunkId::new(1, 0, -1),
-                ChunkId::new(-1, 1, -1),
-                ChunkId::new(0, 1, -1),
-                ChunkId::new(1, 1, -1),
-                ChunkId::new(-1, -1, 0),
-                ChunkId::new(0, -1, 0),
-                ChunkId::new(1, -1, 0),
-                ChunkId::new(-1, 0, 0),
-                ChunkId::ZERO,
-                ChunkId::new(1, 0, 0),
-                ChunkId::new(-1, 1, 0),
-                ChunkId::new(0, 1, 0),
-                ChunkId::new(1, 1, 0),
-                ChunkId::new(-1, -1, 1),
-                ChunkId::new(0, -1, 1),
-                ChunkId::new(1, -1, 1),
-                ChunkId::new(-1, 0, 1),
-                ChunkId::new(0, 0, 1),
-                ChunkId::new(1, 0, 1),
-                ChunkId::new(-1, 1, 1),
-                ChunkId::new(0, 1, 1),
-                ChunkId::new(1, 1, 1),
+                WorldEvent::BlockChanged {
+                    pos: GlobalCoord::new(0, 8, 8),
+                    before: Block::Air,
+                    after: Block::Stone,
+                },
+                WorldEvent::BlockChanged {
+                    pos: GlobalCoord::new(-1, 0, 2),
+                    before: Block::Air,
+                    after: Block::Sand,
+                },
             ]
         );
     }
 
     #[test]
-    fn load_area_iter_cuboid() {
-        let loaded_area = LoadArea::new_cuboid(ChunkId::ZERO, 1).collect::<Vec<_>>();
+    fn view_direction_priority() {
+        let (eye, forward) = (F32x3::new(8.0, 8.0, 8.0), F32x3::Z);
+        let priority = |x, z| load_priority(ChunkId::new(x, 0, z), eye, forward);
+
+        assert!(priority(0, 0) < priority(0, 1));
+        // Chunk in front is loaded before closer chunks on the sides and behind
+        assert!(priority(0, 3) < priority(2, 0));
+        assert!(priority(0, 3) < priority(0, -2));
+        assert!(priority(1, 0) < priority(-1, -1));
+    }
+
+    #[test]
+    fn least_recently_drawn_evicted() {
+        let id = |x| ChunkId::new(x, 0, 0);
+        let chunks = vec![
+            (id(0), 40, 9),
+            (id(1), 30, 2),
+            (id(2), 20, 5),
+            (id(3), 10, 1),
+        ];
 
+        assert_eq!(eviction_order(chunks.clone(), 100, 100, 9), []);
+        assert_eq!(eviction_order(chunks.clone(), 100, 65, 9), [id(3), id(1)]);
+        // Recently drawn chunks are kept even if the budget can't be met
+        assert_eq!(eviction_order(chunks, 100, 10, 9), [id(3), id(1), id(2)]);
+    }
+
+    #[test]
+    fn uploads_within_budget() {
+        let sizes = [40, 30, 20, 10];
+
+        assert_eq!(within_budget(sizes.into_iter(), 100), 4);
+        assert_eq!(within_budget(sizes.into_iter(), 75), 2);
+        // Oversized mesh goes alone
+        assert_eq!(within_budget(sizes.into_iter(), 10), 1);
+        assert_eq!(within_budget(std::iter::empty(), 10), 0);
+    }
+
+    #[test]
+    fn buried_chunks() {
+        let mut manager = ChunkManager::new();
+        let stone = || LogicChunk::from_chunk(Chunk::from_blocks([Block::Stone; CHUNK_CUBE]));
+        manager.chunks.insert(ChunkId::ZERO, stone());
+        for dir in Direction::ALL {
+            manager.chunks.insert(ChunkId::ZERO.neighbor(dir), stone());
+        }
+        assert!(manager.buried(ChunkId::ZERO));
+        assert!(!manager.buried(ChunkId::new(1, 0, 0)));
+
+        // Hole in the side touching the chunk
+        manager.set_block(GlobalCoord::new(-1, 3, 3), Block::Air);
+        assert!(!manager.buried(ChunkId::ZERO));
+        manager.set_block(GlobalCoord::new(-1, 3, 3), Block::Stone);
+        assert!(manager.buried(ChunkId::ZERO));
+
+        // Missing neighbor
+        manager.chunks.remove(&ChunkId::new(0, 1, 0));
+        assert!(!manager.buried(ChunkId::ZERO));
+    }
+
+    #[test]
+    fn chunk_store() {
+        let mut store = ChunkStore::new();
+        for x in [4, -1, 2] {
+            store.insert(ChunkId::new(x, 0, 0), LogicChunk::new());
+        }
         assert_eq!(
-            loaded_area,
-            [
-                ChunkId::new(-1, 0, -1),
-                ChunkId::new(0, 0, -1),
-                ChunkId::new(1, 0, -1),
-                ChunkId::new(-1, 0, 0),
-                ChunkId::ZERO,
-                ChunkId::new(1, 0, 0),
-                ChunkId::new(-1, 0, 1),
-                ChunkId::new(0, 0, 1),
-                ChunkId::new(1, 0, 1),
-            ]
+            store
+                .by_distance(ChunkId::new(1, 0, 0))
+                .into_iter()
+                .map(|(id, _)| id.x)
+                .collect::<Vec<_>>(),
+            [2, -1, 4]
         );
-    }
 
+        // Mesh is kept until the replaced chunk is remeshed
+        let slice = ArenaSlice {
+            page: 0,
+            vertices: 0..4,
+            indices: 0..6,
+        };
+        store.get_mut(&ChunkId::new(2, 0, 0)).unwrap().terrain =
+            Some(TerrainChunk::new(slice, 6, 6, F32x3::ZERO));
+        store.insert(ChunkId::new(2, 0, 0), LogicChunk::new());
+        assert!(store.terrain(&ChunkId::new(2, 0, 0)).is_some());
+        assert_eq!(store.mesh_count(), 1);
+        assert_eq!(store.meshes().next().unwrap().0, ChunkId::new(2, 0, 0));
+
+        assert!(store.remove(&ChunkId::new(2, 0, 0)).is_some());
+        assert_eq!((store.len(), store.mesh_count()), (2, 0));
+    }
     #[test]
-    fn load_area_contains() {
-        let load_area = LoadArea::new_cube(ChunkId::ZERO, 2);
+    fn vertical_range_follows_surface() {
+        // Symmetric without bias or known surface
+        assert_eq!(vertical_extent(8, Some(6), 0.0), (8, 8));
+        assert_eq!(vertical_extent(8, None, 1.0), (8, 8));
+        // High above the surface
+        assert_eq!(vertical_extent(8, Some(6), 0.5), (11, 5));
+        assert_eq!(vertical_extent(8, Some(100), 1.0), (15, 1));
+        // Deep underground
+        assert_eq!(vertical_extent(8, Some(-100), 1.0), (1, 15));
+        assert_eq!(vertical_extent(0, Some(5), 1.0), (0, 0));
+
+        let mut manager = ChunkManager::new();
+        let center = ChunkId::new(0, 10, 0);
+        assert_eq!(manager.surface_altitude(center, 8), None);
+
+        for y in 6..=12 {
+            manager
+                .chunks
+                .insert(ChunkId::new(0, y, 0), LogicChunk::new());
+        }
+        // Only sky is loaded
+        assert_eq!(manager.surface_altitude(center, 8), Some(5));
 
-        assert!(load_area.contains(ChunkId::ZERO));
-        assert!(load_area.contains(ChunkId::new(1, 1, 1)));
-        assert!(!load_area.contains(ChunkId::new(3, 3, 3)));
-        assert!(!load_area.contains(ChunkId::new(3, 32, 12)));
+        let ground = LogicChunk::from_chunk(Chunk::from_blocks([Block::Stone; CHUNK_CUBE]));
+        manager.chunks.insert(ChunkId::new(0, 6, 0), ground);
+        assert_eq!(manager.surface_altitude(center, 8), Some(4));
+        // Surface out of range is assumed below the lowest empty chunk
+        assert_eq!(manager.surface_altitude(center, 1), Some(2));
     }
 }
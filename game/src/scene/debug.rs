@@ -0,0 +1,450 @@
+use std::f32::consts::TAU;
+
+use common::geometry::Aabb;
+use tracing::warn;
+use wgpu::BufferUsages;
+
+use crate::{
+    render::{buffer::DynamicBuffer, primitives::line::DebugLineVertex, renderer::Renderer},
+    types::{F32x3, Mat4},
+};
+
+use super::chunk::TerrainStatus;
+
+/// Colors of debug visualizations. Alternatives stay readable with color vision deficiencies
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub enum DebugPalette {
+    /// Green to red heatmap
+    #[default]
+    Default,
+    /// Okabe-Ito colors and viridis heatmap, for deuteranopia and protanopia
+    RedGreen,
+    /// Red and teal colors and magma heatmap, for tritanopia
+    BlueYellow,
+    /// Colors differ only in lightness
+    Grayscale,
+}
+
+impl DebugPalette {
+    pub const ALL: [Self; 4] = [
+        Self::Default,
+        Self::RedGreen,
+        Self::BlueYellow,
+        Self::Grayscale,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Default => "Default",
+            Self::RedGreen => "Red-green safe",
+            Self::BlueYellow => "Blue-yellow safe",
+            Self::Grayscale => "Grayscale",
+        }
+    }
+
+    /// Name in the settings file
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::RedGreen => "red_green",
+            Self::BlueYellow => "blue_yellow",
+            Self::Grayscale => "grayscale",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|palette| palette.key() == key)
+    }
+
+    /// Heatmap color of `t` in `0..=1`
+    pub fn heat(&self, t: f32) -> [u8; 4] {
+        let stops: &[[u8; 3]] = match self {
+            Self::Default => &[[0, 255, 0], [255, 255, 0], [255, 0, 0]],
+            Self::RedGreen => &[[68, 1, 84], [33, 145, 140], [253, 231, 37]],
+            Self::BlueYellow => &[[0, 0, 4], [183, 55, 121], [252, 253, 191]],
+            Self::Grayscale => &[[64, 64, 64], [255, 255, 255]],
+        };
+
+        let t = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let i = (t as usize).min(stops.len() - 2);
+        let [from, to] =
+            [stops[i], stops[i + 1]].map(|[r, g, b]| F32x3::new(r as f32, g as f32, b as f32));
+        let color = from.lerp(to, t - i as f32);
+
+        [color.x as u8, color.y as u8, color.z as u8, 255]
+    }
+
+    /// Heatmap color of chunks with unknown metric
+    pub fn unknown(&self) -> [u8; 4] {
+        match self {
+            Self::Grayscale => [20, 20, 20, 255],
+            _ => [128, 128, 128, 255],
+        }
+    }
+
+    /// Colors of the near plane, the far plane and the edges of frustum outlines
+    pub fn frustum(&self) -> [[u8; 4]; 3] {
+        match self {
+            Self::Default => [[255, 64, 64, 255], [64, 128, 255, 255], [255, 220, 64, 255]],
+            Self::RedGreen => [[213, 94, 0, 255], [0, 114, 178, 255], [240, 228, 66, 255]],
+            Self::BlueYellow => [[220, 50, 32, 255], [0, 150, 150, 255], [240, 240, 240, 255]],
+            Self::Grayscale => [
+                [255, 255, 255, 255],
+                [90, 90, 90, 255],
+                [170, 170, 170, 255],
+            ],
+        }
+    }
+
+    /// Color of chunk labels
+    pub fn chunk_status(&self, status: TerrainStatus) -> [u8; 3] {
+        let colors = match self {
+            Self::Default => [
+                [160, 160, 160],
+                [255, 220, 64],
+                [96, 220, 96],
+                [255, 96, 96],
+            ],
+            Self::RedGreen => [
+                [160, 160, 160],
+                [230, 159, 0],
+                [86, 180, 233],
+                [204, 121, 167],
+            ],
+            Self::BlueYellow => [
+                [110, 110, 110],
+                [220, 50, 32],
+                [0, 170, 170],
+                [250, 250, 250],
+            ],
+            Self::Grayscale => [
+                [80, 80, 80],
+                [190, 190, 190],
+                [255, 255, 255],
+                [130, 130, 130],
+            ],
+        };
+
+        match status {
+            TerrainStatus::None => colors[0],
+            TerrainStatus::Pending => colors[1],
+            TerrainStatus::Built => colors[2],
+            TerrainStatus::Evicted => colors[3],
+        }
+    }
+
+    /// Color of error messages in the debug overlay
+    pub fn error(&self) -> [u8; 3] {
+        match self {
+            Self::Default => [255, 0, 0],
+            Self::RedGreen => [213, 94, 0],
+            Self::BlueYellow => [220, 50, 32],
+            Self::Grayscale => [255, 255, 255],
+        }
+    }
+}
+
+/// Debug line geometry. Rebuilt every frame
+pub struct DebugLines {
+    vertices: Vec<DebugLineVertex>,
+    buffer: Option<DynamicBuffer<DebugLineVertex>>,
+    vertex_count: u32,
+}
+
+impl DebugLines {
+    const MIN_CAPACITY: usize = 256;
+
+    pub fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            buffer: None,
+            vertex_count: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn line(&mut self, from: F32x3, to: F32x3, color: [u8; 4]) {
+        self.vertices.push(DebugLineVertex::new(from, color));
+        self.vertices.push(DebugLineVertex::new(to, color));
+    }
+
+    /// Outline of an axis-aligned box
+    pub fn cuboid(&mut self, min: F32x3, max: F32x3, color: [u8; 4]) {
+        for (from, to) in cuboid_edges(min, max) {
+            self.line(from, to, color);
+        }
+    }
+
+    /// Three circles around the axes
+    pub fn sphere(&mut self, center: F32x3, radius: f32, color: [u8; 4]) {
+        const SEGMENTS: usize = 24;
+
+        let point = |axis: usize, i: usize| {
+            let (sin, cos) = (i as f32 / SEGMENTS as f32 * TAU).sin_cos();
+            center
+                + radius
+                    * match axis {
+                        0 => F32x3::new(0.0, sin, cos),
+                        1 => F32x3::new(sin, 0.0, cos),
+                        _ => F32x3::new(sin, cos, 0.0),
+                    }
+        };
+
+        for axis in 0..3 {
+            for i in 0..SEGMENTS {
+                self.line(point(axis, i), point(axis, i + 1), color);
+            }
+        }
+    }
+
+    /// Outline of the frustum of `view_proj` (projection * view) matrix
+    pub fn frustum(&mut self, view_proj: Mat4, palette: DebugPalette) {
+        let [near, far, edge] = palette.frustum();
+        let inverse = view_proj.inverse();
+        // Depth range is 0..1
+        let corners = [0.0, 1.0].map(|z| {
+            [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .map(|(x, y)| inverse.project_point3(F32x3::new(x, y, z)))
+        });
+
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.line(corners[0][i], corners[0][next], near);
+            self.line(corners[1][i], corners[1][next], far);
+            self.line(corners[0][i], corners[1][i], edge);
+        }
+    }
+
+    /// Upload lines. Buffer grows when there is not enough space
+    pub fn upload(&mut self, renderer: &Renderer) {
+        self.vertex_count = self.vertices.len() as u32;
+
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let buffer = self.buffer.get_or_insert_with(|| {
+            DynamicBuffer::new(&renderer.device, Self::MIN_CAPACITY, BufferUsages::VERTEX)
+        });
+        buffer.ensure_capacity(&renderer.device, self.vertices.len());
+
+        if let Err(err) = renderer.update_dynamic_buffer(buffer, &self.vertices) {
+            warn!(%err, "Failed to upload debug lines");
+        }
+    }
+
+    /// Drop vertex buffer. It's created again on the next upload
+    pub fn release_buffer(&mut self) {
+        self.buffer = None;
+        self.vertex_count = 0;
+    }
+
+    /// Vertex buffer and number of used vertices
+    pub fn vertices(&self) -> Option<(&DynamicBuffer<DebugLineVertex>, u32)> {
+        self.buffer
+            .as_ref()
+            .filter(|_| self.vertex_count > 0)
+            .map(|buffer| (buffer, self.vertex_count))
+    }
+}
+
+impl Default for DebugLines {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 12 edges of an axis-aligned box
+pub fn cuboid_edges(min: F32x3, max: F32x3) -> impl Iterator<Item = (F32x3, F32x3)> {
+    let corner = move |i: usize| {
+        F32x3::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        )
+    };
+
+    // Edges connect corners differing in a single axis
+    (0..8).flat_map(move |i| {
+        [1, 2, 4]
+            .into_iter()
+            .filter(move |axis| i & axis == 0)
+            .map(move |axis| (corner(i), corner(i | axis)))
+    })
+}
+
+/// Debug shape kept for several frames
+#[derive(Clone, Copy, Debug)]
+enum Gizmo {
+    Line(F32x3, F32x3),
+    Aabb(Aabb),
+    Sphere(F32x3, f32),
+}
+
+/// Retained debug shapes. Any system can add a shape for a number of frames instead of building
+/// lines every frame.
+///
+/// Shapes are added to [`DebugLines`] when they are collected
+pub struct DebugGizmos {
+    /// Shapes with colors and frames left
+    gizmos: Vec<(Gizmo, [u8; 4], u32)>,
+}
+
+impl DebugGizmos {
+    /// Shapes above this are dropped, so a system adding a shape every tick can't exhaust memory
+    pub const MAX_GIZMOS: usize = 1 << 14;
+
+    pub const fn new() -> Self {
+        Self { gizmos: Vec::new() }
+    }
+
+    pub fn draw_line(&mut self, from: F32x3, to: F32x3, color: [u8; 4], frames: u32) {
+        self.add(Gizmo::Line(from, to), color, frames);
+    }
+
+    pub fn draw_aabb(&mut self, aabb: Aabb, color: [u8; 4], frames: u32) {
+        self.add(Gizmo::Aabb(aabb), color, frames);
+    }
+
+    pub fn draw_sphere(&mut self, center: F32x3, radius: f32, color: [u8; 4], frames: u32) {
+        self.add(Gizmo::Sphere(center, radius), color, frames);
+    }
+
+    fn add(&mut self, gizmo: Gizmo, color: [u8; 4], frames: u32) {
+        if frames > 0 && self.gizmos.len() < Self::MAX_GIZMOS {
+            self.gizmos.push((gizmo, color, frames));
+        }
+    }
+
+    /// Number of living shapes
+    pub fn len(&self) -> usize {
+        self.gizmos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gizmos.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.gizmos.clear();
+    }
+
+    /// Add shapes to `lines` for a frame. Shapes which have been drawn for their frames are
+    /// removed
+    pub fn collect(&mut self, lines: &mut DebugLines) {
+        for (gizmo, color, frames) in &mut self.gizmos {
+            match *gizmo {
+                Gizmo::Line(from, to) => lines.line(from, to, *color),
+                Gizmo::Aabb(aabb) => lines.cuboid(aabb.min, aabb.max, *color),
+                Gizmo::Sphere(center, radius) => lines.sphere(center, radius, *color),
+            }
+            *frames -= 1;
+        }
+
+        self.gizmos.retain(|&(_, _, frames)| frames > 0);
+    }
+}
+
+impl Default for DebugGizmos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::geometry::Aabb;
+
+    use crate::{
+        scene::chunk::TerrainStatus,
+        types::{F32x3, Mat4},
+    };
+
+    use super::{DebugGizmos, DebugLines, DebugPalette};
+
+    #[test]
+    fn frustum_outline() {
+        let mut lines = DebugLines::new();
+        lines.frustum(Mat4::IDENTITY, DebugPalette::Default);
+
+        assert_eq!(lines.vertices.len(), 24);
+        assert!(lines.vertices.iter().all(|vertex| {
+            vertex.pos.x.abs() == 1.0
+                && vertex.pos.y.abs() == 1.0
+                && (vertex.pos.z == 0.0 || vertex.pos.z == 1.0)
+        }));
+        assert_eq!(lines.vertices[0].pos, F32x3::new(-1.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn cuboid_outline() {
+        let mut lines = DebugLines::new();
+        lines.cuboid(F32x3::ZERO, F32x3::ONE, [255; 4]);
+
+        assert_eq!(lines.vertices.len(), 24);
+        // Every edge is axis-aligned and has unit length
+        assert!(lines.vertices.chunks(2).all(|edge| {
+            let delta = (edge[1].pos - edge[0].pos).abs();
+            delta.x + delta.y + delta.z == 1.0 && delta.max_element() == 1.0
+        }));
+    }
+
+    #[test]
+    fn gizmos_expire() {
+        let mut gizmos = DebugGizmos::new();
+        gizmos.draw_line(F32x3::ZERO, F32x3::X, [255; 4], 1);
+        gizmos.draw_aabb(Aabb::new(F32x3::ZERO, F32x3::ONE), [255; 4], 2);
+        gizmos.draw_sphere(F32x3::ZERO, 2.0, [255; 4], 3);
+        gizmos.draw_line(F32x3::ZERO, F32x3::Y, [255; 4], 0);
+        assert_eq!(gizmos.len(), 3);
+
+        let frame = |gizmos: &mut DebugGizmos| {
+            let mut lines = DebugLines::new();
+            gizmos.collect(&mut lines);
+            lines.vertices
+        };
+
+        let vertices = frame(&mut gizmos);
+        // Line, 12 box edges and 3 circles of 24 segments
+        assert_eq!(vertices.len(), (1 + 12 + 3 * 24) * 2);
+        assert!(vertices[26..]
+            .iter()
+            .all(|vertex| (vertex.pos.length() - 2.0).abs() < 1e-5));
+        assert_eq!(gizmos.len(), 2);
+        assert_eq!(frame(&mut gizmos).len(), (12 + 3 * 24) * 2);
+        assert_eq!(frame(&mut gizmos).len(), 3 * 24 * 2);
+        assert!(gizmos.is_empty());
+    }
+
+    #[test]
+    fn palettes_distinguish_statuses() {
+        let statuses = [
+            TerrainStatus::None,
+            TerrainStatus::Pending,
+            TerrainStatus::Built,
+            TerrainStatus::Evicted,
+        ];
+
+        for palette in DebugPalette::ALL {
+            assert_eq!(DebugPalette::from_key(palette.key()), Some(palette));
+
+            let colors = statuses.map(|status| palette.chunk_status(status));
+            for (i, color) in colors.iter().enumerate() {
+                assert!(!colors[i + 1..].contains(color), "{palette:?}");
+            }
+            assert_ne!(palette.heat(0.0), palette.heat(1.0));
+        }
+
+        assert_eq!(
+            DebugPalette::Grayscale.heat(7.0),
+            DebugPalette::Grayscale.heat(1.0)
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::{HashMap, VecDeque};
+
+use common::{
+    block::Block,
+    coord::GlobalCoord,
+    net::protocol::{ClientMsg, EditId},
+};
+use tracing::debug;
+
+use crate::net::NetClient;
+
+use super::chunk::ChunkManager;
+
+/// Block edit applied locally and waiting for the server verdict
+#[derive(Clone, Copy, Debug)]
+pub struct PendingEdit {
+    pub pos: GlobalCoord,
+    /// Block before the edit
+    pub previous: Block,
+}
+
+/// Block change recorded in the edit history
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct BlockChange {
+    pub pos: GlobalCoord,
+    pub before: Block,
+    pub after: Block,
+}
+
+/// Undo and redo stacks of the block edits made during the session.
+///
+/// Changes made between [`EditHistory::begin`] and [`EditHistory::end`] (e.g. a fill) are undone
+/// as a single action
+#[derive(Default, Debug)]
+pub struct EditHistory {
+    /// Oldest actions first
+    undo: VecDeque<Vec<BlockChange>>,
+    redo: Vec<Vec<BlockChange>>,
+    /// Action being recorded
+    group: Option<Vec<BlockChange>>,
+}
+
+impl EditHistory {
+    /// Oldest actions are forgotten beyond this
+    pub const MAX_ACTIONS: usize = 64;
+
+    /// Start grouping changes into a single action
+    pub fn begin(&mut self) {
+        self.group.get_or_insert_with(Vec::new);
+    }
+
+    /// Finish the action started by `begin`. Empty actions are dropped
+    pub fn end(&mut self) {
+        if let Some(group) = self.group.take().filter(|group| !group.is_empty()) {
+            self.push(group);
+        }
+    }
+
+    /// Record new change. Redo stack is cleared
+    pub fn record(&mut self, change: BlockChange) {
+        self.redo.clear();
+        match &mut self.group {
+            Some(group) => group.push(change),
+            None => self.push(vec![change]),
+        }
+    }
+
+    fn push(&mut self, action: Vec<BlockChange>) {
+        if self.undo.len() == Self::MAX_ACTIONS {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(action);
+    }
+
+    /// Number of actions that can be undone
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Number of actions that can be redone
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+    }
+}
+
+/// Routes block edits through the server when connected.
+///
+/// Edits are predicted (applied locally right away) and rolled back if the server rejects them.
+/// Accepted and predicted edits are recorded in the [`EditHistory`]
+#[derive(Default)]
+pub struct BlockEdits {
+    next_id: EditId,
+    pending: HashMap<EditId, PendingEdit>,
+    history: EditHistory,
+}
+
+impl BlockEdits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Place `block` at `pos` (`Block::Air` to break). Returns `false` if chunk isn't loaded or
+    /// `pos` is out of the world bounds
+    pub fn edit(
+        &mut self,
+        chunk_manager: &mut ChunkManager,
+        net: Option<&NetClient>,
+        pos: GlobalCoord,
+        block: Block,
+    ) -> bool {
+        match self.apply(chunk_manager, net, pos, block) {
+            Some(previous) => {
+                if previous != block {
+                    self.history.record(BlockChange {
+                        pos,
+                        before: previous,
+                        after: block,
+                    });
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Revert the last action. Blocks changed since then (e.g. by other players or rejected by the
+    /// server) are left as is. Returns number of restored blocks or `None` if history is empty
+    pub fn undo(
+        &mut self,
+        chunk_manager: &mut ChunkManager,
+        net: Option<&NetClient>,
+    ) -> Option<usize> {
+        self.history.end();
+        let action = self.history.undo.pop_back()?;
+
+        let restored = action
+            .iter()
+            .rev()
+            .filter(|change| {
+                self.restore(chunk_manager, net, change.pos, change.after, change.before)
+            })
+            .count();
+        self.history.redo.push(action);
+
+        Some(restored)
+    }
+
+    /// Repeat the last undone action. Returns number of restored blocks or `None` if there is
+    /// nothing to redo
+    pub fn redo(
+        &mut self,
+        chunk_manager: &mut ChunkManager,
+        net: Option<&NetClient>,
+    ) -> Option<usize> {
+        self.history.end();
+        let action = self.history.redo.pop()?;
+
+        let restored = action
+            .iter()
+            .filter(|change| {
+                self.restore(chunk_manager, net, change.pos, change.before, change.after)
+            })
+            .count();
+        self.history.push(action);
+
+        Some(restored)
+    }
+
+    /// Replace `from` with `to` without recording the history
+    fn restore(
+        &mut self,
+        chunk_manager: &mut ChunkManager,
+        net: Option<&NetClient>,
+        pos: GlobalCoord,
+        from: Block,
+        to: Block,
+    ) -> bool {
+        chunk_manager.block(pos) == Some(from) && self.apply(chunk_manager, net, pos, to).is_some()
+    }
+
+    /// Predict the edit and send it to the server. Returns the previous block
+    fn apply(
+        &mut self,
+        chunk_manager: &mut ChunkManager,
+        net: Option<&NetClient>,
+        pos: GlobalCoord,
+        block: Block,
+    ) -> Option<Block> {
+        if !chunk_manager.bounds.contains(pos) {
+            return None;
+        }
+        let previous = chunk_manager.block(pos)?;
+
+        chunk_manager.set_block(pos, block);
+
+        if let Some(net) = net {
+            let id = self.next_id;
+            self.next_id = self.next_id.wrapping_add(1);
+
+            self.pending.insert(id, PendingEdit { pos, previous });
+            net.send(ClientMsg::BlockEdit { id, pos, block });
+        }
+
+        Some(previous)
+    }
+
+    /// Handle server verdict for the edit
+    pub fn acknowledge(
+        &mut self,
+        chunk_manager: &mut ChunkManager,
+        id: EditId,
+        accepted: bool,
+        pos: GlobalCoord,
+        block: Block,
+    ) {
+        if self.pending.remove(&id).is_some() && !accepted {
+            debug!(id, ?pos, "Block edit rejected. Rolling back");
+            // Server sends the actual block, so newer edits from other players are preserved
+            chunk_manager.set_block(pos, block);
+        }
+    }
+
+    /// Number of edits waiting for the server verdict
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn history(&self) -> &EditHistory {
+        &self.history
+    }
+
+    /// Group following edits into a single undo action until [`BlockEdits::end_action`]
+    pub fn begin_action(&mut self) {
+        self.history.begin();
+    }
+
+    pub fn end_action(&mut self) {
+        self.history.end();
+    }
+
+    /// Forget pending edits and the history (e.g. when the world is replaced)
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.history.clear();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        block::Block,
+        coord::{ChunkId, GlobalCoord},
+    };
+
+    use crate::scene::chunk::{ChunkManager, LogicChunk};
+
+    use super::{BlockEdits, EditHistory};
+
+    fn manager() -> ChunkManager {
+        let mut manager = ChunkManager::new();
+        manager.chunks.insert(ChunkId::ZERO, LogicChunk::new());
+        manager
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut manager = manager();
+        let mut edits = BlockEdits::new();
+        let (a, b) = (GlobalCoord::new(1, 2, 3), GlobalCoord::new(4, 5, 6));
+
+        edits.edit(&mut manager, None, a, Block::Stone);
+        edits.begin_action();
+        edits.edit(&mut manager, None, a, Block::Dirt);
+        edits.edit(&mut manager, None, b, Block::Dirt);
+        edits.end_action();
+        assert_eq!(edits.history().undo_len(), 2);
+
+        // Grouped edits are undone together
+        assert_eq!(edits.undo(&mut manager, None), Some(2));
+        assert_eq!(manager.block(a), Some(Block::Stone));
+        assert_eq!(manager.block(b), Some(Block::Air));
+
+        assert_eq!(edits.redo(&mut manager, None), Some(2));
+        assert_eq!(manager.block(a), Some(Block::Dirt));
+
+        assert_eq!(edits.undo(&mut manager, None), Some(2));
+        assert_eq!(edits.undo(&mut manager, None), Some(1));
+        assert_eq!(manager.block(a), Some(Block::Air));
+        assert_eq!(edits.undo(&mut manager, None), None);
+
+        // New edit drops undone actions
+        edits.edit(&mut manager, None, b, Block::Sand);
+        assert_eq!(edits.history().redo_len(), 0);
+        assert_eq!(edits.redo(&mut manager, None), None);
+    }
+
+    #[test]
+    fn undo_skips_changed_blocks() {
+        let mut manager = manager();
+        let mut edits = BlockEdits::new();
+        let pos = GlobalCoord::new(8, 8, 8);
+
+        edits.edit(&mut manager, None, pos, Block::Stone);
+        // Edited by someone else
+        manager.set_block(pos, Block::Sand);
+
+        assert_eq!(edits.undo(&mut manager, None), Some(0));
+        assert_eq!(manager.block(pos), Some(Block::Sand));
+    }
+
+    #[test]
+    fn history_limit() {
+        let mut edits = BlockEdits::new();
+        let mut manager = manager();
+
+        for i in 0..EditHistory::MAX_ACTIONS + 4 {
+            let block = if i % 2 == 0 {
+                Block::Stone
+            } else {
+                Block::Dirt
+            };
+            edits.edit(&mut manager, None, GlobalCoord::ZERO, block);
+        }
+        assert_eq!(edits.history().undo_len(), EditHistory::MAX_ACTIONS);
+    }
+}
@@ -0,0 +1,101 @@
+use common::block::Block;
+
+use crate::{render::pipelines::post::PostLocals, types::F32x3};
+
+/// Camera state effects drawn over the scene by the post pass
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ScreenEffects {
+    /// Liquid the camera is in
+    pub underwater: Option<Block>,
+    /// Screen fades to black while set
+    pub dead: bool,
+    /// Damage vignette intensity (0..1)
+    damage: f32,
+    /// Fade to black progress (0..1)
+    fade: f32,
+}
+
+impl ScreenEffects {
+    /// How much the liquid color tints the scene
+    pub const UNDERWATER_TINT: f32 = 0.6;
+    /// Offset of distorted texture coordinates
+    pub const UNDERWATER_DISTORTION: f32 = 0.004;
+    /// Time for the damage vignette to disappear (seconds)
+    pub const DAMAGE_TIME: f32 = 0.6;
+    /// Time for the screen to become black after death (seconds)
+    pub const FADE_TIME: f32 = 2.0;
+
+    /// Flash damage vignette
+    pub fn hurt(&mut self) {
+        self.damage = 1.0;
+    }
+
+    pub fn damage(&self) -> f32 {
+        self.damage
+    }
+
+    pub fn fade(&self) -> f32 {
+        self.fade
+    }
+
+    /// Animate effects by `dt` seconds. Fade is reset as soon as the player is alive
+    pub fn advance(&mut self, dt: f32) {
+        self.damage = (self.damage - dt / Self::DAMAGE_TIME).max(0.0);
+        self.fade = if self.dead {
+            (self.fade + dt / Self::FADE_TIME).min(1.0)
+        } else {
+            0.0
+        };
+    }
+
+    pub fn locals(&self) -> PostLocals {
+        let (tint, distortion) = match self.underwater {
+            Some(liquid) => (
+                F32x3::ONE.lerp(liquid.color(), Self::UNDERWATER_TINT),
+                Self::UNDERWATER_DISTORTION,
+            ),
+            None => (F32x3::ONE, 0.0),
+        };
+
+        PostLocals::new(tint, distortion, self.damage, self.fade)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::ScreenEffects;
+
+    #[test]
+    fn damage_decays() {
+        let mut effects = ScreenEffects::default();
+        effects.hurt();
+        assert_eq!(effects.damage(), 1.0);
+
+        effects.advance(ScreenEffects::DAMAGE_TIME / 2.0);
+        assert!((effects.damage() - 0.5).abs() < 1e-5);
+
+        effects.advance(ScreenEffects::DAMAGE_TIME);
+        assert_eq!(effects.damage(), 0.0);
+    }
+
+    #[test]
+    fn fade_follows_death() {
+        let mut effects = ScreenEffects {
+            dead: true,
+            ..Default::default()
+        };
+        effects.advance(ScreenEffects::FADE_TIME / 4.0);
+        assert!((effects.fade() - 0.25).abs() < 1e-5);
+
+        effects.advance(ScreenEffects::FADE_TIME * 2.0);
+        assert_eq!(effects.fade(), 1.0);
+
+        effects.dead = false;
+        effects.advance(0.0);
+        assert_eq!(effects.fade(), 0.0);
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use common::{
+    chunk::{Chunk, LoadArea},
+    coord::GlobalCoord,
+    entity::{EntityId, EntityKind},
+    path,
+    rng::{self, salt, SeededRng},
+};
+use common_log::prof;
+use tracing::warn;
+use wgpu::BufferUsages;
+
+use crate::{
+    render::{
+        buffer::DynamicBuffer,
+        primitives::instance::{Instance, RawInstance},
+        renderer::Renderer,
+    },
+    types::{F32x2, F32x3, Rotation},
+};
+
+use super::{
+    ai::Wander,
+    camera::{lerp, lerp_angle},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Remote entities
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Entity state received from the server
+#[derive(Clone, Copy, Debug)]
+pub struct Snapshot {
+    /// Time when snapshot has been received
+    pub time: Instant,
+    pub pos: F32x3,
+    pub rot: F32x2,
+}
+
+/// Buffer of received snapshots used for rendering entity in the past
+#[derive(Debug)]
+pub struct InterpolationBuffer {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl InterpolationBuffer {
+    pub const MAX_SNAPSHOTS: usize = 32;
+
+    pub fn new(snapshot: Snapshot) -> Self {
+        let mut snapshots = VecDeque::with_capacity(Self::MAX_SNAPSHOTS);
+        snapshots.push_back(snapshot);
+
+        Self { snapshots }
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() >= Self::MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// Get interpolated state at `time`
+    pub fn sample(&mut self, time: Instant) -> (F32x3, F32x2) {
+        // Drop snapshots which are not needed anymore (keep one before render time)
+        while self.snapshots.len() > 2 && self.snapshots[1].time <= time {
+            self.snapshots.pop_front();
+        }
+
+        let first = self.snapshots[0];
+
+        match self.snapshots.get(1) {
+            Some(second) if time > first.time => {
+                let span = second.time.duration_since(first.time).as_secs_f32();
+                let f = if span > 0.0 {
+                    (time.duration_since(first.time).as_secs_f32() / span).min(1.0)
+                } else {
+                    1.0
+                };
+
+                (
+                    first.pos.lerp(second.pos, f),
+                    F32x2::new(
+                        lerp_angle(first.rot.x, second.rot.x, f),
+                        lerp(first.rot.y, second.rot.y, f),
+                    ),
+                )
+            }
+            _ => (first.pos, first.rot),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
+/// Entity controlled by the server
+#[derive(Debug)]
+pub struct RemoteEntity {
+    pub kind: EntityKind,
+    /// Name shown above the entity (empty if none)
+    pub name: String,
+    pub buffer: InterpolationBuffer,
+    /// Interpolated position
+    pub pos: F32x3,
+    /// Interpolated rotation (yaw & pitch)
+    pub rot: F32x2,
+}
+
+/// Stores entities received from the server
+pub struct RemoteEntities {
+    /// How far in the past remote entities are rendered
+    pub interp_delay: Duration,
+    pub inner: HashMap<EntityId, RemoteEntity>,
+}
+
+impl RemoteEntities {
+    pub const DEFAULT_INTERP_DELAY: Duration = Duration::from_millis(100);
+    pub const MAX_INTERP_DELAY: Duration = Duration::from_millis(500);
+
+    pub fn new() -> Self {
+        Self {
+            interp_delay: Self::DEFAULT_INTERP_DELAY,
+            inner: HashMap::new(),
+        }
+    }
+
+    pub fn spawn(&mut self, id: EntityId, kind: EntityKind, name: String, pos: F32x3, rot: F32x2) {
+        self.inner.insert(
+            id,
+            RemoteEntity {
+                kind,
+                name,
+                buffer: InterpolationBuffer::new(Snapshot {
+                    time: Instant::now(),
+                    pos,
+                    rot,
+                }),
+                pos,
+                rot,
+            },
+        );
+    }
+
+    pub fn despawn(&mut self, id: EntityId) {
+        self.inner.remove(&id);
+    }
+
+    pub fn push_state(&mut self, id: EntityId, pos: F32x3, rot: F32x2) {
+        if let Some(entity) = self.inner.get_mut(&id) {
+            entity.buffer.push(Snapshot {
+                time: Instant::now(),
+                pos,
+                rot,
+            });
+        }
+    }
+
+    /// Update interpolated states of all entities
+    pub fn update(&mut self) {
+        prof!(_guard, "RemoteEntities::update");
+
+        let time = Instant::now()
+            .checked_sub(self.interp_delay)
+            .unwrap_or_else(Instant::now);
+
+        self.inner.values_mut().for_each(|entity| {
+            (entity.pos, entity.rot) = entity.buffer.sample(time);
+        });
+    }
+
+    pub fn instances(&self) -> Vec<Instance> {
+        self.inner
+            .values()
+            .map(|entity| Instance::new(entity.pos, Rotation::from_rotation_y(entity.rot.x)))
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl Default for RemoteEntities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Local entities
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Identifier of an entity living only on the client
+pub type LocalEntityId = u32;
+
+/// Local entity components
+#[derive(Clone, Debug, Default)]
+pub struct Components {
+    pub pos: F32x3,
+    /// Rotation (yaw & pitch)
+    pub rot: F32x2,
+    /// Keep entity even outside the load area
+    pub persistent: bool,
+    /// Mob AI
+    pub wander: Option<Wander>,
+}
+
+#[derive(Debug)]
+pub struct LocalEntity {
+    pub components: Components,
+    /// Instance buffer slot
+    slot: usize,
+    /// Drives wandering. Seeded from the id, so moves don't depend on the order entities tick in
+    rng: SeededRng,
+}
+
+/// Stores client-side entities and manages their instance buffer.
+///
+/// Every entity occupies one instance slot. Slots are kept dense, so despawning moves the last
+/// entity to the freed slot
+pub struct LocalEntities {
+    next_id: LocalEntityId,
+    inner: HashMap<LocalEntityId, LocalEntity>,
+    /// Entity occupying each instance slot
+    slots: Vec<LocalEntityId>,
+    buffer: Option<DynamicBuffer<RawInstance>>,
+    /// Instances must be uploaded
+    dirty: bool,
+}
+
+impl LocalEntities {
+    const MIN_CAPACITY: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            inner: HashMap::new(),
+            slots: Vec::new(),
+            buffer: None,
+            dirty: false,
+        }
+    }
+
+    pub fn spawn(&mut self, components: Components) -> LocalEntityId {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        self.inner.insert(
+            id,
+            LocalEntity {
+                components,
+                slot: self.slots.len(),
+                rng: SeededRng::new(rng::mix(
+                    rng::mix_seed(Chunk::DEFAULT_SEED, salt::WANDER) ^ id as u64,
+                )),
+            },
+        );
+        self.slots.push(id);
+        self.dirty = true;
+
+        id
+    }
+
+    pub fn despawn(&mut self, id: LocalEntityId) -> Option<Components> {
+        let entity = self.inner.remove(&id)?;
+
+        self.slots.swap_remove(entity.slot);
+        if let Some(moved) = self.slots.get(entity.slot) {
+            if let Some(moved) = self.inner.get_mut(moved) {
+                moved.slot = entity.slot;
+            }
+        }
+        self.dirty = true;
+
+        Some(entity.components)
+    }
+
+    /// Spawn wandering mob on the ground below `pos`
+    pub fn spawn_mob(
+        &mut self,
+        pos: F32x3,
+        solid: impl Fn(GlobalCoord) -> Option<bool>,
+    ) -> Option<LocalEntityId> {
+        const MAX_DROP: i64 = 64;
+
+        let start = Wander::block_pos(pos.floor());
+        let ground = (0..MAX_DROP)
+            .map(|drop| GlobalCoord::new(start.x, start.y - drop, start.z))
+            .find(|&pos| path::walkable(pos, &solid))?;
+
+        Some(self.spawn(Components {
+            pos: ground.as_vec(),
+            wander: Some(Wander::default()),
+            ..Default::default()
+        }))
+    }
+
+    /// Despawn non-persistent entities outside `area`. Returns number of despawned entities
+    pub fn despawn_outside(&mut self, area: &LoadArea) -> usize {
+        let outside = self
+            .inner
+            .iter()
+            .filter(|(_, entity)| {
+                !entity.components.persistent
+                    && !area.contains(GlobalCoord::from_vec3(entity.components.pos).to_chunk_id())
+            })
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+
+        outside.iter().for_each(|&id| {
+            self.despawn(id);
+        });
+
+        outside.len()
+    }
+
+    pub fn get(&self, id: LocalEntityId) -> Option<&Components> {
+        self.inner.get(&id).map(|entity| &entity.components)
+    }
+
+    pub fn get_mut(&mut self, id: LocalEntityId) -> Option<&mut Components> {
+        self.dirty = true;
+        self.inner.get_mut(&id).map(|entity| &mut entity.components)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (LocalEntityId, &Components)> {
+        self.inner
+            .iter()
+            .map(|(&id, entity)| (id, &entity.components))
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Run AI of all entities
+    pub fn tick(&mut self, dt: f32, solid: impl Fn(GlobalCoord) -> Option<bool>) {
+        prof!(_guard, "LocalEntities::tick");
+
+        self.inner.values_mut().for_each(|entity| {
+            let Components {
+                pos, rot, wander, ..
+            } = &mut entity.components;
+
+            if let Some(wander) = wander {
+                if wander.tick(pos, rot, dt, &mut entity.rng, &solid) {
+                    self.dirty = true;
+                }
+            }
+        });
+    }
+
+    /// Upload changed instances. Instance buffer grows when there are not enough slots
+    pub fn upload(&mut self, renderer: &Renderer) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        if self.slots.is_empty() {
+            return;
+        }
+
+        let buffer = self.buffer.get_or_insert_with(|| {
+            DynamicBuffer::new(&renderer.device, Self::MIN_CAPACITY, BufferUsages::VERTEX)
+        });
+        buffer.ensure_capacity(&renderer.device, self.slots.len());
+
+        let instances = self
+            .slots
+            .iter()
+            .map(|id| {
+                let components = &self.inner[id].components;
+                Instance::new(components.pos, Rotation::from_rotation_y(components.rot.x)).as_raw()
+            })
+            .collect::<Vec<_>>();
+
+        if let Err(err) = renderer.update_dynamic_buffer(buffer, &instances) {
+            warn!(%err, "Failed to upload local entities");
+        }
+    }
+
+    /// Drop instance buffer. All instances are uploaded again on the next frame
+    pub fn release_buffer(&mut self) {
+        self.buffer = None;
+        self.dirty = true;
+    }
+
+    /// Instance buffer and number of used slots
+    pub fn instances(&self) -> Option<(&DynamicBuffer<RawInstance>, u32)> {
+        self.buffer
+            .as_ref()
+            .filter(|_| !self.slots.is_empty())
+            .map(|buffer| (buffer, self.slots.len() as u32))
+    }
+}
+
+impl Default for LocalEntities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::types::{F32x2, F32x3};
+
+    use super::{Components, InterpolationBuffer, LocalEntities, Snapshot};
+
+    #[test]
+    fn interpolation_buffer_sample() {
+        let start = Instant::now();
+        let snapshot = |ms: u64, x: f32| Snapshot {
+            time: start + Duration::from_millis(ms),
+            pos: F32x3::new(x, 0.0, 0.0),
+            rot: F32x2::ZERO,
+        };
+
+        let mut buffer = InterpolationBuffer::new(snapshot(0, 0.0));
+        buffer.push(snapshot(100, 10.0));
+        buffer.push(snapshot(200, 20.0));
+
+        // Before first snapshot
+        assert_eq!(buffer.sample(start).0.x, 0.0);
+        // Between snapshots
+        let (pos, _) = buffer.sample(start + Duration::from_millis(150));
+        assert!((pos.x - 15.0).abs() < 0.01);
+        assert_eq!(buffer.len(), 2);
+        // After last snapshot
+        assert_eq!(buffer.sample(start + Duration::from_millis(300)).0.x, 20.0);
+    }
+
+    #[test]
+    fn local_entities_slots() {
+        let mut entities = LocalEntities::new();
+
+        let first = entities.spawn(Components::default());
+        let second = entities.spawn(Components::default());
+        let third = entities.spawn(Components::default());
+
+        assert!(entities.despawn(first).is_some());
+        assert!(entities.despawn(first).is_none());
+
+        // Last entity moved to the freed slot
+        assert_eq!(entities.inner[&third].slot, 0);
+        assert_eq!(entities.inner[&second].slot, 1);
+        assert_eq!(entities.slots, vec![third, second]);
+    }
+}
@@ -8,7 +8,7 @@ use wgpu::{
 use crate::{
     render::{
         model::Model,
-        primitives::{quad::Quad, vertex::Vertex},
+        primitives::{figure::FigureVertex, quad::Quad},
     },
     types::F32x3,
 };
@@ -21,13 +21,13 @@ pub struct Voxel {
 
 impl Voxel {
     pub fn new(device: &Device) -> Self {
-        let vertices: Vec<Vertex> = Direction::ALL
+        let vertices: Vec<FigureVertex> = Direction::ALL
             .into_iter()
             .flat_map(|dir| {
                 Quad::new(dir, F32x3::ZERO)
                     .corners()
                     .into_iter()
-                    .map(|position| Vertex {
+                    .map(|position| FigureVertex {
                         // Rescale
                         position,
                         color: F32x3::ZERO,
@@ -60,6 +60,8 @@ impl Voxel {
 }
 
 impl Model for Voxel {
+    type Vertex = FigureVertex;
+
     const INDEX_FORMAT: IndexFormat = IndexFormat::Uint16;
 
     fn get_vertices(&self) -> &Buffer {
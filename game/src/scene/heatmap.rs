@@ -0,0 +1,117 @@
+use common::coord::CHUNK_SIZE;
+
+use super::{
+    chunk::{ChunkStore, TerrainChunk},
+    debug::{DebugLines, DebugPalette},
+};
+
+/// Property of chunk meshes shown by the heatmap
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ChunkMetric {
+    /// Time spent building the mesh on the CPU
+    BuildTime,
+    Vertices,
+    /// Frames since the mesh has been drawn
+    SinceDrawn,
+}
+
+impl ChunkMetric {
+    pub const ALL: [Self; 3] = [Self::BuildTime, Self::Vertices, Self::SinceDrawn];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BuildTime => "Build time",
+            Self::Vertices => "Vertices",
+            Self::SinceDrawn => "Since drawn",
+        }
+    }
+
+    /// Metric of the chunk mesh. `None` if it isn't known (e.g. mesh has been built on the GPU)
+    pub fn value(&self, terrain: &TerrainChunk, frame: u64) -> Option<f32> {
+        match self {
+            Self::BuildTime => terrain.build_time.map(|time| time.as_secs_f32() * 1000.0),
+            Self::Vertices => Some(terrain.slice.vertices.len() as f32),
+            Self::SinceDrawn => Some(frame.saturating_sub(terrain.last_drawn()) as f32),
+        }
+    }
+
+    pub fn format(&self, value: f32) -> String {
+        match self {
+            Self::BuildTime => format!("{value:.2} ms"),
+            Self::Vertices => format!("{value:.0}"),
+            Self::SinceDrawn => format!("{value:.0} frames"),
+        }
+    }
+}
+
+/// Colors outlines of the meshed chunks by the metric relative to the highest value
+#[derive(Default)]
+pub struct ChunkHeatmap {
+    /// Heatmap is hidden if `None`
+    pub metric: Option<ChunkMetric>,
+    /// Highest value on the last update
+    max: f32,
+}
+
+impl ChunkHeatmap {
+    /// Outlines are shrunk, so the ones of neighbor chunks don't overlap
+    const INSET: f32 = 0.1;
+
+    /// Add outlines of meshed `chunks` to `lines`
+    pub fn update(
+        &mut self,
+        lines: &mut DebugLines,
+        chunks: &ChunkStore,
+        frame: u64,
+        palette: DebugPalette,
+    ) {
+        let Some(metric) = self.metric else {
+            return;
+        };
+
+        self.max = chunks
+            .meshes()
+            .filter_map(|(_, chunk)| metric.value(chunk, frame))
+            .fold(0.0, f32::max);
+
+        for (_, chunk) in chunks.meshes() {
+            let color = metric
+                .value(chunk, frame)
+                .map_or(palette.unknown(), |value| {
+                    palette.heat(if self.max > 0.0 {
+                        value / self.max
+                    } else {
+                        0.0
+                    })
+                });
+
+            lines.cuboid(
+                chunk.origin + Self::INSET,
+                chunk.origin + (CHUNK_SIZE as f32 - Self::INSET),
+                color,
+            );
+        }
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::scene::debug::DebugPalette;
+
+    #[test]
+    fn heat_colors() {
+        let heat_color = |t| DebugPalette::Default.heat(t);
+        assert_eq!(heat_color(0.0), [0, 255, 0, 255]);
+        assert_eq!(heat_color(0.5), [255, 255, 0, 255]);
+        assert_eq!(heat_color(1.0), [255, 0, 0, 255]);
+        assert_eq!(heat_color(7.0), heat_color(1.0));
+    }
+}
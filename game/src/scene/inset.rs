@@ -0,0 +1,157 @@
+use tracing::warn;
+
+use crate::{
+    render::{
+        pipelines::{GlobalModel, Globals, GlobalsBindGroup},
+        renderer::Renderer,
+        Viewport,
+    },
+    types::{Mat4, U32x2},
+};
+
+use super::{camera::Camera, map::MapView};
+
+/// Point of view of [`ViewInset`]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum InsetView {
+    /// Orthographic view from above, the same as the map
+    TopDown,
+    /// View from the frozen frustum, or from the camera if nothing is frozen
+    Frustum,
+}
+
+impl InsetView {
+    pub const ALL: [Self; 2] = [Self::TopDown, Self::Frustum];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::TopDown => "Top-down",
+            Self::Frustum => "Frozen frustum",
+        }
+    }
+}
+
+/// Second view of the scene drawn in the bottom right corner of the frame (picture-in-picture).
+///
+/// Terrain is culled for the main view, so culling can be inspected from another point of view
+pub struct ViewInset {
+    /// `None` if the inset is hidden
+    pub view: Option<InsetView>,
+    /// Fraction of the frame size. The inset has the aspect ratio of the frame, so projections of
+    /// the main view fit it
+    pub size: f32,
+    viewport: Viewport,
+    gpu: Option<(GlobalModel, GlobalsBindGroup)>,
+}
+
+impl ViewInset {
+    pub const MIN_SIZE: f32 = 0.15;
+    pub const MAX_SIZE: f32 = 0.5;
+    pub const DEFAULT_SIZE: f32 = 0.3;
+    /// Distance from the frame corner in pixels
+    pub const MARGIN: u32 = 16;
+
+    pub const fn new() -> Self {
+        Self {
+            view: None,
+            size: Self::DEFAULT_SIZE,
+            viewport: Viewport {
+                pos: U32x2::ZERO,
+                size: U32x2::ZERO,
+            },
+            gpu: None,
+        }
+    }
+
+    /// Projection and view matrices of the inset
+    pub fn matrices(
+        view: InsetView,
+        camera: &Camera,
+        map: &MapView,
+        frozen_frustum: Option<(Mat4, Mat4)>,
+    ) -> (Mat4, Mat4) {
+        match view {
+            InsetView::TopDown => map.matrices(camera),
+            InsetView::Frustum => {
+                frozen_frustum.unwrap_or_else(|| (camera.proj_mat(), camera.view_mat()))
+            }
+        }
+    }
+
+    /// Place of the inset in a frame of `resolution`
+    pub fn viewport(&self, resolution: U32x2) -> Viewport {
+        let size = (resolution.as_vec2() * self.size.clamp(Self::MIN_SIZE, Self::MAX_SIZE))
+            .as_uvec2()
+            .max(U32x2::ONE);
+        let corner = size + Self::MARGIN;
+        let pos = U32x2::new(
+            resolution.x.saturating_sub(corner.x),
+            resolution.y.saturating_sub(corner.y),
+        );
+
+        Viewport { pos, size }
+    }
+
+    /// Upload globals of the inset view. GPU resources are created on the first update
+    pub fn update(&mut self, renderer: &Renderer, globals: Globals) {
+        self.viewport = self.viewport(renderer.resolution());
+
+        let (model, _) = self.gpu.get_or_insert_with(|| {
+            let model = GlobalModel::create(renderer);
+            let bind_group = renderer.bind_globals(&model);
+            (model, bind_group)
+        });
+        if let Err(err) = renderer.update_consts(&model.globals, &[globals]) {
+            warn!(%err, "Failed to update inset globals");
+        }
+    }
+
+    /// Drop GPU resources. They are created again on the next update
+    pub fn release_buffer(&mut self) {
+        self.gpu = None;
+    }
+
+    /// Globals and viewport to draw the inset with. `None` if hidden or until the first update
+    pub fn pass(&self) -> Option<(&GlobalsBindGroup, Viewport)> {
+        self.gpu
+            .as_ref()
+            .filter(|_| self.view.is_some())
+            .map(|(_, bind_group)| (bind_group, self.viewport))
+    }
+}
+
+impl Default for ViewInset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{render::Viewport, types::U32x2};
+
+    use super::ViewInset;
+
+    #[test]
+    fn bottom_right_viewport() {
+        let mut inset = ViewInset::new();
+        inset.size = 0.25;
+        assert_eq!(
+            inset.viewport(U32x2::new(1600, 900)),
+            Viewport {
+                pos: U32x2::new(1184, 659),
+                size: U32x2::new(400, 225),
+            }
+        );
+
+        // Size is clamped and the inset stays inside tiny frames
+        inset.size = 2.0;
+        let viewport = inset.viewport(U32x2::new(20, 10));
+        assert_eq!(viewport.size, U32x2::new(10, 5));
+        assert_eq!(viewport.pos, U32x2::ZERO);
+    }
+}
@@ -0,0 +1,230 @@
+use tracing::warn;
+use wgpu::BufferUsages;
+
+use crate::{
+    assets::AssetManager,
+    error::Error,
+    render::{
+        buffer::DynamicBuffer,
+        font::{LabelFont, GLYPH_HEIGHT},
+        primitives::label::LabelVertex,
+        renderer::Renderer,
+    },
+    types::{F32x2, F32x3},
+};
+
+/// Text shown at a world position, always facing the camera
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub text: String,
+    pub pos: F32x3,
+    /// RGB color
+    pub color: [u8; 3],
+}
+
+impl Label {
+    pub fn new(text: impl Into<String>, pos: F32x3, color: [u8; 3]) -> Self {
+        Self {
+            text: text.into(),
+            pos,
+            color,
+        }
+    }
+}
+
+/// Labels collected for the current frame. Producers (entities, debug tools) push their labels
+/// after `clear` and `upload` builds the mesh
+pub struct Labels {
+    inner: Vec<Label>,
+    /// Size of a pixel of the built-in font (in world units). Other fonts are scaled to the same
+    /// text height
+    pub pixel_size: f32,
+    /// Labels start to fade out after this distance
+    pub fade_distance: f32,
+    /// Labels further than this aren't shown
+    pub max_distance: f32,
+
+    font: LabelFont,
+    /// File of the font, `None` for the built-in one
+    font_file: Option<String>,
+
+    buffer: Option<DynamicBuffer<LabelVertex>>,
+    vertex_count: u32,
+}
+
+impl Labels {
+    pub const DEFAULT_PIXEL_SIZE: f32 = 0.05;
+    pub const DEFAULT_FADE_DISTANCE: f32 = 24.0;
+    pub const DEFAULT_MAX_DISTANCE: f32 = 32.0;
+    const MIN_CAPACITY: usize = 1024;
+
+    pub fn new() -> Self {
+        Self {
+            inner: Vec::new(),
+            pixel_size: Self::DEFAULT_PIXEL_SIZE,
+            fade_distance: Self::DEFAULT_FADE_DISTANCE,
+            max_distance: Self::DEFAULT_MAX_DISTANCE,
+            font: LabelFont::Builtin,
+            font_file: None,
+            buffer: None,
+            vertex_count: 0,
+        }
+    }
+
+    /// Load the font file through the asset manager if it has changed. Built-in font is used if
+    /// `None` or the font is broken
+    pub fn set_font(&mut self, assets: &AssetManager, file: Option<&str>) {
+        if self.font_file.as_deref() == file {
+            return;
+        }
+
+        self.font_file = file.map(str::to_string);
+        self.font = file.map_or(LabelFont::Builtin, |file| {
+            assets
+                .font(file)
+                .and_then(|data| {
+                    LabelFont::ttf(data).map_err(|err| Error::Asset {
+                        path: file.to_string(),
+                        reason: err.to_string(),
+                    })
+                })
+                .unwrap_or_else(|err| {
+                    warn!(%err, "Failed to load label font");
+                    LabelFont::Builtin
+                })
+        });
+    }
+
+    pub fn push(&mut self, label: Label) {
+        self.inner.push(label);
+    }
+
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Label opacity at the distance from the camera
+    fn alpha(&self, distance: f32) -> f32 {
+        if distance >= self.max_distance {
+            0.0
+        } else if distance <= self.fade_distance {
+            1.0
+        } else {
+            1.0 - (distance - self.fade_distance) / (self.max_distance - self.fade_distance)
+        }
+    }
+
+    /// Build label quads (one per lit font pixel) as seen from `camera_pos`
+    pub fn mesh(&self, camera_pos: F32x3) -> Vec<LabelVertex> {
+        let mut vertices = Vec::new();
+        let pixel_size = self.pixel_size * GLYPH_HEIGHT as f32 / self.font.height() as f32;
+
+        for label in &self.inner {
+            let alpha = self.alpha(label.pos.distance(camera_pos));
+            if alpha <= 0.0 {
+                continue;
+            }
+
+            let [r, g, b] = label.color;
+            let color = [r, g, b, (alpha * 255.0) as u8];
+            // Center text horizontally above the anchor
+            let origin = F32x2::new(-(self.font.text_width(&label.text) as f32) / 2.0, 0.0);
+
+            for (x, y) in self.font.text_pixels(&label.text) {
+                let min = (origin + F32x2::new(x as f32, y as f32)) * pixel_size;
+                let max = min + pixel_size;
+
+                vertices.extend(
+                    [
+                        (min.x, min.y),
+                        (max.x, min.y),
+                        (max.x, max.y),
+                        (min.x, min.y),
+                        (max.x, max.y),
+                        (min.x, max.y),
+                    ]
+                    .map(|(x, y)| LabelVertex::new(label.pos, F32x2::new(x, y), color)),
+                );
+            }
+        }
+
+        vertices
+    }
+
+    /// Rebuild and upload label mesh. Buffer grows when there is not enough space
+    pub fn upload(&mut self, renderer: &Renderer, camera_pos: F32x3) {
+        let vertices = self.mesh(camera_pos);
+        self.vertex_count = vertices.len() as u32;
+
+        if vertices.is_empty() {
+            return;
+        }
+
+        let buffer = self.buffer.get_or_insert_with(|| {
+            DynamicBuffer::new(&renderer.device, Self::MIN_CAPACITY, BufferUsages::VERTEX)
+        });
+        buffer.ensure_capacity(&renderer.device, vertices.len());
+
+        if let Err(err) = renderer.update_dynamic_buffer(buffer, &vertices) {
+            warn!(%err, "Failed to upload labels");
+        }
+    }
+
+    /// Drop vertex buffer. It's created again on the next upload
+    pub fn release_buffer(&mut self) {
+        self.buffer = None;
+        self.vertex_count = 0;
+    }
+
+    /// Vertex buffer and number of used vertices
+    pub fn vertices(&self) -> Option<(&DynamicBuffer<LabelVertex>, u32)> {
+        self.buffer
+            .as_ref()
+            .filter(|_| self.vertex_count > 0)
+            .map(|buffer| (buffer, self.vertex_count))
+    }
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::F32x3;
+
+    use super::{Label, Labels};
+
+    #[test]
+    fn label_mesh_and_fade() {
+        let mut labels = Labels::new();
+        // "1" has 8 lit pixels, "-" has 3
+        labels.push(Label::new("1-", F32x3::ZERO, [255; 3]));
+
+        let near = labels.mesh(F32x3::new(0.0, 0.0, 5.0));
+        assert_eq!(near.len(), 11 * 6);
+        assert!(near.iter().all(|vertex| vertex.color[3] == 255));
+        // Text is centered around the anchor
+        let (min, max) = near
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), vertex| {
+                (min.min(vertex.offset.x), max.max(vertex.offset.x))
+            });
+        assert!((min + max).abs() < 1e-4);
+
+        let fading = labels.mesh(F32x3::new(0.0, 0.0, 28.0));
+        assert!(fading[0].color[3] > 0 && fading[0].color[3] < 255);
+
+        assert!(labels.mesh(F32x3::new(0.0, 0.0, 40.0)).is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+use std::time::{Duration, Instant};
+
+use common::{
+    chunk::LoadArea,
+    coord::{GlobalCoord, GlobalUnit},
+};
+use tracing::{info, warn};
+
+use crate::{
+    render::{
+        buffer::Consts,
+        pipelines::{
+            loading::{LoadingBindGroup, LoadingLocals},
+            Environment,
+        },
+    },
+    window::Window,
+};
+
+use super::{
+    camera::Camera,
+    chunk::{ChunkManager, TerrainStatus},
+};
+
+/// Progress of the initial chunk ring around the camera
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub struct LoadingProgress {
+    /// Chunks with blocks (generated locally or received from the server)
+    pub generated: usize,
+    /// Chunks with built (or empty) meshes
+    pub meshed: usize,
+    pub total: usize,
+}
+
+impl LoadingProgress {
+    /// Overall progress (0..1). Generation and meshing are weighted equally
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        (self.generated + self.meshed) as f32 / (self.total * 2) as f32
+    }
+
+    pub fn done(&self) -> bool {
+        self.meshed >= self.total
+    }
+}
+
+/// Loading screen shown instead of the scene until chunks around the camera are generated and
+/// meshed, so the world doesn't pop in during the first frames
+pub struct Loading {
+    started: Instant,
+    progress: LoadingProgress,
+    /// Last percent shown in the window title
+    percent: Option<u32>,
+    gpu: Option<(Consts<LoadingLocals>, LoadingBindGroup)>,
+}
+
+impl Loading {
+    /// Radius (in chunks) of the ring loaded before the game starts
+    pub const RADIUS: GlobalUnit = 2;
+    /// Loading is finished anyway after this time (e.g. the server doesn't send chunks)
+    pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            progress: LoadingProgress::default(),
+            percent: None,
+            gpu: None,
+        }
+    }
+
+    pub fn progress(&self) -> LoadingProgress {
+        self.progress
+    }
+
+    /// Count ready chunks and update the loading screen. Returns `true` when loading is finished
+    pub fn update(
+        &mut self,
+        window: &Window,
+        chunk_manager: &ChunkManager,
+        camera: &Camera,
+        environment: &Environment,
+    ) -> bool {
+        let radius = Self::RADIUS.min(chunk_manager.draw_distance as GlobalUnit);
+        let ring = LoadArea::new_cuboid(GlobalCoord::from_vec3(camera.pos).to_chunk_id(), radius);
+
+        let mut progress = LoadingProgress::default();
+        // Chunks out of bounds are never loaded
+        for id in ring.filter(|&id| chunk_manager.bounds.contains_chunk(id)) {
+            progress.total += 1;
+            if let Some(chunk) = chunk_manager.chunks.get(&id) {
+                progress.generated += 1;
+                if matches!(chunk.status(), TerrainStatus::Built) {
+                    progress.meshed += 1;
+                }
+            }
+        }
+        self.progress = progress;
+
+        let elapsed = self.started.elapsed();
+        if progress.done() || elapsed >= Self::TIMEOUT {
+            if progress.done() {
+                info!(?elapsed, chunks = progress.total, "World loaded");
+            } else {
+                warn!(?progress, "World loading timed out");
+            }
+            window.inner().set_title(&Window::title());
+            return true;
+        }
+
+        let percent = (progress.fraction() * 100.0) as u32;
+        if self.percent.replace(percent) != Some(percent) {
+            window
+                .inner()
+                .set_title(&format!("{} - Loading {percent}%", Window::title()));
+        }
+
+        let renderer = window.renderer();
+        let (locals, _) = self.gpu.get_or_insert_with(|| {
+            let locals = renderer.create_consts(&[LoadingLocals::default()]);
+            let bind_group = renderer.bind_loading(&locals);
+            (locals, bind_group)
+        });
+        let resolution = renderer.resolution().as_vec2();
+        let locals_value = LoadingLocals::new(
+            progress.fraction(),
+            elapsed.as_secs_f32(),
+            resolution.x / resolution.y.max(1.0),
+            environment.sky_color,
+        );
+        if let Err(err) = renderer.update_consts(locals, &[locals_value]) {
+            warn!(%err, "Failed to update loading screen");
+        }
+
+        false
+    }
+
+    /// Drop GPU resources. They are created again on the next update
+    pub fn release_buffer(&mut self) {
+        self.gpu = None;
+    }
+
+    /// Bind group to draw the loading screen with. `None` until the first update
+    pub fn bind_group(&self) -> Option<&LoadingBindGroup> {
+        self.gpu.as_ref().map(|(_, bind_group)| bind_group)
+    }
+}
+
+impl Default for Loading {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::LoadingProgress;
+
+    #[test]
+    fn progress_fraction() {
+        let mut progress = LoadingProgress {
+            generated: 0,
+            meshed: 0,
+            total: 8,
+        };
+        assert_eq!(progress.fraction(), 0.0);
+        assert!(!progress.done());
+
+        progress.generated = 8;
+        progress.meshed = 4;
+        assert_eq!(progress.fraction(), 0.75);
+
+        progress.meshed = 8;
+        assert!(progress.done());
+        assert_eq!(LoadingProgress::default().fraction(), 1.0);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{render::pipelines::Environment, types::Mat4};
+
+use super::camera::{Camera, Projection};
+
+/// Top-down orthographic view of loaded chunks centered on the camera.
+///
+/// Used for orientation and world generation debugging. Replaces the camera view while enabled,
+/// player movement keeps working
+#[derive(Clone, Debug)]
+pub struct MapView {
+    pub enabled: bool,
+    /// Number of blocks visible vertically
+    pub zoom: f32,
+}
+
+impl MapView {
+    /// Height of the map eye above the camera. Blocks higher than this are clipped
+    pub const ALTITUDE: f32 = 256.0;
+    pub const MIN_ZOOM: f32 = 16.0;
+    pub const MAX_ZOOM: f32 = 1024.0;
+    pub const DEFAULT_ZOOM: f32 = 128.0;
+
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            zoom: Self::DEFAULT_ZOOM,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Handle mouse wheel. Positive delta zooms out
+    pub fn scroll(&mut self, delta: f32) {
+        const SENSITIVITY: f32 = 0.1;
+
+        self.zoom = (self.zoom * (1.0 + delta * SENSITIVITY)).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+
+    /// Projection and view matrices of the map
+    pub fn matrices(&self, camera: &Camera) -> (Mat4, Mat4) {
+        let proj = Projection::Orthographic { height: self.zoom }.matrix(
+            camera.fov,
+            camera.aspect,
+            0.0,
+            Self::ALTITUDE * 2.0,
+        );
+
+        (proj, camera.top_down_view_mat(Self::ALTITUDE))
+    }
+
+    /// Environment without fog. Map eye is too far from the terrain to use distance fog
+    pub fn environment(env: &Environment) -> Environment {
+        Environment {
+            fog_start: Self::ALTITUDE * 4.0,
+            fog_end: Self::ALTITUDE * 4.0 + 1.0,
+            ..*env
+        }
+    }
+}
+
+impl Default for MapView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::types::F32x3;
+
+    use super::{
+        super::camera::{Camera, CameraMode},
+        MapView,
+    };
+
+    #[test]
+    fn top_down_projection() {
+        let mut camera = Camera::new(2.0, CameraMode::FirstPerson);
+        camera.pos = F32x3::new(10.0, 20.0, 30.0);
+        let (proj, view) = MapView::new().matrices(&camera);
+        let view_proj = proj * view;
+
+        let project = |pos: F32x3| view_proj.project_point3(pos);
+        // Camera position is in the center
+        let center = project(camera.pos);
+        assert!(center.x.abs() < 1e-4 && center.y.abs() < 1e-4);
+        // North is up, east is right
+        let corner = project(camera.pos + F32x3::new(128.0, 0.0, 64.0));
+        assert!((corner.x - 1.0).abs() < 1e-4 && (corner.y - 1.0).abs() < 1e-4);
+        // Higher blocks are closer
+        assert!(project(camera.pos + F32x3::Y).z < center.z);
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::HashSet;
+
+use common::{
+    block::Block,
+    coord::{BlockCoord, GlobalUnit, CHUNK_SIZE, CHUNK_SQUARE, G_CHUNK_SIZE},
+    event::{EventBus, WorldEvent},
+    palette::Palette,
+};
+use tracing::warn;
+use wgpu::AddressMode;
+
+use crate::{
+    render::{
+        buffer::Consts,
+        pipelines::minimap::{MinimapBindGroup, MinimapLocals},
+        renderer::Renderer,
+        texture::Texture,
+    },
+    settings::UiSettings,
+    types::{F32x2, U32x2},
+};
+
+use super::{
+    camera::Camera,
+    chunk::{ChunkManager, LogicChunk},
+};
+
+/// Minimap GPU resources
+struct MinimapGpu {
+    texture: Texture,
+    locals: Consts<MinimapLocals>,
+    bind_group: MinimapBindGroup,
+}
+
+/// Top block colors of loaded chunk columns, drawn in the screen corner and rotated with the
+/// camera.
+///
+/// Colors are kept in a texture wrapping around the world, so columns are updated only when their
+/// terrain changes. Unloaded columns keep their last colors
+pub struct Minimap {
+    pub enabled: bool,
+    /// Index in [`Self::ZOOM_LEVELS`]
+    zoom: usize,
+    /// Chunk columns (x, z) waiting to be uploaded
+    pending: HashSet<(GlobalUnit, GlobalUnit)>,
+    gpu: Option<MinimapGpu>,
+}
+
+impl Minimap {
+    /// Texture side in blocks
+    pub const TEXTURE_SIZE: u32 = 512;
+    /// Number of blocks visible across the minimap
+    pub const ZOOM_LEVELS: [f32; 4] = [32.0, 64.0, 128.0, 256.0];
+    /// Minimap diameter in pixels
+    pub const SIZE: f32 = 192.0;
+    /// Distance from the screen corner in pixels
+    pub const MARGIN: f32 = 16.0;
+    /// Max chunk columns uploaded per frame
+    const MAX_COLUMNS_PER_FRAME: usize = 64;
+
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            zoom: 1,
+            pending: HashSet::new(),
+            gpu: None,
+        }
+    }
+
+    /// Number of blocks visible across the minimap
+    pub fn zoom(&self) -> f32 {
+        Self::ZOOM_LEVELS[self.zoom]
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom = self.zoom.saturating_sub(1);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom + 1).min(Self::ZOOM_LEVELS.len() - 1);
+    }
+
+    /// Queue columns of changed chunks. Called while disabled too, so the minimap is up to date
+    /// when it's enabled again
+    pub fn collect(&mut self, events: &EventBus) {
+        self.pending.extend(
+            events
+                .read()
+                .filter_map(WorldEvent::changed_chunk)
+                .map(|id| (id.x, id.z)),
+        );
+    }
+
+    /// Upload changed columns and update the view. Size and opacity follow the UI settings, size
+    /// is also multiplied by the scale factor of the monitor
+    pub fn update(
+        &mut self,
+        renderer: &Renderer,
+        chunk_manager: &ChunkManager,
+        camera: &Camera,
+        ui: &UiSettings,
+        scale_factor: f32,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        let gpu = match &mut self.gpu {
+            Some(gpu) => gpu,
+            None => {
+                // New texture is empty
+                self.pending
+                    .extend(chunk_manager.chunks.ids().map(|id| (id.x, id.z)));
+                self.gpu.insert(Self::create(renderer))
+            }
+        };
+
+        let columns = self
+            .pending
+            .iter()
+            .take(Self::MAX_COLUMNS_PER_FRAME)
+            .copied()
+            .collect::<Vec<_>>();
+        for column in columns {
+            self.pending.remove(&column);
+
+            let mut chunks = chunk_manager
+                .chunks
+                .iter()
+                .filter(|(id, _)| (id.x, id.z) == column)
+                .collect::<Vec<_>>();
+            if chunks.is_empty() {
+                continue;
+            }
+            chunks.sort_unstable_by_key(|(id, _)| -id.y);
+            let chunks = chunks
+                .into_iter()
+                .map(|(_, chunk)| chunk)
+                .collect::<Vec<_>>();
+
+            let offset = U32x2::new(
+                (column.0 * G_CHUNK_SIZE).rem_euclid(Self::TEXTURE_SIZE as GlobalUnit) as u32,
+                (column.1 * G_CHUNK_SIZE).rem_euclid(Self::TEXTURE_SIZE as GlobalUnit) as u32,
+            );
+            gpu.texture.write(
+                &renderer.queue,
+                offset,
+                U32x2::splat(CHUNK_SIZE as u32),
+                &column_colors(&chunks, chunk_manager.palette()),
+            );
+        }
+
+        let zoom = Self::ZOOM_LEVELS[self.zoom];
+        let resolution = renderer.resolution().as_vec2();
+        let scale = ui.scale * scale_factor;
+        let (size, margin) = (Self::SIZE * scale, Self::MARGIN * scale);
+        // Top right corner
+        let center = F32x2::ONE - (margin + size / 2.0) * 2.0 / resolution;
+        let locals = MinimapLocals::new(
+            center,
+            F32x2::splat(size) / resolution,
+            F32x2::new(camera.pos.x, camera.pos.z) / Self::TEXTURE_SIZE as f32,
+            zoom / 2.0 / Self::TEXTURE_SIZE as f32,
+            camera.rot.x,
+            ui.hud_opacity,
+        );
+        if let Err(err) = renderer.update_consts(&gpu.locals, &[locals]) {
+            warn!(%err, "Failed to update minimap");
+        }
+    }
+
+    fn create(renderer: &Renderer) -> MinimapGpu {
+        let texture = Texture::new_rgba(
+            &renderer.device,
+            U32x2::splat(Self::TEXTURE_SIZE),
+            AddressMode::Repeat,
+            "Texture: Minimap",
+        );
+        let locals = renderer.create_consts(&[MinimapLocals::default()]);
+        let bind_group = renderer.bind_minimap(&locals, &texture);
+
+        MinimapGpu {
+            texture,
+            locals,
+            bind_group,
+        }
+    }
+
+    /// Drop GPU resources. They are created again (with all loaded columns) on the next update
+    pub fn release_buffer(&mut self) {
+        self.gpu = None;
+    }
+
+    /// Bind group to draw the minimap with. `None` if it's disabled or not created yet
+    pub fn bind_group(&self) -> Option<&MinimapBindGroup> {
+        self.gpu
+            .as_ref()
+            .filter(|_| self.enabled)
+            .map(|gpu| &gpu.bind_group)
+    }
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Colors of the highest opaque blocks of a chunk column (`chunks` go from top to bottom).
+///
+/// Texels go row by row along Z. Alpha is 0 where there are no opaque blocks
+fn column_colors(chunks: &[&LogicChunk], palette: &Palette) -> [[u8; 4]; CHUNK_SQUARE] {
+    let mut colors = [[0; 4]; CHUNK_SQUARE];
+
+    for (i, color) in colors.iter_mut().enumerate() {
+        let (x, z) = ((i % CHUNK_SIZE) as u8, (i / CHUNK_SIZE) as u8);
+        let top = chunks.iter().find_map(|chunk| {
+            (0..CHUNK_SIZE as u8)
+                .rev()
+                .map(|y| chunk.blocks()[BlockCoord::new(x, y, z).flatten()])
+                .find(Block::opaque)
+        });
+
+        if let Some(block) = top {
+            let rgb = palette.color(block) * 255.0;
+            *color = [rgb.x as u8, rgb.y as u8, rgb.z as u8, 255];
+        }
+    }
+
+    colors
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        block::Block,
+        coord::{BlockCoord, CHUNK_SIZE},
+        palette::Palette,
+    };
+    use glam::Vec3;
+
+    use super::{column_colors, LogicChunk};
+
+    #[test]
+    fn top_block_colors() {
+        let mut top = LogicChunk::new();
+        top.set_block(BlockCoord::new(3, 2, 5), Block::Sand);
+        let mut bottom = LogicChunk::new();
+        bottom.set_block(BlockCoord::new(3, 15, 5), Block::Stone);
+        bottom.set_block(BlockCoord::new(0, 0, 0), Block::Grass);
+
+        let mut palette = Palette::new();
+        palette.set(Block::Grass, Vec3::new(1.0, 0.0, 0.0));
+        let colors = column_colors(&[&top, &bottom], &palette);
+        let texel = |x: usize, z: usize| colors[z * CHUNK_SIZE + x];
+
+        let sand = Block::Sand.color() * 255.0;
+        assert_eq!(texel(3, 5), [sand.x as u8, sand.y as u8, sand.z as u8, 255]);
+        assert_eq!(texel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(texel(5, 3), [0; 4]);
+    }
+}
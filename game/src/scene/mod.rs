@@ -1,26 +1,46 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use common::{
     block::Block,
-    coord::{ChunkId, CHUNK_SQUARE},
+    bounds::WorldBounds,
+    clock::Clock,
+    coord::{ChunkId, GlobalCoord, CHUNK_SIZE, CHUNK_SQUARE},
+    entity::EntityId,
+    event::{EventBus, WorldEvent},
+    geometry::{Aabb, Frustum, Ray},
+    net::protocol::{ClientMsg, ServerMsg},
+    palette::Palette,
+    physics::RayHit,
+    rules::GameRules,
+    stats::WorldStats,
 };
 use common_log::span;
+use tracing::{debug, info, warn};
 use wgpu::BufferUsages;
 use winit::event::{ElementState, VirtualKeyCode};
 
 use crate::{
+    consts::SETTINGS_FILE,
+    net::NetClient,
     render::{
-        buffer::{Buffer, DynamicBuffer},
-        pipelines::{GlobalModel, Globals, GlobalsBindGroup},
-        primitives::{
-            instance::{Instance, RawInstance},
-            vertex::Vertex,
+        buffer::{Buffer, Consts, DynamicBuffer},
+        pipelines::{
+            barrier::{BarrierBindGroup, BarrierLocals},
+            post::{PostBindGroup, PostLocals},
+            Environment, GlobalModel, Globals, GlobalsBindGroup,
         },
-        renderer::drawer::FirstPassDrawer,
+        primitives::{instance::RawInstance, terrain::TerrainVertex},
+        renderer::{
+            drawer::{FirstPassDrawer, PostPassDrawer, SecondPassDrawer},
+            Renderer,
+        },
+        RenderMode,
     },
     scene::chunk::LogicChunk,
-    types::{F32x3, Rotation},
+    settings::Settings,
+    types::{F32x3, Mat4},
     window::{
+        binding::{GameInput, KeyBinding},
         event::{Event, Input},
         Window,
     },
@@ -29,13 +49,51 @@ use crate::{
 
 use self::{
     camera::{Camera, CameraController, CameraMode},
-    chunk::ChunkManager,
+    chunk::{ChunkManager, TerrainChunk},
+    debug::{DebugGizmos, DebugLines},
+    edit::BlockEdits,
+    effects::ScreenEffects,
+    entity::{Components, LocalEntities, LocalEntityId, RemoteEntities},
     figure::voxel::Voxel,
+    heatmap::ChunkHeatmap,
+    inset::{InsetView, ViewInset},
+    label::{Label, Labels},
+    loading::Loading,
+    map::MapView,
+    minimap::Minimap,
+    notice::Notice,
+    perf::{PerfHud, PerfStats},
+    prediction::Prediction,
+    selection::BlockSelection,
+    sim::SimClock,
+    sky::Sky,
+    spawn::MobSpawner,
+    survival::Survival,
 };
 
+pub mod ai;
+pub mod audio;
 pub mod camera;
 pub mod chunk;
+pub mod debug;
+pub mod edit;
+pub mod effects;
+pub mod entity;
 pub mod figure;
+pub mod heatmap;
+pub mod inset;
+pub mod label;
+pub mod loading;
+pub mod map;
+pub mod minimap;
+pub mod notice;
+pub mod perf;
+pub mod prediction;
+pub mod selection;
+pub mod sim;
+pub mod sky;
+pub mod spawn;
+pub mod survival;
 
 // FIX: Make implement PlayState to handle events
 /// Represents a world scene state
@@ -43,38 +101,117 @@ pub struct Scene {
     // Render
     pub model: GlobalModel,
     pub globals_bind_group: GlobalsBindGroup,
+    /// Environment of a clear day. Changed by the time of day and weather
+    pub environment: Environment,
+    pub sky: Sky,
+    /// Real time since the scene has been created (seconds). Used for shader animations
+    pub time: f64,
+    /// Underwater, damage and death effects
+    pub effects: ScreenEffects,
+    post_locals: Consts<PostLocals>,
+    post_bind_group: PostBindGroup,
+    barrier_locals: Consts<BarrierLocals>,
+    barrier_bind_group: BarrierBindGroup,
 
     // Camera
     pub camera: Camera,
     pub camera_controller: CameraController,
+    /// Top-down map replacing the camera view
+    pub map: MapView,
+    pub minimap: Minimap,
+    /// Second view in the frame corner
+    pub inset: ViewInset,
+    /// Frame statistics
+    pub perf_hud: PerfHud,
+    /// Messages shown in every build, e.g. the disconnect reason
+    pub notice: Notice,
+    /// Shown instead of the scene until the world around the camera is ready
+    pub loading: Option<Loading>,
 
     // World
     pub chunk_manager: ChunkManager,
+    pub block_edits: BlockEdits,
+    /// Local simulation (physics, entities, survival) time
+    pub sim: SimClock,
+    /// World changes published by the simulation. Readable on the frame after publishing
+    pub events: EventBus,
+    pub rules: GameRules,
+    /// Chunk the camera has been in on the last tick
+    player_chunk: ChunkId,
 
     // Objects
-    pub pyramid_vertices: Buffer<Vertex>,
+    pub pyramid_vertices: Buffer<TerrainVertex>,
     pub pyramid_indices: Buffer<u16>,
     pub voxel: Voxel,
-    pub voxel_instance: Instance,
-    pub voxel_instance_buffer: DynamicBuffer<RawInstance>,
+    pub entities: LocalEntities,
+    /// Entity following the camera in third person mode
+    pub avatar: LocalEntityId,
+    /// Ambient mobs of the local world
+    pub spawner: MobSpawner,
+    /// Survival mode state (local world only)
+    pub survival: Option<Survival>,
+    /// Block under the crosshair
+    pub picked: Option<RayHit>,
+    /// Outline of the picked block
+    pub selection: BlockSelection,
+    pub labels: Labels,
+    /// Show ids of chunks around the camera
+    pub show_chunk_labels: bool,
+    pub debug_lines: DebugLines,
+    /// Debug shapes added by other systems
+    pub gizmos: DebugGizmos,
+    /// Draw planned paths of mobs
+    pub show_paths: bool,
+    /// Draw camera frustum outline
+    pub show_frustum: bool,
+    /// Frustum (projection and view) to draw instead of the current one
+    pub frozen_frustum: Option<(Mat4, Mat4)>,
+    /// Chunk outlines colored by mesh statistics
+    pub chunk_heatmap: ChunkHeatmap,
+
+    // Network
+    pub net: Option<NetClient>,
+    /// Local player entity on the server
+    pub player_id: Option<EntityId>,
+    /// Why the last connection was closed (shown to the player)
+    pub disconnect_reason: Option<String>,
+    /// Last statistics received from the server
+    pub world_stats: Option<WorldStats>,
+    /// Last list of world backups received from the server
+    pub world_backups: Option<Vec<String>>,
+    /// Max interval between player inputs (sent even if player is idle)
+    input_interval: Duration,
+    input_sent: Instant,
+    pub prediction: Prediction,
+    pub remote_entities: RemoteEntities,
+    remote_instance_buffer: Option<DynamicBuffer<RawInstance>>,
+    /// Number of uploaded remote entity instances
+    remote_instance_count: u32,
 
-    // TODO: Store in settings
-    pub fps: u32,
+    /// Sleep before input sampling to reduce latency (vsync only)
+    pub latency_wait: bool,
 
     // UI
     force_cursor_grub: bool,
+    /// UI, graphics and controls, saved to [`SETTINGS_FILE`]. Changes are applied on the next tick
+    pub settings: Settings,
+    /// Input which the next pressed key is bound to, by its scan code if `true`
+    pub binding_capture: Option<(GameInput, bool)>,
+    /// Scale factor of the monitor the window is on. HUD is scaled by it
+    pub scale_factor: f32,
 
     #[cfg(feature = "debug_overlay")]
     pub show_overlay: bool,
 }
 
 impl Scene {
-    pub const FPS_MIN: u32 = 10;
-    pub const FPS_DEFAULT: u32 = 60;
-    pub const FPS_MAX: u32 = 360;
+    /// Max distance of block picking
+    pub const PICK_DISTANCE: f32 = 16.0;
+    /// Color multiplier of other players, so they stand out from local entities
+    pub const REMOTE_TINT: F32x3 = F32x3::new(0.8, 0.9, 1.15);
 
     /// Create new `Scene`
-    pub fn new(window: &mut Window) -> Self {
+    pub fn new(window: &mut Window, settings: Settings) -> Self {
         span!(_guard, "new", "Scene::new");
         window.grab_cursor(true);
         let renderer = window.renderer_mut();
@@ -86,14 +223,20 @@ impl Scene {
         };
 
         let globals_bind_group = renderer.bind_globals(&model);
+        let post_locals = renderer.create_consts(&[PostLocals::default()]);
+        let post_bind_group = renderer.bind_post(&post_locals);
+        let barrier_locals = renderer.create_consts(&[BarrierLocals::default()]);
+        let barrier_bind_group = renderer.bind_barrier(&barrier_locals);
 
-        let voxel_instance = Instance::new(F32x3::ZERO, Rotation::IDENTITY);
-        let voxel_instance_buffer = DynamicBuffer::new(&renderer.device, 1, BufferUsages::VERTEX);
-        voxel_instance_buffer.update(&renderer.queue, &[voxel_instance.as_raw()], 0);
+        let mut entities = LocalEntities::new();
+        let avatar = entities.spawn(Components {
+            persistent: true,
+            ..Default::default()
+        });
 
         let mut chunk_manager = ChunkManager::new();
 
-        chunk_manager.logic.insert(ChunkId::ZERO, {
+        chunk_manager.chunks.insert(ChunkId::ZERO, {
             let mut chunk = LogicChunk::new();
             chunk
                 .blocks_mut()
@@ -107,31 +250,135 @@ impl Scene {
         Self {
             model,
             globals_bind_group,
+            environment: Environment::default(),
+            sky: Sky::default(),
+            time: 0.0,
+            effects: ScreenEffects::default(),
+            post_locals,
+            post_bind_group,
+            barrier_locals,
+            barrier_bind_group,
 
             camera: Camera::new(
                 resolution.x as f32 / resolution.y as f32,
                 CameraMode::FirstPerson,
             ),
             camera_controller: CameraController::default(),
+            map: MapView::new(),
+            minimap: Minimap::new(),
+            inset: ViewInset::new(),
+            perf_hud: PerfHud::new(),
+            notice: Notice::new(),
+            loading: Some(Loading::new()),
 
             chunk_manager,
+            block_edits: BlockEdits::new(),
+            sim: SimClock::new(),
+            events: EventBus::new(),
+            rules: GameRules::new(),
+            player_chunk: ChunkId::new(0, 0, 0),
 
-            pyramid_vertices: Buffer::new(&renderer.device, Vertex::PYRAMID, BufferUsages::VERTEX),
-            pyramid_indices: Buffer::new(&renderer.device, Vertex::INDICES, BufferUsages::INDEX),
+            pyramid_vertices: Buffer::new(
+                &renderer.device,
+                TerrainVertex::PYRAMID,
+                BufferUsages::VERTEX,
+            ),
+            pyramid_indices: Buffer::new(
+                &renderer.device,
+                TerrainVertex::INDICES,
+                BufferUsages::INDEX,
+            ),
 
             voxel: Voxel::new(&renderer.device),
-            voxel_instance,
-            voxel_instance_buffer,
+            entities,
+            avatar,
+            spawner: MobSpawner::new(),
+
+            net: NetClient::from_env(),
+            player_id: None,
+            disconnect_reason: None,
+            world_stats: None,
+            world_backups: None,
+            input_interval: Duration::ZERO,
+            input_sent: Instant::now(),
+            prediction: Prediction::new(),
+            remote_entities: RemoteEntities::new(),
+            remote_instance_buffer: None,
+            remote_instance_count: 0,
+            survival: None,
+            picked: None,
+            selection: BlockSelection::new(),
+            labels: Labels::new(),
+            show_chunk_labels: false,
+            debug_lines: DebugLines::new(),
+            gizmos: DebugGizmos::new(),
+            show_paths: false,
+            show_frustum: false,
+            chunk_heatmap: ChunkHeatmap::default(),
+            frozen_frustum: None,
 
-            fps: Scene::FPS_DEFAULT,
+            latency_wait: false,
 
             force_cursor_grub: true,
+            settings,
+            binding_capture: None,
+            scale_factor: window.scale_factor() as f32,
 
             #[cfg(feature = "debug_overlay")]
             show_overlay: false,
         }
     }
 
+    /// Recreate GPU resources after the renderer has been recreated. Chunk meshes are rebuilt
+    pub fn rebind(&mut self, renderer: &mut Renderer) {
+        span!(_guard, "rebind", "Scene::rebind");
+
+        self.model = GlobalModel {
+            globals: renderer.create_consts(&[Globals::default()]),
+        };
+        self.globals_bind_group = renderer.bind_globals(&self.model);
+        self.post_locals = renderer.create_consts(&[PostLocals::default()]);
+        self.post_bind_group = renderer.bind_post(&self.post_locals);
+        self.barrier_locals = renderer.create_consts(&[BarrierLocals::default()]);
+        self.barrier_bind_group = renderer.bind_barrier(&self.barrier_locals);
+
+        self.pyramid_vertices = Buffer::new(
+            &renderer.device,
+            TerrainVertex::PYRAMID,
+            BufferUsages::VERTEX,
+        );
+        self.pyramid_indices = Buffer::new(
+            &renderer.device,
+            TerrainVertex::INDICES,
+            BufferUsages::INDEX,
+        );
+        self.voxel = Voxel::new(&renderer.device);
+
+        self.chunk_manager.clear_mesh();
+        self.entities.release_buffer();
+        self.labels.release_buffer();
+        self.debug_lines.release_buffer();
+        self.selection.release_buffer();
+        self.minimap.release_buffer();
+        self.inset.release_buffer();
+        self.perf_hud.release_buffer();
+        self.notice.release_buffer();
+        if let Some(loading) = &mut self.loading {
+            loading.release_buffer();
+        }
+        self.remote_instance_buffer = None;
+        self.remote_instance_count = 0;
+    }
+
+    /// Bind captured input and save the settings
+    fn bind(&mut self, input: GameInput, binding: KeyBinding) {
+        debug!(?input, ?binding, "Key bound");
+        self.settings.bindings.bind(input, binding);
+        if let Err(err) = self.settings.save(SETTINGS_FILE) {
+            warn!(%err, "Failed to save settings");
+        }
+    }
+
     fn toggle_cursor_grub(&mut self) {
         self.force_cursor_grub = !self.force_cursor_grub;
         self.camera_controller.reset();
@@ -144,37 +391,114 @@ impl Scene {
 
         let mut exit = false;
 
+        // Events of the previous tick become readable
+        self.events.update();
+
         // Handle events
         events.into_iter().for_each(|event| match event {
             Event::Close => exit = true,
-            Event::Resize(size) => self.camera.aspect = size.x as f32 / size.y as f32,
+            Event::Resize(size) => {
+                self.camera.aspect = size.x as f32 / size.y as f32;
+                #[cfg(feature = "debug_overlay")]
+                game.overlay.resize(size);
+            }
+            Event::ScaleFactor(scale_factor) => {
+                self.scale_factor = scale_factor;
+                #[cfg(feature = "debug_overlay")]
+                game.overlay
+                    .set_window_scale(scale_factor, game.window.renderer().resolution());
+            }
             // FIX: Abnormal touchpad sensitivity
-            Event::MouseMove(delta, true) => self.camera.rotate(delta),
+            Event::MouseMove(delta, true) => self
+                .camera
+                .rotate(delta * self.settings.controls.sensitivity),
+            Event::Zoom(delta, true) if self.map.enabled => self.map.scroll(delta),
             Event::Zoom(delta, true) => self.camera.zoom(delta),
-            Event::Input(Input::Key(key), state, modifiers) => {
-                match key {
-                    VirtualKeyCode::Escape => exit = true,
-                    VirtualKeyCode::P if matches!(state, ElementState::Released) => {
-                        self.toggle_cursor_grub()
+            // Key is bound once released, so it doesn't trigger the new input. Escape cancels
+            Event::Input(Input::Key { key, scan_code }, state, _)
+                if self.binding_capture.is_some() =>
+            {
+                if matches!(state, ElementState::Released) {
+                    let (input, physical) = self.binding_capture.take().unwrap();
+                    let binding = match key {
+                        Some(VirtualKeyCode::Escape) => None,
+                        Some(key) if !physical => Some(KeyBinding::Key(key)),
+                        _ => Some(KeyBinding::ScanCode(scan_code)),
+                    };
+                    if let Some(binding) = binding {
+                        self.bind(input, binding);
                     }
-                    #[cfg(feature = "debug_overlay")]
-                    VirtualKeyCode::F3
-                        if matches!(state, ElementState::Released) && modifiers.shift() =>
-                    {
-                        game.overlay.toggle_top_bar();
+                }
+            }
+            // Buttons are bound once pressed, as the click starting the capture is released later
+            Event::Input(Input::Mouse(button), state, _) if self.binding_capture.is_some() => {
+                if matches!(state, ElementState::Pressed) {
+                    let (input, _) = self.binding_capture.take().unwrap();
+                    self.bind(input, KeyBinding::Mouse(button));
+                }
+            }
+            Event::Input(key, state, modifiers) => {
+                // Fixed key
+                if let Input::Key {
+                    key: Some(VirtualKeyCode::Escape),
+                    ..
+                } = key
+                {
+                    exit = true;
+                }
+
+                if let Some(input) = self.settings.bindings.input(key) {
+                    match input {
+                        GameInput::ToggleCursor if matches!(state, ElementState::Released) => {
+                            self.toggle_cursor_grub()
+                        }
+                        GameInput::ToggleMap if matches!(state, ElementState::Released) => {
+                            self.map.toggle()
+                        }
+                        GameInput::ToggleMinimap if matches!(state, ElementState::Released) => {
+                            self.minimap.enabled = !self.minimap.enabled
+                        }
+                        GameInput::MinimapZoomIn if matches!(state, ElementState::Released) => {
+                            self.minimap.zoom_in()
+                        }
+                        GameInput::MinimapZoomOut if matches!(state, ElementState::Released) => {
+                            self.minimap.zoom_out()
+                        }
+                        GameInput::PauseSimulation if matches!(state, ElementState::Released) => {
+                            self.sim.toggle_pause()
+                        }
+                        GameInput::StepSimulation if matches!(state, ElementState::Released) => {
+                            self.sim.step(1)
+                        }
+                        GameInput::TogglePerfHud if matches!(state, ElementState::Released) => {
+                            self.perf_hud.toggle()
+                        }
+                        #[cfg(feature = "debug_overlay")]
+                        GameInput::ToggleOverlay
+                            if matches!(state, ElementState::Released) && modifiers.shift() =>
+                        {
+                            game.overlay.toggle_top_bar();
+                        }
+                        #[cfg(feature = "debug_overlay")]
+                        GameInput::ToggleOverlay if matches!(state, ElementState::Released) => {
+                            self.show_overlay = !self.show_overlay
+                        }
+                        _ => {}
                     }
-                    #[cfg(feature = "debug_overlay")]
-                    VirtualKeyCode::F3 if matches!(state, ElementState::Released) => {
-                        self.show_overlay = !self.show_overlay
+
+                    if self.force_cursor_grub {
+                        self.camera_controller.game_input(input, state);
                     }
-                    _ => {}
                 }
-
-                if self.force_cursor_grub {
-                    self.camera_controller.virtual_key(key, state);
+            }
+            Event::Focused(focused) => {
+                self.force_cursor_grub = focused;
+                if focused {
+                    debug!("Window focused, leaving reduced work mode");
+                } else {
+                    debug!("Window unfocused, entering reduced work mode");
                 }
             }
-            Event::Focused(focused) => self.force_cursor_grub = focused,
             _ => {}
         });
 
@@ -182,54 +506,713 @@ impl Scene {
         #[cfg(feature = "debug_overlay")]
         game.overlay.update(crate::egui::DebugPayload {
             clock_stats: game.clock.stats(),
+            pacer_stats: game.pacer.stats(),
             scene: self,
             renderer: game.window.renderer_mut(),
+            runtime: &game.runtime,
         });
 
+        // Local simulation runs in fixed steps
+        let sim_steps = self.sim.advance(tick_dur);
+
         // Update camera
         self.camera.update(tick_dur);
-        self.camera_controller
-            .move_camera(&mut self.camera, tick_dur);
-        game.window.renderer().update_consts(
+        if self.player_id.is_some() {
+            self.predict_movement(tick_dur);
+        } else if let Some(survival) = &mut self.survival {
+            for _ in 0..sim_steps {
+                if Self::survival_movement(
+                    survival,
+                    &mut self.camera,
+                    &self.camera_controller,
+                    &self.chunk_manager,
+                    SimClock::STEP,
+                ) {
+                    self.effects.hurt();
+                }
+            }
+        } else {
+            self.camera_controller
+                .move_camera(&mut self.camera, tick_dur, |pos| {
+                    self.chunk_manager.solid(pos)
+                });
+        }
+        let player_chunk = GlobalCoord::from_vec3(self.camera.pos).to_chunk_id();
+        if player_chunk != self.player_chunk {
+            self.events.publish(WorldEvent::PlayerMovedChunk {
+                from: self.player_chunk,
+                to: player_chunk,
+            });
+            self.player_chunk = player_chunk;
+        }
+        self.time += tick_dur.as_secs_f64();
+        // Server time keeps running while the local simulation is paused
+        self.sky.advance(if self.net.is_some() {
+            tick_dur.as_secs_f32()
+        } else {
+            SimClock::STEP.as_secs_f32() * sim_steps as f32
+        });
+        let (proj_mat, view_mat) = self.matrices();
+        let environment = if self.map.enabled {
+            MapView::environment(&self.current_environment())
+        } else {
+            self.current_environment()
+        };
+        self.update_effects(game.window.renderer(), tick_dur);
+        if let Err(err) = game.window.renderer().update_consts(
             &self.model.globals,
-            &[Globals::new(self.camera.proj_mat(), self.camera.view_mat())],
+            &[Globals::new(
+                proj_mat,
+                view_mat,
+                &environment,
+                game.window.renderer().debug_view,
+                self.time as f32,
+                tick_dur.as_secs_f32(),
+                game.window.renderer().resolution().as_vec2(),
+            )],
+        ) {
+            warn!(%err, "Failed to update globals");
+        }
+        if let Some(view) = self.inset.view {
+            let (proj_mat, view_mat) =
+                ViewInset::matrices(view, &self.camera, &self.map, self.frozen_frustum);
+            let environment = match view {
+                InsetView::TopDown => MapView::environment(&self.current_environment()),
+                InsetView::Frustum => self.current_environment(),
+            };
+            let renderer = game.window.renderer();
+            self.inset.update(
+                renderer,
+                Globals::new(
+                    proj_mat,
+                    view_mat,
+                    &environment,
+                    renderer.debug_view,
+                    self.time as f32,
+                    tick_dur.as_secs_f32(),
+                    self.inset.viewport(renderer.resolution()).size.as_vec2(),
+                ),
+            );
+        }
+
+        self.picked = self.chunk_manager.raycast(
+            Ray::new(self.camera.pos, self.camera.forward()),
+            Self::PICK_DISTANCE,
         );
+        // Nothing is aimed at in the map and while loading
+        let aimed = self
+            .picked
+            .filter(|_| !self.map.enabled && self.loading.is_none());
+        self.selection
+            .update(game.window.renderer(), aimed.map(|hit| hit.pos));
 
+        // Network
+        self.handle_server_messages();
+        self.update_remote_entities(game.window.renderer());
+
+        // Non-essential work is reduced while the window isn't focused. Loading isn't throttled
+        let background = !game.window.focused;
+        self.chunk_manager.throttled = background && self.loading.is_none();
         self.chunk_manager
-            .maintain(&game.window.renderer().device, &game.runtime, &self.camera);
+            .jobs
+            .set_workers(&game.runtime, self.settings.threads.blocking_threads());
+        self.chunk_manager.vertical_bias = self.settings.chunks.vertical_bias;
+        self.chunk_manager.draw_distance = self.settings.graphics.draw_distance;
+        let renderer = game.window.renderer_mut();
+        if renderer.render_mode().present_mode != self.settings.graphics.present_mode {
+            renderer.set_render_mode(RenderMode {
+                present_mode: self.settings.graphics.present_mode,
+                ..renderer.render_mode().clone()
+            });
+        }
+        // Fog is fitted only when the view distance changes, so it can still be tweaked
+        let far = self
+            .camera
+            .far_override
+            .unwrap_or_else(|| Camera::far_for(self.chunk_manager.draw_distance));
+        if far != self.camera.far {
+            self.camera.far = far;
+            self.environment.fit_fog(far);
+        }
+        self.chunk_manager.maintain(
+            game.window.renderer(),
+            &game.runtime,
+            &self.camera,
+            self.net.as_ref(),
+        );
+        self.events.extend(self.chunk_manager.take_events());
+        if let Some(loading) = &mut self.loading {
+            if loading.update(
+                &game.window,
+                &self.chunk_manager,
+                &self.camera,
+                &self.sky.apply(&self.environment),
+            ) {
+                self.loading = None;
+            }
+        }
+
+        // Changed columns are kept, so the minimap catches up on focus
+        self.minimap.collect(&self.events);
+        if !background {
+            self.minimap.update(
+                game.window.renderer(),
+                &self.chunk_manager,
+                &self.camera,
+                &self.settings.ui,
+                self.scale_factor,
+            );
+        }
+        if self.perf_hud.enabled {
+            let renderer = game.window.renderer();
+            let pacer = game.pacer.stats();
+            let stats = PerfStats {
+                frame_time: pacer.avg_interval,
+                max_work: pacer.max_work,
+                cpu_time: game.clock.stats().avg_tick_dur,
+                draw_calls: renderer.draw_calls(),
+            };
+            self.perf_hud
+                .update(renderer, &stats, self.settings.ui.scale * self.scale_factor);
+        }
+        self.notice.update(
+            game.window.renderer(),
+            tick_dur,
+            self.settings.ui.scale * self.scale_factor,
+        );
 
-        // Update voxel position
+        // Update avatar position
         if matches!(self.camera.mode, CameraMode::ThirdPerson) {
-            self.voxel_instance.position = self.camera.pos;
-            game.window.renderer().update_dynamic_buffer(
-                &self.voxel_instance_buffer,
-                &[self.voxel_instance.as_raw()],
+            if let Some(avatar) = self.entities.get_mut(self.avatar) {
+                avatar.pos = self.camera.pos;
+            }
+        }
+
+        let chunk_manager = &self.chunk_manager;
+        for _ in 0..sim_steps {
+            self.entities
+                .tick(SimClock::STEP.as_secs_f32(), |pos| chunk_manager.solid(pos));
+        }
+        let load_area = self.chunk_manager.load_area(&self.camera);
+        if self.net.is_none() && self.loading.is_none() {
+            self.spawner.tick(
+                &mut self.entities,
+                &self.chunk_manager,
+                &load_area,
+                self.camera.pos,
+                self.sky.daylight(),
+                &self.rules,
             );
         }
+        self.entities.despawn_outside(&load_area);
+        self.entities.upload(game.window.renderer());
+
+        if !background {
+            self.update_labels(game.window.renderer());
+            self.update_debug_lines(game.window.renderer());
+        }
 
         game.window.grab_cursor(self.force_cursor_grub);
 
         exit
     }
 
+    /// Apply messages received from the server
+    fn handle_server_messages(&mut self) {
+        span!(
+            _guard,
+            "handle_server_messages",
+            "Scene::handle_server_messages"
+        );
+
+        let Some(net) = &self.net else {
+            return;
+        };
+
+        for msg in net.fetch() {
+            match msg {
+                ServerMsg::Welcome {
+                    entity_id,
+                    tps,
+                    spawn,
+                    compression,
+                    bounds,
+                } => {
+                    info!(entity_id, tps, ?compression, ?bounds, "Joined the server");
+                    self.player_id = Some(entity_id);
+                    self.input_interval = Clock::tps_to_duration(tps);
+                    self.prediction.clear();
+                    self.block_edits.clear();
+                    self.camera.pos = spawn;
+                    self.camera.f_pos = spawn;
+                    // Drop locally generated world
+                    self.chunk_manager.bounds = bounds;
+                    self.chunk_manager.chunks.clear();
+                    self.chunk_manager.chunk_gen_ids.clear();
+                }
+                ServerMsg::ChunkData { id, chunk } => self.chunk_manager.insert(id, chunk),
+                ServerMsg::ChunkDelta { id, changes } => {
+                    self.chunk_manager.apply_delta(id, &changes);
+                }
+                ServerMsg::EntitySpawn {
+                    id,
+                    kind,
+                    name,
+                    pos,
+                    rot,
+                } => {
+                    if Some(id) != self.player_id {
+                        self.remote_entities.spawn(id, kind, name, pos, rot);
+                        self.events.publish(WorldEvent::EntitySpawned { id, kind });
+                    }
+                }
+                ServerMsg::EntityState { id, pos, rot } => {
+                    if Some(id) != self.player_id {
+                        self.remote_entities.push_state(id, pos, rot);
+                    }
+                }
+                ServerMsg::EntityDespawn(id) => self.remote_entities.despawn(id),
+                ServerMsg::PlayerAck { seq, pos } => {
+                    let chunk_manager = &self.chunk_manager;
+                    self.camera.f_pos = self
+                        .prediction
+                        .reconcile(seq, pos, |pos| chunk_manager.solid(pos));
+                }
+                ServerMsg::WorldTime { time, weather } => {
+                    self.sky.time = time;
+                    self.sky.weather = weather;
+                }
+                ServerMsg::WorldStats(stats) => self.world_stats = Some(stats),
+                ServerMsg::Backups(names) => self.world_backups = Some(names),
+                ServerMsg::Teleport { pos } => {
+                    info!(%pos, "Teleported by server");
+                    self.prediction.clear();
+                    self.camera.pos = pos;
+                    self.camera.f_pos = pos;
+                }
+                ServerMsg::BlockEditAck {
+                    id,
+                    accepted,
+                    pos,
+                    block,
+                } => {
+                    self.block_edits
+                        .acknowledge(&mut self.chunk_manager, id, accepted, pos, block)
+                }
+                // Handled by the network task
+                ServerMsg::Pong(_) => {}
+                ServerMsg::Disconnect { reason } => {
+                    warn!(%reason, "Disconnected by server");
+                    self.disconnected(reason);
+                    return;
+                }
+            }
+        }
+
+        if !net.connected() {
+            warn!("Connection to server lost");
+            self.disconnected("Connection to server lost".to_string());
+        }
+    }
+
+    /// Apply movement input locally and send it to the server
+    fn predict_movement(&mut self, tick_dur: Duration) {
+        let Some(net) = &self.net else {
+            return;
+        };
+
+        let input = self.camera_controller.input(&self.camera, tick_dur);
+
+        // Don't flood the server while player stays still
+        let idle = input.forward == 0.0 && input.strafe == 0.0 && input.lift == 0.0;
+        if idle && self.input_sent.elapsed() < self.input_interval {
+            return;
+        }
+
+        let input = self.prediction.push(input);
+        self.camera.f_pos =
+            input.apply_collide(self.camera.f_pos, |pos| self.chunk_manager.solid(pos));
+        self.input_sent = Instant::now();
+        net.send(ClientMsg::PlayerInput(input));
+    }
+
+    /// Walk (no flying) under gravity taking environmental damage.
+    ///
+    /// Returns `true` if the player has been hurt
+    fn survival_movement(
+        survival: &mut Survival,
+        camera: &mut Camera,
+        controller: &CameraController,
+        chunk_manager: &ChunkManager,
+        tick_dur: Duration,
+    ) -> bool {
+        if !survival.is_dead() {
+            let mut input = controller.input(camera, tick_dur);
+            input.lift = 0.0;
+            camera.f_pos = input.apply_collide(camera.f_pos, |pos| chunk_manager.solid(pos));
+        }
+
+        let was_dead = survival.is_dead();
+        let hurt = survival.tick(&mut camera.f_pos, tick_dur.as_secs_f32(), |pos| {
+            chunk_manager.block(pos)
+        });
+        if let Some(source) = hurt.filter(|_| survival.is_dead()) {
+            info!(?source, "Player died");
+        }
+        // Don't fly smoothly across the world on respawn
+        if was_dead && !survival.is_dead() {
+            camera.pos = camera.f_pos;
+        }
+
+        hurt.is_some()
+    }
+
+    /// Update screen effects flags from the camera state and upload them
+    fn update_effects(&mut self, renderer: &Renderer, tick_dur: Duration) {
+        // Map view and loading screen aren't seen through the camera
+        let in_view = !self.map.enabled && self.loading.is_none();
+        self.effects.underwater = self
+            .chunk_manager
+            .block(GlobalCoord::from_vec3(self.camera.pos))
+            .filter(|block| in_view && block.liquid());
+        self.effects.dead = self.survival.as_ref().is_some_and(Survival::is_dead);
+        self.effects.advance(tick_dur.as_secs_f32());
+
+        // Crosshair is a part of HUD
+        let (length, thickness) = if renderer.draw_stages.hud && in_view && !self.effects.dead {
+            self.settings.ui.crosshair(self.scale_factor)
+        } else {
+            (0.0, 0.0)
+        };
+        let locals =
+            self.effects
+                .locals()
+                .with_crosshair(length, thickness, self.settings.ui.hud_opacity);
+        if let Err(err) = renderer.update_consts(&self.post_locals, &[locals]) {
+            warn!(%err, "Failed to update screen effects");
+        }
+        let barrier = BarrierLocals::new(&self.chunk_manager.bounds);
+        if let Err(err) = renderer.update_consts(&self.barrier_locals, &[barrier]) {
+            warn!(%err, "Failed to update world border");
+        }
+    }
+
+    /// Interpolate remote entities and upload their instances
+    fn update_remote_entities(&mut self, renderer: &Renderer) {
+        self.remote_entities.update();
+
+        let instances = self
+            .remote_entities
+            .instances()
+            .iter()
+            .map(|instance| instance.as_raw())
+            .collect::<Vec<_>>();
+
+        self.remote_instance_count = 0;
+        if instances.is_empty() {
+            return;
+        }
+
+        let buffer = self.remote_instance_buffer.get_or_insert_with(|| {
+            DynamicBuffer::new(&renderer.device, instances.len(), BufferUsages::VERTEX)
+        });
+        buffer.ensure_capacity(&renderer.device, instances.len());
+
+        match renderer.update_dynamic_buffer(buffer, &instances) {
+            Ok(()) => self.remote_instance_count = instances.len() as u32,
+            Err(err) => warn!(%err, "Failed to upload remote entities"),
+        }
+    }
+
+    /// Projection and view matrices used for rendering (map or camera)
+    pub fn matrices(&self) -> (Mat4, Mat4) {
+        if self.map.enabled {
+            self.map.matrices(&self.camera)
+        } else {
+            (self.camera.proj_mat(), self.camera.view_mat())
+        }
+    }
+
+    /// Collect debug lines and upload them
+    fn update_debug_lines(&mut self, renderer: &Renderer) {
+        self.debug_lines.clear();
+
+        if self.show_frustum {
+            let view_proj = self.frozen_frustum.map_or_else(
+                || self.camera.proj_mat() * self.camera.view_mat(),
+                |(proj, view)| proj * view,
+            );
+            self.debug_lines
+                .frustum(view_proj, self.settings.ui.debug_palette);
+        }
+        if self.show_paths {
+            const PATH_COLOR: [u8; 4] = [255, 255, 255, 255];
+            // Lines go through the block centers a mob stands in
+            const OFFSET: F32x3 = F32x3::new(0.0, 0.5, 0.0);
+
+            for (_, mob) in self.entities.iter() {
+                let Some(wander) = &mob.wander else {
+                    continue;
+                };
+                let mut from = mob.pos + OFFSET;
+                for step in wander.path() {
+                    let to = step.as_vec() + OFFSET;
+                    self.gizmos.draw_line(from, to, PATH_COLOR, 1);
+                    from = to;
+                }
+            }
+        }
+        self.gizmos.collect(&mut self.debug_lines);
+        self.chunk_heatmap.update(
+            &mut self.debug_lines,
+            &self.chunk_manager.chunks,
+            self.chunk_manager.frame(),
+            self.settings.ui.debug_palette,
+        );
+
+        self.debug_lines.upload(renderer);
+    }
+
+    fn update_labels(&mut self, renderer: &Renderer) {
+        const NAME_OFFSET: F32x3 = F32x3::new(0.0, 0.5, 0.0);
+        const NAME_COLOR: [u8; 3] = [255, 255, 255];
+        /// Chunk labels are shown only for chunks this close to the camera (in chunks)
+        const CHUNK_LABEL_RADIUS: i64 = 1;
+
+        self.labels.clear();
+        self.labels
+            .set_font(renderer.assets(), self.settings.ui.font.as_deref());
+        self.labels.pixel_size = Labels::DEFAULT_PIXEL_SIZE * self.settings.ui.font_scale();
+
+        self.remote_entities
+            .inner
+            .values()
+            .filter(|entity| !entity.name.is_empty())
+            .for_each(|entity| {
+                self.labels.push(Label::new(
+                    entity.name.clone(),
+                    entity.pos + NAME_OFFSET,
+                    NAME_COLOR,
+                ))
+            });
+
+        if self.show_chunk_labels {
+            let center = GlobalCoord::from_vec3(self.camera.pos).to_chunk_id();
+
+            self.chunk_manager
+                .chunks
+                .iter()
+                .filter(|(id, _)| {
+                    (id.x - center.x).abs() <= CHUNK_LABEL_RADIUS
+                        && (id.y - center.y).abs() <= CHUNK_LABEL_RADIUS
+                        && (id.z - center.z).abs() <= CHUNK_LABEL_RADIUS
+                })
+                .for_each(|(id, chunk)| {
+                    // Colored by the mesh status
+                    self.labels.push(Label::new(
+                        format!("{},{},{}", id.x, id.y, id.z),
+                        id.to_coord().as_vec() + CHUNK_SIZE as f32 / 2.0,
+                        self.settings.ui.debug_palette.chunk_status(chunk.status()),
+                    ))
+                });
+        }
+
+        self.labels.upload(renderer, self.camera.pos);
+    }
+
+    /// Replace block colors. Terrain and the minimap are rebuilt with the new colors
+    pub fn set_palette(&mut self, palette: Palette) {
+        info!(name = palette.name(), "Changing palette");
+        self.chunk_manager.set_palette(palette);
+        self.minimap.release_buffer();
+    }
+
+    /// Spawn local entity. It's despawned automatically when leaves the load area (unless
+    /// persistent)
+    pub fn spawn(&mut self, components: Components) -> LocalEntityId {
+        self.entities.spawn(components)
+    }
+
+    pub fn despawn(&mut self, id: LocalEntityId) -> Option<Components> {
+        self.entities.despawn(id)
+    }
+
+    /// Environment at the current time of day and weather
+    pub fn current_environment(&self) -> Environment {
+        self.sky.apply(&self.environment)
+    }
+
+    /// Close server connection and return to local world
+    pub fn disconnect(&mut self) {
+        self.net = None;
+        self.player_id = None;
+        self.prediction.clear();
+        self.block_edits.clear();
+        self.remote_entities.clear();
+        self.remote_instance_buffer = None;
+        self.remote_instance_count = 0;
+        self.chunk_manager.chunk_gen_ids.clear();
+        self.chunk_manager.bounds = WorldBounds::default();
+    }
+
+    /// Leave the server and tell the player why
+    fn disconnected(&mut self, reason: String) {
+        self.disconnect();
+        self.notice.show(format!("Disconnected: {reason}"));
+        self.disconnect_reason = Some(reason);
+    }
+
+    /// Terrain chunks in the view frustum. Culled against the frozen frustum too, so culling can
+    /// be inspected from outside
+    fn visible_terrain(&self) -> Vec<&TerrainChunk> {
+        let (proj_mat, view_mat) = self.frozen_frustum.unwrap_or_else(|| self.matrices());
+        let frustum = Frustum::from_matrix(proj_mat * view_mat);
+
+        self.chunk_manager
+            .chunks
+            .meshes()
+            .filter(|(id, _)| {
+                let min = id.to_coord().as_vec();
+                frustum.intersects_aabb(&Aabb::new(min, min + CHUNK_SIZE as f32))
+            })
+            .map(|(_, chunk)| chunk)
+            .collect()
+    }
+
     /// Draw in-game objects
     pub fn draw<'a>(&'a self, mut drawer: FirstPassDrawer<'a>) {
         span!(_guard, "draw", "Scene::draw");
 
+        if let Some(bind_group) = self.loading.as_ref().and_then(Loading::bind_group) {
+            drawer.draw_loading(bind_group);
+            return;
+        }
+
+        self.draw_objects(&mut drawer);
+    }
+
+    /// Draw the scene from the inset point of view. Terrain is culled for the main view
+    pub fn draw_inset<'a>(&'a self, mut drawer: FirstPassDrawer<'a>) {
+        span!(_guard, "draw_inset", "Scene::draw_inset");
+
+        self.draw_objects(&mut drawer);
+        if let Some((vertices, count)) = self.debug_lines.vertices() {
+            drawer.draw_lines(vertices, count);
+        }
+    }
+
+    /// Draw terrain and figures
+    fn draw_objects<'a>(&'a self, drawer: &mut FirstPassDrawer<'a>) {
         // Draw "terrain"
         {
             // Test pyramid
             drawer.draw_pyramid(&self.pyramid_vertices, &self.pyramid_indices);
 
-            let mut drawer = drawer.terrain_drawer();
+            let visible = self.visible_terrain();
 
-            self.chunk_manager
-                .terrain
-                .values()
-                .for_each(|chunk| drawer.draw(chunk));
+            let mut terrain_drawer = drawer.terrain_drawer(&self.chunk_manager.arena);
+            visible.iter().for_each(|chunk| {
+                chunk.mark_drawn(self.chunk_manager.frame());
+                terrain_drawer.draw(chunk)
+            });
+            drop(terrain_drawer);
+
+            // Plants are drawn after opaque terrain, so more fragments are rejected by depth
+            let mut cutout_drawer = drawer.cutout_drawer(&self.chunk_manager.arena);
+            visible.iter().for_each(|chunk| cutout_drawer.draw(chunk));
+            drop(cutout_drawer);
+
+            let mut decoration_drawer = drawer.decoration_drawer();
+            visible
+                .iter()
+                .for_each(|chunk| decoration_drawer.draw(chunk));
         }
 
         // Draw figures
-        drawer.draw_figure(&self.voxel, &self.voxel_instance_buffer);
+        if let Some((instances, count)) = self.entities.instances() {
+            drawer.draw_figure_instances(&self.voxel, instances, count, F32x3::ONE);
+        }
+        if let Some(instances) = self
+            .remote_instance_buffer
+            .as_ref()
+            .filter(|_| self.remote_instance_count > 0)
+        {
+            drawer.draw_figure_instances(
+                &self.voxel,
+                instances,
+                self.remote_instance_count,
+                Self::REMOTE_TINT,
+            );
+        }
+    }
+
+    /// Draw fluids and blended geometry
+    pub fn draw_second_pass<'a>(&'a self, mut drawer: SecondPassDrawer<'a>) {
+        span!(_guard, "draw_second_pass", "Scene::draw_second_pass");
+
+        if self
+            .loading
+            .as_ref()
+            .and_then(Loading::bind_group)
+            .is_some()
+        {
+            return;
+        }
+
+        // Blended without depth writes, so far surfaces go first
+        let mut visible = self.visible_terrain();
+        visible.sort_by(|a, b| {
+            let distance = |chunk: &TerrainChunk| {
+                (chunk.origin + CHUNK_SIZE as f32 / 2.0).distance_squared(self.camera.pos)
+            };
+            distance(b).total_cmp(&distance(a))
+        });
+        let mut fluid_drawer = drawer.fluid_drawer(&self.chunk_manager.arena);
+        visible.iter().for_each(|chunk| fluid_drawer.draw(chunk));
+        drop(fluid_drawer);
+
+        if self
+            .chunk_manager
+            .bounds
+            .border_distance(self.camera.pos)
+            .is_some_and(|distance| distance < BarrierLocals::DISTANCE)
+        {
+            drawer.draw_barrier(&self.barrier_bind_group);
+        }
+
+        if let Some((vertices, count)) = self.selection.vertices() {
+            drawer.draw_selection(vertices, count);
+        }
+
+        if let Some((vertices, count)) = self.debug_lines.vertices() {
+            drawer.draw_lines(vertices, count);
+        }
+
+        // Draw labels (blended, so after everything else)
+        if let Some((vertices, count)) = self.labels.vertices() {
+            drawer.draw_labels(vertices, count);
+        }
+    }
+
+    /// Draw the scene to the screen with effects, then HUD
+    pub fn draw_post_pass<'a>(&'a self, mut drawer: PostPassDrawer<'a>) {
+        span!(_guard, "draw_post_pass", "Scene::draw_post_pass");
+
+        drawer.draw_post(&self.post_bind_group);
+
+        // Map view already shows the same
+        if let Some(bind_group) = self
+            .minimap
+            .bind_group()
+            .filter(|_| !self.map.enabled && self.loading.is_none())
+        {
+            drawer.draw_minimap(bind_group);
+        }
+        if let Some((vertices, count)) = self.perf_hud.vertices() {
+            drawer.draw_text(vertices, count);
+        }
+        if let Some((vertices, count)) = self.notice.vertices() {
+            drawer.draw_text(vertices, count);
+        }
     }
 }
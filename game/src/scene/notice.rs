@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use tracing::warn;
+use wgpu::BufferUsages;
+
+use crate::{
+    render::{
+        buffer::DynamicBuffer, font::text_width, primitives::text::TextVertex, renderer::Renderer,
+    },
+    types::{F32x2, U32x2},
+};
+
+use super::perf::PerfHud;
+
+/// Message in the top center of the screen drawn with the built-in font, e.g. why the server
+/// disconnected. Unlike the debug overlay it's in every build
+pub struct Notice {
+    text: Option<String>,
+    /// Time until the message is hidden
+    remaining: Duration,
+    buffer: Option<DynamicBuffer<TextVertex>>,
+    vertex_count: u32,
+}
+
+impl Notice {
+    pub const DURATION: Duration = Duration::from_secs(10);
+    /// Distance from the top of the screen to the bottom of the text in pixels (before UI scaling)
+    pub const MARGIN: f32 = 64.0;
+    const MIN_CAPACITY: usize = 4096;
+
+    pub const fn new() -> Self {
+        Self {
+            text: None,
+            remaining: Duration::ZERO,
+            buffer: None,
+            vertex_count: 0,
+        }
+    }
+
+    /// Show the message for [`Self::DURATION`], replacing the current one
+    pub fn show(&mut self, text: impl Into<String>) {
+        self.text = Some(text.into());
+        self.remaining = Self::DURATION;
+    }
+
+    /// Build text quads with shadows centered horizontally at the top of the screen
+    pub fn mesh(text: &str, resolution: U32x2, scale: f32) -> Vec<TextVertex> {
+        let pixel_size = PerfHud::PIXEL_SIZE * scale;
+        let width = text_width(text) as f32 * pixel_size;
+        let origin = F32x2::new(
+            ((resolution.x as f32 - width) / 2.0).round(),
+            Self::MARGIN * scale,
+        );
+
+        let mut vertices = Vec::new();
+        PerfHud::line(&mut vertices, origin, text, pixel_size);
+        vertices
+    }
+
+    /// Count down the display time and upload the text
+    pub fn update(&mut self, renderer: &Renderer, dt: Duration, scale: f32) {
+        self.remaining = self.remaining.saturating_sub(dt);
+        if self.remaining.is_zero() {
+            self.text = None;
+        }
+        let Some(text) = &self.text else {
+            self.vertex_count = 0;
+            return;
+        };
+
+        let vertices = Self::mesh(text, renderer.resolution(), scale);
+        self.vertex_count = vertices.len() as u32;
+
+        let buffer = self.buffer.get_or_insert_with(|| {
+            DynamicBuffer::new(&renderer.device, Self::MIN_CAPACITY, BufferUsages::VERTEX)
+        });
+        buffer.ensure_capacity(&renderer.device, vertices.len());
+
+        if let Err(err) = renderer.update_dynamic_buffer(buffer, &vertices) {
+            warn!(%err, "Failed to upload notice");
+        }
+    }
+
+    /// Drop vertex buffer. It's created again on the next update
+    pub fn release_buffer(&mut self) {
+        self.buffer = None;
+        self.vertex_count = 0;
+    }
+
+    /// Vertex buffer and number of used vertices. `None` if there is no message
+    pub fn vertices(&self) -> Option<(&DynamicBuffer<TextVertex>, u32)> {
+        self.buffer
+            .as_ref()
+            .filter(|_| self.vertex_count > 0)
+            .map(|buffer| (buffer, self.vertex_count))
+    }
+}
+
+impl Default for Notice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{render::font::text_width, scene::perf::PerfHud, types::U32x2};
+
+    use super::Notice;
+
+    #[test]
+    fn text_in_top_center() {
+        let resolution = U32x2::new(800, 600);
+        let vertices = Notice::mesh("Incompatible version", resolution, 1.0);
+
+        let (min, max) = vertices
+            .iter()
+            .fold((vertices[0].pos, vertices[0].pos), |(min, max), vertex| {
+                (min.min(vertex.pos), max.max(vertex.pos))
+            });
+        let width = text_width("Incompatible version") as f32 * PerfHud::PIXEL_SIZE;
+        assert!(min.x >= (resolution.x as f32 - width) / 2.0);
+        // Shadow sticks out by a font pixel
+        assert!(max.x <= (resolution.x as f32 + width) / 2.0 + PerfHud::PIXEL_SIZE);
+        assert_eq!(max.y, Notice::MARGIN + PerfHud::PIXEL_SIZE);
+        assert!(max.y < resolution.y as f32 / 2.0);
+    }
+}
@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use tracing::warn;
+use wgpu::BufferUsages;
+
+use crate::{
+    render::{
+        buffer::DynamicBuffer,
+        font::{text_pixels, GLYPH_HEIGHT},
+        primitives::text::TextVertex,
+        renderer::Renderer,
+    },
+    types::{F32x2, U32x2},
+};
+
+/// Frame numbers shown by [`PerfHud`]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PerfStats {
+    /// Average present interval
+    pub frame_time: Duration,
+    /// Slowest recent frame (without swapchain waits)
+    pub max_work: Duration,
+    /// Average CPU time of a tick
+    pub cpu_time: Duration,
+    /// Draw calls of the last frame
+    pub draw_calls: u32,
+}
+
+impl PerfStats {
+    pub fn lines(&self) -> [String; 3] {
+        let ms = |duration: Duration| duration.as_secs_f32() * 1000.0;
+        let fps = match self.frame_time.as_secs_f32() {
+            time if time > 0.0 => 1.0 / time,
+            _ => 0.0,
+        };
+
+        [
+            format!("FPS: {fps:.0}  Frame: {:.1} ms", ms(self.frame_time)),
+            format!(
+                "CPU: {:.1} ms  Max: {:.1} ms",
+                ms(self.cpu_time),
+                ms(self.max_work)
+            ),
+            format!("Draw calls: {}", self.draw_calls),
+        ]
+    }
+}
+
+/// Frame statistics in the bottom left corner, drawn with the built-in font.
+///
+/// Unlike the debug overlay it's in every build, so players can report performance numbers
+pub struct PerfHud {
+    pub enabled: bool,
+    buffer: Option<DynamicBuffer<TextVertex>>,
+    vertex_count: u32,
+}
+
+impl PerfHud {
+    /// Size of a font pixel in screen pixels (before UI scaling)
+    pub const PIXEL_SIZE: f32 = 2.0;
+    /// Distance from the screen corner in pixels (before UI scaling)
+    pub const MARGIN: f32 = 16.0;
+    /// Distance between lines in font pixels
+    const LINE_SPACING: u32 = 2;
+    const TEXT_COLOR: [u8; 4] = [255, 255, 255, 255];
+    /// Drawn under the text offset by a font pixel, so it's readable on any background
+    const SHADOW_COLOR: [u8; 4] = [0, 0, 0, 160];
+    const MIN_CAPACITY: usize = 4096;
+
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            buffer: None,
+            vertex_count: 0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Build text quads with shadows. Lines are stacked above the bottom left corner of the screen
+    pub fn mesh(lines: &[String], resolution: U32x2, scale: f32) -> Vec<TextVertex> {
+        let pixel_size = Self::PIXEL_SIZE * scale;
+        let line_height = (GLYPH_HEIGHT + Self::LINE_SPACING) as f32 * pixel_size;
+        let bottom = resolution.y as f32 - Self::MARGIN * scale;
+        let mut vertices = Vec::new();
+
+        for (i, line) in lines.iter().rev().enumerate() {
+            let origin = F32x2::new(Self::MARGIN * scale, bottom - i as f32 * line_height);
+            Self::line(&mut vertices, origin, line, pixel_size);
+        }
+
+        vertices
+    }
+
+    /// Quads of a text line and its shadow. `origin` is the bottom left corner of the text
+    pub fn line(vertices: &mut Vec<TextVertex>, origin: F32x2, line: &str, pixel_size: f32) {
+        let mut quads = |origin: F32x2, color: [u8; 4]| {
+            for (x, y) in text_pixels(line) {
+                // Font rows go up, screen rows go down
+                let min = origin + F32x2::new(x as f32, -(y as f32 + 1.0)) * pixel_size;
+                let max = min + pixel_size;
+
+                vertices.extend(
+                    [
+                        (min.x, min.y),
+                        (max.x, min.y),
+                        (max.x, max.y),
+                        (min.x, min.y),
+                        (max.x, max.y),
+                        (min.x, max.y),
+                    ]
+                    .map(|(x, y)| TextVertex::new(F32x2::new(x, y), color)),
+                );
+            }
+        };
+
+        quads(origin + pixel_size, Self::SHADOW_COLOR);
+        quads(origin, Self::TEXT_COLOR);
+    }
+
+    /// Rebuild and upload the text. Buffer grows when there is not enough space
+    pub fn update(&mut self, renderer: &Renderer, stats: &PerfStats, scale: f32) {
+        let vertices = Self::mesh(&stats.lines(), renderer.resolution(), scale);
+        self.vertex_count = vertices.len() as u32;
+
+        let buffer = self.buffer.get_or_insert_with(|| {
+            DynamicBuffer::new(&renderer.device, Self::MIN_CAPACITY, BufferUsages::VERTEX)
+        });
+        buffer.ensure_capacity(&renderer.device, vertices.len());
+
+        if let Err(err) = renderer.update_dynamic_buffer(buffer, &vertices) {
+            warn!(%err, "Failed to upload performance HUD");
+        }
+    }
+
+    /// Drop vertex buffer. It's created again on the next update
+    pub fn release_buffer(&mut self) {
+        self.buffer = None;
+        self.vertex_count = 0;
+    }
+
+    /// Vertex buffer and number of used vertices. `None` if hidden
+    pub fn vertices(&self) -> Option<(&DynamicBuffer<TextVertex>, u32)> {
+        self.buffer
+            .as_ref()
+            .filter(|_| self.enabled && self.vertex_count > 0)
+            .map(|buffer| (buffer, self.vertex_count))
+    }
+}
+
+impl Default for PerfHud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{render::font::text_pixels, types::U32x2};
+
+    use super::{PerfHud, PerfStats};
+
+    #[test]
+    fn text_in_bottom_left_corner() {
+        let stats = PerfStats {
+            frame_time: Duration::from_micros(16_667),
+            max_work: Duration::from_millis(5),
+            cpu_time: Duration::from_micros(3_250),
+            draw_calls: 412,
+        };
+        let lines = stats.lines();
+        assert_eq!(lines[0], "FPS: 60  Frame: 16.7 ms");
+        assert_eq!(lines[2], "Draw calls: 412");
+
+        let resolution = U32x2::new(800, 600);
+        let vertices = PerfHud::mesh(&lines, resolution, 1.0);
+        let pixels = lines
+            .iter()
+            .map(|line| text_pixels(line).count())
+            .sum::<usize>();
+        // A quad for every lit pixel and its shadow
+        assert_eq!(vertices.len(), pixels * 2 * 6);
+
+        let (min, max) = vertices
+            .iter()
+            .fold((vertices[0].pos, vertices[0].pos), |(min, max), vertex| {
+                (min.min(vertex.pos), max.max(vertex.pos))
+            });
+        assert_eq!(min.x, PerfHud::MARGIN);
+        // Shadow of the last line ends a font pixel below the margin
+        assert_eq!(
+            max.y,
+            resolution.y as f32 - PerfHud::MARGIN + PerfHud::PIXEL_SIZE
+        );
+        assert!(min.y > resolution.y as f32 / 2.0);
+    }
+}
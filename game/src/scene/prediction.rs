@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+
+use common::{
+    coord::GlobalCoord,
+    movement::{InputSeq, PlayerInput},
+};
+
+use crate::types::F32x3;
+
+/// Client-side prediction of the local player movement.
+///
+/// Inputs are applied locally right away and kept until the server acknowledges them.
+/// On acknowledgement, authoritative position is taken and unacknowledged inputs are replayed
+#[derive(Default)]
+pub struct Prediction {
+    next_seq: InputSeq,
+    pending: VecDeque<PlayerInput>,
+}
+
+impl Prediction {
+    /// Max number of inputs waiting for acknowledgement
+    pub const MAX_PENDING: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assign sequence number to the input and store it until acknowledged
+    pub fn push(&mut self, mut input: PlayerInput) -> PlayerInput {
+        input.seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+
+        if self.pending.len() >= Self::MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(input);
+
+        input
+    }
+
+    /// Drop acknowledged inputs and replay the rest on top of the server position
+    pub fn reconcile(
+        &mut self,
+        seq: InputSeq,
+        server_pos: F32x3,
+        solid: impl Fn(GlobalCoord) -> Option<bool>,
+    ) -> F32x3 {
+        while let Some(input) = self.pending.front() {
+            // Handles sequence number wrapping
+            if seq.wrapping_sub(input.seq) < InputSeq::MAX / 2 {
+                self.pending.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        self.pending
+            .iter()
+            .fold(server_pos, |pos, input| input.apply_collide(pos, &solid))
+    }
+
+    /// Number of inputs waiting for acknowledgement
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::movement::PlayerInput;
+
+    use crate::types::{F32x2, F32x3};
+
+    use super::Prediction;
+
+    #[test]
+    fn reconcile_replays_pending_inputs() {
+        let mut prediction = Prediction::new();
+        let input = PlayerInput {
+            lift: 1.0,
+            rot: F32x2::ZERO,
+            dt: 0.1,
+            ..Default::default()
+        };
+
+        // Empty world
+        let solid = |_| Some(false);
+
+        let first = prediction.push(input);
+        let mut pos = first.apply(F32x3::ZERO);
+        let second = prediction.push(input);
+        pos = second.apply(pos);
+
+        assert_eq!(prediction.pending(), 2);
+
+        // Server processed only the first input and corrected position
+        let server_pos = F32x3::new(1.0, 2.5, 0.0);
+        let reconciled = prediction.reconcile(first.seq, server_pos, solid);
+
+        assert_eq!(prediction.pending(), 1);
+        assert_eq!(reconciled, second.apply(server_pos));
+        assert_ne!(reconciled, pos);
+
+        // Everything acknowledged
+        assert_eq!(prediction.reconcile(second.seq, pos, solid), pos);
+        assert_eq!(prediction.pending(), 0);
+    }
+}
@@ -0,0 +1,112 @@
+use common::coord::GlobalCoord;
+use tracing::warn;
+use wgpu::BufferUsages;
+
+use crate::{
+    render::{buffer::DynamicBuffer, primitives::line::DebugLineVertex, renderer::Renderer},
+    types::F32x3,
+};
+
+use super::debug::cuboid_edges;
+
+/// Wireframe box around the block under the crosshair
+pub struct BlockSelection {
+    pub enabled: bool,
+    /// Outlined block
+    block: Option<GlobalCoord>,
+    buffer: Option<DynamicBuffer<DebugLineVertex>>,
+}
+
+impl BlockSelection {
+    /// Outline is this much larger than the block on every side, so it isn't hidden by its faces
+    pub const MARGIN: f32 = 0.005;
+    const COLOR: [u8; 4] = [0, 0, 0, 200];
+    /// 12 edges of a box
+    const VERTICES: usize = 24;
+
+    pub const fn new() -> Self {
+        Self {
+            enabled: true,
+            block: None,
+            buffer: None,
+        }
+    }
+
+    /// Lines of the outline
+    pub fn mesh(pos: GlobalCoord) -> Vec<DebugLineVertex> {
+        let min = pos.as_vec() - Self::MARGIN;
+        let max = pos.as_vec() + F32x3::ONE + Self::MARGIN;
+
+        cuboid_edges(min, max)
+            .flat_map(|(from, to)| {
+                [
+                    DebugLineVertex::new(from, Self::COLOR),
+                    DebugLineVertex::new(to, Self::COLOR),
+                ]
+            })
+            .collect()
+    }
+
+    /// Outline `block`. Uploaded only when the block changes
+    pub fn update(&mut self, renderer: &Renderer, block: Option<GlobalCoord>) {
+        let block = block.filter(|_| self.enabled);
+        if block == self.block {
+            return;
+        }
+        self.block = block;
+
+        if let Some(pos) = block {
+            let buffer = self.buffer.get_or_insert_with(|| {
+                DynamicBuffer::new(&renderer.device, Self::VERTICES, BufferUsages::VERTEX)
+            });
+            if let Err(err) = renderer.update_dynamic_buffer(buffer, &Self::mesh(pos)) {
+                warn!(%err, "Failed to upload block selection");
+            }
+        }
+    }
+
+    /// Drop vertex buffer. It's created again on the next update
+    pub fn release_buffer(&mut self) {
+        self.buffer = None;
+        self.block = None;
+    }
+
+    /// Vertex buffer and number of vertices. `None` if no block is outlined
+    pub fn vertices(&self) -> Option<(&DynamicBuffer<DebugLineVertex>, u32)> {
+        self.buffer
+            .as_ref()
+            .filter(|_| self.block.is_some())
+            .map(|buffer| (buffer, Self::VERTICES as u32))
+    }
+}
+
+impl Default for BlockSelection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::coord::GlobalCoord;
+
+    use super::BlockSelection;
+
+    #[test]
+    fn outline_encloses_block() {
+        let vertices = BlockSelection::mesh(GlobalCoord::new(-3, 7, 2));
+        assert_eq!(vertices.len(), BlockSelection::VERTICES);
+
+        let margin = BlockSelection::MARGIN;
+        for vertex in vertices {
+            let (x, y, z) = (vertex.pos.x, vertex.pos.y, vertex.pos.z);
+            assert!(x == -3.0 - margin || x == -2.0 + margin);
+            assert!(y == 7.0 - margin || y == 8.0 + margin);
+            assert!(z == 2.0 - margin || z == 3.0 + margin);
+        }
+    }
+}
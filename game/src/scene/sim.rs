@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+/// Fixed timestep simulation clock.
+///
+/// Simulation (physics, entities, survival) advances in fixed steps independently from the frame
+/// rate, so it can be paused, stepped and slowed down or sped up without affecting rendering
+#[derive(Clone, Debug)]
+pub struct SimClock {
+    /// Stop advancing the simulation. Single steps can still be requested
+    pub paused: bool,
+    /// Simulation speed multiplier
+    scale: f32,
+    /// Scaled time not consumed by steps yet
+    accumulator: Duration,
+    /// Steps requested while paused
+    requested: u32,
+    /// Total number of executed steps
+    steps: u64,
+}
+
+impl SimClock {
+    /// Simulation steps per second
+    pub const TICK_RATE: u32 = 60;
+    /// Duration of a single simulation step
+    pub const STEP: Duration = Duration::from_nanos(1_000_000_000 / Self::TICK_RATE as u64);
+    /// Upper bound for steps per frame. Extra time is dropped (simulation slows down instead of
+    /// spiraling when frames are too long)
+    pub const MAX_STEPS_PER_FRAME: u32 = 16;
+
+    pub const MIN_SCALE: f32 = 0.1;
+    pub const MAX_SCALE: f32 = 10.0;
+
+    pub const fn new() -> Self {
+        Self {
+            paused: false,
+            scale: 1.0,
+            accumulator: Duration::ZERO,
+            requested: 0,
+            steps: 0,
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+    }
+
+    /// Total number of executed steps
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.accumulator = Duration::ZERO;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.set_paused(!self.paused);
+    }
+
+    /// Run `count` steps on the next frame. Pauses the simulation
+    pub fn step(&mut self, count: u32) {
+        self.paused = true;
+        self.accumulator = Duration::ZERO;
+        self.requested = self.requested.saturating_add(count);
+    }
+
+    /// Account for the frame duration. Returns number of steps to run this frame
+    pub fn advance(&mut self, frame_dur: Duration) -> u32 {
+        let steps = if self.paused {
+            std::mem::take(&mut self.requested)
+        } else {
+            let scaled = (frame_dur.as_nanos() as f64 * self.scale as f64).round();
+            self.accumulator += Duration::from_nanos(scaled as u64);
+            let steps = (self.accumulator.as_nanos() / Self::STEP.as_nanos()) as u32;
+            self.accumulator -= Self::STEP * steps;
+            steps
+        };
+
+        let steps = if steps > Self::MAX_STEPS_PER_FRAME {
+            self.accumulator = Duration::ZERO;
+            Self::MAX_STEPS_PER_FRAME
+        } else {
+            steps
+        };
+        self.steps += steps as u64;
+
+        steps
+    }
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::SimClock;
+
+    #[test]
+    fn fixed_steps() {
+        let mut clock = SimClock::new();
+        assert_eq!(clock.advance(SimClock::STEP / 2), 0);
+        assert_eq!(clock.advance(SimClock::STEP / 2), 1);
+        assert_eq!(clock.advance(SimClock::STEP * 3), 3);
+
+        clock.set_scale(100.0);
+        assert_eq!(clock.scale(), SimClock::MAX_SCALE);
+        assert_eq!(
+            clock.advance(SimClock::STEP * 2),
+            SimClock::MAX_STEPS_PER_FRAME
+        );
+
+        clock.step(2);
+        assert!(clock.paused);
+        assert_eq!(clock.advance(SimClock::STEP * 5), 2);
+        assert_eq!(clock.advance(SimClock::STEP * 5), 0);
+        assert_eq!(clock.steps(), 22);
+    }
+}
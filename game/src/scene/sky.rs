@@ -0,0 +1,118 @@
+use common::sky::{TimeOfDay, Weather};
+
+use crate::{render::pipelines::Environment, types::F32x3};
+
+/// Day/night cycle and weather applied over the base environment.
+///
+/// Server owns time and weather while connected
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Sky {
+    pub time: TimeOfDay,
+    pub weather: Weather,
+    /// Time doesn't advance while set
+    pub frozen: bool,
+}
+
+impl Sky {
+    /// Sky color at midnight
+    pub const NIGHT_SKY: F32x3 = F32x3::new(0.02, 0.03, 0.08);
+    /// Sky color of the overcast and fog weather
+    pub const CLOUD_SKY: F32x3 = F32x3::new(0.62, 0.66, 0.7);
+    /// Fraction of the ambient light left at night
+    pub const NIGHT_AMBIENT: f32 = 0.2;
+    /// Angle between the sun path and the zenith (radians)
+    pub const SUN_TILT: f32 = 0.4;
+    /// Daylight fades while the sun height is within this distance from the horizon
+    pub const TWILIGHT: f32 = 0.2;
+
+    pub fn advance(&mut self, dt: f32) {
+        if !self.frozen {
+            self.time.advance(dt);
+        }
+    }
+
+    /// Amount of daylight (0 at night, 1 during the day)
+    pub fn daylight(&self) -> f32 {
+        ((self.time.sun_height() + Self::TWILIGHT) / (2.0 * Self::TWILIGHT)).clamp(0.0, 1.0)
+    }
+
+    /// Environment at the current time and weather. `base` describes a clear day
+    pub fn apply(&self, base: &Environment) -> Environment {
+        let daylight = self.daylight();
+
+        // Sun moves from east to west over the tilted path
+        let (angle_sin, angle_cos) = self.time.sun_angle().sin_cos();
+        let (tilt_sin, tilt_cos) = Self::SUN_TILT.sin_cos();
+        let sun = F32x3::new(angle_cos, angle_sin * tilt_cos, angle_sin * tilt_sin);
+
+        // Cloud cover, light and fog multipliers
+        let (cover, light, fog) = match self.weather {
+            Weather::Clear => (0.0, 1.0, 1.0),
+            Weather::Overcast => (0.8, 0.35, 0.8),
+            Weather::Fog => (0.9, 0.5, 0.2),
+        };
+
+        let sky_color = base.sky_color.lerp(Self::CLOUD_SKY, cover);
+
+        Environment {
+            sky_color: Self::NIGHT_SKY.lerp(sky_color, daylight),
+            fog_start: base.fog_start * fog * fog,
+            fog_end: base.fog_end * fog,
+            sun_elevation: sun.y.asin(),
+            sun_azimuth: base.sun_azimuth + sun.x.atan2(sun.z),
+            light_intensity: base.light_intensity * light * daylight,
+            ambient: base.ambient * (Self::NIGHT_AMBIENT + (1.0 - Self::NIGHT_AMBIENT) * daylight),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::sky::{TimeOfDay, Weather};
+
+    use crate::render::pipelines::Environment;
+
+    use super::Sky;
+
+    #[test]
+    fn day_and_night() {
+        let base = Environment::default();
+        let at = |time| {
+            Sky {
+                time,
+                ..Default::default()
+            }
+            .apply(&base)
+        };
+
+        let noon = at(TimeOfDay::NOON);
+        assert!(noon.sky_color.abs_diff_eq(base.sky_color, 1e-6));
+        assert_eq!(noon.light_intensity, base.light_intensity);
+        assert!(noon.sun_elevation > 1.0);
+
+        let midnight = at(TimeOfDay::MIDNIGHT);
+        assert!(midnight.sky_color.abs_diff_eq(Sky::NIGHT_SKY, 1e-6));
+        assert_eq!(midnight.light_intensity, 0.0);
+        assert!(midnight.ambient < base.ambient);
+        assert!(midnight.sun_elevation < 0.0);
+    }
+
+    #[test]
+    fn fog_shortens_view() {
+        let base = Environment::default();
+        let fog = Sky {
+            time: TimeOfDay::NOON,
+            weather: Weather::Fog,
+            frozen: false,
+        }
+        .apply(&base);
+
+        assert!(fog.fog_end < base.fog_end / 2.0);
+        assert!(fog.fog_start < fog.fog_end);
+        assert!(fog.light_intensity < base.light_intensity);
+    }
+}
@@ -0,0 +1,218 @@
+use common::{
+    block::Block,
+    chunk::{Chunk, LoadArea},
+    coord::{GlobalCoord, GlobalUnit},
+    direction::Direction,
+    path,
+    rng::{self, salt, SeededRng},
+    rules::GameRules,
+};
+use common_log::prof;
+
+use crate::types::{F32x2, F32x3};
+
+use super::{
+    ai::Wander,
+    chunk::ChunkManager,
+    entity::{Components, LocalEntities, LocalEntityId},
+};
+
+/// Spawns ambient mobs on the surface around the camera at night and despawns them when the
+/// camera goes away.
+///
+/// Only mobs spawned here are tracked, mobs spawned from the overlay are left alone
+pub struct MobSpawner {
+    mobs: Vec<LocalEntityId>,
+    rng: SeededRng,
+}
+
+impl MobSpawner {
+    pub const MAX_MOBS: usize = 16;
+    /// Mobs spawned per tick at most
+    pub const SPAWNS_PER_TICK: usize = 1;
+    /// Random columns tried per tick. Columns without valid ground are skipped
+    const ATTEMPTS_PER_TICK: usize = 4;
+    /// Horizontal distance from the camera mobs spawn at (in blocks). Mobs don't appear in sight
+    pub const SPAWN_DISTANCE: (f32, f32) = (24.0, 48.0);
+    /// Mobs further away than this are despawned
+    pub const DESPAWN_DISTANCE: f32 = 64.0;
+    /// Mobs spawn while the daylight is below this (see [`Sky::daylight`])
+    ///
+    /// [`Sky::daylight`]: super::sky::Sky::daylight
+    pub const NIGHT_DAYLIGHT: f32 = 0.25;
+    /// Ground is searched this far above and below the camera
+    const SEARCH_HEIGHT: GlobalUnit = 32;
+
+    pub fn new() -> Self {
+        Self {
+            mobs: Vec::new(),
+            rng: SeededRng::new(rng::mix_seed(Chunk::DEFAULT_SEED, salt::SPAWN)),
+        }
+    }
+
+    /// Number of living mobs spawned here
+    pub fn count(&self) -> usize {
+        self.mobs.len()
+    }
+
+    /// Despawn distant mobs and spawn new ones in `area`. Returns number of spawned mobs
+    pub fn tick(
+        &mut self,
+        entities: &mut LocalEntities,
+        chunk_manager: &ChunkManager,
+        area: &LoadArea,
+        center: F32x3,
+        daylight: f32,
+        rules: &GameRules,
+    ) -> usize {
+        prof!(_guard, "MobSpawner::tick");
+
+        // Mobs may be despawned outside of the spawner (e.g. left the load area)
+        self.mobs.retain(|&id| match entities.get(id) {
+            Some(mob) if horizontal_distance(mob.pos, center) > Self::DESPAWN_DISTANCE => {
+                entities.despawn(id);
+                false
+            }
+            Some(_) => true,
+            None => false,
+        });
+
+        if !rules.mob_spawning || daylight >= Self::NIGHT_DAYLIGHT {
+            return 0;
+        }
+
+        let block = |pos: GlobalCoord| {
+            area.contains(pos.to_chunk_id())
+                .then(|| chunk_manager.block(pos))
+                .flatten()
+        };
+        let mut spawned = 0;
+        for _ in 0..Self::ATTEMPTS_PER_TICK {
+            if spawned >= Self::SPAWNS_PER_TICK || self.mobs.len() >= Self::MAX_MOBS {
+                break;
+            }
+
+            let (min, max) = Self::SPAWN_DISTANCE;
+            let angle = self.rng.next_f32() * std::f32::consts::TAU;
+            let distance = min + (max - min) * self.rng.next_f32();
+            let column = center + F32x3::new(angle.cos(), 0.0, angle.sin()) * distance;
+
+            if let Some(ground) = Self::ground(column, block) {
+                self.mobs.push(entities.spawn(Components {
+                    pos: ground.as_vec(),
+                    wander: Some(Wander::default()),
+                    ..Default::default()
+                }));
+                spawned += 1;
+            }
+        }
+
+        spawned
+    }
+
+    /// Highest walkable position of the column on natural ground, searched around `pos`
+    fn ground(pos: F32x3, block: impl Fn(GlobalCoord) -> Option<Block>) -> Option<GlobalCoord> {
+        let top = Wander::block_pos(pos);
+        let solid = |pos| block(pos).map(|block| block.solid());
+
+        (-Self::SEARCH_HEIGHT..=Self::SEARCH_HEIGHT)
+            .rev()
+            .map(|offset| GlobalCoord::new(top.x, top.y + offset, top.z))
+            .find(|&pos| path::walkable(pos, &solid))
+            .filter(|&pos| {
+                // Not in water and not on trees or buildings
+                block(pos).is_some_and(|block| !block.liquid())
+                    && matches!(
+                        block(pos.neighbor(Direction::Down)),
+                        Some(
+                            Block::Grass
+                                | Block::Dirt
+                                | Block::Sand
+                                | Block::SnowBlock
+                                | Block::Clay
+                                | Block::Mud
+                        )
+                    )
+            })
+    }
+}
+
+impl Default for MobSpawner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn horizontal_distance(a: F32x3, b: F32x3) -> f32 {
+    F32x2::new(a.x - b.x, a.z - b.z).length()
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        block::Block,
+        chunk::{Chunk, LoadArea},
+        coord::{ChunkId, CHUNK_CUBE},
+        rules::GameRules,
+    };
+
+    use crate::{
+        scene::{
+            chunk::{ChunkManager, LogicChunk},
+            entity::LocalEntities,
+        },
+        types::F32x3,
+    };
+
+    use super::MobSpawner;
+
+    #[test]
+    fn spawn_at_night() {
+        // Grass plain with air above it
+        let mut manager = ChunkManager::new();
+        for x in -4..=4 {
+            for z in -4..=4 {
+                manager.chunks.insert(
+                    ChunkId::new(x, -1, z),
+                    LogicChunk::from_chunk(Chunk::from_blocks([Block::Grass; CHUNK_CUBE])),
+                );
+                manager
+                    .chunks
+                    .insert(ChunkId::new(x, 0, z), LogicChunk::new());
+            }
+        }
+        let area = LoadArea::new_cuboid(ChunkId::new(0, 0, 0), 8);
+        let center = F32x3::new(0.0, 2.0, 0.0);
+        let mut rules = GameRules::new();
+        let mut entities = LocalEntities::new();
+        let mut spawner = MobSpawner::new();
+
+        let mut tick = |entities: &mut LocalEntities, rules: &GameRules, daylight, center| {
+            spawner.tick(entities, &manager, &area, center, daylight, rules)
+        };
+
+        assert_eq!(tick(&mut entities, &rules, 1.0, center), 0);
+        rules.mob_spawning = false;
+        assert_eq!(tick(&mut entities, &rules, 0.0, center), 0);
+        rules.mob_spawning = true;
+
+        let spawned = (0..100)
+            .map(|_| tick(&mut entities, &rules, 0.0, center))
+            .sum::<usize>();
+        assert_eq!(spawned, MobSpawner::MAX_MOBS);
+        assert_eq!(entities.len(), MobSpawner::MAX_MOBS);
+        for (_, mob) in entities.iter() {
+            // Standing on the grass
+            assert_eq!(mob.pos.y, 0.0);
+            assert!(mob.wander.is_some());
+        }
+
+        // Camera went far away
+        tick(&mut entities, &rules, 1.0, F32x3::new(500.0, 2.0, 0.0));
+        assert_eq!(entities.len(), 0);
+    }
+}
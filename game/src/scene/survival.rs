@@ -0,0 +1,188 @@
+use common::{
+    block::Block,
+    coord::GlobalCoord,
+    health::{fall_damage, DamageSource, FallTracker, Health, LAVA_DAMAGE, LAVA_DAMAGE_INTERVAL},
+    movement::PLAYER_AABB,
+    physics::sweep,
+};
+
+use crate::types::F32x3;
+
+/// Survival mode state of the local player: gravity, damage and respawning
+#[derive(Clone, Debug)]
+pub struct Survival {
+    pub health: Health,
+    /// Respawn position (eye position)
+    pub spawn: F32x3,
+    /// Vertical speed (blocks per second)
+    velocity: f32,
+    fall: FallTracker,
+    /// Time until the next lava damage tick
+    lava_cooldown: f32,
+    /// Time left before respawn (if dead)
+    respawn_in: Option<f32>,
+}
+
+impl Survival {
+    pub const GRAVITY: f32 = 25.0;
+    pub const TERMINAL_VELOCITY: f32 = 50.0;
+    /// Max falling speed inside liquids
+    pub const LIQUID_VELOCITY: f32 = 3.0;
+    pub const RESPAWN_DELAY: f32 = 3.0;
+
+    pub fn new(spawn: F32x3) -> Self {
+        Self {
+            health: Health::default(),
+            spawn,
+            velocity: 0.0,
+            fall: FallTracker::default(),
+            lava_cooldown: 0.0,
+            respawn_in: None,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health.is_dead()
+    }
+
+    /// Seconds left before respawn (if dead)
+    pub fn respawn_in(&self) -> Option<f32> {
+        self.respawn_in
+    }
+
+    /// Forget the current fall and vertical speed (e.g. after teleport)
+    pub fn reset_fall(&mut self) {
+        self.velocity = 0.0;
+        self.fall.reset();
+    }
+
+    /// Move player to the spawn point with full health
+    pub fn respawn(&mut self, pos: &mut F32x3) {
+        *pos = self.spawn;
+        self.health.reset();
+        self.velocity = 0.0;
+        self.fall.reset();
+        self.lava_cooldown = 0.0;
+        self.respawn_in = None;
+    }
+
+    /// Apply gravity and environmental damage. `block` returns `None` for unloaded blocks.
+    ///
+    /// Returns damage source if the player has been hurt this tick
+    pub fn tick(
+        &mut self,
+        pos: &mut F32x3,
+        dt: f32,
+        block: impl Fn(GlobalCoord) -> Option<Block>,
+    ) -> Option<DamageSource> {
+        if let Some(respawn_in) = &mut self.respawn_in {
+            *respawn_in -= dt;
+            if *respawn_in <= 0.0 {
+                self.respawn(pos);
+            }
+            return None;
+        }
+
+        let touching = PLAYER_AABB
+            .translate(*pos)
+            .blocks()
+            .filter_map(&block)
+            .collect::<Vec<_>>();
+        let in_liquid = touching.iter().any(Block::liquid);
+
+        // Gravity
+        self.velocity = (self.velocity - Self::GRAVITY * dt).max(if in_liquid {
+            -Self::LIQUID_VELOCITY
+        } else {
+            -Self::TERMINAL_VELOCITY
+        });
+        let requested = F32x3::new(0.0, self.velocity * dt, 0.0);
+        let result = sweep(PLAYER_AABB.translate(*pos), requested, |pos| {
+            block(pos).map(|block| block.solid())
+        });
+        *pos += result.delta;
+        if result.collided.y {
+            self.velocity = 0.0;
+        }
+
+        let mut hurt = None;
+
+        // Liquids break the fall
+        if in_liquid {
+            self.fall.reset();
+        } else if let Some(distance) = self.fall.update(pos.y, result.on_ground(requested)) {
+            let damage = fall_damage(distance);
+            if damage > 0 {
+                self.health.damage(damage, DamageSource::Fall);
+                hurt = Some(DamageSource::Fall);
+            }
+        }
+
+        self.lava_cooldown = (self.lava_cooldown - dt).max(0.0);
+        if let Some(source) = touching
+            .into_iter()
+            .find_map(DamageSource::from_contact)
+            .filter(|_| self.lava_cooldown <= 0.0)
+        {
+            self.health.damage(LAVA_DAMAGE, source);
+            self.lava_cooldown = LAVA_DAMAGE_INTERVAL;
+            hurt = Some(source);
+        }
+
+        if self.health.is_dead() {
+            self.respawn_in = Some(Self::RESPAWN_DELAY);
+        }
+
+        hurt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{block::Block, coord::GlobalCoord, health::DamageSource};
+
+    use crate::types::F32x3;
+
+    use super::Survival;
+
+    /// Flat floor (y <= 0) made of `floor` blocks
+    fn world(floor: Block) -> impl Fn(GlobalCoord) -> Option<Block> {
+        move |pos| Some(if pos.y <= 0 { floor } else { Block::Air })
+    }
+
+    #[test]
+    fn fall_damage_and_respawn() {
+        let spawn = F32x3::new(0.5, 30.0, 0.5);
+        let mut survival = Survival::new(spawn);
+        let mut pos = spawn;
+
+        let mut hurt = None;
+        for _ in 0..200 {
+            hurt = hurt.or(survival.tick(&mut pos, 0.02, world(Block::Stone)));
+        }
+
+        // Eye position above the floor
+        assert!((pos.y - 2.6).abs() < 1e-3);
+        assert_eq!(hurt, Some(DamageSource::Fall));
+        assert!(survival.is_dead());
+
+        pos = F32x3::ZERO;
+        survival.tick(&mut pos, Survival::RESPAWN_DELAY, world(Block::Stone));
+        assert_eq!(pos, spawn);
+        assert!(!survival.is_dead());
+    }
+
+    #[test]
+    fn lava_contact() {
+        // Standing in lava
+        let mut survival = Survival::new(F32x3::ZERO);
+        let mut pos = F32x3::new(0.5, 1.0, 0.5);
+
+        assert_eq!(
+            survival.tick(&mut pos, 0.01, world(Block::Lava)),
+            Some(DamageSource::Lava)
+        );
+        // Cooldown between damage ticks
+        assert_eq!(survival.tick(&mut pos, 0.01, world(Block::Lava)), None);
+    }
+}
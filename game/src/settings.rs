@@ -0,0 +1,514 @@
+//! Player preferences kept between runs.
+//!
+//! Settings file has a `key = value` line per option (`ui.*` for [`UiSettings`], `graphics.*` for
+//! [`GraphicsSettings`], `controls.*` for [`ControlSettings`], `bind.*` for [`KeyBindings`],
+//! `threads.*` for [`ThreadSettings`], `chunks.*` for [`ChunkSettings`]). Unknown keys and invalid
+//! values are skipped with a warning, so files of other game versions still load
+
+use std::{fs, io, ops::RangeInclusive, path::Path};
+
+use common::file;
+use tracing::warn;
+
+use crate::{
+    consts::{ASYNC_THREADS, CPU_CORES},
+    error::{Context, Error},
+    render::{PresentPolicy, RenderMode},
+    scene::{chunk::ChunkManager, debug::DebugPalette},
+    window::binding::KeyBindings,
+};
+
+/// Everything stored in the settings file
+#[derive(PartialEq, Clone, Debug)]
+pub struct Settings {
+    pub ui: UiSettings,
+    pub graphics: GraphicsSettings,
+    pub controls: ControlSettings,
+    pub bindings: KeyBindings,
+    pub threads: ThreadSettings,
+    pub chunks: ChunkSettings,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            ui: UiSettings::new(),
+            graphics: GraphicsSettings::new(),
+            controls: ControlSettings::new(),
+            bindings: KeyBindings::new(),
+            threads: ThreadSettings::new(),
+            chunks: ChunkSettings::new(),
+        }
+    }
+
+    /// Load settings file. Defaults if it doesn't exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(source) => Ok(Self::parse(&source)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(err) => Err(err).context("Failed to read settings"),
+        }
+    }
+
+    /// Written through a temporary file, so a crash while saving keeps the old settings
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        file::write_atomic(path.as_ref(), self.to_source().as_bytes())
+            .context("Failed to write settings")
+    }
+
+    /// Out of range values are clamped, non-finite ones are invalid
+    pub fn parse(source: &str) -> Self {
+        let mut settings = Self::new();
+
+        for (i, line) in source.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+            else {
+                warn!(line = i + 1, "Skipping settings line without value");
+                continue;
+            };
+
+            let parsed = key.split_once('.').and_then(|section| match section {
+                ("ui", key) => settings.ui.parse_line(key, value),
+                ("graphics", key) => settings.graphics.parse_line(key, value),
+                ("controls", key) => settings.controls.parse_line(key, value),
+                ("bind", input) => settings.bindings.parse_line(input, value),
+                ("threads", key) => settings.threads.parse_line(key, value),
+                ("chunks", key) => settings.chunks.parse_line(key, value),
+                _ => None,
+            });
+            if parsed.is_some() {
+                continue;
+            }
+
+            if Self::known_key(key) {
+                warn!(
+                    line = i + 1,
+                    key, value, "Invalid settings value, using default"
+                );
+            } else {
+                warn!(line = i + 1, key, "Unknown settings key, skipping");
+            }
+        }
+
+        settings
+    }
+
+    /// Key is written by [`Self::to_source`]
+    fn known_key(key: &str) -> bool {
+        Self::new()
+            .to_source()
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .any(|(known, _)| known == key)
+    }
+
+    pub fn to_source(&self) -> String {
+        self.ui.to_source()
+            + &self.graphics.to_source()
+            + &self.controls.to_source()
+            + &self.bindings.to_source()
+            + &self.threads.to_source()
+            + &self.chunks.to_source()
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interface size and HUD visibility
+#[derive(PartialEq, Clone, Debug)]
+pub struct UiSettings {
+    /// Multiplier of UI and HUD sizes over the window scale factor
+    pub scale: f32,
+    /// Longer and thicker crosshair
+    pub large_crosshair: bool,
+    /// Opacity of the crosshair and the minimap
+    pub hud_opacity: f32,
+    /// Colors of debug visualizations
+    pub debug_palette: DebugPalette,
+    /// Font file replacing the built-in fonts of the overlay and labels, see
+    /// [`AssetManager::font`](crate::assets::AssetManager::font)
+    pub font: Option<String>,
+    /// Overlay body text size in points. Other text and labels are scaled along
+    pub font_size: f32,
+}
+
+impl UiSettings {
+    pub const SCALE_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+    pub const HUD_OPACITY_RANGE: RangeInclusive<f32> = 0.1..=1.0;
+    pub const FONT_SIZE_RANGE: RangeInclusive<f32> = 8.0..=32.0;
+    /// Body text size of egui
+    pub const DEFAULT_FONT_SIZE: f32 = 14.0;
+    /// Crosshair half length and thickness in pixels at scale 1
+    const CROSSHAIR: (f32, f32) = (8.0, 2.0);
+    const LARGE_CROSSHAIR: (f32, f32) = (16.0, 4.0);
+
+    pub const fn new() -> Self {
+        Self {
+            scale: 1.0,
+            large_crosshair: false,
+            hud_opacity: 1.0,
+            debug_palette: DebugPalette::Default,
+            font: None,
+            font_size: Self::DEFAULT_FONT_SIZE,
+        }
+    }
+
+    /// Handle `ui.<key> = <value>` line of the settings file. `None` if the line is invalid
+    fn parse_line(&mut self, key: &str, value: &str) -> Option<()> {
+        match key {
+            "scale" => parse_f32(value, Self::SCALE_RANGE).map(|scale| self.scale = scale),
+            "large_crosshair" => value.parse().ok().map(|large| self.large_crosshair = large),
+            "hud_opacity" => {
+                parse_f32(value, Self::HUD_OPACITY_RANGE).map(|opacity| self.hud_opacity = opacity)
+            }
+            "debug_palette" => {
+                DebugPalette::from_key(value).map(|palette| self.debug_palette = palette)
+            }
+            "font" => {
+                self.font = Some(value.to_string()).filter(|font| !font.is_empty());
+                Some(())
+            }
+            "font_size" => {
+                parse_f32(value, Self::FONT_SIZE_RANGE).map(|size| self.font_size = size)
+            }
+            _ => None,
+        }
+    }
+
+    fn to_source(&self) -> String {
+        format!(
+            "ui.scale = {}\nui.large_crosshair = {}\nui.hud_opacity = {}\nui.debug_palette = {}\n\
+             ui.font = {}\nui.font_size = {}\n",
+            self.scale,
+            self.large_crosshair,
+            self.hud_opacity,
+            self.debug_palette.key(),
+            self.font.as_deref().unwrap_or_default(),
+            self.font_size,
+        )
+    }
+
+    /// Crosshair half length and thickness in pixels on a monitor with the scale factor
+    pub fn crosshair(&self, scale_factor: f32) -> (f32, f32) {
+        let (length, thickness) = if self.large_crosshair {
+            Self::LARGE_CROSSHAIR
+        } else {
+            Self::CROSSHAIR
+        };
+
+        let scale = self.scale * scale_factor;
+        (length * scale, thickness * scale)
+    }
+
+    /// Font size relative to the default one
+    pub fn font_scale(&self) -> f32 {
+        self.font_size / Self::DEFAULT_FONT_SIZE
+    }
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Frame rate, presentation and view distance
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GraphicsSettings {
+    /// Max frames per second
+    pub fps: u32,
+    pub present_mode: PresentPolicy,
+    /// Chunks loaded around the camera, see [`ChunkManager::draw_distance`]
+    pub draw_distance: u16,
+}
+
+impl GraphicsSettings {
+    pub const FPS_RANGE: RangeInclusive<u32> = 10..=360;
+    pub const DRAW_DISTANCE_RANGE: RangeInclusive<u16> =
+        ChunkManager::MIN_DRAW_DISTANCE..=ChunkManager::MAX_DRAW_DISTANCE;
+
+    pub const fn new() -> Self {
+        Self {
+            fps: 60,
+            present_mode: RenderMode::new().present_mode,
+            draw_distance: ChunkManager::MIN_DRAW_DISTANCE,
+        }
+    }
+
+    /// Handle `graphics.<key> = <value>` line of the settings file. `None` if the line is invalid
+    fn parse_line(&mut self, key: &str, value: &str) -> Option<()> {
+        match key {
+            "fps" => value.parse().ok().map(|fps: u32| {
+                let (min, max) = Self::FPS_RANGE.into_inner();
+                self.fps = fps.clamp(min, max);
+            }),
+            "present_mode" => {
+                PresentPolicy::from_key(value).map(|policy| self.present_mode = policy)
+            }
+            "draw_distance" => value.parse().ok().map(|distance: u16| {
+                let (min, max) = Self::DRAW_DISTANCE_RANGE.into_inner();
+                self.draw_distance = distance.clamp(min, max);
+            }),
+            _ => None,
+        }
+    }
+
+    fn to_source(&self) -> String {
+        format!(
+            "graphics.fps = {}\ngraphics.present_mode = {}\ngraphics.draw_distance = {}\n",
+            self.fps,
+            self.present_mode.key(),
+            self.draw_distance,
+        )
+    }
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Mouse options. Key bindings are kept separately in [`KeyBindings`]
+#[derive(PartialEq, Clone, Debug)]
+pub struct ControlSettings {
+    /// Multiplier of the camera rotation speed
+    pub sensitivity: f32,
+}
+
+impl ControlSettings {
+    pub const SENSITIVITY_RANGE: RangeInclusive<f32> = 0.1..=5.0;
+
+    pub const fn new() -> Self {
+        Self { sensitivity: 1.0 }
+    }
+
+    /// Handle `controls.<key> = <value>` line of the settings file. `None` if the line is invalid
+    fn parse_line(&mut self, key: &str, value: &str) -> Option<()> {
+        match key {
+            "sensitivity" => parse_f32(value, Self::SENSITIVITY_RANGE)
+                .map(|sensitivity| self.sensitivity = sensitivity),
+            _ => None,
+        }
+    }
+
+    fn to_source(&self) -> String {
+        format!("controls.sensitivity = {}\n", self.sensitivity)
+    }
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thread counts of the async runtime and the job pool. `None` is detected from the CPU
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ThreadSettings {
+    /// Async runtime threads (networking). Applied on the next start
+    pub async_threads: Option<usize>,
+    /// Threads of blocking jobs (chunk generation and meshing), see [`JobPool`]. Applied at once
+    ///
+    /// [`JobPool`]: crate::jobs::JobPool
+    pub blocking_threads: Option<usize>,
+}
+
+impl ThreadSettings {
+    pub const MAX_THREADS: usize = 64;
+
+    pub const fn new() -> Self {
+        Self {
+            async_threads: None,
+            blocking_threads: None,
+        }
+    }
+
+    pub fn async_threads(&self) -> usize {
+        self.async_threads.unwrap_or(ASYNC_THREADS)
+    }
+
+    /// Half of the cores by default, the rest is left for rendering and the game loop
+    pub fn blocking_threads(&self) -> usize {
+        self.blocking_threads
+            .unwrap_or_else(|| (*CPU_CORES / 2).max(2))
+    }
+
+    /// Handle `threads.<key> = <count | auto>` line of the settings file. `None` if the line is
+    /// invalid
+    fn parse_line(&mut self, key: &str, value: &str) -> Option<()> {
+        let count = match value {
+            "auto" => None,
+            count => Some(count.parse::<usize>().ok()?.clamp(1, Self::MAX_THREADS)),
+        };
+        match key {
+            "async" => self.async_threads = count,
+            "blocking" => self.blocking_threads = count,
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    fn to_source(&self) -> String {
+        let count = |count: Option<usize>| count.map_or("auto".to_string(), |c| c.to_string());
+        format!(
+            "threads.async = {}\nthreads.blocking = {}\n",
+            count(self.async_threads),
+            count(self.blocking_threads),
+        )
+    }
+}
+
+impl Default for ThreadSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Chunk loading options of [`ChunkManager`](crate::scene::chunk::ChunkManager)
+#[derive(PartialEq, Clone, Debug)]
+pub struct ChunkSettings {
+    /// Share of the vertical load range moved towards the terrain surface. 0 loads as many chunks
+    /// above the camera as below it
+    pub vertical_bias: f32,
+}
+
+impl ChunkSettings {
+    pub const VERTICAL_BIAS_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+
+    pub const fn new() -> Self {
+        Self {
+            vertical_bias: 0.75,
+        }
+    }
+
+    /// Handle `chunks.<key> = <value>` line of the settings file. `None` if the line is invalid
+    fn parse_line(&mut self, key: &str, value: &str) -> Option<()> {
+        match key {
+            "vertical_bias" => {
+                parse_f32(value, Self::VERTICAL_BIAS_RANGE).map(|bias| self.vertical_bias = bias)
+            }
+            _ => None,
+        }
+    }
+
+    fn to_source(&self) -> String {
+        format!("chunks.vertical_bias = {}\n", self.vertical_bias)
+    }
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finite number clamped to `range`. NaN would pass through the clamp
+fn parse_f32(value: &str, range: RangeInclusive<f32>) -> Option<f32> {
+    let (min, max) = range.into_inner();
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(|value| value.clamp(min, max))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use wgpu::PresentMode;
+    use winit::event::VirtualKeyCode;
+
+    use crate::{
+        render::PresentPolicy,
+        scene::debug::DebugPalette,
+        window::{
+            binding::{GameInput, KeyBinding},
+            event::Input,
+        },
+    };
+
+    use super::{GraphicsSettings, Settings, ThreadSettings, UiSettings};
+
+    #[test]
+    fn settings_round_trip() {
+        let mut settings = Settings::new();
+        settings.ui = UiSettings {
+            scale: 1.5,
+            large_crosshair: true,
+            hud_opacity: 0.6,
+            debug_palette: DebugPalette::RedGreen,
+            font: Some("Hack Regular.ttf".to_string()),
+            font_size: 18.0,
+        };
+        settings.graphics = GraphicsSettings {
+            fps: 144,
+            present_mode: PresentPolicy::Fixed(PresentMode::Mailbox),
+            draw_distance: 24,
+        };
+        settings.controls.sensitivity = 0.5;
+        settings
+            .bindings
+            .bind(GameInput::ToggleMap, KeyBinding::ScanCode(50));
+        settings.threads.blocking_threads = Some(3);
+        settings.chunks.vertical_bias = 0.25;
+        assert_eq!(Settings::parse(&settings.to_source()), settings);
+        assert!(Settings::known_key("graphics.fps"));
+        assert!(!Settings::known_key("graphics.fsp"));
+        assert_eq!(settings.ui.crosshair(1.0), (24.0, 6.0));
+        assert_eq!(settings.ui.crosshair(2.0), (48.0, 12.0));
+
+        let settings = Settings::parse(
+            "# Old\nui.scale = 10\nui.hud_opacity = half\nui.font_size = NaN\nfov = 90\n\
+             controls.sensitivity = inf\nbind.toggle_map = key:Tab\n\
+             threads.async = 500\nthreads.blocking = auto\nchunks.vertical_bias = -1\n\
+             graphics.fps = 1000\ngraphics.present_mode = vsync\ngraphics.draw_distance = 0\n",
+        );
+        assert_eq!(
+            settings.ui,
+            UiSettings {
+                scale: 3.0,
+                ..UiSettings::new()
+            }
+        );
+        assert_eq!(
+            settings.bindings.input(Input::Key {
+                key: Some(VirtualKeyCode::Tab),
+                scan_code: 0
+            }),
+            Some(GameInput::ToggleMap)
+        );
+        assert_eq!(
+            settings.threads,
+            ThreadSettings {
+                async_threads: Some(ThreadSettings::MAX_THREADS),
+                blocking_threads: None,
+            }
+        );
+        assert!(settings.threads.blocking_threads() >= 2);
+        assert_eq!(settings.chunks.vertical_bias, 0.0);
+        assert_eq!(settings.controls.sensitivity, 1.0);
+        assert_eq!(
+            settings.graphics,
+            GraphicsSettings {
+                fps: 360,
+                draw_distance: 2,
+                ..GraphicsSettings::new()
+            }
+        );
+    }
+}
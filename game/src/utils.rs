@@ -15,6 +15,7 @@ pub enum ExitCode {
     Ok = 0,
     OutOfMemory,
     OutOfVideoMemory,
+    RendererLost,
 }
 
 impl ExitCode {
@@ -27,14 +28,17 @@ impl ExitCode {
 // Testing
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-/// Buffers should be 8 byte aligned
+/// Buffers should be 8 byte aligned. Uniforms (std140) pass 16 as `align`
 #[macro_export]
 macro_rules! test_buffer_align {
     ($test_type:ty) => {
+        $crate::test_buffer_align!($test_type, 8);
+    };
+    ($test_type:ty, $align:expr) => {
         #[cfg(test)]
         #[test]
         fn test_alignment() {
-            assert_eq!(core::mem::size_of::<$test_type>() % 8, 0);
+            assert_eq!(core::mem::size_of::<$test_type>() % $align, 0);
         }
     }; // ($test_type:ty) => {
        //     test_buffer_align
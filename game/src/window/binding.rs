@@ -0,0 +1,441 @@
+use winit::event::{MouseButton, ScanCode, VirtualKeyCode};
+
+use super::event::Input;
+
+/// Action triggered by a bound key
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum GameInput {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    /// Release or grab the cursor
+    ToggleCursor,
+    ToggleMap,
+    ToggleMinimap,
+    MinimapZoomIn,
+    MinimapZoomOut,
+    /// Pause or resume the local simulation
+    PauseSimulation,
+    /// Advance paused simulation by a step
+    StepSimulation,
+    /// Frame statistics
+    TogglePerfHud,
+    /// Debug overlay. Hides its menu bar with Shift
+    ToggleOverlay,
+}
+
+impl GameInput {
+    pub const ALL: [Self; 15] = [
+        Self::MoveForward,
+        Self::MoveBackward,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::ToggleCursor,
+        Self::ToggleMap,
+        Self::ToggleMinimap,
+        Self::MinimapZoomIn,
+        Self::MinimapZoomOut,
+        Self::PauseSimulation,
+        Self::StepSimulation,
+        Self::TogglePerfHud,
+        Self::ToggleOverlay,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MoveForward => "Move forward",
+            Self::MoveBackward => "Move backward",
+            Self::MoveLeft => "Move left",
+            Self::MoveRight => "Move right",
+            Self::MoveUp => "Move up",
+            Self::MoveDown => "Move down",
+            Self::ToggleCursor => "Toggle cursor",
+            Self::ToggleMap => "Toggle map",
+            Self::ToggleMinimap => "Toggle minimap",
+            Self::MinimapZoomIn => "Minimap zoom in",
+            Self::MinimapZoomOut => "Minimap zoom out",
+            Self::PauseSimulation => "Pause simulation",
+            Self::StepSimulation => "Step simulation",
+            Self::TogglePerfHud => "Toggle performance HUD",
+            Self::ToggleOverlay => "Toggle debug overlay",
+        }
+    }
+
+    /// Name in the settings file
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::MoveForward => "move_forward",
+            Self::MoveBackward => "move_backward",
+            Self::MoveLeft => "move_left",
+            Self::MoveRight => "move_right",
+            Self::MoveUp => "move_up",
+            Self::MoveDown => "move_down",
+            Self::ToggleCursor => "toggle_cursor",
+            Self::ToggleMap => "toggle_map",
+            Self::ToggleMinimap => "toggle_minimap",
+            Self::MinimapZoomIn => "minimap_zoom_in",
+            Self::MinimapZoomOut => "minimap_zoom_out",
+            Self::PauseSimulation => "pause_simulation",
+            Self::StepSimulation => "step_simulation",
+            Self::TogglePerfHud => "toggle_perf_hud",
+            Self::ToggleOverlay => "toggle_overlay",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|input| input.key() == key)
+    }
+}
+
+/// Key or mouse button an input is bound to
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum KeyBinding {
+    /// Key producing the character in the current layout (e.g. `M` for the map)
+    Key(VirtualKeyCode),
+    /// Physical key position, the same for every layout (e.g. WASD movement). Codes are
+    /// platform-specific
+    ScanCode(ScanCode),
+    Mouse(MouseButton),
+}
+
+impl KeyBinding {
+    pub fn matches(&self, input: Input) -> bool {
+        match (*self, input) {
+            (Self::Key(bound), Input::Key { key, .. }) => key == Some(bound),
+            (Self::ScanCode(bound), Input::Key { scan_code, .. }) => scan_code == bound,
+            (Self::Mouse(bound), Input::Mouse(button)) => button == bound,
+            _ => false,
+        }
+    }
+
+    /// Shown in the settings
+    pub fn name(&self) -> String {
+        match self {
+            Self::Key(key) => format!("{key:?}"),
+            Self::ScanCode(code) => format!("Scan code {code}"),
+            Self::Mouse(MouseButton::Other(button)) => format!("Mouse {button}"),
+            Self::Mouse(button) => format!("{button:?} mouse"),
+        }
+    }
+
+    /// Parse `key:<VirtualKeyCode>`, `scan:<code>` or `mouse:<Left|Right|Middle|number>`
+    pub fn parse(source: &str) -> Option<Self> {
+        match source.split_once(':')? {
+            ("key", name) => key_from_name(name).map(Self::Key),
+            ("scan", code) => code.parse().ok().map(Self::ScanCode),
+            ("mouse", "Left") => Some(Self::Mouse(MouseButton::Left)),
+            ("mouse", "Right") => Some(Self::Mouse(MouseButton::Right)),
+            ("mouse", "Middle") => Some(Self::Mouse(MouseButton::Middle)),
+            ("mouse", button) => button
+                .parse()
+                .ok()
+                .map(|b| Self::Mouse(MouseButton::Other(b))),
+            _ => None,
+        }
+    }
+
+    pub fn to_source(&self) -> String {
+        match self {
+            Self::Key(key) => format!("key:{key:?}"),
+            Self::ScanCode(code) => format!("scan:{code}"),
+            Self::Mouse(MouseButton::Other(button)) => format!("mouse:{button}"),
+            Self::Mouse(button) => format!("mouse:{button:?}"),
+        }
+    }
+}
+
+/// Keys bound to game inputs. Input may have several keys, the first one is shown and replaced
+/// when rebinding
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct KeyBindings {
+    bindings: Vec<(GameInput, KeyBinding)>,
+}
+
+impl KeyBindings {
+    /// Movement keys are bound by position, so they keep the QWERTY layout shape on AZERTY, Dvorak,
+    /// etc. Arrows and mnemonic keys are bound by meaning
+    pub fn new() -> Self {
+        use GameInput::*;
+
+        Self {
+            bindings: vec![
+                (MoveForward, KeyBinding::ScanCode(scan_codes::W)),
+                (MoveForward, KeyBinding::Key(VirtualKeyCode::Up)),
+                (MoveBackward, KeyBinding::ScanCode(scan_codes::S)),
+                (MoveBackward, KeyBinding::Key(VirtualKeyCode::Down)),
+                (MoveLeft, KeyBinding::ScanCode(scan_codes::A)),
+                (MoveLeft, KeyBinding::Key(VirtualKeyCode::Left)),
+                (MoveRight, KeyBinding::ScanCode(scan_codes::D)),
+                (MoveRight, KeyBinding::Key(VirtualKeyCode::Right)),
+                (MoveUp, KeyBinding::Key(VirtualKeyCode::Space)),
+                (MoveDown, KeyBinding::Key(VirtualKeyCode::LShift)),
+                (ToggleCursor, KeyBinding::Key(VirtualKeyCode::P)),
+                (ToggleMap, KeyBinding::Key(VirtualKeyCode::M)),
+                (ToggleMinimap, KeyBinding::Key(VirtualKeyCode::N)),
+                (MinimapZoomIn, KeyBinding::Key(VirtualKeyCode::Equals)),
+                (MinimapZoomOut, KeyBinding::Key(VirtualKeyCode::Minus)),
+                (PauseSimulation, KeyBinding::Key(VirtualKeyCode::F6)),
+                (StepSimulation, KeyBinding::Key(VirtualKeyCode::F7)),
+                (TogglePerfHud, KeyBinding::Key(VirtualKeyCode::F1)),
+                (ToggleOverlay, KeyBinding::Key(VirtualKeyCode::F3)),
+            ],
+        }
+    }
+
+    /// Input bound to the pressed key or button
+    pub fn input(&self, input: Input) -> Option<GameInput> {
+        self.bindings
+            .iter()
+            .find(|(_, binding)| binding.matches(input))
+            .map(|&(input, _)| input)
+    }
+
+    /// First key bound to the input
+    pub fn binding(&self, input: GameInput) -> Option<KeyBinding> {
+        self.bindings
+            .iter()
+            .find(|&&(other, _)| other == input)
+            .map(|&(_, binding)| binding)
+    }
+
+    /// Replace the first key of the input. The key is unbound from other inputs
+    pub fn bind(&mut self, input: GameInput, binding: KeyBinding) {
+        if self.binding(input) == Some(binding) {
+            return;
+        }
+
+        self.bindings.retain(|&(_, other)| other != binding);
+        match self.bindings.iter_mut().find(|(other, _)| *other == input) {
+            Some((_, old)) => *old = binding,
+            None => self.bindings.push((input, binding)),
+        }
+    }
+
+    /// Handle a line of the settings file (`bind.<input> = <binding>`). Bindings of the file
+    /// replace the first default key of the input. `None` if the line is invalid
+    pub fn parse_line(&mut self, input: &str, binding: &str) -> Option<()> {
+        self.bind(GameInput::from_key(input)?, KeyBinding::parse(binding)?);
+        Some(())
+    }
+
+    /// First key of every input as settings file lines
+    pub fn to_source(&self) -> String {
+        GameInput::ALL
+            .into_iter()
+            .filter_map(|input| Some((input, self.binding(input)?)))
+            .map(|(input, binding)| format!("bind.{} = {}\n", input.key(), binding.to_source()))
+            .collect()
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scan codes of the letter keys, as reported by winit
+mod scan_codes {
+    use winit::event::ScanCode;
+
+    #[cfg(target_os = "macos")]
+    pub const W: ScanCode = 0x0d;
+    #[cfg(target_os = "macos")]
+    pub const A: ScanCode = 0x00;
+    #[cfg(target_os = "macos")]
+    pub const S: ScanCode = 0x01;
+    #[cfg(target_os = "macos")]
+    pub const D: ScanCode = 0x02;
+
+    // PC set 1 on Windows, evdev (the same for these keys) on Linux
+    #[cfg(not(target_os = "macos"))]
+    pub const W: ScanCode = 0x11;
+    #[cfg(not(target_os = "macos"))]
+    pub const A: ScanCode = 0x1e;
+    #[cfg(not(target_os = "macos"))]
+    pub const S: ScanCode = 0x1f;
+    #[cfg(not(target_os = "macos"))]
+    pub const D: ScanCode = 0x20;
+}
+
+/// Virtual key code by its `Debug` name
+fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+
+    // Keys which can be bound from the settings file
+    const KEYS: &[VirtualKeyCode] = &[
+        Key1,
+        Key2,
+        Key3,
+        Key4,
+        Key5,
+        Key6,
+        Key7,
+        Key8,
+        Key9,
+        Key0,
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
+        Escape,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        Insert,
+        Home,
+        Delete,
+        End,
+        PageDown,
+        PageUp,
+        Left,
+        Up,
+        Right,
+        Down,
+        Back,
+        Return,
+        Space,
+        Tab,
+        Numpad0,
+        Numpad1,
+        Numpad2,
+        Numpad3,
+        Numpad4,
+        Numpad5,
+        Numpad6,
+        Numpad7,
+        Numpad8,
+        Numpad9,
+        NumpadAdd,
+        NumpadSubtract,
+        NumpadMultiply,
+        NumpadDivide,
+        NumpadEnter,
+        Apostrophe,
+        Backslash,
+        Comma,
+        Equals,
+        Grave,
+        LAlt,
+        LBracket,
+        LControl,
+        LShift,
+        Minus,
+        Period,
+        RAlt,
+        RBracket,
+        RControl,
+        RShift,
+        Semicolon,
+        Slash,
+    ];
+
+    KEYS.iter().copied().find(|key| format!("{key:?}") == name)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use winit::event::{MouseButton, VirtualKeyCode};
+
+    use crate::window::event::Input;
+
+    use super::{GameInput, KeyBinding, KeyBindings};
+
+    fn key(key: Option<VirtualKeyCode>, scan_code: u32) -> Input {
+        Input::Key { key, scan_code }
+    }
+
+    #[test]
+    fn bind_by_scan_code() {
+        let mut bindings = KeyBindings::new();
+        // AZERTY `Z` is in place of QWERTY `W`
+        let forward = bindings.binding(GameInput::MoveForward).unwrap();
+        let KeyBinding::ScanCode(w) = forward else {
+            panic!("Movement should be bound by position");
+        };
+        assert_eq!(
+            bindings.input(key(Some(VirtualKeyCode::Z), w)),
+            Some(GameInput::MoveForward)
+        );
+        assert_eq!(
+            bindings.input(key(Some(VirtualKeyCode::Up), 0)),
+            Some(GameInput::MoveForward)
+        );
+
+        // Key is moved from the other input
+        bindings.bind(GameInput::ToggleMap, forward);
+        assert_eq!(bindings.input(key(None, w)), Some(GameInput::ToggleMap));
+
+        // Mouse buttons don't match keys
+        bindings.bind(GameInput::MoveUp, KeyBinding::Mouse(MouseButton::Right));
+        assert_eq!(
+            bindings.input(Input::Mouse(MouseButton::Right)),
+            Some(GameInput::MoveUp)
+        );
+        assert_eq!(bindings.input(Input::Mouse(MouseButton::Left)), None);
+        bindings.bind(
+            GameInput::MoveDown,
+            KeyBinding::Mouse(MouseButton::Other(4)),
+        );
+        assert_eq!(
+            bindings.binding(GameInput::MoveForward),
+            Some(KeyBinding::Key(VirtualKeyCode::Up))
+        );
+
+        let mut parsed = KeyBindings::new();
+        for line in bindings.to_source().lines() {
+            let (input, binding) = line.split_once(" = ").unwrap();
+            parsed
+                .parse_line(input.strip_prefix("bind.").unwrap(), binding)
+                .unwrap();
+        }
+        for input in GameInput::ALL {
+            assert_eq!(parsed.binding(input), bindings.binding(input));
+        }
+        assert_eq!(
+            KeyBinding::parse("key:LShift"),
+            Some(KeyBinding::Key(VirtualKeyCode::LShift))
+        );
+        assert_eq!(KeyBinding::parse("key:Shift"), None);
+    }
+}
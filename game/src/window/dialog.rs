@@ -0,0 +1,67 @@
+//! Native message boxes for errors which happen before anything can be drawn (e.g. no compatible
+//! graphics device). Uses only system facilities, so it works when the renderer doesn't
+
+/// Show a blocking error message box. Returns `false` if no message box could be shown
+#[cfg(target_os = "windows")]
+pub fn show_error(title: &str, message: &str) -> bool {
+    use std::{ffi::c_void, iter::once, ptr::null_mut};
+
+    const MB_ICONERROR: u32 = 0x10;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn MessageBoxW(hwnd: *mut c_void, text: *const u16, caption: *const u16, kind: u32) -> i32;
+    }
+
+    let wide = |text: &str| text.encode_utf16().chain(once(0)).collect::<Vec<_>>();
+    let (title, message) = (wide(title), wide(message));
+    // SAFETY: Strings are null-terminated and outlive the call
+    unsafe { MessageBoxW(null_mut(), message.as_ptr(), title.as_ptr(), MB_ICONERROR) != 0 }
+}
+
+/// Show a blocking error message box. Returns `false` if no message box could be shown
+#[cfg(target_os = "macos")]
+pub fn show_error(title: &str, message: &str) -> bool {
+    use std::process::Command;
+
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let script = format!(
+        "display alert {} message {} as critical",
+        quote(title),
+        quote(message)
+    );
+
+    Command::new("osascript")
+        .args(["-e", &script])
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Show a blocking error message box. Returns `false` if no message box could be shown.
+///
+/// Tries dialog tools of the common desktops, since there is no system API
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn show_error(title: &str, message: &str) -> bool {
+    use std::process::Command;
+
+    let tools = [
+        (
+            "zenity",
+            vec![
+                "--error",
+                "--no-markup",
+                "--title",
+                title,
+                "--text",
+                message,
+            ],
+        ),
+        ("kdialog", vec!["--title", title, "--error", message]),
+        ("xmessage", vec!["-center", message]),
+    ];
+
+    // Exit code only tells how the dialog has been closed
+    tools
+        .into_iter()
+        .any(|(tool, args)| Command::new(tool).args(args).status().is_ok())
+}
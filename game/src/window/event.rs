@@ -1,4 +1,4 @@
-use std::mem::replace;
+use std::{mem::replace, time::Instant};
 
 use common_log::span;
 use tracing::debug;
@@ -18,9 +18,13 @@ use super::Window;
 /// Represents input from keyboard and mouse
 #[derive(Clone, Copy, Debug)]
 pub enum Input {
-    Key(VirtualKeyCode),
+    /// Keyboard key. Keys which don't map to a virtual key code in the current layout have only
+    /// the scan code
+    Key {
+        key: Option<VirtualKeyCode>,
+        scan_code: ScanCode,
+    },
     Mouse(MouseButton),
-    ScanCode(ScanCode),
 }
 
 /// Represents incoming events
@@ -30,15 +34,14 @@ pub enum Event {
     Close,
     /// The window has been resized
     Resize(U32x2),
+    /// The window has been moved to a monitor with another scale factor (physical pixels per
+    /// logical pixel). Preceded by [`Event::Resize`]
+    ScaleFactor(f32),
     /// The cursor has been moved across the window
     MouseMove(F32x2, bool),
-    // A mouse button has been pressed/released
-    // TODO: Use this for mouse input after adding GameInputs
-    // MouseButton(MouseButton, ElementState),
     /// A mouse wheel has been scrolled
     Zoom(f32, bool),
-    // TODO: Add GameInput and keybindings
-    /// A keyboard button has been pressed/released
+    /// A keyboard key or a mouse button has been pressed/released
     Input(Input, ElementState, ModifiersState),
     /// The window is (un)focused
     Focused(bool),
@@ -46,11 +49,20 @@ pub enum Event {
 
 /// Window logic for processing incoming events
 impl Window {
-    // TODO: Don't hardcode this
+    /// Scaled by the sensitivity of the settings
     const MOTION_SENSITIVITY: f32 = 2.5;
     const EVENTS_PREALLOCATE: usize = 4;
 
     pub fn handle_window_event(&mut self, event: WindowEvent) {
+        if matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::MouseInput { .. }
+        ) {
+            self.input_received();
+        }
+
         // TODO: Check out occluded event
         match event {
             WindowEvent::Resized(_) => self.resized = true,
@@ -72,10 +84,10 @@ impl Window {
                     Some(VirtualKeyCode::F11) if matches!(input.state, ElementState::Released) => {
                         self.toggle_fullscreen = true
                     }
-                    virtual_keycode => self.events.push(Event::Input(
-                        match virtual_keycode {
-                            Some(key) => Input::Key(key),
-                            None => Input::ScanCode(input.scancode),
+                    key => self.events.push(Event::Input(
+                        Input::Key {
+                            key,
+                            scan_code: input.scancode,
                         },
                         input.state,
                         self.modifiers,
@@ -96,8 +108,12 @@ impl Window {
                 self.events
                     .push(Event::Input(Input::Mouse(button), state, self.modifiers))
             }
-            // TODO: Throw event when UI is implemented
-            WindowEvent::ScaleFactorChanged { .. } => self.resized = true,
+            // Window size changes along
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.scale_factor = scale_factor;
+                self.scale_factor_changed = true;
+                self.resized = true;
+            }
             _ => {}
         }
     }
@@ -109,6 +125,7 @@ impl Window {
         const MOTION_FIX: f32 = 1.0;
 
         if let DeviceEvent::MouseMotion { delta } = event {
+            self.input_received();
             self.events.push(Event::MouseMove(
                 F32x2::new(delta.0 as f32, delta.1 as f32) * Self::MOTION_SENSITIVITY * MOTION_FIX,
                 self.cursor_grabbed,
@@ -127,12 +144,20 @@ impl Window {
                 U32x2::new(width, height)
             };
 
-            self.renderer.on_resize(size);
+            self.renderer_mut().on_resize(size);
 
             // Emit event to notify UI and scene
             self.events.push(Event::Resize(size));
         }
 
+        // Handle deduplicated scale factor change event
+        if self.scale_factor_changed {
+            self.scale_factor_changed = false;
+            debug!(scale_factor = self.scale_factor, "Scale factor changed");
+            self.events
+                .push(Event::ScaleFactor(self.scale_factor as f32));
+        }
+
         // Handle deduplicated fullscreen toggle event
         if self.toggle_fullscreen {
             self.toggle_fullscreen = false;
@@ -176,4 +201,14 @@ impl Window {
             Vec::with_capacity(Self::EVENTS_PREALLOCATE),
         )
     }
+
+    /// When the oldest input event since the last call has been received. Used to measure input
+    /// latency
+    pub fn fetch_input_time(&mut self) -> Option<Instant> {
+        self.input_at.take()
+    }
+
+    fn input_received(&mut self) {
+        self.input_at.get_or_insert_with(Instant::now);
+    }
 }
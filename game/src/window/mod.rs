@@ -1,5 +1,8 @@
+use std::time::Instant;
+
 use tokio::runtime::Runtime;
 use tracing::{error, warn};
+use wgpu::Color;
 use winit::{
     dpi::LogicalSize,
     event::ModifiersState,
@@ -7,6 +10,7 @@ use winit::{
 };
 
 use crate::{
+    assets::AssetManager,
     consts::{MIN_WINDOW_HEIGHT, MIN_WINDOW_WIDTH},
     render::{error::RenderError, renderer::Renderer, RenderMode},
     types::EventLoop,
@@ -15,6 +19,8 @@ use crate::{
 
 use event::Event;
 
+pub mod binding;
+pub mod dialog;
 pub mod event;
 
 /// Handler for Winit Window and EventLoop
@@ -22,17 +28,23 @@ pub struct Window {
     /// winit window handle
     inner: WinitWindow,
 
-    renderer: Renderer,
+    /// Always present, except while being recreated
+    renderer: Option<Renderer>,
 
     pub fullscreen: bool,
     pub focused: bool,
     cursor_grabbed: bool,
 
     events: Vec<Event>,
+    /// When the oldest input event not fetched yet has been received
+    input_at: Option<Instant>,
     modifiers: ModifiersState,
+    /// Physical pixels per logical pixel of the current monitor
+    scale_factor: f64,
 
     // Deduplicated events
     resized: bool,
+    scale_factor_changed: bool,
     toggle_fullscreen: bool,
 }
 
@@ -40,48 +52,85 @@ impl Window {
     pub const INITIAL_WIDTH: u32 = 1280;
     pub const INITIAL_HEIGHT: u32 = 720;
 
-    pub fn new(runtime: &Runtime) -> Result<(Self, EventLoop), RenderError> {
+    pub fn new(
+        runtime: &Runtime,
+        render_mode: RenderMode,
+    ) -> Result<(Self, EventLoop), RenderError> {
         let event_loop = EventLoop::new();
 
         let window = WindowBuilder::new()
             .with_resizable(true)
             .with_transparent(false)
             .with_maximized(true)
+            // Shown once the renderer has drawn something
+            .with_visible(false)
             .with_min_inner_size(LogicalSize::new(MIN_WINDOW_WIDTH, MIN_WINDOW_HEIGHT))
-            .with_title(format!("ECG v{VERSION}"))
+            .with_title(Self::title())
             .with_inner_size(LogicalSize::new(Self::INITIAL_WIDTH, Self::INITIAL_HEIGHT))
             .build(&event_loop)
             .unwrap();
 
-        // TODO: Load `RenderMode` from settings
-        let renderer = Renderer::new(&window, RenderMode::new(), runtime)?;
+        let mut renderer = Renderer::new(&window, render_mode, AssetManager::new(), runtime)?;
+        if let Err(err) = renderer.present_clear(Color::BLACK) {
+            warn!(%err, "Failed to clear the first frame");
+        }
+        window.set_visible(true);
+        let scale_factor = window.scale_factor();
 
         Ok((
             Self {
                 inner: window,
-                renderer,
+                renderer: Some(renderer),
                 cursor_grabbed: false,
                 fullscreen: false,
                 focused: false,
                 events: Vec::new(),
+                input_at: None,
                 modifiers: Default::default(),
+                scale_factor,
                 resized: false,
+                scale_factor_changed: false,
                 toggle_fullscreen: false,
             },
             event_loop,
         ))
     }
 
+    /// Default window title
+    pub fn title() -> String {
+        format!("ECG v{VERSION}")
+    }
+
     pub fn inner(&self) -> &WinitWindow {
         &self.inner
     }
 
     pub fn renderer(&self) -> &Renderer {
-        &self.renderer
+        self.renderer.as_ref().expect("Renderer has been lost")
     }
 
     pub fn renderer_mut(&mut self) -> &mut Renderer {
-        &mut self.renderer
+        self.renderer.as_mut().expect("Renderer has been lost")
+    }
+
+    /// Rebuild renderer from scratch. See [`Renderer::recreate`]
+    pub fn recreate_renderer(&mut self, runtime: &Runtime) -> Result<(), RenderError> {
+        let renderer = self.renderer.take().expect("Renderer has been lost");
+        self.renderer = Some(renderer.recreate(&self.inner, runtime)?);
+
+        Ok(())
+    }
+
+    /// Wait for GPU to finish and drop the renderer. Used on shutdown
+    pub fn destroy_renderer(&mut self) {
+        if let Some(mut renderer) = self.renderer.take() {
+            renderer.wait_idle();
+        }
+    }
+
+    /// Physical pixels per logical pixel of the current monitor
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
     }
 
     pub fn cursor_grabbed(&self) -> bool {
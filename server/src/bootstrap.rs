@@ -0,0 +1,31 @@
+use std::{env::var, str::FromStr};
+
+use thiserror::Error;
+use tracing::metadata::LevelFilter;
+use tracing_subscriber::{fmt::fmt, EnvFilter};
+
+#[derive(Error, Debug)]
+pub enum BootstrapError {
+    #[error("Can't parse log level (found: {0:?})")]
+    LogLevelError(Option<String>),
+}
+
+pub fn bootstrap() -> Result<(), BootstrapError> {
+    let filter = EnvFilter::default().add_directive(
+        match var("LOG_LEVEL") {
+            Ok(level) => match LevelFilter::from_str(level.to_lowercase().as_str()) {
+                Ok(level) => level,
+                Err(_) => return Err(BootstrapError::LogLevelError(Some(level))),
+            },
+            #[cfg(debug_assertions)]
+            Err(_) => LevelFilter::DEBUG,
+            #[cfg(not(debug_assertions))]
+            Err(_) => LevelFilter::INFO,
+        }
+        .into(),
+    );
+
+    fmt().with_env_filter(filter).init();
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::collections::HashSet;
+
+use common::{
+    coord::ChunkId,
+    entity::EntityId,
+    movement::InputSeq,
+    net::{connection::Connection, protocol::ServerMsg},
+};
+
+/// Connected client state
+pub struct Client {
+    pub conn: Connection,
+    /// Player name. Set after handshake
+    pub name: Option<String>,
+    /// Player entity. Set after handshake
+    pub entity: Option<EntityId>,
+    /// Chunks requested by the client, but not sent yet
+    pub chunk_requests: HashSet<ChunkId>,
+    /// Last processed movement input
+    pub last_input: Option<InputSeq>,
+    /// Movement time (in seconds) client is allowed to spend. Protects from speed hacks
+    pub input_budget: f32,
+    /// Client should be disconnected
+    pub disconnected: bool,
+}
+
+impl Client {
+    pub fn new(conn: Connection) -> Self {
+        Self {
+            conn,
+            name: None,
+            entity: None,
+            chunk_requests: HashSet::new(),
+            last_input: None,
+            input_budget: 0.0,
+            disconnected: false,
+        }
+    }
+
+    /// Send disconnect reason and mark client as disconnected
+    pub fn kick(&mut self, reason: String) {
+        self.conn.send(&ServerMsg::Disconnect { reason });
+        // Best effort, connection is dropped at the end of the tick
+        let _ = self.conn.flush();
+        self.disconnected = true;
+    }
+}
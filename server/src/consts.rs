@@ -0,0 +1,31 @@
+use lazy_static::lazy_static;
+
+pub const DEFAULT_TPS: u32 = 20;
+pub const DEFAULT_VIEW_DISTANCE: u16 = 4;
+pub const DEFAULT_WORLD_PATH: &str = "world";
+
+/// Autosave interval in ticks
+pub const AUTOSAVE_INTERVAL: u64 = 60 * DEFAULT_TPS as u64;
+/// Scheduled backup interval in minutes
+pub const DEFAULT_BACKUP_INTERVAL: u32 = 30;
+/// Number of world backups kept
+pub const DEFAULT_BACKUPS_KEPT: usize = 5;
+/// Random block ticks per loaded chunk per server tick
+pub const RANDOM_TICKS_PER_CHUNK: usize = 3;
+/// Max block changes sent as a chunk delta. Full chunk is resent above that
+pub const MAX_CHUNK_DELTA: usize = 64;
+/// Interval (in ticks) of full resync for chunks updated with deltas
+pub const CHUNK_RESYNC_INTERVAL: u64 = 30 * DEFAULT_TPS as u64;
+/// Max chunks sent to a single client per tick
+pub const CHUNKS_PER_TICK: usize = 8;
+/// Max duration of a single movement input (in seconds)
+pub const MAX_INPUT_DT: f32 = 0.25;
+/// Max accumulated movement time of a client (in seconds)
+pub const MAX_INPUT_BUDGET: f32 = 1.0;
+/// Max distance between player and edited block
+pub const REACH_DISTANCE: f32 = 16.0;
+
+lazy_static! {
+    pub static ref CPU_CORES: usize = num_cpus::get();
+    pub static ref BLOCKING_THREADS: usize = (*CPU_CORES / 2).max(2);
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use common::entity::{EntityId, EntityKind};
+use glam::{Vec2, Vec3};
+
+/// Server-side entity state
+#[derive(Clone, Debug)]
+pub struct Entity {
+    pub kind: EntityKind,
+    /// Name shown above the entity
+    pub name: Option<String>,
+    pub pos: Vec3,
+    pub rot: Vec2,
+    /// State has been changed since last snapshot
+    pub changed: bool,
+}
+
+/// Stores every entity in the world
+#[derive(Default)]
+pub struct Entities {
+    next_id: EntityId,
+    pub inner: HashMap<EntityId, Entity>,
+}
+
+impl Entities {
+    pub fn spawn(&mut self, kind: EntityKind, pos: Vec3, rot: Vec2) -> EntityId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.inner.insert(
+            id,
+            Entity {
+                kind,
+                name: None,
+                pos,
+                rot,
+                changed: true,
+            },
+        );
+
+        id
+    }
+
+    pub fn despawn(&mut self, id: EntityId) -> Option<Entity> {
+        self.inner.remove(&id)
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        self.inner.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        self.inner.get_mut(&id)
+    }
+}
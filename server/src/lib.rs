@@ -0,0 +1,904 @@
+use std::{
+    collections::HashSet,
+    io,
+    net::TcpListener,
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+};
+
+use common::{
+    block::Block,
+    clock::Clock,
+    coord::{ChunkId, GlobalCoord},
+    entity::EntityKind,
+    event::{EventBus, WorldEvent},
+    net::{
+        compression::Compression,
+        connection::Connection,
+        protocol::{ClientMsg, ServerMsg},
+        PROTOCOL_VERSION,
+    },
+    sky::{TimeOfDay, Weather},
+};
+use common_log::span;
+use glam::{Vec2, Vec3};
+use tokio::{runtime::Runtime, task::JoinHandle};
+use tracing::{debug, error, info, warn};
+
+pub mod bootstrap;
+pub mod client;
+pub mod consts;
+pub mod entity;
+#[cfg(feature = "remote-console")]
+pub mod remote;
+pub mod settings;
+pub mod world;
+
+use crate::{
+    client::Client,
+    consts::{
+        AUTOSAVE_INTERVAL, CHUNKS_PER_TICK, CHUNK_RESYNC_INTERVAL, MAX_INPUT_BUDGET, MAX_INPUT_DT,
+        REACH_DISTANCE,
+    },
+    entity::Entities,
+    settings::ServerSettings,
+    world::{backup::WorldBackups, storage::WorldStorage, World},
+};
+
+/// Commands read from the server console
+#[derive(Clone, Debug)]
+pub enum ConsoleCommand {
+    Stop,
+    Save,
+    /// Show world statistics
+    Stats,
+    /// Save the world and back it up
+    Backup,
+    /// List world backups
+    Backups,
+    List,
+    /// Show spawn point or move it
+    Spawn(Option<Vec3>),
+    /// Move player to the position (spawn point if `None`)
+    Teleport {
+        name: String,
+        pos: Option<Vec3>,
+    },
+    Time(TimeCommand),
+    /// Show weather or change it
+    Weather(Option<Weather>),
+}
+
+/// Time of day control
+#[derive(Clone, Copy, Debug)]
+pub enum TimeCommand {
+    Show,
+    Set(TimeOfDay),
+    /// Move time by hours (may be negative)
+    Add(f32),
+}
+
+/// Console command with an optional channel for the command output
+#[derive(Debug)]
+pub struct ConsoleRequest {
+    pub command: ConsoleCommand,
+    pub reply: Option<Sender<String>>,
+}
+
+impl ConsoleCommand {
+    /// Parse command line. Leading `/` is optional
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let mut args = line.strip_prefix('/').unwrap_or(line).split_whitespace();
+
+        let command = match args.next()? {
+            "stop" => Self::Stop,
+            "save" => Self::Save,
+            "stats" => Self::Stats,
+            "backup" => Self::Backup,
+            "backups" => Self::Backups,
+            "list" => Self::List,
+            "spawn" => Self::Spawn(Self::parse_pos(&mut args)?),
+            "tp" => Self::Teleport {
+                name: args.next()?.to_string(),
+                pos: Self::parse_pos(&mut args)?,
+            },
+            "time" => Self::Time(match args.next() {
+                None => TimeCommand::Show,
+                Some("set") => TimeCommand::Set(args.next()?.parse().ok()?),
+                Some("add") => {
+                    TimeCommand::Add(args.next()?.parse().ok().filter(|h: &f32| h.is_finite())?)
+                }
+                Some(_) => return None,
+            }),
+            "weather" => Self::Weather(match args.next() {
+                Some(weather) => Some(weather.parse().ok()?),
+                None => None,
+            }),
+            _ => return None,
+        };
+
+        // Trailing arguments are not allowed
+        args.next().is_none().then_some(command)
+    }
+
+    /// Parse optional `x y z` position. Returns `None` if the position is incomplete or invalid
+    fn parse_pos<'a>(args: &mut impl Iterator<Item = &'a str>) -> Option<Option<Vec3>> {
+        let Some(x) = args.next() else {
+            return Some(None);
+        };
+        let coord = |arg: Option<&str>| arg?.parse::<f32>().ok().filter(|v| v.is_finite());
+
+        Some(Some(Vec3::new(
+            coord(Some(x))?,
+            coord(args.next())?,
+            coord(args.next())?,
+        )))
+    }
+}
+
+/// Dedicated server instance
+pub struct Server {
+    pub settings: ServerSettings,
+    pub runtime: Runtime,
+    pub clock: Clock,
+
+    listener: TcpListener,
+    clients: Vec<Client>,
+    console_rx: Receiver<ConsoleRequest>,
+
+    pub world: World,
+    pub backups: WorldBackups,
+    /// World changes made by players, readable on the next tick
+    pub events: EventBus,
+    pub entities: Entities,
+    pub time: TimeOfDay,
+    pub weather: Weather,
+
+    tick: u64,
+    running: bool,
+    /// Backup being copied
+    backup_task: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    pub fn new(settings: ServerSettings, runtime: Runtime) -> io::Result<Self> {
+        span!(_guard, "ServerInit");
+
+        let backups = WorldBackups::new(&settings.world_path, settings.backups_kept);
+        if let Some(name) = &settings.restore {
+            info!(name, "Restoring world from backup");
+            backups.restore(name)?;
+        }
+
+        info!(path = ?settings.world_path, "Opening world");
+        let storage = WorldStorage::open(&settings.world_path)?;
+        let world = World::open(storage, settings.bounds, &backups)?;
+
+        info!(address = %settings.address, "Listening for connections");
+        let listener = TcpListener::bind(settings.address)?;
+        listener.set_nonblocking(true)?;
+
+        let (console_tx, console_rx) = channel();
+        #[cfg(feature = "remote-console")]
+        if let Some(address) = settings.remote_console {
+            info!(%address, "Listening for remote console");
+            remote::spawn(address, console_tx.clone())?;
+        }
+        thread::Builder::new()
+            .name("console".to_string())
+            .spawn(move || {
+                for line in io::stdin().lines().map_while(Result::ok) {
+                    match ConsoleCommand::parse(&line) {
+                        Some(command) => {
+                            let request = ConsoleRequest {
+                                command,
+                                reply: None,
+                            };
+                            if console_tx.send(request).is_err() {
+                                break;
+                            }
+                        }
+                        None => warn!("Unknown command: {:?}", line.trim()),
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            clock: Clock::new(Clock::tps_to_duration(settings.tps)),
+            settings,
+            runtime,
+            listener,
+            clients: Vec::new(),
+            console_rx,
+            world,
+            backups,
+            events: EventBus::new(),
+            entities: Entities::default(),
+            time: TimeOfDay::default(),
+            weather: Weather::default(),
+            tick: 0,
+            running: true,
+            backup_task: None,
+        })
+    }
+
+    pub fn running(&self) -> bool {
+        self.running
+    }
+
+    /// Run server until stopped
+    pub fn run(mut self) {
+        debug!("Entering server loop");
+
+        while self.running {
+            self.tick();
+            self.clock.tick();
+        }
+
+        self.shutdown();
+    }
+
+    /// Single simulation step
+    pub fn tick(&mut self) {
+        span!(_guard, "tick", "Server::tick");
+
+        self.tick += 1;
+        self.time.advance(self.clock.duration().as_secs_f32());
+
+        self.events.update();
+        let stats = self.world.stats_mut();
+        stats.collect(&self.events);
+        if self.clients.iter().any(|client| client.entity.is_some()) {
+            stats.play(self.clock.duration());
+        }
+
+        self.handle_console();
+        self.accept_clients();
+        self.handle_clients();
+        self.maintain_world();
+
+        self.world.random_ticks(self.tick);
+
+        self.send_chunks();
+        self.send_chunk_updates();
+        self.send_entities();
+        self.flush_clients();
+
+        if self.tick.is_multiple_of(AUTOSAVE_INTERVAL) {
+            let saved = self.world.save();
+            debug!(saved, "World autosave");
+        }
+
+        let backup_interval = self.settings.backup_interval as u64 * 60 * self.settings.tps as u64;
+        if backup_interval > 0 && self.tick.is_multiple_of(backup_interval) {
+            self.backup("scheduled");
+        }
+    }
+
+    /// Save the world and copy it in background. Returns `false` if the previous backup is still
+    /// being copied
+    fn backup(&mut self, reason: &'static str) -> bool {
+        if matches!(&self.backup_task, Some(task) if !task.is_finished()) {
+            warn!("Previous backup isn't finished yet, skipping");
+            return false;
+        }
+
+        self.world.save();
+        let backups = self.backups.clone();
+        self.backup_task = Some(self.runtime.spawn_blocking(move || {
+            if let Err(err) = backups.create(reason) {
+                error!(%err, "Failed to back up world");
+            }
+        }));
+
+        true
+    }
+
+    fn handle_console(&mut self) {
+        while let Ok(ConsoleRequest { command, reply }) = self.console_rx.try_recv() {
+            let output = match command {
+                ConsoleCommand::Stop => {
+                    self.running = false;
+                    "Stopping server".to_string()
+                }
+                ConsoleCommand::Save => {
+                    let saved = self.world.save();
+                    format!("World saved ({saved} chunks)")
+                }
+                ConsoleCommand::Stats => self.world.stats().to_string(),
+                ConsoleCommand::Backup => {
+                    if self.backup("manual") {
+                        format!("Backing up world to {:?}", self.backups.dir())
+                    } else {
+                        "Previous backup isn't finished yet".to_string()
+                    }
+                }
+                ConsoleCommand::Backups => match self.backups.list() {
+                    Ok(names) if names.is_empty() => "No backups".to_string(),
+                    Ok(names) => format!(
+                        "Backups ({}), restore with --restore <name>: {}",
+                        names.len(),
+                        names.join(", ")
+                    ),
+                    Err(err) => format!("Failed to list backups: {err}"),
+                },
+                ConsoleCommand::List => {
+                    let names = self
+                        .clients
+                        .iter()
+                        .filter_map(|client| client.name.as_deref())
+                        .collect::<Vec<_>>();
+                    format!("Players online ({}): {}", names.len(), names.join(", "))
+                }
+                ConsoleCommand::Spawn(None) => format!("Spawn point: {}", self.world.spawn()),
+                ConsoleCommand::Spawn(Some(pos)) => match self.world.set_spawn(pos) {
+                    Ok(()) => format!("Spawn point moved to {pos}"),
+                    Err(err) => format!("Failed to save spawn point: {err}"),
+                },
+                ConsoleCommand::Teleport { name, pos } => {
+                    let pos = pos.unwrap_or_else(|| self.world.spawn());
+                    self.teleport(&name, pos)
+                }
+                ConsoleCommand::Time(TimeCommand::Show) => format!("Time: {}", self.time),
+                ConsoleCommand::Time(TimeCommand::Set(time)) => {
+                    self.time = time;
+                    self.send_world_time();
+                    format!("Time set to {time}")
+                }
+                ConsoleCommand::Time(TimeCommand::Add(hours)) => {
+                    self.time.add(hours);
+                    self.send_world_time();
+                    format!("Time set to {}", self.time)
+                }
+                ConsoleCommand::Weather(None) => format!("Weather: {}", self.weather),
+                ConsoleCommand::Weather(Some(weather)) => {
+                    self.weather = weather;
+                    self.send_world_time();
+                    format!("Weather set to {weather}")
+                }
+            };
+
+            info!("{output}");
+            if let Some(reply) = reply {
+                // Remote console may have disconnected already
+                let _ = reply.send(output);
+            }
+        }
+    }
+
+    /// Notify joined clients about changed time or weather
+    fn send_world_time(&mut self) {
+        let msg = ServerMsg::WorldTime {
+            time: self.time,
+            weather: self.weather,
+        };
+        self.clients
+            .iter_mut()
+            .filter(|client| client.entity.is_some())
+            .for_each(|client| client.conn.send(&msg));
+    }
+
+    /// Move player and notify its client. Returns command output
+    fn teleport(&mut self, name: &str, pos: Vec3) -> String {
+        let client = self
+            .clients
+            .iter_mut()
+            .find(|client| client.name.as_deref() == Some(name));
+        let Some((client, entity)) = client.and_then(|client| {
+            let entity = client.entity.and_then(|id| self.entities.get_mut(id))?;
+            Some((client, entity))
+        }) else {
+            return format!("Player {name:?} not found");
+        };
+
+        entity.pos = pos;
+        entity.changed = true;
+        client.conn.send(&ServerMsg::Teleport { pos });
+
+        format!("Teleported {name} to {pos}")
+    }
+
+    fn accept_clients(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, addr)) => match Connection::new(stream) {
+                    Ok(conn) => {
+                        info!(%addr, "New connection");
+                        self.clients.push(Client::new(conn));
+                    }
+                    Err(err) => warn!(%addr, %err, "Failed to set up connection"),
+                },
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    warn!(%err, "Failed to accept connection");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_clients(&mut self) {
+        span!(_guard, "handle_clients", "Server::handle_clients");
+
+        let mut spawned = Vec::new();
+
+        let tick_dur = self.clock.duration().as_secs_f32();
+
+        for client in self.clients.iter_mut() {
+            client.input_budget = (client.input_budget + tick_dur).min(MAX_INPUT_BUDGET);
+            let last_input = client.last_input;
+
+            let messages = match client.conn.recv::<ClientMsg>() {
+                Ok(messages) => messages,
+                Err(err) => {
+                    debug!(addr = %client.conn.addr(), %err, "Client connection lost");
+                    client.disconnected = true;
+                    continue;
+                }
+            };
+
+            for msg in messages {
+                match msg {
+                    ClientMsg::Hello {
+                        version,
+                        registry,
+                        name,
+                        compression,
+                    } if client.entity.is_none() => {
+                        if version != PROTOCOL_VERSION {
+                            warn!(addr = %client.conn.addr(), version, "Incompatible client version");
+                            client.kick(format!(
+                                "Incompatible version (server protocol: {PROTOCOL_VERSION}, \
+                                 client protocol: {version})"
+                            ));
+                            break;
+                        }
+                        if registry != Block::registry_hash() {
+                            warn!(addr = %client.conn.addr(), "Block registry mismatch");
+                            client
+                                .kick("Incompatible version (block registry mismatch)".to_string());
+                            break;
+                        }
+
+                        let id =
+                            self.entities
+                                .spawn(EntityKind::Player, self.world.spawn(), Vec2::ZERO);
+                        if let Some(entity) = self.entities.get_mut(id) {
+                            entity.name = Some(name.clone());
+                        }
+                        let compression = if self.settings.compression {
+                            Compression::negotiate(&compression)
+                        } else {
+                            Compression::None
+                        };
+                        info!(%name, id, ?compression, "Player joined");
+
+                        client.conn.send(&ServerMsg::Welcome {
+                            entity_id: id,
+                            tps: self.settings.tps,
+                            spawn: self.world.spawn(),
+                            compression,
+                            bounds: *self.world.bounds(),
+                        });
+                        // Welcome itself is sent uncompressed
+                        client.conn.set_compression(compression);
+                        client.conn.send(&ServerMsg::WorldTime {
+                            time: self.time,
+                            weather: self.weather,
+                        });
+                        // Notify new client about existing entities
+                        self.entities
+                            .inner
+                            .iter()
+                            .filter(|(&other, _)| other != id)
+                            .for_each(|(&other, entity)| {
+                                client.conn.send(&ServerMsg::EntitySpawn {
+                                    id: other,
+                                    kind: entity.kind,
+                                    name: entity.name.clone().unwrap_or_default(),
+                                    pos: entity.pos,
+                                    rot: entity.rot,
+                                })
+                            });
+
+                        client.name = Some(name);
+                        client.entity = Some(id);
+                        spawned.push(id);
+                    }
+                    ClientMsg::PlayerInput(mut input) => {
+                        if !input.is_finite() {
+                            warn!(addr = %client.conn.addr(), "Non-finite player input");
+                            client.kick("Invalid player input".to_string());
+                            break;
+                        }
+                        if let Some(entity) = client.entity.and_then(|id| self.entities.get_mut(id))
+                        {
+                            // Inputs that exceed movement budget are acknowledged but ignored
+                            input.dt = input.dt.clamp(0.0, MAX_INPUT_DT);
+                            if input.dt <= client.input_budget {
+                                client.input_budget -= input.dt;
+                                let pos = input.apply_collide(entity.pos, |pos| {
+                                    self.world.block(pos).map(|block| block.solid())
+                                });
+                                self.world.stats_mut().travel(pos.distance(entity.pos));
+                                entity.pos = pos;
+                            }
+                            entity.rot = input.rot;
+                            entity.changed = true;
+                            client.last_input = Some(input.seq);
+                        }
+                    }
+                    ClientMsg::RequestChunk(id) => {
+                        // Only chunks around the player are sent, otherwise a single client could
+                        // make the server generate and keep the whole world
+                        let Some(entity) = client.entity.and_then(|id| self.entities.get(id))
+                        else {
+                            debug!(addr = %client.conn.addr(), ?id, "Chunk request before handshake");
+                            continue;
+                        };
+                        let center = GlobalCoord::from_vec3(entity.pos).to_chunk_id();
+
+                        // Chunks out of bounds would never be sent
+                        if self.world.bounds().contains_chunk(id)
+                            && self.settings.view_area(center).contains(id)
+                            && client.chunk_requests.len() < self.settings.view_area_size()
+                        {
+                            client.chunk_requests.insert(id);
+                        }
+                    }
+                    ClientMsg::BlockEdit { id, pos, block } => {
+                        let player = client.entity.and_then(|id| self.entities.get(id));
+                        let before = self.world.block(pos).unwrap_or_default();
+                        let accepted = !self.settings.read_only
+                            && player.is_some_and(|player| {
+                                player.pos.distance(pos.as_vec()) <= REACH_DISTANCE
+                            })
+                            && self.world.set_block(pos, block);
+
+                        if !accepted {
+                            debug!(name = ?client.name, ?pos, ?block, "Block edit rejected");
+                        } else if before != block {
+                            self.events.publish(WorldEvent::BlockEdited {
+                                pos,
+                                before,
+                                after: block,
+                            });
+                        }
+
+                        client.conn.send(&ServerMsg::BlockEditAck {
+                            id,
+                            accepted,
+                            pos,
+                            block: self.world.block(pos).unwrap_or_default(),
+                        });
+                    }
+                    ClientMsg::Ping(seq) => client.conn.send(&ServerMsg::Pong(seq)),
+                    ClientMsg::RequestStats => {
+                        client
+                            .conn
+                            .send(&ServerMsg::WorldStats(*self.world.stats()));
+                        match self.backups.list() {
+                            Ok(names) => client.conn.send(&ServerMsg::Backups(names)),
+                            Err(err) => warn!(%err, "Failed to list backups"),
+                        }
+                    }
+                    ClientMsg::Disconnect => client.disconnected = true,
+                    ClientMsg::Hello { .. } => warn!("Repeated handshake. Ignoring"),
+                }
+            }
+
+            // Acknowledge processed inputs
+            if let (Some(seq), Some(entity)) = (
+                client
+                    .last_input
+                    .filter(|_| client.last_input != last_input),
+                client.entity.and_then(|id| self.entities.get(id)),
+            ) {
+                client.conn.send(&ServerMsg::PlayerAck {
+                    seq,
+                    pos: entity.pos,
+                });
+            }
+        }
+
+        // Notify other clients about new players
+        spawned.into_iter().for_each(|id| {
+            if let Some(entity) = self.entities.get(id) {
+                let msg = ServerMsg::EntitySpawn {
+                    id,
+                    kind: entity.kind,
+                    name: entity.name.clone().unwrap_or_default(),
+                    pos: entity.pos,
+                    rot: entity.rot,
+                };
+                self.clients
+                    .iter_mut()
+                    .filter(|client| client.entity.is_some_and(|entity| entity != id))
+                    .for_each(|client| client.conn.send(&msg));
+            }
+        });
+
+        self.remove_disconnected();
+    }
+
+    fn remove_disconnected(&mut self) {
+        let mut despawned = Vec::new();
+
+        self.clients.retain(|client| {
+            if client.disconnected {
+                if let Some(id) = client.entity {
+                    let stats = client.conn.stats();
+                    info!(
+                        name = ?client.name,
+                        id,
+                        sent = stats.bytes_sent,
+                        ratio = stats.sent_ratio(),
+                        "Player left"
+                    );
+                    despawned.push(id);
+                }
+            }
+
+            !client.disconnected
+        });
+
+        despawned.into_iter().for_each(|id| {
+            self.entities.despawn(id);
+            self.broadcast(&ServerMsg::EntityDespawn(id));
+        });
+    }
+
+    /// Load chunks around players and requested chunks, unload the rest
+    fn maintain_world(&mut self) {
+        let areas = self
+            .clients
+            .iter()
+            .filter_map(|client| client.entity.and_then(|id| self.entities.get(id)))
+            .map(|entity| {
+                self.settings
+                    .view_area(GlobalCoord::from_vec3(entity.pos).to_chunk_id())
+            })
+            .collect::<Vec<_>>();
+
+        let requested = self
+            .clients
+            .iter()
+            .flat_map(|client| client.chunk_requests.iter().copied())
+            .collect::<HashSet<_>>();
+
+        areas
+            .iter()
+            .flat_map(|area| area.clone())
+            .chain(requested.iter().copied())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .for_each(|id| self.world.request(&self.runtime, id));
+
+        self.world.maintain(|id: ChunkId| {
+            requested.contains(&id) || areas.iter().any(|area| area.contains(id))
+        });
+    }
+
+    fn send_chunks(&mut self) {
+        span!(_guard, "send_chunks", "Server::send_chunks");
+
+        for client in self.clients.iter_mut() {
+            let Some(entity) = client.entity.and_then(|id| self.entities.get(id)) else {
+                continue;
+            };
+            // Requests the player moved away from are dropped
+            let area = self
+                .settings
+                .view_area(GlobalCoord::from_vec3(entity.pos).to_chunk_id());
+            let mut sent = 0;
+            let world = &self.world;
+
+            client.chunk_requests.retain(|&id| {
+                if !area.contains(id) {
+                    return false;
+                }
+                if sent >= CHUNKS_PER_TICK {
+                    return true;
+                }
+
+                match world.chunk(id) {
+                    Some(chunk) => {
+                        client.conn.send(&ServerMsg::ChunkData {
+                            id,
+                            chunk: chunk.clone(),
+                        });
+                        sent += 1;
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+    }
+
+    /// Broadcast changed blocks (accepted edits and block ticks)
+    fn send_chunk_updates(&mut self) {
+        span!(_guard, "send_chunk_updates", "Server::send_chunk_updates");
+
+        let resync = self.tick.is_multiple_of(CHUNK_RESYNC_INTERVAL);
+        let updates = self.world.chunk_updates(resync);
+        updates.iter().for_each(|msg| self.broadcast(msg));
+    }
+
+    fn send_entities(&mut self) {
+        span!(_guard, "send_entities", "Server::send_entities");
+
+        let states = self
+            .entities
+            .inner
+            .iter_mut()
+            .filter(|(_, entity)| entity.changed)
+            .map(|(&id, entity)| {
+                entity.changed = false;
+                ServerMsg::EntityState {
+                    id,
+                    pos: entity.pos,
+                    rot: entity.rot,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        states.iter().for_each(|msg| self.broadcast(msg));
+    }
+
+    fn flush_clients(&mut self) {
+        self.clients.iter_mut().for_each(|client| {
+            if let Err(err) = client.conn.flush() {
+                debug!(addr = %client.conn.addr(), %err, "Failed to send data");
+                client.disconnected = true;
+            }
+        });
+    }
+
+    /// Send message to every client that completed handshake
+    pub fn broadcast(&mut self, msg: &ServerMsg) {
+        self.clients
+            .iter_mut()
+            .filter(|client| client.entity.is_some())
+            .for_each(|client| client.conn.send(msg));
+    }
+
+    /// Disconnect clients and save the world
+    fn shutdown(&mut self) {
+        info!("Shutting down");
+
+        self.broadcast(&ServerMsg::Disconnect {
+            reason: "Server closed".to_string(),
+        });
+        self.flush_clients();
+
+        let saved = self.world.save();
+        info!(saved, "World saved");
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// Testing
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::TcpStream, path::PathBuf, thread, time::Duration};
+
+    use common::{
+        block::Block,
+        coord::{ChunkId, GlobalCoord},
+        movement::PlayerInput,
+        net::{
+            connection::Connection,
+            protocol::{ClientMsg, ServerMsg},
+            PROTOCOL_VERSION,
+        },
+    };
+    use tokio::runtime::Runtime;
+
+    use crate::{settings::ServerSettings, Server};
+
+    /// Tick the server until the client receives a message matching `until`
+    fn tick_until(
+        server: &mut Server,
+        conn: &mut Connection,
+        until: impl Fn(&ServerMsg) -> bool,
+    ) -> ServerMsg {
+        for _ in 0..1000 {
+            server.tick();
+            if let Some(msg) = conn.recv::<ServerMsg>().unwrap().into_iter().find(&until) {
+                return msg;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("Expected message wasn't received");
+    }
+
+    /// Start a server with a temporary world and connect to it
+    fn connect(name: &str) -> (Server, Connection, PathBuf) {
+        let world_path = std::env::temp_dir().join(format!("ecg-{name}-{}", std::process::id()));
+        let settings = ServerSettings {
+            address: ([127, 0, 0, 1], 0).into(),
+            world_path: world_path.clone(),
+            ..Default::default()
+        };
+        let server = Server::new(settings, Runtime::new().unwrap()).unwrap();
+        let addr = server.listener.local_addr().unwrap();
+        let conn = Connection::new(TcpStream::connect(addr).unwrap()).unwrap();
+
+        (server, conn, world_path)
+    }
+
+    fn hello(server: &mut Server, conn: &mut Connection) {
+        conn.send(&ClientMsg::Hello {
+            version: PROTOCOL_VERSION,
+            registry: Block::registry_hash(),
+            name: "player".to_string(),
+            compression: Vec::new(),
+        });
+        conn.flush().unwrap();
+        tick_until(server, conn, |msg| matches!(msg, ServerMsg::Welcome { .. }));
+    }
+
+    #[test]
+    fn chunk_requests_limited_to_view_area() {
+        let (mut server, mut conn, world_path) = connect("requests");
+        let far = ChunkId::new(server.settings.view_distance as i64 * 4, 0, 0);
+        assert_eq!(
+            server.settings.view_area(ChunkId::ZERO).count(),
+            server.settings.view_area_size()
+        );
+
+        // Before handshake
+        conn.send(&ClientMsg::RequestChunk(ChunkId::ZERO));
+        conn.flush().unwrap();
+        for _ in 0..10 {
+            server.tick();
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(server.clients[0].chunk_requests.is_empty());
+
+        hello(&mut server, &mut conn);
+        let spawn = GlobalCoord::from_vec3(server.world.spawn()).to_chunk_id();
+        conn.send(&ClientMsg::RequestChunk(far));
+        conn.send(&ClientMsg::RequestChunk(spawn));
+        conn.flush().unwrap();
+        tick_until(
+            &mut server,
+            &mut conn,
+            |msg| matches!(msg, ServerMsg::ChunkData { id, .. } if *id == spawn),
+        );
+        assert!(server.world.chunk(far).is_none());
+        assert!(server.clients[0].chunk_requests.is_empty());
+
+        drop(server);
+        fs::remove_dir_all(world_path).unwrap();
+    }
+
+    #[test]
+    fn kick_on_non_finite_input() {
+        let (mut server, mut conn, world_path) = connect("input");
+        hello(&mut server, &mut conn);
+
+        conn.send(&ClientMsg::PlayerInput(PlayerInput {
+            seq: 1,
+            forward: f32::NAN,
+            dt: 0.05,
+            ..Default::default()
+        }));
+        conn.flush().unwrap();
+        let msg = tick_until(&mut server, &mut conn, |msg| {
+            matches!(msg, ServerMsg::Disconnect { .. })
+        });
+
+        assert!(
+            matches!(msg, ServerMsg::Disconnect { reason } if reason == "Invalid player input")
+        );
+        assert!(server.entities.inner.is_empty());
+
+        drop(server);
+        fs::remove_dir_all(world_path).unwrap();
+    }
+}
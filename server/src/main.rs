@@ -0,0 +1,41 @@
+use std::env::args;
+
+use tokio::runtime::Builder;
+use tracing::{error, info};
+
+use ecg_server::{
+    bootstrap::bootstrap, consts::BLOCKING_THREADS, settings::ServerSettings, Server,
+};
+
+fn main() {
+    if args().any(|arg| arg == "--help") {
+        println!("{}", ServerSettings::USAGE);
+        return;
+    }
+
+    if let Err(err) = bootstrap() {
+        eprintln!("{err}");
+        return;
+    }
+
+    let settings = match ServerSettings::from_args(args().skip(1)) {
+        Ok(settings) => settings,
+        Err(err) => {
+            error!("{err}");
+            return;
+        }
+    };
+
+    info!(?settings, "Starting ECG server");
+
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .max_blocking_threads(*BLOCKING_THREADS)
+        .build()
+        .unwrap();
+
+    match Server::new(settings, runtime) {
+        Ok(server) => server.run(),
+        Err(err) => error!("Failed to start server: {err}"),
+    }
+}
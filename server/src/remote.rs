@@ -0,0 +1,90 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::mpsc::{channel, Sender},
+    thread,
+    time::Duration,
+};
+
+use tracing::{info, warn};
+
+use crate::{ConsoleCommand, ConsoleRequest};
+
+/// How long a remote console waits for the server to execute a command
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Start remote console listener. Only connections from loopback addresses are accepted
+pub fn spawn(address: SocketAddr, console_tx: Sender<ConsoleRequest>) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+
+    thread::Builder::new()
+        .name("remote-console".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!(%err, "Failed to accept remote console");
+                        continue;
+                    }
+                };
+
+                match stream.peer_addr() {
+                    Ok(addr) if addr.ip().is_loopback() => {
+                        info!(%addr, "Remote console connected");
+                        let console_tx = console_tx.clone();
+                        let spawned = thread::Builder::new()
+                            .name("remote-console-client".to_string())
+                            .spawn(move || {
+                                if let Err(err) = serve(stream, console_tx) {
+                                    warn!(%addr, %err, "Remote console error");
+                                }
+                                info!(%addr, "Remote console disconnected");
+                            });
+                        if let Err(err) = spawned {
+                            warn!(%err, "Failed to spawn remote console thread");
+                        }
+                    }
+                    Ok(addr) => warn!(%addr, "Rejected non-local remote console"),
+                    Err(err) => warn!(%err, "Failed to get remote console address"),
+                }
+            }
+        })?;
+
+    Ok(())
+}
+
+/// Pipe lines from the stream into the console and command output back
+fn serve(stream: TcpStream, console_tx: Sender<ConsoleRequest>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let output = match ConsoleCommand::parse(&line) {
+            Some(command) => {
+                let (reply_tx, reply_rx) = channel();
+                let request = ConsoleRequest {
+                    command,
+                    reply: Some(reply_tx),
+                };
+                if console_tx.send(request).is_err() {
+                    // Server has been stopped
+                    break;
+                }
+
+                reply_rx
+                    .recv_timeout(REPLY_TIMEOUT)
+                    .unwrap_or_else(|_| "Server didn't respond in time".to_string())
+            }
+            None => format!("Unknown command: {:?}", line.trim()),
+        };
+
+        writeln!(writer, "{output}")?;
+    }
+
+    Ok(())
+}
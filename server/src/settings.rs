@@ -0,0 +1,146 @@
+use std::{net::SocketAddr, path::PathBuf, str::FromStr};
+
+use common::{bounds::WorldBounds, chunk::LoadArea, coord::ChunkId, net::DEFAULT_PORT};
+use thiserror::Error;
+
+use crate::consts::{
+    DEFAULT_BACKUPS_KEPT, DEFAULT_BACKUP_INTERVAL, DEFAULT_TPS, DEFAULT_VIEW_DISTANCE,
+    DEFAULT_WORLD_PATH,
+};
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Missing value for argument {0}")]
+    MissingValue(String),
+    #[error("Invalid value for argument {0} (found: {1:?})")]
+    InvalidValue(String, String),
+    #[error("Unknown argument {0} (see --help)")]
+    UnknownArgument(String),
+}
+
+/// Server settings parsed from command line arguments
+#[derive(Clone, Debug)]
+pub struct ServerSettings {
+    /// Address to listen on
+    pub address: SocketAddr,
+    /// Target ticks per second
+    pub tps: u32,
+    /// Path to the world directory
+    pub world_path: PathBuf,
+    /// Minutes between scheduled world backups (disabled if 0)
+    pub backup_interval: u32,
+    /// Number of world backups kept
+    pub backups_kept: usize,
+    /// Backup to restore the world from before opening it
+    pub restore: Option<String>,
+    /// Chunk loading distance around players
+    pub view_distance: u16,
+    /// Forbid block edits by players
+    pub read_only: bool,
+    /// Height limits and horizontal border of the world
+    pub bounds: WorldBounds,
+    /// Allow payload compression (if supported by the client)
+    pub compression: bool,
+    /// Address of the remote console listener (disabled if `None`)
+    #[cfg(feature = "remote-console")]
+    pub remote_console: Option<SocketAddr>,
+}
+
+impl ServerSettings {
+    /// Printed by `--help`
+    pub const USAGE: &'static str = "\
+Usage: ecg-server [OPTIONS]
+
+Options:
+  --address <ADDR>          Address to listen on [default: 0.0.0.0:25300]
+  --tps <N>                 Target ticks per second [default: 20]
+  --world <PATH>            Path to the world directory [default: world]
+  --backup-interval <MIN>   Minutes between scheduled backups, 0 disables them [default: 30]
+  --backups-kept <N>        Number of world backups kept [default: 5]
+  --restore <NAME>          Restore the world from a backup before opening it. NAME is one of
+                            the names listed by the `backups` console command or `latest`.
+                            The current world is backed up first
+  --view-distance <N>       Chunk loading distance around players [default: 4]
+  --read-only               Forbid block edits by players
+  --min-height <Y>          Lowest block of the world
+  --max-height <Y>          Highest block of the world
+  --border <N>              Horizontal world border in blocks
+  --no-compression          Disable payload compression
+  --remote-console <ADDR>   Remote console address (remote-console feature only)
+  --help                    Print this message";
+
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, SettingsError> {
+        let mut settings = Self::default();
+
+        fn value<T: FromStr>(
+            arg: &str,
+            args: &mut impl Iterator<Item = String>,
+        ) -> Result<T, SettingsError> {
+            let value = args
+                .next()
+                .ok_or_else(|| SettingsError::MissingValue(arg.to_string()))?;
+            value
+                .parse()
+                .map_err(|_| SettingsError::InvalidValue(arg.to_string(), value))
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--address" => settings.address = value(&arg, &mut args)?,
+                "--tps" => settings.tps = value::<u32>(&arg, &mut args)?.max(1),
+                "--world" => settings.world_path = value(&arg, &mut args)?,
+                "--backup-interval" => settings.backup_interval = value(&arg, &mut args)?,
+                "--backups-kept" => settings.backups_kept = value::<usize>(&arg, &mut args)?.max(1),
+                "--restore" => settings.restore = Some(value(&arg, &mut args)?),
+                "--view-distance" => settings.view_distance = value(&arg, &mut args)?,
+                "--read-only" => settings.read_only = true,
+                "--min-height" => settings.bounds.min_y = value(&arg, &mut args)?,
+                "--max-height" => settings.bounds.max_y = value(&arg, &mut args)?,
+                "--border" => settings.bounds.border = Some(value::<i64>(&arg, &mut args)?.max(1)),
+                "--no-compression" => settings.compression = false,
+                #[cfg(feature = "remote-console")]
+                "--remote-console" => settings.remote_console = Some(value(&arg, &mut args)?),
+                _ => return Err(SettingsError::UnknownArgument(arg)),
+            }
+        }
+
+        if settings.bounds.min_y > settings.bounds.max_y {
+            return Err(SettingsError::InvalidValue(
+                "--max-height".to_string(),
+                settings.bounds.max_y.to_string(),
+            ));
+        }
+
+        Ok(settings)
+    }
+
+    /// Chunks loaded around a player in the `center` chunk
+    pub fn view_area(&self, center: ChunkId) -> LoadArea {
+        LoadArea::new_cuboid(center, self.view_distance as i64)
+    }
+
+    /// Number of chunks in [`Self::view_area`]
+    pub fn view_area_size(&self) -> usize {
+        let dist = self.view_distance as usize;
+        (2 * dist + 1).pow(2) * (2 * (dist / 2) + 1)
+    }
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            address: SocketAddr::from(([0, 0, 0, 0], DEFAULT_PORT)),
+            tps: DEFAULT_TPS,
+            world_path: PathBuf::from(DEFAULT_WORLD_PATH),
+            backup_interval: DEFAULT_BACKUP_INTERVAL,
+            backups_kept: DEFAULT_BACKUPS_KEPT,
+            restore: None,
+            view_distance: DEFAULT_VIEW_DISTANCE,
+            read_only: false,
+            bounds: WorldBounds::default(),
+            compression: true,
+            #[cfg(feature = "remote-console")]
+            remote_console: None,
+        }
+    }
+}
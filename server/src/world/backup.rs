@@ -0,0 +1,165 @@
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{info, warn};
+
+/// Copies of the world directory kept beside it (`world` is backed up to `world.backups/<name>`).
+///
+/// Backup is named after its creation time and reason, so names sort by age
+#[derive(Clone, Debug)]
+pub struct WorldBackups {
+    world: PathBuf,
+    dir: PathBuf,
+    /// Oldest backups above the limit are removed
+    keep: usize,
+}
+
+impl WorldBackups {
+    /// Name accepted by [`Self::restore`] for the newest backup
+    pub const LATEST: &'static str = "latest";
+    const DIR_SUFFIX: &'static str = ".backups";
+    /// Extension of backups being copied
+    const TMP_EXTENSION: &'static str = "tmp";
+
+    pub fn new(world: &Path, keep: usize) -> Self {
+        let mut name = world.file_name().unwrap_or_default().to_os_string();
+        name.push(Self::DIR_SUFFIX);
+
+        Self {
+            world: world.to_path_buf(),
+            dir: world.with_file_name(name),
+            keep: keep.max(1),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Copy the world directory. Returns name of the backup
+    pub fn create(&self, reason: &str) -> io::Result<String> {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let mut name = format!("{secs}-{reason}");
+        // Several backups in a second
+        for i in 2.. {
+            if !self.dir.join(&name).exists() {
+                break;
+            }
+            name = format!("{secs}-{reason}-{i}");
+        }
+        let path = self.dir.join(&name);
+        let tmp = path.with_extension(Self::TMP_EXTENSION);
+
+        // Partial copy of a crashed backup
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        fs::create_dir_all(&self.dir)?;
+        copy_dir(&self.world, &tmp)?;
+        fs::rename(tmp, path)?;
+        info!(name, "World backup created");
+
+        self.prune()?;
+
+        Ok(name)
+    }
+
+    /// Backup names from the oldest to the newest
+    pub fn list(&self) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut names = entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|path| path.is_dir() && path.extension().is_none())
+            .filter_map(|path| Some(path.file_name()?.to_str()?.to_string()))
+            .collect::<Vec<_>>();
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Replace the world with the backup. Current world is backed up first, so the restore can be
+    /// undone. The world mustn't be open
+    pub fn restore(&self, name: &str) -> io::Result<()> {
+        let names = self.list()?;
+        let name = match name {
+            Self::LATEST => names.last(),
+            name => names.iter().find(|other| *other == name),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Backup {name:?} not found"),
+            )
+        })?
+        .clone();
+
+        // Copy beside the world first, so a failed copy leaves it intact
+        let tmp = self.world.with_extension(Self::TMP_EXTENSION);
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        copy_dir(&self.dir.join(&name), &tmp)?;
+
+        // May prune the restored backup, so it's already copied
+        if self.world.is_dir() {
+            self.create("restore")?;
+            fs::remove_dir_all(&self.world)?;
+        }
+        fs::rename(tmp, &self.world)?;
+        info!(name, "World restored from backup");
+
+        Ok(())
+    }
+
+    /// Remove the oldest backups above the limit
+    fn prune(&self) -> io::Result<()> {
+        let names = self.list()?;
+        for name in &names[..names.len().saturating_sub(self.keep)] {
+            if let Err(err) = fs::remove_dir_all(self.dir.join(name)) {
+                warn!(name, %err, "Failed to remove old backup");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Copy directory recursively. Temporary files of unfinished saves are skipped
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension() == Some(OsStr::new("tmp")) {
+            continue;
+        }
+
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            match fs::copy(&path, &target) {
+                Ok(_) => {}
+                // Removed by a concurrent save
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    Ok(())
+}
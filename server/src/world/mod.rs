@@ -0,0 +1,331 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+};
+
+use common::{
+    block::Block,
+    bounds::WorldBounds,
+    chunk::Chunk,
+    coord::{BlockCoord, ChunkId, GlobalCoord, CHUNK_CUBE},
+    movement::PLAYER_AABB,
+    net::protocol::ServerMsg,
+    rng::{self, salt, SeededRng},
+    stats::WorldStats,
+};
+use common_log::span;
+use glam::Vec3;
+use tokio::runtime::Runtime;
+use tracing::{error, info, warn};
+
+use crate::consts::{BLOCKING_THREADS, MAX_CHUNK_DELTA, RANDOM_TICKS_PER_CHUNK};
+
+use self::{
+    backup::WorldBackups,
+    storage::{WorldMeta, WorldStorage},
+};
+
+pub mod backup;
+pub mod storage;
+
+/// Chunk loaded on the server
+pub struct WorldChunk {
+    pub chunk: Chunk,
+    /// Chunk has been changed since last save
+    pub dirty: bool,
+    /// Block changes not sent to clients yet
+    pub changes: HashMap<BlockCoord, Block>,
+    /// Chunk has been updated with deltas since the last full sync
+    pub delta_synced: bool,
+}
+
+impl WorldChunk {
+    pub fn new(chunk: Chunk) -> Self {
+        Self {
+            chunk,
+            dirty: false,
+            changes: HashMap::new(),
+            delta_synced: false,
+        }
+    }
+
+    pub fn set(&mut self, pos: BlockCoord, block: Block) {
+        self.chunk.set(pos, block);
+        self.changes.insert(pos, block);
+        self.dirty = true;
+    }
+}
+
+/// Server-side world state
+pub struct World {
+    storage: Arc<WorldStorage>,
+    bounds: WorldBounds,
+    meta: WorldMeta,
+    stats: WorldStats,
+
+    chunk_load_rx: Receiver<(ChunkId, Chunk)>,
+    chunk_load_tx: Sender<(ChunkId, Chunk)>,
+    chunk_load_ids: HashSet<ChunkId>,
+
+    pub chunks: HashMap<ChunkId, WorldChunk>,
+}
+
+impl World {
+    /// Spawn point used if there is no solid ground in the spawn column
+    pub const FALLBACK_SPAWN: Vec3 = Vec3::new(5.0, 20.0, 0.0);
+
+    /// Open world from `storage`. Spawn point is found on the surface if the world is new. Worlds
+    /// of older versions are backed up and migrated
+    pub fn open(
+        storage: WorldStorage,
+        bounds: WorldBounds,
+        backups: &WorldBackups,
+    ) -> io::Result<Self> {
+        let meta = match storage.load_meta()? {
+            Some(mut meta) if meta.version < WorldMeta::VERSION => {
+                info!(version = meta.version, "Migrating world");
+                backups.create("migration")?;
+                let migrated = storage.migrate(&mut meta)?;
+                info!(migrated, "World migrated");
+                meta
+            }
+            Some(meta) => meta,
+            None => {
+                let meta = WorldMeta::new(Self::find_spawn(&bounds));
+                info!(spawn = %meta.spawn, "Creating new world");
+                storage.save_meta(&meta)?;
+                meta
+            }
+        };
+
+        let stats = storage.load_stats().unwrap_or_else(|err| {
+            warn!(%err, "Failed to load world statistics. Starting over");
+            WorldStats::new()
+        });
+
+        Ok(Self::new(storage, bounds, meta, stats))
+    }
+
+    pub fn new(
+        storage: WorldStorage,
+        bounds: WorldBounds,
+        meta: WorldMeta,
+        stats: WorldStats,
+    ) -> Self {
+        let (chunk_load_tx, chunk_load_rx) = channel();
+
+        Self {
+            storage: Arc::new(storage),
+            bounds,
+            meta,
+            stats,
+            chunk_load_rx,
+            chunk_load_tx,
+            chunk_load_ids: HashSet::with_capacity(*BLOCKING_THREADS * 4),
+            chunks: HashMap::new(),
+        }
+    }
+
+    pub fn storage(&self) -> &WorldStorage {
+        &self.storage
+    }
+
+    pub fn bounds(&self) -> &WorldBounds {
+        &self.bounds
+    }
+
+    /// Stand on the first solid block below the sky in the world origin column
+    fn find_spawn(bounds: &WorldBounds) -> Vec3 {
+        match Chunk::generated_surface(0, 0, bounds) {
+            Some(surface) => Vec3::new(0.5, surface.y as f32 + 1.0 - PLAYER_AABB.min.y, 0.5),
+            None => {
+                warn!("No solid ground at the world origin");
+                Self::FALLBACK_SPAWN
+            }
+        }
+    }
+
+    /// Player spawn point (eye position)
+    pub fn spawn(&self) -> Vec3 {
+        self.meta.spawn
+    }
+
+    /// Totals of all players, saved along with the chunks
+    pub fn stats(&self) -> &WorldStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut WorldStats {
+        &mut self.stats
+    }
+
+    /// Move spawn point and save it to the world metadata
+    pub fn set_spawn(&mut self, spawn: Vec3) -> io::Result<()> {
+        self.meta.spawn = spawn;
+        self.storage.save_meta(&self.meta)
+    }
+
+    pub fn chunk(&self, id: ChunkId) -> Option<&Chunk> {
+        self.chunks.get(&id).map(|chunk| &chunk.chunk)
+    }
+
+    pub fn block(&self, pos: GlobalCoord) -> Option<Block> {
+        self.chunk(pos.to_chunk_id())
+            .map(|chunk| chunk.get(pos.to_block()))
+    }
+
+    /// Set block in a loaded chunk. Returns `false` if chunk isn't loaded or `pos` is out of bounds
+    pub fn set_block(&mut self, pos: GlobalCoord, block: Block) -> bool {
+        if !self.bounds.contains(pos) {
+            return false;
+        }
+
+        match self.chunks.get_mut(&pos.to_chunk_id()) {
+            Some(chunk) => {
+                chunk.set(pos.to_block(), block);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Queue chunk loading (from disk or by generating) if it isn't loaded yet. Chunks out of
+    /// bounds are never loaded
+    pub fn request(&mut self, runtime: &Runtime, id: ChunkId) {
+        if self.chunks.contains_key(&id)
+            || self.chunk_load_ids.contains(&id)
+            || !self.bounds.contains_chunk(id)
+        {
+            return;
+        }
+
+        self.chunk_load_ids.insert(id);
+
+        let tx = self.chunk_load_tx.clone();
+        let storage = self.storage.clone();
+        let bounds = self.bounds;
+        runtime.spawn_blocking(move || {
+            let chunk = match storage.load_chunk(id) {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => Chunk::generate(id, &bounds),
+                Err(err) => {
+                    warn!(?id, %err, "Failed to load chunk. Regenerating");
+                    Chunk::generate(id, &bounds)
+                }
+            };
+            let _ = tx.send((id, chunk));
+        });
+    }
+
+    /// Collect loaded chunks and unload chunks rejected by `keep`
+    pub fn maintain(&mut self, keep: impl Fn(ChunkId) -> bool) {
+        span!(_guard, "maintain", "World::maintain");
+
+        // Collect loaded chunks
+        self.chunk_load_rx.try_iter().for_each(|(id, chunk)| {
+            self.chunk_load_ids.remove(&id);
+            self.chunks.insert(id, WorldChunk::new(chunk));
+        });
+
+        // Unload chunks nobody needs anymore
+        let unload = self
+            .chunks
+            .keys()
+            .filter(|&&id| !keep(id))
+            .copied()
+            .collect::<Vec<_>>();
+        unload.into_iter().for_each(|id| {
+            if let Some(chunk) = self.chunks.remove(&id) {
+                if chunk.dirty {
+                    if let Err(err) = self.storage.save_chunk(id, &chunk.chunk) {
+                        error!(?id, %err, "Failed to save unloaded chunk");
+                    }
+                }
+            }
+        });
+    }
+
+    /// Apply random block ticks to every loaded chunk. Returns number of changed blocks.
+    ///
+    /// Blocks are picked by a generator of the chunk and `tick`, so they don't depend on the order
+    /// of chunks
+    pub fn random_ticks(&mut self, tick: u64) -> usize {
+        span!(_guard, "random_ticks", "World::random_ticks");
+
+        let seed = rng::mix_seed(Chunk::DEFAULT_SEED, salt::RANDOM_TICK) ^ tick;
+        let mut changed = 0;
+
+        self.chunks.iter_mut().for_each(|(&id, chunk)| {
+            let mut rng = SeededRng::for_chunk(seed, id);
+            (0..RANDOM_TICKS_PER_CHUNK).for_each(|_| {
+                // Chunk volume is a power of two, so the remainder is uniform
+                let pos = BlockCoord::from(rng.next_u64() as usize % CHUNK_CUBE);
+                if let Some(block) = chunk.chunk.random_tick(pos) {
+                    chunk.set(pos, block);
+                    changed += 1;
+                }
+            });
+        });
+
+        changed
+    }
+
+    /// Take pending block changes as network updates.
+    ///
+    /// Chunks with too many changes are sent in full. With `resync`, chunks previously updated
+    /// with deltas are sent in full too, so clients can't drift away from the server state
+    pub fn chunk_updates(&mut self, resync: bool) -> Vec<ServerMsg> {
+        span!(_guard, "chunk_updates", "World::chunk_updates");
+
+        self.chunks
+            .iter_mut()
+            .filter(|(_, chunk)| !chunk.changes.is_empty() || (resync && chunk.delta_synced))
+            .map(|(&id, chunk)| {
+                if chunk.changes.len() > MAX_CHUNK_DELTA || resync {
+                    chunk.changes.clear();
+                    chunk.delta_synced = false;
+                    ServerMsg::ChunkData {
+                        id,
+                        chunk: chunk.chunk.clone(),
+                    }
+                } else {
+                    chunk.delta_synced = true;
+                    ServerMsg::ChunkDelta {
+                        id,
+                        changes: chunk.changes.drain().collect(),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Save all changed chunks and the statistics to disk. Returns number of saved chunks
+    pub fn save(&mut self) -> usize {
+        span!(_guard, "save", "World::save");
+
+        let mut saved = 0;
+
+        self.chunks
+            .iter_mut()
+            .filter(|(_, chunk)| chunk.dirty)
+            .for_each(
+                |(id, chunk)| match self.storage.save_chunk(*id, &chunk.chunk) {
+                    Ok(()) => {
+                        chunk.dirty = false;
+                        saved += 1;
+                    }
+                    Err(err) => error!(?id, %err, "Failed to save chunk"),
+                },
+            );
+
+        if let Err(err) = self.storage.save_stats(&self.stats) {
+            error!(%err, "Failed to save world statistics");
+        }
+
+        saved
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use common::{
+    block::Block,
+    chunk::Chunk,
+    coord::{ChunkId, CHUNK_CUBE},
+    file,
+    stats::WorldStats,
+};
+use glam::Vec3;
+use tracing::{info, warn};
+
+/// World state stored beside the chunks
+#[derive(Clone, Debug)]
+pub struct WorldMeta {
+    /// Player spawn point (eye position)
+    pub spawn: Vec3,
+    /// Storage format the world is saved in. Older worlds are migrated on opening
+    pub version: u32,
+}
+
+impl WorldMeta {
+    /// Current storage format. 1 added chunk file checksums
+    pub const VERSION: u32 = 1;
+
+    pub fn new(spawn: Vec3) -> Self {
+        Self {
+            spawn,
+            version: Self::VERSION,
+        }
+    }
+
+    /// Text with a `key value...` pair per line
+    fn encode(&self) -> String {
+        format!(
+            "version {}\nspawn {} {} {}\n",
+            self.version, self.spawn.x, self.spawn.y, self.spawn.z
+        )
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        let mut spawn = None;
+        // Worlds of the first format didn't store it
+        let mut version = 0;
+
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            // Keys of newer versions are ignored
+            match words.next() {
+                Some("spawn") => {
+                    let values = words
+                        .map(|word| word.parse::<f32>().ok())
+                        .collect::<Option<Vec<_>>>()?;
+                    spawn = Some(Vec3::from_slice(values.get(..3)?));
+                }
+                Some("version") => version = words.next()?.parse().ok()?,
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            spawn: spawn?,
+            version,
+        })
+    }
+}
+
+/// On-disk world storage. Every chunk is stored in a separate file.
+///
+/// Files are written to a temporary file first and renamed over the old one, so a crash during
+/// a save leaves either the old or the new file. Chunk files start with a checksum of the blocks
+/// to catch files damaged otherwise (e.g. by the disk or by older versions writing in place)
+pub struct WorldStorage {
+    root: PathBuf,
+}
+
+impl WorldStorage {
+    const CHUNKS_DIR: &'static str = "chunks";
+    const META_FILE: &'static str = "world.meta";
+    const STATS_FILE: &'static str = "world.stats";
+    /// Extension of chunk files failed the check. Kept for manual recovery
+    const CORRUPT_EXTENSION: &'static str = "corrupt";
+    const CHUNK_MAGIC: &'static [u8; 4] = b"ECGC";
+    /// Magic and CRC-32 of the blocks
+    const CHUNK_HEADER: usize = 8;
+
+    /// Open world directory. Saves interrupted by a crash are finished or discarded
+    pub fn open(root: &Path) -> io::Result<Self> {
+        fs::create_dir_all(root.join(Self::CHUNKS_DIR))?;
+
+        let storage = Self {
+            root: root.to_path_buf(),
+        };
+        storage.repair()?;
+
+        Ok(storage)
+    }
+
+    /// Handle temporary files left by interrupted saves. Complete ones replace the old file,
+    /// since they are newer, torn ones are removed
+    fn repair(&self) -> io::Result<()> {
+        let meta_tmp = file::tmp_path(&self.root.join(Self::META_FILE));
+        let stats_tmp = file::tmp_path(&self.root.join(Self::STATS_FILE));
+        let chunk_tmps = fs::read_dir(self.root.join(Self::CHUNKS_DIR))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|path| path.extension() == Some(OsStr::new(file::TMP_EXTENSION)));
+
+        let (mut restored, mut removed) = (0, 0);
+        let root_tmps = [meta_tmp.clone(), stats_tmp.clone()]
+            .into_iter()
+            .filter(|path| path.is_file());
+        for tmp in chunk_tmps.chain(root_tmps) {
+            let complete = match fs::read(&tmp) {
+                Ok(bytes) if tmp == meta_tmp => std::str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(WorldMeta::decode)
+                    .is_some(),
+                // Truncated statistics look valid, the old file is a few seconds behind only
+                Ok(_) if tmp == stats_tmp => false,
+                Ok(bytes) => Self::decode_chunk(&bytes).is_ok(),
+                Err(_) => false,
+            };
+
+            if complete {
+                fs::rename(&tmp, tmp.with_extension(""))?;
+                restored += 1;
+            } else {
+                fs::remove_file(&tmp)?;
+                removed += 1;
+            }
+        }
+
+        if restored + removed > 0 {
+            warn!(restored, removed, "Found files of interrupted saves");
+        }
+
+        Ok(())
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Load world metadata. Returns `None` if the world has just been created
+    pub fn load_meta(&self) -> io::Result<Option<WorldMeta>> {
+        let text = match fs::read_to_string(self.root.join(Self::META_FILE)) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        WorldMeta::decode(&text)
+            .map(Some)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Corrupted world metadata"))
+    }
+
+    pub fn save_meta(&self, meta: &WorldMeta) -> io::Result<()> {
+        file::write_atomic(&self.root.join(Self::META_FILE), meta.encode().as_bytes())
+    }
+
+    /// Load world statistics. Zero if there are none yet
+    pub fn load_stats(&self) -> io::Result<WorldStats> {
+        let text = match fs::read_to_string(self.root.join(Self::STATS_FILE)) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(WorldStats::new()),
+            Err(err) => return Err(err),
+        };
+
+        WorldStats::decode(&text)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Corrupted world statistics"))
+    }
+
+    pub fn save_stats(&self, stats: &WorldStats) -> io::Result<()> {
+        file::write_atomic(&self.root.join(Self::STATS_FILE), stats.encode().as_bytes())
+    }
+
+    /// Bring the world to the current format. Returns number of rewritten chunk files
+    pub fn migrate(&self, meta: &mut WorldMeta) -> io::Result<usize> {
+        let mut migrated = 0;
+
+        // Headerless chunk files get checksums
+        if meta.version < 1 {
+            for entry in fs::read_dir(self.root.join(Self::CHUNKS_DIR))? {
+                let path = entry?.path();
+                if path.extension() != Some(OsStr::new("chunk")) {
+                    continue;
+                }
+
+                let bytes = fs::read(&path)?;
+                if bytes.len() == CHUNK_CUBE {
+                    let chunk = Self::decode_chunk(&bytes)?;
+                    file::write_atomic(&path, &Self::encode_chunk(&chunk))?;
+                    migrated += 1;
+                }
+            }
+        }
+
+        meta.version = WorldMeta::VERSION;
+        self.save_meta(meta)?;
+
+        Ok(migrated)
+    }
+
+    fn chunk_path(&self, id: ChunkId) -> PathBuf {
+        self.root
+            .join(Self::CHUNKS_DIR)
+            .join(format!("{}_{}_{}.chunk", id.x, id.y, id.z))
+    }
+
+    /// Load chunk from disk. Returns `None` if chunk has never been saved. Corrupted files are
+    /// moved aside, so the chunk is regenerated next time
+    pub fn load_chunk(&self, id: ChunkId) -> io::Result<Option<Chunk>> {
+        let path = self.chunk_path(id);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        match Self::decode_chunk(&bytes) {
+            Ok(chunk) => Ok(Some(chunk)),
+            Err(err) => {
+                let corrupt = path.with_extension(Self::CORRUPT_EXTENSION);
+                info!(?corrupt, "Moving corrupted chunk file aside");
+                fs::rename(&path, corrupt)?;
+                Err(err)
+            }
+        }
+    }
+
+    pub fn save_chunk(&self, id: ChunkId, chunk: &Chunk) -> io::Result<()> {
+        file::write_atomic(&self.chunk_path(id), &Self::encode_chunk(chunk))
+    }
+
+    fn encode_chunk(chunk: &Chunk) -> Vec<u8> {
+        let blocks = chunk
+            .blocks()
+            .iter()
+            .map(|block| block.id())
+            .collect::<Vec<_>>();
+
+        let mut bytes = Vec::with_capacity(Self::CHUNK_HEADER + blocks.len());
+        bytes.extend_from_slice(Self::CHUNK_MAGIC);
+        bytes.extend_from_slice(&crc32(&blocks).to_le_bytes());
+        bytes.extend_from_slice(&blocks);
+
+        bytes
+    }
+
+    /// Checked chunk file or the headerless one of older versions
+    fn decode_chunk(bytes: &[u8]) -> io::Result<Chunk> {
+        let blocks = match bytes.len() {
+            CHUNK_CUBE => bytes,
+            len if len == Self::CHUNK_HEADER + CHUNK_CUBE
+                && bytes.starts_with(Self::CHUNK_MAGIC) =>
+            {
+                let (header, blocks) = bytes.split_at(Self::CHUNK_HEADER);
+                let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+                if crc32(blocks) != checksum {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Corrupted chunk file (checksum mismatch)",
+                    ));
+                }
+                blocks
+            }
+            len => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Corrupted chunk file ({len} bytes)"),
+                ))
+            }
+        };
+
+        let mut chunk = Chunk::new();
+        chunk
+            .blocks_mut()
+            .iter_mut()
+            .zip(blocks)
+            .for_each(|(block, &id)| *block = Block::from(id));
+
+        Ok(chunk)
+    }
+}
+
+/// CRC-32 (IEEE)
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
@@ -0,0 +1,24 @@
+use common::direction::Direction;
+use game::scene::chunk::ChunkManager;
+
+use crate::{block_coord, check, chunk_id};
+
+#[test]
+fn edges_lead_to_neighbor_chunks() {
+    check("edges_lead_to_neighbor_chunks", |rng| {
+        let id = chunk_id(rng);
+        let pos = block_coord(rng);
+        let global = id.to_coord().to_global(&pos);
+        let edges = ChunkManager::edges(pos).collect::<Vec<_>>();
+
+        // A neighbor block is in another chunk exactly when the block is on that edge
+        for dir in Direction::ALL {
+            let neighbor = global.neighbor(dir).to_chunk_id();
+            assert_eq!(edges.contains(&dir), neighbor != id);
+            if neighbor != id {
+                assert_eq!(neighbor, id.neighbor(dir));
+            }
+        }
+        assert!(edges.len() <= 3);
+    });
+}
@@ -0,0 +1,49 @@
+use common::coord::{
+    BlockCoord, GlobalCoord, GlobalUnit, LocalUnit, CHUNK_CUBE, G_CHUNK_SIZE, L_CHUNK_SIZE,
+};
+
+use crate::{block_coord, check, chunk_id, global_coord};
+
+#[test]
+fn global_coord_round_trip() {
+    check("global_coord_round_trip", |rng| {
+        let pos = global_coord(rng);
+        let (id, block) = (pos.to_chunk_id(), pos.to_block());
+
+        assert_eq!(id.to_coord().to_global(&block), pos);
+        assert_eq!(pos.to_chunk(), id.to_coord());
+        assert_eq!(pos.to_chunk().to_id(), id);
+        // Block is inside its chunk, also for negative coordinates
+        let origin = id.to_coord();
+        assert!((origin.x..origin.x + G_CHUNK_SIZE).contains(&pos.x));
+        assert!((origin.y..origin.y + G_CHUNK_SIZE).contains(&pos.y));
+        assert!((origin.z..origin.z + G_CHUNK_SIZE).contains(&pos.z));
+    });
+}
+
+#[test]
+fn chunk_neighbors_share_edges() {
+    check("chunk_neighbors_share_edges", |rng| {
+        let id = chunk_id(rng);
+        let origin = id.to_coord().to_global(&BlockCoord::new(0, 0, 0));
+
+        assert_eq!(origin.to_chunk_id(), id);
+        // Block before the origin is the last block of the previous chunk
+        let before = GlobalCoord::new(origin.x - 1, origin.y - 1, origin.z - 1);
+        assert_eq!(before.to_chunk_id(), id - 1);
+        let last: LocalUnit = L_CHUNK_SIZE - 1;
+        assert_eq!(before.to_block(), BlockCoord::new(last, last, last));
+    });
+}
+
+#[test]
+fn block_index_round_trip() {
+    check("block_index_round_trip", |rng| {
+        let pos = block_coord(rng);
+        let index = pos.flatten();
+
+        assert!(index < CHUNK_CUBE);
+        assert_eq!(BlockCoord::from(index), pos);
+        assert_eq!(BlockCoord::from(index as GlobalUnit), pos);
+    });
+}
@@ -0,0 +1,87 @@
+//! Property tests of the world shared by the game and the server.
+//!
+//! Each property is checked on [`CASES`] generated cases. Cases come from [`SeededRng`], so a
+//! failed case is reproduced by running the property with [`case_rng`] of its number
+
+use std::{
+    ops::RangeInclusive,
+    panic::{self, AssertUnwindSafe},
+};
+
+use common::{
+    block::Block,
+    coord::{BlockCoord, ChunkId, GlobalCoord, GlobalUnit, CHUNK_CUBE, G_CHUNK_SIZE},
+    rng::{self, SeededRng},
+};
+
+#[cfg(test)]
+mod chunk;
+#[cfg(test)]
+mod coord;
+#[cfg(test)]
+mod load_area;
+#[cfg(test)]
+mod mesh;
+
+/// Cases generated for every property
+pub const CASES: u64 = 256;
+
+/// Generator of the case
+pub fn case_rng(case: u64) -> SeededRng {
+    SeededRng::new(rng::mix(case))
+}
+
+/// Run `property` on [`CASES`] cases. Panics with the number of the first failed case
+pub fn check(name: &str, property: impl Fn(&mut SeededRng)) {
+    for case in 0..CASES {
+        let mut rng = case_rng(case);
+        if panic::catch_unwind(AssertUnwindSafe(|| property(&mut rng))).is_err() {
+            panic!("Property `{name}` failed on case {case}");
+        }
+    }
+}
+
+/// Uniform value in `range`
+pub fn range(rng: &mut SeededRng, range: RangeInclusive<GlobalUnit>) -> GlobalUnit {
+    let (start, end) = range.into_inner();
+    start + (rng.next_u64() % (end - start + 1) as u64) as GlobalUnit
+}
+
+/// Chunk with negative coordinates as often as positive ones
+pub fn chunk_id(rng: &mut SeededRng) -> ChunkId {
+    let mut unit = || range(rng, -1000..=1000);
+    ChunkId::new(unit(), unit(), unit())
+}
+
+/// Block coordinate, half of the time on a chunk edge, since most bugs are there
+pub fn block_coord(rng: &mut SeededRng) -> BlockCoord {
+    let mut unit = || match rng.next_u64() % 4 {
+        0 => 0,
+        1 => G_CHUNK_SIZE - 1,
+        _ => range(rng, 0..=G_CHUNK_SIZE - 1),
+    };
+    BlockCoord::from(unit() * G_CHUNK_SIZE * G_CHUNK_SIZE + unit() * G_CHUNK_SIZE + unit())
+}
+
+/// Block in any chunk around the origin
+pub fn global_coord(rng: &mut SeededRng) -> GlobalCoord {
+    chunk_id(rng).to_coord().to_global(&block_coord(rng))
+}
+
+/// Chunk of air with about `density` of its blocks replaced by random solid blocks
+pub fn solid_blocks(rng: &mut SeededRng, density: f32) -> Box<[Block]> {
+    let solid = Block::ALL
+        .into_iter()
+        .filter(Block::solid)
+        .collect::<Vec<_>>();
+
+    (0..CHUNK_CUBE)
+        .map(|_| {
+            if rng.next_f32() < density {
+                solid[rng.next_u64() as usize % solid.len()]
+            } else {
+                Block::Air
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+
+use common::{chunk::LoadArea, coord::ChunkId};
+
+use crate::{check, chunk_id, range};
+
+#[test]
+fn load_area_covers_column() {
+    check("load_area_covers_column", |rng| {
+        let center = chunk_id(rng);
+        let dist = range(rng, 0..=6);
+        let (below, above) = (range(rng, 0..=6), range(rng, 0..=6));
+        let area = LoadArea::new_column(center, dist, below, above);
+
+        let ids = area.clone().collect::<Vec<_>>();
+        let unique = ids.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(ids.len(), unique.len());
+        assert_eq!(
+            ids.len() as i64,
+            (2 * dist + 1) * (2 * dist + 1) * (below + above + 1)
+        );
+
+        // Every chunk within the distances is covered and nothing else
+        for _ in 0..32 {
+            let offset = ChunkId::new(range(rng, -8..=8), range(rng, -8..=8), range(rng, -8..=8));
+            let id = ChunkId::new(
+                center.x + offset.x,
+                center.y + offset.y,
+                center.z + offset.z,
+            );
+            let inside = offset.x.abs() <= dist
+                && offset.z.abs() <= dist
+                && (-below..=above).contains(&offset.y);
+
+            assert_eq!(area.contains(id), inside);
+            assert_eq!(unique.contains(&id), inside);
+        }
+    });
+}
+
+#[test]
+fn extended_load_area_keeps_chunks() {
+    check("extended_load_area_keeps_chunks", |rng| {
+        let dist = range(rng, 0..=4);
+        let area = LoadArea::new_cube(chunk_id(rng), dist);
+        let offset = ChunkId::new(range(rng, -3..=3), range(rng, -3..=3), range(rng, -3..=3));
+        let extended = area.clone().extend(offset);
+
+        assert!(area.clone().all(|id| extended.contains(id)));
+        // Area grows only by the offset
+        let side = |offset: i64| 2 * dist + 1 + offset.abs();
+        assert_eq!(
+            extended.count() as i64,
+            side(offset.x) * side(offset.y) * side(offset.z)
+        );
+    });
+}
@@ -0,0 +1,47 @@
+use common::{
+    coord::{BlockCoord, CHUNK_CUBE},
+    direction::Direction,
+};
+use game::render::mesh::TerrainMesh;
+
+use crate::{check, chunk_id, solid_blocks};
+
+#[test]
+fn mesh_has_face_per_open_side() {
+    check("mesh_has_face_per_open_side", |rng| {
+        let density = rng.next_f32();
+        let blocks = solid_blocks(rng, density);
+        let mesh = TerrainMesh::build(chunk_id(rng).to_coord(), &blocks);
+
+        // Every solid block has 6 faces, minus 2 for every pair of touching solid blocks
+        let solid = (0..CHUNK_CUBE).filter(|&i| blocks[i].solid());
+        let touching = solid
+            .clone()
+            .map(BlockCoord::from)
+            .flat_map(|pos| {
+                [Direction::Up, Direction::Right, Direction::Back]
+                    .into_iter()
+                    .filter(move |&dir| !pos.on_chunk_edge(dir))
+                    .map(move |dir| pos.neighbor(dir))
+            })
+            .filter(|pos| blocks[pos.flatten()].solid())
+            .count();
+        let faces = solid.count() * 6 - touching * 2;
+
+        assert_eq!(mesh.validate(), Ok(()));
+        assert_eq!(mesh.vertices.len(), faces * 4);
+        assert_eq!(mesh.indices.len(), faces * 6);
+        // Only opaque geometry
+        assert_eq!(mesh.cutout as usize, mesh.indices.len());
+        assert_eq!(mesh.fluid as usize, mesh.indices.len());
+        assert!(mesh
+            .indices
+            .iter()
+            .all(|&index| (index as usize) < mesh.vertices.len()));
+        // Quads are two triangles sharing their first and third corners
+        assert!(mesh
+            .indices
+            .chunks(6)
+            .all(|quad| quad[0] == quad[3] && quad[2] == quad[4] && quad[0] % 4 == 0));
+    });
+}